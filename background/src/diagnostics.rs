@@ -0,0 +1,324 @@
+use crate::Background;
+use bip39::Language;
+use config::{
+    storage::{INDICATORS_DB_KEY, STORAGE_VERSION},
+    wallet::{HISTORY_KEY_SUFFIX, UNDO_LOG_KEY_SUFFIX},
+};
+use serde::Serialize;
+use wallet::wallet_types::WalletTypes;
+use zil_errors::{background::BackgroundError, storage::LocalStorageError};
+use zilliqa::json_rpc::{journal::JournalEntry, zil::ZilliqaJsonRPC};
+
+const MAX_LOG_LINES: usize = 500;
+const REDACTED_SECRET: &str = "[redacted]";
+const REDACTED_ADDRESS: &str = "[address]";
+const MNEMONIC_MIN_WORDS: usize = 12; // shortest BIP-39 phrase
+
+#[derive(Debug, Default)]
+pub struct SupportBundleOptions {
+    pub include_addresses: bool, // opt-in, addresses are redacted by default
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SupportBundle {
+    pub app_version: String,
+    pub storage_version: u16,
+    pub db_size: u64,
+    pub wallets: Vec<WalletSummary>,
+    pub network: Option<NetworkSummary>,
+    pub integrity: Vec<IntegrityEntry>,
+    pub logs: Vec<String>,
+    pub rpc_journal: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletSummary {
+    pub index: usize,
+    pub wallet_type: &'static str,
+    pub accounts: usize,
+    pub archived: usize,
+    pub addresses: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkSummary {
+    pub nodes: usize,
+    pub connectivity: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntegrityEntry {
+    pub record: String,
+    pub status: String,
+}
+
+/// Builds a JSON support bundle for bug reports. Keys never leave the core,
+/// addresses only when `include_addresses` is set, and every log line goes
+/// through [redact].
+pub fn export_support_bundle(
+    bg: &Background,
+    rpc: Option<&ZilliqaJsonRPC>,
+    options: &SupportBundleOptions,
+) -> Result<Vec<u8>, BackgroundError> {
+    let bundle = SupportBundle {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        storage_version: STORAGE_VERSION,
+        db_size: bg.storage.get_db_size(),
+        wallets: wallet_summaries(bg, options.include_addresses),
+        network: rpc.map(|rpc| NetworkSummary {
+            nodes: rpc.nodes.len(),
+            connectivity: format!("{:?}", rpc.connectivity()),
+        }),
+        integrity: integrity_report(bg),
+        logs: options
+            .logs
+            .iter()
+            .rev()
+            .take(MAX_LOG_LINES)
+            .rev()
+            .map(|line| redact(line, options.include_addresses))
+            .collect(),
+        rpc_journal: rpc
+            .and_then(|rpc| rpc.journal())
+            .map(|journal| journal.entries())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|mut entry| {
+                entry.errors = entry
+                    .errors
+                    .iter()
+                    .map(|e| redact(e, options.include_addresses))
+                    .collect();
+                entry
+            })
+            .collect(),
+    };
+
+    serde_json::to_vec_pretty(&bundle).or(Err(BackgroundError::FailToSerializeSupportBundle))
+}
+
+/// Replaces anything that looks like a private key, a seed or a tx hash
+/// (64+ hex chars), a mnemonic (a run of BIP-39 words) and, unless
+/// allowed, addresses (base16 and bech32).
+pub fn redact(line: &str, include_addresses: bool) -> String {
+    // every token with the separator that ends it
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+
+    for c in line.chars() {
+        if c.is_ascii_alphanumeric() {
+            token.push(c);
+        } else {
+            tokens.push((std::mem::take(&mut token), Some(c)));
+        }
+    }
+    tokens.push((token, None));
+
+    let phrase = mnemonic_words(&tokens);
+    let mut out = String::with_capacity(line.len());
+
+    for ((token, sep), in_phrase) in tokens.iter().zip(phrase) {
+        match classify(token) {
+            _ if in_phrase => out.push_str(REDACTED_SECRET),
+            Some(Sensitive::Secret) => out.push_str(REDACTED_SECRET),
+            Some(Sensitive::Address) if !include_addresses => out.push_str(REDACTED_ADDRESS),
+            _ => out.push_str(token),
+        }
+
+        out.extend(sep);
+    }
+
+    out
+}
+
+// Marks runs of at least MNEMONIC_MIN_WORDS wordlist words, punctuation
+// between them doesn't break a run.
+fn mnemonic_words(tokens: &[(String, Option<char>)]) -> Vec<bool> {
+    let mut marked = vec![false; tokens.len()];
+    let mut run = Vec::new();
+
+    for (index, (token, _)) in tokens.iter().enumerate() {
+        if token.is_empty() {
+            continue;
+        }
+
+        if Language::English.find_word(&token.to_lowercase()).is_some() {
+            run.push(index);
+            continue;
+        }
+
+        mark_run(&mut marked, &mut run);
+    }
+
+    mark_run(&mut marked, &mut run);
+
+    marked
+}
+
+fn mark_run(marked: &mut [bool], run: &mut Vec<usize>) {
+    if run.len() >= MNEMONIC_MIN_WORDS {
+        for &index in run.iter() {
+            marked[index] = true;
+        }
+    }
+
+    run.clear();
+}
+
+enum Sensitive {
+    Secret,
+    Address,
+}
+
+fn classify(token: &str) -> Option<Sensitive> {
+    let body = token.strip_prefix("0x").unwrap_or(token);
+    let is_hex = !body.is_empty() && body.chars().all(|c| c.is_ascii_hexdigit());
+
+    if is_hex && body.len() >= 64 {
+        return Some(Sensitive::Secret);
+    }
+
+    if is_hex && body.len() == 40 {
+        return Some(Sensitive::Address);
+    }
+
+    if token.len() == 42 && token.to_lowercase().starts_with("zil1") {
+        return Some(Sensitive::Address);
+    }
+
+    None
+}
+
+fn wallet_summaries(bg: &Background, include_addresses: bool) -> Vec<WalletSummary> {
+    bg.wallets
+        .iter()
+        .enumerate()
+        .map(|(index, w)| WalletSummary {
+            index,
+            wallet_type: match w.wallet_type() {
+                WalletTypes::Ledger(_) => "ledger",
+                WalletTypes::SecretPhrase(_) => "secret_phrase",
+                WalletTypes::SecretKey => "secret_key",
+            },
+            accounts: w.accounts().count() + w.archived_accounts().count(),
+            archived: w.archived_accounts().count(),
+            addresses: include_addresses.then(|| {
+                w.accounts()
+                    .chain(w.archived_accounts())
+                    .filter_map(|(_, a)| a.addr.get_bech32().ok())
+                    .collect()
+            }),
+        })
+        .collect()
+}
+
+// Records are named by wallet index, storage keys are derived from addresses.
+fn integrity_report(bg: &Background) -> Vec<IntegrityEntry> {
+    let mut report = vec![IntegrityEntry {
+        record: "indicators".to_string(),
+        status: check(bg.storage.get(INDICATORS_DB_KEY)),
+    }];
+
+    report.push(IntegrityEntry {
+        record: "stale_namespaces".to_string(),
+        status: bg.stale_namespaces().len().to_string(),
+    });
+
+    for (index, w) in bg.wallets.iter().enumerate() {
+        let Ok(key) = w.key() else {
+            report.push(IntegrityEntry {
+                record: format!("wallet_{index}"),
+                status: "invalid key".to_string(),
+            });
+            continue;
+        };
+        report.push(IntegrityEntry {
+            record: format!("wallet_{index}.data"),
+            status: check(bg.storage.get(&key)),
+        });
+
+        for (name, suffix) in [
+            ("undo_log", UNDO_LOG_KEY_SUFFIX),
+            ("history", HISTORY_KEY_SUFFIX),
+        ] {
+            report.push(IntegrityEntry {
+                record: format!("wallet_{index}.{name}"),
+                status: check(bg.storage.ns_get(&key, suffix)),
+            });
+        }
+    }
+
+    report
+}
+
+fn check(res: Result<Vec<u8>, LocalStorageError>) -> String {
+    match res {
+        Ok(_) => "ok".to_string(),
+        Err(LocalStorageError::StorageDataNotFound) => "missing".to_string(),
+        Err(e) => format!("corrupted: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use serde_json::Value;
+    use zilliqa::json_rpc::journal::RpcJournal;
+
+    #[test]
+    fn test_redact() {
+        let key = "e93c035175b08613c4b0251ca92cd007026ca032ba53bafa3c839838f8b52d04";
+        let line = format!(
+            "signed by zil1w7f636xqn5vf6n2zrnjmckekw3jkckkpyrd6z8 (0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1) sk={key}"
+        );
+
+        assert_eq!(
+            redact(&line, false),
+            "signed by [address] ([address]) sk=[redacted]"
+        );
+        assert_eq!(
+            redact(&line, true),
+            "signed by zil1w7f636xqn5vf6n2zrnjmckekw3jkckkpyrd6z8 (0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1) sk=[redacted]"
+        );
+        assert_eq!(redact("nonce 42, gas 50", false), "nonce 42, gas 50");
+
+        let words =
+            "green process gate doctor slide whip priority shrug diamond crumble average help";
+        let line = format!("restore failed for '{words}', retry");
+
+        assert_eq!(
+            redact(&line, true),
+            format!(
+                "restore failed for '{}', retry",
+                ["[redacted]"; 12].join(" ")
+            )
+        );
+        // a short run of ordinary words stays
+        assert_eq!(
+            redact("gas price too low, retry", true),
+            "gas price too low, retry"
+        );
+    }
+
+    #[test]
+    fn test_export_support_bundle() {
+        let mut rng = rand::thread_rng();
+        let dir = format!("/tmp/{}", rng.gen::<usize>());
+        let bg = Background::from_storage_path(&dir).unwrap();
+        let rpc = ZilliqaJsonRPC::new().with_journal(RpcJournal::open(&bg.storage).unwrap());
+        let options = SupportBundleOptions {
+            include_addresses: false,
+            logs: vec!["to 0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1".to_string()],
+        };
+        let bytes = export_support_bundle(&bg, Some(&rpc), &options).unwrap();
+        let bundle: Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(bundle["storage_version"], STORAGE_VERSION);
+        assert_eq!(bundle["network"]["nodes"], 1);
+        assert_eq!(bundle["integrity"][0]["status"], "missing");
+        assert_eq!(bundle["logs"][0], "to [address]");
+        assert_eq!(bundle["rpc_journal"], Value::Array(Vec::new()));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::Background;
+use config::key::SECRET_KEY_SIZE;
+use crypto::bip49::Bip49DerivationPath;
+use proto::secret_key::SecretKey;
+use storage::migration::extension::{ExtensionData, ExtensionIdentity, ExtensionImport};
+use zil_errors::{background::BackgroundError, storage::LocalStorageError};
+
+// `account_type` of the extension identities.
+const HD_ACCOUNT: u8 = 0;
+const IMPORTED_ACCOUNT: u8 = 1;
+
+impl Background {
+    /// Moves a browser extension install over. The vault is opened with the
+    /// extension password, its seed phrase becomes a wallet holding the HD
+    /// identities and every imported key a wallet of its own, all sealed
+    /// with `password`. Ledger identities stay behind. Tokens are added to
+    /// each new account, contacts to the contacts namespace.
+    pub fn import_extension(
+        &mut self,
+        data: &ExtensionData,
+        extension_password: &[u8],
+        password: &str,
+    ) -> Result<ExtensionImport, BackgroundError> {
+        let first = self.wallets.len();
+        let mut accounts = 0;
+
+        if let Some(wallet) = data.wallet.as_ref().filter(|w| !w.identities.is_empty()) {
+            let secrets = wallet
+                .open_vault(extension_password)
+                .map_err(BackgroundError::FailToImportExtension)?;
+            let of_type = |account_type: u8| -> Vec<&ExtensionIdentity> {
+                wallet
+                    .identities
+                    .iter()
+                    .filter(|identity| identity.account_type == account_type)
+                    .collect()
+            };
+            let hd = of_type(HD_ACCOUNT);
+
+            if !hd.is_empty() {
+                let indexes: Vec<usize> =
+                    hd.iter().map(|identity| identity.index as usize).collect();
+
+                self.add_bip39_wallet(
+                    password,
+                    &secrets.decrypt_seed,
+                    &indexes,
+                    Bip49DerivationPath::Zilliqa,
+                )?;
+
+                for (account, identity) in hd.iter().enumerate() {
+                    self.wallets[first]
+                        .rename_account(account, &identity.name)
+                        .map_err(BackgroundError::FailToSaveWallet)?;
+                }
+
+                accounts += hd.len();
+            }
+
+            for identity in of_type(IMPORTED_ACCOUNT) {
+                let secret_key = secrets
+                    .decrypt_imported
+                    .iter()
+                    .find(|key| key.index == identity.index)
+                    .and_then(|key| hex::decode(key.private_key.trim_start_matches("0x")).ok())
+                    .and_then(|bytes| <[u8; SECRET_KEY_SIZE]>::try_from(bytes).ok())
+                    .ok_or_else(|| {
+                        BackgroundError::FailToImportExtension(
+                            LocalStorageError::StorageExtensionFormat(format!(
+                                "no private key for {}",
+                                identity.bech32
+                            )),
+                        )
+                    })?;
+
+                self.add_sk_wallet(
+                    password,
+                    &SecretKey::Secp256k1Sha256Zilliqa(secret_key),
+                    identity.name.clone(),
+                )?;
+                accounts += 1;
+            }
+        }
+
+        let tokens: Vec<String> = data
+            .tokens
+            .iter()
+            .map(|token| token.base16.trim_start_matches("0x").to_lowercase())
+            .collect();
+
+        for wallet in &mut self.wallets[first..] {
+            let accounts: Vec<usize> = wallet.accounts().map(|(account, _)| account).collect();
+
+            for account in accounts {
+                for token in &tokens {
+                    wallet
+                        .add_token(account, token)
+                        .map_err(BackgroundError::FailToSaveWallet)?;
+                }
+            }
+
+            wallet
+                .save_to_storage()
+                .map_err(BackgroundError::FailToSaveWallet)?;
+        }
+
+        let imported = self
+            .storage
+            .import_extension(data)
+            .map_err(BackgroundError::FailToImportExtension)?;
+
+        // account names changed after the wallets were indexed
+        (first..self.wallets.len()).for_each(|wallet| self.index_wallet(wallet));
+        self.index_extension(data);
+
+        Ok(ExtensionImport {
+            accounts,
+            ..imported
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::SearchTarget;
+    use crate::Background;
+    use storage::{
+        migration::extension::{ExtensionData, ExtensionImport},
+        LocalStorage,
+    };
+    use zil_errors::{background::BackgroundError, storage::LocalStorageError};
+
+    // CryptoJS.AES.encrypt of the seed "abandon ... about" and one imported
+    // key, under "extension-pass".
+    const VAULT: &str = "U2FsdGVkX1/xvLJzFscbjQqtZqsl5d/wYaq4KhpV9BFnbTCjelUt3fbBzvU6ai8EhS9xWtrqBo9HnSWfWUDSj0ejh8oEoX8+yRMXK/YfbKyGl0lckppzFDD5IBffrx5+OAnaLezmzrJQauVOjEj0q+waxUChIvKMEiTH3+PLGYH4t4JXtjz7ztYivxbCMrzQwlidS9V436V8E1a0BvIq2Pj5CjMlX0ClXpHf4sGWcCe9VqXhiEutQaYS2aO5lyTIafcRTIt1Qi7LvSP/2Pr7ZBSeGHYy1TX4qoG6e9ZaG+X2LrV5dw63Hbv+uZM/iwOE";
+
+    #[test]
+    fn test_import_extension() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut background = Background::from_storage(LocalStorage::from(&dir).unwrap()).unwrap();
+        let dump = serde_json::json!({
+            "vault": VAULT,
+            "wallet": {
+                "selectedAddress": 0,
+                "identities": [
+                    { "name": "Savings", "bech32": "zil1a", "index": 0, "type": 0 },
+                    { "name": "Mining", "bech32": "zil1b", "index": 1, "type": 0 },
+                    { "name": "Imported", "bech32": "zil1c", "index": 0, "type": 1 },
+                    { "name": "Ledger", "bech32": "zil1d", "index": 0, "type": 2 }
+                ]
+            },
+            "contacts": [{ "name": "Alice", "address": "zil1alice" }],
+            "tokens": [{ "symbol": "ZLP", "base16": "0xFBD07E692543D3064B9CF570B27FAABFD7948DA4" }]
+        });
+        let data = ExtensionData::from_json(dump.to_string().as_bytes()).unwrap();
+
+        assert_eq!(
+            background.import_extension(&data, b"wrong", "password"),
+            Err(BackgroundError::FailToImportExtension(
+                LocalStorageError::StorageWrongPassword
+            ))
+        );
+        assert!(background.wallets.is_empty());
+        assert_eq!(
+            background
+                .import_extension(&data, b"extension-pass", "password")
+                .unwrap(),
+            ExtensionImport {
+                accounts: 3,
+                contacts: 1,
+                tokens: 1
+            }
+        );
+        assert_eq!(background.wallets.len(), 2);
+
+        let names: Vec<&str> = background
+            .wallets
+            .iter()
+            .flat_map(|w| w.accounts().map(|(_, a)| a.name.as_str()))
+            .collect();
+
+        assert_eq!(names, vec!["Savings", "Mining", "Imported"]);
+        assert!(background
+            .wallets
+            .iter()
+            .all(|w| w.accounts().all(|(_, a)| a
+                .ft_map
+                .contains_key("fbd07e692543d3064b9cf570b27faabfd7948da4"))));
+        assert_eq!(
+            background.search("mining")[0].target,
+            SearchTarget::Account {
+                wallet: 0,
+                account: 1
+            }
+        );
+
+        // everything survives a restart
+        drop(background);
+
+        let background = Background::from_storage(LocalStorage::from(&dir).unwrap()).unwrap();
+
+        assert_eq!(background.wallets.len(), 2);
+        assert_eq!(background.wallets[1].account(0).unwrap().name, "Imported");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::Background;
+use config::storage::{
+    ALLOWANCE_TREE, CONTRACT_INIT_TREE, CONTRACT_VERIFICATION_TREE, GC_INTERVAL_MS,
+    GC_LAST_RUN_DB_KEY, PREFETCH_CACHE_TREE, TOKEN_OVERRIDES_TREE, TOMBSTONE_RETENTION_MS,
+};
+use std::collections::HashSet;
+use storage::gc::Reclaimed;
+use zil_errors::{background::BackgroundError, storage::LocalStorageError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub expired: Reclaimed,    // TTL entries and old tombstones
+    pub orphaned: Reclaimed,   // caches of removed accounts and networks
+    pub namespaces: Reclaimed, // data of removed wallets
+}
+
+impl GcReport {
+    pub fn total(&self) -> Reclaimed {
+        let mut total = self.expired;
+
+        total += self.orphaned;
+        total += self.namespaces;
+
+        total
+    }
+}
+
+impl Background {
+    /// Drops cache entries nothing refers to anymore. `networks` are the
+    /// ids the contract caches are keyed by that are still configured.
+    pub fn collect_garbage(
+        &self,
+        networks: &[&str],
+        now: u64,
+    ) -> Result<GcReport, BackgroundError> {
+        let map_err = BackgroundError::FailToCollectGarbage;
+        let accounts: HashSet<String> = self
+            .wallets
+            .iter()
+            .flat_map(|w| w.owned_addresses())
+            .map(|addr| hex::encode(addr.addr_bytes()))
+            .collect();
+        let mut report = GcReport {
+            expired: self
+                .storage
+                .collect_expired(now, TOMBSTONE_RETENTION_MS)
+                .map_err(map_err)?,
+            ..Default::default()
+        };
+
+        report.orphaned += self
+            .storage
+            .retain_tree(PREFETCH_CACHE_TREE, |key| {
+                key_scope(key).is_some_and(|account| accounts.contains(account))
+            })
+            .map_err(map_err)?;
+
+        for tree in [
+            ALLOWANCE_TREE,
+            CONTRACT_INIT_TREE,
+            CONTRACT_VERIFICATION_TREE,
+            TOKEN_OVERRIDES_TREE,
+        ] {
+            report.orphaned += self
+                .storage
+                .retain_tree(tree, |key| {
+                    key_scope(key).is_some_and(|network| networks.contains(&network))
+                })
+                .map_err(map_err)?;
+        }
+
+        let stale = self.stale_namespaces();
+
+        for (ns, stats) in self.storage.namespace_stats().map_err(map_err)? {
+            if stale.contains(&ns) && self.storage.purge_namespace(&ns).map_err(map_err)? {
+                report.namespaces += Reclaimed {
+                    entries: stats.entries,
+                    bytes: stats.bytes,
+                };
+            }
+        }
+
+        Ok(report)
+    }
+
+    // For a periodic task, runs at most once per GC_INTERVAL_MS.
+    pub fn collect_garbage_if_due(
+        &self,
+        networks: &[&str],
+        now: u64,
+    ) -> Result<Option<GcReport>, BackgroundError> {
+        let last_run = match self.storage.get(GC_LAST_RUN_DB_KEY) {
+            Ok(bytes) => bytes.try_into().map(u64::from_le_bytes).unwrap_or(0),
+            Err(LocalStorageError::StorageDataNotFound) => 0,
+            Err(e) => return Err(BackgroundError::FailToCollectGarbage(e)),
+        };
+
+        if now.saturating_sub(last_run) < GC_INTERVAL_MS {
+            return Ok(None);
+        }
+
+        let report = self.collect_garbage(networks, now)?;
+
+        self.storage
+            .set(GC_LAST_RUN_DB_KEY, &now.to_le_bytes())
+            .map_err(BackgroundError::FailToCollectGarbage)?;
+
+        Ok(Some(report))
+    }
+
+    /// Trims the history of every wallet to `settings.storage.retention`,
+    /// for a periodic task next to `collect_garbage_if_due`.
+    pub fn prune(&mut self, now: u64) -> Result<Reclaimed, BackgroundError> {
+        let policy = self.settings.storage.retention;
+        let mut reclaimed = Reclaimed::default();
+
+        for wallet in self.wallets.iter_mut() {
+            let pruned = wallet.prune_history(&policy, now);
+
+            if pruned.entries > 0 {
+                wallet
+                    .save_to_storage()
+                    .map_err(BackgroundError::FailToSaveWallet)?;
+                reclaimed += pruned;
+            }
+        }
+
+        Ok(reclaimed)
+    }
+}
+
+// "scope:rest" keys, the scope being an account or a network id.
+fn key_scope(key: &[u8]) -> Option<&str> {
+    let key = std::str::from_utf8(key).ok()?;
+
+    key.split_once(':').map(|(scope, _)| scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{build_test_vault, TestVaultConfig};
+    use crypto::bip49::Bip49DerivationPath;
+    use storage::retention::RetentionPolicy;
+
+    #[test]
+    fn test_collect_garbage() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut bg = Background::from_storage_path(&dir).unwrap();
+        let words =
+            "green process gate doctor slide whip priority shrug diamond crumble average help";
+
+        bg.add_bip39_wallet("password", words, &[0], Bip49DerivationPath::Zilliqa)
+            .unwrap();
+
+        let account = hex::encode(bg.wallets[0].account(0).unwrap().addr.addr_bytes());
+
+        bg.storage
+            .tree_set(
+                PREFETCH_CACHE_TREE,
+                format!("{account}:balance").as_bytes(),
+                b"{}",
+            )
+            .unwrap();
+        bg.storage
+            .tree_set(PREFETCH_CACHE_TREE, b"deadbeef:balance", b"{}")
+            .unwrap();
+        bg.storage
+            .tree_set(CONTRACT_INIT_TREE, b"mainnet:a1", b"[]")
+            .unwrap();
+        bg.storage
+            .tree_set(CONTRACT_INIT_TREE, b"olddevnet:a1", b"[]")
+            .unwrap();
+        bg.storage.ns_set(&[7u8; 32], b"history", b"[]").unwrap();
+        bg.storage.set_with_ttl(b"gas_price", b"2000", 0).unwrap();
+
+        let report = bg.collect_garbage(&["mainnet"], u64::MAX).unwrap();
+
+        assert_eq!(report.expired.entries, 1);
+        assert_eq!(report.orphaned.entries, 2);
+        assert_eq!(report.namespaces.entries, 1);
+        assert!(report.total().bytes > 0);
+        assert!(bg
+            .storage
+            .tree_get(CONTRACT_INIT_TREE, b"mainnet:a1")
+            .unwrap()
+            .is_some());
+        assert!(bg
+            .storage
+            .tree_get(PREFETCH_CACHE_TREE, format!("{account}:balance").as_bytes())
+            .unwrap()
+            .is_some());
+
+        assert!(bg
+            .collect_garbage_if_due(&["mainnet"], GC_INTERVAL_MS)
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            bg.collect_garbage_if_due(&["mainnet"], GC_INTERVAL_MS + 1),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_prune_history() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut bg = build_test_vault(&dir, TestVaultConfig::default())
+            .unwrap()
+            .background;
+
+        bg.settings.storage.retention = RetentionPolicy {
+            max_records: Some(4),
+            ..RetentionPolicy::unlimited()
+        };
+
+        // the four newest stay, and the pending record among the rest
+        assert_eq!(bg.prune(u64::MAX).unwrap().entries, 5);
+        assert_eq!(bg.wallets[0].history().records().len(), 5);
+        assert_eq!(bg.prune(u64::MAX).unwrap(), Reclaimed::default());
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    rc::Rc,
+};
+
+use config::{
+    storage::KEY_USAGE_DB_KEY,
+    wallet::{KEY_USAGE_ANOMALY_FACTOR, KEY_USAGE_MIN_SAMPLES, KEY_USAGE_WINDOW},
+};
+use serde::{Deserialize, Serialize};
+use storage::LocalStorage;
+use zil_errors::{key_usage::KeyUsageErrors, storage::LocalStorageError};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningEvent {
+    pub account: String,         // bech32 or base16, as long as it is stable
+    pub asset: String,           // "zil" or token contract
+    pub amount: u128,            // base units of `asset`
+    pub country: Option<String>, // of the dApp origin, when known
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyAnomaly {
+    AmountSpike { typical: u128, amount: u128 },
+    NewCountry(String),
+    SigningBurst { last_hour: usize, typical: usize },
+}
+
+/// Per-account baseline, the user specific input of risk scoring.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountUsage {
+    pub signatures: u64,
+    pub first_at: u64,
+    pub last_at: u64,
+    recent_at: VecDeque<u64>,
+    amounts: HashMap<String, VecDeque<u128>>,
+    countries: BTreeSet<String>,
+}
+
+impl AccountUsage {
+    // Median of the recent amounts of `asset`, None until there are enough.
+    pub fn typical_amount(&self, asset: &str) -> Option<u128> {
+        let amounts = self.amounts.get(asset)?;
+
+        if amounts.len() < KEY_USAGE_MIN_SAMPLES {
+            return None;
+        }
+
+        let mut sorted: Vec<u128> = amounts.iter().copied().collect();
+
+        sorted.sort_unstable();
+
+        Some(sorted[sorted.len() / 2])
+    }
+
+    // Average signatures per active hour over the recent window.
+    pub fn typical_hourly(&self) -> Option<usize> {
+        if self.recent_at.len() < KEY_USAGE_MIN_SAMPLES {
+            return None;
+        }
+
+        let first = self.recent_at.front()?;
+        let last = self.recent_at.back()?;
+        let hours = (last.saturating_sub(*first) / HOUR_MS).max(1) as usize;
+
+        Some(self.recent_at.len().div_ceil(hours))
+    }
+
+    pub fn countries(&self) -> &BTreeSet<String> {
+        &self.countries
+    }
+
+    fn last_hour(&self, now: u64) -> usize {
+        self.recent_at
+            .iter()
+            .filter(|at| now.saturating_sub(**at) < HOUR_MS)
+            .count()
+    }
+
+    fn record(&mut self, event: &SigningEvent, now: u64) {
+        if self.signatures == 0 {
+            self.first_at = now;
+        }
+
+        self.signatures += 1;
+        self.last_at = now;
+        push_window(&mut self.recent_at, now);
+        push_window(
+            self.amounts.entry(event.asset.clone()).or_default(),
+            event.amount,
+        );
+
+        if let Some(country) = &event.country {
+            self.countries.insert(country.to_uppercase());
+        }
+    }
+}
+
+/// Local signing statistics. Every approved signature is observed, the
+/// returned anomalies are checked against the baseline before it is updated.
+pub struct KeyUsageStats {
+    storage: Rc<LocalStorage>,
+    accounts: HashMap<String, AccountUsage>,
+}
+
+impl KeyUsageStats {
+    pub fn load(storage: Rc<LocalStorage>) -> Result<Self, KeyUsageErrors> {
+        let accounts = match storage.get(KEY_USAGE_DB_KEY) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).or(Err(KeyUsageErrors::FailToDeserialize))?
+            }
+            Err(LocalStorageError::StorageDataNotFound) => HashMap::new(),
+            Err(e) => return Err(KeyUsageErrors::FailToLoad(e)),
+        };
+
+        Ok(Self { storage, accounts })
+    }
+
+    pub fn baseline(&self, account: &str) -> Option<&AccountUsage> {
+        self.accounts.get(account)
+    }
+
+    // Dry run, e.g. to warn on the confirmation screen.
+    pub fn check(&self, event: &SigningEvent, now: u64) -> Vec<KeyAnomaly> {
+        let Some(usage) = self.accounts.get(&event.account) else {
+            return Vec::new();
+        };
+        let mut anomalies = Vec::new();
+
+        if let Some(typical) = usage.typical_amount(&event.asset) {
+            if event.amount > typical.max(1).saturating_mul(KEY_USAGE_ANOMALY_FACTOR) {
+                anomalies.push(KeyAnomaly::AmountSpike {
+                    typical,
+                    amount: event.amount,
+                });
+            }
+        }
+
+        if let Some(country) = &event.country {
+            let country = country.to_uppercase();
+
+            if !usage.countries.is_empty() && !usage.countries.contains(&country) {
+                anomalies.push(KeyAnomaly::NewCountry(country));
+            }
+        }
+
+        if let Some(typical) = usage.typical_hourly() {
+            let last_hour = usage.last_hour(now) + 1;
+
+            if last_hour as u128 > typical as u128 * KEY_USAGE_ANOMALY_FACTOR {
+                anomalies.push(KeyAnomaly::SigningBurst { last_hour, typical });
+            }
+        }
+
+        anomalies
+    }
+
+    pub fn observe(
+        &mut self,
+        event: &SigningEvent,
+        now: u64,
+    ) -> Result<Vec<KeyAnomaly>, KeyUsageErrors> {
+        let anomalies = self.check(event, now);
+
+        self.accounts
+            .entry(event.account.clone())
+            .or_default()
+            .record(event, now);
+        self.save()?;
+
+        Ok(anomalies)
+    }
+
+    fn save(&self) -> Result<(), KeyUsageErrors> {
+        let bytes = serde_json::to_vec(&self.accounts).or(Err(KeyUsageErrors::FailToSerialize))?;
+
+        self.storage
+            .set(KEY_USAGE_DB_KEY, &bytes)
+            .map_err(KeyUsageErrors::FailToSave)
+    }
+}
+
+fn push_window<T>(window: &mut VecDeque<T>, value: T) {
+    if window.len() == KEY_USAGE_WINDOW {
+        window.pop_front();
+    }
+
+    window.push_back(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(amount: u128, country: Option<&str>) -> SigningEvent {
+        SigningEvent {
+            account: "zil1account".to_string(),
+            asset: "zil".to_string(),
+            amount,
+            country: country.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let mut stats = KeyUsageStats::load(Rc::clone(&storage)).unwrap();
+
+        for day in 0..KEY_USAGE_MIN_SAMPLES as u64 {
+            let anomalies = stats
+                .observe(&event(100 + day as u128, Some("de")), day * 24 * HOUR_MS)
+                .unwrap();
+
+            assert!(anomalies.is_empty());
+        }
+
+        let now = 10 * 24 * HOUR_MS;
+
+        assert_eq!(stats.check(&event(900, Some("DE")), now), Vec::new());
+        assert_eq!(
+            stats.check(&event(5_000, Some("br")), now),
+            vec![
+                KeyAnomaly::AmountSpike {
+                    typical: 102,
+                    amount: 5_000
+                },
+                KeyAnomaly::NewCountry("BR".to_string()),
+            ]
+        );
+
+        drop(stats);
+
+        let stats = KeyUsageStats::load(storage).unwrap();
+        let baseline = stats.baseline("zil1account").unwrap();
+
+        assert_eq!(baseline.signatures, KEY_USAGE_MIN_SAMPLES as u64);
+        assert_eq!(baseline.typical_amount("zil"), Some(102));
+        assert!(stats.check(&event(5_000, None), now).len() == 1);
+    }
+
+    #[test]
+    fn test_signing_burst() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut stats = KeyUsageStats::load(Rc::new(LocalStorage::from(&dir).unwrap())).unwrap();
+        let mut burst = Vec::new();
+
+        // one signature a day, then a flood within minutes
+        for n in 0..KEY_USAGE_MIN_SAMPLES as u64 {
+            stats.observe(&event(1, None), n * 24 * HOUR_MS).unwrap();
+        }
+
+        for n in 0..12 {
+            burst = stats
+                .observe(&event(1, None), 30 * 24 * HOUR_MS + n)
+                .unwrap();
+        }
+
+        assert!(matches!(
+            burst.as_slice(),
+            [KeyAnomaly::SigningBurst { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_clock_went_back() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut stats = KeyUsageStats::load(Rc::new(LocalStorage::from(&dir).unwrap())).unwrap();
+
+        for n in 0..KEY_USAGE_MIN_SAMPLES as u64 {
+            stats.observe(&event(1, None), 100 * HOUR_MS + n).unwrap();
+        }
+
+        // the window ends before it starts, the rate is taken over one hour
+        stats.observe(&event(1, None), 0).unwrap();
+        stats.observe(&event(1, None), 1).unwrap();
+    }
+}
@@ -1,3 +1,12 @@
+pub mod diagnostics;
+pub mod extension;
+pub mod gc;
+pub mod key_usage;
+pub mod search;
+pub mod sign_requests;
+#[cfg(any(test, feature = "test_support"))]
+pub mod test_support;
+
 use std::rc::Rc;
 
 use bip39::Mnemonic;
@@ -5,15 +14,25 @@ use cipher::{argon2, keychain::KeyChain};
 use config::{
     cipher::PROOF_SIZE,
     sha::SHA256_SIZE,
-    storage::{INDICATORS_DB_KEY, SELECTED_WALLET_DB_KEY},
+    storage::{BROADCAST_QUEUE_DB_KEY, INDICATORS_DB_KEY, SELECTED_WALLET_DB_KEY},
 };
 use crypto::bip49::Bip49DerivationPath;
+use key_usage::KeyUsageStats;
 use proto::secret_key::SecretKey;
+use search::SearchIndex;
 use session::Session;
-use settings::common_settings::CommonSettings;
+use settings::{common_settings::CommonSettings, wallet_settings::WalletSettings};
+use sign_requests::SignRequestGuard;
 use storage::LocalStorage;
 use wallet::{Wallet, WalletConfig};
-use zil_errors::background::BackgroundError;
+use zil_errors::{
+    background::BackgroundError, key_usage::KeyUsageErrors, sign_request::SignRequestErrors,
+    storage::LocalStorageError,
+};
+use zilliqa::json_rpc::{
+    allowance_cache::AllowanceCache,
+    broadcast::{BroadcastQueue, QueuedTx},
+};
 
 pub struct Background {
     storage: Rc<LocalStorage>,
@@ -22,12 +41,23 @@ pub struct Background {
     pub indicators: Vec<[u8; SHA256_SIZE]>,
     pub is_old_storage: bool,
     pub settings: CommonSettings,
+    pub wallet_settings: WalletSettings, // template for new wallets
+    pub search: SearchIndex,
 }
 
 impl Background {
     pub fn from_storage_path(path: &str) -> Result<Self, BackgroundError> {
         let storage =
             LocalStorage::from(path).map_err(BackgroundError::TryInitLocalStorageError)?;
+
+        Self::from_storage(storage)
+    }
+
+    pub fn storage(&self) -> Rc<LocalStorage> {
+        Rc::clone(&self.storage)
+    }
+
+    pub fn from_storage(storage: LocalStorage) -> Result<Self, BackgroundError> {
         let storage = Rc::new(storage);
         let is_old_storage = false; // TODO: check old storage from first ZilPay version
 
@@ -56,14 +86,22 @@ impl Background {
             wallets.push(w);
         }
 
-        Ok(Self {
+        let mut background = Self {
             storage,
             wallets,
             selected,
             indicators: Vec::new(),
             is_old_storage,
             settings: Default::default(),
-        })
+            wallet_settings: Default::default(),
+            search: Default::default(),
+        };
+
+        background
+            .rebuild_search()
+            .map_err(BackgroundError::FailToBuildSearchIndex)?;
+
+        Ok(background)
     }
 
     pub fn add_bip39_wallet<F>(
@@ -76,8 +114,9 @@ impl Background {
     where
         F: Fn(usize) -> Bip49DerivationPath,
     {
-        let argon_seed = argon2::derive_key(password.as_bytes())
-            .map_err(BackgroundError::ArgonPasswordHashError)?;
+        let argon_seed =
+            argon2::derive_key_with_params(password.as_bytes(), &self.wallet_settings.crypto.kdf)
+                .map_err(BackgroundError::ArgonPasswordHashError)?;
         let (session, key) =
             Session::unlock(&argon_seed).map_err(BackgroundError::CreateSessionError)?;
         let keychain =
@@ -94,7 +133,7 @@ impl Background {
             session,
             keychain,
             storage: Rc::clone(&self.storage),
-            settings: Default::default(), // TODO: setup settings
+            settings: self.wallet_settings.clone(),
         };
         let wallet = Wallet::from_bip39_words(&proof, &mnemonic, "", &indexes, wallet_config)
             .map_err(BackgroundError::FailToInitWallet)?;
@@ -106,9 +145,9 @@ impl Background {
         self.indicators.push(indicator);
         self.wallets.push(wallet);
         self.selected = indicator;
+        self.index_wallet(self.wallets.len() - 1);
 
         self.save_indicators()?;
-        self.save_selected()?;
 
         Ok(key)
     }
@@ -119,8 +158,9 @@ impl Background {
         secret_key: &SecretKey,
         account_name: String,
     ) -> Result<[u8; SHA256_SIZE], BackgroundError> {
-        let argon_seed = argon2::derive_key(password.as_bytes())
-            .map_err(BackgroundError::ArgonPasswordHashError)?;
+        let argon_seed =
+            argon2::derive_key_with_params(password.as_bytes(), &self.wallet_settings.crypto.kdf)
+                .map_err(BackgroundError::ArgonPasswordHashError)?;
         let (session, key) =
             Session::unlock(&argon_seed).map_err(BackgroundError::CreateSessionError)?;
         let keychain =
@@ -131,7 +171,7 @@ impl Background {
             session,
             keychain,
             storage: Rc::clone(&self.storage),
-            settings: Default::default(), // TODO: setup settings
+            settings: self.wallet_settings.clone(),
         };
         let wallet = Wallet::from_sk(secret_key, account_name, &proof, wallet_config)
             .map_err(BackgroundError::FailToInitWallet)?;
@@ -143,13 +183,85 @@ impl Background {
         self.indicators.push(indicator);
         self.wallets.push(wallet);
         self.selected = indicator;
+        self.index_wallet(self.wallets.len() - 1);
 
         self.save_indicators()?;
-        self.save_selected()?;
 
         Ok(key)
     }
 
+    pub fn sign_request_guard(&self) -> Result<SignRequestGuard, SignRequestErrors> {
+        SignRequestGuard::load(Rc::clone(&self.storage))
+    }
+
+    pub fn key_usage_stats(&self) -> Result<KeyUsageStats, KeyUsageErrors> {
+        KeyUsageStats::load(Rc::clone(&self.storage))
+    }
+
+    pub fn allowance_cache(&self) -> AllowanceCache {
+        AllowanceCache::new(Rc::clone(&self.storage))
+    }
+
+    // Receipts of the queue's txs keep the allowances of `network` current.
+    pub fn load_broadcast_queue(&self, network: &str) -> Result<BroadcastQueue, BackgroundError> {
+        let entries: Vec<QueuedTx> = match self.storage.get(BROADCAST_QUEUE_DB_KEY) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .or(Err(BackgroundError::FailToDeserializeBroadcastQueue))?,
+            Err(LocalStorageError::StorageDataNotFound) => Vec::new(),
+            Err(e) => return Err(BackgroundError::FailToLoadBroadcastQueue(e)),
+        };
+
+        let mut queue = BroadcastQueue::from_entries(entries);
+
+        queue.track_allowances(self.allowance_cache(), network);
+
+        Ok(queue)
+    }
+
+    pub fn save_broadcast_queue(&self, queue: &BroadcastQueue) -> Result<(), BackgroundError> {
+        let bytes = serde_json::to_vec(queue.entries())
+            .or(Err(BackgroundError::FailToSerializeBroadcastQueue))?;
+
+        self.storage
+            .set(BROADCAST_QUEUE_DB_KEY, &bytes)
+            .map_err(BackgroundError::FailToSaveBroadcastQueue)
+    }
+
+    /// Namespaces left by wallets that are no longer in the vault, e.g. after
+    /// restoring a different mnemonic on the same device.
+    // Only wallet fingerprints count, named namespaces belong to subsystems.
+    pub fn stale_namespaces(&self) -> Vec<Vec<u8>> {
+        let active: Vec<[u8; SHA256_SIZE]> =
+            self.wallets.iter().filter_map(|w| w.key().ok()).collect();
+
+        self.storage
+            .namespaces()
+            .into_iter()
+            .filter(|ns| ns.len() == SHA256_SIZE)
+            .filter(|ns| !active.iter().any(|key| key.as_slice() == ns.as_slice()))
+            .collect()
+    }
+
+    // Returns how many namespaces were dropped.
+    pub fn purge_stale_namespaces(&self) -> Result<usize, BackgroundError> {
+        let mut purged = 0;
+
+        for ns in self.stale_namespaces() {
+            if self
+                .storage
+                .purge_namespace(&ns)
+                .map_err(BackgroundError::FailToPurgeNamespace)?
+            {
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+
+    // Selected wallet goes along, an index pointing past the list would
+    // survive a crash between two separate writes. Called last when a wallet
+    // is added, the flush makes the whole onboarding durable.
     fn save_indicators(&self) -> Result<(), BackgroundError> {
         let bytes: Vec<u8> = self
             .indicators
@@ -158,16 +270,14 @@ impl Background {
             .collect();
 
         self.storage
-            .set(INDICATORS_DB_KEY, &bytes)
+            .set_batch(&[
+                (INDICATORS_DB_KEY, &bytes),
+                (SELECTED_WALLET_DB_KEY, &self.selected),
+            ])
             .map_err(BackgroundError::FailToWriteIndicatorsWallet)?;
-
-        Ok(())
-    }
-
-    fn save_selected(&self) -> Result<(), BackgroundError> {
         self.storage
-            .set(SELECTED_WALLET_DB_KEY, &self.selected)
-            .map_err(BackgroundError::FailWriteSelectedWallet)?;
+            .flush()
+            .map_err(BackgroundError::FailToFlushStorage)?;
 
         Ok(())
     }
@@ -179,6 +289,42 @@ mod tests_background {
     use proto::keypair::KeyPair;
     use rand::Rng;
 
+    #[test]
+    fn test_broadcast_queue_persists() {
+        let mut rng = rand::thread_rng();
+        let dir = format!("/tmp/{}", rng.gen::<usize>());
+        let bg = Background::from_storage_path(&dir).unwrap();
+        let mut queue = bg.load_broadcast_queue("mainnet").unwrap();
+
+        assert!(queue.entries().is_empty());
+
+        queue.enqueue("tx".to_string(), serde_json::json!({ "nonce": 1 }), 0);
+        bg.save_broadcast_queue(&queue).unwrap();
+
+        let restored = bg.load_broadcast_queue("mainnet").unwrap();
+
+        assert_eq!(restored.entries(), queue.entries());
+    }
+
+    #[test]
+    fn test_purge_stale_namespaces() {
+        let mut rng = rand::thread_rng();
+        let dir = format!("/tmp/{}", rng.gen::<usize>());
+        let mut bg = Background::from_storage_path(&dir).unwrap();
+        let words: &str =
+            "green process gate doctor slide whip priority shrug diamond crumble average help";
+
+        bg.add_bip39_wallet("password", words, &[0], Bip49DerivationPath::Zilliqa)
+            .unwrap();
+        bg.storage.ns_set(&[7u8; 32], b"history", b"[]").unwrap();
+
+        assert_eq!(bg.stale_namespaces(), vec![vec![7u8; 32]]);
+        assert_eq!(bg.purge_stale_namespaces(), Ok(1));
+        assert!(bg.stale_namespaces().is_empty());
+        // the wallet's and the vault
+        assert_eq!(bg.storage.namespaces().len(), 2);
+    }
+
     #[test]
     fn test_from_bip39() {
         let mut rng = rand::thread_rng();
@@ -0,0 +1,404 @@
+use crate::Background;
+use config::{
+    storage::{EXTENSION_CONTACTS_NS, EXTENSION_TOKENS_NS},
+    wallet::SEARCH_RESULTS_LIMIT,
+};
+use std::collections::{HashMap, HashSet};
+use storage::{
+    collection::Collection,
+    migration::extension::{ExtensionContact, ExtensionData, ExtensionToken},
+};
+use wallet::Wallet;
+use zil_errors::{background::BackgroundError, storage::LocalStorageError, wallet::WalletErrors};
+
+type Trigram = [char; 3];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SearchTarget {
+    Account { wallet: usize, account: usize },
+    Contact(String), // address
+    Token(String),   // base16 contract, lowercase
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub target: SearchTarget,
+    pub label: String,
+    pub score: u32,
+}
+
+/// Trigram index over account names, contact names and token symbols for
+/// the global search bar. Kept in memory and updated on every write, it is
+/// small enough to rebuild from storage on start.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    labels: HashMap<SearchTarget, String>,
+    trigrams: HashMap<Trigram, HashSet<SearchTarget>>,
+}
+
+impl SearchIndex {
+    pub fn insert(&mut self, target: SearchTarget, label: &str) {
+        self.remove(&target);
+
+        for trigram in trigrams(&normalize(label), true) {
+            self.trigrams
+                .entry(trigram)
+                .or_default()
+                .insert(target.clone());
+        }
+
+        self.labels.insert(target, label.to_string());
+    }
+
+    pub fn remove(&mut self, target: &SearchTarget) -> bool {
+        let Some(label) = self.labels.remove(target) else {
+            return false;
+        };
+
+        for trigram in trigrams(&normalize(&label), true) {
+            if let Some(targets) = self.trigrams.get_mut(&trigram) {
+                targets.remove(target);
+
+                if targets.is_empty() {
+                    self.trigrams.remove(&trigram);
+                }
+            }
+        }
+
+        true
+    }
+
+    // Accounts of `wallet`, before they are indexed again.
+    fn remove_wallet(&mut self, wallet: usize) {
+        let targets: Vec<SearchTarget> = self
+            .labels
+            .keys()
+            .filter(
+                |target| matches!(target, SearchTarget::Account { wallet: w, .. } if *w == wallet),
+            )
+            .cloned()
+            .collect();
+
+        for target in targets {
+            self.remove(&target);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Exact matches first, then prefixes, word prefixes, substrings and
+    /// finally labels sharing enough trigrams to be a typo away.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query = normalize(query);
+
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let query_trigrams = trigrams(&query, false);
+        // too short for trigrams, the labels are few enough for a scan
+        let candidates: HashSet<&SearchTarget> = if query.chars().count() < 3 {
+            self.labels.keys().collect()
+        } else {
+            query_trigrams
+                .iter()
+                .filter_map(|trigram| self.trigrams.get(trigram))
+                .flatten()
+                .collect()
+        };
+        let mut hits: Vec<SearchHit> = candidates
+            .into_iter()
+            .filter_map(|target| {
+                let label = &self.labels[target];
+                let score = score(&query, &query_trigrams, &normalize(label))?;
+
+                Some(SearchHit {
+                    target: target.clone(),
+                    label: label.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.label.cmp(&b.label))
+                .then_with(|| a.target.cmp(&b.target))
+        });
+        hits.truncate(SEARCH_RESULTS_LIMIT);
+
+        hits
+    }
+}
+
+impl Background {
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        self.search.search(query)
+    }
+
+    /// Account changes go through here: `update` runs on the wallet, which
+    /// is saved and indexed again, so renames, additions and removals that
+    /// shift the account indexes are found right away.
+    pub fn update_wallet<R>(
+        &mut self,
+        wallet: usize,
+        update: impl FnOnce(&mut Wallet) -> Result<R, WalletErrors>,
+    ) -> Result<R, BackgroundError> {
+        let target = self
+            .wallets
+            .get_mut(wallet)
+            .ok_or(BackgroundError::WalletNotExists(wallet))?;
+        let out = update(target).map_err(BackgroundError::FailToUpdateWallet)?;
+
+        // indexed even if the save fails, memory is what the user sees
+        self.index_wallet(wallet);
+        self.wallets[wallet]
+            .save_to_storage()
+            .map_err(BackgroundError::FailToSaveWallet)?;
+
+        Ok(out)
+    }
+
+    // Keyed by address, an existing contact is replaced.
+    pub fn set_contact(&mut self, contact: &ExtensionContact) -> Result<(), BackgroundError> {
+        Collection::<ExtensionContact>::new(&self.storage, EXTENSION_CONTACTS_NS)
+            .and_then(|contacts| contacts.insert(&contact.address, contact))
+            .map_err(BackgroundError::FailToSaveContact)?;
+        self.search.insert(
+            SearchTarget::Contact(contact.address.clone()),
+            &contact.name,
+        );
+
+        Ok(())
+    }
+
+    pub fn remove_contact(&mut self, address: &str) -> Result<bool, BackgroundError> {
+        let removed = Collection::<ExtensionContact>::new(&self.storage, EXTENSION_CONTACTS_NS)
+            .and_then(|contacts| contacts.remove(&address.to_string()))
+            .map_err(BackgroundError::FailToSaveContact)?;
+
+        self.search
+            .remove(&SearchTarget::Contact(address.to_string()));
+
+        Ok(removed)
+    }
+
+    pub(crate) fn index_extension(&mut self, data: &ExtensionData) {
+        for contact in &data.contacts {
+            self.search.insert(
+                SearchTarget::Contact(contact.address.clone()),
+                &contact.name,
+            );
+        }
+
+        for token in &data.tokens {
+            self.search.insert(
+                SearchTarget::Token(token.base16.to_lowercase()),
+                &token.symbol,
+            );
+        }
+    }
+
+    pub(crate) fn index_wallet(&mut self, wallet: usize) {
+        self.search.remove_wallet(wallet);
+
+        let accounts: Vec<(usize, String)> = self.wallets[wallet]
+            .accounts()
+            .map(|(account, a)| (account, a.name.clone()))
+            .collect();
+
+        for (account, name) in accounts {
+            self.search
+                .insert(SearchTarget::Account { wallet, account }, &name);
+        }
+    }
+
+    pub(crate) fn rebuild_search(&mut self) -> Result<(), LocalStorageError> {
+        self.search = SearchIndex::default();
+
+        (0..self.wallets.len()).for_each(|wallet| self.index_wallet(wallet));
+
+        // opening a collection creates its namespace, nothing to index then
+        let namespaces = self.storage.namespaces();
+        let exists = |ns: &str| namespaces.iter().any(|name| name == ns.as_bytes());
+
+        if exists(EXTENSION_CONTACTS_NS) {
+            let contacts =
+                Collection::<ExtensionContact>::new(&self.storage, EXTENSION_CONTACTS_NS)?;
+
+            for entry in contacts.iter() {
+                let (address, contact) = entry?;
+
+                self.search
+                    .insert(SearchTarget::Contact(address), &contact.name);
+            }
+        }
+
+        if exists(EXTENSION_TOKENS_NS) {
+            let tokens = Collection::<ExtensionToken>::new(&self.storage, EXTENSION_TOKENS_NS)?;
+
+            for entry in tokens.iter() {
+                let (base16, token) = entry?;
+
+                self.search
+                    .insert(SearchTarget::Token(base16), &token.symbol);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+// Labels are padded so that prefixes of a word get trigrams of their own.
+fn trigrams(text: &str, pad_end: bool) -> Vec<Trigram> {
+    let mut chars: Vec<char> = "  ".chars().chain(text.chars()).collect();
+
+    if pad_end {
+        chars.push(' ');
+    }
+
+    let mut out: Vec<Trigram> = chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
+
+    out.sort_unstable();
+    out.dedup();
+
+    out
+}
+
+fn score(query: &str, query_trigrams: &[Trigram], label: &str) -> Option<u32> {
+    if label == query {
+        return Some(1000);
+    }
+
+    // shorter labels rank first within a tier
+    let rest = (label.len().saturating_sub(query.len())).min(99) as u32;
+
+    if label.starts_with(query) {
+        return Some(800 - rest);
+    }
+
+    if label.split_whitespace().any(|word| word.starts_with(query)) {
+        return Some(600 - rest);
+    }
+
+    if label.contains(query) {
+        return Some(400 - rest);
+    }
+
+    let label_trigrams = trigrams(label, true);
+    let shared = query_trigrams
+        .iter()
+        .filter(|trigram| label_trigrams.contains(trigram))
+        .count();
+
+    // Dice coefficient, one typo in a short word still makes half of it
+    let total = query_trigrams.len() + label_trigrams.len();
+
+    (shared * 4 >= total).then(|| (shared * 2 * 300 / total) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(index: &SearchIndex, query: &str) -> Vec<String> {
+        index
+            .search(query)
+            .into_iter()
+            .map(|hit| hit.label)
+            .collect()
+    }
+
+    #[test]
+    fn test_search_ranking() {
+        let mut index = SearchIndex::default();
+
+        index.insert(SearchTarget::Contact("zil1alice".to_string()), "Alice");
+        index.insert(SearchTarget::Contact("zil1ali".to_string()), "Ali Baba");
+        index.insert(
+            SearchTarget::Account {
+                wallet: 0,
+                account: 1,
+            },
+            "Savings Alice",
+        );
+        index.insert(SearchTarget::Token("0xa1".to_string()), "ZLP");
+
+        assert_eq!(
+            labels(&index, "ali"),
+            vec!["Alice", "Ali Baba", "Savings Alice"]
+        );
+        assert_eq!(labels(&index, "alice"), vec!["Alice", "Savings Alice"]);
+        assert_eq!(labels(&index, "zl"), vec!["ZLP"]);
+        // one typo
+        assert_eq!(labels(&index, "alise"), vec!["Alice"]);
+        assert!(labels(&index, "   ").is_empty());
+
+        index.insert(SearchTarget::Contact("zil1alice".to_string()), "Bob");
+
+        assert_eq!(labels(&index, "alice"), vec!["Savings Alice"]);
+        assert!(index.remove(&SearchTarget::Token("0xa1".to_string())));
+        assert!(labels(&index, "zlp").is_empty());
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn test_index_follows_writes() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut background =
+            Background::from_storage(storage::LocalStorage::from(&dir).unwrap()).unwrap();
+        let words = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        background
+            .add_bip39_wallet(
+                "password",
+                words,
+                &[0, 1, 2],
+                crypto::bip49::Bip49DerivationPath::Zilliqa,
+            )
+            .unwrap();
+        background
+            .update_wallet(0, |wallet| wallet.rename_account(2, "Trading"))
+            .unwrap();
+        background
+            .update_wallet(0, |wallet| wallet.remove_account(1))
+            .unwrap();
+
+        let hits = background.search("trading");
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].target,
+            SearchTarget::Account {
+                wallet: 0,
+                account: 1
+            }
+        );
+        assert_eq!(background.search.len(), 2);
+        assert_eq!(
+            background.update_wallet(1, |_| Ok(())),
+            Err(BackgroundError::WalletNotExists(1))
+        );
+
+        let contact = ExtensionContact {
+            name: "Alice".to_string(),
+            address: "zil1alice".to_string(),
+        };
+
+        background.set_contact(&contact).unwrap();
+        assert_eq!(labels(&background.search, "alice"), vec!["Alice"]);
+        assert!(background.remove_contact("zil1alice").unwrap());
+        assert!(background.search("alice").is_empty());
+    }
+}
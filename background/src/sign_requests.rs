@@ -0,0 +1,125 @@
+use std::{collections::HashMap, rc::Rc};
+
+use config::{session::SIGN_REQUEST_MAX_TTL_MS, storage::SERVED_SIGN_REQUESTS_DB_KEY};
+use storage::LocalStorage;
+use zil_errors::{sign_request::SignRequestErrors, storage::LocalStorageError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignRequest {
+    pub origin: String,  // dApp domain
+    pub nonce: u64,      // unique per origin, chosen by the dApp
+    pub expires_at: u64, // unix millis
+}
+
+impl SignRequest {
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.origin, self.nonce)
+    }
+}
+
+/// Tracks served dApp signing requests so an approval can't be replayed,
+/// ids are kept until the request expires and survive restarts.
+pub struct SignRequestGuard {
+    storage: Rc<LocalStorage>,
+    served: HashMap<String, u64>,
+}
+
+impl SignRequestGuard {
+    pub fn load(storage: Rc<LocalStorage>) -> Result<Self, SignRequestErrors> {
+        let served = match storage.get(SERVED_SIGN_REQUESTS_DB_KEY) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).or(Err(SignRequestErrors::FailToDeserialize))?
+            }
+            Err(LocalStorageError::StorageDataNotFound) => HashMap::new(),
+            Err(e) => return Err(SignRequestErrors::FailToLoad(e)),
+        };
+
+        Ok(Self { storage, served })
+    }
+
+    pub fn check(&self, req: &SignRequest, now: u64) -> Result<(), SignRequestErrors> {
+        if req.expires_at <= now {
+            return Err(SignRequestErrors::Expired(req.expires_at));
+        }
+
+        // the served id would otherwise be stored for as long as the dApp asks
+        if req.expires_at - now > SIGN_REQUEST_MAX_TTL_MS {
+            return Err(SignRequestErrors::ExpiresTooLate(req.expires_at));
+        }
+
+        let id = req.id();
+
+        if self.served.contains_key(&id) {
+            return Err(SignRequestErrors::Replayed(id));
+        }
+
+        Ok(())
+    }
+
+    pub fn accept(&mut self, req: &SignRequest, now: u64) -> Result<(), SignRequestErrors> {
+        self.check(req, now)?;
+
+        // expired ids can't be replayed anyway
+        self.served.retain(|_, expires_at| *expires_at > now);
+        self.served.insert(req.id(), req.expires_at);
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), SignRequestErrors> {
+        let bytes = serde_json::to_vec(&self.served).or(Err(SignRequestErrors::FailToSerialize))?;
+
+        self.storage
+            .set(SERVED_SIGN_REQUESTS_DB_KEY, &bytes)
+            .map_err(SignRequestErrors::FailToSave)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn req(nonce: u64, expires_at: u64) -> SignRequest {
+        SignRequest {
+            origin: "dapp.example".to_string(),
+            nonce,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_replay_and_expiry() {
+        let mut rng = rand::thread_rng();
+        let dir = format!("/tmp/{}", rng.gen::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let mut guard = SignRequestGuard::load(Rc::clone(&storage)).unwrap();
+
+        guard.accept(&req(1, 2_000), 1_000).unwrap();
+
+        assert_eq!(
+            guard.accept(&req(1, 2_000), 1_500),
+            Err(SignRequestErrors::Replayed("dapp.example:1".to_string()))
+        );
+        assert_eq!(
+            guard.accept(&req(2, 1_000), 1_500),
+            Err(SignRequestErrors::Expired(1_000))
+        );
+        assert_eq!(
+            guard.accept(&req(4, u64::MAX), 1_500),
+            Err(SignRequestErrors::ExpiresTooLate(u64::MAX))
+        );
+        guard
+            .accept(&req(5, 1_500 + SIGN_REQUEST_MAX_TTL_MS), 1_500)
+            .unwrap();
+
+        drop(guard);
+
+        // served ids survive a reconnect
+        let mut guard = SignRequestGuard::load(Rc::clone(&storage)).unwrap();
+
+        assert!(guard.check(&req(1, 2_000), 1_500).is_err());
+
+        guard.accept(&req(3, 5_000), 3_000).unwrap();
+        assert!(!guard.served.contains_key("dapp.example:1"));
+    }
+}
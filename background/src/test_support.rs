@@ -0,0 +1,197 @@
+//! Deterministic vault for UI and integration tests, never touches a network.
+//! Seed, accounts, addresses, token balances and history are fixed for a
+//! given config; only ciphertexts and their storage slots are random.
+
+use crate::Background;
+use config::sha::SHA256_SIZE;
+use crypto::bip49::Bip49DerivationPath;
+use num256::uint256::Uint256;
+use proto::{
+    address::Address,
+    asset::{AssetAmount, AssetId, TokenAmount},
+    token_meta::TokenMetadata,
+    units::QA_PER_ZIL,
+    zil_tx::ZilAmount,
+};
+use wallet::history::{HistoryRecord, TxStatus};
+use zil_errors::background::BackgroundError;
+
+pub const TEST_VAULT_PASSWORD: &str = "test_vault_password";
+pub const TEST_VAULT_MNEMONIC: &str =
+    "green process gate doctor slide whip priority shrug diamond crumble average help";
+// 2024-01-01, history timestamps go back one hour per record
+const HISTORY_START_MS: u64 = 1_704_067_200_000;
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+// contract, name, symbol, decimals, balance of the first account
+const SAMPLE_TOKENS: [(&str, &str, &str, u8, u128); 3] = [
+    (
+        "00000000000000000000000000000000000000a1",
+        "Test Governance",
+        "tGOV",
+        15,
+        2_500_000_000_000_000,
+    ),
+    (
+        "00000000000000000000000000000000000000a2",
+        "Test Eighteen",
+        "tE18",
+        18,
+        42_000_000_000_000_000_000,
+    ),
+    (
+        "00000000000000000000000000000000000000a3",
+        "Test Dollar",
+        "tUSD",
+        6,
+        100_000_000,
+    ),
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct TestVaultConfig {
+    pub accounts: usize,
+    pub history: usize, // records per wallet
+}
+
+impl Default for TestVaultConfig {
+    fn default() -> Self {
+        Self {
+            accounts: 3,
+            history: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleToken {
+    pub contract: Address,
+    pub meta: TokenMetadata,
+}
+
+pub struct TestVault {
+    pub background: Background,
+    pub cipher_key: [u8; SHA256_SIZE],
+    pub tokens: Vec<SampleToken>,
+}
+
+pub fn sample_tokens() -> Vec<SampleToken> {
+    SAMPLE_TOKENS
+        .iter()
+        .map(|(contract, name, symbol, decimals, _)| SampleToken {
+            contract: Address::from_zil_base16(contract).expect("valid sample token address"),
+            meta: TokenMetadata {
+                name: name.to_string(),
+                symbol: symbol.to_string(),
+                decimals: *decimals,
+            },
+        })
+        .collect()
+}
+
+/// Builds a populated vault at `path`, unlocked with [TEST_VAULT_PASSWORD].
+pub fn build_test_vault(path: &str, config: TestVaultConfig) -> Result<TestVault, BackgroundError> {
+    let mut background = Background::from_storage_path(path)?;
+    let indexes: Vec<usize> = (0..config.accounts).collect();
+    let cipher_key = background.add_bip39_wallet(
+        TEST_VAULT_PASSWORD,
+        TEST_VAULT_MNEMONIC,
+        &indexes,
+        Bip49DerivationPath::Zilliqa,
+    )?;
+    let tokens = sample_tokens();
+    let last = background.wallets.len() - 1; // just added
+    let wallet = &mut background.wallets[last];
+
+    for i in 0..config.accounts {
+        for (token, (_, _, _, _, balance)) in tokens.iter().zip(SAMPLE_TOKENS.iter()) {
+            // every next account holds half of the previous one
+            let amount = balance >> i.min(127);
+
+            if let Ok(contract) = token.contract.get_bech32() {
+                wallet
+                    .set_token_balance(i, &contract, Uint256::from(amount))
+                    .map_err(BackgroundError::FailToSaveWallet)?;
+            }
+        }
+    }
+
+    let senders: Vec<Address> = wallet.accounts().map(|(_, a)| a.addr.clone()).collect();
+
+    if !senders.is_empty() {
+        for n in 0..config.history {
+            let record = sample_record(n, &senders[n % senders.len()], &tokens);
+
+            wallet.add_intent(record);
+        }
+    }
+
+    wallet
+        .save_to_storage()
+        .map_err(BackgroundError::FailToSaveWallet)?;
+
+    Ok(TestVault {
+        background,
+        cipher_key,
+        tokens,
+    })
+}
+
+fn sample_record(n: usize, sender: &Address, tokens: &[SampleToken]) -> HistoryRecord {
+    let nonce = n as u64 + 1;
+    let status = match n % 5 {
+        0 => TxStatus::Pending,
+        4 => TxStatus::Rejected,
+        _ => TxStatus::Confirmed,
+    };
+    // native transfers with a token transfer every third record
+    let amount = match (n % 3, tokens.get(n % tokens.len().max(1))) {
+        (2, Some(token)) => AssetAmount::new(
+            AssetId::Zrc2(token.contract.clone()),
+            TokenAmount::from_u128(10u128.pow(token.meta.decimals.into())),
+        ),
+        _ => AssetAmount::native(ZilAmount::from_raw((n as u128 + 1) * QA_PER_ZIL)),
+    };
+
+    HistoryRecord {
+        hash: (status != TxStatus::Pending).then(|| format!("{:064x}", n + 1)),
+        sender: sender.clone(),
+        nonce,
+        status,
+        amount: Some(amount),
+        block: (status == TxStatus::Confirmed).then_some(4_000_000 + nonce),
+        memo: n.is_multiple_of(4).then(|| format!("test memo {n}")),
+        origin: (n % 2 == 1).then(|| "https://dapp.example".to_string()),
+        timestamp: HISTORY_START_MS - n as u64 * HOUR_MS,
+        fiat: None,
+        gas: None,
+        payload: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_is_deterministic() {
+        let config = TestVaultConfig {
+            accounts: 2,
+            history: 6,
+        };
+        let a = build_test_vault(&format!("/tmp/{}", rand::random::<usize>()), config).unwrap();
+        let b = build_test_vault(&format!("/tmp/{}", rand::random::<usize>()), config).unwrap();
+        let (wa, wb) = (&a.background.wallets[0], &b.background.wallets[0]);
+
+        assert_eq!(wa.accounts().count(), 2);
+        assert_eq!(wa.wallet_address(), wb.wallet_address());
+        assert!(wa.accounts().eq(wb.accounts()));
+        assert_eq!(wa.history(), wb.history());
+        assert_eq!(wa.history().records().len(), 6);
+        assert_eq!(wa.account(0).unwrap().ft_map.len(), SAMPLE_TOKENS.len());
+        assert_eq!(
+            wa.reveal_mnemonic(&a.cipher_key).unwrap().to_string(),
+            TEST_VAULT_MNEMONIC
+        );
+    }
+}
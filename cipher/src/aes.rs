@@ -1,11 +1,13 @@
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit},
     Aes256Gcm, Key, Nonce,
 };
+use crypto::entropy;
 use zil_errors::cipher::AesGCMErrors;
 
 pub const AES_GCM_KEY_SIZE: usize = 32;
 pub const AES_GCM_NONCE_SIZE: usize = 12;
+pub const AES_GCM_TAG_SIZE: usize = 16;
 
 pub fn aes_gcm_encrypt(
     key: &[u8; AES_GCM_KEY_SIZE],
@@ -13,7 +15,7 @@ pub fn aes_gcm_encrypt(
 ) -> Result<Vec<u8>, AesGCMErrors> {
     let key: &Key<Aes256Gcm> = key.into();
     let cipher = Aes256Gcm::new(key);
-    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let nonce = Aes256Gcm::generate_nonce(&mut entropy::rng());
     let mut bytes = cipher
         .encrypt(&nonce, plaintext)
         .map_err(|e| AesGCMErrors::EncryptError(e.to_string()))?;
@@ -29,8 +31,11 @@ pub fn aes_gcm_decrypt(
 ) -> Result<Vec<u8>, AesGCMErrors> {
     let key: &Key<Aes256Gcm> = key.into();
     let cipher = Aes256Gcm::new(key);
-    let ciphertext = &cipher_nonce[..cipher_nonce.len() - AES_GCM_NONCE_SIZE];
-    let nonce = &cipher_nonce[cipher_nonce.len() - AES_GCM_NONCE_SIZE..];
+    let split = cipher_nonce
+        .len()
+        .checked_sub(AES_GCM_NONCE_SIZE)
+        .ok_or(AesGCMErrors::DecryptError("too short".to_string()))?;
+    let (ciphertext, nonce) = cipher_nonce.split_at(split);
     let nonce = Nonce::from_slice(nonce);
 
     cipher
@@ -41,12 +46,12 @@ pub fn aes_gcm_decrypt(
 #[cfg(test)]
 mod tests {
     use super::{aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE};
-    use rand::{RngCore, SeedableRng};
-    use rand_chacha::ChaCha20Rng;
+    use crypto::entropy;
+    use rand::RngCore;
 
     #[test]
     fn encrypt_and_decrypt() {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut plaintext = [0u8; 100];
         let mut key = [0u8; AES_GCM_KEY_SIZE];
 
@@ -58,4 +63,13 @@ mod tests {
 
         assert_eq!(plaintext_restore, plaintext);
     }
+
+    #[test]
+    fn decrypt_short_input() {
+        let key = [1u8; AES_GCM_KEY_SIZE];
+
+        for len in [0, 1, 11, 12, 27] {
+            assert!(aes_gcm_decrypt(&key, &vec![0u8; len]).is_err());
+        }
+    }
 }
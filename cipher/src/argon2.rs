@@ -1,13 +1,34 @@
-use argon2::Argon2;
-use config::argon::{KEY_SIZE, WALLET_SALT};
+use argon2::{Algorithm, Argon2, Params, Version};
+use config::{
+    argon::{KEY_SIZE, WALLET_SALT},
+    file::KdfConfig,
+};
 use zil_errors::cipher::CipherErrors;
 
 pub fn derive_key(password: &[u8]) -> Result<[u8; KEY_SIZE], CipherErrors> {
+    derive_key_with_salt(password, WALLET_SALT)
+}
+
+// `derive_key` with the Argon2 cost a wallet was created with.
+pub fn derive_key_with_params(
+    password: &[u8],
+    kdf: &KdfConfig,
+) -> Result<[u8; KEY_SIZE], CipherErrors> {
+    hash(password, WALLET_SALT, kdf)
+}
+
+pub fn derive_key_with_salt(password: &[u8], salt: &[u8]) -> Result<[u8; KEY_SIZE], CipherErrors> {
+    hash(password, salt, &KdfConfig::default())
+}
+
+fn hash(password: &[u8], salt: &[u8], kdf: &KdfConfig) -> Result<[u8; KEY_SIZE], CipherErrors> {
     let mut output_key_material = [0u8; KEY_SIZE];
-    let argon2 = Argon2::default();
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, None)
+        .map_err(|e| CipherErrors::ArgonKeyDerivingError(e.to_string()))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
 
     argon2
-        .hash_password_into(password, WALLET_SALT, &mut output_key_material)
+        .hash_password_into(password, salt, &mut output_key_material)
         .map_err(|e| CipherErrors::ArgonKeyDerivingError(e.to_string()))?;
 
     Ok(output_key_material)
@@ -15,7 +36,7 @@ pub fn derive_key(password: &[u8]) -> Result<[u8; KEY_SIZE], CipherErrors> {
 
 #[cfg(test)]
 mod tests {
-    use super::derive_key;
+    use super::*;
 
     #[test]
     fn test_derive_key() {
@@ -32,4 +53,22 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_derive_key_with_params() {
+        let cheap = KdfConfig {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+
+        assert_eq!(
+            derive_key_with_params(b"test_password", &KdfConfig::default()).unwrap(),
+            derive_key(b"test_password").unwrap()
+        );
+        assert_ne!(
+            derive_key_with_params(b"test_password", &cheap).unwrap(),
+            derive_key(b"test_password").unwrap()
+        );
+    }
 }
@@ -0,0 +1,94 @@
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit},
+    Aes256,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use md5::{Digest, Md5};
+use zil_errors::cipher::AesGCMErrors;
+
+const SALTED: &[u8] = b"Salted__";
+const SALT_SIZE: usize = 8;
+const BLOCK_SIZE: usize = 16;
+
+/// Opens `CryptoJS.AES.encrypt(text, passphrase)` output, the OpenSSL
+/// format the browser extension sealed its vault with: base64 of
+/// `Salted__`, an 8 byte salt and AES-256-CBC under EVP_BytesToKey (MD5).
+pub fn cryptojs_decrypt(passphrase: &[u8], sealed: &str) -> Result<Vec<u8>, AesGCMErrors> {
+    let bytes = STANDARD
+        .decode(sealed.trim())
+        .map_err(|e| AesGCMErrors::DecryptError(e.to_string()))?;
+
+    if !bytes.starts_with(SALTED)
+        || bytes.len() < SALTED.len() + SALT_SIZE + BLOCK_SIZE
+        || !(bytes.len() - SALTED.len() - SALT_SIZE).is_multiple_of(BLOCK_SIZE)
+    {
+        return Err(AesGCMErrors::DecryptError(
+            "not a CryptoJS cipher".to_string(),
+        ));
+    }
+
+    let (salt, ciphertext) = bytes[SALTED.len()..].split_at(SALT_SIZE);
+    let (key, mut prev) = evp_bytes_to_key(passphrase, salt);
+    let aes = Aes256::new(GenericArray::from_slice(&key));
+    let mut plain = Vec::with_capacity(ciphertext.len());
+
+    for chunk in ciphertext.chunks(BLOCK_SIZE) {
+        let mut block = GenericArray::clone_from_slice(chunk);
+
+        aes.decrypt_block(&mut block);
+        plain.extend(block.iter().zip(prev).map(|(b, p)| b ^ p));
+        prev.copy_from_slice(chunk);
+    }
+
+    // PKCS#7, a wrong passphrase almost never leaves valid padding
+    let pad = plain.last().copied().unwrap_or(0) as usize;
+
+    if pad == 0 || pad > BLOCK_SIZE || !plain.ends_with(&vec![pad as u8; pad]) {
+        return Err(AesGCMErrors::DecryptError("bad padding".to_string()));
+    }
+
+    plain.truncate(plain.len() - pad);
+
+    Ok(plain)
+}
+
+fn evp_bytes_to_key(passphrase: &[u8], salt: &[u8]) -> ([u8; 32], [u8; BLOCK_SIZE]) {
+    let mut derived = Vec::with_capacity(48);
+    let mut block: Vec<u8> = Vec::new();
+
+    while derived.len() < 48 {
+        let mut hasher = Md5::new();
+
+        hasher.update(&block);
+        hasher.update(passphrase);
+        hasher.update(salt);
+        block = hasher.finalize().to_vec();
+        derived.extend_from_slice(&block);
+    }
+
+    let mut key = [0u8; 32];
+    let mut iv = [0u8; BLOCK_SIZE];
+
+    key.copy_from_slice(&derived[..32]);
+    iv.copy_from_slice(&derived[32..48]);
+
+    (key, iv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cryptojs_decrypt() {
+        // openssl enc -aes-256-cbc -md md5 -a -salt -pass pass:extension-pass
+        const VAULT: &str = "U2FsdGVkX1/xvLJzFscbjQqtZqsl5d/wYaq4KhpV9BFnbTCjelUt3fbBzvU6ai8EhS9xWtrqBo9HnSWfWUDSj0ejh8oEoX8+yRMXK/YfbKyGl0lckppzFDD5IBffrx5+OAnaLezmzrJQauVOjEj0q+waxUChIvKMEiTH3+PLGYH4t4JXtjz7ztYivxbCMrzQwlidS9V436V8E1a0BvIq2Pj5CjMlX0ClXpHf4sGWcCe9VqXhiEutQaYS2aO5lyTIafcRTIt1Qi7LvSP/2Pr7ZBSeGHYy1TX4qoG6e9ZaG+X2LrV5dw63Hbv+uZM/iwOE";
+
+        let plain = cryptojs_decrypt(b"extension-pass", VAULT).unwrap();
+
+        assert!(plain.starts_with(br#"{"decryptSeed":"abandon abandon"#));
+        assert!(plain.ends_with(br#"a578a6930"}]}"#));
+        assert!(cryptojs_decrypt(b"wrong", VAULT).is_err());
+        assert!(cryptojs_decrypt(b"extension-pass", "U2FsdGVkX1+sealed").is_err());
+    }
+}
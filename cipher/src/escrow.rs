@@ -0,0 +1,134 @@
+use crate::aes::{aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE};
+use crypto::entropy;
+use k256::{ecdh::diffie_hellman, PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+use zil_errors::escrow::EscrowErrors;
+
+pub const ESCROW_VERSION: u8 = 1;
+pub const ESCROW_PUB_KEY_SIZE: usize = 33; // compressed secp256k1
+pub const ESCROW_SECRET_KEY_SIZE: usize = 32;
+
+const KDF_DOMAIN: &[u8] = b"zilpay-escrow-v1";
+
+/// Wraps vault key material for an organization's secp256k1 key (ECIES:
+/// ephemeral ECDH + SHA-256 KDF + AES-256-GCM).
+///
+/// Layout: `version (1) | ephemeral pub key (33) | AES-GCM ciphertext`.
+///
+/// The core only ever sees the organization public key. Splitting the
+/// matching secret key between admins, so that a recovery needs a quorum,
+/// is done on the organization side and is out of scope here.
+pub fn escrow_wrap(
+    org_pub_key: &[u8; ESCROW_PUB_KEY_SIZE],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, EscrowErrors> {
+    let org_pub_key =
+        PublicKey::from_sec1_bytes(org_pub_key).or(Err(EscrowErrors::InvalidPublicKey))?;
+    let ephemeral = SecretKey::random(&mut entropy::rng());
+    let ephemeral_pub: [u8; ESCROW_PUB_KEY_SIZE] = ephemeral
+        .public_key()
+        .to_sec1_bytes()
+        .to_vec()
+        .try_into()
+        .or(Err(EscrowErrors::InvalidPublicKey))?;
+    let key = derive_key(&ephemeral, &org_pub_key, &ephemeral_pub);
+    let ciphertext = aes_gcm_encrypt(&key, plaintext).map_err(EscrowErrors::EncryptError)?;
+    let mut blob = Vec::with_capacity(1 + ESCROW_PUB_KEY_SIZE + ciphertext.len());
+
+    blob.push(ESCROW_VERSION);
+    blob.extend_from_slice(&ephemeral_pub);
+    blob.extend(ciphertext);
+
+    Ok(blob)
+}
+
+pub fn escrow_unwrap(
+    org_secret_key: &[u8; ESCROW_SECRET_KEY_SIZE],
+    blob: &[u8],
+) -> Result<Vec<u8>, EscrowErrors> {
+    let (version, rest) = blob.split_first().ok_or(EscrowErrors::InvalidBlob)?;
+
+    if *version != ESCROW_VERSION {
+        return Err(EscrowErrors::UnsupportedVersion(*version));
+    }
+
+    if rest.len() <= ESCROW_PUB_KEY_SIZE {
+        return Err(EscrowErrors::InvalidBlob);
+    }
+
+    let (ephemeral_pub, ciphertext) = rest.split_at(ESCROW_PUB_KEY_SIZE);
+    let org_secret_key =
+        SecretKey::from_slice(org_secret_key).or(Err(EscrowErrors::InvalidSecretKey))?;
+    let ephemeral = PublicKey::from_sec1_bytes(ephemeral_pub).or(Err(EscrowErrors::InvalidBlob))?;
+    let key = derive_key(&org_secret_key, &ephemeral, ephemeral_pub);
+
+    aes_gcm_decrypt(&key, ciphertext).map_err(EscrowErrors::DecryptError)
+}
+
+fn derive_key(
+    secret: &SecretKey,
+    public: &PublicKey,
+    ephemeral_pub: &[u8],
+) -> [u8; AES_GCM_KEY_SIZE] {
+    let shared = diffie_hellman(secret.to_nonzero_scalar(), public.as_affine());
+    let mut hasher = Sha256::new();
+
+    hasher.update(KDF_DOMAIN);
+    hasher.update(shared.raw_secret_bytes());
+    hasher.update(ephemeral_pub);
+
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn org_keys() -> ([u8; ESCROW_SECRET_KEY_SIZE], [u8; ESCROW_PUB_KEY_SIZE]) {
+        let sk = SecretKey::random(&mut entropy::rng());
+        let pk = sk.public_key().to_sec1_bytes().to_vec().try_into().unwrap();
+
+        (sk.to_bytes().into(), pk)
+    }
+
+    #[test]
+    fn test_wrap_unwrap() {
+        let (sk, pk) = org_keys();
+        let vault_key = b"vault key material".to_vec();
+        let blob = escrow_wrap(&pk, &vault_key).unwrap();
+
+        assert_eq!(blob[0], ESCROW_VERSION);
+        assert_eq!(escrow_unwrap(&sk, &blob).unwrap(), vault_key);
+
+        let (other_sk, _) = org_keys();
+
+        assert!(matches!(
+            escrow_unwrap(&other_sk, &blob),
+            Err(EscrowErrors::DecryptError(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_blob() {
+        let (sk, pk) = org_keys();
+        let mut blob = escrow_wrap(&pk, b"key").unwrap();
+
+        assert_eq!(escrow_unwrap(&sk, &[]), Err(EscrowErrors::InvalidBlob));
+        assert_eq!(
+            escrow_unwrap(&sk, &blob[..10]),
+            Err(EscrowErrors::InvalidBlob)
+        );
+        // shorter than a nonce after the key
+        assert!(escrow_unwrap(&sk, &blob[..1 + ESCROW_PUB_KEY_SIZE + 1]).is_err());
+
+        blob[0] = 9;
+        assert_eq!(
+            escrow_unwrap(&sk, &blob),
+            Err(EscrowErrors::UnsupportedVersion(9))
+        );
+        assert_eq!(
+            escrow_wrap(&[0u8; ESCROW_PUB_KEY_SIZE], b"key"),
+            Err(EscrowErrors::InvalidPublicKey)
+        );
+    }
+}
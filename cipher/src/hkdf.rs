@@ -0,0 +1,32 @@
+use crate::aes::AES_GCM_KEY_SIZE;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zil_errors::cipher::CipherErrors;
+
+// HKDF-SHA256 subkey of `master` bound to `info`. Knowing one subkey tells
+// nothing about `master` or the subkeys of other domains.
+pub fn derive_subkey(master: &[u8], info: &[u8]) -> Result<[u8; AES_GCM_KEY_SIZE], CipherErrors> {
+    let mut okm = [0u8; AES_GCM_KEY_SIZE];
+
+    Hkdf::<Sha256>::new(None, master)
+        .expand(info, &mut okm)
+        .map_err(|e| CipherErrors::HkdfExpandError(e.to_string()))?;
+
+    Ok(okm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::derive_subkey;
+
+    #[test]
+    fn test_derive_subkey() {
+        let master = [1u8; 32];
+        let vault = derive_subkey(&master, b"vault").unwrap();
+
+        assert_eq!(vault, derive_subkey(&master, b"vault").unwrap());
+        assert_ne!(vault, derive_subkey(&master, b"sessions").unwrap());
+        assert_ne!(vault, derive_subkey(&[2u8; 32], b"vault").unwrap());
+        assert_ne!(vault, master);
+    }
+}
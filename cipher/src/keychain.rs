@@ -149,13 +149,13 @@ mod tests {
 
     use super::{CipherOrders, KeyChain};
     use config::cipher::PROOF_SIZE;
-    use rand::{RngCore, SeedableRng};
-    use rand_chacha::ChaCha20Rng;
+    use crypto::entropy;
+    use rand::RngCore;
     use zil_errors::{cipher::AesGCMErrors, keychain::KeyChainErrors};
 
     #[test]
     fn test_init_keychain() {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut password = [0u8; 32];
 
         rng.fill_bytes(&mut password);
@@ -167,7 +167,7 @@ mod tests {
 
     #[test]
     fn test_bytes() {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut password = [0u8; 32];
         let mut plaintext = [0u8; 1024];
 
@@ -191,7 +191,7 @@ mod tests {
 
     #[test]
     fn test_encrypt_and_decrypt() {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut password = [0u8; 32];
         let mut plaintext = [0u8; 1024];
 
@@ -220,7 +220,7 @@ mod tests {
 
     #[test]
     fn test_make_verify_proof() {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut password = [0u8; 32];
 
         rng.fill_bytes(&mut password);
@@ -1,5 +1,9 @@
 pub mod aes;
 pub mod argon2;
+pub mod cryptojs;
+pub mod escrow;
+pub mod hkdf;
 pub mod keychain;
 pub mod ntrup;
 pub mod options;
+pub mod timelock;
@@ -1,15 +1,82 @@
-use config::sha::{SHA256_SIZE, SHA512_SIZE};
+use config::{
+    cipher::{NTRU_MAX_PLAINTEXT_SIZE, NTRU_SINGLE_SHOT_MAX},
+    sha::{SHA256_SIZE, SHA512_SIZE},
+    SYS_SIZE,
+};
+use crypto::entropy;
 use ntrulp::{
+    compress::r3::{
+        pack_bytes, r3_decode_chunks, r3_encode_chunks, r3_merge_w_chunks, r3_split_w_chunks,
+        unpack_bytes,
+    },
     key::{priv_key::PrivKey, pub_key::PubKey},
-    ntru,
+    ntru::{
+        cipher::{r3_encrypt, rq_decrypt},
+        std_error::CipherError,
+    },
+    params::params::{P, RQ_BYTES},
     poly::{r3::R3, rq::Rq},
     rng::{random_small, short_random},
 };
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
 use zil_errors::ntru::NTRULPCipherErrors;
 
+// 0x7FFF is out of range for an Rq coefficient, so a single-shot ciphertext
+// can never start with it.
+const CHUNKED_MAGIC: [u8; 4] = [0x7F, 0xFF, b'N', b'C'];
+const LEN_SIZE: usize = std::mem::size_of::<u32>();
+// size vector length and seed ntrulp appends to every ciphertext
+const SINGLE_SHOT_TRAILER: usize = SYS_SIZE + std::mem::size_of::<u64>();
+
+/// Threads one NTRU encryption or decryption may keep busy. Unlocking a
+/// wallet on every core makes phones stutter and heat up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkBudget {
+    #[default]
+    AllCores,
+    Threads(usize),
+    LowPower, // one thread, slower but leaves the UI alone
+}
+
+// 0 for all cores, the thread count otherwise.
+static WORK_BUDGET: AtomicUsize = AtomicUsize::new(0);
+
+/// Process wide, like the entropy source, every keychain shares it.
+pub fn set_work_budget(budget: WorkBudget) {
+    let threads = match budget {
+        WorkBudget::AllCores => 0,
+        WorkBudget::Threads(threads) => threads.max(1),
+        WorkBudget::LowPower => 1,
+    };
+
+    WORK_BUDGET.store(threads, Ordering::Relaxed);
+}
+
+pub fn work_budget() -> WorkBudget {
+    match WORK_BUDGET.load(Ordering::Relaxed) {
+        0 => WorkBudget::AllCores,
+        1 => WorkBudget::LowPower,
+        threads => WorkBudget::Threads(threads),
+    }
+}
+
+// Never more threads than cores or chunks.
+fn thread_count(chunks: usize) -> usize {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let threads = match WORK_BUDGET.load(Ordering::Relaxed) {
+        0 => cores,
+        threads => threads.min(cores),
+    };
+
+    threads.min(chunks).max(1)
+}
+
 pub fn ntru_keys_from_seed(
     seed_bytes: &[u8; SHA512_SIZE],
 ) -> Result<(PubKey, PrivKey), NTRULPCipherErrors> {
@@ -34,28 +101,178 @@ pub fn ntru_keys_from_seed(
     Ok((pk, sk))
 }
 
+/// Plaintexts up to [NTRU_SINGLE_SHOT_MAX] go through ntrulp in one call,
+/// bigger ones (and empty ones, which ntrulp can't handle) are chunked.
+/// Anything over [NTRU_MAX_PLAINTEXT_SIZE] is refused.
 pub fn ntru_encrypt(pk: PubKey, plaintext: &[u8]) -> Result<Vec<u8>, NTRULPCipherErrors> {
-    let mut pq_rng = ChaChaRng::from_entropy();
+    if plaintext.len() > NTRU_MAX_PLAINTEXT_SIZE {
+        return Err(NTRULPCipherErrors::PlaintextTooLarge(
+            plaintext.len(),
+            NTRU_MAX_PLAINTEXT_SIZE,
+        ));
+    }
 
-    ntru::std_cipher::bytes_encrypt(&mut pq_rng, plaintext, pk)
-        .map_err(NTRULPCipherErrors::EncryptError)
+    if plaintext.is_empty() || plaintext.len() > NTRU_SINGLE_SHOT_MAX {
+        return ntru_encrypt_chunked(&pk, plaintext);
+    }
+
+    single_shot_encrypt(&pk, plaintext)
 }
 
 pub fn ntru_decrypt(sk: PrivKey, ciphertext: Vec<u8>) -> Result<Vec<u8>, NTRULPCipherErrors> {
-    let ciphertext = Arc::new(ciphertext);
+    if ciphertext.starts_with(&CHUNKED_MAGIC) {
+        return ntru_decrypt_chunked(&sk, &ciphertext[CHUNKED_MAGIC.len()..]);
+    }
+
+    single_shot_decrypt(&sk, &ciphertext)
+}
+
+// magic | chunks count | (len | single-shot ciphertext)*, lengths u32 LE
+fn ntru_encrypt_chunked(pk: &PubKey, plaintext: &[u8]) -> Result<Vec<u8>, NTRULPCipherErrors> {
+    let chunks: Vec<&[u8]> = plaintext.chunks(NTRU_SINGLE_SHOT_MAX).collect();
+    let mut out = CHUNKED_MAGIC.to_vec();
+
+    out.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+
+    for chunk in chunks {
+        let ciphertext = single_shot_encrypt(pk, chunk)?;
+
+        out.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+        out.extend_from_slice(&ciphertext);
+    }
+
+    Ok(out)
+}
+
+fn ntru_decrypt_chunked(sk: &PrivKey, mut bytes: &[u8]) -> Result<Vec<u8>, NTRULPCipherErrors> {
+    let count = take_len(&mut bytes)?;
+    let mut plaintext = Vec::new();
+
+    for _ in 0..count {
+        let len = take_len(&mut bytes)?;
+
+        if bytes.len() < len {
+            return Err(NTRULPCipherErrors::InvalidCiphertext);
+        }
+
+        let (chunk, rest) = bytes.split_at(len);
+
+        plaintext.extend(single_shot_decrypt(sk, chunk)?);
+        bytes = rest;
+    }
+
+    if !bytes.is_empty() {
+        return Err(NTRULPCipherErrors::InvalidCiphertext);
+    }
+
+    Ok(plaintext)
+}
+
+fn take_len(bytes: &mut &[u8]) -> Result<usize, NTRULPCipherErrors> {
+    if bytes.len() < LEN_SIZE {
+        return Err(NTRULPCipherErrors::InvalidCiphertext);
+    }
 
-    ntru::std_cipher::bytes_decrypt(&ciphertext, sk).map_err(NTRULPCipherErrors::DecryptError)
+    let (len, rest) = bytes.split_at(LEN_SIZE);
+    let len = u32::from_le_bytes(
+        len.try_into()
+            .or(Err(NTRULPCipherErrors::InvalidCiphertext))?,
+    );
+
+    *bytes = rest;
+
+    Ok(len as usize)
+}
+
+// Same output as ntrulp's `bytes_encrypt`, on `thread_count` threads.
+fn single_shot_encrypt(pk: &PubKey, plaintext: &[u8]) -> Result<Vec<u8>, NTRULPCipherErrors> {
+    let mut pq_rng = entropy::rng();
+    let (chunks, size, seed) = r3_split_w_chunks(&r3_decode_chunks(plaintext), &mut pq_rng);
+    let per_thread = chunks.len().div_ceil(thread_count(chunks.len())).max(1);
+    let parts = thread::scope(|s| {
+        let handles: Vec<_> = chunks
+            .chunks(per_thread)
+            .map(|part| {
+                s.spawn(move || {
+                    part.iter()
+                        .flat_map(|chunk| r3_encrypt(&R3::from(*chunk), pk).to_bytes())
+                        .collect::<Vec<u8>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join())
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .or(Err(NTRULPCipherErrors::EncryptError(
+        CipherError::SyncThreadJoinError,
+    )))?;
+
+    Ok(pack_bytes(parts.concat(), size, seed))
+}
+
+// ntrulp slices the trailer without checking the length.
+fn single_shot_decrypt(sk: &PrivKey, ciphertext: &[u8]) -> Result<Vec<u8>, NTRULPCipherErrors> {
+    if ciphertext.len() <= SINGLE_SHOT_TRAILER {
+        return Err(NTRULPCipherErrors::InvalidCiphertext);
+    }
+
+    let (bytes, size, seed) = unpack_bytes(ciphertext)
+        .map_err(|e| NTRULPCipherErrors::DecryptError(CipherError::CompressError(e)))?;
+    let chunks = bytes.len() / RQ_BYTES;
+    let per_thread = chunks.div_ceil(thread_count(chunks)).max(1);
+    let parts = thread::scope(|s| {
+        let handles: Vec<_> = bytes
+            .chunks(per_thread * RQ_BYTES)
+            .map(|part| {
+                s.spawn(move || {
+                    part.chunks(RQ_BYTES)
+                        .map(|chunk| {
+                            let chunk: [u8; RQ_BYTES] =
+                                chunk.try_into().or(Err(CipherError::InvalidRqChunkSize))?;
+                            let rq: Rq = chunk.into();
+
+                            Ok(rq_decrypt(&rq, sk).coeffs)
+                        })
+                        .collect::<Result<Vec<[i8; P]>, CipherError>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or(Err(CipherError::SyncThreadJoinError))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .map_err(NTRULPCipherErrors::DecryptError)?;
+
+    Ok(r3_encode_chunks(&r3_merge_w_chunks(
+        &parts.concat(),
+        &size,
+        seed,
+    )))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ntru_keys_from_seed, SHA512_SIZE};
-    use crate::ntrup::{ntru_decrypt, ntru_encrypt};
+    use super::{
+        ntru_keys_from_seed, set_work_budget, thread_count, work_budget, WorkBudget, SHA512_SIZE,
+    };
+    use crate::ntrup::{ntru_decrypt, ntru_encrypt, CHUNKED_MAGIC};
+    use config::cipher::{NTRU_MAX_PLAINTEXT_SIZE, NTRU_SINGLE_SHOT_MAX};
+    use crypto::entropy;
     use rand::RngCore;
+    use zil_errors::ntru::NTRULPCipherErrors;
 
     #[test]
     fn test_encrypt_and_decrypt() {
-        let mut rng = rand::thread_rng();
+        let mut rng = entropy::rng();
         let mut password = [0u8; 2000];
         let mut plaintext = vec![0u8; 255];
         let mut seed = [0u8; SHA512_SIZE];
@@ -70,4 +287,67 @@ mod tests {
 
         assert_eq!(res, plaintext);
     }
+
+    #[test]
+    fn test_work_budget() {
+        let mut rng = entropy::rng();
+        let mut seed = [0u8; SHA512_SIZE];
+        let mut plaintext = vec![0u8; 3000];
+
+        rng.fill_bytes(&mut seed);
+        rng.fill_bytes(&mut plaintext);
+
+        let (pk, sk) = ntru_keys_from_seed(&seed).unwrap();
+
+        set_work_budget(WorkBudget::LowPower);
+
+        assert_eq!(work_budget(), WorkBudget::LowPower);
+        assert_eq!(thread_count(16), 1);
+
+        // the wire format is ntrulp's, whatever the budget
+        let ciphertext = ntru_encrypt(pk.clone(), &plaintext).unwrap();
+
+        assert_eq!(
+            ntrulp::ntru::std_cipher::bytes_decrypt(&ciphertext, sk.clone()).unwrap(),
+            plaintext
+        );
+
+        set_work_budget(WorkBudget::AllCores);
+
+        let ciphertext = ntrulp::ntru::std_cipher::bytes_encrypt(&mut rng, &plaintext, pk).unwrap();
+
+        assert_eq!(ntru_decrypt(sk, ciphertext).unwrap(), plaintext);
+        assert_eq!(thread_count(0), 1);
+    }
+
+    #[test]
+    fn test_chunked_and_limits() {
+        let mut rng = entropy::rng();
+        let mut seed = [0u8; SHA512_SIZE];
+        let mut plaintext = vec![0u8; NTRU_SINGLE_SHOT_MAX + 100];
+
+        rng.fill_bytes(&mut seed);
+        rng.fill_bytes(&mut plaintext);
+
+        let (pk, sk) = ntru_keys_from_seed(&seed).unwrap();
+        let ciphertext = ntru_encrypt(pk.clone(), &plaintext).unwrap();
+
+        assert!(ciphertext.starts_with(&CHUNKED_MAGIC));
+        assert_eq!(ntru_decrypt(sk.clone(), ciphertext).unwrap(), plaintext);
+
+        let empty = ntru_encrypt(pk.clone(), &[]).unwrap();
+
+        assert_eq!(ntru_decrypt(sk.clone(), empty).unwrap(), Vec::<u8>::new());
+        assert_eq!(
+            ntru_encrypt(pk, &vec![0u8; NTRU_MAX_PLAINTEXT_SIZE + 1]),
+            Err(NTRULPCipherErrors::PlaintextTooLarge(
+                NTRU_MAX_PLAINTEXT_SIZE + 1,
+                NTRU_MAX_PLAINTEXT_SIZE
+            ))
+        );
+        assert_eq!(
+            ntru_decrypt(sk, vec![1, 2, 3]),
+            Err(NTRULPCipherErrors::InvalidCiphertext)
+        );
+    }
 }
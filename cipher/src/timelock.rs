@@ -0,0 +1,239 @@
+use crate::{
+    aes::{
+        aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE, AES_GCM_NONCE_SIZE, AES_GCM_TAG_SIZE,
+    },
+    escrow::{escrow_unwrap, escrow_wrap, ESCROW_PUB_KEY_SIZE, ESCROW_SECRET_KEY_SIZE},
+};
+use crypto::entropy;
+use rand::RngCore;
+use std::mem::size_of;
+use zil_errors::timelock::TimeLockErrors;
+
+pub const TIMELOCK_VERSION: u8 = 2;
+
+const SEALED_SHARE_SIZE: usize = AES_GCM_KEY_SIZE + AES_GCM_TAG_SIZE + AES_GCM_NONCE_SIZE;
+const HEADER_SIZE: usize = 1 + size_of::<u64>() + SEALED_SHARE_SIZE + size_of::<u32>();
+
+struct Export<'a> {
+    unlock_at: u64,
+    sealed_share: &'a [u8],
+    notary_blob: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+/// Inheritance export with a split key: the vault is encrypted with
+/// `user_share ^ notary_share`. The user share is sealed with `heir_key`,
+/// the notary share is wrapped (see [escrow_wrap]) for a notary together
+/// with the unlock date, and the notary only releases it once that date
+/// has passed. Neither side can open the vault alone.
+///
+/// Layout: `version (1) | unlock_at (8) | sealed user share (60) |
+/// notary blob len (4) | notary blob | AES-GCM ciphertext`.
+pub fn timelock_seal(
+    notary_pub_key: &[u8; ESCROW_PUB_KEY_SIZE],
+    heir_key: &[u8; AES_GCM_KEY_SIZE],
+    unlock_at: u64,
+    now: u64,
+    secret: &[u8],
+) -> Result<Vec<u8>, TimeLockErrors> {
+    if unlock_at <= now {
+        return Err(TimeLockErrors::UnlockInPast);
+    }
+
+    let mut rng = entropy::rng();
+    let mut user_share = [0u8; AES_GCM_KEY_SIZE];
+    let mut notary_share = [0u8; AES_GCM_KEY_SIZE];
+
+    rng.fill_bytes(&mut user_share);
+    rng.fill_bytes(&mut notary_share);
+
+    let notary_payload = [unlock_at.to_le_bytes().as_slice(), &notary_share].concat();
+    let notary_blob = escrow_wrap(notary_pub_key, &notary_payload)?;
+    let key = combine(&user_share, &notary_share);
+    let ciphertext = aes_gcm_encrypt(&key, secret).map_err(TimeLockErrors::EncryptError)?;
+    let sealed_share =
+        aes_gcm_encrypt(heir_key, &user_share).map_err(TimeLockErrors::EncryptError)?;
+    let blob_len = u32::try_from(notary_blob.len()).or(Err(TimeLockErrors::InvalidExport))?;
+    let mut export = Vec::with_capacity(HEADER_SIZE + notary_blob.len() + ciphertext.len());
+
+    export.push(TIMELOCK_VERSION);
+    export.extend_from_slice(&unlock_at.to_le_bytes());
+    export.extend(sealed_share);
+    export.extend_from_slice(&blob_len.to_le_bytes());
+    export.extend(notary_blob);
+    export.extend(ciphertext);
+
+    Ok(export)
+}
+
+pub fn timelock_unlock_at(export: &[u8]) -> Result<u64, TimeLockErrors> {
+    Ok(parse(export)?.unlock_at)
+}
+
+// The part of the export handed to the notary, nothing else leaves the heir.
+pub fn timelock_notary_blob(export: &[u8]) -> Result<&[u8], TimeLockErrors> {
+    Ok(parse(export)?.notary_blob)
+}
+
+/// Notary side, takes only its blob (see [timelock_notary_blob]). The date
+/// is taken from the wrapped payload, so editing the export header doesn't
+/// release the share earlier.
+pub fn notary_release(
+    notary_secret_key: &[u8; ESCROW_SECRET_KEY_SIZE],
+    notary_blob: &[u8],
+    now: u64,
+) -> Result<[u8; AES_GCM_KEY_SIZE], TimeLockErrors> {
+    let payload = escrow_unwrap(notary_secret_key, notary_blob)?;
+
+    if payload.len() != size_of::<u64>() + AES_GCM_KEY_SIZE {
+        return Err(TimeLockErrors::InvalidExport);
+    }
+
+    let (unlock_at, notary_share) = payload.split_at(size_of::<u64>());
+    let unlock_at = u64::from_le_bytes(
+        unlock_at
+            .try_into()
+            .or(Err(TimeLockErrors::InvalidExport))?,
+    );
+
+    if now < unlock_at {
+        return Err(TimeLockErrors::Locked(unlock_at));
+    }
+
+    notary_share
+        .try_into()
+        .or(Err(TimeLockErrors::InvalidExport))
+}
+
+pub fn timelock_open(
+    export: &[u8],
+    heir_key: &[u8; AES_GCM_KEY_SIZE],
+    notary_share: &[u8; AES_GCM_KEY_SIZE],
+) -> Result<Vec<u8>, TimeLockErrors> {
+    let export = parse(export)?;
+    let user_share: [u8; AES_GCM_KEY_SIZE] = aes_gcm_decrypt(heir_key, export.sealed_share)
+        .map_err(TimeLockErrors::DecryptError)?
+        .try_into()
+        .or(Err(TimeLockErrors::InvalidExport))?;
+    let key = combine(&user_share, notary_share);
+
+    aes_gcm_decrypt(&key, export.ciphertext).map_err(TimeLockErrors::DecryptError)
+}
+
+fn parse(export: &[u8]) -> Result<Export<'_>, TimeLockErrors> {
+    let (version, _) = export.split_first().ok_or(TimeLockErrors::InvalidExport)?;
+
+    if *version != TIMELOCK_VERSION {
+        return Err(TimeLockErrors::UnsupportedVersion(*version));
+    }
+
+    if export.len() < HEADER_SIZE {
+        return Err(TimeLockErrors::InvalidExport);
+    }
+
+    let (unlock_at, rest) = export[1..].split_at(size_of::<u64>());
+    let (sealed_share, rest) = rest.split_at(SEALED_SHARE_SIZE);
+    let (blob_len, rest) = rest.split_at(size_of::<u32>());
+    let blob_len =
+        u32::from_le_bytes(blob_len.try_into().or(Err(TimeLockErrors::InvalidExport))?) as usize;
+
+    if rest.len() <= blob_len {
+        return Err(TimeLockErrors::InvalidExport);
+    }
+
+    let (notary_blob, ciphertext) = rest.split_at(blob_len);
+
+    Ok(Export {
+        unlock_at: u64::from_le_bytes(
+            unlock_at
+                .try_into()
+                .or(Err(TimeLockErrors::InvalidExport))?,
+        ),
+        sealed_share,
+        notary_blob,
+        ciphertext,
+    })
+}
+
+fn combine(a: &[u8; AES_GCM_KEY_SIZE], b: &[u8; AES_GCM_KEY_SIZE]) -> [u8; AES_GCM_KEY_SIZE] {
+    let mut key = [0u8; AES_GCM_KEY_SIZE];
+
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = a[i] ^ b[i];
+    }
+
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::SecretKey;
+
+    const UNLOCK_AT: u64 = 1_900_000_000_000;
+    const HEIR_KEY: [u8; AES_GCM_KEY_SIZE] = [7u8; AES_GCM_KEY_SIZE];
+
+    fn notary_keys() -> ([u8; ESCROW_SECRET_KEY_SIZE], [u8; ESCROW_PUB_KEY_SIZE]) {
+        let sk = SecretKey::random(&mut entropy::rng());
+        let pk = sk.public_key().to_sec1_bytes().to_vec().try_into().unwrap();
+
+        (sk.to_bytes().into(), pk)
+    }
+
+    #[test]
+    fn test_seal_release_open() {
+        let (sk, pk) = notary_keys();
+        let secret = b"green process gate doctor slide whip".to_vec();
+        let export = timelock_seal(&pk, &HEIR_KEY, UNLOCK_AT, 0, &secret).unwrap();
+        let blob = timelock_notary_blob(&export).unwrap();
+
+        assert_eq!(timelock_unlock_at(&export), Ok(UNLOCK_AT));
+        assert_eq!(
+            notary_release(&sk, blob, UNLOCK_AT - 1),
+            Err(TimeLockErrors::Locked(UNLOCK_AT))
+        );
+
+        let share = notary_release(&sk, blob, UNLOCK_AT).unwrap();
+
+        assert_eq!(timelock_open(&export, &HEIR_KEY, &share).unwrap(), secret);
+        // the notary share alone, or with a wrong heir key, opens nothing
+        assert!(timelock_open(&export, &[0u8; AES_GCM_KEY_SIZE], &share).is_err());
+        assert!(timelock_open(&export, &HEIR_KEY, &[0u8; AES_GCM_KEY_SIZE]).is_err());
+    }
+
+    #[test]
+    fn test_header_tampering() {
+        let (sk, pk) = notary_keys();
+        let mut export = timelock_seal(&pk, &HEIR_KEY, UNLOCK_AT, 0, b"secret").unwrap();
+
+        export[1..9].copy_from_slice(&0u64.to_le_bytes());
+
+        assert_eq!(timelock_unlock_at(&export), Ok(0));
+        assert_eq!(
+            notary_release(&sk, timelock_notary_blob(&export).unwrap(), 1),
+            Err(TimeLockErrors::Locked(UNLOCK_AT))
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let (_, pk) = notary_keys();
+
+        assert_eq!(
+            timelock_seal(&pk, &HEIR_KEY, 10, 10, b"secret"),
+            Err(TimeLockErrors::UnlockInPast)
+        );
+        assert_eq!(
+            timelock_unlock_at(&[TIMELOCK_VERSION, 0, 0]),
+            Err(TimeLockErrors::InvalidExport)
+        );
+        assert_eq!(
+            timelock_unlock_at(&[3u8; HEADER_SIZE]),
+            Err(TimeLockErrors::UnsupportedVersion(3))
+        );
+        assert_eq!(
+            timelock_open(&[TIMELOCK_VERSION], &HEIR_KEY, &HEIR_KEY),
+            Err(TimeLockErrors::InvalidExport)
+        );
+    }
+}
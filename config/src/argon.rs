@@ -1,2 +1,9 @@
 pub const KEY_SIZE: usize = 64;
 pub const WALLET_SALT: &[u8] = b"ZILPAY:54040c2f-1ec1-4eb1-9595-6e4294d14fd6";
+// Argon2id defaults (OWASP), used unless a config file overrides them.
+pub const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+pub const ARGON2_ITERATIONS: u32 = 2;
+pub const ARGON2_PARALLELISM: u32 = 1;
+// Argon2 limits: lanes are capped at 2^24-1, each needs at least 8 KiB.
+pub const ARGON2_MAX_LANES: u32 = (1 << 24) - 1;
+pub const ARGON2_MIN_KIB_PER_LANE: u32 = 8;
@@ -0,0 +1,26 @@
+pub const BROADCAST_BASE_DELAY_MS: u64 = 2_000;
+pub const BROADCAST_MAX_DELAY_MS: u64 = 5 * 60 * 1000;
+// Signed payload is dropped from the queue once it is this old.
+pub const BROADCAST_TX_TTL_MS: u64 = 60 * 60 * 1000;
+// Gas limits used when a flow step doesn't bring its own estimate.
+pub const TRANSFER_GAS_LIMIT: u64 = 50;
+pub const CONTRACT_CALL_GAS_LIMIT: u64 = 10_000;
+// Same key on every attempt of one transaction, for gateways that dedupe.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+// Gas history: calls needed before a contract has a typical cost, and how
+// far above the most ever used a new limit may go before it is flagged.
+pub const GAS_STATS_MIN_CALLS: usize = 3;
+pub const GAS_LIMIT_EXCESS_FACTOR: u64 = 5;
+// dApp transactions are polled for their receipt after acceptance, the
+// result is pushed back to the dApp session once it is mined.
+pub const BROADCAST_RECEIPT_POLL_MS: u64 = 10_000;
+pub const BROADCAST_RECEIPT_TTL_MS: u64 = 30 * 60 * 1000;
+// Minimum gas price per chain in raw (10^-12) ZIL, used until the node was
+// asked with GetMinimumGasPrice. Prices this many times the minimum are
+// flagged, and refused as a likely typo.
+pub const GAS_PRICE_FLOORS: &[(u16, u128)] = &[(1, 2_000_000_000), (333, 2_000_000_000)];
+pub const DEFAULT_MIN_GAS_PRICE: u128 = 2_000_000_000;
+pub const GAS_PRICE_WARN_FACTOR: u128 = 10;
+pub const GAS_PRICE_MAX_FACTOR: u128 = 1000;
+// A node reported minimum is capped at this many times the floor.
+pub const GAS_PRICE_REFRESH_MAX_FACTOR: u128 = 100;
@@ -1 +1,6 @@
 pub const PROOF_SIZE: usize = 8;
+// NTRU plaintexts up to this size are encrypted in one ntrulp call, larger
+// ones are split into chunks of this size.
+pub const NTRU_SINGLE_SHOT_MAX: usize = 4 * 1024;
+// Hard limit for one ntru_encrypt call, chunked mode included.
+pub const NTRU_MAX_PLAINTEXT_SIZE: usize = 16 * 1024 * 1024;
@@ -1 +1,8 @@
 pub const STAKEING: &str = "a7C67D49C82c7dc1B73D231640B2e4d0661D37c1";
+pub const IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
+pub const ARWEAVE_GATEWAY: &str = "https://arweave.net/";
+// ERC-4337 EntryPoint v0.6, same address on every chain.
+pub const ENTRY_POINT_V06: &str = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789";
+pub const SOURCIFY_URL: &str = "https://sourcify.dev/server";
+// Unverified contracts are checked again after this long, verified ones never.
+pub const VERIFICATION_RECHECK_MS: u64 = 24 * 60 * 60 * 1000;
@@ -0,0 +1,271 @@
+use crate::{
+    argon::{
+        ARGON2_ITERATIONS, ARGON2_MAX_LANES, ARGON2_MEMORY_KIB, ARGON2_MIN_KIB_PER_LANE,
+        ARGON2_PARALLELISM,
+    },
+    MAIN_CHAIN_ID, MAIN_URL, MAIN_WS_URL,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+use zil_errors::config::ConfigErrors;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub name: String,
+    pub chain_id: u16,
+    pub rpc_nodes: Vec<String>,
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    #[serde(default)]
+    pub evm_rpc: Option<String>,
+    #[serde(default)]
+    pub indexer_url: Option<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            name: "mainnet".to_string(),
+            chain_id: MAIN_CHAIN_ID,
+            rpc_nodes: vec![MAIN_URL.to_string()],
+            ws_url: Some(MAIN_WS_URL.to_string()),
+            evm_rpc: None,
+            indexer_url: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub path: Option<String>,  // platform data directory when unset
+    pub blake3: bool,          // hashsums of new records, SHA-256 when off
+    pub audit: Option<String>, // actor tag of the audit log, off when unset
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KdfConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: ARGON2_MEMORY_KIB,
+            iterations: ARGON2_ITERATIONS,
+            parallelism: ARGON2_PARALLELISM,
+        }
+    }
+}
+
+/// Settings an embedder (desktop daemon, tests) provides at runtime. Every
+/// section is optional, missing ones fall back to the compiled constants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub networks: Vec<NetworkConfig>,
+    pub storage: StorageConfig,
+    pub kdf: KdfConfig,
+    pub features: BTreeMap<String, bool>,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            networks: vec![NetworkConfig::default()],
+            storage: StorageConfig::default(),
+            kdf: KdfConfig::default(),
+            features: BTreeMap::new(),
+        }
+    }
+}
+
+impl ConfigFile {
+    pub fn from_toml(content: &str) -> Result<Self, ConfigErrors> {
+        let config: Self =
+            toml::from_str(content).map_err(|e| ConfigErrors::FailToParse(e.to_string()))?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ConfigErrors> {
+        toml::to_string_pretty(self).map_err(|e| ConfigErrors::FailToParse(e.to_string()))
+    }
+
+    // First one listed is the default network.
+    pub fn network(&self, name: &str) -> Option<&NetworkConfig> {
+        self.networks.iter().find(|n| n.name == name)
+    }
+
+    // Unlisted features are off.
+    pub fn feature(&self, name: &str) -> bool {
+        self.features.get(name).copied().unwrap_or(false)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigErrors> {
+        if self.networks.is_empty() {
+            return Err(ConfigErrors::NoNetworks);
+        }
+
+        for (index, network) in self.networks.iter().enumerate() {
+            let invalid = |reason: &str| {
+                Err(ConfigErrors::InvalidNetwork(
+                    network.name.clone(),
+                    reason.to_string(),
+                ))
+            };
+
+            if network.name.is_empty() {
+                return invalid("empty name");
+            }
+
+            if self.networks[..index]
+                .iter()
+                .any(|n| n.name == network.name)
+            {
+                return invalid("duplicate name");
+            }
+
+            if network.rpc_nodes.is_empty() {
+                return invalid("no rpc nodes");
+            }
+
+            let http = |url: &String| url.starts_with("http://") || url.starts_with("https://");
+
+            if !network.rpc_nodes.iter().all(http)
+                || !network.evm_rpc.iter().all(http)
+                || !network.indexer_url.iter().all(http)
+            {
+                return invalid("expected an http(s) url");
+            }
+
+            if !network
+                .ws_url
+                .iter()
+                .all(|url| url.starts_with("ws://") || url.starts_with("wss://"))
+            {
+                return invalid("expected a ws(s) url");
+            }
+        }
+
+        let kdf = &self.kdf;
+
+        if kdf.iterations == 0 || kdf.parallelism == 0 {
+            return Err(ConfigErrors::InvalidKdf(
+                "iterations and parallelism must be positive".to_string(),
+            ));
+        }
+
+        if kdf.parallelism > ARGON2_MAX_LANES {
+            return Err(ConfigErrors::InvalidKdf(format!(
+                "{} lanes is above the limit of {ARGON2_MAX_LANES}",
+                kdf.parallelism
+            )));
+        }
+
+        let min_memory = ARGON2_MIN_KIB_PER_LANE.checked_mul(kdf.parallelism);
+
+        if min_memory.is_none_or(|min| kdf.memory_kib < min) {
+            return Err(ConfigErrors::InvalidKdf(format!(
+                "{} KiB is below {ARGON2_MIN_KIB_PER_LANE} KiB per lane",
+                kdf.memory_kib
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<ConfigFile, ConfigErrors> {
+    let content = std::fs::read_to_string(path.as_ref())
+        .map_err(|e| ConfigErrors::FailToRead(e.to_string()))?;
+
+    ConfigFile::from_toml(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_with_defaults() {
+        let path = std::env::temp_dir().join("zilpay_config_test.toml");
+
+        std::fs::write(
+            &path,
+            r#"
+            [[networks]]
+            name = "devnet"
+            chain_id = 333
+            rpc_nodes = ["http://127.0.0.1:4201"]
+            ws_url = "ws://127.0.0.1:4202"
+
+            [storage]
+            path = "/tmp/zilpay"
+
+            [kdf]
+            iterations = 4
+
+            [features]
+            evm = true
+            "#,
+        )
+        .unwrap();
+
+        let config = load_from_file(&path).unwrap();
+        let devnet = config.network("devnet").unwrap();
+
+        assert_eq!(devnet.chain_id, 333);
+        assert_eq!(devnet.indexer_url, None);
+        assert_eq!(config.storage.path.as_deref(), Some("/tmp/zilpay"));
+        assert_eq!(config.kdf.iterations, 4);
+        assert_eq!(config.kdf.memory_kib, ARGON2_MEMORY_KIB);
+        assert!(config.feature("evm"));
+        assert!(!config.feature("staking"));
+        assert_eq!(ConfigFile::from_toml("").unwrap(), ConfigFile::default());
+        assert_eq!(
+            ConfigFile::from_toml(&config.to_toml().unwrap()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(
+            ConfigFile::from_toml("networks = []"),
+            Err(ConfigErrors::NoNetworks)
+        );
+        assert!(matches!(
+            ConfigFile::from_toml(
+                "[[networks]]\nname = \"dev\"\nchain_id = 1\nrpc_nodes = [\"ftp://node\"]"
+            ),
+            Err(ConfigErrors::InvalidNetwork(name, _)) if name == "dev"
+        ));
+        assert!(matches!(
+            ConfigFile::from_toml("[kdf]\nparallelism = 0"),
+            Err(ConfigErrors::InvalidKdf(_))
+        ));
+        // 8 * parallelism would overflow u32
+        assert!(matches!(
+            ConfigFile::from_toml("[kdf]\nmemory_kib = 4294967295\nparallelism = 4294967295"),
+            Err(ConfigErrors::InvalidKdf(_))
+        ));
+        assert!(matches!(
+            ConfigFile::from_toml("[kdf]\nmemory_kib = 4294967295\nparallelism = 16777216"),
+            Err(ConfigErrors::InvalidKdf(_))
+        ));
+        assert!(
+            ConfigFile::from_toml("[kdf]\nmemory_kib = 4294967295\nparallelism = 16777215").is_ok()
+        );
+        assert!(matches!(
+            ConfigFile::from_toml("[storage]\nunknown = 1"),
+            Err(ConfigErrors::FailToParse(_))
+        ));
+    }
+}
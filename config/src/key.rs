@@ -1,3 +1,8 @@
 pub const PUB_KEY_SIZE: usize = 33;
 pub const SECRET_KEY_SIZE: usize = 32;
 pub const BIP39_SEED_SIZE: usize = 64;
+pub const ED25519_PUB_KEY_SIZE: usize = 32;
+pub const ED25519_SIGNATURE_SIZE: usize = 64;
+pub const BIP32_HARDENED: u32 = 0x8000_0000;
+pub const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+pub const XPUB_SIZE: usize = 78;
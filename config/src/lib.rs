@@ -1,11 +1,19 @@
 pub const MAIN_URL: &str = "https://api.zilliqa.com";
+pub const MAIN_WS_URL: &str = "wss://api-ws.zilliqa.com";
+pub const MAIN_CHAIN_ID: u16 = 1;
 pub const SYS_SIZE: usize = std::mem::size_of::<usize>();
 
 pub mod address;
 pub mod argon;
+pub mod broadcast;
 pub mod cipher;
 pub mod contracts;
+pub mod file;
 pub mod key;
+pub mod node;
+pub mod session;
 pub mod sha;
 pub mod storage;
 pub mod wallet;
+
+pub use file::load_from_file;
@@ -0,0 +1,16 @@
+// Node health score used for weighted selection, a node at zero is skipped.
+pub const NODE_MAX_SCORE: u32 = 100;
+pub const NODE_SUCCESS_BONUS: u32 = 10;
+
+// GetVersion majors: ZQ1 releases are v8 and up, ZQ2 restarted at v0 and its
+// v0.x builds only carry part of the ZQ1 API.
+pub const ZQ1_MIN_MAJOR: u64 = 8;
+pub const ZQ2_STABLE_MAJOR: u64 = 1;
+// RPC journal: requests kept for support bundles, error text cut at this length.
+pub const RPC_JOURNAL_CAPACITY: usize = 200;
+pub const RPC_JOURNAL_MAX_MESSAGE: usize = 200;
+// Live subscriptions: reconnect backoff after the feed drops.
+pub const RECONNECT_BASE_DELAY_MS: u64 = 500;
+pub const RECONNECT_MAX_DELAY_MS: u64 = 30_000;
+// Pages of a `*ForTxBlockEx` walk, a larger NumPages from the node is refused.
+pub const MAX_TX_BLOCK_PAGES: u64 = 1_000;
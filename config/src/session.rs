@@ -0,0 +1,4 @@
+// How long a spend elevation stays valid after it was granted.
+pub const SPEND_ELEVATION_TTL_MS: u64 = 5 * 60 * 1000;
+// Furthest a dApp sign request may expire, its id is kept until then.
+pub const SIGN_REQUEST_MAX_TTL_MS: u64 = 60 * 60 * 1000;
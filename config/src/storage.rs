@@ -1,3 +1,96 @@
 pub const STORAGE_VERSION: u16 = 0;
 pub const INDICATORS_DB_KEY: &[u8] = b"address_indicators";
 pub const SELECTED_WALLET_DB_KEY: &[u8] = b"selected_wallet_db_key";
+pub const SYNC_META_TREE: &[u8] = b"sync_meta";
+pub const SERVED_SIGN_REQUESTS_DB_KEY: &[u8] = b"served_sign_requests";
+pub const BROADCAST_QUEUE_DB_KEY: &[u8] = b"broadcast_queue";
+pub const CONTRACT_INIT_TREE: &[u8] = b"contract_init";
+pub const TOKEN_OVERRIDES_TREE: &[u8] = b"token_overrides";
+// Token allowances per network and owner, updated from receipts.
+pub const ALLOWANCE_TREE: &[u8] = b"allowances";
+// A spender's own `TransferFrom` never reaches our receipts, cached pairs
+// are read from the contract again after this long.
+pub const ALLOWANCE_CACHE_TTL_MS: u64 = 10 * 60 * 1000;
+// Platform data directory used when no explicit storage path is given.
+pub const STORAGE_QUALIFIER: &str = "com.zilpay";
+pub const STORAGE_ORGANIZATION: &str = "ZilPay";
+pub const STORAGE_APPLICATION: &str = "ZilPay Core";
+pub const NAMESPACE_TREE_PREFIX: &[u8] = b"ns:";
+// Secondary indexes of a collection, followed by its tree and index name.
+pub const INDEX_TREE_PREFIX: &[u8] = b"index:";
+// HKDF info of the digest an index key is stored as in an encrypted storage,
+// followed by the index tree name.
+pub const INDEX_BLIND_INFO: &[u8] = b"zilpay:storage:index:";
+// Salt and password check of an encrypted storage, kept in plaintext.
+pub const ENCRYPTION_META_TREE: &[u8] = b"encryption_meta";
+pub const ENCRYPTION_SALT_KEY: &[u8] = b"salt";
+pub const ENCRYPTION_CHECK_KEY: &[u8] = b"check";
+pub const ENCRYPTION_SALT_SIZE: usize = 32;
+// Storage wide settings that outlive a session, kept in plaintext.
+pub const STORAGE_META_TREE: &[u8] = b"storage_meta";
+pub const HASH_ALGO_KEY: &[u8] = b"hash_algo";
+// Namespaces sealed with their own HKDF subkey of the storage key, the info
+// is the prefix followed by the namespace tree name.
+pub const ISOLATED_NAMESPACES: &[&[u8]] = &[VAULT_NS, SESSIONS_NS];
+pub const ENCRYPTION_DOMAIN_INFO: &[u8] = b"zilpay:storage:domain:";
+// Seed vault of every wallet and session tokens.
+pub const VAULT_NS: &[u8] = b"vault";
+pub const SESSIONS_NS: &[u8] = b"sessions";
+pub const KEY_USAGE_DB_KEY: &[u8] = b"key_usage_stats";
+// Bumped whenever the layout of an exported snapshot changes.
+pub const SNAPSHOT_FORMAT_VERSION: u16 = 1;
+pub const RPC_JOURNAL_TREE: &[u8] = b"rpc_journal";
+// Expiry time (ms, big endian) of records written with a TTL.
+pub const TTL_TREE: &[u8] = b"ttl";
+// Last live result of every cold-start prefetch stage, per account.
+pub const PREFETCH_CACHE_TREE: &[u8] = b"prefetch_cache";
+// Values at least this large are zstd compressed when compression is on.
+pub const STORAGE_COMPRESSION_MIN_SIZE: usize = 512;
+pub const STORAGE_COMPRESSION_LEVEL: i32 = 3;
+// Deletion time (ms, big endian) of removed keys while tombstones are on.
+pub const TOMBSTONE_TREE: &[u8] = b"tombstones";
+// Append-only log of writes and removes while auditing is on.
+pub const AUDIT_TREE: &[u8] = b"audit_log";
+// Cache garbage collection: how often it runs and how long tombstones are
+// kept for devices that have not synced yet.
+pub const GC_INTERVAL_MS: u64 = 24 * 60 * 60 * 1000;
+pub const GC_LAST_RUN_DB_KEY: &[u8] = b"gc_last_run";
+pub const TOMBSTONE_RETENTION_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+// HTTP sync client state: last pushed hashsum per key, and the pull cursor.
+pub const SYNC_CLIENT_TREE: &[u8] = b"sync_client";
+pub const SYNC_CURSOR_TREE: &[u8] = b"sync_cursor";
+pub const SYNC_CURSOR_KEY: &[u8] = b"pull";
+// Verification status, ABI and sources of contracts, per network.
+pub const CONTRACT_VERIFICATION_TREE: &[u8] = b"contract_verification";
+// Password protected backup file: magic, format byte, Argon2 salt, then the
+// AES-GCM sealed snapshot.
+pub const BACKUP_MAGIC: &[u8] = b"ZPVAULT";
+pub const BACKUP_FORMAT_VERSION: u8 = 1;
+// Isolated databases under the same data directory, and the JSON file
+// listing their names.
+pub const PROFILES_DIR: &str = "profiles";
+pub const PROFILES_REGISTRY_FILE: &str = "profiles.json";
+pub const PROFILE_NAME_MAX_LEN: usize = 64;
+// Advisory lock inside the database directory, held while it is open.
+pub const STORAGE_LOCK_FILE: &str = "zilpay.lock";
+// Sibling of the database holding a read-only copy, followed by a random id,
+// and how many times a copy that raced with a write is retaken.
+pub const READ_ONLY_COPY_SUFFIX: &str = ".zilpay-ro-";
+pub const READ_ONLY_COPY_ATTEMPTS: usize = 3;
+// Contacts and token metadata written by the browser extension importer.
+pub const EXTENSION_CONTACTS_NS: &str = "contacts";
+pub const EXTENSION_TOKENS_NS: &str = "tokens";
+// Decoded records kept in memory for repeated reads of the same key.
+pub const STORAGE_READ_CACHE_CAPACITY: usize = 256;
+// IndexedDB database version and the object store holding every tree of
+// the web build.
+pub const IDB_VERSION: u32 = 1;
+pub const IDB_OBJECT_STORE: &str = "records";
+// Default retention of history and cached responses on the device.
+pub const RETENTION_MAX_RECORDS: usize = 1000;
+pub const RETENTION_MAX_AGE_MS: u64 = 365 * 24 * 60 * 60 * 1000;
+pub const RETENTION_MAX_BYTES: u64 = 5 * 1024 * 1024;
+// Siblings of the database directory while `compact` swaps it: the fresh
+// copy and the previous files.
+pub const COMPACT_NEW_SUFFIX: &str = ".compact";
+pub const COMPACT_OLD_SUFFIX: &str = ".old";
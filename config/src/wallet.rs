@@ -3,3 +3,34 @@ pub const N_SALT: [u8; 26] = [
     167, 36, 156, 3, 14, 212, 191, 102, 69, 11, 214, 43, 181, 138, 7, 21, 241, 122, 104, 60, 132,
     106, 5, 135, 186, 182,
 ];
+pub const UNDO_LOG_CAPACITY: usize = 32;
+pub const UNDO_GRACE_PERIOD_MS: u64 = 24 * 60 * 60 * 1000;
+pub const UNDO_LOG_KEY_SUFFIX: &[u8] = b"undo_log";
+pub const HISTORY_KEY_SUFFIX: &[u8] = b"history";
+pub const ESCROW_KEY_SUFFIX: &[u8] = b"escrow";
+// Contract call templates, in the vault namespace after the wallet key.
+pub const TEMPLATES_KEY_SUFFIX: &[u8] = b"templates";
+// Default bound on rate movement between quoting and confirming a fiat send.
+pub const FIAT_MAX_SLIPPAGE_BPS: u16 = 100;
+// Rates are turned into fixed point with this many decimals before any math.
+pub const FIAT_RATE_DECIMALS: u32 = 12;
+// Swap quotes: price impact that needs the user's attention, and the trade
+// share of the pool (bps) and slippage tolerance that invite a sandwich.
+pub const SWAP_HIGH_IMPACT_BPS: u16 = 300;
+pub const SWAP_SANDWICH_MEDIUM_SHARE_BPS: u128 = 10;
+pub const SWAP_SANDWICH_HIGH_SHARE_BPS: u128 = 100;
+pub const SWAP_SANDWICH_SLIPPAGE_BPS: u16 = 100;
+// Signing baselines: samples kept per account, how many are needed before
+// anything is flagged and how far a request may deviate from typical.
+pub const KEY_USAGE_WINDOW: usize = 50;
+pub const KEY_USAGE_MIN_SAMPLES: usize = 5;
+pub const KEY_USAGE_ANOMALY_FACTOR: u128 = 10;
+pub const ACCOUNT_TAG_MAX_LEN: usize = 32;
+// Mixed into the duress password proof to find the decoy keys slot.
+pub const DURESS_SLOT_DOMAIN: &[u8] = b"zilpay:duress_slot";
+// Hits returned by the global search bar.
+pub const SEARCH_RESULTS_LIMIT: usize = 20;
+// Backup check after a mnemonic is generated: words asked for and how many
+// wrong options a multiple choice question shows beside the right one.
+pub const MNEMONIC_CHALLENGE_COUNT: usize = 3;
+pub const MNEMONIC_CHALLENGE_DECOYS: usize = 2;
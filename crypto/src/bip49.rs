@@ -7,6 +7,8 @@ pub enum Bip49DerivationPath {
 impl<'a> Bip49DerivationPath {
     pub const ZIL_PATH: &'a str = "m/44'/313'/0'/0/";
     pub const ETH_PATH: &'a str = "m/44'/60'/0'/0/";
+    pub const ZIL_ACCOUNT_PATH: &'a str = "m/44'/313'/0'";
+    pub const ETH_ACCOUNT_PATH: &'a str = "m/44'/60'/0'";
 
     pub fn get_path(&self) -> String {
         match self {
@@ -15,6 +17,14 @@ impl<'a> Bip49DerivationPath {
         }
     }
 
+    // Level an xpub is exported at, receive keys are `0/index` below it.
+    pub fn get_account_path(&self) -> &'static str {
+        match self {
+            Bip49DerivationPath::Zilliqa(_) => Bip49DerivationPath::ZIL_ACCOUNT_PATH,
+            Bip49DerivationPath::Ethereum(_) => Bip49DerivationPath::ETH_ACCOUNT_PATH,
+        }
+    }
+
     pub fn get_index(&self) -> usize {
         match self {
             Bip49DerivationPath::Zilliqa(i) => *i,
@@ -0,0 +1,122 @@
+use rand::{rngs::OsRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::sync::{Arc, RwLock};
+
+/// Where every key-critical random byte comes from: secret keys, nonces,
+/// salts and session keys. Other randomness (node picking, retry jitter) may
+/// use `thread_rng`, nothing that protects a secret does.
+pub trait EntropySource: Send + Sync {
+    fn fill(&self, dest: &mut [u8]);
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsEntropy;
+
+impl EntropySource for OsEntropy {
+    fn fill(&self, dest: &mut [u8]) {
+        OsRng.fill_bytes(dest);
+    }
+}
+
+/// XOR of two independent sources, at least as strong as the better one.
+/// E.g. the OS RNG and a secure enclave of the mobile host.
+pub struct MixedEntropy<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: EntropySource, B: EntropySource> MixedEntropy<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: EntropySource, B: EntropySource> EntropySource for MixedEntropy<A, B> {
+    fn fill(&self, dest: &mut [u8]) {
+        let mut other = vec![0u8; dest.len()];
+
+        self.first.fill(dest);
+        self.second.fill(&mut other);
+
+        for (byte, mix) in dest.iter_mut().zip(&other) {
+            *byte ^= mix;
+        }
+    }
+}
+
+static SOURCE: RwLock<Option<Arc<dyn EntropySource>>> = RwLock::new(None);
+
+/// Replaces the process wide source, the OS RNG until this is called.
+pub fn set_entropy_source(source: impl EntropySource + 'static) {
+    let mut current = SOURCE.write().unwrap_or_else(|e| e.into_inner());
+
+    *current = Some(Arc::new(source));
+}
+
+pub fn fill_random(dest: &mut [u8]) {
+    let source = SOURCE.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+    match source {
+        Some(source) => source.fill(dest),
+        None => OsEntropy.fill(dest),
+    }
+}
+
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+
+    fill_random(&mut bytes);
+
+    bytes
+}
+
+// For APIs that take an `RngCore + CryptoRng`, seeded from the source.
+pub fn rng() -> ChaCha20Rng {
+    ChaCha20Rng::from_seed(random_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counting;
+
+    impl EntropySource for Counting {
+        fn fill(&self, dest: &mut [u8]) {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            dest.fill(0xAA);
+        }
+    }
+
+    struct Fixed(u8);
+
+    impl EntropySource for Fixed {
+        fn fill(&self, dest: &mut [u8]) {
+            dest.fill(self.0);
+        }
+    }
+
+    #[test]
+    fn test_injected_source() {
+        let mut mixed = [0u8; 4];
+
+        MixedEntropy::new(Fixed(0b1010), Fixed(0b0110)).fill(&mut mixed);
+
+        assert_eq!(mixed, [0b1100; 4]);
+
+        // still random, the host part is mixed into the OS RNG
+        set_entropy_source(MixedEntropy::new(OsEntropy, Counting));
+
+        let first: [u8; 32] = random_bytes();
+        let second: [u8; 32] = random_bytes();
+        let _ = rng().next_u64();
+
+        assert_ne!(first, second);
+        assert!(CALLS.load(Ordering::SeqCst) >= 3);
+
+        set_entropy_source(OsEntropy);
+    }
+}
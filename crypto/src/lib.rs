@@ -1,2 +1,4 @@
 pub mod bip49;
+pub mod entropy;
+pub mod mnemonic_challenge;
 pub mod schnorr;
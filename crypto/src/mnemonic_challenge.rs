@@ -0,0 +1,173 @@
+use bip39::Mnemonic;
+use config::wallet::MNEMONIC_CHALLENGE_DECOYS;
+use rand::{seq::SliceRandom, Rng};
+use zil_errors::mnemonic::MnemonicChallengeErrors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeKind {
+    Type,   // the word has to be typed
+    Choose, // picked among decoys
+}
+
+/// One backup verification question, positions start at 1 as shown to
+/// the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Challenge {
+    WordAt {
+        position: usize,
+    },
+    ChooseWord {
+        position: usize,
+        options: Vec<String>,
+    },
+}
+
+impl Challenge {
+    pub fn position(&self) -> usize {
+        match self {
+            Challenge::WordAt { position } | Challenge::ChooseWord { position, .. } => *position,
+        }
+    }
+
+    // Case and surrounding whitespace of the answer are ignored.
+    pub fn check(&self, mnemonic: &Mnemonic, answer: &str) -> bool {
+        let answer = answer.trim().to_lowercase();
+
+        mnemonic
+            .word_iter()
+            .nth(self.position().wrapping_sub(1))
+            .is_some_and(|word| word == answer)
+    }
+}
+
+/// `count` questions about distinct random positions, in ascending order.
+/// Decoys come from the wordlist of the mnemonic and never from the
+/// mnemonic itself.
+pub fn challenges<R: Rng>(
+    mnemonic: &Mnemonic,
+    count: usize,
+    kind: ChallengeKind,
+    rng: &mut R,
+) -> Result<Vec<Challenge>, MnemonicChallengeErrors> {
+    let words: Vec<&str> = mnemonic.word_iter().collect();
+
+    if count > words.len() {
+        return Err(MnemonicChallengeErrors::TooManyChallenges(
+            count,
+            words.len(),
+        ));
+    }
+
+    let mut positions = rand::seq::index::sample(rng, words.len(), count).into_vec();
+
+    positions.sort_unstable();
+
+    Ok(positions
+        .into_iter()
+        .map(|index| match kind {
+            ChallengeKind::Type => Challenge::WordAt {
+                position: index + 1,
+            },
+            ChallengeKind::Choose => Challenge::ChooseWord {
+                position: index + 1,
+                options: options(mnemonic, &words, words[index], rng),
+            },
+        })
+        .collect())
+}
+
+/// Every challenge answered in order, the first wrong answer is reported.
+pub fn verify(
+    mnemonic: &Mnemonic,
+    challenges: &[Challenge],
+    answers: &[&str],
+) -> Result<(), MnemonicChallengeErrors> {
+    if challenges.len() != answers.len() {
+        return Err(MnemonicChallengeErrors::AnswersMismatch(
+            challenges.len(),
+            answers.len(),
+        ));
+    }
+
+    match challenges
+        .iter()
+        .zip(answers)
+        .find(|(challenge, answer)| !challenge.check(mnemonic, answer))
+    {
+        Some((challenge, _)) => Err(MnemonicChallengeErrors::WrongWord(challenge.position())),
+        None => Ok(()),
+    }
+}
+
+fn options<R: Rng>(mnemonic: &Mnemonic, words: &[&str], word: &str, rng: &mut R) -> Vec<String> {
+    let wordlist = mnemonic.language().word_list();
+    let mut options = vec![word.to_string()];
+
+    while options.len() <= MNEMONIC_CHALLENGE_DECOYS {
+        let decoy = wordlist[rng.gen_range(0..wordlist.len())];
+
+        if !words.contains(&decoy) && !options.iter().any(|o| o == decoy) {
+            options.push(decoy.to_string());
+        }
+    }
+
+    options.shuffle(rng);
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::wallet::MNEMONIC_CHALLENGE_COUNT;
+
+    const WORDS: &str =
+        "green process gate doctor slide whip priority shrug diamond crumble average help";
+
+    #[test]
+    fn test_challenges() {
+        let mnemonic = Mnemonic::parse_normalized(WORDS).unwrap();
+        let words: Vec<&str> = WORDS.split(' ').collect();
+        let mut rng = rand::thread_rng();
+        let typed = challenges(
+            &mnemonic,
+            MNEMONIC_CHALLENGE_COUNT,
+            ChallengeKind::Type,
+            &mut rng,
+        )
+        .unwrap();
+        let answers: Vec<&str> = typed.iter().map(|c| words[c.position() - 1]).collect();
+
+        assert_eq!(typed.len(), MNEMONIC_CHALLENGE_COUNT);
+        assert!(typed.windows(2).all(|w| w[0].position() < w[1].position()));
+        assert_eq!(verify(&mnemonic, &typed, &answers), Ok(()));
+
+        let chosen = challenges(&mnemonic, 12, ChallengeKind::Choose, &mut rng).unwrap();
+
+        for challenge in &chosen {
+            let Challenge::ChooseWord { position, options } = challenge else {
+                panic!("expected a multiple choice question");
+            };
+            let word = words[position - 1];
+
+            assert_eq!(options.len(), MNEMONIC_CHALLENGE_DECOYS + 1);
+            assert_eq!(
+                options
+                    .iter()
+                    .filter(|o| words.contains(&o.as_str()))
+                    .count(),
+                1
+            );
+            assert!(challenge.check(&mnemonic, &format!(" {} ", word.to_uppercase())));
+        }
+
+        assert_eq!(
+            verify(&mnemonic, &chosen[..1], &["zoo"]),
+            Err(MnemonicChallengeErrors::WrongWord(1))
+        );
+        assert_eq!(
+            challenges(&mnemonic, 13, ChallengeKind::Type, &mut rng),
+            Err(MnemonicChallengeErrors::TooManyChallenges(13, 12))
+        );
+    }
+}
@@ -1,17 +1,17 @@
+use crate::entropy;
 pub use k256::{ecdsa::Signature, PublicKey, SecretKey};
 use k256::{
     elliptic_curve::{ops::Reduce, sec1::ToEncodedPoint, Group, PrimeField},
     AffinePoint, FieldBytes, Scalar, U256,
 };
-use rand::{RngCore, SeedableRng};
-use rand_chacha::ChaCha20Rng;
+use rand::RngCore;
 use sha2::{Digest, Sha256};
 use zil_errors::crypto::SchorrError;
 
 pub const MAX_TRY_SIGN: usize = 100_000_000;
 
 pub fn sign(message: &[u8], secret_key: &SecretKey) -> Result<Signature, SchorrError> {
-    let mut rng = ChaCha20Rng::from_entropy();
+    let mut rng = entropy::rng();
     let safe_counter: usize = 0;
 
     loop {
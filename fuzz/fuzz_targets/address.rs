@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use proto::{address_format::parse_address, zil_address::from_zil_bech32_address};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(value) = std::str::from_utf8(data) {
+        let _ = parse_address(value);
+        let _ = from_zil_bech32_address(value);
+    }
+});
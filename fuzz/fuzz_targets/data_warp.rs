@@ -0,0 +1,9 @@
+#![no_main]
+
+use bincode::FromBytes;
+use libfuzzer_sys::fuzz_target;
+use storage::data_warp::DataWarp;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = DataWarp::from_bytes(data.into());
+});
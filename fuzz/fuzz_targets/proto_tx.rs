@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost::Message;
+use proto::zq1_proto::ProtoTransactionCoreInfo;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ProtoTransactionCoreInfo::decode(data);
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zilliqa::json_rpc::zil_interfaces::{
+    ContractInitParam, CreateTransactionRes, GetBalanceRes, ResultRes, TxBlockBodiesPage,
+    TxBlockHashesPage,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Vec<ResultRes<GetBalanceRes>>>(data);
+    let _ = serde_json::from_slice::<Vec<ResultRes<TxBlockHashesPage>>>(data);
+    let _ = serde_json::from_slice::<Vec<ResultRes<TxBlockBodiesPage>>>(data);
+    let _ = serde_json::from_slice::<Vec<ResultRes<CreateTransactionRes>>>(data);
+    let _ = serde_json::from_slice::<Vec<ResultRes<Vec<ContractInitParam>>>>(data);
+});
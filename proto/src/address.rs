@@ -1,13 +1,12 @@
 use crate::{
-    pubkey::PubKey,
+    address_format::to_eip55,
+    pubkey::{keccak256_addr, PubKey},
     zil_address::{from_zil_base16, from_zil_pub_key, to_zil_bech32},
 };
-use ethers::{core::k256::ecdsa::VerifyingKey, types::H160, utils::to_checksum};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::str::FromStr;
 
 use config::address::ADDR_LEN;
-use ethers::utils::public_key_to_address;
 use zil_errors::address::AddressError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -31,9 +30,7 @@ impl Address {
                 Ok(Self::Secp256k1Sha256Zilliqa(addr))
             }
             PubKey::Secp256k1Keccak256Ethereum(pk) => {
-                let public_key =
-                    VerifyingKey::from_sec1_bytes(pk).or(Err(AddressError::InvalidVerifyingKey))?;
-                let addr: [u8; ADDR_LEN] = public_key_to_address(&public_key).into();
+                let addr = keccak256_addr(pk).ok_or(AddressError::InvalidVerifyingKey)?;
 
                 Ok(Self::Secp256k1Keccak256Ethereum(addr))
             }
@@ -74,10 +71,7 @@ impl std::fmt::Display for Address {
                 // unwrap shouldn't execpt
                 write!(f, "{}", to_zil_bech32(bytes).unwrap())
             }
-            Self::Secp256k1Keccak256Ethereum(bytes) => {
-                let h = H160::from_slice(bytes);
-                write!(f, "{}", to_checksum(&h, None))
-            }
+            Self::Secp256k1Keccak256Ethereum(bytes) => write!(f, "{}", to_eip55(bytes)),
         }
     }
 }
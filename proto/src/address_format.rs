@@ -0,0 +1,220 @@
+//! Pure string conversions between address formats, no wallet types needed.
+
+use crate::zil_address::{from_zil_bech32_address, to_zil_bech32};
+use config::address::{ADDR_LEN, HRP};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use zil_errors::address::AddressError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddrFormat {
+    Bech32,      // zil1...
+    Base16,      // lowercase hex, 0x prefixed
+    ZilChecksum, // ZIP-1 mixed case hex
+    Eip55,       // EIP-55 mixed case hex
+}
+
+/// Accepts bech32 or hex (with or without 0x). Mixed case hex must carry a
+/// valid ZIP-1 or EIP-55 checksum.
+pub fn parse_address(value: &str) -> Result<[u8; ADDR_LEN], AddressError> {
+    let value = value.trim();
+
+    if value.to_lowercase().starts_with(HRP) && !is_hex(value) {
+        return from_zil_bech32_address(value);
+    }
+
+    let hex_value = value.strip_prefix("0x").unwrap_or(value);
+    let bytes: [u8; ADDR_LEN] = hex::decode(hex_value)
+        .or(Err(AddressError::InvalidHex))?
+        .try_into()
+        .or(Err(AddressError::InvalidLength))?;
+    let is_mixed_case = hex_value.chars().any(|c| c.is_ascii_uppercase())
+        && hex_value.chars().any(|c| c.is_ascii_lowercase());
+
+    if is_mixed_case
+        && to_zil_checksum(&bytes) != format!("0x{hex_value}")
+        && to_eip55(&bytes) != format!("0x{hex_value}")
+    {
+        return Err(AddressError::InvalidChecksum);
+    }
+
+    Ok(bytes)
+}
+
+pub fn format_address(bytes: &[u8; ADDR_LEN], format: AddrFormat) -> Result<String, AddressError> {
+    match format {
+        AddrFormat::Bech32 => to_zil_bech32(bytes),
+        AddrFormat::Base16 => Ok(format!("0x{}", hex::encode(bytes))),
+        AddrFormat::ZilChecksum => Ok(to_zil_checksum(bytes)),
+        AddrFormat::Eip55 => Ok(to_eip55(bytes)),
+    }
+}
+
+pub fn convert(value: &str, format: AddrFormat) -> Result<String, AddressError> {
+    format_address(&parse_address(value)?, format)
+}
+
+pub fn convert_batch(values: &[&str], format: AddrFormat) -> Vec<Result<String, AddressError>> {
+    values.iter().map(|v| convert(v, format)).collect()
+}
+
+pub fn bech32_to_base16(value: &str) -> Result<String, AddressError> {
+    let bytes = from_zil_bech32_address(value)?;
+
+    Ok(to_zil_checksum(&bytes))
+}
+
+pub fn base16_to_bech32(value: &str) -> Result<String, AddressError> {
+    convert(value, AddrFormat::Bech32)
+}
+
+/// ZIP-1 checksum: hex chars are upper-cased when bit `255 - 6 * i` of
+/// SHA-256(address bytes) is set.
+pub fn to_zil_checksum(bytes: &[u8; ADDR_LEN]) -> String {
+    let hash: [u8; 32] = Sha256::digest(bytes).into();
+    let lower = hex::encode(bytes);
+    let mut result = String::with_capacity(2 + lower.len());
+
+    result.push_str("0x");
+
+    for (i, c) in lower.chars().enumerate() {
+        let bit = 255 - 6 * i;
+        let is_set = hash[31 - bit / 8] & (1 << (bit % 8)) != 0;
+
+        if c.is_ascii_alphabetic() && is_set {
+            result.push(c.to_ascii_uppercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// EIP-55 checksum: a hex letter is upper-cased when the matching nibble of
+/// Keccak-256(lowercase hex) is 8 or more.
+pub fn to_eip55(bytes: &[u8; ADDR_LEN]) -> String {
+    let lower = hex::encode(bytes);
+    let hash: [u8; 32] = Keccak256::digest(lower.as_bytes()).into();
+    let mut result = String::with_capacity(2 + lower.len());
+
+    result.push_str("0x");
+
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+
+        if c.is_ascii_alphabetic() && nibble >= 8 {
+            result.push(c.to_ascii_uppercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+pub fn is_valid_zil_checksum(value: &str) -> bool {
+    parse_hex(value).is_some_and(|bytes| to_zil_checksum(&bytes) == with_prefix(value))
+}
+
+pub fn is_valid_eip55(value: &str) -> bool {
+    parse_hex(value).is_some_and(|bytes| to_eip55(&bytes) == with_prefix(value))
+}
+
+fn parse_hex(value: &str) -> Option<[u8; ADDR_LEN]> {
+    let hex_value = value.strip_prefix("0x").unwrap_or(value);
+
+    hex::decode(hex_value).ok()?.try_into().ok()
+}
+
+fn with_prefix(value: &str) -> String {
+    if value.starts_with("0x") {
+        value.to_string()
+    } else {
+        format!("0x{value}")
+    }
+}
+
+fn is_hex(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .unwrap_or(value)
+        .chars()
+        .all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BECH32: &str = "zil1w7f636xqn5vf6n2zrnjmckekw3jkckkpyrd6z8";
+    const BASE16: &str = "0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1";
+
+    #[test]
+    fn test_zil_checksum() {
+        // vector from zilliqa-js
+        let bytes = parse_hex("4BAF5FADA8E5DB92C3D3242618C5B47133AE003C").unwrap();
+
+        assert_eq!(
+            to_zil_checksum(&bytes),
+            "0x4BAF5faDA8e5Db92C3d3242618c5B47133AE003C"
+        );
+        assert!(is_valid_zil_checksum(
+            "0x4BAF5faDA8e5Db92C3d3242618c5B47133AE003C"
+        ));
+        assert!(!is_valid_zil_checksum(
+            "0x4baf5faDA8e5Db92C3d3242618c5B47133AE003C"
+        ));
+    }
+
+    #[test]
+    fn test_bech32_base16() {
+        let checksummed = bech32_to_base16(BECH32).unwrap();
+
+        assert_eq!(checksummed.to_lowercase(), BASE16);
+        assert_eq!(base16_to_bech32(BASE16).unwrap(), BECH32);
+        assert_eq!(base16_to_bech32(&checksummed).unwrap(), BECH32);
+        assert_eq!(convert(BECH32, AddrFormat::Base16).unwrap(), BASE16);
+    }
+
+    #[test]
+    fn test_eip55() {
+        let eip55 = convert(BASE16, AddrFormat::Eip55).unwrap();
+
+        assert!(is_valid_eip55(&eip55));
+        assert_eq!(convert(&eip55, AddrFormat::Bech32).unwrap(), BECH32);
+    }
+
+    #[test]
+    fn test_batch() {
+        let res = convert_batch(
+            &[
+                BECH32,
+                "0x1234",
+                "0x7793A8E8C09D189D4D421CE5BC5B3674656C5Ac1",
+            ],
+            AddrFormat::Base16,
+        );
+
+        assert_eq!(res[0].as_deref(), Ok(BASE16));
+        assert_eq!(res[1], Err(AddressError::InvalidLength));
+        assert_eq!(res[2], Err(AddressError::InvalidChecksum));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_parse_never_panics(value in "\\PC*") {
+            let _ = parse_address(&value);
+            let _ = from_zil_bech32_address(&value);
+        }
+
+        #[test]
+        fn prop_roundtrip(bytes in proptest::prelude::any::<[u8; ADDR_LEN]>()) {
+            for format in [AddrFormat::Bech32, AddrFormat::Base16, AddrFormat::ZilChecksum, AddrFormat::Eip55] {
+                let formatted = format_address(&bytes, format).unwrap();
+
+                proptest::prop_assert_eq!(parse_address(&formatted), Ok(bytes));
+            }
+        }
+    }
+}
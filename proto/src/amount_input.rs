@@ -0,0 +1,216 @@
+use crate::units::{to_base_units, ZilUnit};
+use zil_errors::units::{AmountViolation, UnitsErrors};
+
+/// How a token's amounts are typed in: its precision and the separators of
+/// the user's locale, e.g. `,` for decimals and `.` for thousands in German.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountRules {
+    pub symbol: String,
+    pub decimals: u32,
+    pub decimal_separator: char,
+    pub group_separator: Option<char>,
+}
+
+impl AmountRules {
+    pub fn new(symbol: &str, decimals: u32) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            decimals,
+            decimal_separator: '.',
+            group_separator: None,
+        }
+    }
+
+    pub fn zil() -> Self {
+        Self::new(&ZilUnit::Zil.to_string(), ZilUnit::Zil.decimals())
+    }
+
+    pub fn with_locale(mut self, decimal_separator: char, group_separator: Option<char>) -> Self {
+        self.decimal_separator = decimal_separator;
+        self.group_separator = group_separator;
+        self
+    }
+
+    /// Checks `input` against these rules and `balance` minus `fee`, all in
+    /// smallest units. Tokens pay the fee in ZIL, pass zero for them.
+    /// Returns the amount in smallest units or every violation found.
+    pub fn validate(
+        &self,
+        input: &str,
+        balance: u128,
+        fee: u128,
+    ) -> Result<u128, Vec<AmountViolation>> {
+        let (int_part, frac_part) = self.split(input.trim()).map_err(|v| vec![v])?;
+        let mut violations = Vec::new();
+        let max = self.decimals as usize;
+
+        if frac_part.len() > max {
+            violations.push(AmountViolation::TooManyDecimals(
+                self.symbol.clone(),
+                self.decimals,
+            ));
+        }
+
+        // the cut digits don't matter for the checks below
+        let frac_part = &frac_part[..frac_part.len().min(max)];
+        let value = match to_base_units(
+            &format!("{int_part}.{frac_part}"),
+            self.decimals,
+            &self.symbol,
+        ) {
+            Ok(value) => value,
+            Err(UnitsErrors::Overflow) => {
+                violations.push(AmountViolation::Overflow);
+                return Err(violations);
+            }
+            Err(_) => return Err(vec![AmountViolation::Empty]),
+        };
+
+        if value == 0 {
+            violations.push(AmountViolation::Zero);
+        }
+
+        match balance.checked_sub(fee) {
+            None => violations.push(AmountViolation::FeeExceedsBalance),
+            Some(spendable) if value > spendable => {
+                violations.push(AmountViolation::ExceedsBalance(spendable))
+            }
+            Some(_) => {}
+        }
+
+        if violations.is_empty() {
+            Ok(value)
+        } else {
+            Err(violations)
+        }
+    }
+
+    // Plain integer and fraction digits, group separators removed.
+    fn split(&self, input: &str) -> Result<(String, String), AmountViolation> {
+        if input.is_empty() {
+            return Err(AmountViolation::Empty);
+        }
+
+        if input.starts_with('-') {
+            return Err(AmountViolation::Negative);
+        }
+
+        let is_separator = |c: char| c == self.decimal_separator || Some(c) == self.group_separator;
+
+        if let Some(c) = input
+            .chars()
+            .find(|c| !c.is_ascii_digit() && !is_separator(*c))
+        {
+            return Err(match c {
+                'e' | 'E' => AmountViolation::ExponentNotation,
+                _ => AmountViolation::InvalidCharacter(c),
+            });
+        }
+
+        let (int_part, frac_part) = input
+            .split_once(self.decimal_separator)
+            .unwrap_or((input, ""));
+
+        if frac_part.contains(self.decimal_separator) {
+            return Err(AmountViolation::MisplacedSeparator(self.decimal_separator));
+        }
+
+        if let Some(group) = self.group_separator {
+            if frac_part.contains(group) || !is_grouped(int_part, group) {
+                return Err(AmountViolation::MisplacedSeparator(group));
+            }
+        }
+
+        let int_part: String = int_part.chars().filter(char::is_ascii_digit).collect();
+
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(AmountViolation::Empty);
+        }
+
+        Ok((int_part, frac_part.to_string()))
+    }
+}
+
+// "1 234 567" but not "12 34" or "1 234 ".
+fn is_grouped(int_part: &str, group: char) -> bool {
+    if !int_part.contains(group) {
+        return true;
+    }
+
+    let mut groups = int_part.split(group);
+    let first = groups.next().unwrap_or_default().len();
+
+    (1..=3).contains(&first) && groups.all(|g| g.len() == 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::QA_PER_ZIL;
+
+    #[test]
+    fn test_validate_amount() {
+        let zil = AmountRules::zil();
+        let balance = 10 * QA_PER_ZIL;
+        let fee = QA_PER_ZIL;
+
+        assert_eq!(zil.validate(" 1.5 ", balance, fee), Ok(1_500_000_000_000));
+        assert_eq!(zil.validate(".5", balance, fee), Ok(QA_PER_ZIL / 2));
+        assert_eq!(zil.validate("9", balance, fee), Ok(9 * QA_PER_ZIL));
+        assert_eq!(
+            zil.validate("9.5", balance, fee),
+            Err(vec![AmountViolation::ExceedsBalance(9 * QA_PER_ZIL)])
+        );
+        assert_eq!(
+            zil.validate("1", fee - 1, fee),
+            Err(vec![AmountViolation::FeeExceedsBalance])
+        );
+        assert_eq!(
+            zil.validate("1e3", balance, fee),
+            Err(vec![AmountViolation::ExponentNotation])
+        );
+        assert_eq!(
+            zil.validate("-1", balance, fee),
+            Err(vec![AmountViolation::Negative])
+        );
+        assert_eq!(
+            zil.validate("1,5", balance, fee),
+            Err(vec![AmountViolation::InvalidCharacter(',')])
+        );
+        assert_eq!(
+            zil.validate("1.2.3", balance, fee),
+            Err(vec![AmountViolation::MisplacedSeparator('.')])
+        );
+        assert_eq!(
+            zil.validate(".", balance, fee),
+            Err(vec![AmountViolation::Empty])
+        );
+        assert_eq!(
+            zil.validate("0.0000000000001", balance, fee),
+            Err(vec![
+                AmountViolation::TooManyDecimals("ZIL".to_string(), 12),
+                AmountViolation::Zero,
+            ])
+        );
+
+        let token = AmountRules::new("ZLP", 18).with_locale(',', Some('.'));
+        let balance = u128::MAX;
+
+        assert_eq!(
+            token.validate("1.234,5", balance, 0),
+            Ok(12_345 * 10u128.pow(17))
+        );
+        assert_eq!(
+            token.validate("12.34", balance, 0),
+            Err(vec![AmountViolation::MisplacedSeparator('.')])
+        );
+        assert_eq!(
+            token.validate("1,000.5", balance, 0),
+            Err(vec![AmountViolation::MisplacedSeparator('.')])
+        );
+        assert_eq!(
+            token.validate("999999999999999999999", balance, 0),
+            Err(vec![AmountViolation::Overflow])
+        );
+    }
+}
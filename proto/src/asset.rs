@@ -0,0 +1,127 @@
+use crate::{address::Address, zil_tx::ZilAmount};
+use num256::uint256::Uint256;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AssetId {
+    Zil,            // native ZIL
+    Zrc2(Address),  // ZRC-2 token contract
+    Erc20(Address), // ERC-20 token contract
+}
+
+impl AssetId {
+    pub fn is_native(&self) -> bool {
+        matches!(self, AssetId::Zil)
+    }
+
+    pub fn contract(&self) -> Option<&Address> {
+        match self {
+            AssetId::Zil => None,
+            AssetId::Zrc2(addr) => Some(addr),
+            AssetId::Erc20(addr) => Some(addr),
+        }
+    }
+}
+
+/// An amount in the smallest units of an asset. For native ZIL that is Qa
+/// (10^-12 ZIL), the same unit [ZilAmount] uses in the Zilliqa API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TokenAmount(pub Uint256);
+
+impl TokenAmount {
+    pub fn from_u128(value: u128) -> Self {
+        Self(Uint256::from(value))
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        let bytes = self.0.to_be_bytes();
+        let (high, low) = bytes.split_at(bytes.len() - std::mem::size_of::<u128>());
+
+        if high.iter().any(|b| *b != 0) {
+            return None;
+        }
+
+        Some(u128::from_be_bytes(low.try_into().ok()?))
+    }
+}
+
+impl std::fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetAmount {
+    pub asset: AssetId,
+    pub amount: TokenAmount,
+}
+
+impl AssetAmount {
+    pub fn new(asset: AssetId, amount: TokenAmount) -> Self {
+        Self { asset, amount }
+    }
+
+    pub fn native(amount: ZilAmount) -> Self {
+        Self {
+            asset: AssetId::Zil,
+            amount: TokenAmount::from_u128(amount.raw()),
+        }
+    }
+
+    // None for tokens, or when the amount doesn't fit into a native transfer.
+    pub fn zil_amount(&self) -> Option<ZilAmount> {
+        if !self.asset.is_native() {
+            return None;
+        }
+
+        self.amount.to_u128().map(ZilAmount::from_raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::parse_zil;
+    use config::address::ADDR_LEN;
+
+    #[test]
+    fn test_native_roundtrip() {
+        let zil = parse_zil("1.5 ZIL").unwrap();
+        let asset_amount = AssetAmount::native(zil);
+
+        assert!(asset_amount.asset.is_native());
+        assert_eq!(asset_amount.asset.contract(), None);
+        assert_eq!(asset_amount.zil_amount(), Some(zil));
+    }
+
+    #[test]
+    fn test_token_is_not_native() {
+        let contract = Address::Secp256k1Sha256Zilliqa([1u8; ADDR_LEN]);
+        let asset_amount =
+            AssetAmount::new(AssetId::Zrc2(contract.clone()), TokenAmount::from_u128(42));
+
+        assert_eq!(asset_amount.asset.contract(), Some(&contract));
+        assert_eq!(asset_amount.zil_amount(), None);
+    }
+
+    #[test]
+    fn test_to_u128_overflow() {
+        let max = TokenAmount::from_u128(u128::MAX);
+        let overflow = TokenAmount(max.0 + Uint256::from(1u8));
+
+        assert_eq!(max.to_u128(), Some(u128::MAX));
+        assert_eq!(overflow.to_u128(), None);
+    }
+
+    #[test]
+    fn test_json() {
+        let contract = Address::Secp256k1Keccak256Ethereum([2u8; ADDR_LEN]);
+        let asset_amount = AssetAmount::new(AssetId::Erc20(contract), TokenAmount::from_u128(7));
+        let json = serde_json::to_string(&asset_amount).unwrap();
+        let restored: AssetAmount = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, asset_amount);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::units::{format_base_units, to_base_units};
+use serde::{Deserialize, Serialize};
+use zil_errors::units::UnitsErrors;
+
+pub const FIAT_DECIMALS: u32 = 2;
+
+/// Fiat value in cents, kept next to the crypto amount for reporting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FiatAmount {
+    pub currency: String, // lowercase code, e.g. "usd"
+    pub cents: u64,
+}
+
+impl FiatAmount {
+    // "50", "49.99"
+    pub fn parse(currency: &str, value: &str) -> Result<Self, UnitsErrors> {
+        let currency = currency.to_lowercase();
+        let cents = to_base_units(value.trim(), FIAT_DECIMALS, &currency)?;
+
+        Ok(Self {
+            currency,
+            cents: u64::try_from(cents).or(Err(UnitsErrors::Overflow))?,
+        })
+    }
+
+    pub fn value(&self) -> f64 {
+        self.cents as f64 / 10f64.powi(FIAT_DECIMALS as i32)
+    }
+}
+
+impl std::fmt::Display for FiatAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = format_base_units(self.cents.into(), FIAT_DECIMALS);
+
+        write!(f, "{} {}", value, self.currency.to_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let amount = FiatAmount::parse("USD", "49.99").unwrap();
+
+        assert_eq!(amount.cents, 4_999);
+        assert_eq!(amount.to_string(), "49.99 USD");
+        assert_eq!(
+            FiatAmount::parse("usd", "0.001"),
+            Err(UnitsErrors::TooManyDecimals("usd".to_string(), 2))
+        );
+    }
+}
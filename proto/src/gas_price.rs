@@ -0,0 +1,127 @@
+use crate::zil_tx::ZilAmount;
+use config::broadcast::{
+    DEFAULT_MIN_GAS_PRICE, GAS_PRICE_FLOORS, GAS_PRICE_MAX_FACTOR, GAS_PRICE_REFRESH_MAX_FACTOR,
+    GAS_PRICE_WARN_FACTOR,
+};
+use zil_errors::gas_price::GasPriceErrors;
+
+/// Plausible gas prices of one network, derived from its minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPriceBounds {
+    pub min: ZilAmount,
+    floor: ZilAmount, // embedded, caps what a node may report
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasPriceCheck {
+    Plausible,
+    High { factor: u128 }, // times the minimum, signed only after a warning
+}
+
+impl GasPriceBounds {
+    // The embedded floor, until `refresh` saw what the node reports.
+    pub fn for_chain(chain_id: u16) -> Self {
+        let min = GAS_PRICE_FLOORS
+            .iter()
+            .find(|(id, _)| *id == chain_id)
+            .map(|(_, min)| *min)
+            .unwrap_or(DEFAULT_MIN_GAS_PRICE);
+
+        Self {
+            min: ZilAmount::from_raw(min),
+            floor: ZilAmount::from_raw(min),
+        }
+    }
+
+    /// Takes the `GetMinimumGasPrice` result, a decimal string of raw units.
+    /// A node can't push the minimum, and with it `max`, past
+    /// `GAS_PRICE_REFRESH_MAX_FACTOR` times the embedded floor.
+    pub fn refresh(&mut self, min_gas_price: &str) -> Result<(), GasPriceErrors> {
+        let min: u128 = min_gas_price
+            .trim()
+            .parse()
+            .map_err(|_| GasPriceErrors::InvalidMinimum(min_gas_price.to_string()))?;
+
+        if min == 0 {
+            return Err(GasPriceErrors::InvalidMinimum(min_gas_price.to_string()));
+        }
+
+        self.min = ZilAmount::from_raw(
+            min.min(
+                self.floor
+                    .raw()
+                    .saturating_mul(GAS_PRICE_REFRESH_MAX_FACTOR),
+            ),
+        );
+
+        Ok(())
+    }
+
+    pub fn max(&self) -> ZilAmount {
+        ZilAmount::from_raw(self.min.raw().saturating_mul(GAS_PRICE_MAX_FACTOR))
+    }
+
+    /// Below the minimum the node rejects the tx anyway, above the maximum
+    /// it is most likely a typo that would burn the balance on fees.
+    pub fn check(&self, gas_price: ZilAmount) -> Result<GasPriceCheck, GasPriceErrors> {
+        let (price, min) = (gas_price.raw(), self.min.raw());
+
+        if price < min {
+            Err(GasPriceErrors::TooLow(price, min))
+        } else if price > self.max().raw() {
+            Err(GasPriceErrors::TooHigh(price, self.max().raw()))
+        } else if price >= min.saturating_mul(GAS_PRICE_WARN_FACTOR) {
+            Ok(GasPriceCheck::High {
+                factor: price / min,
+            })
+        } else {
+            Ok(GasPriceCheck::Plausible)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_price_bounds() {
+        let mut bounds = GasPriceBounds::for_chain(1);
+
+        assert_eq!(
+            bounds.check(ZilAmount::from_raw(2_000_000_000)),
+            Ok(GasPriceCheck::Plausible)
+        );
+        assert_eq!(
+            bounds.check(ZilAmount::from_raw(1)),
+            Err(GasPriceErrors::TooLow(1, 2_000_000_000))
+        );
+        assert_eq!(
+            bounds.check(ZilAmount::from_raw(40_000_000_000)),
+            Ok(GasPriceCheck::High { factor: 20 })
+        );
+        // 2000 ZIL per unit of gas instead of 0.002
+        assert_eq!(
+            bounds.check(ZilAmount::from_raw(2_000_000_000_000_000)),
+            Err(GasPriceErrors::TooHigh(
+                2_000_000_000_000_000,
+                2_000_000_000_000
+            ))
+        );
+
+        bounds.refresh("4000000000").unwrap();
+
+        assert!(bounds.check(ZilAmount::from_raw(2_000_000_000)).is_err());
+        assert_eq!(
+            bounds.refresh("0x10"),
+            Err(GasPriceErrors::InvalidMinimum("0x10".to_string()))
+        );
+        assert_eq!(bounds.min, ZilAmount::from_raw(4_000_000_000));
+
+        // a malicious node can't inflate the maximum without limit
+        bounds.refresh(&u128::MAX.to_string()).unwrap();
+
+        assert_eq!(bounds.min, ZilAmount::from_raw(200_000_000_000));
+        assert_eq!(bounds.max(), ZilAmount::from_raw(200_000_000_000_000));
+    }
+}
@@ -4,13 +4,21 @@ use crypto::bip49::Bip49DerivationPath;
 use crypto::schnorr;
 use k256::SecretKey as K256SecretKey;
 
-use crate::{address::Address, pubkey::PubKey, signature::Signature};
-
-use ethers::{core::k256::ecdsa::SigningKey, signers::LocalWallet, utils::hash_message};
+use crate::{
+    address::Address,
+    pubkey::{ed25519_to_pub_key, PubKey},
+    signature::Signature,
+};
+
+use ed25519_dalek::Signer;
+#[cfg(feature = "evm")]
+use ethers_core::{k256::ecdsa::SigningKey, utils::hash_message};
+#[cfg(feature = "evm")]
+use ethers_signers::LocalWallet;
 
 use super::secret_key::SecretKey;
-use rand::{RngCore, SeedableRng};
-use rand_chacha::ChaCha20Rng;
+use crypto::entropy;
+use rand::RngCore;
 use tiny_hderive::bip32::ExtendedPrivKey;
 use zil_errors::keypair::KeyPairError;
 
@@ -21,6 +29,7 @@ pub const KEYPAIR_BYTES_SIZE: usize = PUB_KEY_SIZE + SECRET_KEY_SIZE + 1;
 pub enum KeyPair {
     Secp256k1Sha256Zilliqa(([u8; PUB_KEY_SIZE], [u8; SECRET_KEY_SIZE])), // ZILLIQA
     Secp256k1Keccak256Ethereum(([u8; PUB_KEY_SIZE], [u8; SECRET_KEY_SIZE])), // Ethereum
+    Ed25519Solana(([u8; PUB_KEY_SIZE], [u8; SECRET_KEY_SIZE])),          // Ed25519
 }
 
 impl KeyPair {
@@ -36,8 +45,17 @@ impl KeyPair {
         Ok(Self::Secp256k1Keccak256Ethereum(keys))
     }
 
+    pub fn gen_ed25519() -> Result<Self, KeyPairError> {
+        let mut rng = entropy::rng();
+        let mut sk_bytes = [0u8; SECRET_KEY_SIZE];
+
+        rng.fill_bytes(&mut sk_bytes);
+
+        Ok(Self::Ed25519Solana((ed25519_pub_key(&sk_bytes), sk_bytes)))
+    }
+
     pub fn gen_keys_bytes() -> Result<([u8; PUB_KEY_SIZE], [u8; SECRET_KEY_SIZE]), KeyPairError> {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut sk_bytes = [0u8; SECRET_KEY_SIZE];
 
         rng.fill_bytes(&mut sk_bytes);
@@ -56,22 +74,16 @@ impl KeyPair {
     }
 
     pub fn from_secret_key(sk: &SecretKey) -> Result<Self, KeyPairError> {
-        let secret_key: K256SecretKey =
-            K256SecretKey::from_slice(sk.as_ref()).or(Err(KeyPairError::InvalidSecretKey))?;
-        let pub_key: [u8; PUB_KEY_SIZE] = secret_key
-            .public_key()
-            .to_sec1_bytes()
-            .to_vec()
-            .try_into()
-            .or(Err(KeyPairError::InvalidSecretKey))?;
-
         match sk {
-            SecretKey::Secp256k1Keccak256Ethereum(sk) => {
-                Ok(KeyPair::Secp256k1Keccak256Ethereum((pub_key, *sk)))
-            }
-            SecretKey::Secp256k1Sha256Zilliqa(sk) => {
-                Ok(KeyPair::Secp256k1Sha256Zilliqa((pub_key, *sk)))
-            }
+            SecretKey::Secp256k1Keccak256Ethereum(sk) => Ok(KeyPair::Secp256k1Keccak256Ethereum((
+                secp256k1_pub_key(sk)?,
+                *sk,
+            ))),
+            SecretKey::Secp256k1Sha256Zilliqa(sk) => Ok(KeyPair::Secp256k1Sha256Zilliqa((
+                secp256k1_pub_key(sk)?,
+                *sk,
+            ))),
+            SecretKey::Ed25519Solana(sk) => Ok(KeyPair::Ed25519Solana((ed25519_pub_key(sk), *sk))),
         }
     }
 
@@ -115,6 +127,7 @@ impl KeyPair {
             KeyPair::Secp256k1Keccak256Ethereum((_, sk)) => {
                 Ok(SecretKey::Secp256k1Keccak256Ethereum(*sk))
             }
+            KeyPair::Ed25519Solana((_, sk)) => Ok(SecretKey::Ed25519Solana(*sk)),
         }
     }
 
@@ -124,11 +137,13 @@ impl KeyPair {
             KeyPair::Secp256k1Keccak256Ethereum((pk, _)) => {
                 Ok(PubKey::Secp256k1Keccak256Ethereum(*pk))
             }
+            KeyPair::Ed25519Solana((pk, _)) => Ok(PubKey::Ed25519Solana(*pk)),
         }
     }
 
     pub fn sign_message(&self, msg: &[u8]) -> Result<Signature, KeyPairError> {
         match self {
+            #[cfg(feature = "evm")]
             KeyPair::Secp256k1Keccak256Ethereum((_, sk)) => {
                 let hash_msg = hash_message(msg);
                 let signing_key = SigningKey::from_slice(sk)
@@ -143,6 +158,8 @@ impl KeyPair {
 
                 Ok(sig)
             }
+            #[cfg(not(feature = "evm"))]
+            KeyPair::Secp256k1Keccak256Ethereum(_) => Err(KeyPairError::EvmDisabled),
             KeyPair::Secp256k1Sha256Zilliqa((_, sk)) => {
                 let secret_key =
                     K256SecretKey::from_slice(sk).or(Err(KeyPairError::InvalidSecretKey))?;
@@ -153,6 +170,12 @@ impl KeyPair {
 
                 Ok(sig)
             }
+            KeyPair::Ed25519Solana((_, sk)) => {
+                let signing_key = ed25519_dalek::SigningKey::from_bytes(sk);
+                let sig = signing_key.sign(msg);
+
+                Ok(Signature::Ed25519(sig.to_bytes()))
+            }
         }
     }
 
@@ -170,6 +193,22 @@ impl KeyPair {
     }
 }
 
+fn secp256k1_pub_key(sk: &[u8; SECRET_KEY_SIZE]) -> Result<[u8; PUB_KEY_SIZE], KeyPairError> {
+    K256SecretKey::from_slice(sk)
+        .or(Err(KeyPairError::InvalidSecretKey))?
+        .public_key()
+        .to_sec1_bytes()
+        .to_vec()
+        .try_into()
+        .or(Err(KeyPairError::InvalidSecretKey))
+}
+
+fn ed25519_pub_key(sk: &[u8; SECRET_KEY_SIZE]) -> [u8; PUB_KEY_SIZE] {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(sk);
+
+    ed25519_to_pub_key(signing_key.verifying_key().as_bytes())
+}
+
 impl ToBytes<{ KEYPAIR_BYTES_SIZE }> for KeyPair {
     type Error = KeyPairError;
     fn to_bytes(&self) -> Result<[u8; KEYPAIR_BYTES_SIZE], Self::Error> {
@@ -186,6 +225,11 @@ impl ToBytes<{ KEYPAIR_BYTES_SIZE }> for KeyPair {
                 result[1..PUB_KEY_SIZE + 1].copy_from_slice(pk);
                 result[PUB_KEY_SIZE + 1..].copy_from_slice(sk);
             }
+            KeyPair::Ed25519Solana((pk, sk)) => {
+                result[0] = 3;
+                result[1..PUB_KEY_SIZE + 1].copy_from_slice(pk);
+                result[PUB_KEY_SIZE + 1..].copy_from_slice(sk);
+            }
         };
 
         Ok(result)
@@ -211,6 +255,7 @@ impl FromBytes for KeyPair {
         match key_type {
             0 => Ok(KeyPair::Secp256k1Sha256Zilliqa((pk, sk))),
             1 => Ok(KeyPair::Secp256k1Keccak256Ethereum((pk, sk))),
+            3 => Ok(KeyPair::Ed25519Solana((pk, sk))),
             _ => Err(KeyPairError::InvalidKeyType),
         }
     }
@@ -297,10 +342,7 @@ mod tests {
 
     #[test]
     fn test_sign_message() {
-        use rand::{RngCore, SeedableRng};
-        use rand_chacha::ChaCha20Rng;
-
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
 
         for _ in 0..10 {
             let key_pair = KeyPair::gen_sha256().unwrap();
@@ -315,6 +357,7 @@ mod tests {
             assert!(verify.unwrap());
         }
 
+        #[cfg(feature = "evm")]
         for _ in 0..10 {
             let key_pair = KeyPair::gen_keccak256().unwrap();
             let mut message_bytes = [0u8; 100];
@@ -328,6 +371,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ed25519() {
+        // RFC 8032 test vector 1
+        let sk: [u8; SECRET_KEY_SIZE] =
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let key_pair = KeyPair::from_secret_key(&SecretKey::Ed25519Solana(sk)).unwrap();
+        let pk = key_pair.get_pubkey().unwrap();
+
+        assert_eq!(
+            hex::encode(&pk.as_ref()[1..]),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        assert!(key_pair.get_addr().is_err());
+
+        let sig = key_pair.sign_message(b"").unwrap();
+
+        assert!(key_pair.verify_sig(b"", &sig).unwrap());
+        assert!(!key_pair.verify_sig(b"tampered", &sig).unwrap());
+
+        let other = KeyPair::gen_sha256().unwrap();
+
+        assert!(other.verify_sig(b"", &sig).is_err());
+
+        let bytes = key_pair.to_bytes().unwrap();
+
+        assert_eq!(KeyPair::from_bytes(bytes[..].into()).unwrap(), key_pair);
+        assert_eq!(
+            key_pair.get_secretkey().unwrap(),
+            SecretKey::Ed25519Solana(sk)
+        );
+    }
+
     #[test]
     fn from_to_bytes() {
         use crate::keypair::KeyPair;
@@ -1,14 +1,32 @@
+#[allow(clippy::large_enum_variant)]
 pub mod zilliqa_proto {
     include!("zilliqa_message.rs");
 }
 
 pub mod address;
+pub mod address_format;
+pub mod amount_input;
+pub mod asset;
 pub mod btc_addr;
+pub mod fiat;
+pub mod gas_price;
 pub mod keypair;
+pub mod portfolio;
 pub mod pubkey;
 pub mod secret_key;
 pub mod signature;
+pub mod signer;
+pub mod siwz;
+pub mod statement;
+#[cfg(feature = "evm")]
+pub mod swap;
+pub mod token_meta;
 pub mod tx;
+pub mod units;
+#[cfg(feature = "evm")]
+pub mod user_op;
+pub mod xpub;
 pub mod zil_address;
 pub mod zil_tx;
 pub mod zq1_proto;
+pub mod zrc2;
@@ -0,0 +1,158 @@
+use crate::{
+    asset::{AssetId, TokenAmount},
+    zil_tx::ZilAmount,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HoldingCategory {
+    Liquid,
+    Staked,
+    PendingWithdrawal,
+    UnclaimedRewards,
+}
+
+impl HoldingCategory {
+    pub const ALL: [HoldingCategory; 4] = [
+        HoldingCategory::Liquid,
+        HoldingCategory::Staked,
+        HoldingCategory::PendingWithdrawal,
+        HoldingCategory::UnclaimedRewards,
+    ];
+}
+
+/// Stake of one delegator summed over all SSNs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeSummary {
+    pub staked: u128,
+    pub pending_withdrawals: u128,
+    pub unclaimed_rewards: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Holding {
+    pub asset: AssetId,
+    pub category: HoldingCategory,
+    pub amount: TokenAmount,
+}
+
+/// Balances split by category, so staked and not yet withdrawn ZIL is
+/// counted in totals but still shown apart from the spendable balance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub holdings: Vec<Holding>,
+}
+
+impl Portfolio {
+    pub fn add(&mut self, asset: AssetId, category: HoldingCategory, amount: TokenAmount) {
+        match self
+            .holdings
+            .iter_mut()
+            .find(|h| h.asset == asset && h.category == category)
+        {
+            Some(holding) => holding.amount = TokenAmount(holding.amount.0 + amount.0),
+            None => self.holdings.push(Holding {
+                asset,
+                category,
+                amount,
+            }),
+        }
+    }
+
+    pub fn add_native(&mut self, amount: ZilAmount) {
+        self.add(
+            AssetId::Zil,
+            HoldingCategory::Liquid,
+            TokenAmount::from_u128(amount.raw()),
+        );
+    }
+
+    pub fn add_stake(&mut self, stake: &StakeSummary) {
+        let parts = [
+            (HoldingCategory::Staked, stake.staked),
+            (
+                HoldingCategory::PendingWithdrawal,
+                stake.pending_withdrawals,
+            ),
+            (HoldingCategory::UnclaimedRewards, stake.unclaimed_rewards),
+        ];
+
+        for (category, amount) in parts {
+            if amount != 0 {
+                self.add(AssetId::Zil, category, TokenAmount::from_u128(amount));
+            }
+        }
+    }
+
+    pub fn category_total(&self, asset: &AssetId, category: HoldingCategory) -> TokenAmount {
+        self.sum(|h| &h.asset == asset && h.category == category)
+    }
+
+    pub fn total(&self, asset: &AssetId) -> TokenAmount {
+        self.sum(|h| &h.asset == asset)
+    }
+
+    /// Fiat value of every holding, `price` gives the value of one whole
+    /// token and its decimals, assets without a price are skipped.
+    pub fn value(&self, price: impl Fn(&AssetId) -> Option<(f64, u8)>) -> f64 {
+        self.holdings
+            .iter()
+            .filter_map(|h| {
+                let (price, decimals) = price(&h.asset)?;
+                let amount: f64 = h.amount.to_string().parse().ok()?;
+
+                Some(amount / 10f64.powi(decimals as i32) * price)
+            })
+            .sum()
+    }
+
+    fn sum(&self, filter: impl Fn(&Holding) -> bool) -> TokenAmount {
+        self.holdings
+            .iter()
+            .filter(|h| filter(h))
+            .fold(TokenAmount::default(), |acc, h| {
+                TokenAmount(acc.0 + h.amount.0)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::QA_PER_ZIL;
+
+    #[test]
+    fn test_stake_in_totals() {
+        let mut portfolio = Portfolio::default();
+
+        portfolio.add_native(ZilAmount::from_raw(10 * QA_PER_ZIL));
+        portfolio.add_stake(&StakeSummary {
+            staked: 100 * QA_PER_ZIL,
+            pending_withdrawals: 5 * QA_PER_ZIL,
+            unclaimed_rewards: 0,
+        });
+        portfolio.add_stake(&StakeSummary {
+            staked: 0,
+            pending_withdrawals: 0,
+            unclaimed_rewards: QA_PER_ZIL,
+        });
+
+        assert_eq!(portfolio.holdings.len(), 4);
+        assert_eq!(
+            portfolio.total(&AssetId::Zil),
+            TokenAmount::from_u128(116 * QA_PER_ZIL)
+        );
+        assert_eq!(
+            portfolio.category_total(&AssetId::Zil, HoldingCategory::Liquid),
+            TokenAmount::from_u128(10 * QA_PER_ZIL)
+        );
+        assert_eq!(
+            portfolio.category_total(&AssetId::Zil, HoldingCategory::UnclaimedRewards),
+            TokenAmount::from_u128(QA_PER_ZIL)
+        );
+
+        let value = portfolio.value(|asset| asset.is_native().then_some((0.5, 12)));
+
+        assert!((value - 58.0).abs() < f64::EPSILON);
+    }
+}
@@ -1,10 +1,10 @@
 use bincode::ToBytes;
 use config::address::ADDR_LEN;
-use config::key::PUB_KEY_SIZE;
-use ethers::core::k256::ecdsa::VerifyingKey;
-use ethers::utils::public_key_to_address;
+use config::key::{ED25519_PUB_KEY_SIZE, PUB_KEY_SIZE};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
 use k256::PublicKey as K256PublicKey;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{Digest, Keccak256};
 use std::str::FromStr;
 use zil_errors::keypair::PubKeyError;
 
@@ -19,15 +19,38 @@ pub enum PubKey {
     Ed25519Solana([u8; PUB_KEY_SIZE]),              // Solana
 }
 
+// Ed25519 keys are 32 bytes, stored after a zero byte to fit PUB_KEY_SIZE.
+pub fn ed25519_to_pub_key(pk: &[u8; ED25519_PUB_KEY_SIZE]) -> [u8; PUB_KEY_SIZE] {
+    let mut result = [0u8; PUB_KEY_SIZE];
+
+    result[1..].copy_from_slice(pk);
+
+    result
+}
+
+// Last 20 bytes of Keccak-256 over the uncompressed point, tag byte dropped.
+pub(crate) fn keccak256_addr(pk: &[u8; PUB_KEY_SIZE]) -> Option<[u8; ADDR_LEN]> {
+    let point = K256PublicKey::from_sec1_bytes(pk)
+        .ok()?
+        .to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+
+    hash[hash.len() - ADDR_LEN..].try_into().ok()
+}
+
+pub fn pub_key_to_ed25519(pk: &[u8; PUB_KEY_SIZE]) -> [u8; ED25519_PUB_KEY_SIZE] {
+    let mut result = [0u8; ED25519_PUB_KEY_SIZE];
+
+    result.copy_from_slice(&pk[1..]);
+
+    result
+}
+
 impl PubKey {
     pub fn get_bytes_addr(&self) -> Result<[u8; ADDR_LEN], PubKeyError> {
         match self {
             PubKey::Secp256k1Keccak256Ethereum(pk) => {
-                let public_key =
-                    VerifyingKey::from_sec1_bytes(pk).or(Err(PubKeyError::InvalidVerifyingKey))?;
-                let addr = public_key_to_address(&public_key);
-
-                Ok(addr.into())
+                keccak256_addr(pk).ok_or(PubKeyError::InvalidVerifyingKey)
             }
             PubKey::Secp256k1Sha256Zilliqa(pk) => {
                 from_zil_pub_key(pk).or(Err(PubKeyError::InvalidPubKey))
@@ -8,6 +8,7 @@ use zil_errors::keypair::SecretKeyError;
 pub enum SecretKey {
     Secp256k1Sha256Zilliqa([u8; SECRET_KEY_SIZE]), // ZILLIQA
     Secp256k1Keccak256Ethereum([u8; SECRET_KEY_SIZE]), // Ethereum
+    Ed25519Solana([u8; SECRET_KEY_SIZE]),          // Ed25519 seed
 }
 
 impl SecretKey {
@@ -15,6 +16,7 @@ impl SecretKey {
         match self {
             SecretKey::Secp256k1Sha256Zilliqa(buf) => buf.to_vec(),
             SecretKey::Secp256k1Keccak256Ethereum(buf) => buf.to_vec(),
+            SecretKey::Ed25519Solana(buf) => buf.to_vec(),
         }
     }
 }
@@ -33,6 +35,7 @@ impl ToBytes<{ SECRET_KEY_SIZE + 1 }> for SecretKey {
         result[0] = match self {
             SecretKey::Secp256k1Sha256Zilliqa(_) => 0,
             SecretKey::Secp256k1Keccak256Ethereum(_) => 1,
+            SecretKey::Ed25519Solana(_) => 3, // same code as PubKey::Ed25519Solana
         };
         result[1..].copy_from_slice(self.as_ref());
 
@@ -61,6 +64,7 @@ impl FromBytes for SecretKey {
         match key_type {
             0 => Ok(SecretKey::Secp256k1Sha256Zilliqa(key_data)),
             1 => Ok(SecretKey::Secp256k1Keccak256Ethereum(key_data)),
+            3 => Ok(SecretKey::Ed25519Solana(key_data)),
             _ => panic!("Invalid key type"),
         }
     }
@@ -71,6 +75,7 @@ impl AsRef<[u8]> for SecretKey {
         match self {
             SecretKey::Secp256k1Sha256Zilliqa(data) => data,
             SecretKey::Secp256k1Keccak256Ethereum(data) => data,
+            SecretKey::Ed25519Solana(data) => data,
         }
     }
 }
@@ -88,6 +93,7 @@ impl FromStr for SecretKey {
         match prefix {
             0 => Ok(SecretKey::Secp256k1Sha256Zilliqa(bytes)),
             1 => Ok(SecretKey::Secp256k1Keccak256Ethereum(bytes)),
+            3 => Ok(SecretKey::Ed25519Solana(bytes)),
             _ => Err(SecretKeyError::InvalidKeyType),
         }
     }
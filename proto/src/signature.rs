@@ -1,18 +1,24 @@
+use config::key::ED25519_SIGNATURE_SIZE;
 use config::sha::{ECDSAS_ECP256K1_KECCAK256_SIZE, SHA512_SIZE};
 use crypto::schnorr;
-use ethers::core::types::Signature as EthersSignature;
-use ethers::types::H160;
-use ethers::utils::hash_message;
+#[cfg(feature = "evm")]
+use ethers_core::types::Signature as EthersSignature;
+#[cfg(feature = "evm")]
+use ethers_core::types::H160;
+#[cfg(feature = "evm")]
+use ethers_core::utils::hash_message;
 use k256::ecdsa::Signature as SchnorrSignature;
 use k256::PublicKey as K256PublicKey;
 use zil_errors::crypto::SignatureError;
+use zil_errors::keypair::PubKeyError;
 
-use crate::pubkey::PubKey;
+use crate::pubkey::{pub_key_to_ed25519, PubKey};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Signature {
     SchnorrSecp256k1Sha256([u8; SHA512_SIZE]), // Zilliqa
     ECDSASecp256k1Keccak256([u8; ECDSAS_ECP256K1_KECCAK256_SIZE]), // Ethereum
+    Ed25519([u8; ED25519_SIGNATURE_SIZE]),     // Ed25519
 }
 
 impl Signature {
@@ -26,6 +32,7 @@ impl Signature {
 
                 Ok(verify.is_some())
             }
+            #[cfg(feature = "evm")]
             Signature::ECDSASecp256k1Keccak256(sig) => {
                 let message_hash = hash_message(msg_bytes);
                 let sig = EthersSignature::try_from(&sig[..])
@@ -41,10 +48,25 @@ impl Signature {
 
                 Ok(recovered_address == signer_address)
             }
+            #[cfg(not(feature = "evm"))]
+            Signature::ECDSASecp256k1Keccak256(_) => Err(SignatureError::EvmDisabled),
+            Signature::Ed25519(sig) => {
+                let pk = match pk {
+                    PubKey::Ed25519Solana(pk) => pub_key_to_ed25519(pk),
+                    _ => return Err(SignatureError::FailIntoPubKey(PubKeyError::InvalidKeyType)),
+                };
+                let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(&pk).or(Err(
+                    SignatureError::FailIntoPubKey(PubKeyError::InvalidPubKey),
+                ))?;
+                let sig = ed25519_dalek::Signature::from_bytes(sig);
+
+                Ok(verifying_key.verify_strict(msg_bytes, &sig).is_ok())
+            }
         }
     }
 }
 
+#[cfg(feature = "evm")]
 impl TryFrom<EthersSignature> for Signature {
     type Error = SignatureError;
 
@@ -0,0 +1,19 @@
+use crate::{keypair::KeyPair, pubkey::PubKey, signature::Signature};
+use zil_errors::keypair::KeyPairError;
+
+/// Signs on behalf of one account, a local key pair or e.g. a hardware
+/// device that never exposes the key.
+pub trait Signer {
+    fn pub_key(&self) -> Result<PubKey, KeyPairError>;
+    fn sign_message(&self, msg: &[u8]) -> Result<Signature, KeyPairError>;
+}
+
+impl Signer for KeyPair {
+    fn pub_key(&self) -> Result<PubKey, KeyPairError> {
+        self.get_pubkey()
+    }
+
+    fn sign_message(&self, msg: &[u8]) -> Result<Signature, KeyPairError> {
+        KeyPair::sign_message(self, msg)
+    }
+}
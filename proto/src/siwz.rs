@@ -0,0 +1,438 @@
+use crate::{
+    address::Address, pubkey::PubKey, signature::Signature, zil_address::from_zil_bech32_address,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use config::address::ADDR_LEN;
+use std::{fmt, str::FromStr};
+use url::Url;
+use zil_errors::siwz::SiwzErrors;
+
+pub const SIWZ_VERSION: &str = "1";
+pub const MIN_NONCE_LEN: usize = 8;
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Zilliqa account:";
+
+/// Sign-in message in the CAIP-122 / EIP-4361 layout:
+///
+/// ```text
+/// {domain} wants you to sign in with your Zilliqa account:
+/// {address}
+///
+/// {statement}
+///
+/// URI: {uri}
+/// Version: 1
+/// Chain ID: {chain_id}
+/// Nonce: {nonce}
+/// Issued At: {issued_at}
+/// Expiration Time: {expiration_time}
+/// Not Before: {not_before}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiwzMessage {
+    pub domain: String,
+    pub address: Address,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u16,
+    pub nonce: String,
+    pub issued_at: DateTime<Utc>,
+    pub expiration_time: Option<DateTime<Utc>>,
+    pub not_before: Option<DateTime<Utc>>,
+}
+
+pub struct SiwzBuilder {
+    domain: String,
+    address: Address,
+    uri: String,
+    chain_id: u16,
+    statement: Option<String>,
+    nonce: Option<String>,
+    issued_at: Option<DateTime<Utc>>,
+    expiration_time: Option<DateTime<Utc>>,
+    not_before: Option<DateTime<Utc>>,
+}
+
+impl SiwzBuilder {
+    pub fn new(domain: &str, address: Address, uri: &str, chain_id: u16) -> Self {
+        Self {
+            domain: domain.to_string(),
+            address,
+            uri: uri.to_string(),
+            chain_id,
+            statement: None,
+            nonce: None,
+            issued_at: None,
+            expiration_time: None,
+            not_before: None,
+        }
+    }
+
+    pub fn statement(mut self, statement: &str) -> Self {
+        self.statement = Some(statement.to_string());
+        self
+    }
+
+    pub fn nonce(mut self, nonce: &str) -> Self {
+        self.nonce = Some(nonce.to_string());
+        self
+    }
+
+    pub fn issued_at(mut self, issued_at: DateTime<Utc>) -> Self {
+        self.issued_at = Some(issued_at);
+        self
+    }
+
+    pub fn expiration_time(mut self, expiration_time: DateTime<Utc>) -> Self {
+        self.expiration_time = Some(expiration_time);
+        self
+    }
+
+    pub fn not_before(mut self, not_before: DateTime<Utc>) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    pub fn build(self) -> Result<SiwzMessage, SiwzErrors> {
+        let nonce = self.nonce.ok_or(SiwzErrors::MissingField("nonce"))?;
+
+        validate_nonce(&nonce)?;
+
+        if self.domain.is_empty() || self.domain.contains(char::is_whitespace) {
+            return Err(SiwzErrors::InvalidField("domain", self.domain));
+        }
+
+        if let Some(statement) = &self.statement {
+            // a newline would let a dApp spoof the fields below the statement
+            if statement.contains(char::is_control) {
+                return Err(SiwzErrors::InvalidField("statement", statement.clone()));
+            }
+        }
+
+        validate_uri(&self.uri)?;
+
+        Ok(SiwzMessage {
+            domain: self.domain,
+            address: self.address,
+            statement: self.statement,
+            uri: self.uri,
+            version: SIWZ_VERSION.to_string(),
+            chain_id: self.chain_id,
+            nonce,
+            issued_at: self.issued_at.unwrap_or_else(Utc::now),
+            expiration_time: self.expiration_time,
+            not_before: self.not_before,
+        })
+    }
+}
+
+impl SiwzMessage {
+    /// Checks the message was made for `domain` with the `nonce` the dApp
+    /// handed out, is inside its validity window and is signed by `pub_key`.
+    pub fn verify(
+        &self,
+        sig: &Signature,
+        pub_key: &PubKey,
+        domain: &str,
+        nonce: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), SiwzErrors> {
+        if self.domain != domain {
+            return Err(SiwzErrors::DomainMismatch(domain.to_string()));
+        }
+
+        if self.nonce != nonce {
+            return Err(SiwzErrors::NonceMismatch);
+        }
+
+        if self.expiration_time.is_some_and(|exp| now >= exp) {
+            return Err(SiwzErrors::Expired);
+        }
+
+        if self.not_before.is_some_and(|nbf| now < nbf) {
+            return Err(SiwzErrors::NotYetValid);
+        }
+
+        let signer = Address::from_pubkey(pub_key).map_err(SiwzErrors::InvalidAddress)?;
+
+        if signer != self.address {
+            return Err(SiwzErrors::AddressMismatch);
+        }
+
+        let is_valid = sig
+            .verify(self.to_string().as_bytes(), pub_key)
+            .map_err(SiwzErrors::FailToVerify)?;
+
+        if !is_valid {
+            return Err(SiwzErrors::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SiwzMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}{}", self.domain, HEADER_SUFFIX)?;
+        writeln!(f, "{}", self.address)?;
+        writeln!(f)?;
+
+        if let Some(statement) = &self.statement {
+            writeln!(f, "{}", statement)?;
+            writeln!(f)?;
+        }
+
+        writeln!(f, "URI: {}", self.uri)?;
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Chain ID: {}", self.chain_id)?;
+        writeln!(f, "Nonce: {}", self.nonce)?;
+        write!(f, "Issued At: {}", format_time(&self.issued_at))?;
+
+        if let Some(exp) = &self.expiration_time {
+            write!(f, "\nExpiration Time: {}", format_time(exp))?;
+        }
+
+        if let Some(nbf) = &self.not_before {
+            write!(f, "\nNot Before: {}", format_time(nbf))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SiwzMessage {
+    type Err = SiwzErrors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let domain = lines
+            .next()
+            .and_then(|l| l.strip_suffix(HEADER_SUFFIX))
+            .ok_or(SiwzErrors::MissingField("domain"))?;
+        let address = lines
+            .next()
+            .ok_or(SiwzErrors::MissingField("address"))
+            .and_then(parse_address)?;
+
+        if lines.next() != Some("") {
+            return Err(SiwzErrors::MissingField("address"));
+        }
+
+        let mut statement = None;
+        let mut line = lines.next().ok_or(SiwzErrors::MissingField("uri"))?;
+
+        if !line.starts_with("URI: ") {
+            statement = Some(line.to_string());
+
+            if lines.next() != Some("") {
+                return Err(SiwzErrors::InvalidField("statement", line.to_string()));
+            }
+
+            line = lines.next().ok_or(SiwzErrors::MissingField("uri"))?;
+        }
+
+        let uri = field(Some(line), "URI", "uri")?;
+        let version = field(lines.next(), "Version", "version")?;
+        let chain_id = field(lines.next(), "Chain ID", "chain_id")?;
+        let chain_id = chain_id.parse().or(Err(SiwzErrors::InvalidField(
+            "chain_id",
+            chain_id.to_string(),
+        )))?;
+        let nonce = field(lines.next(), "Nonce", "nonce")?;
+        let issued_at = parse_time(field(lines.next(), "Issued At", "issued_at")?, "issued_at")?;
+        let mut expiration_time = None;
+        let mut not_before = None;
+
+        for line in lines {
+            if let Some(v) = line.strip_prefix("Expiration Time: ") {
+                expiration_time = Some(parse_time(v, "expiration_time")?);
+            } else if let Some(v) = line.strip_prefix("Not Before: ") {
+                not_before = Some(parse_time(v, "not_before")?);
+            } else {
+                return Err(SiwzErrors::InvalidField("line", line.to_string()));
+            }
+        }
+
+        if version != SIWZ_VERSION {
+            return Err(SiwzErrors::InvalidField("version", version.to_string()));
+        }
+
+        validate_nonce(nonce)?;
+        validate_uri(uri)?;
+
+        Ok(Self {
+            domain: domain.to_string(),
+            address,
+            statement,
+            uri: uri.to_string(),
+            version: version.to_string(),
+            chain_id,
+            nonce: nonce.to_string(),
+            issued_at,
+            expiration_time,
+            not_before,
+        })
+    }
+}
+
+fn validate_nonce(nonce: &str) -> Result<(), SiwzErrors> {
+    if nonce.len() < MIN_NONCE_LEN || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(SiwzErrors::InvalidNonce);
+    }
+
+    Ok(())
+}
+
+// An absolute RFC 3986 URI on one line.
+fn validate_uri(uri: &str) -> Result<(), SiwzErrors> {
+    if uri.contains(|c: char| c.is_control() || c.is_whitespace()) || Url::parse(uri).is_err() {
+        return Err(SiwzErrors::InvalidField("uri", uri.to_string()));
+    }
+
+    Ok(())
+}
+
+fn field<'a>(line: Option<&'a str>, tag: &str, name: &'static str) -> Result<&'a str, SiwzErrors> {
+    line.and_then(|l| l.strip_prefix(tag))
+        .and_then(|l| l.strip_prefix(": "))
+        .ok_or(SiwzErrors::MissingField(name))
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_time(value: &str, name: &'static str) -> Result<DateTime<Utc>, SiwzErrors> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .or(Err(SiwzErrors::InvalidField(name, value.to_string())))
+}
+
+// Accepts the forms Address is displayed in: bech32 for Zilliqa, 0x hex for EVM.
+fn parse_address(value: &str) -> Result<Address, SiwzErrors> {
+    if let Some(hex_addr) = value.strip_prefix("0x") {
+        let bytes: [u8; ADDR_LEN] = hex::decode(hex_addr)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or(SiwzErrors::InvalidField("address", value.to_string()))?;
+
+        return Ok(Address::Secp256k1Keccak256Ethereum(bytes));
+    }
+
+    from_zil_bech32_address(value)
+        .map(Address::Secp256k1Sha256Zilliqa)
+        .map_err(SiwzErrors::InvalidAddress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keypair::KeyPair;
+    use chrono::Duration;
+
+    const DOMAIN: &str = "dapp.example";
+    const NONCE: &str = "32891756ab";
+
+    fn message(key_pair: &KeyPair) -> SiwzMessage {
+        let issued_at = DateTime::parse_from_rfc3339("2024-08-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        SiwzBuilder::new(
+            DOMAIN,
+            key_pair.get_addr().unwrap(),
+            "https://dapp.example/login",
+            1,
+        )
+        .statement("Sign in to dapp.example")
+        .nonce(NONCE)
+        .issued_at(issued_at)
+        .expiration_time(issued_at + Duration::minutes(10))
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for key_pair in [
+            KeyPair::gen_sha256().unwrap(),
+            KeyPair::gen_keccak256().unwrap(),
+        ] {
+            let msg = message(&key_pair);
+            let text = msg.to_string();
+
+            assert!(text.starts_with("dapp.example wants you to sign in"));
+            assert_eq!(SiwzMessage::from_str(&text).unwrap(), msg);
+        }
+
+        let key_pair = KeyPair::gen_sha256().unwrap();
+        let mut msg = message(&key_pair);
+
+        msg.statement = None;
+        msg.expiration_time = None;
+
+        assert_eq!(SiwzMessage::from_str(&msg.to_string()).unwrap(), msg);
+    }
+
+    #[test]
+    fn test_verify() {
+        let key_pair = KeyPair::gen_sha256().unwrap();
+        let pub_key = key_pair.get_pubkey().unwrap();
+        let msg = message(&key_pair);
+        let sig = key_pair.sign_message(msg.to_string().as_bytes()).unwrap();
+        let now = msg.issued_at + Duration::minutes(1);
+
+        assert_eq!(msg.verify(&sig, &pub_key, DOMAIN, NONCE, now), Ok(()));
+        assert_eq!(
+            msg.verify(&sig, &pub_key, "evil.example", NONCE, now),
+            Err(SiwzErrors::DomainMismatch("evil.example".to_string()))
+        );
+        assert_eq!(
+            msg.verify(&sig, &pub_key, DOMAIN, "otherNonce1", now),
+            Err(SiwzErrors::NonceMismatch)
+        );
+        assert_eq!(
+            msg.verify(&sig, &pub_key, DOMAIN, NONCE, now + Duration::minutes(10)),
+            Err(SiwzErrors::Expired)
+        );
+
+        let other = KeyPair::gen_sha256().unwrap();
+
+        assert_eq!(
+            msg.verify(&sig, &other.get_pubkey().unwrap(), DOMAIN, NONCE, now),
+            Err(SiwzErrors::AddressMismatch)
+        );
+    }
+
+    #[test]
+    fn test_builder_rejects() {
+        let addr = KeyPair::gen_sha256().unwrap().get_addr().unwrap();
+        let builder = || SiwzBuilder::new(DOMAIN, addr.clone(), "https://dapp.example", 1);
+
+        assert_eq!(builder().build(), Err(SiwzErrors::MissingField("nonce")));
+        assert_eq!(
+            builder().nonce("short").build(),
+            Err(SiwzErrors::InvalidNonce)
+        );
+        assert!(builder()
+            .nonce(NONCE)
+            .statement("hi\nURI: https://evil.example")
+            .build()
+            .is_err());
+
+        for uri in [
+            "https://dapp.example\nChain ID: 2",
+            "https://dapp.example\rNonce: x",
+            "dapp.example",
+            "",
+        ] {
+            assert_eq!(
+                SiwzBuilder::new(DOMAIN, addr.clone(), uri, 1)
+                    .nonce(NONCE)
+                    .build(),
+                Err(SiwzErrors::InvalidField("uri", uri.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    address::Address,
+    asset::{AssetAmount, AssetId},
+    keypair::KeyPair,
+    pubkey::PubKey,
+    signature::Signature,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zil_errors::statement::StatementErrors;
+
+const HEADER: &str = "ZilPay balance statement";
+
+/// Proof of funds: balances and ownership of an address at a block height.
+/// The signed bytes are the text form, so a verifier reads exactly what was
+/// signed:
+///
+/// ```text
+/// ZilPay balance statement
+/// Address: {address}
+/// Chain ID: {chain_id}
+/// Block: {block_height}
+/// Issued At: {issued_at}
+/// Purpose: {purpose}
+/// Balance: {asset} {amount}
+/// ```
+///
+/// One `Balance` line per asset, amounts in base units, `Purpose` optional.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceStatement {
+    pub address: Address,
+    pub chain_id: u16,
+    pub block_height: u64,
+    pub issued_at: u64, // unix seconds
+    pub purpose: Option<String>,
+    pub balances: Vec<AssetAmount>,
+}
+
+/// What is handed to an exchange or auditor, JSON friendly.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedStatement {
+    pub statement: BalanceStatement,
+    pub pub_key: PubKey,
+    pub signature: String, // hex
+}
+
+impl BalanceStatement {
+    pub fn sign(self, key_pair: &KeyPair) -> Result<SignedStatement, StatementErrors> {
+        if self.balances.is_empty() {
+            return Err(StatementErrors::NoBalances);
+        }
+
+        let signer = key_pair.get_addr().map_err(StatementErrors::FailToSign)?;
+
+        if signer != self.address {
+            return Err(StatementErrors::AddressMismatch);
+        }
+
+        let signature = key_pair
+            .sign_message(self.to_string().as_bytes())
+            .map_err(StatementErrors::FailToSign)?;
+
+        Ok(SignedStatement {
+            pub_key: key_pair.get_pubkey().map_err(StatementErrors::FailToSign)?,
+            signature: hex::encode(signature_bytes(&signature)),
+            statement: self,
+        })
+    }
+}
+
+impl SignedStatement {
+    // Anyone can run this, only the statement and the public key are needed.
+    pub fn verify(&self) -> Result<(), StatementErrors> {
+        let signer =
+            Address::from_pubkey(&self.pub_key).map_err(StatementErrors::InvalidAddress)?;
+
+        if signer != self.statement.address {
+            return Err(StatementErrors::AddressMismatch);
+        }
+
+        let bytes = hex::decode(&self.signature).or(Err(StatementErrors::MalformedSignature))?;
+        let signature = signature_for(&self.pub_key, &bytes)?;
+        let is_valid = signature
+            .verify(self.statement.to_string().as_bytes(), &self.pub_key)
+            .map_err(StatementErrors::FailToVerify)?;
+
+        if !is_valid {
+            return Err(StatementErrors::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for BalanceStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "Address: {}", self.address)?;
+        writeln!(f, "Chain ID: {}", self.chain_id)?;
+        writeln!(f, "Block: {}", self.block_height)?;
+        write!(f, "Issued At: {}", self.issued_at)?;
+
+        if let Some(purpose) = &self.purpose {
+            // one line, a newline would let it pose as a balance
+            write!(f, "\nPurpose: {}", purpose.replace(['\n', '\r'], " "))?;
+        }
+
+        for balance in &self.balances {
+            let asset = match &balance.asset {
+                AssetId::Zil => "ZIL".to_string(),
+                AssetId::Zrc2(addr) => format!("ZRC2:{addr}"),
+                AssetId::Erc20(addr) => format!("ERC20:{addr}"),
+            };
+
+            write!(f, "\nBalance: {asset} {}", balance.amount)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn signature_bytes(signature: &Signature) -> &[u8] {
+    match signature {
+        Signature::SchnorrSecp256k1Sha256(sig) => sig,
+        Signature::ECDSASecp256k1Keccak256(sig) => sig,
+        Signature::Ed25519(sig) => sig,
+    }
+}
+
+// Schnorr and Ed25519 signatures have the same length, the key tells them apart.
+fn signature_for(pub_key: &PubKey, bytes: &[u8]) -> Result<Signature, StatementErrors> {
+    let malformed = |_| StatementErrors::MalformedSignature;
+
+    match pub_key {
+        PubKey::Secp256k1Sha256Zilliqa(_) => bytes
+            .try_into()
+            .map(Signature::SchnorrSecp256k1Sha256)
+            .map_err(malformed),
+        PubKey::Secp256k1Keccak256Ethereum(_) => bytes
+            .try_into()
+            .map(Signature::ECDSASecp256k1Keccak256)
+            .map_err(malformed),
+        PubKey::Ed25519Solana(_) => bytes.try_into().map(Signature::Ed25519).map_err(malformed),
+        PubKey::Secp256k1Bitcoin(_) => Err(StatementErrors::MalformedSignature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asset::TokenAmount, units::QA_PER_ZIL};
+
+    fn statement(address: Address) -> BalanceStatement {
+        BalanceStatement {
+            address,
+            chain_id: 1,
+            block_height: 4_500_000,
+            issued_at: 1_722_513_600,
+            purpose: Some("exchange verification".to_string()),
+            balances: vec![
+                AssetAmount::new(AssetId::Zil, TokenAmount::from_u128(QA_PER_ZIL)),
+                AssetAmount::new(
+                    AssetId::Zrc2(Address::Secp256k1Sha256Zilliqa([1u8; 20])),
+                    TokenAmount::from_u128(42),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let key_pairs = [
+            Some(KeyPair::gen_sha256().unwrap()),
+            cfg!(feature = "evm").then(|| KeyPair::gen_keccak256().unwrap()),
+        ];
+
+        for key_pair in key_pairs.into_iter().flatten() {
+            let signed = statement(key_pair.get_addr().unwrap())
+                .sign(&key_pair)
+                .unwrap();
+            let json = serde_json::to_string(&signed).unwrap();
+            let restored: SignedStatement = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(restored.verify(), Ok(()));
+            assert!(restored.statement.to_string().ends_with(&format!(
+                "Balance: ZIL 1000000000000\nBalance: ZRC2:{} 42",
+                Address::Secp256k1Sha256Zilliqa([1u8; 20])
+            )));
+
+            let mut forged = restored;
+
+            forged.statement.balances[0].amount = TokenAmount::from_u128(u128::MAX);
+
+            assert_eq!(forged.verify(), Err(StatementErrors::InvalidSignature));
+        }
+
+        let key_pair = KeyPair::gen_sha256().unwrap();
+        let other = KeyPair::gen_sha256().unwrap().get_addr().unwrap();
+
+        assert_eq!(
+            statement(other).sign(&key_pair),
+            Err(StatementErrors::AddressMismatch)
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use config::wallet::{
+    SWAP_HIGH_IMPACT_BPS, SWAP_SANDWICH_HIGH_SHARE_BPS, SWAP_SANDWICH_MEDIUM_SHARE_BPS,
+    SWAP_SANDWICH_SLIPPAGE_BPS,
+};
+use ethers_core::types::U256;
+use serde::{Deserialize, Serialize};
+use zil_errors::swap::SwapErrors;
+
+const BPS: u16 = 10_000;
+
+/// Constant product pool, reserves of the sold and bought token in their
+/// smallest units. The fee is taken from the sold amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pool {
+    pub reserve_in: u128,
+    pub reserve_out: u128,
+    pub fee_bps: u16,
+}
+
+/// How exposed a trade is to being front- and back-run, judged by its share
+/// of the pool and the slippage the user allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SandwichRisk {
+    Low,
+    Medium,
+    High,
+}
+
+/// Everything the confirmation screen has to show for a swap.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuoteAnalysis {
+    pub amount_in: u128,
+    pub expected_out: u128,
+    pub min_received: u128, // what the swap tx must ask for
+    pub price_impact_bps: u16,
+    pub slippage_bps: u16,
+    pub sandwich_risk: SandwichRisk,
+}
+
+impl Pool {
+    pub fn new(reserve_in: u128, reserve_out: u128, fee_bps: u16) -> Self {
+        Self {
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        }
+    }
+
+    pub fn amount_out(&self, amount_in: u128) -> Result<u128, SwapErrors> {
+        let with_fee = self.after_fee(amount_in)?;
+
+        mul_div(
+            with_fee,
+            self.reserve_out,
+            U256::from(self.reserve_in) + U256::from(with_fee),
+        )
+    }
+
+    /// Quote for selling `amount_in` with at most `slippage_bps` less
+    /// received than expected.
+    pub fn analyze(&self, amount_in: u128, slippage_bps: u16) -> Result<QuoteAnalysis, SwapErrors> {
+        if slippage_bps >= BPS {
+            return Err(SwapErrors::InvalidBps(slippage_bps));
+        }
+
+        let expected_out = self.amount_out(amount_in)?;
+
+        if expected_out == 0 {
+            return Err(SwapErrors::NothingReceived);
+        }
+
+        let min_received = mul_div(
+            expected_out,
+            u128::from(BPS - slippage_bps),
+            U256::from(BPS),
+        )?;
+        let share_bps = self.share_bps(amount_in)?;
+
+        Ok(QuoteAnalysis {
+            amount_in,
+            expected_out,
+            min_received,
+            price_impact_bps: self.price_impact_bps(amount_in)?,
+            slippage_bps,
+            sandwich_risk: sandwich_risk(share_bps, slippage_bps),
+        })
+    }
+
+    // Price moved by the trade alone, the fee is not part of it:
+    // `a / (reserve_in + a)` for the sold amount `a` after the fee.
+    pub fn price_impact_bps(&self, amount_in: u128) -> Result<u16, SwapErrors> {
+        let with_fee = self.after_fee(amount_in)?;
+        let impact = mul_div(
+            with_fee,
+            u128::from(BPS),
+            U256::from(self.reserve_in) + U256::from(with_fee),
+        )?;
+
+        Ok(impact as u16)
+    }
+
+    fn share_bps(&self, amount_in: u128) -> Result<u128, SwapErrors> {
+        mul_div(amount_in, u128::from(BPS), U256::from(self.reserve_in))
+    }
+
+    fn after_fee(&self, amount_in: u128) -> Result<u128, SwapErrors> {
+        if self.reserve_in == 0 || self.reserve_out == 0 {
+            return Err(SwapErrors::EmptyPool);
+        }
+
+        if amount_in == 0 {
+            return Err(SwapErrors::ZeroAmount);
+        }
+
+        if self.fee_bps >= BPS {
+            return Err(SwapErrors::InvalidBps(self.fee_bps));
+        }
+
+        mul_div(amount_in, u128::from(BPS - self.fee_bps), U256::from(BPS))
+    }
+}
+
+impl QuoteAnalysis {
+    // The user has to acknowledge the quote before signing.
+    pub fn needs_warning(&self) -> bool {
+        self.price_impact_bps >= SWAP_HIGH_IMPACT_BPS || self.sandwich_risk == SandwichRisk::High
+    }
+}
+
+// A sandwich can take at most the allowed slippage, and only pays off when
+// the trade moves the pool enough to cover the attacker's fees.
+fn sandwich_risk(share_bps: u128, slippage_bps: u16) -> SandwichRisk {
+    let loose = slippage_bps >= SWAP_SANDWICH_SLIPPAGE_BPS;
+
+    if share_bps >= SWAP_SANDWICH_HIGH_SHARE_BPS && loose {
+        SandwichRisk::High
+    } else if share_bps >= SWAP_SANDWICH_MEDIUM_SHARE_BPS || loose {
+        SandwichRisk::Medium
+    } else {
+        SandwichRisk::Low
+    }
+}
+
+// `a * b / c` rounded down, without overflowing the product.
+fn mul_div(a: u128, b: u128, c: U256) -> Result<u128, SwapErrors> {
+    let result = U256::from(a)
+        .checked_mul(U256::from(b))
+        .ok_or(SwapErrors::Overflow)?
+        / c;
+
+    u128::try_from(result).or(Err(SwapErrors::Overflow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::QA_PER_ZIL as ZIL;
+
+    #[test]
+    fn test_analyze_quote() {
+        // 1M ZIL against 10M of a 12 decimals token, 0.3% fee
+        let pool = Pool::new(1_000_000 * ZIL, 10_000_000 * ZIL, 30);
+        let small = pool.analyze(100 * ZIL, 50).unwrap();
+
+        assert_eq!(small.expected_out, 996_900_609_009_281);
+        assert_eq!(small.min_received, 991_916_105_964_234);
+        assert_eq!(small.price_impact_bps, 0);
+        assert_eq!(small.sandwich_risk, SandwichRisk::Low);
+        assert!(!small.needs_warning());
+
+        let large = pool.analyze(50_000 * ZIL, 100).unwrap();
+
+        assert_eq!(large.price_impact_bps, 474);
+        assert_eq!(large.sandwich_risk, SandwichRisk::High);
+        assert!(large.min_received < large.expected_out);
+        assert!(large.needs_warning());
+
+        assert_eq!(
+            pool.analyze(10 * ZIL, 300).unwrap().sandwich_risk,
+            SandwichRisk::Medium
+        );
+        assert_eq!(pool.analyze(0, 50), Err(SwapErrors::ZeroAmount));
+        assert_eq!(pool.analyze(ZIL, BPS), Err(SwapErrors::InvalidBps(BPS)));
+        assert_eq!(
+            Pool::new(0, ZIL, 30).analyze(ZIL, 50),
+            Err(SwapErrors::EmptyPool)
+        );
+        assert_eq!(
+            Pool::new(u128::MAX, 1, 30).analyze(1, 50),
+            Err(SwapErrors::NothingReceived)
+        );
+    }
+}
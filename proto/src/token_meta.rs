@@ -0,0 +1,95 @@
+use crate::{
+    asset::TokenAmount,
+    units::{format_base_units, to_base_units},
+};
+use serde::{Deserialize, Serialize};
+use zil_errors::units::UnitsErrors;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Local correction of what the contract reports, `None` keeps the
+/// contract value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataOverride {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+}
+
+impl MetadataOverride {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.symbol.is_none() && self.decimals.is_none()
+    }
+}
+
+impl TokenMetadata {
+    pub fn with_override(mut self, fix: &MetadataOverride) -> Self {
+        if let Some(name) = &fix.name {
+            self.name = name.clone();
+        }
+
+        if let Some(symbol) = &fix.symbol {
+            self.symbol = symbol.clone();
+        }
+
+        if let Some(decimals) = fix.decimals {
+            self.decimals = decimals;
+        }
+
+        self
+    }
+
+    // Display, e.g. "1.5".
+    pub fn format_amount(&self, amount: &TokenAmount) -> String {
+        match amount.to_u128() {
+            Some(value) => format_base_units(value, self.decimals.into()),
+            None => amount.to_string(),
+        }
+    }
+
+    // Send validation, rejects more fraction digits than the token has.
+    pub fn parse_amount(&self, value: &str) -> Result<TokenAmount, UnitsErrors> {
+        to_base_units(value.trim(), self.decimals.into(), &self.symbol).map(TokenAmount::from_u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override() {
+        let reported = TokenMetadata {
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            decimals: 18,
+        };
+        let fix = MetadataOverride {
+            symbol: Some("gZIL".to_string()),
+            decimals: Some(15),
+            ..Default::default()
+        };
+        let meta = reported.clone().with_override(&fix);
+
+        assert_eq!(meta.name, "Token");
+        assert_eq!(meta.symbol, "gZIL");
+        assert_eq!(
+            meta.format_amount(&TokenAmount::from_u128(1_500_000_000_000_000)),
+            "1.5"
+        );
+        assert_eq!(
+            meta.parse_amount("1.5"),
+            Ok(TokenAmount::from_u128(1_500_000_000_000_000))
+        );
+        assert_eq!(
+            meta.parse_amount("0.0000000000000001"),
+            Err(UnitsErrors::TooManyDecimals("gZIL".to_string(), 15))
+        );
+        assert_eq!(reported.with_override(&Default::default()).decimals, 18);
+    }
+}
@@ -1,19 +1,22 @@
 use crate::keypair::KeyPair;
 use crate::zil_tx::{encode_zilliqa_transaction, ZILTransactionReceipt, ZILTransactionRequest};
 use crypto::schnorr::sign as zil_sign;
-use ethers::types::TransactionRequest as ETHTransactionRequest;
+#[cfg(feature = "evm")]
+use ethers_core::types::TransactionRequest as ETHTransactionRequest;
 use k256::SecretKey as K256SecretKey;
 use zil_errors::keypair::KeyPairError;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum TransactionReceipt {
-    Zilliqa(ZILTransactionReceipt),  // ZILLIQA
+    Zilliqa(ZILTransactionReceipt), // ZILLIQA
+    #[cfg(feature = "evm")]
     Ethereum(ETHTransactionRequest), // Ethereum
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum TransactionRequest {
-    Zilliqa(ZILTransactionRequest),  // ZILLIQA
+    Zilliqa(ZILTransactionRequest), // ZILLIQA
+    #[cfg(feature = "evm")]
     Ethereum(ETHTransactionRequest), // Ethereum
 }
 
@@ -42,7 +45,8 @@ impl TransactionRequest {
                     data: tx.data.clone(),
                 }))
             }
-            TransactionRequest::Ethereum(tx) => {
+            #[cfg(feature = "evm")]
+            TransactionRequest::Ethereum(_tx) => {
                 unimplemented!()
             }
         }
@@ -51,8 +55,6 @@ impl TransactionRequest {
 
 #[cfg(test)]
 mod tests_transaction_request {
-    use super::*;
-
     #[test]
     fn test_sign_zil() {}
 }
@@ -0,0 +1,195 @@
+use crate::zil_tx::ZilAmount;
+use std::{fmt, str::FromStr};
+use zil_errors::units::UnitsErrors;
+
+pub const QA_PER_LI: u128 = 1_000_000;
+pub const QA_PER_ZIL: u128 = 1_000_000_000_000;
+
+pub const ONE_QA: ZilAmount = ZilAmount::from_raw(1);
+pub const ONE_LI: ZilAmount = ZilAmount::from_raw(QA_PER_LI);
+pub const ONE_ZIL: ZilAmount = ZilAmount::from_raw(QA_PER_ZIL);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZilUnit {
+    Qa,  // 10^-12 ZIL
+    Li,  // 10^-6 ZIL
+    Zil, // 1 ZIL
+}
+
+impl ZilUnit {
+    pub fn qa_factor(&self) -> u128 {
+        match self {
+            ZilUnit::Qa => 1,
+            ZilUnit::Li => QA_PER_LI,
+            ZilUnit::Zil => QA_PER_ZIL,
+        }
+    }
+
+    pub fn decimals(&self) -> u32 {
+        match self {
+            ZilUnit::Qa => 0,
+            ZilUnit::Li => 6,
+            ZilUnit::Zil => 12,
+        }
+    }
+}
+
+impl fmt::Display for ZilUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZilUnit::Qa => write!(f, "Qa"),
+            ZilUnit::Li => write!(f, "Li"),
+            ZilUnit::Zil => write!(f, "ZIL"),
+        }
+    }
+}
+
+impl FromStr for ZilUnit {
+    type Err = UnitsErrors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "qa" => Ok(ZilUnit::Qa),
+            "li" => Ok(ZilUnit::Li),
+            "zil" => Ok(ZilUnit::Zil),
+            _ => Err(UnitsErrors::UnknownUnit(s.to_string())),
+        }
+    }
+}
+
+/// Converts a decimal `value` expressed in `unit` into Qa.
+pub fn to_qa(value: &str, unit: ZilUnit) -> Result<u128, UnitsErrors> {
+    to_base_units(value, unit.decimals(), &unit.to_string())
+}
+
+/// Converts a decimal `value` of a token with `decimals` into its smallest
+/// units, `label` (unit or token symbol) goes into errors.
+pub fn to_base_units(value: &str, decimals: u32, label: &str) -> Result<u128, UnitsErrors> {
+    let invalid = || UnitsErrors::InvalidNumber(value.to_string());
+    let (int_part, frac_part) = value.split_once('.').unwrap_or((value, ""));
+
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(invalid());
+    }
+
+    if !int_part
+        .chars()
+        .chain(frac_part.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    if frac_part.len() > decimals as usize {
+        return Err(UnitsErrors::TooManyDecimals(label.to_string(), decimals));
+    }
+
+    let factor = 10u128.checked_pow(decimals).ok_or(UnitsErrors::Overflow)?;
+    let int_value = if int_part.is_empty() {
+        0
+    } else {
+        u128::from_str(int_part).or(Err(UnitsErrors::Overflow))?
+    };
+    let frac_value = if frac_part.is_empty() {
+        0
+    } else {
+        let scale = 10u128.pow(decimals - frac_part.len() as u32);
+
+        u128::from_str(frac_part).or(Err(invalid()))? * scale
+    };
+
+    int_value
+        .checked_mul(factor)
+        .and_then(|v| v.checked_add(frac_value))
+        .ok_or(UnitsErrors::Overflow)
+}
+
+/// Parses amounts with a unit suffix such as "1.5 ZIL" or "2000 Li".
+pub fn parse_zil(s: &str) -> Result<ZilAmount, UnitsErrors> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| c.is_ascii_alphabetic())
+        .ok_or(UnitsErrors::MissingUnit(s.to_string()))?;
+    let (value, unit) = s.split_at(split);
+    let unit = ZilUnit::from_str(unit.trim())?;
+
+    to_qa(value.trim(), unit).map(ZilAmount::from_raw)
+}
+
+/// Formats an amount in `unit` without trailing zeros, e.g. "1.5".
+pub fn format_zil(amount: ZilAmount, unit: ZilUnit) -> String {
+    format_base_units(amount.raw(), unit.decimals())
+}
+
+pub fn format_base_units(amount: u128, decimals: u32) -> String {
+    let Some(factor) = 10u128.checked_pow(decimals) else {
+        return format!("0.{:0>width$}", amount, width = decimals as usize)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string();
+    };
+    let int_part = amount / factor;
+    let frac_part = amount % factor;
+
+    if frac_part == 0 {
+        return int_part.to_string();
+    }
+
+    let frac = format!("{:0width$}", frac_part, width = decimals as usize);
+
+    format!("{}.{}", int_part, frac.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_zil("1.5 ZIL").unwrap().raw(), 1_500_000_000_000);
+        assert_eq!(parse_zil("2000 Li").unwrap().raw(), 2_000 * QA_PER_LI);
+        assert_eq!(parse_zil("1zil").unwrap(), ONE_ZIL);
+        assert_eq!(parse_zil(".000001 ZIL").unwrap(), ONE_LI);
+        assert_eq!(parse_zil("7 qa").unwrap().raw(), 7);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_zil("1.5"),
+            Err(UnitsErrors::MissingUnit("1.5".to_string()))
+        );
+        assert_eq!(
+            parse_zil("1 BTC"),
+            Err(UnitsErrors::UnknownUnit("BTC".to_string()))
+        );
+        assert_eq!(
+            parse_zil("0.5 Qa"),
+            Err(UnitsErrors::TooManyDecimals("Qa".to_string(), 0))
+        );
+        assert_eq!(
+            parse_zil("1,5 ZIL"),
+            Err(UnitsErrors::InvalidNumber("1,5".to_string()))
+        );
+        assert_eq!(
+            parse_zil("340282366920938463463374607431768211455 ZIL"),
+            Err(UnitsErrors::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let amount = parse_zil("1.5 ZIL").unwrap();
+
+        assert_eq!(format_zil(amount, ZilUnit::Zil), "1.5");
+        assert_eq!(format_zil(amount, ZilUnit::Li), "1500000");
+        assert_eq!(format_zil(ONE_LI, ZilUnit::Zil), "0.000001");
+        assert_eq!(format_zil(ONE_ZIL, ZilUnit::Zil), "1");
+        assert_eq!(format_base_units(1_500, 3), "1.5");
+        assert_eq!(to_base_units("1.5", 3, "TKN"), Ok(1_500));
+        assert_eq!(
+            to_base_units("1.5", 0, "TKN"),
+            Err(UnitsErrors::TooManyDecimals("TKN".to_string(), 0))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{address::Address, signature::Signature, signer::Signer};
+use ethers_core::{
+    abi::{encode, Token},
+    types::{Bytes, H160, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use zil_errors::user_op::UserOpErrors;
+
+/// ERC-4337 user operation in the v0.6 EntryPoint layout, serialized the
+/// way bundlers expect it (camelCase, hex quantities).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: H160,
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOpGasEstimate {
+    pub pre_verification_gas: U256,
+    pub verification_gas_limit: U256,
+    pub call_gas_limit: U256,
+}
+
+impl UserOperation {
+    pub fn new(sender: &Address, nonce: U256, call_data: Vec<u8>) -> Result<Self, UserOpErrors> {
+        match sender {
+            Address::Secp256k1Keccak256Ethereum(bytes) => Ok(Self {
+                sender: H160::from(*bytes),
+                nonce,
+                call_data: call_data.into(),
+                ..Default::default()
+            }),
+            _ => Err(UserOpErrors::InvalidSender),
+        }
+    }
+
+    pub fn with_gas(mut self, estimate: &UserOpGasEstimate) -> Self {
+        self.pre_verification_gas = estimate.pre_verification_gas;
+        self.verification_gas_limit = estimate.verification_gas_limit;
+        self.call_gas_limit = estimate.call_gas_limit;
+        self
+    }
+
+    /// userOpHash as computed by `EntryPoint.getUserOpHash`, the signature
+    /// itself is not part of it.
+    pub fn hash(&self, entry_point: H160, chain_id: u64) -> [u8; 32] {
+        let packed = encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ]);
+
+        keccak256(encode(&[
+            Token::FixedBytes(keccak256(packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(chain_id.into()),
+        ]))
+    }
+
+    // Smart accounts check an EIP-191 signature over the hash, which is what
+    // an Ethereum key signs through `sign_message`.
+    pub fn sign(
+        &mut self,
+        signer: &impl Signer,
+        entry_point: H160,
+        chain_id: u64,
+    ) -> Result<(), UserOpErrors> {
+        let hash = self.hash(entry_point, chain_id);
+
+        match signer
+            .sign_message(&hash)
+            .map_err(UserOpErrors::SignError)?
+        {
+            Signature::ECDSASecp256k1Keccak256(sig) => {
+                self.signature = sig.to_vec().into();
+
+                Ok(())
+            }
+            _ => Err(UserOpErrors::UnsupportedSignature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keypair::KeyPair;
+    use config::contracts::ENTRY_POINT_V06;
+    use ethers_core::{types::Signature as EthersSignature, utils::hash_message};
+
+    #[test]
+    fn test_sign_user_op() {
+        let keypair = KeyPair::gen_keccak256().unwrap();
+        let owner = keypair.get_addr().unwrap();
+        let entry_point: H160 = ENTRY_POINT_V06.parse().unwrap();
+        let mut op = UserOperation::new(&owner, 1.into(), vec![0xb6, 0x1d, 0x27, 0xf6]).unwrap();
+        let hash = op.hash(entry_point, 32769);
+
+        // chain and entry point are bound into the hash
+        assert_ne!(hash, op.hash(entry_point, 33101));
+        assert_ne!(hash, op.hash(H160::zero(), 32769));
+
+        op.sign(&keypair, entry_point, 32769).unwrap();
+
+        let sig = EthersSignature::try_from(op.signature.as_ref()).unwrap();
+        let recovered = sig.recover(hash_message(hash)).unwrap();
+
+        assert_eq!(recovered.as_bytes(), owner.addr_bytes());
+        // signing does not change what is signed
+        assert_eq!(op.hash(entry_point, 32769), hash);
+        assert_eq!(
+            op.clone()
+                .sign(&KeyPair::gen_sha256().unwrap(), entry_point, 32769),
+            Err(UserOpErrors::UnsupportedSignature)
+        );
+        assert_eq!(serde_json::to_value(&op).unwrap()["callData"], "0xb61d27f6");
+    }
+}
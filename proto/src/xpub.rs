@@ -0,0 +1,236 @@
+use coins_bip32::{
+    ecdsa::VerifyingKey,
+    prelude::{Hint, Parent, XKeyInfo, XPriv, XPub},
+};
+use config::key::{BIP32_HARDENED, PUB_KEY_SIZE, XPUB_SIZE, XPUB_VERSION};
+use k256::PublicKey;
+use std::{fmt, str::FromStr};
+use zil_errors::xpub::XpubErrors;
+
+/// BIP-32 extended public key. Exported at the account level so watch-only
+/// clients can derive every receive address without private material.
+/// Derivation is done by coins-bip32, tiny-hderive keeps chain codes private.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPubKey {
+    pub depth: u8,
+    pub parent_fingerprint: [u8; 4],
+    pub child_number: u32,
+    pub chain_code: [u8; 32],
+    pub pub_key: [u8; PUB_KEY_SIZE],
+}
+
+impl ExtendedPubKey {
+    /// Derives privately down `path` (e.g. `m/44'/313'/0'`) and keeps only the
+    /// public part.
+    pub fn from_seed(seed: &[u8], path: &str) -> Result<Self, XpubErrors> {
+        let mut node =
+            XPriv::root_from_seed(seed, Some(Hint::Legacy)).or(Err(XpubErrors::InvalidChild(0)))?;
+
+        for index in parse_path(path)? {
+            node = node
+                .derive_child(index)
+                .or(Err(XpubErrors::InvalidChild(index)))?;
+        }
+
+        Self::from_xpub(&node.verify_key())
+    }
+
+    pub fn child(&self, index: u32) -> Result<Self, XpubErrors> {
+        if index >= BIP32_HARDENED {
+            return Err(XpubErrors::HardenedFromPublic(index));
+        }
+
+        let child = self
+            .to_xpub()?
+            .derive_child(index)
+            .or(Err(XpubErrors::InvalidChild(index)))?;
+
+        Self::from_xpub(&child)
+    }
+
+    // Receive address key, external chain `0/index`.
+    pub fn receive_pub_key(&self, index: u32) -> Result<[u8; PUB_KEY_SIZE], XpubErrors> {
+        Ok(self.child(0)?.child(index)?.pub_key)
+    }
+
+    pub fn to_bytes(&self) -> [u8; XPUB_SIZE] {
+        let mut bytes = [0u8; XPUB_SIZE];
+
+        bytes[..4].copy_from_slice(&XPUB_VERSION);
+        bytes[4] = self.depth;
+        bytes[5..9].copy_from_slice(&self.parent_fingerprint);
+        bytes[9..13].copy_from_slice(&self.child_number.to_be_bytes());
+        bytes[13..45].copy_from_slice(&self.chain_code);
+        bytes[45..].copy_from_slice(&self.pub_key);
+
+        bytes
+    }
+
+    fn from_xpub(xpub: &XPub) -> Result<Self, XpubErrors> {
+        let info: &XKeyInfo = xpub.as_ref();
+        let key: &VerifyingKey = xpub.as_ref();
+
+        Ok(Self {
+            depth: info.depth,
+            parent_fingerprint: info.parent.0,
+            child_number: info.index,
+            chain_code: info.chain_code.0,
+            pub_key: key
+                .to_sec1_bytes()
+                .as_ref()
+                .try_into()
+                .or(Err(XpubErrors::InvalidPubKey))?,
+        })
+    }
+
+    fn to_xpub(&self) -> Result<XPub, XpubErrors> {
+        let key =
+            VerifyingKey::from_sec1_bytes(&self.pub_key).or(Err(XpubErrors::InvalidPubKey))?;
+        let info = XKeyInfo {
+            depth: self.depth,
+            parent: self.parent_fingerprint.into(),
+            index: self.child_number,
+            chain_code: self.chain_code.into(),
+            hint: Hint::Legacy,
+        };
+
+        Ok(XPub::new(key, info))
+    }
+}
+
+impl fmt::Display for ExtendedPubKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            bs58::encode(self.to_bytes()).with_check().into_string()
+        )
+    }
+}
+
+impl FromStr for ExtendedPubKey {
+    type Err = XpubErrors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes: [u8; XPUB_SIZE] = bs58::decode(s)
+            .with_check(None)
+            .into_vec()
+            .or(Err(XpubErrors::InvalidEncoding))?
+            .try_into()
+            .or(Err(XpubErrors::InvalidEncoding))?;
+
+        if bytes[..4] != XPUB_VERSION {
+            return Err(XpubErrors::InvalidVersion);
+        }
+
+        let pub_key: [u8; PUB_KEY_SIZE] = bytes[45..]
+            .try_into()
+            .or(Err(XpubErrors::InvalidEncoding))?;
+
+        PublicKey::from_sec1_bytes(&pub_key).or(Err(XpubErrors::InvalidPubKey))?;
+
+        Ok(Self {
+            depth: bytes[4],
+            parent_fingerprint: bytes[5..9]
+                .try_into()
+                .or(Err(XpubErrors::InvalidEncoding))?,
+            child_number: u32::from_be_bytes(
+                bytes[9..13]
+                    .try_into()
+                    .or(Err(XpubErrors::InvalidEncoding))?,
+            ),
+            chain_code: bytes[13..45]
+                .try_into()
+                .or(Err(XpubErrors::InvalidEncoding))?,
+            pub_key,
+        })
+    }
+}
+
+fn parse_path(path: &str) -> Result<Vec<u32>, XpubErrors> {
+    let mut parts = path.split('/');
+
+    if parts.next() != Some("m") {
+        return Err(XpubErrors::InvalidPath(path.to_string()));
+    }
+
+    parts
+        .filter(|p| !p.is_empty())
+        .map(|part| {
+            let (digits, offset) = match part.strip_suffix('\'') {
+                Some(digits) => (digits, BIP32_HARDENED),
+                None => (part, 0),
+            };
+            let index: u32 = digits
+                .parse()
+                .or(Err(XpubErrors::InvalidPath(path.to_string())))?;
+
+            if index >= BIP32_HARDENED {
+                return Err(XpubErrors::InvalidPath(path.to_string()));
+            }
+
+            Ok(index + offset)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keypair::KeyPair, pubkey::PubKey};
+    use crypto::bip49::Bip49DerivationPath;
+
+    #[test]
+    fn test_bip32_vector_1() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedPubKey::from_seed(&seed, "m").unwrap();
+
+        assert_eq!(
+            master.to_string(),
+            "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"
+        );
+        assert_eq!(
+            master.to_string().parse::<ExtendedPubKey>().unwrap(),
+            master
+        );
+    }
+
+    #[test]
+    fn test_matches_private_derivation() {
+        let seed = [7u8; 64];
+        let xpub = ExtendedPubKey::from_seed(&seed, "m/44'/313'/0'").unwrap();
+
+        for index in 0..3 {
+            let keypair =
+                KeyPair::from_bip39_seed(&seed, &Bip49DerivationPath::Zilliqa(index)).unwrap();
+            let pub_key = keypair.get_pubkey().unwrap();
+
+            assert_eq!(
+                PubKey::Secp256k1Sha256Zilliqa(xpub.receive_pub_key(index as u32).unwrap()),
+                pub_key
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let seed = [7u8; 64];
+        let xpub = ExtendedPubKey::from_seed(&seed, "m/44'/313'/0'").unwrap();
+
+        assert_eq!(
+            xpub.child(BIP32_HARDENED),
+            Err(XpubErrors::HardenedFromPublic(BIP32_HARDENED))
+        );
+        assert!(ExtendedPubKey::from_seed(&seed, "44'/0").is_err());
+
+        let mut encoded = xpub.to_string();
+
+        encoded.pop();
+        encoded.push('1');
+
+        assert_eq!(
+            encoded.parse::<ExtendedPubKey>(),
+            Err(XpubErrors::InvalidEncoding)
+        );
+    }
+}
@@ -6,11 +6,14 @@ use std::{
 
 use crate::{
     address::Address,
+    asset::AssetAmount,
+    units::QA_PER_LI,
     zq1_proto::{Code, Data, Nonce, ProtoTransactionCoreInfo},
 };
 // use crypto::schnorr::PublicKey;
 use crate::pubkey::PubKey;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 pub const EVM_GAS_PER_SCILLA_GAS: u64 = 420;
 
@@ -64,17 +67,22 @@ pub struct ZilAmount(u128);
 impl ZilAmount {
     /// Construct a [ZilAmount] from an amount in (10^-18) ZILs. The value will be truncated and rounded down.
     pub fn from_amount(amount: u128) -> ZilAmount {
-        ZilAmount(amount / 10u128.pow(6))
+        ZilAmount(amount / QA_PER_LI)
     }
 
     // Construct a [ZilAmount] from an amount in (10^-12) ZILs.
-    pub fn from_raw(amount: u128) -> ZilAmount {
+    pub const fn from_raw(amount: u128) -> ZilAmount {
         ZilAmount(amount)
     }
 
+    /// Get the ZIL amount in units of (10^-12) ZILs.
+    pub fn raw(self) -> u128 {
+        self.0
+    }
+
     /// Get the ZIL amount in units of (10^-18) ZILs.
     pub fn get(self) -> u128 {
-        self.0.checked_mul(10u128.pow(6)).expect("amount overflow")
+        self.0.checked_mul(QA_PER_LI).expect("amount overflow")
     }
 
     /// Return the memory representation of this amount as a big-endian byte array.
@@ -95,6 +103,12 @@ pub struct ZILTransactionRequest {
     pub data: String,
 }
 
+impl ZILTransactionRequest {
+    pub fn asset_amount(&self) -> AssetAmount {
+        AssetAmount::native(self.amount)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ZILTransactionReceipt {
     pub chain_id: u16,
@@ -125,3 +139,46 @@ pub fn encode_zilliqa_transaction(txn: &ZILTransactionRequest, pub_key: PubKey)
 
     prost::Message::encode_to_vec(&proto)
 }
+
+/// TranID the node assigns, known before the transaction is sent.
+pub fn zilliqa_tx_hash(txn: &ZILTransactionRequest, pub_key: PubKey) -> String {
+    hex::encode(Sha256::digest(encode_zilliqa_transaction(txn, pub_key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::ONE_ZIL;
+
+    #[test]
+    fn test_zilliqa_tx_hash() {
+        let mut key = [0x11u8; 33];
+
+        key[0] = 0x03;
+
+        let txn = ZILTransactionRequest {
+            chain_id: 1,
+            nonce: 7,
+            gas_price: ZilAmount::from_raw(2_000_000_000),
+            gas_limit: ScillaGas(50),
+            to_addr: Address::Secp256k1Sha256Zilliqa([0x22; 20]),
+            amount: ONE_ZIL,
+            code: String::new(),
+            data: String::new(),
+        };
+        let pub_key = || PubKey::Secp256k1Sha256Zilliqa(key);
+
+        // core info fields in tag order, as the node serializes them
+        assert_eq!(
+            hex::encode(encode_zilliqa_transaction(&txn, pub_key())),
+            "0881800410071a14222222222222222222222222222222222222222222230a21\
+             0311111111111111111111111111111111111111111111111111111111111111\
+             112a120a100000000000000000000000e8d4a5100032120a1000000000000000\
+             0000000000773594003832"
+        );
+        assert_eq!(
+            zilliqa_tx_hash(&txn, pub_key()),
+            "b9ed162be6d25c69b86bc5397ababef079d8ca5d7b93be46f7529b2178489616"
+        );
+    }
+}
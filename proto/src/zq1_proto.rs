@@ -54,3 +54,41 @@ pub enum Data {
     #[prost(bytes, tag = "9")]
     Data(Vec<u8>),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use prost::Message;
+
+    proptest! {
+        #[test]
+        fn prop_decode_never_panics(bytes in proptest::collection::vec(any::<u8>(), 0..512)) {
+            let _ = ProtoTransactionCoreInfo::decode(bytes.as_slice());
+        }
+
+        #[test]
+        fn prop_roundtrip(
+            version in any::<u32>(),
+            nonce in any::<u64>(),
+            toaddr in proptest::collection::vec(any::<u8>(), 0..32),
+            gaslimit in any::<u64>(),
+            data in proptest::collection::vec(any::<u8>(), 0..128),
+        ) {
+            let info = ProtoTransactionCoreInfo {
+                version,
+                toaddr,
+                senderpubkey: Some(vec![2u8; 33].into()),
+                amount: None,
+                gasprice: None,
+                gaslimit,
+                oneof2: Some(Nonce::Nonce(nonce)),
+                oneof8: None,
+                oneof9: Some(Data::Data(data)),
+            };
+            let decoded = ProtoTransactionCoreInfo::decode(info.encode_to_vec().as_slice()).unwrap();
+
+            prop_assert_eq!(decoded, info);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    address::Address,
+    zil_tx::{ScillaGas, ZILTransactionRequest, ZilAmount},
+};
+use serde_json::{json, Value};
+use zil_errors::zrc2::Zrc2Errors;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Zrc2Transition {
+    Transfer { to: Address, amount: u128 },
+    Burn { burn_account: Address, amount: u128 }, // burnable extension
+    Mint { recipient: Address, amount: u128 },    // mintable extension
+    IncreaseAllowance { spender: Address, amount: u128 },
+    DecreaseAllowance { spender: Address, amount: u128 },
+}
+
+impl Zrc2Transition {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::Transfer { .. } => "Transfer",
+            Self::Burn { .. } => "Burn",
+            Self::Mint { .. } => "Mint",
+            Self::IncreaseAllowance { .. } => "IncreaseAllowance",
+            Self::DecreaseAllowance { .. } => "DecreaseAllowance",
+        }
+    }
+
+    pub fn amount(&self) -> u128 {
+        match self {
+            Self::Transfer { amount, .. }
+            | Self::Burn { amount, .. }
+            | Self::Mint { amount, .. }
+            | Self::IncreaseAllowance { amount, .. }
+            | Self::DecreaseAllowance { amount, .. } => *amount,
+        }
+    }
+
+    pub fn data(&self) -> Value {
+        let (vname, addr) = match self {
+            Self::Transfer { to, .. } => ("to", to),
+            Self::Burn { burn_account, .. } => ("burn_account", burn_account),
+            Self::Mint { recipient, .. } => ("recipient", recipient),
+            Self::IncreaseAllowance { spender, .. } | Self::DecreaseAllowance { spender, .. } => {
+                ("spender", spender)
+            }
+        };
+
+        json!({
+            "_tag": self.tag(),
+            "params": [
+                {
+                    "vname": vname,
+                    "type": "ByStr20",
+                    "value": format!("0x{}", hex::encode(addr.addr_bytes())),
+                },
+                {
+                    "vname": "amount",
+                    "type": "Uint128",
+                    "value": self.amount().to_string(),
+                },
+            ],
+        })
+    }
+
+    // The other way round, None for anything that is not a ZRC-2 call.
+    pub fn from_data(data: &Value) -> Option<Self> {
+        let params = data["params"].as_array()?;
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|p| p["vname"] == name)
+                .and_then(|p| p["value"].as_str())
+        };
+        let addr = |name: &str| {
+            param(name)
+                .map(|v| v.trim_start_matches("0x").to_lowercase())
+                .and_then(|v| Address::from_zil_base16(&v).ok())
+        };
+        let amount = param("amount")?.parse().ok()?;
+
+        Some(match data["_tag"].as_str()? {
+            "Transfer" => Self::Transfer {
+                to: addr("to")?,
+                amount,
+            },
+            "Burn" => Self::Burn {
+                burn_account: addr("burn_account")?,
+                amount,
+            },
+            "Mint" => Self::Mint {
+                recipient: addr("recipient")?,
+                amount,
+            },
+            "IncreaseAllowance" => Self::IncreaseAllowance {
+                spender: addr("spender")?,
+                amount,
+            },
+            "DecreaseAllowance" => Self::DecreaseAllowance {
+                spender: addr("spender")?,
+                amount,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Call of `contract`, fails when the token doesn't implement the
+    /// transition or the amount is zero.
+    pub fn to_request(
+        &self,
+        contract: Address,
+        capabilities: &Zrc2Capabilities,
+        chain_id: u16,
+        nonce: u64,
+        gas_price: ZilAmount,
+        gas_limit: ScillaGas,
+    ) -> Result<ZILTransactionRequest, Zrc2Errors> {
+        if self.amount() == 0 {
+            return Err(Zrc2Errors::ZeroAmount);
+        }
+
+        capabilities.check(self)?;
+
+        Ok(ZILTransactionRequest {
+            chain_id,
+            nonce,
+            gas_price,
+            gas_limit,
+            to_addr: contract,
+            amount: ZilAmount::from_raw(0),
+            code: String::new(),
+            data: self.data().to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Zrc2Capabilities {
+    pub transfer: bool,
+    pub mint: bool,
+    pub burn: bool,
+    pub increase_allowance: bool,
+    pub decrease_allowance: bool,
+}
+
+impl Zrc2Capabilities {
+    /// Scans scilla source for the transitions the token declares,
+    /// commented out ones don't count.
+    pub fn from_code(code: &str) -> Self {
+        let mut caps = Self::default();
+        let code = strip_comments(code);
+        let mut words = code.split_whitespace().peekable();
+
+        while let Some(word) = words.next() {
+            if word != "transition" {
+                continue;
+            }
+
+            let Some(name) = words.peek() else {
+                break;
+            };
+            let name: String = name
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                .collect();
+
+            match name.as_str() {
+                "Transfer" => caps.transfer = true,
+                "Mint" => caps.mint = true,
+                "Burn" => caps.burn = true,
+                "IncreaseAllowance" => caps.increase_allowance = true,
+                "DecreaseAllowance" => caps.decrease_allowance = true,
+                _ => {}
+            }
+        }
+
+        caps
+    }
+
+    pub fn supports(&self, transition: &Zrc2Transition) -> bool {
+        match transition {
+            Zrc2Transition::Transfer { .. } => self.transfer,
+            Zrc2Transition::Burn { .. } => self.burn,
+            Zrc2Transition::Mint { .. } => self.mint,
+            Zrc2Transition::IncreaseAllowance { .. } => self.increase_allowance,
+            Zrc2Transition::DecreaseAllowance { .. } => self.decrease_allowance,
+        }
+    }
+
+    pub fn check(&self, transition: &Zrc2Transition) -> Result<(), Zrc2Errors> {
+        if self.supports(transition) {
+            Ok(())
+        } else {
+            Err(Zrc2Errors::Unsupported(transition.tag()))
+        }
+    }
+}
+
+// Scilla comments are `(* ... *)` and nest, they are replaced by a space
+// so the words around them stay apart.
+fn strip_comments(code: &str) -> String {
+    let mut out = String::with_capacity(code.len());
+    let mut depth = 0usize;
+    let mut chars = code.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('(', Some('*')) => {
+                chars.next();
+                depth += 1;
+            }
+            ('*', Some(')')) if depth > 0 => {
+                chars.next();
+                depth -= 1;
+                out.push(' ');
+            }
+            _ if depth > 0 => {}
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::address::ADDR_LEN;
+
+    const CODE: &str = r#"
+        scilla_version 0
+        contract FungibleToken (contract_owner: ByStr20, name: String)
+
+        transition Mint(recipient: ByStr20, amount: Uint128)
+        end
+
+        transition Transfer (to: ByStr20, amount: Uint128)
+        end
+
+        transition IncreaseAllowance(spender: ByStr20, amount: Uint128)
+        end
+
+        (* transition Burn(burn_account: ByStr20, amount: Uint128)
+           (* nested *) transition DecreaseAllowance
+        end *)
+    "#;
+
+    fn addr(byte: u8) -> Address {
+        Address::Secp256k1Sha256Zilliqa([byte; ADDR_LEN])
+    }
+
+    #[test]
+    fn test_capabilities_from_code() {
+        let caps = Zrc2Capabilities::from_code(CODE);
+
+        assert_eq!(
+            caps,
+            Zrc2Capabilities {
+                transfer: true,
+                mint: true,
+                burn: false,
+                increase_allowance: true,
+                decrease_allowance: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_burn_data() {
+        let burn = Zrc2Transition::Burn {
+            burn_account: addr(0xab),
+            amount: 1000,
+        };
+        let data = burn.data();
+
+        assert_eq!(data["_tag"], "Burn");
+        assert_eq!(data["params"][0]["vname"], "burn_account");
+        assert_eq!(
+            data["params"][0]["value"],
+            format!("0x{}", "ab".repeat(ADDR_LEN))
+        );
+        assert_eq!(data["params"][1]["value"], "1000");
+        assert_eq!(Zrc2Transition::from_data(&data), Some(burn));
+        assert_eq!(
+            Zrc2Transition::from_data(&json!({"_tag": "Swap", "params": []})),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_request() {
+        let caps = Zrc2Capabilities::from_code(CODE);
+        let mint = Zrc2Transition::Mint {
+            recipient: addr(1),
+            amount: 5,
+        };
+        let req = mint
+            .to_request(
+                addr(2),
+                &caps,
+                1,
+                7,
+                ZilAmount::from_raw(2000000000),
+                ScillaGas(1000),
+            )
+            .unwrap();
+
+        assert_eq!(req.to_addr, addr(2));
+        assert_eq!(req.amount, ZilAmount::from_raw(0));
+        assert_eq!(req.nonce, 7);
+
+        let burn = Zrc2Transition::Burn {
+            burn_account: addr(1),
+            amount: 5,
+        };
+
+        assert_eq!(
+            burn.to_request(addr(2), &caps, 1, 7, ZilAmount::from_raw(0), ScillaGas(0)),
+            Err(Zrc2Errors::Unsupported("Burn"))
+        );
+
+        let zero = Zrc2Transition::DecreaseAllowance {
+            spender: addr(1),
+            amount: 0,
+        };
+
+        assert_eq!(
+            zero.to_request(addr(2), &caps, 1, 7, ZilAmount::from_raw(0), ScillaGas(0)),
+            Err(Zrc2Errors::ZeroAmount)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use config::session::SPEND_ELEVATION_TTL_MS;
+use std::collections::HashMap;
+use zil_errors::session::SessionErrors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    View,  // public keys and addresses only
+    Spend, // needs the private keys
+}
+
+/// Session-scoped cache of public material. Reads never touch private keys,
+/// spending needs a short lived elevation on top of the unlocked session.
+#[derive(Debug)]
+pub struct CapabilityCache {
+    pub_keys: HashMap<usize, Vec<u8>>,
+    elevated_until: Option<u64>,
+    elevation_ttl: u64,
+}
+
+impl Default for CapabilityCache {
+    fn default() -> Self {
+        Self::new(SPEND_ELEVATION_TTL_MS)
+    }
+}
+
+impl CapabilityCache {
+    pub fn new(elevation_ttl: u64) -> Self {
+        Self {
+            pub_keys: HashMap::new(),
+            elevated_until: None,
+            elevation_ttl,
+        }
+    }
+
+    pub fn cache_pub_key(&mut self, index: usize, pub_key: Vec<u8>) {
+        self.pub_keys.insert(index, pub_key);
+    }
+
+    pub fn pub_key(&self, index: usize) -> Option<&[u8]> {
+        self.pub_keys.get(&index).map(|k| k.as_slice())
+    }
+
+    // Keeps the elevation, for when account indexes shift.
+    pub fn clear_pub_keys(&mut self) {
+        self.pub_keys.clear();
+    }
+
+    pub fn elevate(&mut self, now: u64) -> u64 {
+        let until = now.saturating_add(self.elevation_ttl);
+
+        self.elevated_until = Some(until);
+
+        until
+    }
+
+    pub fn drop_elevation(&mut self) {
+        self.elevated_until = None;
+    }
+
+    pub fn is_elevated(&self, now: u64) -> bool {
+        self.elevated_until.is_some_and(|until| now < until)
+    }
+
+    pub fn require(&self, capability: Capability, now: u64) -> Result<(), SessionErrors> {
+        match (capability, self.elevated_until) {
+            (Capability::View, _) => Ok(()),
+            (Capability::Spend, None) => Err(SessionErrors::SpendNotElevated),
+            (Capability::Spend, Some(until)) if now >= until => {
+                Err(SessionErrors::ElevationExpired)
+            }
+            (Capability::Spend, Some(_)) => Ok(()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pub_keys.clear();
+        self.elevated_until = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_without_elevation() {
+        let mut cache = CapabilityCache::new(1000);
+
+        cache.cache_pub_key(0, vec![2u8; 33]);
+
+        assert_eq!(cache.pub_key(0), Some([2u8; 33].as_slice()));
+        assert_eq!(cache.pub_key(1), None);
+        assert_eq!(cache.require(Capability::View, 0), Ok(()));
+        assert_eq!(
+            cache.require(Capability::Spend, 0),
+            Err(SessionErrors::SpendNotElevated)
+        );
+    }
+
+    #[test]
+    fn test_elevation_expires() {
+        let mut cache = CapabilityCache::new(1000);
+
+        assert_eq!(cache.elevate(500), 1500);
+        assert!(cache.is_elevated(1499));
+        assert_eq!(cache.require(Capability::Spend, 1499), Ok(()));
+        assert_eq!(
+            cache.require(Capability::Spend, 1500),
+            Err(SessionErrors::ElevationExpired)
+        );
+
+        cache.elevate(2000);
+        cache.drop_elevation();
+
+        assert_eq!(
+            cache.require(Capability::Spend, 2001),
+            Err(SessionErrors::SpendNotElevated)
+        );
+    }
+}
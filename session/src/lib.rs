@@ -1,18 +1,22 @@
+use capability::{Capability, CapabilityCache};
 use cipher::{
     aes::{aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE},
     keychain::KeyChain,
 };
 use config::argon::KEY_SIZE;
-use rand::{RngCore, SeedableRng};
-use rand_chacha::ChaCha20Rng;
+use crypto::entropy;
+use rand::RngCore;
 use zil_errors::session::SessionErrors;
 
+pub mod capability;
+
 pub const CIPHER_KEYCHAIN_SIZE: usize = 92;
 
 #[derive(Debug)]
 pub struct Session {
     cipher_keychain: [u8; CIPHER_KEYCHAIN_SIZE],
     pub is_enabdle: bool,
+    pub capabilities: CapabilityCache,
 }
 
 impl Default for Session {
@@ -20,6 +24,7 @@ impl Default for Session {
         Self {
             cipher_keychain: [0u8; CIPHER_KEYCHAIN_SIZE],
             is_enabdle: false,
+            capabilities: CapabilityCache::default(),
         }
     }
 }
@@ -28,7 +33,7 @@ impl Session {
     pub fn unlock(
         seed_bytes: &[u8; KEY_SIZE],
     ) -> Result<(Self, [u8; AES_GCM_KEY_SIZE]), SessionErrors> {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut key = [0u8; AES_GCM_KEY_SIZE];
 
         rng.fill_bytes(&mut key);
@@ -40,6 +45,7 @@ impl Session {
         let cipher_keychain = Self {
             cipher_keychain,
             is_enabdle: true,
+            capabilities: CapabilityCache::default(),
         };
 
         Ok((cipher_keychain, key))
@@ -62,8 +68,34 @@ impl Session {
         Ok(keychain)
     }
 
+    /// Grants spend capability until `now` + elevation TTL, the key is checked
+    /// by decrypting the keychain once.
+    pub fn elevate(
+        &mut self,
+        key: &[u8; AES_GCM_KEY_SIZE],
+        now: u64,
+    ) -> Result<u64, SessionErrors> {
+        self.decrypt_keychain(key)?;
+
+        Ok(self.capabilities.elevate(now))
+    }
+
+    pub fn spend_keychain(
+        &self,
+        key: &[u8; AES_GCM_KEY_SIZE],
+        now: u64,
+    ) -> Result<KeyChain, SessionErrors> {
+        if !self.is_enabdle {
+            return Err(SessionErrors::SessionNotEnabled);
+        }
+
+        self.capabilities.require(Capability::Spend, now)?;
+        self.decrypt_keychain(key)
+    }
+
     pub fn logout(&mut self) {
         self.is_enabdle = false;
+        self.capabilities.clear();
         self.cipher_keychain = [0u8; CIPHER_KEYCHAIN_SIZE];
     }
 }
@@ -71,14 +103,15 @@ impl Session {
 #[cfg(test)]
 mod tests {
     use cipher::{argon2::derive_key, keychain::KeyChain};
-    use rand::{RngCore, SeedableRng};
-    use rand_chacha::ChaCha20Rng;
+    use crypto::entropy;
+    use rand::RngCore;
 
     use crate::Session;
+    use zil_errors::session::SessionErrors;
 
     #[test]
     fn test_session_from_password() {
-        let mut rng = ChaCha20Rng::from_entropy();
+        let mut rng = entropy::rng();
         let mut password = [0u8; 100];
 
         rng.fill_bytes(&mut password);
@@ -100,4 +133,28 @@ mod tests {
         );
         assert_eq!(keychain.aes_key, keychain_shouldbe.aes_key);
     }
+
+    #[test]
+    fn test_spend_requires_elevation() {
+        let seed_bytes = derive_key(b"password").unwrap();
+        let (mut session, key) = Session::unlock(&seed_bytes).unwrap();
+
+        assert_eq!(
+            session.spend_keychain(&key, 0).err(),
+            Some(SessionErrors::SpendNotElevated)
+        );
+        assert!(session.elevate(&[0u8; 32], 0).is_err());
+
+        let until = session.elevate(&key, 0).unwrap();
+
+        assert!(session.spend_keychain(&key, until - 1).is_ok());
+        assert_eq!(
+            session.spend_keychain(&key, until).err(),
+            Some(SessionErrors::ElevationExpired)
+        );
+
+        session.logout();
+
+        assert!(!session.capabilities.is_elevated(0));
+    }
 }
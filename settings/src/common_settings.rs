@@ -13,7 +13,7 @@ impl Default for CommonSettings {
         Self {
             language: Language {},
             notificcations: Notificcations {},
-            storage: Storage {},
+            storage: Storage::default(),
             theme: Theme {},
         }
     }
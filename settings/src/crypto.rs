@@ -1,15 +1,23 @@
-use cipher::options::CipherOrders;
+use cipher::{ntrup::WorkBudget, options::CipherOrders};
+use config::file::KdfConfig;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct CryptoSettings {
     pub cipher_orders: Vec<CipherOrders>,
+    #[serde(default)]
+    pub work_budget: WorkBudget,
+    // Argon2 cost of the wallet password, kept so unlock derives the same key
+    #[serde(default)]
+    pub kdf: KdfConfig,
 }
 
 impl Default for CryptoSettings {
     fn default() -> Self {
         Self {
             cipher_orders: [CipherOrders::AESGCM256, CipherOrders::NTRUP1277].into(),
+            work_budget: WorkBudget::default(),
+            kdf: KdfConfig::default(),
         }
     }
 }
@@ -7,4 +7,5 @@ pub mod notificcations;
 pub mod security;
 pub mod storage;
 pub mod theme;
+pub mod transport;
 pub mod wallet_settings;
@@ -1,4 +1,94 @@
+use crate::transport::TransportConfig;
+use config::{contracts::STAKEING, MAIN_CHAIN_ID, MAIN_URL, MAIN_WS_URL};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
-pub struct Network {}
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Network {
+    pub chain_id: u16,
+    pub rpc_nodes: Vec<String>,
+    pub evm_rpc: Option<String>,
+    pub ws_url: Option<String>,
+    pub staking_contract: Option<String>,
+    pub indexer_url: Option<String>,
+    pub transport: TransportConfig,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            chain_id: MAIN_CHAIN_ID,
+            rpc_nodes: vec![MAIN_URL.to_string()],
+            evm_rpc: None,
+            ws_url: Some(MAIN_WS_URL.to_string()),
+            staking_contract: None,
+            indexer_url: None,
+            transport: TransportConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct NetworkCapabilities {
+    pub evm: bool,
+    pub websocket: bool,
+    pub staking: bool,
+    pub indexer: bool,
+}
+
+impl Network {
+    // The staking contract only exists on mainnet, custom networks built
+    // from `Default` don't inherit it.
+    pub fn mainnet() -> Self {
+        Self {
+            staking_contract: Some(STAKEING.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn capabilities(&self) -> NetworkCapabilities {
+        let is_set = |v: &Option<String>| v.as_ref().is_some_and(|s| !s.is_empty());
+
+        NetworkCapabilities {
+            evm: is_set(&self.evm_rpc),
+            websocket: is_set(&self.ws_url),
+            staking: is_set(&self.staking_contract),
+            indexer: is_set(&self.indexer_url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_empty_network() {
+        let network: Network = serde_json::from_str("{}").unwrap();
+
+        assert_eq!(network, Network::default());
+        assert!(!network.capabilities().staking);
+        assert!(Network::mainnet().capabilities().staking);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let network = Network {
+            chain_id: 333,
+            evm_rpc: Some("https://dev-api.zilliqa.com".to_string()),
+            ws_url: None,
+            staking_contract: Some(String::new()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            network.capabilities(),
+            NetworkCapabilities {
+                evm: true,
+                websocket: false,
+                staking: false,
+                indexer: false,
+            }
+        );
+    }
+}
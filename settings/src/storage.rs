@@ -1,2 +1,6 @@
-#[derive(Debug)]
-pub struct Storage {}
+use storage::retention::RetentionPolicy;
+
+#[derive(Debug, Default)]
+pub struct Storage {
+    pub retention: RetentionPolicy, // history and cached responses
+}
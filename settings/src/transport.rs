@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DnsOverride {
+    pub host: String,
+    pub addrs: Vec<SocketAddr>,
+}
+
+/// How RPC traffic of a network leaves the device.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TransportConfig {
+    // e.g. "socks5h://127.0.0.1:9050" for Tor, socks needs reqwest's `socks` feature
+    pub proxy: Option<String>,
+    pub dns: Vec<DnsOverride>,
+    // new proxy credentials on every node failover, Tor isolates circuits by them
+    pub rotate_circuit: bool,
+    pub timeout_ms: Option<u64>,
+}
@@ -1,7 +1,7 @@
 use crate::{crypto::CryptoSettings, currency::Currency, network::Network, security::Security};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct WalletSettings {
     pub crypto: CryptoSettings,
     pub currency: Currency,
@@ -14,7 +14,7 @@ impl Default for WalletSettings {
         Self {
             crypto: CryptoSettings::default(),
             currency: Currency {},
-            network: Network {},
+            network: Network::mainnet(),
             security: Security {},
         }
     }
@@ -0,0 +1,185 @@
+use crate::{data_warp::stored_hashsum, now_millis, LocalStorage};
+use config::{sha::SHA256_SIZE, storage::AUDIT_TREE};
+use serde::{Deserialize, Serialize};
+use zil_errors::storage::LocalStorageError;
+
+// Key and sealed value of an entry in the audit tree.
+pub(crate) type AuditRow = ([u8; 8], Vec<u8>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditAction {
+    Set,
+    Removed,
+    Cleared, // namespace emptied or dropped, `key` is empty
+}
+
+/// One mutation of the user's records. Hex strings, so the log can be
+/// handed to an auditor as JSON. `hashsum` is the one stored with the
+/// record, over the ciphertext when the storage is encrypted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub namespace: Option<String>,
+    pub key: String,
+    pub action: AuditAction,
+    pub hashsum: Option<String>,
+    pub timestamp: u64,
+    pub actor: String,
+}
+
+impl LocalStorage {
+    /// While set, every write and remove of the main records and namespaces
+    /// is appended to the audit log, tagged with `actor` (e.g. "ui",
+    /// "sync"). The log is never trimmed.
+    pub fn set_audit(&mut self, actor: Option<&str>) {
+        self.audit = actor.map(str::to_owned);
+    }
+
+    // Oldest first.
+    pub fn audit_log(&self) -> Result<Vec<AuditEntry>, LocalStorageError> {
+        self.open_tree(AUDIT_TREE)?
+            .iter()
+            .values()
+            .map(|value| {
+                let value =
+                    value.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+                serde_json::from_slice(&self.decrypt(AUDIT_TREE, &value)?)
+                    .or(Err(LocalStorageError::PayloadParseError))
+            })
+            .collect()
+    }
+
+    // Called once the mutation is committed, a failure can't undo it and
+    // reaches the writer as `StorageAuditError`. Plain writes log theirs in
+    // the same transaction, see `audit_row`.
+    pub(crate) fn record_audit(
+        &self,
+        tree: &[u8],
+        key: &[u8],
+        action: AuditAction,
+    ) -> Result<(), LocalStorageError> {
+        let hashsum = match action {
+            AuditAction::Set => self
+                .existing_tree(tree)?
+                .and_then(|tree| tree.get(key).ok().flatten())
+                .and_then(|bytes| stored_hashsum(&bytes)),
+            _ => None,
+        };
+
+        if let Some((id, row)) = self.audit_row(tree, key, action, hashsum)? {
+            self.open_tree(AUDIT_TREE)?
+                .insert(id, row)
+                .map_err(|e| LocalStorageError::StorageAuditError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    // Entry for a mutation of `tree`, None while the log is off or for
+    // bookkeeping trees.
+    pub(crate) fn audit_row(
+        &self,
+        tree: &[u8],
+        key: &[u8],
+        action: AuditAction,
+        hashsum: Option<[u8; SHA256_SIZE]>,
+    ) -> Result<Option<AuditRow>, LocalStorageError> {
+        let (Some(actor), Some(namespace)) = (&self.audit, self.namespace_of(tree)) else {
+            return Ok(None);
+        };
+        let entry = AuditEntry {
+            namespace: namespace.map(hex::encode),
+            key: hex::encode(key),
+            action,
+            hashsum: hashsum.map(hex::encode),
+            timestamp: now_millis().unwrap_or(0),
+            actor: actor.clone(),
+        };
+        let id = self
+            .tree
+            .generate_id()
+            .map_err(|e| LocalStorageError::StorageAuditError(e.to_string()))?;
+        let bytes = serde_json::to_vec(&entry)
+            .map_err(|e| LocalStorageError::StorageAuditError(e.to_string()))?;
+
+        Ok(Some((id.to_be_bytes(), self.encrypt(AUDIT_TREE, &bytes)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical::canonical_hashsum;
+
+    #[test]
+    fn test_audit_log() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"untracked", b"1").unwrap();
+        db.set_audit(Some("ui"));
+        db.set(b"settings", br#"{"theme":"dark"}"#).unwrap();
+        db.ns_set(b"contacts", b"alice", b"{}").unwrap();
+        db.set_with_ttl(b"gas_price", b"2000", 1000).unwrap();
+        db.set_audit(Some("sync"));
+        db.remove(b"settings").unwrap();
+        db.purge_namespace(b"contacts").unwrap();
+        db.set_audit(None);
+        db.set(b"untracked", b"2").unwrap();
+
+        let log = db.audit_log().unwrap();
+        let summary: Vec<_> = log
+            .iter()
+            .map(|e| {
+                (
+                    e.namespace.as_deref(),
+                    e.key.as_str(),
+                    e.action,
+                    e.actor.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    None,
+                    hex::encode("settings").as_str(),
+                    AuditAction::Set,
+                    "ui"
+                ),
+                (
+                    Some(hex::encode("contacts").as_str()),
+                    hex::encode("alice").as_str(),
+                    AuditAction::Set,
+                    "ui"
+                ),
+                (
+                    None,
+                    hex::encode("gas_price").as_str(),
+                    AuditAction::Set,
+                    "ui"
+                ),
+                (
+                    None,
+                    hex::encode("settings").as_str(),
+                    AuditAction::Removed,
+                    "sync"
+                ),
+                (
+                    Some(hex::encode("contacts").as_str()),
+                    "",
+                    AuditAction::Cleared,
+                    "sync"
+                ),
+            ]
+        );
+        assert_eq!(
+            log[0].hashsum,
+            Some(hex::encode(canonical_hashsum(br#"{"theme":"dark"}"#)))
+        );
+        assert_eq!(log[3].hashsum, None);
+        assert!(log.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{codec::Codec, now_millis, ttl::decode_expiry, LocalStorage};
+use config::storage::TTL_TREE;
+use sled::transaction::{
+    ConflictableTransactionError, TransactionError, Transactional, TransactionalTree,
+    UnabortableTransactionError,
+};
+use std::cell::RefCell;
+use zil_errors::storage::LocalStorageError;
+
+/// Records of the main tree inside a [LocalStorage::transaction]. Reads see
+/// the writes made earlier in the same transaction and go through the same
+/// decoding, expiry and migrations as `LocalStorage::get`.
+pub struct StorageTx<'a> {
+    storage: &'a LocalStorage,
+    tx: &'a TransactionalTree,
+    ttl: &'a TransactionalTree,
+    now: u64,
+    // sled conflicts must reach the retry loop, not the caller's closure
+    failure: RefCell<Option<UnabortableTransactionError>>,
+    changes: &'a RefCell<Vec<(Vec<u8>, bool)>>, // key, removed
+}
+
+impl StorageTx<'_> {
+    pub fn get(&self, key: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        if let Some(bytes) = self.ttl.get(key).map_err(|e| self.fail(e))? {
+            let at = decode_expiry(&self.storage.decrypt(TTL_TREE, &bytes)?)?;
+
+            if at <= self.now {
+                return Err(LocalStorageError::StorageDataNotFound);
+            }
+        }
+
+        let name = self.storage.tree.name();
+        let value = self
+            .tx
+            .get(key)
+            .map_err(|e| self.fail(e))?
+            .ok_or(LocalStorageError::StorageDataNotFound)?;
+        let (data, migrated) = self.storage.decode_record(&name, key, value)?;
+
+        if migrated {
+            let last_update = data.last_update.unwrap_or_default();
+            let record = self
+                .storage
+                .seal(&name, key, &data.payload, Codec::Json, last_update)?;
+
+            self.tx.insert(key, record).map_err(|e| self.fail(e))?;
+        }
+
+        Ok(data.payload)
+    }
+
+    pub fn set(&self, key: &[u8], payload: &[u8]) -> Result<(), LocalStorageError> {
+        let record = self.storage.seal(
+            &self.storage.tree.name(),
+            key,
+            payload,
+            Codec::Json,
+            self.now,
+        )?;
+
+        self.tx.insert(key, record).map_err(|e| self.fail(e))?;
+        self.ttl.remove(key).map_err(|e| self.fail(e))?;
+        self.changes.borrow_mut().push((key.to_vec(), false));
+
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        let removed = self.tx.remove(key).map_err(|e| self.fail(e))?;
+
+        self.ttl.remove(key).map_err(|e| self.fail(e))?;
+
+        if removed.is_some() {
+            self.changes.borrow_mut().push((key.to_vec(), true));
+        }
+
+        Ok(removed.is_some())
+    }
+
+    fn fail(&self, e: UnabortableTransactionError) -> LocalStorageError {
+        let message = e.to_string();
+
+        self.failure.borrow_mut().replace(e);
+
+        LocalStorageError::StorageAccessError(message)
+    }
+}
+
+impl LocalStorage {
+    /// Writes all pairs or none of them.
+    pub fn set_batch(&self, entries: &[(&[u8], &[u8])]) -> Result<(), LocalStorageError> {
+        self.writable()?;
+
+        let now = now_millis()?;
+        let mut batch = sled::Batch::default();
+
+        for (key, payload) in entries {
+            batch.insert(
+                *key,
+                self.seal(&self.tree.name(), key, payload, Codec::Json, now)?,
+            );
+        }
+
+        self.tree
+            .apply_batch(batch)
+            .or(Err(LocalStorageError::StorageWriteError))?;
+
+        for (key, _) in entries {
+            self.settle(key, false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read-modify-write over several keys. Returning an error aborts and
+    /// discards every write, `f` may run more than once on conflicts.
+    pub fn transaction<T, F>(&self, f: F) -> Result<T, LocalStorageError>
+    where
+        F: Fn(&StorageTx) -> Result<T, LocalStorageError>,
+    {
+        self.writable()?;
+
+        let now = now_millis()?;
+        // opened up front, creating a tree inside the transaction deadlocks
+        let ttl = self.open_tree(TTL_TREE)?;
+        // only the attempt that commits is reported
+        let changes = RefCell::new(Vec::new());
+        let res = (&*self.tree, &ttl).transaction(|(tx, ttl)| {
+            changes.borrow_mut().clear();
+
+            let storage_tx = StorageTx {
+                storage: self,
+                tx,
+                ttl,
+                now,
+                failure: RefCell::new(None),
+                changes: &changes,
+            };
+            let res = f(&storage_tx);
+
+            if let Some(e) = storage_tx.failure.take() {
+                return Err(e.into());
+            }
+
+            res.map_err(ConflictableTransactionError::Abort)
+        });
+
+        let res = res.map_err(|e| match e {
+            TransactionError::Abort(e) => e,
+            TransactionError::Storage(e) => LocalStorageError::StorageAccessError(e.to_string()),
+        })?;
+
+        for (key, removed) in changes.take() {
+            self.settle(&key, removed)?;
+        }
+
+        Ok(res)
+    }
+
+    // What `set` and `remove` do around the write itself.
+    fn settle(&self, key: &[u8], removed: bool) -> Result<(), LocalStorageError> {
+        self.cache().invalidate(&self.tree.name(), key);
+        self.tree_remove(TTL_TREE, key)?;
+
+        match removed {
+            true => self.bury(key)?,
+            false => self.unbury(key)?,
+        }
+
+        self.changed(&self.tree.name(), key, removed)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_and_transaction() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from_encrypted(&dir, b"password").unwrap();
+
+        db.set_batch(&[(b"accounts", b"[0]"), (b"next_index", b"1")])
+            .unwrap();
+
+        db.transaction(|tx| {
+            let next: u8 = String::from_utf8(tx.get(b"next_index")?)
+                .unwrap()
+                .parse()
+                .unwrap();
+
+            tx.set(b"accounts", format!("[0,{next}]").as_bytes())?;
+            tx.set(b"next_index", (next + 1).to_string().as_bytes())
+        })
+        .unwrap();
+
+        assert_eq!(db.get(b"accounts").unwrap(), b"[0,1]");
+        assert_eq!(db.get(b"next_index").unwrap(), b"2");
+
+        let res: Result<(), _> = db.transaction(|tx| {
+            tx.set(b"accounts", b"[]")?;
+            tx.get(b"missing").map(|_| ())
+        });
+
+        // the abort discards the write made before it
+        assert_eq!(res, Err(LocalStorageError::StorageDataNotFound));
+        assert_eq!(db.get(b"accounts").unwrap(), b"[0,1]");
+    }
+
+    #[test]
+    fn test_transaction_matches_set() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        db.set_compression(true);
+        db.set_tombstones(true);
+        db.set(b"big", "x".repeat(4096).as_bytes()).unwrap();
+        db.set_with_ttl(b"session", b"1", 0).unwrap();
+        db.set_with_ttl(b"draft", b"1", 0).unwrap();
+        db.set(b"gone", b"1").unwrap();
+        db.remove(b"gone").unwrap();
+
+        db.transaction(|tx| {
+            // decompressed like `get`, and an expired record reads as missing
+            assert_eq!(tx.get(b"big")?, "x".repeat(4096).as_bytes());
+            assert_eq!(
+                tx.get(b"session"),
+                Err(LocalStorageError::StorageDataNotFound)
+            );
+
+            tx.set(b"draft", b"2")?;
+            tx.get(b"draft")?;
+            tx.set(b"gone", b"back")
+        })
+        .unwrap();
+
+        // the write made the record permanent again
+        assert_eq!(db.ttl_remaining(b"draft").unwrap(), None);
+        assert_eq!(db.get(b"draft").unwrap(), b"2");
+
+        db.set_batch(&[(b"session", b"2")]).unwrap();
+
+        assert_eq!(db.get(b"session").unwrap(), b"2");
+        assert!(db.tombstones().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::data_warp::DataWarp;
+use config::sha::SHA256_SIZE;
+use std::collections::HashMap;
+
+type CacheKey = (sled::IVec, Vec<u8>); // tree name, record key
+
+/// Checked, decrypted and decompressed records by the hashsum stored with
+/// them, so a hot key skips the canonical hashsum. A record changed behind
+/// the cache (sync, snapshot import) has another hashsum and is read again.
+#[derive(Debug)]
+pub(crate) struct ReadCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<CacheKey, Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    hashsum: [u8; SHA256_SIZE],
+    data: DataWarp,
+    used: u64,
+}
+
+impl ReadCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn get(
+        &mut self,
+        tree: &sled::IVec,
+        key: &[u8],
+        hashsum: &[u8; SHA256_SIZE],
+    ) -> Option<DataWarp> {
+        self.tick += 1;
+
+        let entry = self.entries.get_mut(&(tree.clone(), key.to_vec()))?;
+
+        if &entry.hashsum != hashsum {
+            return None;
+        }
+
+        entry.used = self.tick;
+
+        Some(entry.data.clone())
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        tree: sled::IVec,
+        key: &[u8],
+        hashsum: [u8; SHA256_SIZE],
+        data: &DataWarp,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = (tree, key.to_vec());
+
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            // least recently used, the cache is small enough for a scan
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(
+            key,
+            Entry {
+                hashsum,
+                data: data.clone(),
+                used: self.tick,
+            },
+        );
+    }
+
+    pub(crate) fn invalidate(&mut self, tree: &sled::IVec, key: &[u8]) {
+        self.entries.remove(&(tree.clone(), key.to_vec()));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub(crate) fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalStorage;
+
+    fn record(payload: &[u8]) -> DataWarp {
+        crate::decode_data(&crate::encode_data(0, Default::default(), payload, 0)).unwrap()
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = ReadCache::new(2);
+        let tree = sled::IVec::from("main");
+
+        cache.insert(tree.clone(), b"a", [1; SHA256_SIZE], &record(b"1"));
+        cache.insert(tree.clone(), b"b", [2; SHA256_SIZE], &record(b"2"));
+
+        assert!(cache.get(&tree, b"a", &[1; SHA256_SIZE]).is_some());
+
+        cache.insert(tree.clone(), b"c", [3; SHA256_SIZE], &record(b"3"));
+
+        // `b` was used least recently
+        assert!(cache.get(&tree, b"b", &[2; SHA256_SIZE]).is_none());
+        assert!(cache.get(&tree, b"a", &[1; SHA256_SIZE]).is_some());
+        assert!(cache.get(&tree, b"a", &[9; SHA256_SIZE]).is_none());
+    }
+
+    #[test]
+    fn test_cached_reads() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"selected", b"0").unwrap();
+
+        assert_eq!(db.get(b"selected").unwrap(), b"0");
+        assert_eq!(db.get(b"selected").unwrap(), b"0");
+
+        db.set(b"selected", b"1").unwrap();
+
+        assert_eq!(db.get(b"selected").unwrap(), b"1");
+
+        // written past the cache, e.g. by sync
+        db.tree
+            .insert(
+                b"selected",
+                crate::encode_data(0, Default::default(), b"2", 0),
+            )
+            .unwrap();
+
+        assert_eq!(db.get(b"selected").unwrap(), b"2");
+        assert!(db.remove(b"selected").unwrap());
+        assert_eq!(
+            db.get(b"selected"),
+            Err(zil_errors::storage::LocalStorageError::StorageDataNotFound)
+        );
+    }
+}
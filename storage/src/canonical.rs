@@ -0,0 +1,217 @@
+use config::sha::SHA256_SIZE;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use sha2::{Digest, Sha256};
+use zil_errors::storage::LocalStorageError;
+
+// Largest integer an f64 can hold without losing precision (2^53).
+const MAX_SAFE_FLOAT_INT: f64 = 9_007_199_254_740_992.0;
+
+/// Digest behind `Data.hashsum`, recorded per record so records written
+/// before switching keep verifying. BLAKE3 is several times faster on large
+/// payloads, both give 32 bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashAlgo {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgo {
+    pub fn id(&self) -> u8 {
+        match self {
+            HashAlgo::Sha256 => 0,
+            HashAlgo::Blake3 => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, LocalStorageError> {
+        match id {
+            0 => Ok(HashAlgo::Sha256),
+            1 => Ok(HashAlgo::Blake3),
+            _ => Err(LocalStorageError::UnknownHashAlgo(id)),
+        }
+    }
+
+    pub fn digest(&self, bytes: &[u8]) -> [u8; SHA256_SIZE] {
+        match self {
+            HashAlgo::Sha256 => sha256(bytes),
+            HashAlgo::Blake3 => blake3::hash(bytes).into(),
+        }
+    }
+}
+
+pub fn to_canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+
+    write_value(value, &mut out);
+
+    out
+}
+
+/// Hashsum of a stored payload. JSON payloads are hashed in canonical form so
+/// the digest does not depend on map ordering or float formatting of the
+/// serializer that produced them, anything else is hashed as raw bytes.
+pub fn canonical_hashsum(payload: &[u8]) -> [u8; SHA256_SIZE] {
+    canonical_hashsum_with(HashAlgo::Sha256, payload)
+}
+
+pub fn canonical_hashsum_with(algo: HashAlgo, payload: &[u8]) -> [u8; SHA256_SIZE] {
+    match serde_json::from_slice::<Value>(payload) {
+        Ok(value) => algo.digest(to_canonical_json(&value).as_bytes()),
+        Err(_) => algo.digest(payload),
+    }
+}
+
+/// Hashsum as it was computed before canonicalization: SHA-256 over the
+/// payload exactly as serialized.
+pub fn legacy_hashsum(payload: &[u8]) -> [u8; SHA256_SIZE] {
+    sha256(payload)
+}
+
+pub fn verify_hashsum(payload: &[u8], hashsum: &[u8; SHA256_SIZE]) -> bool {
+    verify_hashsum_with(HashAlgo::Sha256, payload, hashsum)
+}
+
+// A canonical payload matches its plain digest, only other JSON is parsed.
+// Only SHA-256 records predate canonicalization.
+pub fn verify_hashsum_with(algo: HashAlgo, payload: &[u8], hashsum: &[u8; SHA256_SIZE]) -> bool {
+    algo.digest(payload) == *hashsum
+        || canonical_hashsum_with(algo, payload) == *hashsum
+        || (algo == HashAlgo::Sha256 && legacy_hashsum(payload) == *hashsum)
+}
+
+fn sha256(bytes: &[u8]) -> [u8; SHA256_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+
+    hasher.finalize().into()
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(n, out),
+        Value::String(s) => write_string(s, out),
+        Value::Array(arr) => {
+            out.push('[');
+
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                write_value(v, out);
+            }
+
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+
+            keys.sort();
+            out.push('{');
+
+            for (i, k) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                write_string(k, out);
+                out.push(':');
+                write_value(&map[k], out);
+            }
+
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(n: &Number, out: &mut String) {
+    if n.is_i64() || n.is_u64() {
+        out.push_str(&n.to_string());
+
+        return;
+    }
+
+    match n.as_f64() {
+        Some(f) if f.fract() == 0.0 && f.abs() < MAX_SAFE_FLOAT_INT => {
+            out.push_str(&(f as i64).to_string());
+        }
+        _ => out.push_str(&n.to_string()),
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    // serde_json escaping of a plain str can't fail
+    out.push_str(&serde_json::to_string(s).unwrap_or_default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sorted_keys() {
+        let a = br#"{"b":1,"a":{"d":[1,2],"c":"x"}}"#;
+        let b = br#"{ "a": { "c": "x", "d": [1, 2] }, "b": 1 }"#;
+
+        assert_eq!(canonical_hashsum(a), canonical_hashsum(b));
+        assert_eq!(
+            to_canonical_json(&serde_json::from_slice(a).unwrap()),
+            r#"{"a":{"c":"x","d":[1,2]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        let value = json!({ "int": 1.0, "float": 1.5, "neg": -2.0, "big": 1e300 });
+
+        assert_eq!(
+            to_canonical_json(&value),
+            r#"{"big":1e300,"float":1.5,"int":1,"neg":-2}"#
+        );
+        assert_eq!(canonical_hashsum(b"[1.0]"), canonical_hashsum(b"[1]"));
+    }
+
+    #[test]
+    fn test_non_json_payload() {
+        let payload = [255u8, 0, 1, 2];
+
+        assert_eq!(canonical_hashsum(&payload), legacy_hashsum(&payload));
+    }
+
+    #[test]
+    fn test_blake3() {
+        let a = br#"{"b":1,"a":2}"#;
+        let b = br#"{ "a": 2, "b": 1 }"#;
+        let hashsum = canonical_hashsum_with(HashAlgo::Blake3, a);
+
+        assert_eq!(hashsum, canonical_hashsum_with(HashAlgo::Blake3, b));
+        assert_ne!(hashsum, canonical_hashsum(a));
+        assert!(verify_hashsum_with(HashAlgo::Blake3, b, &hashsum));
+        assert!(!verify_hashsum(b, &hashsum));
+        assert_eq!(
+            HashAlgo::from_id(HashAlgo::Blake3.id()),
+            Ok(HashAlgo::Blake3)
+        );
+        assert_eq!(
+            HashAlgo::from_id(7),
+            Err(LocalStorageError::UnknownHashAlgo(7))
+        );
+    }
+
+    #[test]
+    fn test_verify_legacy() {
+        let payload = br#"{"b": 1, "a": 2}"#;
+        let legacy = legacy_hashsum(payload);
+        let canonical = canonical_hashsum(payload);
+
+        assert_ne!(legacy, canonical);
+        assert!(verify_hashsum(payload, &legacy));
+        assert!(verify_hashsum(payload, &canonical));
+        assert!(!verify_hashsum(b"{}", &canonical));
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use zil_errors::storage::LocalStorageError;
+
+/// How a typed payload is serialized. Recorded per record, so switching the
+/// codec of a storage leaves older JSON records readable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Codec {
+    #[default]
+    Json,
+    Cbor,
+}
+
+impl Codec {
+    pub fn id(&self) -> u8 {
+        match self {
+            Codec::Json => 0,
+            Codec::Cbor => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, LocalStorageError> {
+        match id {
+            0 => Ok(Codec::Json),
+            1 => Ok(Codec::Cbor),
+            _ => Err(LocalStorageError::UnknownCodec(id)),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, LocalStorageError> {
+        match self {
+            Codec::Json => serde_json::to_vec(value)
+                .map_err(|e| LocalStorageError::PayloadEncodeError(e.to_string())),
+            Codec::Cbor => {
+                let mut bytes = Vec::new();
+
+                ciborium::into_writer(value, &mut bytes)
+                    .map_err(|e| LocalStorageError::PayloadEncodeError(e.to_string()))?;
+
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, LocalStorageError> {
+        match self {
+            Codec::Json => {
+                serde_json::from_slice(bytes).or(Err(LocalStorageError::PayloadParseError))
+            }
+            Codec::Cbor => {
+                ciborium::from_reader(bytes).or(Err(LocalStorageError::PayloadParseError))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalStorage;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        name: String,
+        balances: Vec<u64>,
+    }
+
+    #[test]
+    fn test_mixed_codecs() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+        let record = Record {
+            name: "zil1".to_string(),
+            balances: vec![1, 200, 30_000],
+        };
+
+        db.set_value(b"old", &record).unwrap();
+        db.set_codec(Codec::Cbor);
+        db.set_value(b"new", &record).unwrap();
+
+        let old = db.get_data(b"old").unwrap();
+        let new = db.get_data(b"new").unwrap();
+
+        assert_eq!(old.codec, Codec::Json);
+        assert_eq!(new.codec, Codec::Cbor);
+        assert!(new.payload.len() < old.payload.len());
+        assert_eq!(db.get_value::<Record>(b"old").unwrap(), record);
+        assert_eq!(db.get_value::<Record>(b"new").unwrap(), record);
+
+        let scanned: Vec<(String, Record)> = db.scan_prefix("").map(Result::unwrap).collect();
+
+        assert_eq!(scanned.len(), 2);
+        assert_eq!(Codec::from_id(7), Err(LocalStorageError::UnknownCodec(7)));
+    }
+}
@@ -0,0 +1,447 @@
+use crate::{namespace::Namespace, now_millis, LocalStorage};
+use config::storage::INDEX_TREE_PREFIX;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sled::{
+    transaction::{ConflictableTransactionError, TransactionError, Transactional},
+    Tree,
+};
+use std::marker::PhantomData;
+use zil_errors::storage::LocalStorageError;
+
+// Index keys of a record, e.g. both addresses of a transaction.
+pub type IndexKeys<T> = fn(&T) -> Vec<Vec<u8>>;
+
+/// Typed records of one namespace, e.g. `Collection::<Account, Address>`.
+/// Keys that serialize to a string (addresses, names) are stored as that
+/// text, any other key as its JSON.
+pub struct Collection<'a, T, K = String> {
+    ns: Namespace<'a>,
+    indexes: Vec<Index<T>>,
+    _types: PhantomData<fn() -> (K, T)>,
+}
+
+struct Index<T> {
+    name: String,
+    tree: Tree,
+    keys: IndexKeys<T>,
+}
+
+impl<'a, T, K> Collection<'a, T, K>
+where
+    T: Serialize + DeserializeOwned,
+    K: Serialize + DeserializeOwned,
+{
+    pub fn new(storage: &'a LocalStorage, name: &str) -> Result<Self, LocalStorageError> {
+        Ok(Self {
+            ns: storage.open_namespace(name)?,
+            indexes: Vec::new(),
+            _types: PhantomData,
+        })
+    }
+
+    /// Secondary index kept in the same transaction as the records, built
+    /// from the existing ones when it is new. Index keys are stored as
+    /// given, also in an encrypted storage.
+    pub fn with_index(mut self, name: &str, keys: IndexKeys<T>) -> Result<Self, LocalStorageError> {
+        let tree = self.ns.storage.open_tree(
+            &[
+                INDEX_TREE_PREFIX,
+                &self.ns.tree.name(),
+                b":",
+                name.as_bytes(),
+            ]
+            .concat(),
+        )?;
+        let backfill = tree.is_empty() && !self.ns.is_empty();
+
+        self.indexes.push(Index {
+            name: name.to_string(),
+            tree,
+            keys,
+        });
+
+        if backfill {
+            self.reindex()?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn insert(&self, key: &K, value: &T) -> Result<(), LocalStorageError> {
+        let storage = self.ns.storage;
+        let payload = storage.codec.encode(value)?;
+        let key = encode_key(key)?;
+
+        if self.indexes.is_empty() {
+            return storage.write_as(&self.ns.tree, &key, &payload, now_millis()?, storage.codec);
+        }
+
+        // a single writer holds the storage lock, the old record can't move
+        let old = self.get_raw(&key)?;
+        let record = storage.seal(
+            &self.ns.tree.name(),
+            &key,
+            &payload,
+            storage.codec,
+            now_millis()?,
+        )?;
+
+        let mut changes = Vec::with_capacity(self.indexes.len());
+
+        for index in &self.indexes {
+            let stale = self.entries(index, old.iter().flat_map(index.keys), &key)?;
+            let fresh = self.entries(index, (index.keys)(value), &key)?;
+
+            changes.push((stale, fresh));
+        }
+
+        storage.writable()?;
+        storage.cache().invalidate(&self.ns.tree.name(), &key);
+
+        self.update(|tx| {
+            tx[0].insert(key.as_slice(), record.clone())?;
+
+            for ((stale, fresh), tree) in changes.iter().zip(&tx[1..]) {
+                for entry in stale {
+                    tree.remove(entry.as_slice())?;
+                }
+
+                for entry in fresh {
+                    tree.insert(entry.as_slice(), &[])?;
+                }
+            }
+
+            Ok(())
+        })?;
+        storage.changed(&self.ns.tree.name(), &key, false)?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, key: &K) -> Result<Option<T>, LocalStorageError> {
+        self.get_raw(&encode_key(key)?)
+    }
+
+    // Records with `index_key` in the index, in key order.
+    pub fn find_by(&self, index: &str, index_key: &[u8]) -> Result<Vec<(K, T)>, LocalStorageError> {
+        let index = self.index(index)?;
+        let prefix = self.entries(index, [index_key.to_vec()], &[])?.remove(0);
+
+        index
+            .tree
+            .scan_prefix(&prefix)
+            .keys()
+            .map(|entry| {
+                let entry =
+                    entry.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+                let key = &entry[prefix.len()..];
+                let value = self
+                    .get_raw(key)?
+                    .ok_or(LocalStorageError::StorageDataNotFound)?;
+
+                Ok((decode_key(key)?, value))
+            })
+            .collect()
+    }
+
+    /// Builds every index again from the records.
+    pub fn reindex(&self) -> Result<(), LocalStorageError> {
+        self.ns.storage.writable()?;
+
+        for index in &self.indexes {
+            index
+                .tree
+                .clear()
+                .or(Err(LocalStorageError::StorageWriteError))?;
+
+            for key in self.ns.tree.iter().keys() {
+                let key = key.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+                if let Some(value) = self.get_raw(&key)? {
+                    for entry in self.entries(index, (index.keys)(&value), &key)? {
+                        index
+                            .tree
+                            .insert(entry, &[])
+                            .or(Err(LocalStorageError::StorageWriteError))?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn contains(&self, key: &K) -> Result<bool, LocalStorageError> {
+        self.ns
+            .tree
+            .contains_key(encode_key(key)?)
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))
+    }
+
+    pub fn remove(&self, key: &K) -> Result<bool, LocalStorageError> {
+        let key = encode_key(key)?;
+
+        if self.indexes.is_empty() {
+            return self.ns.remove(&key);
+        }
+
+        let Some(old) = self.get_raw(&key)? else {
+            return Ok(false);
+        };
+        let stale = self
+            .indexes
+            .iter()
+            .map(|index| self.entries(index, (index.keys)(&old), &key))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.ns.storage.writable()?;
+        self.ns
+            .storage
+            .cache()
+            .invalidate(&self.ns.tree.name(), &key);
+
+        self.update(|tx| {
+            tx[0].remove(key.as_slice())?;
+
+            for (entries, tree) in stale.iter().zip(&tx[1..]) {
+                for entry in entries {
+                    tree.remove(entry.as_slice())?;
+                }
+            }
+
+            Ok(())
+        })?;
+        self.ns.storage.changed(&self.ns.tree.name(), &key, true)?;
+
+        Ok(true)
+    }
+
+    // In key order.
+    pub fn iter(&self) -> impl Iterator<Item = Result<(K, T), LocalStorageError>> + '_ {
+        self.ns.tree.iter().keys().map(move |key| {
+            let key = key.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+            let data = self.ns.storage.read(&self.ns.tree, &key)?;
+
+            Ok((decode_key(&key)?, data.codec.decode(&data.payload)?))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.ns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ns.is_empty()
+    }
+
+    fn get_raw(&self, key: &[u8]) -> Result<Option<T>, LocalStorageError> {
+        match self.ns.storage.read(&self.ns.tree, key) {
+            Ok(data) => data.codec.decode(&data.payload).map(Some),
+            Err(LocalStorageError::StorageDataNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Entries of `key` in `index`, index keys as the storage keeps them.
+    fn entries(
+        &self,
+        index: &Index<T>,
+        index_keys: impl IntoIterator<Item = Vec<u8>>,
+        key: &[u8],
+    ) -> Result<Vec<Vec<u8>>, LocalStorageError> {
+        let tree = index.tree.name();
+
+        index_keys
+            .into_iter()
+            .map(|index_key| {
+                let index_key = self.ns.storage.index_key(&tree, &index_key)?;
+
+                Ok(index_entry(&index_key, key))
+            })
+            .collect()
+    }
+
+    fn index(&self, name: &str) -> Result<&Index<T>, LocalStorageError> {
+        self.indexes
+            .iter()
+            .find(|index| index.name == name)
+            .ok_or_else(|| LocalStorageError::StorageIndexNotFound(name.to_string()))
+    }
+
+    // The records tree first, then one tree per index.
+    fn update<F>(&self, f: F) -> Result<(), LocalStorageError>
+    where
+        F: Fn(&[sled::transaction::TransactionalTree]) -> Result<(), TxError>,
+    {
+        let trees: Vec<&Tree> = std::iter::once(&self.ns.tree)
+            .chain(self.indexes.iter().map(|index| &index.tree))
+            .collect();
+
+        trees
+            .as_slice()
+            .transaction(|tx| f(tx))
+            .map_err(|e: TransactionError<()>| match e {
+                TransactionError::Storage(e) => {
+                    LocalStorageError::StorageAccessError(e.to_string())
+                }
+                // nothing aborts, conflicts are retried by sled
+                TransactionError::Abort(()) => LocalStorageError::StorageWriteError,
+            })
+    }
+}
+
+type TxError = ConflictableTransactionError<()>;
+
+// Length prefixed, an index key never runs into the record key after it.
+fn index_entry(index_key: &[u8], key: &[u8]) -> Vec<u8> {
+    let len = u16::try_from(index_key.len()).unwrap_or(u16::MAX);
+
+    [&len.to_be_bytes(), &index_key[..len as usize], key].concat()
+}
+
+fn encode_key<K: Serialize>(key: &K) -> Result<Vec<u8>, LocalStorageError> {
+    match serde_json::to_value(key).or(Err(LocalStorageError::PayloadParseError))? {
+        Value::String(text) => Ok(text.into_bytes()),
+        other => Ok(other.to_string().into_bytes()),
+    }
+}
+
+// The text form is tried first, so a `String` key never reads as JSON.
+fn decode_key<K: DeserializeOwned>(bytes: &[u8]) -> Result<K, LocalStorageError> {
+    let text = std::str::from_utf8(bytes).or(Err(LocalStorageError::PayloadParseError))?;
+
+    serde_json::from_value(Value::String(text.to_string()))
+        .or_else(|_| serde_json::from_str(text))
+        .or(Err(LocalStorageError::PayloadParseError))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Token {
+        symbol: String,
+        decimals: u8,
+    }
+
+    fn token(symbol: &str) -> Token {
+        Token {
+            symbol: symbol.to_string(),
+            decimals: 12,
+        }
+    }
+
+    #[test]
+    fn test_collection() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+        let tokens = Collection::<Token>::new(&db, "tokens").unwrap();
+
+        tokens.insert(&"zil".to_string(), &token("ZIL")).unwrap();
+        tokens
+            .insert(&"\"zlp\"".to_string(), &token("ZLP"))
+            .unwrap();
+
+        assert_eq!(tokens.get(&"zil".to_string()).unwrap(), Some(token("ZIL")));
+        assert_eq!(tokens.get(&"gzil".to_string()).unwrap(), None);
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|entry| entry.unwrap().0)
+                .collect::<Vec<_>>(),
+            vec!["\"zlp\"".to_string(), "zil".to_string()]
+        );
+        assert!(tokens.remove(&"zil".to_string()).unwrap());
+        assert!(!tokens.contains(&"zil".to_string()).unwrap());
+
+        let by_index = Collection::<Token, (u16, u64)>::new(&db, "blocks").unwrap();
+
+        by_index.insert(&(1, 42), &token("ZIL")).unwrap();
+
+        assert_eq!(
+            by_index.iter().next().unwrap().unwrap(),
+            ((1, 42), token("ZIL"))
+        );
+        assert_eq!(tokens.len() + by_index.len(), 2);
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Transfer {
+        from: String,
+        to: String,
+        block: u64,
+    }
+
+    fn transfer(from: &str, to: &str, block: u64) -> Transfer {
+        Transfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            block,
+        }
+    }
+
+    fn by_address(tx: &Transfer) -> Vec<Vec<u8>> {
+        vec![tx.from.clone().into_bytes(), tx.to.clone().into_bytes()]
+    }
+
+    fn by_block(tx: &Transfer) -> Vec<Vec<u8>> {
+        vec![tx.block.to_be_bytes().to_vec()]
+    }
+
+    #[test]
+    fn test_secondary_index() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+        let history = Collection::<Transfer>::new(&db, "history").unwrap();
+
+        // written before the index existed
+        history
+            .insert(&"0x01".to_string(), &transfer("alice", "bob", 1))
+            .unwrap();
+
+        let history = history
+            .with_index("address", by_address)
+            .unwrap()
+            .with_index("block", by_block)
+            .unwrap();
+
+        history
+            .insert(&"0x02".to_string(), &transfer("bob", "carol", 2))
+            .unwrap();
+        history
+            .insert(&"0x03".to_string(), &transfer("carol", "alice", 2))
+            .unwrap();
+
+        let hashes = |index: &str, key: &[u8]| -> Vec<String> {
+            history
+                .find_by(index, key)
+                .unwrap()
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect()
+        };
+
+        assert_eq!(hashes("address", b"alice"), vec!["0x01", "0x03"]);
+        assert_eq!(hashes("address", b"bob"), vec!["0x01", "0x02"]);
+        assert_eq!(hashes("block", &2u64.to_be_bytes()), vec!["0x02", "0x03"]);
+
+        // entries of the old value go along with it
+        history
+            .insert(&"0x01".to_string(), &transfer("dave", "bob", 1))
+            .unwrap();
+        assert!(history.remove(&"0x02".to_string()).unwrap());
+
+        assert_eq!(hashes("address", b"alice"), vec!["0x03"]);
+        assert_eq!(hashes("address", b"bob"), vec!["0x01"]);
+        assert_eq!(hashes("block", &2u64.to_be_bytes()), vec!["0x03"]);
+        assert_eq!(
+            history.find_by("amount", b"1"),
+            Err(LocalStorageError::StorageIndexNotFound(
+                "amount".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{lock, LocalStorage};
+use config::storage::{COMPACT_NEW_SUFFIX, COMPACT_OLD_SUFFIX};
+use std::{fs, path::Path};
+use zil_errors::storage::LocalStorageError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Compaction {
+    pub before: u64, // bytes on disk
+    pub after: u64,
+}
+
+impl Compaction {
+    pub fn reclaimed(&self) -> u64 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+impl LocalStorage {
+    /// sled never gives space of removed records back to the OS. This copies
+    /// every tree into a fresh database next to the current one and swaps the
+    /// directories, worth it after heavy pruning or garbage collection.
+    pub fn compact(&mut self) -> Result<Compaction, LocalStorageError> {
+        self.writable()?;
+        self.flush()?;
+
+        let access = |e: std::io::Error| LocalStorageError::StorageAccessError(e.to_string());
+        let sled_err = |e: sled::Error| LocalStorageError::StorageAccessError(e.to_string());
+        let before = self.get_db_size();
+        let fresh_path = format!("{}{COMPACT_NEW_SUFFIX}", self.path);
+        let old_path = format!("{}{COMPACT_OLD_SUFFIX}", self.path);
+
+        if Path::new(&fresh_path).exists() {
+            fs::remove_dir_all(&fresh_path).map_err(access)?;
+        }
+
+        // locked before the swap, so the path is never open to others
+        let fresh_lock = lock::acquire(&fresh_path)?;
+        let fresh = sled::open(&fresh_path).map_err(sled_err)?;
+
+        fresh.import(self.tree.export());
+        fresh.flush().map_err(sled_err)?;
+        drop(fresh);
+
+        // sled must let go of the old files before they move
+        let placeholder = sled::Config::new()
+            .temporary(true)
+            .open()
+            .map_err(sled_err)?;
+
+        drop(std::mem::replace(&mut self.tree, placeholder));
+        self.swap_in(&fresh_path, &old_path)?;
+        self._lock = Some(fresh_lock);
+        self.cache().clear();
+        fs::remove_dir_all(&old_path).map_err(access)?;
+
+        Ok(Compaction {
+            before,
+            after: self.get_db_size(),
+        })
+    }
+
+    // On failure the original files are put back and reopened before the
+    // error is returned, writes never land in the placeholder.
+    fn swap_in(&mut self, fresh_path: &str, old_path: &str) -> Result<(), LocalStorageError> {
+        let access = |e: std::io::Error| LocalStorageError::StorageAccessError(e.to_string());
+        let sled_err = |e: sled::Error| LocalStorageError::StorageAccessError(e.to_string());
+        let swapped = fs::rename(&self.path, old_path).map_err(access).map(|_| {
+            fs::rename(fresh_path, &self.path)
+                .map_err(access)
+                .and_then(|_| sled::open(&self.path).map_err(sled_err))
+        });
+        let error = match swapped {
+            Ok(Ok(tree)) => {
+                self.tree = tree;
+
+                return Ok(());
+            }
+            Err(e) => e, // nothing moved
+            Ok(Err(e)) => {
+                if Path::new(&self.path).exists() {
+                    fs::rename(&self.path, fresh_path).map_err(access)?;
+                }
+
+                fs::rename(old_path, &self.path).map_err(access)?;
+
+                e
+            }
+        };
+
+        self.tree = sled::open(&self.path).map_err(sled_err)?;
+
+        Err(error)
+    }
+}
+
+// A compaction interrupted between the two renames left no database at
+// `path`, the old one is put back.
+pub(crate) fn recover(path: &str) -> Result<(), LocalStorageError> {
+    let old_path = format!("{path}{COMPACT_OLD_SUFFIX}");
+
+    if !Path::new(path).exists() && Path::new(&old_path).exists() {
+        fs::rename(&old_path, path)
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+// Leftovers of an interrupted compaction, only safe with the lock held.
+pub(crate) fn remove_leftovers(path: &str) -> Result<(), LocalStorageError> {
+    for suffix in [COMPACT_NEW_SUFFIX, COMPACT_OLD_SUFFIX] {
+        let leftover = format!("{path}{suffix}");
+
+        if Path::new(&leftover).exists() {
+            fs::remove_dir_all(&leftover)
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+        let payload = vec![7u8; 4096];
+
+        for i in 0..500u32 {
+            db.set(&i.to_be_bytes(), &payload).unwrap();
+        }
+
+        db.ns_set(b"tokens", b"zlp", b"{}").unwrap();
+
+        for i in 10..500u32 {
+            db.remove(&i.to_be_bytes()).unwrap();
+        }
+
+        let compaction = db.compact().unwrap();
+
+        assert!(compaction.reclaimed() > 0);
+        assert_eq!(db.get(&9u32.to_be_bytes()).unwrap(), payload);
+        assert_eq!(db.ns_get(b"tokens", b"zlp").unwrap(), b"{}");
+        assert!(db.get(&10u32.to_be_bytes()).is_err());
+        assert!(!Path::new(&format!("{dir}{COMPACT_OLD_SUFFIX}")).exists());
+        // still locked after the swap
+        assert_eq!(
+            LocalStorage::from(&dir).err(),
+            Some(LocalStorageError::StorageLockedByAnotherProcess)
+        );
+
+        drop(db);
+
+        // interrupted between the renames
+        fs::rename(&dir, format!("{dir}{COMPACT_OLD_SUFFIX}")).unwrap();
+
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        assert_eq!(db.get(&9u32.to_be_bytes()).unwrap(), payload);
+
+        // a failed swap leaves the original database in use
+        let blocker = format!("{dir}{COMPACT_OLD_SUFFIX}");
+
+        fs::create_dir_all(&blocker).unwrap();
+        fs::write(format!("{blocker}/file"), b"").unwrap();
+
+        assert!(db.compact().is_err());
+
+        db.set(b"after", b"1").unwrap();
+        drop(db);
+
+        let db = LocalStorage::from(&dir).unwrap();
+
+        assert_eq!(db.get(b"after").unwrap(), b"1");
+        assert_eq!(db.get(&9u32.to_be_bytes()).unwrap(), payload);
+    }
+}
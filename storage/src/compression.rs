@@ -0,0 +1,59 @@
+use config::storage::{STORAGE_COMPRESSION_LEVEL, STORAGE_COMPRESSION_MIN_SIZE};
+use std::borrow::Cow;
+use zil_errors::storage::LocalStorageError;
+
+// Small values and ones zstd can't shrink are kept as they are.
+pub fn compress(payload: &[u8]) -> Result<Cow<'_, [u8]>, LocalStorageError> {
+    if payload.len() < STORAGE_COMPRESSION_MIN_SIZE {
+        return Ok(Cow::Borrowed(payload));
+    }
+
+    let compressed = zstd::bulk::compress(payload, STORAGE_COMPRESSION_LEVEL)
+        .map_err(|e| LocalStorageError::StorageCompressionError(e.to_string()))?;
+
+    if compressed.len() < payload.len() {
+        Ok(Cow::Owned(compressed))
+    } else {
+        Ok(Cow::Borrowed(payload))
+    }
+}
+
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+    zstd::stream::decode_all(bytes)
+        .map_err(|e| LocalStorageError::StorageCompressionError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocalStorage;
+
+    #[test]
+    fn test_compressed_values() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+        let history = serde_json::to_vec(&vec!["0x1a2b3c4d5e6f"; 200]).unwrap();
+
+        db.set(b"plain", &history).unwrap();
+        db.set_compression(true);
+        db.set(b"history", &history).unwrap();
+        db.set(b"small", b"{}").unwrap();
+
+        let raw = |key: &[u8]| crate::read_data(&db.tree, key).unwrap();
+
+        assert!(raw(b"history").compressed);
+        assert!(raw(b"history").payload.len() < history.len() / 4);
+        assert!(!raw(b"small").compressed);
+        assert_eq!(db.get(b"history").unwrap(), history);
+        assert_eq!(db.get(b"plain").unwrap(), history);
+        assert_eq!(
+            db.get_data(b"history").unwrap().hashsum,
+            db.get_data(b"plain").unwrap().hashsum
+        );
+
+        drop(db);
+
+        let db = LocalStorage::from_encrypted(&dir, b"password").unwrap();
+
+        assert_eq!(db.get(b"history").unwrap(), history);
+    }
+}
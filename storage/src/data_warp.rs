@@ -1,31 +1,58 @@
+use crate::canonical::HashAlgo;
+use crate::codec::Codec;
 use bincode::{FromBytes, ToVecBytes};
+use config::sha::SHA256_SIZE;
 use std::borrow::Cow;
 use std::mem::size_of;
 use zil_errors::storage::LocalStorageError;
 
-#[derive(Debug)]
+// The payload length is a u64 on every target, a record written natively
+// reads back on wasm32.
+const PAYLOAD_LEN_SIZE: usize = size_of::<u64>();
+const LAST_UPDATE_TRAILER_SIZE: usize = SHA256_SIZE + size_of::<u64>();
+const CODEC_TRAILER_SIZE: usize = LAST_UPDATE_TRAILER_SIZE + size_of::<u8>();
+const FLAGS_TRAILER_SIZE: usize = CODEC_TRAILER_SIZE + size_of::<u8>();
+const HASH_TRAILER_SIZE: usize = FLAGS_TRAILER_SIZE + size_of::<u8>();
+const DIGEST_TRAILER_SIZE: usize = HASH_TRAILER_SIZE + SHA256_SIZE;
+const FLAG_COMPRESSED: u8 = 1;
+
+#[derive(Debug, Clone)]
 pub struct DataWarp {
     pub payload: Vec<u8>,
     // Storage verions
     pub version: u16,
+    // Records written before hashsums were introduced have none
+    pub hashsum: Option<[u8; SHA256_SIZE]>,
+    // Unix time in milliseconds of the last write, stored only along with hashsum
+    pub last_update: Option<u64>,
+    // Stored after last_update, only for payloads that are not JSON
+    pub codec: Codec,
+    // zstd, flagged in a byte after the codec
+    pub compressed: bool,
+    // After the flags, only for hashsums that are not SHA-256
+    pub hash: HashAlgo,
+    // Plain `hash` digest of the payload bytes, last in the trailer. Reads
+    // verify it instead of re-serialising the payload to canonical JSON.
+    pub digest: Option<[u8; SHA256_SIZE]>,
 }
 
 impl FromBytes for DataWarp {
     type Error = LocalStorageError;
 
     fn from_bytes(bytes: Cow<[u8]>) -> Result<Self, Self::Error> {
-        if bytes.len() < size_of::<usize>() + size_of::<u16>() {
+        if bytes.len() < PAYLOAD_LEN_SIZE + size_of::<u16>() {
             return Err(LocalStorageError::InsufficientBytes);
         }
 
-        let (len_bytes, rest) = bytes.split_at(size_of::<usize>());
+        let (len_bytes, rest) = bytes.split_at(PAYLOAD_LEN_SIZE);
 
-        let payload_len = usize::from_le_bytes(
+        let payload_len = u64::from_le_bytes(
             len_bytes
-                .as_ref()
                 .try_into()
                 .map_err(|_| LocalStorageError::PayloadLengthError)?,
         );
+        let payload_len =
+            usize::try_from(payload_len).or(Err(LocalStorageError::InvalidBytesSizeOverflow))?;
         let remains_len = payload_len
             .checked_add(size_of::<u16>())
             .ok_or(LocalStorageError::InvalidBytesSizeOverflow)?;
@@ -35,30 +62,130 @@ impl FromBytes for DataWarp {
         }
 
         let (payload, version_bytes) = rest.split_at(payload_len);
+        let (version_bytes, trailer) = version_bytes.split_at(size_of::<u16>());
         let version = u16::from_le_bytes(
-            version_bytes[..size_of::<u16>()]
+            version_bytes
                 .try_into()
                 .or(Err(LocalStorageError::PayloadVersionParseError))?,
         );
+        let (hashsum, last_update, codec, flags, hash, digest) = match trailer.len() {
+            0 => (None, None, Codec::Json, 0, HashAlgo::Sha256, None),
+            SHA256_SIZE => (
+                Some(parse_hashsum(trailer)?),
+                None,
+                Codec::Json,
+                0,
+                HashAlgo::Sha256,
+                None,
+            ),
+            LAST_UPDATE_TRAILER_SIZE
+            | CODEC_TRAILER_SIZE
+            | FLAGS_TRAILER_SIZE
+            | HASH_TRAILER_SIZE
+            | DIGEST_TRAILER_SIZE => {
+                let (hashsum_bytes, rest) = trailer.split_at(SHA256_SIZE);
+                let (last_update_bytes, codec_bytes) = rest.split_at(size_of::<u64>());
+                let last_update = u64::from_le_bytes(
+                    last_update_bytes
+                        .try_into()
+                        .or(Err(LocalStorageError::InsufficientBytes))?,
+                );
+                let codec = match codec_bytes.first() {
+                    Some(id) => Codec::from_id(*id)?,
+                    None => Codec::Json,
+                };
+                let flags = codec_bytes.get(1).copied().unwrap_or(0);
+                let hash = match codec_bytes.get(2) {
+                    Some(id) => HashAlgo::from_id(*id)?,
+                    None => HashAlgo::Sha256,
+                };
+                let digest = match codec_bytes.get(3..) {
+                    Some(bytes) if !bytes.is_empty() => Some(parse_hashsum(bytes)?),
+                    _ => None,
+                };
+
+                (
+                    Some(parse_hashsum(hashsum_bytes)?),
+                    Some(last_update),
+                    codec,
+                    flags,
+                    hash,
+                    digest,
+                )
+            }
+            _ => return Err(LocalStorageError::InsufficientBytes),
+        };
 
         Ok(Self {
             payload: payload.to_vec(),
             version,
+            hashsum,
+            last_update,
+            codec,
+            compressed: flags & FLAG_COMPRESSED != 0,
+            hash,
+            digest,
         })
     }
 }
 
+// Hashsum of an encoded record without decoding it, None for old records.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn stored_hashsum(bytes: &[u8]) -> Option<[u8; SHA256_SIZE]> {
+    let len = u64::from_le_bytes(bytes.get(..PAYLOAD_LEN_SIZE)?.try_into().ok()?);
+    let start = PAYLOAD_LEN_SIZE
+        .checked_add(usize::try_from(len).ok()?)?
+        .checked_add(size_of::<u16>())?;
+
+    bytes
+        .get(start..start.checked_add(SHA256_SIZE)?)?
+        .try_into()
+        .ok()
+}
+
+fn parse_hashsum(bytes: &[u8]) -> Result<[u8; SHA256_SIZE], LocalStorageError> {
+    bytes
+        .try_into()
+        .or(Err(LocalStorageError::InsufficientBytes))
+}
+
 impl ToVecBytes for DataWarp {
     fn to_bytes(&self) -> Vec<u8> {
         // let payload_bytes = ST::to_bytes(&self.payload);
         let payload_len = self.payload.len();
         let mut bytes: Vec<u8> =
-            Vec::with_capacity(size_of::<usize>() + payload_len + size_of::<u16>());
+            Vec::with_capacity(PAYLOAD_LEN_SIZE + payload_len + size_of::<u16>() + SHA256_SIZE);
 
-        bytes.extend_from_slice(&payload_len.to_le_bytes());
+        bytes.extend_from_slice(&(payload_len as u64).to_le_bytes());
         bytes.extend_from_slice(&self.payload);
         bytes.extend_from_slice(&self.version.to_le_bytes());
 
+        if let Some(hashsum) = &self.hashsum {
+            bytes.extend_from_slice(hashsum);
+
+            if let Some(last_update) = self.last_update {
+                bytes.extend_from_slice(&last_update.to_le_bytes());
+
+                let hash = self.hash != HashAlgo::Sha256 || self.digest.is_some();
+
+                if self.codec != Codec::Json || self.compressed || hash {
+                    bytes.push(self.codec.id());
+                }
+
+                if self.compressed || hash {
+                    bytes.push(if self.compressed { FLAG_COMPRESSED } else { 0 });
+                }
+
+                if hash {
+                    bytes.push(self.hash.id());
+                }
+
+                if let Some(digest) = &self.digest {
+                    bytes.extend_from_slice(digest);
+                }
+            }
+        }
+
         bytes
     }
 }
@@ -78,13 +205,19 @@ mod storage_tests {
         let data = DataWarp {
             payload: b"Hello, World!".to_vec(),
             version: 1,
+            hashsum: None,
+            last_update: None,
+            codec: Codec::Json,
+            compressed: false,
+            hash: HashAlgo::Sha256,
+            digest: None,
         };
 
         let bytes = data.to_bytes();
-        assert_eq!(bytes.len(), size_of::<usize>() + 13 + size_of::<u16>());
+        assert_eq!(bytes.len(), PAYLOAD_LEN_SIZE + 13 + size_of::<u16>());
 
-        let (len_bytes, rest) = bytes.split_at(size_of::<usize>());
-        assert_eq!(usize::from_le_bytes(len_bytes.try_into().unwrap()), 13);
+        let (len_bytes, rest) = bytes.split_at(PAYLOAD_LEN_SIZE);
+        assert_eq!(u64::from_le_bytes(len_bytes.try_into().unwrap()), 13);
 
         let (payload_bytes, version_bytes) = rest.split_at(13);
         assert_eq!(payload_bytes, b"Hello, World!");
@@ -100,6 +233,12 @@ mod storage_tests {
         let original = DataWarp {
             payload: b"Test data".to_vec(),
             version: 42,
+            hashsum: None,
+            last_update: None,
+            codec: Codec::Json,
+            compressed: false,
+            hash: HashAlgo::Sha256,
+            digest: None,
         };
 
         let bytes = original.to_bytes();
@@ -109,6 +248,59 @@ mod storage_tests {
         assert_eq!(original.version, deserialized.version);
     }
 
+    #[test]
+    fn test_datawarp_hashsum_roundtrip() {
+        let original = DataWarp {
+            payload: b"Test data".to_vec(),
+            version: 0,
+            hashsum: Some([7u8; SHA256_SIZE]),
+            last_update: None,
+            codec: Codec::Json,
+            compressed: false,
+            hash: HashAlgo::Sha256,
+            digest: None,
+        };
+
+        let bytes = original.to_bytes();
+        assert_eq!(
+            bytes.len(),
+            PAYLOAD_LEN_SIZE + 9 + size_of::<u16>() + SHA256_SIZE
+        );
+
+        let deserialized = DataWarp::from_bytes(bytes.into()).unwrap();
+
+        assert_eq!(original.payload, deserialized.payload);
+        assert_eq!(original.hashsum, deserialized.hashsum);
+
+        let mut truncated = original.to_bytes();
+        truncated.pop();
+
+        assert!(matches!(
+            DataWarp::from_bytes(truncated.into()),
+            Err(LocalStorageError::InsufficientBytes)
+        ));
+    }
+
+    #[test]
+    fn test_datawarp_last_update_roundtrip() {
+        let original = DataWarp {
+            payload: b"Test data".to_vec(),
+            version: 0,
+            hashsum: Some([7u8; SHA256_SIZE]),
+            last_update: Some(1_700_000_000_000),
+            codec: Codec::Json,
+            compressed: false,
+            hash: HashAlgo::Sha256,
+            digest: None,
+        };
+
+        let bytes = original.to_bytes();
+        let deserialized = DataWarp::from_bytes(bytes.into()).unwrap();
+
+        assert_eq!(original.hashsum, deserialized.hashsum);
+        assert_eq!(original.last_update, deserialized.last_update);
+    }
+
     #[test]
     fn test_datawarp_invalid_payload() {
         let invalid_bytes = vec![255; 10]; // Invalid UTF-8
@@ -127,4 +319,36 @@ mod storage_tests {
         let result = DataWarp::from_bytes(bytes.into());
         assert!(matches!(result, Err(LocalStorageError::InsufficientBytes)));
     }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_from_bytes_never_panics(bytes in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..256)) {
+            let _ = DataWarp::from_bytes(bytes.into());
+        }
+
+        #[test]
+        fn prop_roundtrip(
+            payload in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..256),
+            version in proptest::prelude::any::<u16>(),
+            hashsum in proptest::option::of(proptest::prelude::any::<[u8; SHA256_SIZE]>()),
+            last_update in proptest::prelude::any::<u64>(),
+        ) {
+            let original = DataWarp {
+                payload,
+                version,
+                hashsum,
+                last_update: hashsum.map(|_| last_update),
+                codec: Codec::Json,
+                compressed: false,
+                hash: HashAlgo::Sha256,
+                digest: None,
+            };
+            let restored = DataWarp::from_bytes(original.to_bytes().into()).unwrap();
+
+            proptest::prop_assert_eq!(restored.payload, original.payload);
+            proptest::prop_assert_eq!(restored.version, original.version);
+            proptest::prop_assert_eq!(restored.hashsum, original.hashsum);
+            proptest::prop_assert_eq!(restored.last_update, original.last_update);
+        }
+    }
 }
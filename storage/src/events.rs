@@ -0,0 +1,174 @@
+use crate::{audit::AuditAction, LocalStorage};
+use config::storage::NAMESPACE_TREE_PREFIX;
+use std::sync::MutexGuard;
+use zil_errors::storage::LocalStorageError;
+
+/// A committed change of the user's records. `namespace` is None for the
+/// main records, bookkeeping trees (TTL, tombstones, sync) never show up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageEvent {
+    Set {
+        namespace: Option<Vec<u8>>,
+        key: Vec<u8>,
+    },
+    Removed {
+        namespace: Option<Vec<u8>>,
+        key: Vec<u8>,
+    },
+    Cleared(Vec<u8>), // namespace emptied or dropped
+}
+
+pub type ChangeCallback = Box<dyn Fn(StorageEvent) + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenerId(u64);
+
+#[derive(Default)]
+pub(crate) struct Listeners {
+    next: u64,
+    callbacks: Vec<(ListenerId, ChangeCallback)>,
+}
+
+impl LocalStorage {
+    /// Called on the writing thread after every successful write or remove,
+    /// e.g. to post a refresh to the UI thread. A callback must not write
+    /// to this storage itself.
+    pub fn on_change(&self, callback: ChangeCallback) -> ListenerId {
+        let mut listeners = self.listeners();
+        let id = ListenerId(listeners.next);
+
+        listeners.next += 1;
+        listeners.callbacks.push((id, callback));
+
+        id
+    }
+
+    pub fn remove_listener(&self, id: ListenerId) -> bool {
+        let mut listeners = self.listeners();
+        let before = listeners.callbacks.len();
+
+        listeners.callbacks.retain(|(listener, _)| *listener != id);
+
+        listeners.callbacks.len() != before
+    }
+
+    // Listeners hear about the change even when its audit entry failed.
+    pub(crate) fn changed(
+        &self,
+        tree: &[u8],
+        key: &[u8],
+        removed: bool,
+    ) -> Result<(), LocalStorageError> {
+        let action = match removed {
+            false => AuditAction::Set,
+            true => AuditAction::Removed,
+        };
+        let audited = self.record_audit(tree, key, action);
+
+        self.notify(tree, key, removed);
+
+        audited
+    }
+
+    // Listeners only, for writes that logged their audit entry themselves.
+    pub(crate) fn notify(&self, tree: &[u8], key: &[u8], removed: bool) {
+        let Some(namespace) = self.namespace_of(tree) else {
+            return;
+        };
+        let key = key.to_vec();
+
+        self.emit(|| match removed {
+            false => StorageEvent::Set { namespace, key },
+            true => StorageEvent::Removed { namespace, key },
+        });
+    }
+
+    pub(crate) fn cleared(&self, tree: &[u8]) -> Result<(), LocalStorageError> {
+        let Some(Some(namespace)) = self.namespace_of(tree) else {
+            return Ok(());
+        };
+        let audited = self.record_audit(tree, &[], AuditAction::Cleared);
+
+        self.emit(|| StorageEvent::Cleared(namespace));
+
+        audited
+    }
+
+    // Some(None) for the main records, None for bookkeeping trees.
+    pub(crate) fn namespace_of(&self, tree: &[u8]) -> Option<Option<Vec<u8>>> {
+        if self.tree.name() == tree {
+            return Some(None);
+        }
+
+        tree.strip_prefix(NAMESPACE_TREE_PREFIX)
+            .and_then(|ns| hex::decode(ns).ok())
+            .map(Some)
+    }
+
+    fn emit(&self, event: impl FnOnce() -> StorageEvent) {
+        let listeners = self.listeners();
+
+        if listeners.callbacks.is_empty() {
+            return;
+        }
+
+        let event = event();
+
+        for (_, callback) in &listeners.callbacks {
+            callback(event.clone());
+        }
+    }
+
+    fn listeners(&self) -> MutexGuard<'_, Listeners> {
+        self.listeners.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_change_events() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let id = db.on_change(Box::new(move |event| sink.lock().unwrap().push(event)));
+
+        db.set(b"selected", b"0").unwrap();
+        db.ns_set(b"tokens", b"zlp", b"{}").unwrap();
+        db.set_with_ttl(b"gas_price", b"2000", 1000).unwrap();
+        db.transaction(|tx| tx.remove(b"selected")).unwrap();
+        db.purge_namespace(b"tokens").unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                StorageEvent::Set {
+                    namespace: None,
+                    key: b"selected".to_vec()
+                },
+                StorageEvent::Set {
+                    namespace: Some(b"tokens".to_vec()),
+                    key: b"zlp".to_vec()
+                },
+                StorageEvent::Set {
+                    namespace: None,
+                    key: b"gas_price".to_vec()
+                },
+                StorageEvent::Removed {
+                    namespace: None,
+                    key: b"selected".to_vec()
+                },
+                StorageEvent::Cleared(b"tokens".to_vec()),
+            ]
+        );
+
+        assert!(db.remove_listener(id));
+        db.set(b"selected", b"1").unwrap();
+
+        assert_eq!(events.lock().unwrap().len(), 5);
+    }
+}
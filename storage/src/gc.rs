@@ -0,0 +1,143 @@
+use crate::LocalStorage;
+use config::storage::TTL_TREE;
+use sled::IVec;
+use std::ops::AddAssign;
+use zil_errors::storage::LocalStorageError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reclaimed {
+    pub entries: usize,
+    pub bytes: u64, // keys plus stored values
+}
+
+impl AddAssign for Reclaimed {
+    fn add_assign(&mut self, other: Self) {
+        self.entries += other.entries;
+        self.bytes += other.bytes;
+    }
+}
+
+impl LocalStorage {
+    /// Drops every entry of a cache tree whose key `keep` rejects.
+    pub fn retain_tree(
+        &self,
+        tree: &[u8],
+        keep: impl Fn(&[u8]) -> bool,
+    ) -> Result<Reclaimed, LocalStorageError> {
+        self.writable()?;
+
+        let tree = match self.existing_tree(tree)? {
+            Some(tree) => tree,
+            None => return Ok(Reclaimed::default()),
+        };
+        let mut reclaimed = Reclaimed::default();
+
+        for entry in tree.iter() {
+            let (key, value) =
+                entry.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+            if keep(&key) {
+                continue;
+            }
+
+            if tree
+                .remove(&key)
+                .or(Err(LocalStorageError::StorageWriteError))?
+                .is_some()
+            {
+                self.changed(&tree.name(), &key, true)?;
+                reclaimed += Reclaimed {
+                    entries: 1,
+                    bytes: (key.len() + value.len()) as u64,
+                };
+            }
+        }
+
+        Ok(reclaimed)
+    }
+
+    /// Expired TTL records, and tombstones older than `tombstone_retention_ms`.
+    pub fn collect_expired(
+        &self,
+        now: u64,
+        tombstone_retention_ms: u64,
+    ) -> Result<Reclaimed, LocalStorageError> {
+        self.writable()?;
+
+        let keys = self
+            .open_tree(TTL_TREE)?
+            .iter()
+            .keys()
+            .collect::<Result<Vec<IVec>, sled::Error>>()
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+        let mut reclaimed = Reclaimed::default();
+
+        for key in keys {
+            let size = self
+                .tree
+                .get(&key)
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?
+                .map_or(0, |value| key.len() + value.len());
+
+            if self.expire_at(&key, now)? {
+                reclaimed += Reclaimed {
+                    entries: 1,
+                    bytes: size as u64,
+                };
+            }
+        }
+
+        let before = now.saturating_sub(tombstone_retention_ms);
+        let buried = self.tombstones()?;
+        let purged = self.purge_tombstones(before)?;
+
+        reclaimed += Reclaimed {
+            entries: purged,
+            bytes: buried
+                .iter()
+                .filter(|(_, deleted_at)| *deleted_at < before)
+                .map(|(key, _)| (key.len() + size_of::<u64>()) as u64)
+                .sum(),
+        };
+
+        Ok(reclaimed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retain_and_expire() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        db.tree_set(b"cache", b"mainnet:a1", b"{}").unwrap();
+        db.tree_set(b"cache", b"devnet:a1", b"{}").unwrap();
+        db.set_with_ttl(b"gas_price", b"2000", 0).unwrap();
+        db.set_tombstones(true);
+        db.set(b"account", b"{}").unwrap();
+        db.remove(b"account").unwrap();
+
+        let reclaimed = db
+            .retain_tree(b"cache", |key| key.starts_with(b"mainnet:"))
+            .unwrap();
+
+        assert_eq!(reclaimed.entries, 1);
+        assert_eq!(reclaimed.bytes, (b"devnet:a1".len() + 2) as u64);
+        assert_eq!(
+            db.tree_get(b"cache", b"mainnet:a1").unwrap(),
+            Some(b"{}".to_vec())
+        );
+
+        let reclaimed = db.collect_expired(u64::MAX, 0).unwrap();
+
+        assert_eq!(reclaimed.entries, 2);
+        assert!(db.tombstones().unwrap().is_empty());
+        assert_eq!(
+            db.retain_tree(b"missing", |_| false).unwrap(),
+            Reclaimed::default()
+        );
+    }
+}
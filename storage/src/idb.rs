@@ -0,0 +1,596 @@
+use crate::{
+    canonical::{canonical_hashsum_with, HashAlgo},
+    cipher_key,
+    codec::Codec,
+    data_warp::DataWarp,
+    decode_data, domain_key, encode_data, is_plain_tree, namespace_tree,
+};
+use cipher::aes::{aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE};
+use config::storage::{
+    ENCRYPTION_CHECK_KEY, ENCRYPTION_META_TREE, ENCRYPTION_SALT_KEY, ENCRYPTION_SALT_SIZE,
+    IDB_OBJECT_STORE, IDB_VERSION, NAMESPACE_TREE_PREFIX, STORAGE_VERSION,
+};
+use crypto::entropy::random_bytes;
+use js_sys::{Array, Promise, Uint8Array};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{cell::RefCell, collections::BTreeMap};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Event, IdbDatabase, IdbFactory, IdbRequest, IdbTransaction, IdbTransactionMode};
+use zil_errors::storage::LocalStorageError;
+
+// The main records live in the tree without a name.
+const MAIN_TREE: &[u8] = b"";
+
+type Entry = (Vec<u8>, Vec<u8>); // key, value
+
+/// `LocalStorage` of the web build. IndexedDB only answers asynchronously,
+/// so the whole store is read into memory by `open`, reads and writes are
+/// served from there and `flush` commits the queued writes in one
+/// transaction. Records keep the sled encoding and are encrypted the same
+/// way, a snapshot moves between both backends as is. Compression and TTLs
+/// need the native backend.
+pub struct LocalStorage {
+    db: IdbDatabase,
+    name: String,
+    codec: Codec,
+    cipher_key: Option<[u8; AES_GCM_KEY_SIZE]>,
+    records: RefCell<Records>,
+}
+
+// Every tree in one object store: u16 BE tree name length, tree, key.
+#[derive(Debug, Default)]
+struct Records {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    pending: BTreeMap<Vec<u8>, Option<Vec<u8>>>, // None removes
+}
+
+impl LocalStorage {
+    pub async fn open(name: &str) -> Result<Self, LocalStorageError> {
+        let factory: IdbFactory = js_sys::Reflect::get(&js_sys::global(), &"indexedDB".into())
+            .and_then(|factory| factory.dyn_into())
+            .map_err(js_error)?;
+        let request = factory.open_with_u32(name, IDB_VERSION).map_err(js_error)?;
+        let upgrade = Closure::once_into_js(move |event: Event| {
+            let db = event
+                .target()
+                .and_then(|target| target.dyn_into::<IdbRequest>().ok())
+                .and_then(|request| request.result().ok())
+                .and_then(|db| db.dyn_into::<IdbDatabase>().ok());
+
+            if let Some(db) = db {
+                if !db.object_store_names().contains(IDB_OBJECT_STORE) {
+                    let _ = db.create_object_store(IDB_OBJECT_STORE);
+                }
+            }
+        });
+
+        request.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+
+        let db: IdbDatabase = request_done(&request).await?.dyn_into().map_err(js_error)?;
+        let store = db
+            .transaction_with_str(IDB_OBJECT_STORE)
+            .and_then(|tx| tx.object_store(IDB_OBJECT_STORE))
+            .map_err(js_error)?;
+        // both come in key order
+        let keys = request_done(&store.get_all_keys().map_err(js_error)?).await?;
+        let values = request_done(&store.get_all().map_err(js_error)?).await?;
+        let entries = Array::from(&keys)
+            .iter()
+            .zip(Array::from(&values).iter())
+            .map(|(key, value)| {
+                (
+                    Uint8Array::new(&key).to_vec(),
+                    Uint8Array::new(&value).to_vec(),
+                )
+            })
+            .collect();
+
+        Ok(Self {
+            db,
+            name: name.to_string(),
+            codec: Codec::default(),
+            cipher_key: None,
+            records: RefCell::new(Records {
+                entries,
+                pending: BTreeMap::new(),
+            }),
+        })
+    }
+
+    /// Every value is encrypted with a key derived from `password` before it
+    /// is queued for IndexedDB. Records of a plaintext store are encrypted in
+    /// place the first time it is opened this way.
+    pub async fn open_encrypted(name: &str, password: &[u8]) -> Result<Self, LocalStorageError> {
+        let mut storage = Self::open(name).await?;
+
+        storage.unlock(password)?;
+        storage.flush().await?;
+
+        Ok(storage)
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher_key.is_some()
+    }
+
+    pub fn get_path(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn set_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
+
+    /// Commits the writes since the last flush, call it after writes a
+    /// closed tab must not lose. Returns the number of records committed.
+    pub async fn flush(&self) -> Result<usize, LocalStorageError> {
+        let pending = std::mem::take(&mut self.records.borrow_mut().pending);
+
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self
+            .db
+            .transaction_with_str_and_mode(IDB_OBJECT_STORE, IdbTransactionMode::Readwrite)
+            .map_err(flush_error)?;
+        let store = tx.object_store(IDB_OBJECT_STORE).map_err(flush_error)?;
+
+        for (key, value) in &pending {
+            let key = Uint8Array::from(key.as_slice());
+
+            match value {
+                Some(value) => store.put_with_key(&Uint8Array::from(value.as_slice()), &key),
+                None => store.delete(&key),
+            }
+            .map_err(flush_error)?;
+        }
+
+        transaction_done(&tx).await.inspect_err(|_| {
+            // queued again, so the next flush retries them
+            let mut records = self.records.borrow_mut();
+
+            for (key, value) in &pending {
+                records.pending.entry(key.clone()).or_insert(value.clone());
+            }
+        })?;
+
+        Ok(pending.len())
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        Ok(self.records.borrow().get(MAIN_TREE, key).is_some())
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        Ok(self.get_data(key)?.payload)
+    }
+
+    pub fn get_data(&self, key: &[u8]) -> Result<DataWarp, LocalStorageError> {
+        self.read(MAIN_TREE, key)
+    }
+
+    pub fn set(&self, key: &[u8], payload: &[u8]) -> Result<(), LocalStorageError> {
+        self.write(MAIN_TREE, key, payload, Codec::default())
+    }
+
+    pub fn set_value<T: Serialize>(&self, key: &[u8], value: &T) -> Result<(), LocalStorageError> {
+        let payload = self.codec.encode(value)?;
+
+        self.write(MAIN_TREE, key, &payload, self.codec)
+    }
+
+    pub fn get_value<T: DeserializeOwned>(&self, key: &[u8]) -> Result<T, LocalStorageError> {
+        let data = self.get_data(key)?;
+
+        data.codec.decode(&data.payload)
+    }
+
+    pub fn get_or_default<ST: DeserializeOwned + Default>(
+        &self,
+        key: &[u8],
+    ) -> Result<ST, LocalStorageError> {
+        match self.get_value(key) {
+            Err(LocalStorageError::StorageDataNotFound) => Ok(ST::default()),
+            res => res,
+        }
+    }
+
+    pub fn keys(&self) -> Vec<Vec<u8>> {
+        self.records
+            .borrow()
+            .scan(MAIN_TREE, b"")
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    pub fn scan_prefix_raw(&self, prefix: &[u8]) -> Result<Vec<Entry>, LocalStorageError> {
+        self.records
+            .borrow()
+            .scan(MAIN_TREE, prefix)
+            .map(|(key, bytes)| Ok((key, self.open_record(MAIN_TREE, &bytes)?.payload)))
+            .collect()
+    }
+
+    pub fn remove(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        Ok(self.records.borrow_mut().remove(MAIN_TREE, key))
+    }
+
+    pub fn ns_get(&self, ns: &[u8], key: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        Ok(self.read(&namespace_tree(ns), key)?.payload)
+    }
+
+    pub fn ns_set(&self, ns: &[u8], key: &[u8], payload: &[u8]) -> Result<(), LocalStorageError> {
+        self.write(&namespace_tree(ns), key, payload, Codec::default())
+    }
+
+    pub fn ns_remove(&self, ns: &[u8], key: &[u8]) -> Result<bool, LocalStorageError> {
+        Ok(self.records.borrow_mut().remove(&namespace_tree(ns), key))
+    }
+
+    pub fn namespaces(&self) -> Vec<Vec<u8>> {
+        self.records.borrow().namespaces()
+    }
+
+    pub fn purge_namespace(&self, ns: &[u8]) -> Result<bool, LocalStorageError> {
+        Ok(self.records.borrow_mut().clear(&namespace_tree(ns)) > 0)
+    }
+
+    pub fn tree_get(&self, tree: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, LocalStorageError> {
+        let value = self.records.borrow().get(tree, key).cloned();
+
+        value.map(|v| self.decrypt(tree, &v)).transpose()
+    }
+
+    pub fn tree_set(&self, tree: &[u8], key: &[u8], value: &[u8]) -> Result<(), LocalStorageError> {
+        let value = self.encrypt(tree, value)?;
+
+        self.records.borrow_mut().insert(tree, key, value);
+
+        Ok(())
+    }
+
+    pub fn tree_remove(&self, tree: &[u8], key: &[u8]) -> Result<bool, LocalStorageError> {
+        Ok(self.records.borrow_mut().remove(tree, key))
+    }
+
+    fn read(&self, tree: &[u8], key: &[u8]) -> Result<DataWarp, LocalStorageError> {
+        let bytes = self
+            .records
+            .borrow()
+            .get(tree, key)
+            .cloned()
+            .ok_or(LocalStorageError::StorageDataNotFound)?;
+
+        self.open_record(tree, &bytes)
+    }
+
+    // Hashsum of an encrypted record covers the ciphertext, callers get the
+    // one of the plaintext like on the native backend.
+    fn open_record(&self, tree: &[u8], bytes: &[u8]) -> Result<DataWarp, LocalStorageError> {
+        let mut data = checked(bytes)?;
+
+        if self.is_encrypted() {
+            data.payload = self.decrypt(tree, &data.payload)?;
+            data.hashsum = Some(canonical_hashsum_with(data.hash, &data.payload));
+        }
+
+        Ok(data)
+    }
+
+    fn write(
+        &self,
+        tree: &[u8],
+        key: &[u8],
+        payload: &[u8],
+        codec: Codec,
+    ) -> Result<(), LocalStorageError> {
+        self.write_at(tree, key, payload, codec, js_sys::Date::now() as u64)
+    }
+
+    fn write_at(
+        &self,
+        tree: &[u8],
+        key: &[u8],
+        payload: &[u8],
+        codec: Codec,
+        last_update: u64,
+    ) -> Result<(), LocalStorageError> {
+        let bytes = encode_data(
+            STORAGE_VERSION,
+            (codec, false, HashAlgo::Sha256),
+            &self.encrypt(tree, payload)?,
+            last_update,
+        );
+
+        self.records.borrow_mut().insert(tree, key, bytes);
+
+        Ok(())
+    }
+
+    fn encrypt(&self, tree: &[u8], payload: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        match self.tree_key(tree)? {
+            Some(key) => aes_gcm_encrypt(&key, payload)
+                .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string())),
+            None => Ok(payload.to_vec()),
+        }
+    }
+
+    fn decrypt(&self, tree: &[u8], bytes: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        match self.tree_key(tree)? {
+            Some(key) => aes_gcm_decrypt(&key, bytes)
+                .map_err(|e| LocalStorageError::StorageDecryptError(e.to_string())),
+            None => Ok(bytes.to_vec()),
+        }
+    }
+
+    fn tree_key(&self, tree: &[u8]) -> Result<Option<[u8; AES_GCM_KEY_SIZE]>, LocalStorageError> {
+        self.cipher_key
+            .map(|key| domain_key(&key, tree))
+            .transpose()
+    }
+
+    // Same salt and check records as the native backend, so a snapshot opens
+    // with the same password on both.
+    fn unlock(&mut self, password: &[u8]) -> Result<(), LocalStorageError> {
+        let get = |key: &[u8]| {
+            self.records
+                .borrow()
+                .get(ENCRYPTION_META_TREE, key)
+                .cloned()
+        };
+        let salt = match get(ENCRYPTION_SALT_KEY) {
+            Some(salt) => salt,
+            None => {
+                let salt: [u8; ENCRYPTION_SALT_SIZE] = random_bytes();
+
+                self.records.borrow_mut().insert(
+                    ENCRYPTION_META_TREE,
+                    ENCRYPTION_SALT_KEY,
+                    salt.to_vec(),
+                );
+
+                salt.to_vec()
+            }
+        };
+        let key = cipher_key(password, &salt)?;
+
+        match get(ENCRYPTION_CHECK_KEY) {
+            Some(check) => {
+                aes_gcm_decrypt(&key, &check).or(Err(LocalStorageError::StorageWrongPassword))?;
+                self.cipher_key = Some(key);
+            }
+            None => {
+                self.cipher_key = Some(key);
+                self.encrypt_existing()?;
+
+                let check = self.encrypt(ENCRYPTION_META_TREE, ENCRYPTION_CHECK_KEY)?;
+
+                self.records
+                    .borrow_mut()
+                    .insert(ENCRYPTION_META_TREE, ENCRYPTION_CHECK_KEY, check);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Main and namespace trees hold DataWarp records, other trees raw values.
+    fn encrypt_existing(&self) -> Result<(), LocalStorageError> {
+        let entries: Vec<Entry> = self
+            .records
+            .borrow()
+            .entries
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        for (record_key, value) in entries {
+            let (tree, key) = split_record_key(&record_key)?;
+
+            if is_plain_tree(tree) {
+                continue;
+            }
+
+            if tree == MAIN_TREE || tree.starts_with(NAMESPACE_TREE_PREFIX) {
+                let data = checked(&value)?;
+
+                self.write_at(
+                    tree,
+                    key,
+                    &data.payload,
+                    data.codec,
+                    data.last_update.unwrap_or(0),
+                )?;
+            } else {
+                let value = self.encrypt(tree, &value)?;
+
+                self.records.borrow_mut().insert(tree, key, value);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Records {
+    fn get(&self, tree: &[u8], key: &[u8]) -> Option<&Vec<u8>> {
+        self.entries.get(&record_key(tree, key))
+    }
+
+    fn insert(&mut self, tree: &[u8], key: &[u8], value: Vec<u8>) {
+        let key = record_key(tree, key);
+
+        self.pending.insert(key.clone(), Some(value.clone()));
+        self.entries.insert(key, value);
+    }
+
+    fn remove(&mut self, tree: &[u8], key: &[u8]) -> bool {
+        let key = record_key(tree, key);
+        let removed = self.entries.remove(&key).is_some();
+
+        if removed {
+            self.pending.insert(key, None);
+        }
+
+        removed
+    }
+
+    fn clear(&mut self, tree: &[u8]) -> usize {
+        let keys: Vec<Vec<u8>> = self
+            .scan(tree, b"")
+            .map(|(key, _)| record_key(tree, &key))
+            .collect();
+
+        for key in &keys {
+            self.entries.remove(key);
+            self.pending.insert(key.clone(), None);
+        }
+
+        keys.len()
+    }
+
+    // Keys of `tree` under `prefix` in key order, without the tree part.
+    fn scan<'a>(&'a self, tree: &[u8], prefix: &[u8]) -> impl Iterator<Item = Entry> + 'a {
+        let start = record_key(tree, prefix);
+        let skip = record_key(tree, b"").len();
+
+        self.entries
+            .range(start.clone()..)
+            .take_while(move |(key, _)| key.starts_with(&start))
+            .map(move |(key, value)| (key[skip..].to_vec(), value.clone()))
+    }
+
+    fn namespaces(&self) -> Vec<Vec<u8>> {
+        let mut namespaces: Vec<Vec<u8>> = self
+            .entries
+            .keys()
+            .filter_map(|key| {
+                let (tree, _) = split_record_key(key).ok()?;
+
+                tree.strip_prefix(NAMESPACE_TREE_PREFIX)
+                    .and_then(|ns| hex::decode(ns).ok())
+            })
+            .collect();
+
+        namespaces.dedup();
+
+        namespaces
+    }
+}
+
+fn record_key(tree: &[u8], key: &[u8]) -> Vec<u8> {
+    [&(tree.len() as u16).to_be_bytes(), tree, key].concat()
+}
+
+fn split_record_key(record_key: &[u8]) -> Result<(&[u8], &[u8]), LocalStorageError> {
+    let broken = || LocalStorageError::StorageAccessError("broken record key".to_string());
+    let len = record_key
+        .get(..2)
+        .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+        .ok_or_else(broken)?;
+
+    record_key
+        .get(2..2 + len)
+        .map(|tree| (tree, &record_key[2 + len..]))
+        .ok_or_else(broken)
+}
+
+fn checked(bytes: &[u8]) -> Result<DataWarp, LocalStorageError> {
+    let data = decode_data(bytes)?;
+
+    if data.compressed {
+        return Err(LocalStorageError::StorageCompressionError(
+            "compressed records need the native backend".to_string(),
+        ));
+    }
+
+    Ok(data)
+}
+
+// Resolves with the request's result once it succeeded.
+async fn request_done(request: &IdbRequest) -> Result<JsValue, LocalStorageError> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let done = request.clone();
+        let on_success = Closure::once_into_js(move |_: Event| {
+            let _ = resolve.call1(&JsValue::NULL, &done.result().unwrap_or(JsValue::NULL));
+        });
+        let failed = request.clone();
+        let on_error = Closure::once_into_js(move |_: Event| {
+            let error = failed.error().ok().flatten().map(JsValue::from);
+            let _ = reject.call1(&JsValue::NULL, &error.unwrap_or(JsValue::NULL));
+        });
+
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+
+    JsFuture::from(promise).await.map_err(js_error)
+}
+
+async fn transaction_done(tx: &IdbTransaction) -> Result<(), LocalStorageError> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let on_complete = Closure::once_into_js(move |_: Event| {
+            let _ = resolve.call0(&JsValue::NULL);
+        });
+        // a failed request aborts the whole transaction
+        let on_abort = Closure::once_into_js(move |event: Event| {
+            let _ = reject.call1(&JsValue::NULL, &event);
+        });
+
+        tx.set_oncomplete(Some(on_complete.unchecked_ref()));
+        tx.set_onabort(Some(on_abort.unchecked_ref()));
+    });
+
+    JsFuture::from(promise)
+        .await
+        .map(|_| ())
+        .map_err(flush_error)
+}
+
+fn js_error(e: JsValue) -> LocalStorageError {
+    LocalStorageError::StorageAccessError(format!("{e:?}"))
+}
+
+fn flush_error(e: JsValue) -> LocalStorageError {
+    LocalStorageError::StorageFlushError(format!("{e:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_layout() {
+        let mut records = Records::default();
+        let tokens = namespace_tree(b"tokens");
+
+        records.insert(MAIN_TREE, b"account:0", b"a".to_vec());
+        records.insert(MAIN_TREE, b"account:1", b"b".to_vec());
+        records.insert(MAIN_TREE, b"selected", b"0".to_vec());
+        records.insert(&tokens, b"zlp", b"{}".to_vec());
+        records.insert(&tokens, b"gzil", b"{}".to_vec());
+
+        let accounts: Vec<Vec<u8>> = records
+            .scan(MAIN_TREE, b"account:")
+            .map(|(key, _)| key)
+            .collect();
+
+        assert_eq!(accounts, vec![b"account:0".to_vec(), b"account:1".to_vec()]);
+        assert_eq!(records.scan(MAIN_TREE, b"").count(), 3);
+        assert_eq!(records.namespaces(), vec![b"tokens".to_vec()]);
+        assert!(records.remove(MAIN_TREE, b"selected"));
+        assert!(!records.remove(MAIN_TREE, b"selected"));
+        assert_eq!(records.clear(&tokens), 2);
+        assert!(records.namespaces().is_empty());
+        // every change is queued for the next flush
+        assert_eq!(records.pending.len(), 5);
+        assert_eq!(records.pending[&record_key(&tokens, b"zlp")], None);
+        assert_eq!(
+            split_record_key(&record_key(&tokens, b"zlp")).unwrap(),
+            (tokens.as_slice(), b"zlp".as_slice())
+        );
+        assert!(split_record_key(&[0, 9, 1]).is_err());
+    }
+}
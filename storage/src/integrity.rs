@@ -0,0 +1,129 @@
+use crate::{
+    canonical::verify_hashsum_with, compression::decompress, data_warp::DataWarp, namespace_tree,
+    LocalStorage,
+};
+use bincode::FromBytes;
+use sled::IVec;
+use zil_errors::storage::LocalStorageError;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CorruptedRecord {
+    pub namespace: Option<Vec<u8>>, // None for the main tree
+    pub key: Vec<u8>,
+    pub last_update: Option<u64>, // None when the envelope itself is broken
+    pub error: LocalStorageError,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    pub checked: usize,
+    pub corrupted: Vec<CorruptedRecord>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.corrupted.is_empty()
+    }
+}
+
+impl LocalStorage {
+    /// Reads every record of the main and namespace trees the way `get`
+    /// would, without migrating, and collects the ones that fail instead of
+    /// stopping at the first.
+    pub fn verify_integrity(&self) -> Result<IntegrityReport, LocalStorageError> {
+        let mut report = IntegrityReport::default();
+        let trees = std::iter::once(((*self.tree).clone(), None)).chain(
+            self.namespaces()
+                .into_iter()
+                .map(|ns| Ok((self.open_tree(&namespace_tree(&ns))?, Some(ns))))
+                .collect::<Result<Vec<_>, LocalStorageError>>()?,
+        );
+
+        for (tree, namespace) in trees {
+            let entries = tree
+                .iter()
+                .collect::<Result<Vec<(IVec, IVec)>, sled::Error>>()
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+            for (key, value) in entries {
+                report.checked += 1;
+
+                if let Err((last_update, error)) = self.check_record(&tree.name(), &value) {
+                    report.corrupted.push(CorruptedRecord {
+                        namespace: namespace.clone(),
+                        key: key.to_vec(),
+                        last_update,
+                        error,
+                    });
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn check_record(
+        &self,
+        tree: &[u8],
+        bytes: &[u8],
+    ) -> Result<(), (Option<u64>, LocalStorageError)> {
+        let data = DataWarp::from_bytes(bytes.into()).map_err(|e| (None, e))?;
+        let broken = |e| (data.last_update, e);
+
+        if let Some(hashsum) = &data.hashsum {
+            if !verify_hashsum_with(data.hash, &data.payload, hashsum) {
+                return Err(broken(LocalStorageError::StorageDataBroken));
+            }
+        }
+
+        // the cipher tag catches what a missing hashsum can't
+        let payload = self.decrypt(tree, &data.payload).map_err(broken)?;
+
+        if data.compressed {
+            decompress(&payload).map_err(broken)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_integrity() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from_encrypted(&dir, b"password").unwrap();
+
+        db.set(b"wallets", br#"{"a":1}"#).unwrap();
+        db.ns_set(b"wallet 0", b"history", b"[1,2]").unwrap();
+        db.ns_set(b"wallet 0", b"tokens", b"[]").unwrap();
+
+        assert_eq!(db.verify_integrity().unwrap().checked, 3);
+
+        // flip a payload byte on disk, as a bad sector would
+        let tree = db.open_tree(&namespace_tree(b"wallet 0")).unwrap();
+        let mut raw = tree.get(b"history").unwrap().unwrap().to_vec();
+        let last_update = DataWarp::from_bytes(raw.as_slice().into())
+            .unwrap()
+            .last_update;
+
+        raw[size_of::<usize>()] ^= 0xff;
+        tree.insert(b"history", raw).unwrap();
+
+        let report = db.verify_integrity().unwrap();
+
+        assert!(!report.is_ok());
+        assert_eq!(report.checked, 3);
+        assert_eq!(
+            report.corrupted,
+            vec![CorruptedRecord {
+                namespace: Some(b"wallet 0".to_vec()),
+                key: b"history".to_vec(),
+                last_update,
+                error: LocalStorageError::StorageDataBroken,
+            }]
+        );
+    }
+}
@@ -1,18 +1,111 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
+#[cfg(not(target_arch = "wasm32"))]
+mod cache;
+pub mod canonical;
+pub mod codec;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod collection;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compaction;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compression;
 pub mod data_warp;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gc;
+#[cfg(feature = "wasm")]
+pub mod idb;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod integrity;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lock;
+pub mod migration;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod namespace;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod profile;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod retention;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ring_log;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rotation;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod snapshot;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sync;
+#[cfg(all(feature = "sync-client", not(target_arch = "wasm32")))]
+pub mod sync_client;
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub mod task;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tombstone;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ttl;
 
 use bincode::{FromBytes, ToVecBytes};
-use config::storage::STORAGE_VERSION;
+use canonical::{canonical_hashsum_with, verify_hashsum_with, HashAlgo};
+use cipher::{aes::AES_GCM_KEY_SIZE, argon2::derive_key_with_salt, hkdf::derive_subkey};
+use codec::Codec;
+use config::storage::{
+    ENCRYPTION_DOMAIN_INFO, ENCRYPTION_META_TREE, ISOLATED_NAMESPACES, NAMESPACE_TREE_PREFIX,
+    STORAGE_META_TREE, SYNC_CLIENT_TREE, SYNC_CURSOR_TREE, SYNC_META_TREE,
+};
 use data_warp::DataWarp;
-use directories::ProjectDirs;
-use sled::{Db, IVec};
 use zil_errors::storage::LocalStorageError;
 
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use idb::LocalStorage;
+
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    audit::AuditAction,
+    cache::ReadCache,
+    cipher::aes::{aes_gcm_decrypt, aes_gcm_encrypt},
+    compression::{compress, decompress},
+    config::storage::{
+        AUDIT_TREE, ENCRYPTION_CHECK_KEY, ENCRYPTION_SALT_KEY, ENCRYPTION_SALT_SIZE, HASH_ALGO_KEY,
+        INDEX_BLIND_INFO, INDEX_TREE_PREFIX, STORAGE_READ_CACHE_CAPACITY, STORAGE_VERSION,
+        TTL_TREE,
+    },
+    crypto::entropy::random_bytes,
+    data_warp::stored_hashsum,
+    directories::ProjectDirs,
+    migration::{MigrationRegistry, Migrator},
+    serde::{de::DeserializeOwned, Serialize},
+    sled::{
+        transaction::{TransactionError, Transactional},
+        Db, IVec,
+    },
+    std::{
+        borrow::Cow,
+        sync::{Mutex, MutexGuard},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+#[cfg(not(target_arch = "wasm32"))]
 pub struct LocalStorage {
     tree: Db,
-    version: u16,
     path: String,
+    cipher_key: Option<[u8; AES_GCM_KEY_SIZE]>,
+    migrations: MigrationRegistry,
+    codec: Codec,
+    compress: bool,
+    hash: HashAlgo,
+    tombstones: bool,
+    audit: Option<String>, // actor tag while the audit log is on
+    cache: Mutex<ReadCache>,
+    listeners: Mutex<events::Listeners>,
+    _lock: Option<std::fs::File>,
+    read_only: Option<std::path::PathBuf>, // the copy opened by `open_read_only`
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl std::fmt::Display for LocalStorage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let size = self.get_db_size().to_string();
@@ -22,17 +115,131 @@ impl std::fmt::Display for LocalStorage {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl LocalStorage {
+    // Fails with `StorageLockedByAnotherProcess` while the path is open
+    // elsewhere, see `open_read_only` for inspecting a running wallet.
     pub fn from(path: &str) -> Result<Self, LocalStorageError> {
+        compaction::recover(path)?;
+
+        let lock = lock::acquire(path)?;
+
+        compaction::remove_leftovers(path)?;
+        lock::remove_stale_copies(path)?;
+
+        let mut storage = Self::open_unlocked(path)?;
+
+        storage._lock = Some(lock);
+
+        Ok(storage)
+    }
+
+    fn open_unlocked(path: &str) -> Result<Self, LocalStorageError> {
         let tree =
             sled::open(path).map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
-        let version = STORAGE_VERSION;
-
-        Ok(LocalStorage {
+        let mut storage = LocalStorage {
             tree,
-            version,
             path: path.to_owned(),
-        })
+            cipher_key: None,
+            migrations: MigrationRegistry::default(),
+            codec: Codec::default(),
+            compress: false,
+            hash: HashAlgo::default(),
+            tombstones: false,
+            audit: None,
+            cache: Mutex::new(ReadCache::new(STORAGE_READ_CACHE_CAPACITY)),
+            listeners: Default::default(),
+            _lock: None,
+            read_only: None,
+        };
+
+        if let Some(meta) = storage.existing_tree(STORAGE_META_TREE)? {
+            let id = meta
+                .get(HASH_ALGO_KEY)
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+            if let Some(id) = id.as_deref().and_then(|id| id.first()) {
+                storage.hash = HashAlgo::from_id(*id)?;
+            }
+        }
+
+        Ok(storage)
+    }
+
+    /// Every value is encrypted with a key derived from `password` before it
+    /// reaches sled. Records of a plaintext storage are encrypted in place
+    /// the first time it is opened this way.
+    pub fn from_encrypted(path: &str, password: &[u8]) -> Result<Self, LocalStorageError> {
+        let mut storage = Self::from(path)?;
+
+        storage.unlock(password)?;
+
+        Ok(storage)
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher_key.is_some()
+    }
+
+    /// Records under `prefix` older than the newest migration registered for
+    /// it are upgraded on read and written back, new ones get the bumped
+    /// version. Other records keep `STORAGE_VERSION`.
+    pub fn register_migration(
+        &mut self,
+        prefix: &[u8],
+        from_version: u16,
+        migrator: Migrator,
+    ) -> Result<(), LocalStorageError> {
+        self.migrations
+            .register(&self.tree.name(), prefix, from_version, migrator)
+    }
+
+    // Newest version any record is written with.
+    pub fn version(&self) -> u16 {
+        self.migrations.latest(STORAGE_VERSION)
+    }
+
+    fn record_version(&self, tree: &[u8], key: &[u8]) -> u16 {
+        self.migrations.version_of(tree, key, STORAGE_VERSION)
+    }
+
+    // Codec of values written with `set_value`, raw `set` is not affected.
+    pub fn set_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// Large values written from now on are zstd compressed, reads handle
+    /// both kinds regardless of this setting.
+    pub fn set_compression(&mut self, enabled: bool) {
+        self.compress = enabled;
+    }
+
+    // Hashsum of records written from now on, each record keeps its own.
+    // Kept across reopens.
+    pub fn set_hash_algo(&mut self, algo: HashAlgo) -> Result<(), LocalStorageError> {
+        self.writable()?;
+        self.open_tree(STORAGE_META_TREE)?
+            .insert(HASH_ALGO_KEY, &[algo.id()])
+            .or(Err(LocalStorageError::StorageWriteError))?;
+        self.hash = algo;
+
+        Ok(())
+    }
+
+    pub fn hash_algo(&self) -> HashAlgo {
+        self.hash
+    }
+
+    // Records kept decoded for repeated reads, zero turns the cache off.
+    pub fn set_read_cache(&mut self, capacity: usize) {
+        self.cache
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .resize(capacity);
     }
 
     pub fn new(
@@ -40,65 +247,942 @@ impl LocalStorage {
         organization: &str,
         application: &str,
     ) -> Result<Self, LocalStorageError> {
+        Self::from(&Self::default_path(qualifier, organization, application)?)
+    }
+
+    // The platform data directory `new` opens.
+    pub fn default_path(
+        qualifier: &str,
+        organization: &str,
+        application: &str,
+    ) -> Result<String, LocalStorageError> {
         let path = ProjectDirs::from(qualifier, organization, application)
             .ok_or(LocalStorageError::StoragePathError)?;
-        let tree = sled::open(path.data_dir())
-            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
-        let version = STORAGE_VERSION;
 
-        Ok(LocalStorage {
-            tree,
-            version,
-            path: path.data_dir().to_str().unwrap_or("").to_string(),
-        })
+        path.data_dir()
+            .to_str()
+            .map(str::to_owned)
+            .ok_or(LocalStorageError::StoragePathError)
     }
 
     pub fn get_path(&self) -> String {
         self.path.clone()
     }
 
+    /// sled buffers writes and syncs them every few hundred milliseconds,
+    /// call this after writes a crash must not lose. Returns bytes flushed.
+    pub fn flush(&self) -> Result<usize, LocalStorageError> {
+        self.tree
+            .flush()
+            .map_err(|e| LocalStorageError::StorageFlushError(e.to_string()))
+    }
+
+    pub async fn flush_async(&self) -> Result<usize, LocalStorageError> {
+        self.tree
+            .flush_async()
+            .await
+            .map_err(|e| LocalStorageError::StorageFlushError(e.to_string()))
+    }
+
     pub fn get_db_size(&self) -> u64 {
         self.tree.size_on_disk().unwrap_or(0)
     }
 
-    pub fn exists(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+    pub fn contains_key(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        if self.expire(key)? {
+            return Ok(false);
+        }
+
         self.tree
             .contains_key(key)
             .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))
     }
 
+    #[deprecated(note = "renamed to `contains_key`")]
+    pub fn exists(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        self.contains_key(key)
+    }
+
     pub fn get(&self, key: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
-        let some_value = self
-            .tree
-            .get(key)
-            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
-        let value = some_value
-            .ok_or(LocalStorageError::StorageDataNotFound)?
-            .to_vec();
-        let data = DataWarp::from_bytes(value.into())?;
+        let data = self.get_data(key)?;
 
         Ok(data.payload)
     }
 
+    pub fn get_data(&self, key: &[u8]) -> Result<DataWarp, LocalStorageError> {
+        if self.expire(key)? {
+            return Err(LocalStorageError::StorageDataNotFound);
+        }
+
+        self.read(&self.tree, key)
+    }
+
     pub fn set(&self, key: &[u8], payload: &[u8]) -> Result<(), LocalStorageError> {
-        let data = DataWarp {
-            payload: payload.into(),
-            version: self.version,
+        self.set_with_update(key, payload, now_millis()?)?;
+        self.tree_remove(TTL_TREE, key)?;
+        self.unbury(key)?;
+
+        Ok(())
+    }
+
+    pub fn set_value<T: Serialize>(&self, key: &[u8], value: &T) -> Result<(), LocalStorageError> {
+        let payload = self.codec.encode(value)?;
+
+        self.write_as(&self.tree, key, &payload, now_millis()?, self.codec)?;
+        self.tree_remove(TTL_TREE, key)?;
+        self.unbury(key)?;
+
+        Ok(())
+    }
+
+    // Decoded with the codec the record was written with.
+    pub fn get_value<T: DeserializeOwned>(&self, key: &[u8]) -> Result<T, LocalStorageError> {
+        let data = self.get_data(key)?;
+
+        data.codec.decode(&data.payload)
+    }
+
+    pub fn get_or_default<ST: DeserializeOwned + Default>(
+        &self,
+        key: &[u8],
+    ) -> Result<ST, LocalStorageError> {
+        match self.get_value(key) {
+            Err(LocalStorageError::StorageDataNotFound) => Ok(ST::default()),
+            res => res,
+        }
+    }
+
+    // A missing record is written with `init`, any other error is returned.
+    pub fn get_or_insert_with<ST, F>(&self, key: &[u8], init: F) -> Result<ST, LocalStorageError>
+    where
+        ST: Serialize + DeserializeOwned,
+        F: FnOnce() -> ST,
+    {
+        match self.get_value(key) {
+            Err(LocalStorageError::StorageDataNotFound) => {
+                let value = init();
+
+                self.set_value(key, &value)?;
+
+                Ok(value)
+            }
+            res => res,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Result<Vec<u8>, LocalStorageError>> + '_ {
+        self.tree.iter().keys().map(|key| {
+            key.map(|k| k.to_vec())
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))
+        })
+    }
+
+    /// Records under `prefix` in key order, checked and decrypted like with
+    /// `get`. Keys removed while iterating are skipped.
+    pub fn scan_prefix_raw<'a>(
+        &'a self,
+        prefix: &[u8],
+    ) -> impl Iterator<Item = Result<(Vec<u8>, Vec<u8>), LocalStorageError>> + 'a {
+        self.scan_prefix_data(prefix)
+            .map(|entry| entry.map(|(key, data)| (key, data.payload)))
+    }
+
+    // Typed records under a text prefix, e.g. every `account:` entry.
+    pub fn scan_prefix<'a, ST: DeserializeOwned>(
+        &'a self,
+        prefix: &str,
+    ) -> impl Iterator<Item = Result<(String, ST), LocalStorageError>> + 'a {
+        self.scan_prefix_data(prefix.as_bytes()).map(|entry| {
+            let (key, data) = entry?;
+            let key = String::from_utf8(key).or(Err(LocalStorageError::PayloadParseError))?;
+
+            Ok((key, data.codec.decode(&data.payload)?))
+        })
+    }
+
+    fn scan_prefix_data<'a>(
+        &'a self,
+        prefix: &[u8],
+    ) -> impl Iterator<Item = Result<(Vec<u8>, DataWarp), LocalStorageError>> + 'a {
+        self.tree.scan_prefix(prefix).keys().filter_map(move |key| {
+            let key = match key {
+                Ok(key) => key.to_vec(),
+                Err(e) => return Some(Err(LocalStorageError::StorageAccessError(e.to_string()))),
+            };
+
+            match self.get_data(&key) {
+                Ok(data) => Some(Ok((key, data))),
+                Err(LocalStorageError::StorageDataNotFound) => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+
+    pub fn remove(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        self.writable()?;
+
+        let removed = self.remove_entry(key)?;
+
+        if removed {
+            self.bury(key)?;
+        }
+
+        Ok(removed)
+    }
+
+    // Without a tombstone, for entries that were never the user's choice.
+    pub(crate) fn remove_entry(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        let removed = self
+            .tree
+            .remove(key)
+            .or(Err(LocalStorageError::StorageWriteError))?;
+
+        self.tree_remove(TTL_TREE, key)?;
+
+        if removed.is_some() {
+            self.changed(&self.tree.name(), key, true)?;
+        }
+
+        Ok(removed.is_some())
+    }
+
+    pub(crate) fn set_with_update(
+        &self,
+        key: &[u8],
+        payload: &[u8],
+        last_update: u64,
+    ) -> Result<(), LocalStorageError> {
+        self.write(&self.tree, key, payload, last_update)
+    }
+
+    /// Records of one wallet, scoped by its fingerprint (the wallet key) in
+    /// a tree of their own, so data of a wallet restored from another
+    /// mnemonic never mixes with what an earlier wallet left behind.
+    pub fn ns_get(&self, ns: &[u8], key: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        let tree = self
+            .existing_tree(&namespace_tree(ns))?
+            .ok_or(LocalStorageError::StorageDataNotFound)?;
+
+        Ok(self.read(&tree, key)?.payload)
+    }
+
+    pub fn ns_set(&self, ns: &[u8], key: &[u8], payload: &[u8]) -> Result<(), LocalStorageError> {
+        let tree = self.open_tree(&namespace_tree(ns))?;
+
+        self.write(&tree, key, payload, now_millis()?)
+    }
+
+    pub fn ns_remove(&self, ns: &[u8], key: &[u8]) -> Result<bool, LocalStorageError> {
+        self.writable()?;
+
+        let Some(tree) = self.existing_tree(&namespace_tree(ns))? else {
+            return Ok(false);
         };
-        let vec = IVec::from(data.to_bytes());
+        let removed = tree
+            .remove(key)
+            .or(Err(LocalStorageError::StorageWriteError))?
+            .is_some();
 
+        if removed {
+            self.changed(&tree.name(), key, true)?;
+        }
+
+        Ok(removed)
+    }
+
+    // Fingerprints of every namespace on disk, including abandoned ones.
+    pub fn namespaces(&self) -> Vec<Vec<u8>> {
         self.tree
-            .insert(key, vec)
+            .tree_names()
+            .iter()
+            .filter_map(|name| name.strip_prefix(NAMESPACE_TREE_PREFIX))
+            .filter_map(|ns| hex::decode(ns).ok())
+            .collect()
+    }
+
+    pub fn purge_namespace(&self, ns: &[u8]) -> Result<bool, LocalStorageError> {
+        self.writable()?;
+
+        let tree = namespace_tree(ns);
+        let dropped = self
+            .tree
+            .drop_tree(&tree)
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+        if dropped {
+            self.cleared(&tree)?;
+        }
+
+        Ok(dropped)
+    }
+
+    // Raw values in a named tree, for caches that live beside the main records.
+    pub fn tree_get(&self, tree: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, LocalStorageError> {
+        let value = self
+            .open_tree(tree)?
+            .get(key)
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+        value.map(|v| self.decrypt(tree, &v)).transpose()
+    }
+
+    pub fn tree_set(&self, tree: &[u8], key: &[u8], value: &[u8]) -> Result<(), LocalStorageError> {
+        self.writable()?;
+
+        self.open_tree(tree)?
+            .insert(key, self.encrypt(tree, value)?)
+            .or(Err(LocalStorageError::StorageWriteError))?;
+
+        Ok(())
+    }
+
+    pub fn tree_remove(&self, tree: &[u8], key: &[u8]) -> Result<bool, LocalStorageError> {
+        self.writable()?;
+
+        let removed = self
+            .open_tree(tree)?
+            .remove(key)
             .or(Err(LocalStorageError::StorageWriteError))?;
 
+        Ok(removed.is_some())
+    }
+
+    // Hashsum of an encrypted record covers the ciphertext on disk, callers
+    // get the one of the plaintext like with a plain storage.
+    fn read(&self, tree: &sled::Tree, key: &[u8]) -> Result<DataWarp, LocalStorageError> {
+        self.open_record(tree, key, fetch(tree, key)?)
+    }
+
+    fn open_record(
+        &self,
+        tree: &sled::Tree,
+        key: &[u8],
+        bytes: IVec,
+    ) -> Result<DataWarp, LocalStorageError> {
+        let (data, migrated) = self.decode_record(&tree.name(), key, bytes)?;
+
+        // a read-only copy is migrated in memory only
+        if migrated && self.read_only.is_none() {
+            self.write(
+                tree,
+                key,
+                &data.payload,
+                data.last_update.unwrap_or_default(),
+            )?;
+        }
+
+        Ok(data)
+    }
+
+    // Decrypted, decompressed and migrated, true when the migrated record
+    // still has to be written back.
+    fn decode_record(
+        &self,
+        tree: &IVec,
+        key: &[u8],
+        bytes: IVec,
+    ) -> Result<(DataWarp, bool), LocalStorageError> {
+        // plaintext of an encrypted storage is not kept around
+        let hashsum = stored_hashsum(&bytes).filter(|_| !self.is_encrypted());
+
+        if let Some(hashsum) = &hashsum {
+            let version = self.record_version(tree, key);
+            let cached = self.cache().get(tree, key, hashsum);
+
+            if let Some(data) = cached.filter(|data| data.version >= version) {
+                return Ok((data, false));
+            }
+        }
+
+        let mut data = decode_data(&bytes)?;
+
+        if self.is_encrypted() {
+            data.payload = self.decrypt(tree, &data.payload)?;
+            data.hashsum = Some(canonical_hashsum_with(data.hash, &data.payload));
+        }
+
+        if data.compressed {
+            data.payload = decompress(&data.payload)?;
+            data.hashsum = Some(canonical_hashsum_with(data.hash, &data.payload));
+            data.compressed = false;
+        }
+
+        // migrators work on JSON, the upgraded record is stored as such and
+        // a payload that isn't one is never handed to them
+        let upgraded = match data.codec.decode::<serde_json::Value>(&data.payload) {
+            Ok(json) => self.migrations.upgrade(
+                tree,
+                key,
+                &Codec::Json.encode(&json)?,
+                data.version,
+                STORAGE_VERSION,
+            )?,
+            Err(_) => None,
+        };
+
+        if let Some(payload) = upgraded {
+            // not a user change, sync must keep seeing the old write time
+            let last_update = match data.last_update {
+                Some(last_update) => last_update,
+                None => now_millis()?,
+            };
+            let data = DataWarp {
+                hashsum: Some(canonical_hashsum_with(self.hash, &payload)),
+                payload,
+                version: self.record_version(tree, key),
+                last_update: Some(last_update),
+                codec: Codec::Json,
+                compressed: false,
+                hash: self.hash,
+                digest: None,
+            };
+
+            return Ok((data, true));
+        }
+
+        if let Some(hashsum) = hashsum {
+            self.cache().insert(tree.clone(), key, hashsum, &data);
+        }
+
+        Ok((data, false))
+    }
+
+    fn cache(&self) -> MutexGuard<'_, ReadCache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(
+        &self,
+        tree: &sled::Tree,
+        key: &[u8],
+        payload: &[u8],
+        last_update: u64,
+    ) -> Result<(), LocalStorageError> {
+        self.write_as(tree, key, payload, last_update, Codec::Json)
+    }
+
+    fn write_as(
+        &self,
+        tree: &sled::Tree,
+        key: &[u8],
+        payload: &[u8],
+        last_update: u64,
+        codec: Codec,
+    ) -> Result<(), LocalStorageError> {
+        self.writable()?;
+        self.cache().invalidate(&tree.name(), key);
+
+        let name = tree.name();
+        let sealed = self.seal(&name, key, payload, codec, last_update)?;
+
+        // the record and its audit entry land together or not at all
+        match self.audit_row(&name, key, AuditAction::Set, stored_hashsum(&sealed))? {
+            Some((id, row)) => (tree, &self.open_tree(AUDIT_TREE)?)
+                .transaction(|(tree, audit)| {
+                    tree.insert(key, &sealed)?;
+                    audit.insert(&id, row.as_slice())?;
+
+                    Ok(())
+                })
+                .map_err(|_: TransactionError<()>| LocalStorageError::StorageWriteError)?,
+            None => {
+                tree.insert(key, sealed)
+                    .or(Err(LocalStorageError::StorageWriteError))?;
+            }
+        }
+
+        self.notify(&name, key, false);
+
         Ok(())
     }
+
+    // A record as `write_as` stores it, for writers that need the bytes.
+    fn seal(
+        &self,
+        tree: &[u8],
+        key: &[u8],
+        payload: &[u8],
+        codec: Codec,
+        last_update: u64,
+    ) -> Result<IVec, LocalStorageError> {
+        let payload = match self.compress {
+            true => compress(payload)?,
+            false => Cow::Borrowed(payload),
+        };
+        let compressed = matches!(payload, Cow::Owned(_));
+
+        Ok(encode_data(
+            self.record_version(tree, key),
+            (codec, compressed, self.hash),
+            &self.encrypt(tree, &payload)?,
+            last_update,
+        )
+        .into())
+    }
+
+    fn encrypt(&self, tree: &[u8], payload: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        match self.tree_key(tree)? {
+            Some(key) => aes_gcm_encrypt(&key, payload)
+                .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string())),
+            None => Ok(payload.to_vec()),
+        }
+    }
+
+    fn decrypt(&self, tree: &[u8], bytes: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        match self.tree_key(tree)? {
+            Some(key) => aes_gcm_decrypt(&key, bytes)
+                .map_err(|e| LocalStorageError::StorageDecryptError(e.to_string())),
+            None => Ok(bytes.to_vec()),
+        }
+    }
+
+    fn tree_key(&self, tree: &[u8]) -> Result<Option<[u8; AES_GCM_KEY_SIZE]>, LocalStorageError> {
+        self.cipher_key
+            .map(|key| domain_key(&key, tree))
+            .transpose()
+    }
+
+    // Salt is random per storage, the check value tells a wrong password
+    // apart from broken records.
+    fn unlock(&mut self, password: &[u8]) -> Result<(), LocalStorageError> {
+        self.cache
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+
+        let meta = self.open_tree(ENCRYPTION_META_TREE)?;
+        let get = |key: &[u8]| {
+            meta.get(key)
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))
+        };
+        let salt = match get(ENCRYPTION_SALT_KEY)? {
+            Some(salt) => salt.to_vec(),
+            None => {
+                let salt: [u8; ENCRYPTION_SALT_SIZE] = random_bytes();
+
+                meta.insert(ENCRYPTION_SALT_KEY, &salt)
+                    .or(Err(LocalStorageError::StorageWriteError))?;
+
+                salt.to_vec()
+            }
+        };
+        let key = cipher_key(password, &salt)?;
+
+        match get(ENCRYPTION_CHECK_KEY)? {
+            Some(check) => {
+                aes_gcm_decrypt(&key, &check).or(Err(LocalStorageError::StorageWrongPassword))?;
+                self.cipher_key = Some(key);
+            }
+            None => {
+                self.cipher_key = Some(key);
+                self.encrypt_existing()?;
+
+                let check = self.encrypt(ENCRYPTION_META_TREE, ENCRYPTION_CHECK_KEY)?;
+
+                meta.insert(ENCRYPTION_CHECK_KEY, check)
+                    .or(Err(LocalStorageError::StorageWriteError))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // An encrypted storage keeps index keys as a keyed digest: lookups still
+    // match, the addresses behind them don't show on disk.
+    pub(crate) fn index_key(
+        &self,
+        tree: &[u8],
+        index_key: &[u8],
+    ) -> Result<Vec<u8>, LocalStorageError> {
+        let Some(key) = &self.cipher_key else {
+            return Ok(index_key.to_vec());
+        };
+
+        derive_subkey(key, &[INDEX_BLIND_INFO, tree, b":", index_key].concat())
+            .map(|digest| digest.to_vec())
+            .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string()))
+    }
+
+    // Main and namespace trees hold DataWarp records, other trees raw values.
+    // Index trees are dropped, collections build them again with the key.
+    fn encrypt_existing(&self) -> Result<(), LocalStorageError> {
+        for name in self.tree.tree_names() {
+            if is_plain_tree(&name) {
+                continue;
+            }
+
+            let tree = self.open_tree(&name)?;
+
+            if is_index_tree(&name) {
+                tree.clear().or(Err(LocalStorageError::StorageWriteError))?;
+                continue;
+            }
+
+            let is_records = name == self.tree.name() || name.starts_with(NAMESPACE_TREE_PREFIX);
+
+            let entries = tree
+                .iter()
+                .collect::<Result<Vec<(IVec, IVec)>, sled::Error>>()
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+            for (key, value) in entries {
+                if is_records {
+                    let mut data = read_data(&tree, &key)?;
+
+                    if data.compressed {
+                        data.payload = decompress(&data.payload)?;
+                    }
+
+                    self.write_as(
+                        &tree,
+                        &key,
+                        &data.payload,
+                        data.last_update.unwrap_or(0),
+                        data.codec,
+                    )?;
+                } else {
+                    tree.insert(&key, self.encrypt(&name, &value)?)
+                        .or(Err(LocalStorageError::StorageWriteError))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Reads must not leave empty namespaces behind.
+    fn existing_tree(&self, tree: &[u8]) -> Result<Option<sled::Tree>, LocalStorageError> {
+        if self.tree.tree_names().iter().any(|name| name == tree) {
+            self.open_tree(tree).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn open_tree(&self, tree: &[u8]) -> Result<sled::Tree, LocalStorageError> {
+        self.tree
+            .open_tree(tree)
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))
+    }
 }
 
+fn cipher_key(password: &[u8], salt: &[u8]) -> Result<[u8; AES_GCM_KEY_SIZE], LocalStorageError> {
+    let derived = derive_key_with_salt(password, salt)
+        .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string()))?;
+    let mut key = [0u8; AES_GCM_KEY_SIZE];
+
+    key.copy_from_slice(&derived[..AES_GCM_KEY_SIZE]);
+
+    Ok(key)
+}
+
+// Isolated namespaces are sealed with their own subkey, so handing out the
+// key of one tree never opens the seed vault.
+fn domain_key(
+    key: &[u8; AES_GCM_KEY_SIZE],
+    tree: &[u8],
+) -> Result<[u8; AES_GCM_KEY_SIZE], LocalStorageError> {
+    if !ISOLATED_NAMESPACES
+        .iter()
+        .any(|ns| namespace_tree(ns) == tree)
+    {
+        return Ok(*key);
+    }
+
+    derive_subkey(key, &[ENCRYPTION_DOMAIN_INFO, tree].concat())
+        .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string()))
+}
+
+// Bookkeeping trees stay readable without the password.
+fn is_plain_tree(name: &[u8]) -> bool {
+    name == ENCRYPTION_META_TREE
+        || name == STORAGE_META_TREE
+        || name == SYNC_META_TREE
+        || name == SYNC_CLIENT_TREE
+        || name == SYNC_CURSOR_TREE
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+// Keys only, the values are empty.
+fn is_index_tree(name: &[u8]) -> bool {
+    name.starts_with(INDEX_TREE_PREFIX)
+}
+
+fn namespace_tree(ns: &[u8]) -> Vec<u8> {
+    [NAMESPACE_TREE_PREFIX, hex::encode(ns).as_bytes()].concat()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch(tree: &sled::Tree, key: &[u8]) -> Result<IVec, LocalStorageError> {
+    tree.get(key)
+        .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?
+        .ok_or(LocalStorageError::StorageDataNotFound)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_data(tree: &sled::Tree, key: &[u8]) -> Result<DataWarp, LocalStorageError> {
+    let some_value = tree
+        .get(key)
+        .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+    let value = some_value.ok_or(LocalStorageError::StorageDataNotFound)?;
+
+    decode_data(&value)
+}
+
+pub(crate) fn decode_data(bytes: &[u8]) -> Result<DataWarp, LocalStorageError> {
+    let data = DataWarp::from_bytes(bytes.into())?;
+
+    let intact = match (&data.digest, &data.hashsum) {
+        (Some(digest), _) => data.hash.digest(&data.payload) == *digest,
+        (None, Some(hashsum)) => verify_hashsum_with(data.hash, &data.payload, hashsum),
+        (None, None) => true,
+    };
+
+    if !intact {
+        return Err(LocalStorageError::StorageDataBroken);
+    }
+
+    Ok(data)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_data(
+    tree: &sled::Tree,
+    version: u16,
+    format: (Codec, bool, HashAlgo),
+    key: &[u8],
+    payload: &[u8],
+    last_update: u64,
+) -> Result<(), LocalStorageError> {
+    tree.insert(key, encode_data(version, format, payload, last_update))
+        .or(Err(LocalStorageError::StorageWriteError))?;
+
+    Ok(())
+}
+
+// `format` is the codec, whether `payload` is compressed and the hashsum
+// algorithm.
+pub(crate) fn encode_data(
+    version: u16,
+    format: (Codec, bool, HashAlgo),
+    payload: &[u8],
+    last_update: u64,
+) -> Vec<u8> {
+    let (codec, compressed, hash) = format;
+    let data = DataWarp {
+        payload: payload.into(),
+        version,
+        hashsum: Some(canonical_hashsum_with(hash, payload)),
+        last_update: Some(last_update),
+        codec,
+        compressed,
+        hash,
+        digest: Some(hash.digest(payload)),
+    };
+
+    data.to_bytes()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_millis() -> Result<u64, LocalStorageError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .or(Err(LocalStorageError::StorageTimeWentBackwards))?;
+
+    Ok(now.as_millis() as u64)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod storage_tests {
     use super::*;
 
+    #[test]
+    fn test_tree_read_write() {
+        const TREE: &[u8] = b"TEST_TREE";
+
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        assert_eq!(db.tree_get(TREE, b"key").unwrap(), None);
+
+        db.tree_set(TREE, b"key", b"value").unwrap();
+
+        assert_eq!(db.tree_get(TREE, b"key").unwrap(), Some(b"value".to_vec()));
+        assert!(!db.contains_key(b"key").unwrap());
+        assert!(db.tree_remove(TREE, b"key").unwrap());
+        assert!(!db.tree_remove(TREE, b"key").unwrap());
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+        let (old, new) = ([1u8; 32], [2u8; 32]);
+
+        db.ns_set(&old, b"history", b"old wallet").unwrap();
+        db.ns_set(&new, b"history", b"new wallet").unwrap();
+
+        assert_eq!(db.ns_get(&new, b"history").unwrap(), b"new wallet");
+        assert_eq!(
+            db.ns_get(&[3u8; 32], b"history"),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+        assert!(!db.contains_key(b"history").unwrap());
+
+        let mut namespaces = db.namespaces();
+
+        namespaces.sort();
+
+        assert_eq!(namespaces, vec![old.to_vec(), new.to_vec()]);
+        assert!(db.purge_namespace(&old).unwrap());
+        assert_eq!(db.namespaces(), vec![new.to_vec()]);
+        assert_eq!(
+            db.ns_get(&old, b"history"),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+    }
+
+    #[test]
+    fn test_encrypted_storage() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let plain = LocalStorage::from(&dir).unwrap();
+
+        plain.set(b"legacy", br#"{"seed":"words"}"#).unwrap();
+        plain.ns_set(&[1u8; 32], b"history", b"[]").unwrap();
+        drop(plain);
+
+        let db = LocalStorage::from_encrypted(&dir, b"password").unwrap();
+
+        assert!(db.is_encrypted());
+        assert_eq!(db.get(b"legacy").unwrap(), br#"{"seed":"words"}"#);
+        assert_eq!(db.ns_get(&[1u8; 32], b"history").unwrap(), b"[]");
+
+        db.tree_set(b"cache", b"key", b"value").unwrap();
+        db.set(b"new", b"secret").unwrap();
+
+        assert_eq!(
+            db.tree_get(b"cache", b"key").unwrap(),
+            Some(b"value".to_vec())
+        );
+        assert_eq!(
+            db.get_data(b"new").unwrap().hashsum,
+            Some(canonical::canonical_hashsum(b"secret"))
+        );
+        drop(db);
+
+        assert!(matches!(
+            LocalStorage::from_encrypted(&dir, b"wrong"),
+            Err(LocalStorageError::StorageWrongPassword)
+        ));
+
+        let raw = LocalStorage::from(&dir).unwrap();
+
+        assert_ne!(raw.get(b"legacy").unwrap(), br#"{"seed":"words"}"#);
+        assert_ne!(raw.get(b"new").unwrap(), b"secret");
+        assert_ne!(
+            raw.tree_get(b"cache", b"key").unwrap(),
+            Some(b"value".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_isolated_namespace() {
+        use config::storage::VAULT_NS;
+
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let plain = LocalStorage::from(&dir).unwrap();
+
+        plain.ns_set(VAULT_NS, b"proof", b"cipher").unwrap();
+        drop(plain);
+
+        let mut db = LocalStorage::from_encrypted(&dir, b"password").unwrap();
+
+        db.ns_set(&[1u8; 32], b"history", b"[]").unwrap();
+
+        let master = db.cipher_key.unwrap();
+        let sealed = |ns: &[u8], key: &[u8]| {
+            let tree = db.open_tree(&namespace_tree(ns)).unwrap();
+
+            read_data(&tree, key).unwrap().payload
+        };
+
+        assert!(aes_gcm_decrypt(&master, &sealed(&[1u8; 32], b"history")).is_ok());
+        // the master key alone doesn't open the vault
+        assert!(aes_gcm_decrypt(&master, &sealed(VAULT_NS, b"proof")).is_err());
+        assert_eq!(db.ns_get(VAULT_NS, b"proof").unwrap(), b"cipher");
+
+        db.rotate_key(b"password", b"new").unwrap();
+        drop(db);
+
+        let db = LocalStorage::from_encrypted(&dir, b"new").unwrap();
+
+        assert_eq!(db.ns_get(VAULT_NS, b"proof").unwrap(), b"cipher");
+        assert!(db.verify_integrity().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_migrate_on_read() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let old = LocalStorage::from(&dir).unwrap();
+
+        old.set(b"contact:0", br#"{"addr":"zil1"}"#).unwrap();
+        old.set(b"contact:1", &[0xde, 0xad]).unwrap();
+        old.set(b"gc_last_run", &[0xbe, 0xef]).unwrap();
+        drop(old);
+
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        db.register_migration(b"contact:", STORAGE_VERSION, |old, _| {
+            Ok(serde_json::to_vec(&serde_json::json!({ "address": old["addr"] })).unwrap())
+        })
+        .unwrap();
+
+        assert_eq!(db.version(), STORAGE_VERSION + 1);
+        assert_eq!(db.get(b"contact:0").unwrap(), br#"{"address":"zil1"}"#);
+        // written back, the raw record is upgraded as well
+        assert_eq!(
+            read_data(&db.tree, b"contact:0").unwrap().version,
+            STORAGE_VERSION + 1
+        );
+        // binary records in and out of scope are never handed to the migrator
+        assert_eq!(db.get(b"contact:1").unwrap(), [0xde, 0xad]);
+        assert_eq!(db.get(b"gc_last_run").unwrap(), [0xbe, 0xef]);
+        db.set(b"gc_last_run", &[0x01]).unwrap();
+        assert_eq!(
+            read_data(&db.tree, b"gc_last_run").unwrap().version,
+            STORAGE_VERSION
+        );
+    }
+
+    #[test]
+    fn test_scan_prefix() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from_encrypted(&dir, b"password").unwrap();
+
+        db.set(b"account:1", b"\"bob\"").unwrap();
+        db.set(b"account:0", b"\"alice\"").unwrap();
+        db.set(b"token:zlp", b"{}").unwrap();
+
+        let accounts: Vec<(String, String)> = db
+            .scan_prefix("account:")
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            accounts,
+            vec![
+                ("account:0".to_string(), "alice".to_string()),
+                ("account:1".to_string(), "bob".to_string()),
+            ]
+        );
+        assert_eq!(db.keys().count(), 3);
+        assert!(matches!(
+            db.scan_prefix::<u64>("token:").next(),
+            Some(Err(LocalStorageError::PayloadParseError))
+        ));
+    }
+
     #[test]
     fn test_read_write() {
         const KEY: &[u8] = b"TEST_KEY_FOR_STORAGE";
@@ -112,4 +1196,121 @@ mod storage_tests {
 
         assert_eq!(out, payload);
     }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        assert!(!db.contains_key(b"nonce").unwrap());
+        assert_eq!(db.get_or_default::<u64>(b"nonce").unwrap(), 0);
+        assert_eq!(db.get_or_insert_with(b"nonce", || 7u64).unwrap(), 7);
+        assert_eq!(db.get_or_insert_with(b"nonce", || 9u64).unwrap(), 7);
+        assert_eq!(db.get_or_default::<u64>(b"nonce").unwrap(), 7);
+        assert!(db.contains_key(b"nonce").unwrap());
+
+        db.set(b"broken", b"not json").unwrap();
+
+        assert_eq!(
+            db.get_or_default::<u64>(b"broken"),
+            Err(LocalStorageError::PayloadParseError)
+        );
+    }
+
+    #[test]
+    fn test_flush() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"wallet", b"[]").unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        let db = LocalStorage::from(&dir).unwrap();
+
+        assert_eq!(db.get(b"wallet").unwrap(), b"[]");
+    }
+
+    #[tokio::test]
+    async fn test_flush_async() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"account", b"{}").unwrap();
+        db.flush_async().await.unwrap();
+        drop(db);
+
+        let db = LocalStorage::from(&dir).unwrap();
+
+        assert_eq!(db.get(b"account").unwrap(), b"{}");
+    }
+
+    #[test]
+    fn test_blake3_hashsum() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"old", br#"{"a":1}"#).unwrap();
+        db.set_hash_algo(HashAlgo::Blake3).unwrap();
+        drop(db);
+
+        let db = LocalStorage::from(&dir).unwrap();
+
+        assert_eq!(db.hash_algo(), HashAlgo::Blake3);
+
+        db.set(b"new", br#"{ "b": 2 }"#).unwrap();
+
+        let new = db.get_data(b"new").unwrap();
+
+        assert_eq!(new.hash, HashAlgo::Blake3);
+        assert_eq!(
+            new.hashsum,
+            Some(canonical_hashsum_with(HashAlgo::Blake3, br#"{"b":2}"#))
+        );
+        assert_eq!(new.digest, Some(HashAlgo::Blake3.digest(br#"{ "b": 2 }"#)));
+        // written before the switch, still SHA-256
+        assert_eq!(db.get_data(b"old").unwrap().hash, HashAlgo::Sha256);
+        assert_eq!(db.get(b"old").unwrap(), br#"{"a":1}"#);
+        assert!(db.verify_integrity().unwrap().is_ok());
+
+        let mut broken = decode_data(&db.tree.get(b"new").unwrap().unwrap()).unwrap();
+
+        broken.payload = br#"{"b":3}"#.to_vec();
+        db.tree.insert(b"new", broken.to_bytes()).unwrap();
+        db.cache().clear();
+
+        assert_eq!(db.get(b"new"), Err(LocalStorageError::StorageDataBroken));
+    }
+
+    #[test]
+    fn test_broken_hashsum() {
+        const KEY: &[u8] = b"TEST_KEY_BROKEN_HASHSUM";
+
+        let db =
+            LocalStorage::new("com.test_hashsum", "HashsumTest Corp", "HashsumTest App").unwrap();
+        let data = DataWarp {
+            payload: br#"{"a":1}"#.to_vec(),
+            version: 0,
+            hashsum: Some([0u8; config::sha::SHA256_SIZE]),
+            last_update: None,
+            codec: Codec::Json,
+            compressed: false,
+            hash: HashAlgo::Sha256,
+            digest: None,
+        };
+
+        db.tree.insert(KEY, data.to_bytes()).unwrap();
+
+        assert_eq!(db.get(KEY), Err(LocalStorageError::StorageDataBroken));
+
+        let legacy = DataWarp {
+            hashsum: None,
+            last_update: None,
+            ..data
+        };
+
+        db.tree.insert(KEY, legacy.to_bytes()).unwrap();
+
+        assert_eq!(db.get(KEY).unwrap(), br#"{"a":1}"#.to_vec());
+    }
 }
@@ -0,0 +1,236 @@
+use crate::LocalStorage;
+use config::storage::{READ_ONLY_COPY_ATTEMPTS, READ_ONLY_COPY_SUFFIX, STORAGE_LOCK_FILE};
+use crypto::entropy::random_bytes;
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use zil_errors::storage::LocalStorageError;
+
+// Size and modification time of every file, to tell whether a copy raced
+// with a writer.
+type DirState = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+// Held for the lifetime of the storage, the OS drops it if the process dies.
+pub(crate) fn acquire(path: &str) -> Result<File, LocalStorageError> {
+    fs::create_dir_all(path).map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(Path::new(path).join(STORAGE_LOCK_FILE))
+        .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(LocalStorageError::StorageLockedByAnotherProcess),
+        Err(TryLockError::Error(e)) => Err(LocalStorageError::StorageAccessError(e.to_string())),
+    }
+}
+
+impl LocalStorage {
+    /// Opens a copy of the database at `path` without taking its lock, so a
+    /// companion tool can inspect it while the wallet runs. The copy sits
+    /// next to the database in a directory only the owner can open, and
+    /// holds what was flushed when no file changed from the start of the
+    /// copy to its end. A copy that raced with a write is taken again, up
+    /// to `READ_ONLY_COPY_ATTEMPTS` times. Writes are rejected, the copy is
+    /// deleted on drop, or by the next open after a crash.
+    pub fn open_read_only(path: &str) -> Result<Self, LocalStorageError> {
+        let access = |e: std::io::Error| LocalStorageError::StorageAccessError(e.to_string());
+
+        remove_stale_copies(path)?;
+
+        for _ in 0..READ_ONLY_COPY_ATTEMPTS {
+            let copy = PathBuf::from(format!(
+                "{path}{READ_ONLY_COPY_SUFFIX}{}",
+                hex::encode(random_bytes::<8>())
+            ));
+            let copy_str = copy.to_str().ok_or(LocalStorageError::StoragePathError)?;
+            let before = dir_state(Path::new(path)).map_err(access)?;
+
+            create_private_dir(&copy).map_err(access)?;
+
+            // held while open, tells `remove_stale_copies` this one is in use
+            let lock = acquire(copy_str)?;
+
+            copy_dir(Path::new(path), &copy).map_err(access)?;
+
+            if dir_state(Path::new(path)).map_err(access)? != before {
+                drop(lock);
+                fs::remove_dir_all(&copy).map_err(access)?;
+                continue;
+            }
+
+            let mut storage = Self::open_unlocked(copy_str)?;
+
+            storage._lock = Some(lock);
+            storage.read_only = Some(copy);
+
+            return Ok(storage);
+        }
+
+        Err(LocalStorageError::StorageAccessError(
+            "database kept changing while it was copied".to_string(),
+        ))
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.is_some()
+    }
+
+    pub(crate) fn writable(&self) -> Result<(), LocalStorageError> {
+        if self.is_read_only() {
+            Err(LocalStorageError::StorageReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for LocalStorage {
+    fn drop(&mut self) {
+        if let Some(copy) = self.read_only.take() {
+            let _ = fs::remove_dir_all(copy);
+        }
+    }
+}
+
+// Copies left behind by a crashed inspector, the ones still open keep their
+// lock.
+pub(crate) fn remove_stale_copies(path: &str) -> Result<(), LocalStorageError> {
+    let path = Path::new(path);
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(());
+    };
+    let prefix = format!("{}{READ_ONLY_COPY_SUFFIX}", name.to_string_lossy());
+    let parent = match parent.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => parent,
+    };
+    let entries = match fs::read_dir(parent) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(()),
+    };
+
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with(&prefix) {
+            continue;
+        }
+
+        let copy = entry.path();
+        let in_use = match copy.to_str() {
+            Some(copy) => acquire(copy).is_err(),
+            None => true,
+        };
+
+        if !in_use {
+            fs::remove_dir_all(&copy)
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+    builder.recursive(true).create(path)
+}
+
+fn dir_state(dir: &Path) -> std::io::Result<DirState> {
+    let mut state = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_dir() {
+            state.extend(dir_state(&entry.path())?);
+        } else if entry.file_name() != STORAGE_LOCK_FILE {
+            let meta = entry.metadata()?;
+
+            state.push((entry.path(), meta.len(), meta.modified().ok()));
+        }
+    }
+
+    state.sort();
+
+    Ok(state)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    create_private_dir(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if entry.file_name() != STORAGE_LOCK_FILE {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_instance() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"wallets", b"[]").unwrap();
+        db.flush().unwrap();
+
+        assert_eq!(
+            LocalStorage::from(&dir).err(),
+            Some(LocalStorageError::StorageLockedByAnotherProcess)
+        );
+
+        let inspect = LocalStorage::open_read_only(&dir).unwrap();
+
+        assert!(inspect.is_read_only());
+        assert_eq!(inspect.get(b"wallets").unwrap(), b"[]");
+        assert_eq!(
+            inspect.set(b"wallets", b"[1]"),
+            Err(LocalStorageError::StorageReadOnly)
+        );
+        assert_eq!(db.get(b"wallets").unwrap(), b"[]");
+
+        let copy = inspect.read_only.clone().unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&copy).unwrap().permissions().mode();
+
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        // still open, a second inspector leaves it alone
+        drop(LocalStorage::open_read_only(&dir).unwrap());
+        assert!(copy.exists());
+
+        drop(inspect);
+        assert!(!copy.exists());
+
+        // what a crashed inspector leaves behind, nobody holds its lock
+        let stale = format!("{dir}{READ_ONLY_COPY_SUFFIX}0000");
+
+        copy_dir(Path::new(&dir), Path::new(&stale)).unwrap();
+        drop(LocalStorage::open_read_only(&dir).unwrap());
+        assert!(!Path::new(&stale).exists());
+    }
+}
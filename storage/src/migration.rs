@@ -0,0 +1,156 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod extension;
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use zil_errors::storage::LocalStorageError;
+
+/// Upgrades a record written at `old_version` to `old_version + 1`.
+pub type Migrator = fn(old_json: Value, old_version: u16) -> Result<Vec<u8>, LocalStorageError>;
+
+/// Migrations only apply to the records of their scope, a tree and a key
+/// prefix in it, everything else keeps the base version. Vaults, markers
+/// and other binary records never reach a migrator this way.
+#[derive(Debug, Default, Clone)]
+pub struct MigrationRegistry {
+    scopes: BTreeMap<(Vec<u8>, Vec<u8>), BTreeMap<u16, Migrator>>,
+}
+
+impl MigrationRegistry {
+    pub fn register(
+        &mut self,
+        tree: &[u8],
+        prefix: &[u8],
+        from_version: u16,
+        migrator: Migrator,
+    ) -> Result<(), LocalStorageError> {
+        if from_version == u16::MAX {
+            return Err(LocalStorageError::StorageMigrationError(
+                "no version after u16::MAX".to_string(),
+            ));
+        }
+
+        self.scopes
+            .entry((tree.to_vec(), prefix.to_vec()))
+            .or_default()
+            .insert(from_version, migrator);
+
+        Ok(())
+    }
+
+    // Newest version of any scope.
+    pub fn latest(&self, base: u16) -> u16 {
+        self.scopes
+            .values()
+            .map(|migrators| latest_of(migrators, base))
+            .fold(base, u16::max)
+    }
+
+    // Version the record at `key` is written with, the longest prefix wins.
+    pub fn version_of(&self, tree: &[u8], key: &[u8], base: u16) -> u16 {
+        self.scope(tree, key)
+            .map(|migrators| latest_of(migrators, base))
+            .unwrap_or(base)
+    }
+
+    /// Runs every step from `from` up to the version of the key's scope, a
+    /// gap in the chain is an error rather than a silently half-upgraded
+    /// record. `None` when there is nothing to do for this record.
+    pub fn upgrade(
+        &self,
+        tree: &[u8],
+        key: &[u8],
+        payload: &[u8],
+        from: u16,
+        base: u16,
+    ) -> Result<Option<Vec<u8>>, LocalStorageError> {
+        let Some(migrators) = self.scope(tree, key) else {
+            return Ok(None);
+        };
+        let to = latest_of(migrators, base);
+
+        if from >= to {
+            return Ok(None);
+        }
+
+        upgrade_chain(migrators, payload, from, to).map(Some)
+    }
+
+    fn scope(&self, tree: &[u8], key: &[u8]) -> Option<&BTreeMap<u16, Migrator>> {
+        self.scopes
+            .iter()
+            .filter(|((t, prefix), _)| t == tree && key.starts_with(prefix))
+            .max_by_key(|((_, prefix), _)| prefix.len())
+            .map(|(_, migrators)| migrators)
+    }
+}
+
+fn latest_of(migrators: &BTreeMap<u16, Migrator>, base: u16) -> u16 {
+    // `register` keeps u16::MAX out, the step can't overflow
+    migrators
+        .keys()
+        .next_back()
+        .map(|v| (v + 1).max(base))
+        .unwrap_or(base)
+}
+
+fn upgrade_chain(
+    migrators: &BTreeMap<u16, Migrator>,
+    payload: &[u8],
+    from: u16,
+    to: u16,
+) -> Result<Vec<u8>, LocalStorageError> {
+    let mut payload = payload.to_vec();
+
+    for version in from..to {
+        let migrator = migrators
+            .get(&version)
+            .ok_or(LocalStorageError::StorageMigrationMissing(version))?;
+        let json = serde_json::from_slice(&payload)
+            .map_err(|e| LocalStorageError::StorageMigrationError(e.to_string()))?;
+
+        payload = migrator(json, version)?;
+    }
+
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn v0_to_v1(old: Value, _: u16) -> Result<Vec<u8>, LocalStorageError> {
+        // v1 renamed `addr` to `address`
+        Ok(serde_json::to_vec(&json!({ "address": old["addr"] })).unwrap())
+    }
+
+    #[test]
+    fn test_upgrade_chain() {
+        let mut registry = MigrationRegistry::default();
+
+        registry
+            .register(b"main", b"contact:", 0, v0_to_v1)
+            .unwrap();
+
+        assert_eq!(registry.latest(0), 1);
+        assert_eq!(registry.version_of(b"main", b"contact:0", 0), 1);
+        assert_eq!(registry.version_of(b"main", b"vault", 0), 0);
+        assert_eq!(
+            registry
+                .upgrade(b"main", b"contact:0", br#"{"addr":"zil1"}"#, 0, 0)
+                .unwrap(),
+            Some(br#"{"address":"zil1"}"#.to_vec())
+        );
+        // out of scope records are left alone
+        assert_eq!(
+            registry.upgrade(b"main", b"vault", b"\x00\x01", 0, 0),
+            Ok(None)
+        );
+        assert_eq!(
+            registry.upgrade(b"main", b"contact:0", br#"{}"#, 0, 2),
+            Err(LocalStorageError::StorageMigrationMissing(1))
+        );
+        assert!(registry.register(b"main", b"", u16::MAX, v0_to_v1).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{collection::Collection, LocalStorage};
+use cipher::cryptojs::cryptojs_decrypt;
+use config::storage::{EXTENSION_CONTACTS_NS, EXTENSION_TOKENS_NS};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use zil_errors::storage::LocalStorageError;
+
+/// One account of the extension, `account_type` 0 is derived from the seed,
+/// 1 an imported private key, 2 a Ledger account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionIdentity {
+    #[serde(default)]
+    pub name: String,
+    pub bech32: String,
+    #[serde(default)]
+    pub base16: String,
+    #[serde(default)]
+    pub index: u32,
+    #[serde(default, rename = "type")]
+    pub account_type: u8,
+    #[serde(default)]
+    pub pub_key: Option<String>,
+}
+
+/// The extension's `wallet` entry. The `vault` is kept as the extension
+/// sealed it, the seed is only recovered once the user enters the password.
+/// Identities of type 1 point at `decryptImported` by their `index`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionWallet {
+    #[serde(default)]
+    pub selected_address: usize,
+    #[serde(default)]
+    pub identities: Vec<ExtensionIdentity>,
+    #[serde(default)]
+    pub vault: Option<String>,
+}
+
+/// Plaintext of the vault.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionSecrets {
+    #[serde(default)]
+    pub decrypt_seed: String,
+    #[serde(default)]
+    pub decrypt_imported: Vec<ExtensionImportedKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionImportedKey {
+    pub index: u32,
+    pub private_key: String,
+}
+
+impl ExtensionWallet {
+    pub fn open_vault(&self, password: &[u8]) -> Result<ExtensionSecrets, LocalStorageError> {
+        let vault = self
+            .vault
+            .as_deref()
+            .ok_or(LocalStorageError::StorageExtensionFormat(
+                "no vault".to_string(),
+            ))?;
+        let plain =
+            cryptojs_decrypt(password, vault).or(Err(LocalStorageError::StorageWrongPassword))?;
+
+        serde_json::from_slice(&plain)
+            .map_err(|e| LocalStorageError::StorageExtensionFormat(format!("vault: {e}")))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionContact {
+    pub name: String,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionToken {
+    #[serde(default)]
+    pub name: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub decimals: u8,
+    pub base16: String,
+    #[serde(default)]
+    pub bech32: String,
+}
+
+/// What the extension keeps in localStorage, as dumped by its export: an
+/// object of `wallet`, `vault`, `contacts` and `tokens`. Values may also be
+/// the JSON strings localStorage holds, unknown keys are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionData {
+    pub wallet: Option<ExtensionWallet>,
+    pub contacts: Vec<ExtensionContact>,
+    pub tokens: Vec<ExtensionToken>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtensionImport {
+    pub accounts: usize,
+    pub contacts: usize,
+    pub tokens: usize,
+}
+
+impl ExtensionData {
+    pub fn from_json(bytes: &[u8]) -> Result<Self, LocalStorageError> {
+        let dump: Value = serde_json::from_slice(bytes)
+            .map_err(|e| LocalStorageError::StorageExtensionFormat(e.to_string()))?;
+        let entries = dump.as_object().ok_or_else(|| {
+            LocalStorageError::StorageExtensionFormat("not an object".to_string())
+        })?;
+        let mut wallet: Option<ExtensionWallet> = field(entries.get("wallet"), "wallet")?;
+
+        if let Some(vault) = field::<String>(entries.get("vault"), "vault")? {
+            wallet.get_or_insert_with(Default::default).vault = Some(vault);
+        }
+
+        Ok(Self {
+            wallet,
+            contacts: field(entries.get("contacts"), "contacts")?.unwrap_or_default(),
+            tokens: field(entries.get("tokens"), "tokens")?.unwrap_or_default(),
+        })
+    }
+}
+
+impl LocalStorage {
+    /// Writes the contacts keyed by address and the token metadata by
+    /// base16 address. Importing twice keeps one record per key. Wallets
+    /// and their accounts come from the vault, see `Background`.
+    pub fn import_extension(
+        &self,
+        data: &ExtensionData,
+    ) -> Result<ExtensionImport, LocalStorageError> {
+        let contacts = Collection::<ExtensionContact>::new(self, EXTENSION_CONTACTS_NS)?;
+        let tokens = Collection::<ExtensionToken>::new(self, EXTENSION_TOKENS_NS)?;
+
+        for contact in &data.contacts {
+            contacts.insert(&contact.address, contact)?;
+        }
+
+        for token in &data.tokens {
+            tokens.insert(&token.base16.to_lowercase(), token)?;
+        }
+
+        Ok(ExtensionImport {
+            accounts: 0,
+            contacts: data.contacts.len(),
+            tokens: data.tokens.len(),
+        })
+    }
+}
+
+fn field<T: DeserializeOwned>(
+    value: Option<&Value>,
+    name: &str,
+) -> Result<Option<T>, LocalStorageError> {
+    let invalid =
+        |e: serde_json::Error| LocalStorageError::StorageExtensionFormat(format!("{name}: {e}"));
+
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        // a JSON string as localStorage keeps it, or a plain string value
+        Some(Value::String(text)) => serde_json::from_str(text)
+            .or_else(|_| serde_json::from_value(Value::String(text.clone())))
+            .map(Some)
+            .map_err(invalid),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(invalid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"{
+        "vault": "U2FsdGVkX1+sealed",
+        "wallet": "{\"selectedAddress\":1,\"identities\":[{\"name\":\"Account 0\",\"bech32\":\"zil1a\",\"base16\":\"0xA\",\"index\":0,\"type\":0,\"pubKey\":\"02ab\"},{\"name\":\"Imported\",\"bech32\":\"zil1b\",\"base16\":\"0xB\",\"index\":0,\"type\":1}]}",
+        "contacts": [{ "name": "Alice", "address": "zil1alice" }],
+        "tokens": [
+            { "name": "ZilPay wallet", "symbol": "ZLP", "decimals": 18, "base16": "0xFBD07E692543D3064B9CF570B27FAABFD7948DA4", "bech32": "zil1l0g8u6f9g0fsvjuu74ctyla2hltefrdyt7k5f4" }
+        ],
+        "theme": "dark"
+    }"#;
+
+    #[test]
+    fn test_import_extension() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+        let data = ExtensionData::from_json(DUMP.as_bytes()).unwrap();
+        let wallet = data.wallet.clone().unwrap();
+
+        assert_eq!(wallet.selected_address, 1);
+        assert_eq!(wallet.identities[1].account_type, 1);
+        assert_eq!(wallet.vault.as_deref(), Some("U2FsdGVkX1+sealed"));
+        assert_eq!(
+            db.import_extension(&data).unwrap(),
+            ExtensionImport {
+                accounts: 0,
+                contacts: 1,
+                tokens: 1
+            }
+        );
+
+        db.import_extension(&data).unwrap();
+
+        let tokens = Collection::<ExtensionToken>::new(&db, EXTENSION_TOKENS_NS).unwrap();
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(
+            tokens
+                .get(&"0xfbd07e692543d3064b9cf570b27faabfd7948da4".to_string())
+                .unwrap()
+                .unwrap()
+                .symbol,
+            "ZLP"
+        );
+        assert_eq!(
+            wallet.open_vault(b"password"),
+            Err(LocalStorageError::StorageWrongPassword)
+        );
+        assert!(matches!(
+            ExtensionData::from_json(br#"{ "contacts": {} }"#),
+            Err(LocalStorageError::StorageExtensionFormat(_))
+        ));
+    }
+}
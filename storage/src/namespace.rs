@@ -0,0 +1,210 @@
+use crate::{namespace_tree, now_millis, LocalStorage};
+use config::storage::NAMESPACE_TREE_PREFIX;
+use zil_errors::storage::LocalStorageError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NamespaceStats {
+    pub entries: usize,
+    pub bytes: u64, // keys plus stored values, as on disk
+}
+
+/// Everything on disk: the main records, each namespace and the raw trees
+/// (caches, sync and encryption bookkeeping).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageStats {
+    pub main: NamespaceStats,
+    pub namespaces: Vec<(Vec<u8>, NamespaceStats)>,
+    pub trees: Vec<(Vec<u8>, NamespaceStats)>,
+    pub disk_bytes: u64,
+}
+
+impl StorageStats {
+    pub fn total(&self) -> NamespaceStats {
+        self.namespaces
+            .iter()
+            .chain(&self.trees)
+            .fold(self.main, |total, (_, stats)| NamespaceStats {
+                entries: total.entries + stats.entries,
+                bytes: total.bytes + stats.bytes,
+            })
+    }
+
+    // sled does not track disk usage per tree, this is the share of `stats`
+    // in the stored bytes applied to the file size.
+    pub fn disk_usage(&self, stats: &NamespaceStats) -> u64 {
+        let total = self.total().bytes;
+
+        if total == 0 {
+            return 0;
+        }
+
+        (self.disk_bytes as u128 * stats.bytes as u128 / total as u128) as u64
+    }
+}
+
+/// Records of one subsystem ("accounts", "tokens") in a tree of their own.
+/// Same trees as `ns_get`/`ns_set`, so a namespace can be reached either way.
+pub struct Namespace<'a> {
+    pub(crate) storage: &'a LocalStorage,
+    pub(crate) tree: sled::Tree,
+}
+
+impl LocalStorage {
+    pub fn open_namespace(&self, name: &str) -> Result<Namespace<'_>, LocalStorageError> {
+        Ok(Namespace {
+            storage: self,
+            tree: self.open_tree(&namespace_tree(name.as_bytes()))?,
+        })
+    }
+
+    pub fn drop_namespace(&self, name: &str) -> Result<bool, LocalStorageError> {
+        self.purge_namespace(name.as_bytes())
+    }
+
+    pub fn namespace_stats(&self) -> Result<Vec<(Vec<u8>, NamespaceStats)>, LocalStorageError> {
+        self.namespaces()
+            .into_iter()
+            .map(|ns| {
+                let tree = self.open_tree(&namespace_tree(&ns))?;
+
+                Ok((ns, tree_stats(&tree)?))
+            })
+            .collect()
+    }
+
+    pub fn stats(&self) -> Result<StorageStats, LocalStorageError> {
+        let mut trees = Vec::new();
+
+        for name in self.tree.tree_names() {
+            if name == self.tree.name() || name.starts_with(NAMESPACE_TREE_PREFIX) {
+                continue;
+            }
+
+            trees.push((name.to_vec(), tree_stats(&self.open_tree(&name)?)?));
+        }
+
+        Ok(StorageStats {
+            main: tree_stats(&self.tree)?,
+            namespaces: self.namespace_stats()?,
+            trees,
+            disk_bytes: self.get_db_size(),
+        })
+    }
+}
+
+impl Namespace<'_> {
+    pub fn get(&self, key: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        Ok(self.storage.read(&self.tree, key)?.payload)
+    }
+
+    pub fn set(&self, key: &[u8], payload: &[u8]) -> Result<(), LocalStorageError> {
+        self.storage.write(&self.tree, key, payload, now_millis()?)
+    }
+
+    pub fn remove(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        self.storage.writable()?;
+
+        let removed = self
+            .tree
+            .remove(key)
+            .or(Err(LocalStorageError::StorageWriteError))?
+            .is_some();
+
+        if removed {
+            self.storage.changed(&self.tree.name(), key, true)?;
+        }
+
+        Ok(removed)
+    }
+
+    // Empties the namespace but keeps it, other namespaces are untouched.
+    pub fn clear(&self) -> Result<(), LocalStorageError> {
+        self.storage.writable()?;
+        self.tree
+            .clear()
+            .or(Err(LocalStorageError::StorageWriteError))?;
+        self.storage.cleared(&self.tree.name())?;
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn stats(&self) -> Result<NamespaceStats, LocalStorageError> {
+        tree_stats(&self.tree)
+    }
+}
+
+fn tree_stats(tree: &sled::Tree) -> Result<NamespaceStats, LocalStorageError> {
+    tree.iter()
+        .try_fold(NamespaceStats::default(), |mut stats, entry| {
+            let (key, value) =
+                entry.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+            stats.entries += 1;
+            stats.bytes += (key.len() + value.len()) as u64;
+
+            Ok(stats)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaces_are_isolated() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+        let accounts = db.open_namespace("accounts").unwrap();
+        let tokens = db.open_namespace("tokens").unwrap();
+
+        accounts.set(b"0", b"alice").unwrap();
+        accounts.set(b"1", b"bob").unwrap();
+        tokens.set(b"0", b"zlp").unwrap();
+
+        assert_eq!(accounts.get(b"0").unwrap(), b"alice");
+        assert_eq!(tokens.get(b"0").unwrap(), b"zlp");
+        assert_eq!(db.ns_get(b"tokens", b"0").unwrap(), b"zlp");
+        assert_eq!(accounts.stats().unwrap().entries, 2);
+
+        accounts.clear().unwrap();
+
+        assert!(accounts.is_empty());
+        assert_eq!(tokens.len(), 1);
+
+        let stats = db.namespace_stats().unwrap();
+        let (_, token_stats) = stats.iter().find(|(ns, _)| ns == b"tokens").unwrap();
+
+        assert_eq!(token_stats.entries, 1);
+        assert!(token_stats.bytes > 4);
+
+        db.set(b"settings", b"{}").unwrap();
+        db.tree_set(b"cache", b"key", b"value").unwrap();
+
+        let stats = db.stats().unwrap();
+
+        assert_eq!(stats.main.entries, 1);
+        assert_eq!(stats.namespaces.len(), 2);
+        assert!(stats
+            .trees
+            .iter()
+            .any(|(name, s)| name == b"cache" && s.entries == 1));
+        assert_eq!(
+            stats.total().entries,
+            stats.main.entries + 1 + stats.trees.iter().map(|(_, s)| s.entries).sum::<usize>()
+        );
+        assert!(stats.disk_usage(&stats.main) <= stats.disk_bytes);
+        assert!(db.drop_namespace("tokens").unwrap());
+        assert_eq!(
+            db.ns_get(b"tokens", b"0"),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{snapshot::write_atomic, LocalStorage};
+use config::storage::{
+    PROFILES_DIR, PROFILES_REGISTRY_FILE, PROFILE_NAME_MAX_LEN, STORAGE_APPLICATION,
+    STORAGE_ORGANIZATION, STORAGE_QUALIFIER,
+};
+use directories::ProjectDirs;
+use std::{fs, path::PathBuf};
+use zil_errors::storage::LocalStorageError;
+
+/// Separate databases ("work", "personal") side by side under one root,
+/// each in a directory of its own so they never share state. A registry
+/// file lists the names, in creation order. It is not a sled database, sled
+/// holds on to its lock a while after the last handle is dropped.
+pub struct Profiles {
+    root: PathBuf,
+}
+
+impl Profiles {
+    // The platform data directory, as used by `LocalStorage::new`.
+    pub fn new() -> Result<Self, LocalStorageError> {
+        let dirs = ProjectDirs::from(STORAGE_QUALIFIER, STORAGE_ORGANIZATION, STORAGE_APPLICATION)
+            .ok_or(LocalStorageError::StoragePathError)?;
+
+        Ok(Self {
+            root: dirs.data_dir().to_path_buf(),
+        })
+    }
+
+    pub fn at(root: &str) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Opens the profile, creating and registering it on first use.
+    pub fn open(&self, name: &str) -> Result<LocalStorage, LocalStorageError> {
+        validate_name(name)?;
+
+        let path = self.path(name)?;
+        let storage = LocalStorage::from(&path)?;
+        let mut names = self.list()?;
+
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+            self.save(&names)?;
+        }
+
+        Ok(storage)
+    }
+
+    pub fn list(&self) -> Result<Vec<String>, LocalStorageError> {
+        match fs::read(self.root.join(PROFILES_REGISTRY_FILE)) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).or(Err(LocalStorageError::PayloadParseError))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(_) => Err(LocalStorageError::FailToReadFile),
+        }
+    }
+
+    // Deletes the database of the profile, it must not be open.
+    pub fn remove(&self, name: &str) -> Result<bool, LocalStorageError> {
+        validate_name(name)?;
+
+        let mut names = self.list()?;
+        let registered = names.iter().any(|n| n == name);
+        let dir = self.root.join(PROFILES_DIR).join(name);
+
+        if dir.exists() {
+            fs::remove_dir_all(&dir)
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+        }
+
+        if registered {
+            names.retain(|n| n != name);
+            self.save(&names)?;
+        }
+
+        Ok(registered)
+    }
+
+    fn save(&self, names: &[String]) -> Result<(), LocalStorageError> {
+        let path = self.root.join(PROFILES_REGISTRY_FILE);
+        let bytes = serde_json::to_vec(names).or(Err(LocalStorageError::PayloadParseError))?;
+
+        write_atomic(
+            path.to_str().ok_or(LocalStorageError::StoragePathError)?,
+            &bytes,
+        )
+    }
+
+    fn path(&self, name: &str) -> Result<String, LocalStorageError> {
+        self.root
+            .join(PROFILES_DIR)
+            .join(name)
+            .to_str()
+            .map(String::from)
+            .ok_or(LocalStorageError::StoragePathError)
+    }
+}
+
+impl LocalStorage {
+    pub fn profile(name: &str) -> Result<Self, LocalStorageError> {
+        Profiles::new()?.open(name)
+    }
+}
+
+// Names become directory names, nothing that could leave the profiles dir.
+fn validate_name(name: &str) -> Result<(), LocalStorageError> {
+    let valid = !name.is_empty()
+        && name.len() <= PROFILE_NAME_MAX_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(LocalStorageError::StorageInvalidProfile(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_are_isolated() {
+        let root = format!("/tmp/{}", rand::random::<usize>());
+        let profiles = Profiles::at(&root);
+        let work = profiles.open("work").unwrap();
+        let personal = profiles.open("personal").unwrap();
+
+        work.set(b"wallets", b"[\"work\"]").unwrap();
+
+        assert_eq!(
+            personal.get(b"wallets"),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+        assert_eq!(profiles.list().unwrap(), vec!["work", "personal"]);
+
+        drop(work);
+
+        // reopening does not register twice, and sees the same records
+        let work = profiles.open("work").unwrap();
+
+        assert_eq!(work.get(b"wallets").unwrap(), b"[\"work\"]");
+        assert_eq!(profiles.list().unwrap().len(), 2);
+        drop(work);
+
+        assert_eq!(profiles.remove("work"), Ok(true));
+        assert_eq!(profiles.list().unwrap(), vec!["personal"]);
+        assert_eq!(
+            profiles.open("../escape").err(),
+            Some(LocalStorageError::StorageInvalidProfile(
+                "../escape".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{decode_data, gc::Reclaimed, namespace_tree, LocalStorage};
+use config::storage::{RETENTION_MAX_AGE_MS, RETENTION_MAX_BYTES, RETENTION_MAX_RECORDS};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use zil_errors::storage::LocalStorageError;
+
+/// Bounds on data that only grows, like transaction history. The newest
+/// entries are kept until one of the limits is hit, everything older than
+/// that goes. `None` disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub max_records: Option<usize>,
+    pub max_age_ms: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retained {
+    pub last_update: Option<u64>, // unknown counts as the oldest, never as expired
+    pub bytes: u64,
+    pub pinned: bool, // kept whatever the limits, e.g. a pending tx
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_records: Some(RETENTION_MAX_RECORDS),
+            max_age_ms: Some(RETENTION_MAX_AGE_MS),
+            max_bytes: Some(RETENTION_MAX_BYTES),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn unlimited() -> Self {
+        Self {
+            max_records: None,
+            max_age_ms: None,
+            max_bytes: None,
+        }
+    }
+
+    // Whether to keep each entry, in the order of `entries`.
+    pub fn keep_mask(&self, entries: &[Retained], now: u64) -> Vec<bool> {
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        let mut keep = vec![false; entries.len()];
+        let (mut records, mut bytes, mut full) = (0usize, 0u64, false);
+
+        order.sort_by_key(|&i| Reverse(entries[i].last_update));
+
+        for i in order {
+            let entry = &entries[i];
+            let expired = self
+                .max_age_ms
+                .zip(entry.last_update)
+                .is_some_and(|(max, at)| now.saturating_sub(at) > max);
+
+            full = full
+                || expired
+                || self.max_records.is_some_and(|max| records >= max)
+                || self.max_bytes.is_some_and(|max| bytes + entry.bytes > max);
+
+            if entry.pinned || !full {
+                keep[i] = true;
+                records += 1;
+                bytes += entry.bytes;
+            }
+        }
+
+        keep
+    }
+}
+
+impl LocalStorage {
+    /// Applies `policy` to the records of a namespace by their write time.
+    pub fn prune(
+        &self,
+        ns: &[u8],
+        policy: &RetentionPolicy,
+        now: u64,
+    ) -> Result<Reclaimed, LocalStorageError> {
+        self.writable()?;
+
+        let Some(tree) = self.existing_tree(&namespace_tree(ns))? else {
+            return Ok(Reclaimed::default());
+        };
+        let mut keys = Vec::new();
+        let mut entries = Vec::new();
+
+        for entry in tree.iter() {
+            let (key, value) =
+                entry.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+            entries.push(Retained {
+                // the envelope is plaintext in an encrypted storage too
+                last_update: decode_data(&value).ok().and_then(|data| data.last_update),
+                bytes: (key.len() + value.len()) as u64,
+                pinned: false,
+            });
+            keys.push(key);
+        }
+
+        let mut reclaimed = Reclaimed::default();
+
+        for ((key, entry), keep) in keys
+            .iter()
+            .zip(&entries)
+            .zip(policy.keep_mask(&entries, now))
+        {
+            if keep {
+                continue;
+            }
+
+            self.cache().invalidate(&tree.name(), key);
+
+            if tree
+                .remove(key)
+                .or(Err(LocalStorageError::StorageWriteError))?
+                .is_some()
+            {
+                self.changed(&tree.name(), key, true)?;
+                reclaimed += Reclaimed {
+                    entries: 1,
+                    bytes: entry.bytes,
+                };
+            }
+        }
+
+        Ok(reclaimed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(last_update: u64, bytes: u64) -> Retained {
+        Retained {
+            last_update: Some(last_update),
+            bytes,
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn test_keep_mask() {
+        let entries = [entry(10, 5), entry(30, 5), entry(20, 5), entry(0, 1)];
+        let policy = RetentionPolicy {
+            max_records: Some(2),
+            ..RetentionPolicy::unlimited()
+        };
+
+        assert_eq!(policy.keep_mask(&entries, 30), [false, true, true, false]);
+
+        let policy = RetentionPolicy {
+            max_age_ms: Some(15),
+            ..RetentionPolicy::unlimited()
+        };
+
+        assert_eq!(policy.keep_mask(&entries, 30), [false, true, true, false]);
+
+        // the small oldest entry would fit, but older than a dropped one
+        let policy = RetentionPolicy {
+            max_bytes: Some(12),
+            ..RetentionPolicy::unlimited()
+        };
+
+        assert_eq!(policy.keep_mask(&entries, 30), [false, true, true, false]);
+
+        let mut pinned = entries;
+
+        pinned[3].pinned = true;
+
+        assert_eq!(policy.keep_mask(&pinned, 30), [false, true, true, true]);
+        assert!(RetentionPolicy::unlimited()
+            .keep_mask(&entries, u64::MAX)
+            .iter()
+            .all(|keep| *keep));
+    }
+
+    #[test]
+    fn test_prune_namespace() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+        let ns = b"responses";
+        let tree = db.open_tree(&namespace_tree(ns)).unwrap();
+
+        for (i, at) in [100u64, 300, 200].into_iter().enumerate() {
+            db.write(&tree, format!("r{i}").as_bytes(), b"{}", at)
+                .unwrap();
+        }
+
+        let policy = RetentionPolicy {
+            max_records: Some(1),
+            ..RetentionPolicy::unlimited()
+        };
+        let reclaimed = db.prune(ns, &policy, 300).unwrap();
+
+        assert_eq!(reclaimed.entries, 2);
+        assert_eq!(db.ns_get(ns, b"r1").unwrap(), b"{}");
+        assert!(db.ns_get(ns, b"r0").is_err());
+        assert_eq!(
+            db.prune(b"missing", &policy, 300).unwrap(),
+            Reclaimed::default()
+        );
+    }
+}
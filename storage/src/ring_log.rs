@@ -0,0 +1,131 @@
+use crate::LocalStorage;
+use cipher::aes::{aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use zil_errors::storage::LocalStorageError;
+
+/// Capped append-only log in a tree of its own, the oldest entries go first.
+/// Holds no reference to the storage, so it can be shared across threads.
+#[derive(Clone)]
+pub struct RingLog {
+    tree: sled::Tree,
+    capacity: usize,
+    next: Arc<AtomicU64>,
+    cipher_key: Option<[u8; AES_GCM_KEY_SIZE]>,
+}
+
+impl fmt::Debug for RingLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RingLog")
+            .field("tree", &String::from_utf8_lossy(&self.tree.name()))
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl LocalStorage {
+    pub fn ring_log(&self, name: &[u8], capacity: usize) -> Result<RingLog, LocalStorageError> {
+        let tree = self.open_tree(name)?;
+        let next = match tree
+            .last()
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?
+        {
+            Some((key, _)) => seq(&key)? + 1,
+            None => 0,
+        };
+
+        Ok(RingLog {
+            tree,
+            capacity,
+            next: Arc::new(AtomicU64::new(next)),
+            cipher_key: self.cipher_key,
+        })
+    }
+}
+
+impl RingLog {
+    pub fn push(&self, entry: &[u8]) -> Result<(), LocalStorageError> {
+        let key = self.next.fetch_add(1, Ordering::SeqCst).to_be_bytes();
+        let value = match &self.cipher_key {
+            Some(key) => aes_gcm_encrypt(key, entry)
+                .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string()))?,
+            None => entry.to_vec(),
+        };
+
+        self.tree
+            .insert(key, value)
+            .or(Err(LocalStorageError::StorageWriteError))?;
+
+        while self.tree.len() > self.capacity {
+            self.tree
+                .pop_min()
+                .or(Err(LocalStorageError::StorageWriteError))?;
+        }
+
+        Ok(())
+    }
+
+    // Oldest first.
+    pub fn entries(&self) -> Result<Vec<Vec<u8>>, LocalStorageError> {
+        self.tree
+            .iter()
+            .values()
+            .map(|value| {
+                let value =
+                    value.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+                match &self.cipher_key {
+                    Some(key) => aes_gcm_decrypt(key, &value)
+                        .map_err(|e| LocalStorageError::StorageDecryptError(e.to_string())),
+                    None => Ok(value.to_vec()),
+                }
+            })
+            .collect()
+    }
+
+    pub fn clear(&self) -> Result<(), LocalStorageError> {
+        self.tree
+            .clear()
+            .or(Err(LocalStorageError::StorageWriteError))
+    }
+}
+
+fn seq(key: &[u8]) -> Result<u64, LocalStorageError> {
+    let bytes: [u8; 8] = key
+        .try_into()
+        .or(Err(LocalStorageError::PayloadLengthError))?;
+
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_log_capacity() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from_encrypted(&dir, b"password").unwrap();
+        let log = db.ring_log(b"journal", 3).unwrap();
+
+        for n in 0..5u8 {
+            log.push(&[n]).unwrap();
+        }
+
+        assert_eq!(log.entries().unwrap(), vec![vec![2], vec![3], vec![4]]);
+
+        drop(log);
+
+        // numbering goes on after a reopen
+        let log = db.ring_log(b"journal", 3).unwrap();
+
+        log.push(&[5]).unwrap();
+
+        assert_eq!(log.entries().unwrap(), vec![vec![3], vec![4], vec![5]]);
+    }
+}
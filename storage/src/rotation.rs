@@ -0,0 +1,252 @@
+use crate::{
+    cipher_key, domain_key, encode_data, is_index_tree, is_plain_tree, read_data, LocalStorage,
+};
+use cipher::aes::{aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE};
+use config::storage::{
+    ENCRYPTION_CHECK_KEY, ENCRYPTION_META_TREE, ENCRYPTION_SALT_KEY, ENCRYPTION_SALT_SIZE,
+    NAMESPACE_TREE_PREFIX,
+};
+use crypto::entropy::random_bytes;
+use sled::{
+    transaction::{ConflictableTransactionError, TransactionError},
+    IVec, Transactional,
+};
+use zil_errors::storage::LocalStorageError;
+
+impl LocalStorage {
+    /// Re-encrypts every value under a fresh salt and `new_key`, e.g. after
+    /// the wallet password changed. `old_key` and `new_key` are what
+    /// `from_encrypted` takes. All values are sealed again in memory first
+    /// and written in one transaction together with the new salt, so on
+    /// failure the storage still opens with `old_key` only. Index digests
+    /// depend on the key, index trees are emptied in the same transaction
+    /// and built again when their collection is opened.
+    pub fn rotate_key(&mut self, old_key: &[u8], new_key: &[u8]) -> Result<(), LocalStorageError> {
+        if !self.is_encrypted() {
+            return Err(LocalStorageError::StorageNotEncrypted);
+        }
+
+        self.writable()?;
+
+        let meta = self.open_tree(ENCRYPTION_META_TREE)?;
+        let get = |key: &[u8]| {
+            meta.get(key)
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?
+                .ok_or(LocalStorageError::StorageDataNotFound)
+        };
+        let old = cipher_key(old_key, &get(ENCRYPTION_SALT_KEY)?)?;
+
+        aes_gcm_decrypt(&old, &get(ENCRYPTION_CHECK_KEY)?)
+            .or(Err(LocalStorageError::StorageWrongPassword))?;
+
+        let salt: [u8; ENCRYPTION_SALT_SIZE] = random_bytes();
+        let new = cipher_key(new_key, &salt)?;
+        let mut trees = vec![meta];
+        let mut writes: Vec<(usize, IVec, IVec)> = vec![
+            (0, ENCRYPTION_SALT_KEY.into(), salt.as_slice().into()),
+            (
+                0,
+                ENCRYPTION_CHECK_KEY.into(),
+                reseal(&new, ENCRYPTION_CHECK_KEY)?.into(),
+            ),
+        ];
+        let mut removes: Vec<(usize, IVec)> = Vec::new();
+
+        for name in self.tree.tree_names() {
+            if is_plain_tree(&name) {
+                continue;
+            }
+
+            let tree = self.open_tree(&name)?;
+
+            if is_index_tree(&name) {
+                let index = trees.len();
+
+                for key in tree.iter().keys() {
+                    let key =
+                        key.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+                    removes.push((index, key));
+                }
+
+                trees.push(tree);
+                continue;
+            }
+
+            let is_records = name == self.tree.name() || name.starts_with(NAMESPACE_TREE_PREFIX);
+            let index = trees.len();
+            let (old, new) = (domain_key(&old, &name)?, domain_key(&new, &name)?);
+
+            for entry in tree.iter() {
+                let (key, value) =
+                    entry.map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+                let value = if is_records {
+                    let data = read_data(&tree, &key)?;
+                    let payload = reseal(&new, &open(&old, &data.payload)?)?;
+
+                    encode_data(
+                        data.version,
+                        (data.codec, data.compressed, data.hash),
+                        &payload,
+                        data.last_update.unwrap_or(0),
+                    )
+                    .into()
+                } else {
+                    reseal(&new, &open(&old, &value)?)?.into()
+                };
+
+                writes.push((index, key, value));
+            }
+
+            trees.push(tree);
+        }
+
+        trees
+            .as_slice()
+            .transaction(|txs| {
+                for (index, key, value) in &writes {
+                    txs[*index].insert(key, value)?;
+                }
+
+                for (index, key) in &removes {
+                    txs[*index].remove(key)?;
+                }
+
+                Ok::<(), ConflictableTransactionError<()>>(())
+            })
+            .map_err(|e| match e {
+                TransactionError::Abort(()) => LocalStorageError::StorageWriteError,
+                TransactionError::Storage(e) => {
+                    LocalStorageError::StorageAccessError(e.to_string())
+                }
+            })?;
+
+        self.cipher_key = Some(new);
+
+        Ok(())
+    }
+}
+
+fn open(key: &[u8; AES_GCM_KEY_SIZE], bytes: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+    aes_gcm_decrypt(key, bytes).map_err(|e| LocalStorageError::StorageDecryptError(e.to_string()))
+}
+
+fn reseal(key: &[u8; AES_GCM_KEY_SIZE], payload: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+    aes_gcm_encrypt(key, payload).map_err(|e| LocalStorageError::StorageEncryptError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_key() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from_encrypted(&dir, b"old").unwrap();
+
+        db.set(b"vault", b"seed words").unwrap();
+        db.ns_set(&[1u8; 32], b"history", b"[]").unwrap();
+        db.tree_set(b"cache", b"key", b"value").unwrap();
+
+        assert_eq!(
+            db.rotate_key(b"wrong", b"new"),
+            Err(LocalStorageError::StorageWrongPassword)
+        );
+
+        db.rotate_key(b"old", b"new").unwrap();
+
+        assert_eq!(db.get(b"vault").unwrap(), b"seed words");
+        drop(db);
+
+        assert_eq!(
+            LocalStorage::from_encrypted(&dir, b"old").err(),
+            Some(LocalStorageError::StorageWrongPassword)
+        );
+
+        let db = LocalStorage::from_encrypted(&dir, b"new").unwrap();
+
+        assert_eq!(db.get(b"vault").unwrap(), b"seed words");
+        assert_eq!(db.ns_get(&[1u8; 32], b"history").unwrap(), b"[]");
+        assert_eq!(
+            db.tree_get(b"cache", b"key").unwrap(),
+            Some(b"value".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_rotate_key_failure() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from_encrypted(&dir, b"old").unwrap();
+
+        db.set(b"vault", b"seed words").unwrap();
+        // a value the old key cannot open stops the rotation before any write
+        db.open_tree(b"cache")
+            .unwrap()
+            .insert(b"broken", b"not a ciphertext".as_slice())
+            .unwrap();
+
+        assert!(matches!(
+            db.rotate_key(b"old", b"new"),
+            Err(LocalStorageError::StorageDecryptError(_))
+        ));
+        assert_eq!(db.get(b"vault").unwrap(), b"seed words");
+        drop(db);
+
+        assert!(LocalStorage::from_encrypted(&dir, b"new").is_err());
+        assert_eq!(
+            LocalStorage::from_encrypted(&dir, b"old")
+                .unwrap()
+                .get(b"vault")
+                .unwrap(),
+            b"seed words"
+        );
+    }
+
+    #[test]
+    fn test_rotate_key_with_index() {
+        use crate::collection::{Collection, IndexKeys};
+        use config::storage::INDEX_TREE_PREFIX;
+
+        let by_owner: IndexKeys<String> = |owner| vec![owner.clone().into_bytes()];
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from_encrypted(&dir, b"old").unwrap();
+
+        Collection::<String>::new(&db, "owners")
+            .unwrap()
+            .with_index("owner", by_owner)
+            .unwrap()
+            .insert(&"0x01".to_string(), &"zil1alice".to_string())
+            .unwrap();
+
+        let index_tree = db
+            .tree
+            .tree_names()
+            .into_iter()
+            .find(|name| name.starts_with(INDEX_TREE_PREFIX))
+            .unwrap();
+        let leaks = |db: &LocalStorage| {
+            db.open_tree(&index_tree)
+                .unwrap()
+                .iter()
+                .keys()
+                .any(|key| key.unwrap().windows(9).any(|w| w == b"zil1alice"))
+        };
+
+        assert!(!leaks(&db));
+
+        db.rotate_key(b"old", b"new").unwrap();
+        drop(db);
+
+        let db = LocalStorage::from_encrypted(&dir, b"new").unwrap();
+        let owners = Collection::<String>::new(&db, "owners")
+            .unwrap()
+            .with_index("owner", by_owner)
+            .unwrap();
+
+        assert_eq!(
+            owners.find_by("owner", b"zil1alice").unwrap(),
+            vec![("0x01".to_string(), "zil1alice".to_string())]
+        );
+        assert!(!leaks(&db));
+    }
+}
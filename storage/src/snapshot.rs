@@ -0,0 +1,372 @@
+use crate::{
+    canonical::{verify_hashsum_with, HashAlgo},
+    codec::Codec,
+    is_plain_tree, now_millis, write_data, LocalStorage,
+};
+use cipher::{
+    aes::{aes_gcm_decrypt, aes_gcm_encrypt, AES_GCM_KEY_SIZE},
+    argon2::derive_key_with_salt,
+};
+use config::{
+    sha::SHA256_SIZE,
+    storage::{
+        BACKUP_FORMAT_VERSION, BACKUP_MAGIC, ENCRYPTION_SALT_SIZE, NAMESPACE_TREE_PREFIX,
+        SNAPSHOT_FORMAT_VERSION,
+    },
+};
+use crypto::entropy::random_bytes;
+use serde::{Deserialize, Serialize};
+use sled::IVec;
+use std::fs;
+use zil_errors::storage::LocalStorageError;
+
+/// Every record of a storage, payloads in plaintext even when the storage is
+/// encrypted, so it can be restored on a device with another password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub format: u16,
+    pub storage_version: u16,
+    pub created_at: u64,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub tree: String, // hex, empty for the main tree
+    pub key: String,
+    pub payload: String,
+    pub version: Option<u16>, // None for raw values of cache trees
+    pub hashsum: Option<String>,
+    pub last_update: Option<u64>,
+    #[serde(default)]
+    pub codec: Codec,
+    #[serde(default)]
+    pub hash: HashAlgo, // of `hashsum`
+}
+
+impl LocalStorage {
+    // Encryption, storage settings and sync state belong to this device and
+    // stay out.
+    pub fn export_snapshot(&self) -> Result<Snapshot, LocalStorageError> {
+        let mut entries = Vec::new();
+
+        for name in self.tree.tree_names() {
+            if is_plain_tree(&name) {
+                continue;
+            }
+
+            let tree = self.open_tree(&name)?;
+            let is_main = name == self.tree.name();
+            let is_records = is_main || name.starts_with(NAMESPACE_TREE_PREFIX);
+            let tree_hex = if is_main {
+                String::new()
+            } else {
+                hex::encode(&name)
+            };
+            let pairs = tree
+                .iter()
+                .collect::<Result<Vec<(IVec, IVec)>, sled::Error>>()
+                .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+            for (key, value) in pairs {
+                let entry = if is_records {
+                    let data = self.read(&tree, &key)?;
+
+                    SnapshotEntry {
+                        tree: tree_hex.clone(),
+                        key: hex::encode(&key),
+                        payload: hex::encode(&data.payload),
+                        version: Some(data.version),
+                        hashsum: data.hashsum.map(hex::encode),
+                        last_update: data.last_update,
+                        codec: data.codec,
+                        hash: data.hash,
+                    }
+                } else {
+                    SnapshotEntry {
+                        tree: tree_hex.clone(),
+                        key: hex::encode(&key),
+                        payload: hex::encode(self.decrypt(&tree.name(), &value)?),
+                        version: None,
+                        hashsum: None,
+                        last_update: None,
+                        codec: Codec::Json,
+                        hash: HashAlgo::Sha256,
+                    }
+                };
+
+                entries.push(entry);
+            }
+        }
+
+        Ok(Snapshot {
+            format: SNAPSHOT_FORMAT_VERSION,
+            storage_version: self.version(),
+            created_at: now_millis()?,
+            entries,
+        })
+    }
+
+    /// Restores every entry, replacing records under the same key. Nothing is
+    /// written unless the whole snapshot checks out.
+    pub fn import_snapshot(&self, snapshot: &Snapshot) -> Result<usize, LocalStorageError> {
+        self.writable()?;
+
+        if snapshot.format != SNAPSHOT_FORMAT_VERSION {
+            return Err(LocalStorageError::StorageSnapshotVersion(snapshot.format));
+        }
+
+        let decoded = snapshot
+            .entries
+            .iter()
+            .map(decode_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (entry, tree, key, payload) in &decoded {
+            let tree = match tree {
+                Some(name) => self.open_tree(name)?,
+                None => (*self.tree).clone(),
+            };
+
+            match entry.version {
+                Some(version) => write_data(
+                    &tree,
+                    version,
+                    (entry.codec, false, self.hash),
+                    key,
+                    &self.encrypt(&tree.name(), payload)?,
+                    entry.last_update.unwrap_or(snapshot.created_at),
+                )?,
+                None => {
+                    tree.insert(key.as_slice(), self.encrypt(&tree.name(), payload)?)
+                        .or(Err(LocalStorageError::StorageWriteError))?;
+                }
+            }
+
+            self.changed(&tree.name(), key, false)?;
+        }
+
+        Ok(decoded.len())
+    }
+
+    pub fn export_to_file(&self, path: &str) -> Result<usize, LocalStorageError> {
+        let snapshot = self.export_snapshot()?;
+        let bytes = serde_json::to_vec(&snapshot)
+            .map_err(|e| LocalStorageError::StorageSnapshotBroken(e.to_string()))?;
+
+        write_atomic(path, &bytes)?;
+
+        Ok(snapshot.entries.len())
+    }
+
+    pub fn import_from_file(&self, path: &str) -> Result<usize, LocalStorageError> {
+        let bytes = fs::read(path).or(Err(LocalStorageError::FailToReadFile))?;
+        let snapshot: Snapshot = serde_json::from_slice(&bytes)
+            .map_err(|e| LocalStorageError::StorageSnapshotBroken(e.to_string()))?;
+
+        self.import_snapshot(&snapshot)
+    }
+
+    /// Snapshot sealed with a key derived from `password` alone, so the file
+    /// can be kept anywhere (cloud drives) and restored on any device.
+    pub fn backup_encrypted(
+        &self,
+        path: &str,
+        password: &[u8],
+    ) -> Result<usize, LocalStorageError> {
+        let snapshot = self.export_snapshot()?;
+        let bytes = serde_json::to_vec(&snapshot)
+            .map_err(|e| LocalStorageError::StorageSnapshotBroken(e.to_string()))?;
+        let salt: [u8; ENCRYPTION_SALT_SIZE] = random_bytes();
+        let sealed = aes_gcm_encrypt(&backup_key(password, &salt)?, &bytes)
+            .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string()))?;
+        let mut vault = Vec::with_capacity(BACKUP_MAGIC.len() + 1 + salt.len() + sealed.len());
+
+        vault.extend_from_slice(BACKUP_MAGIC);
+        vault.push(BACKUP_FORMAT_VERSION);
+        vault.extend_from_slice(&salt);
+        vault.extend_from_slice(&sealed);
+        write_atomic(path, &vault)?;
+
+        Ok(snapshot.entries.len())
+    }
+
+    pub fn restore_encrypted(
+        &self,
+        path: &str,
+        password: &[u8],
+    ) -> Result<usize, LocalStorageError> {
+        self.writable()?;
+
+        let vault = fs::read(path).or(Err(LocalStorageError::FailToReadFile))?;
+        let header = BACKUP_MAGIC.len() + 1;
+
+        if vault.len() < header + ENCRYPTION_SALT_SIZE || !vault.starts_with(BACKUP_MAGIC) {
+            return Err(LocalStorageError::StorageSnapshotBroken(
+                "not a backup file".to_string(),
+            ));
+        }
+
+        if vault[header - 1] != BACKUP_FORMAT_VERSION {
+            return Err(LocalStorageError::StorageSnapshotVersion(
+                vault[header - 1].into(),
+            ));
+        }
+
+        let (salt, sealed) = vault[header..].split_at(ENCRYPTION_SALT_SIZE);
+        let bytes = aes_gcm_decrypt(&backup_key(password, salt)?, sealed)
+            .or(Err(LocalStorageError::StorageWrongPassword))?;
+        let snapshot: Snapshot = serde_json::from_slice(&bytes)
+            .map_err(|e| LocalStorageError::StorageSnapshotBroken(e.to_string()))?;
+
+        self.import_snapshot(&snapshot)
+    }
+}
+
+fn backup_key(password: &[u8], salt: &[u8]) -> Result<[u8; AES_GCM_KEY_SIZE], LocalStorageError> {
+    let derived = derive_key_with_salt(password, salt)
+        .map_err(|e| LocalStorageError::StorageEncryptError(e.to_string()))?;
+    let mut key = [0u8; AES_GCM_KEY_SIZE];
+
+    key.copy_from_slice(&derived[..AES_GCM_KEY_SIZE]);
+
+    Ok(key)
+}
+
+// A crash mid-write must not leave a truncated file behind.
+pub(crate) fn write_atomic(path: &str, bytes: &[u8]) -> Result<(), LocalStorageError> {
+    let tmp = format!("{path}.tmp");
+
+    fs::write(&tmp, bytes).or(Err(LocalStorageError::FailToCreateFile))?;
+    fs::rename(&tmp, path).or(Err(LocalStorageError::FailToWriteFile))
+}
+
+type DecodedEntry<'a> = (&'a SnapshotEntry, Option<Vec<u8>>, Vec<u8>, Vec<u8>);
+
+fn decode_entry(entry: &SnapshotEntry) -> Result<DecodedEntry<'_>, LocalStorageError> {
+    let decode = |s: &str| {
+        hex::decode(s).map_err(|e| LocalStorageError::StorageSnapshotBroken(e.to_string()))
+    };
+    let tree = match entry.tree.is_empty() {
+        true => None,
+        false => Some(decode(&entry.tree)?),
+    };
+    let key = decode(&entry.key)?;
+    let payload = decode(&entry.payload)?;
+
+    if let Some(hashsum) = &entry.hashsum {
+        let hashsum: [u8; SHA256_SIZE] = decode(hashsum)?
+            .try_into()
+            .or(Err(LocalStorageError::StorageDataBroken))?;
+
+        if !verify_hashsum_with(entry.hash, &payload, &hashsum) {
+            return Err(LocalStorageError::StorageDataBroken);
+        }
+    }
+
+    Ok((entry, tree, key, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_import() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let file = format!("/tmp/{}.json", rand::random::<usize>());
+        let db = LocalStorage::from_encrypted(&dir, b"old device").unwrap();
+
+        db.set(b"wallets", br#"{"a":1}"#).unwrap();
+        db.ns_set(b"wallet 0", b"history", b"[1,2]").unwrap();
+        db.tree_set(b"contract_init", b"zil1", b"init").unwrap();
+
+        assert_eq!(db.export_to_file(&file).unwrap(), 3);
+
+        let restored_dir = format!("/tmp/{}", rand::random::<usize>());
+        let restored = LocalStorage::from_encrypted(&restored_dir, b"new device").unwrap();
+
+        assert_eq!(restored.import_from_file(&file).unwrap(), 3);
+        assert_eq!(restored.get(b"wallets").unwrap(), br#"{"a":1}"#);
+        assert_eq!(
+            restored.get_data(b"wallets").unwrap().last_update,
+            db.get_data(b"wallets").unwrap().last_update
+        );
+        assert_eq!(restored.ns_get(b"wallet 0", b"history").unwrap(), b"[1,2]");
+        assert_eq!(
+            restored.tree_get(b"contract_init", b"zil1").unwrap(),
+            Some(b"init".to_vec())
+        );
+
+        let mut snapshot = db.export_snapshot().unwrap();
+        let wallets = snapshot
+            .entries
+            .iter_mut()
+            .find(|e| e.key == hex::encode(b"wallets"))
+            .unwrap();
+
+        wallets.payload = hex::encode(b"tampered");
+
+        assert_eq!(
+            restored.import_snapshot(&snapshot),
+            Err(LocalStorageError::StorageDataBroken)
+        );
+
+        snapshot.format += 1;
+
+        assert_eq!(
+            restored.import_snapshot(&snapshot),
+            Err(LocalStorageError::StorageSnapshotVersion(snapshot.format))
+        );
+    }
+
+    #[test]
+    fn test_encrypted_backup() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let file = format!("/tmp/{}.vault", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"wallets", br#"{"a":1}"#).unwrap();
+
+        assert_eq!(db.backup_encrypted(&file, b"backup password").unwrap(), 1);
+
+        let vault = fs::read(&file).unwrap();
+
+        assert!(vault.starts_with(BACKUP_MAGIC));
+        // entries are hex in the snapshot
+        let key = hex::encode(b"wallets");
+
+        assert!(!vault.windows(key.len()).any(|w| w == key.as_bytes()));
+
+        let restored_dir = format!("/tmp/{}", rand::random::<usize>());
+        let restored = LocalStorage::from(&restored_dir).unwrap();
+
+        assert_eq!(
+            restored.restore_encrypted(&file, b"wrong"),
+            Err(LocalStorageError::StorageWrongPassword)
+        );
+        assert_eq!(restored.restore_encrypted(&file, b"backup password"), Ok(1));
+        assert_eq!(restored.get(b"wallets").unwrap(), br#"{"a":1}"#);
+
+        let truncated = format!("{file}.truncated");
+
+        fs::write(&truncated, &vault[..40]).unwrap();
+        assert_eq!(
+            restored.restore_encrypted(&truncated, b"backup password"),
+            Err(LocalStorageError::StorageWrongPassword)
+        );
+
+        drop(restored);
+
+        let read_only = LocalStorage::open_read_only(&restored_dir).unwrap();
+
+        assert_eq!(
+            read_only.restore_encrypted(&file, b"backup password"),
+            Err(LocalStorageError::StorageReadOnly)
+        );
+        assert_eq!(
+            read_only.import_snapshot(&db.export_snapshot().unwrap()),
+            Err(LocalStorageError::StorageReadOnly)
+        );
+    }
+}
@@ -0,0 +1,611 @@
+use crate::{canonical::canonical_hashsum_with, LocalStorage};
+use cipher::{keychain::KeyChain, options::CipherOrders};
+use config::{sha::SHA256_SIZE, storage::SYNC_META_TREE};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use zil_errors::{storage::LocalStorageError, sync::SyncErrors};
+
+/// Per-record vector timestamp, one counter per device id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorClock(BTreeMap<String, u64>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ClockOrdering {
+    Equal,
+    Before,
+    After,
+    Concurrent,
+}
+
+impl VectorClock {
+    pub fn get(&self, device_id: &str) -> u64 {
+        self.0.get(device_id).copied().unwrap_or(0)
+    }
+
+    pub fn increment(&mut self, device_id: &str) {
+        *self.0.entry(device_id.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut merged = self.0.clone();
+
+        for (device_id, counter) in other.0.iter() {
+            let entry = merged.entry(device_id.clone()).or_insert(0);
+            *entry = (*entry).max(*counter);
+        }
+
+        Self(merged)
+    }
+
+    pub fn compare(&self, other: &Self) -> ClockOrdering {
+        let mut less = false;
+        let mut greater = false;
+
+        for device_id in self.0.keys().chain(other.0.keys()) {
+            let (a, b) = (self.get(device_id), other.get(device_id));
+
+            if a < b {
+                less = true;
+            } else if a > b {
+                greater = true;
+            }
+        }
+
+        match (less, greater) {
+            (false, false) => ClockOrdering::Equal,
+            (true, false) => ClockOrdering::Before,
+            (false, true) => ClockOrdering::After,
+            (true, true) => ClockOrdering::Concurrent,
+        }
+    }
+}
+
+/// A record as it is stored on the remote, the payload never leaves the
+/// device unencrypted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub key: Vec<u8>,
+    pub cipher: Vec<u8>,
+    pub clock: VectorClock,
+    pub last_update: u64,
+}
+
+pub trait SyncRemote {
+    fn pull(&self, keys: &[&[u8]]) -> Result<Vec<SyncRecord>, SyncErrors>;
+    fn push(&self, records: &[SyncRecord]) -> Result<(), SyncErrors>;
+}
+
+pub type MergeFn = fn(local: &[u8], remote: &[u8]) -> Option<Vec<u8>>;
+
+#[derive(Clone, Copy)]
+pub enum ConflictStrategy {
+    LastWriteWins,
+    LocalWins,
+    // Falls back to last-write-wins when the callback can't merge payloads
+    Merge(MergeFn),
+}
+
+impl ConflictStrategy {
+    // Local wins ties, the remote copy was already seen by someone else.
+    pub fn resolve(
+        &self,
+        local: &[u8],
+        local_update: u64,
+        remote: &[u8],
+        remote_update: u64,
+    ) -> Vec<u8> {
+        let last_write = if local_update >= remote_update {
+            local
+        } else {
+            remote
+        };
+
+        match self {
+            ConflictStrategy::LastWriteWins => last_write.to_vec(),
+            ConflictStrategy::LocalWins => local.to_vec(),
+            ConflictStrategy::Merge(merge) => {
+                merge(local, remote).unwrap_or_else(|| last_write.to_vec())
+            }
+        }
+    }
+}
+
+/// Strategy per namespace, i.e. key prefix ("contacts", "settings:"). The
+/// longest matching namespace wins, other keys get the default.
+#[derive(Clone)]
+pub struct ConflictPolicy {
+    default: ConflictStrategy,
+    namespaces: Vec<(Vec<u8>, ConflictStrategy)>,
+}
+
+impl ConflictPolicy {
+    pub fn new(default: ConflictStrategy) -> Self {
+        Self {
+            default,
+            namespaces: Vec::new(),
+        }
+    }
+
+    pub fn namespace(mut self, prefix: &[u8], strategy: ConflictStrategy) -> Self {
+        self.namespaces.retain(|(p, _)| p != prefix);
+        self.namespaces.push((prefix.to_vec(), strategy));
+        self
+    }
+
+    pub fn strategy(&self, key: &[u8]) -> &ConflictStrategy {
+        self.namespaces
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(&self.default, |(_, strategy)| strategy)
+    }
+
+    pub fn resolve(
+        &self,
+        key: &[u8],
+        local: &[u8],
+        local_update: u64,
+        remote: &[u8],
+        remote_update: u64,
+    ) -> Vec<u8> {
+        self.strategy(key)
+            .resolve(local, local_update, remote, remote_update)
+    }
+}
+
+impl Default for ConflictPolicy {
+    fn default() -> Self {
+        Self::new(ConflictStrategy::LastWriteWins)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub pushed: usize,
+    pub pulled: usize,
+    pub conflicts: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncMeta {
+    clock: VectorClock,
+    synced_hashsum: Option<[u8; SHA256_SIZE]>,
+}
+
+pub struct SyncEngine<'a, R: SyncRemote> {
+    storage: &'a LocalStorage,
+    remote: R,
+    keychain: &'a KeyChain,
+    options: &'a [CipherOrders],
+    device_id: String,
+    policy: ConflictPolicy,
+}
+
+impl<'a, R: SyncRemote> SyncEngine<'a, R> {
+    pub fn new(
+        storage: &'a LocalStorage,
+        remote: R,
+        keychain: &'a KeyChain,
+        options: &'a [CipherOrders],
+        device_id: String,
+        strategy: ConflictStrategy,
+    ) -> Self {
+        Self {
+            storage,
+            remote,
+            keychain,
+            options,
+            device_id,
+            policy: ConflictPolicy::new(strategy),
+        }
+    }
+
+    // Overrides the strategy given to `new` for keys starting with `prefix`.
+    pub fn with_namespace_strategy(mut self, prefix: &[u8], strategy: ConflictStrategy) -> Self {
+        self.policy = self.policy.namespace(prefix, strategy);
+        self
+    }
+
+    pub fn sync(&self, keys: &[&[u8]]) -> Result<SyncReport, SyncErrors> {
+        let remote_records: HashMap<Vec<u8>, SyncRecord> = self
+            .remote
+            .pull(keys)?
+            .into_iter()
+            .map(|r| (r.key.clone(), r))
+            .collect();
+        let mut report = SyncReport::default();
+        let mut outgoing: Vec<SyncRecord> = Vec::new();
+        let mut metas: Vec<(&[u8], SyncMeta)> = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let local = match self.storage.get_data(key) {
+                Ok(data) => Some(data),
+                Err(LocalStorageError::StorageDataNotFound) => None,
+                Err(e) => return Err(SyncErrors::StorageError(e)),
+            };
+            let mut meta = self.load_meta(key)?;
+
+            if let Some(data) = &local {
+                if data.hashsum != meta.synced_hashsum {
+                    meta.clock.increment(&self.device_id);
+                }
+            }
+
+            match (local, remote_records.get(*key)) {
+                (None, None) => continue,
+                // deleted here after the remote copy was written
+                (None, Some(record))
+                    if self
+                        .storage
+                        .tombstone(key)?
+                        .is_some_and(|deleted_at| deleted_at >= record.last_update) =>
+                {
+                    continue
+                }
+                (None, Some(record)) => {
+                    self.apply_remote(key, record, &mut meta)?;
+                    report.pulled += 1;
+                }
+                (Some(data), None) => {
+                    outgoing.push(self.seal(key, &data.payload, &meta.clock, data.last_update)?);
+                    meta.synced_hashsum = data.hashsum;
+                    report.pushed += 1;
+                }
+                (Some(data), Some(record)) => match meta.clock.compare(&record.clock) {
+                    ClockOrdering::Equal => {
+                        meta.synced_hashsum = data.hashsum;
+                    }
+                    ClockOrdering::After => {
+                        outgoing.push(self.seal(
+                            key,
+                            &data.payload,
+                            &meta.clock,
+                            data.last_update,
+                        )?);
+                        meta.synced_hashsum = data.hashsum;
+                        report.pushed += 1;
+                    }
+                    ClockOrdering::Before => {
+                        self.apply_remote(key, record, &mut meta)?;
+                        report.pulled += 1;
+                    }
+                    ClockOrdering::Concurrent => {
+                        let remote_payload = self.open(record)?;
+                        let resolved = self.policy.resolve(
+                            key,
+                            &data.payload,
+                            data.last_update.unwrap_or(0),
+                            &remote_payload,
+                            record.last_update,
+                        );
+
+                        report.conflicts += 1;
+                        meta.clock = meta.clock.merge(&record.clock);
+                        meta.clock.increment(&self.device_id);
+
+                        if resolved != data.payload {
+                            self.storage.set(key, &resolved)?;
+                            report.pulled += 1;
+                        }
+
+                        let written = self.storage.get_data(key)?;
+
+                        outgoing.push(self.seal(
+                            key,
+                            &written.payload,
+                            &meta.clock,
+                            written.last_update,
+                        )?);
+                        meta.synced_hashsum = written.hashsum;
+                        report.pushed += 1;
+                    }
+                },
+            }
+
+            metas.push((key, meta));
+        }
+
+        if !outgoing.is_empty() {
+            self.remote.push(&outgoing)?;
+        }
+
+        for (key, meta) in metas {
+            self.save_meta(key, &meta)?;
+        }
+
+        Ok(report)
+    }
+
+    fn apply_remote(
+        &self,
+        key: &[u8],
+        record: &SyncRecord,
+        meta: &mut SyncMeta,
+    ) -> Result<(), SyncErrors> {
+        let payload = self.open(record)?;
+
+        self.storage
+            .set_with_update(key, &payload, record.last_update)?;
+        meta.clock = record.clock.clone();
+        meta.synced_hashsum = Some(canonical_hashsum_with(self.storage.hash, &payload));
+
+        Ok(())
+    }
+
+    fn seal(
+        &self,
+        key: &[u8],
+        payload: &[u8],
+        clock: &VectorClock,
+        last_update: Option<u64>,
+    ) -> Result<SyncRecord, SyncErrors> {
+        let cipher = self
+            .keychain
+            .encrypt(payload.to_vec(), self.options)
+            .map_err(SyncErrors::EncryptError)?;
+
+        Ok(SyncRecord {
+            key: key.to_vec(),
+            cipher,
+            clock: clock.clone(),
+            last_update: last_update.unwrap_or(0),
+        })
+    }
+
+    fn open(&self, record: &SyncRecord) -> Result<Vec<u8>, SyncErrors> {
+        self.keychain
+            .decrypt(record.cipher.clone(), self.options)
+            .map_err(SyncErrors::DecryptError)
+    }
+
+    fn load_meta(&self, key: &[u8]) -> Result<SyncMeta, SyncErrors> {
+        let tree = self.meta_tree()?;
+        let bytes = tree
+            .get(key)
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+        match bytes {
+            Some(bytes) => {
+                serde_json::from_slice(&bytes).or(Err(SyncErrors::FailToDeserializeMeta))
+            }
+            None => Ok(SyncMeta::default()),
+        }
+    }
+
+    fn save_meta(&self, key: &[u8], meta: &SyncMeta) -> Result<(), SyncErrors> {
+        let tree = self.meta_tree()?;
+        let bytes = serde_json::to_vec(meta).or(Err(SyncErrors::FailToSerializeMeta))?;
+
+        tree.insert(key, bytes)
+            .or(Err(LocalStorageError::StorageWriteError))?;
+
+        Ok(())
+    }
+
+    fn meta_tree(&self) -> Result<sled::Tree, LocalStorageError> {
+        self.storage
+            .tree
+            .open_tree(SYNC_META_TREE)
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))
+    }
+}
+
+/// Merge for list-like records such as contacts or tokens: arrays are
+/// unioned, objects are merged key by key and local scalars win.
+pub fn merge_json(local: &[u8], remote: &[u8]) -> Option<Vec<u8>> {
+    let local: Value = serde_json::from_slice(local).ok()?;
+    let remote: Value = serde_json::from_slice(remote).ok()?;
+    let merged = merge_values(local, remote)?;
+
+    serde_json::to_vec(&merged).ok()
+}
+
+fn merge_values(local: Value, remote: Value) -> Option<Value> {
+    match (local, remote) {
+        (Value::Array(mut local), Value::Array(remote)) => {
+            for value in remote {
+                if !local.contains(&value) {
+                    local.push(value);
+                }
+            }
+
+            Some(Value::Array(local))
+        }
+        (Value::Object(mut local), Value::Object(remote)) => {
+            for (key, remote_value) in remote {
+                let merged = match local.remove(&key) {
+                    Some(local_value) if local_value == remote_value => local_value,
+                    Some(local_value) => {
+                        merge_values(local_value.clone(), remote_value).unwrap_or(local_value)
+                    }
+                    None => remote_value,
+                };
+
+                local.insert(key, merged);
+            }
+
+            Some(Value::Object(local))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Clone, Default)]
+    struct MemoryRemote(Rc<RefCell<HashMap<Vec<u8>, SyncRecord>>>);
+
+    impl SyncRemote for MemoryRemote {
+        fn pull(&self, keys: &[&[u8]]) -> Result<Vec<SyncRecord>, SyncErrors> {
+            let records = self.0.borrow();
+
+            Ok(keys
+                .iter()
+                .filter_map(|key| records.get(*key).cloned())
+                .collect())
+        }
+
+        fn push(&self, records: &[SyncRecord]) -> Result<(), SyncErrors> {
+            let mut stored = self.0.borrow_mut();
+
+            for record in records {
+                stored.insert(record.key.clone(), record.clone());
+            }
+
+            Ok(())
+        }
+    }
+
+    fn tmp_storage() -> LocalStorage {
+        let mut rng = rand::thread_rng();
+        let dir = std::env::temp_dir().join(format!("sync_test_{}", rng.gen::<u64>()));
+
+        LocalStorage::from(dir.to_str().unwrap()).unwrap()
+    }
+
+    const OPTIONS: [CipherOrders; 1] = [CipherOrders::AESGCM256];
+    const KEY: &[u8] = b"contacts";
+
+    #[test]
+    fn test_vector_clock_compare() {
+        let mut a = VectorClock::default();
+        let mut b = VectorClock::default();
+
+        assert_eq!(a.compare(&b), ClockOrdering::Equal);
+
+        a.increment("a");
+        assert_eq!(a.compare(&b), ClockOrdering::After);
+        assert_eq!(b.compare(&a), ClockOrdering::Before);
+
+        b.increment("b");
+        assert_eq!(a.compare(&b), ClockOrdering::Concurrent);
+
+        let merged = a.merge(&b);
+        assert_eq!(merged.get("a"), 1);
+        assert_eq!(merged.get("b"), 1);
+        assert_eq!(merged.compare(&a), ClockOrdering::After);
+    }
+
+    #[test]
+    fn test_push_pull() {
+        let keychain = KeyChain::from_pass(b"sync_password").unwrap();
+        let remote = MemoryRemote::default();
+        let storage_a = tmp_storage();
+        let storage_b = tmp_storage();
+        let engine_a = SyncEngine::new(
+            &storage_a,
+            remote.clone(),
+            &keychain,
+            &OPTIONS,
+            "a".to_string(),
+            ConflictStrategy::LastWriteWins,
+        );
+        let engine_b = SyncEngine::new(
+            &storage_b,
+            remote.clone(),
+            &keychain,
+            &OPTIONS,
+            "b".to_string(),
+            ConflictStrategy::LastWriteWins,
+        );
+
+        storage_a.set(KEY, br#"["alice"]"#).unwrap();
+
+        let report = engine_a.sync(&[KEY]).unwrap();
+        assert_eq!(report.pushed, 1);
+
+        let stored = remote.0.borrow().get(KEY).cloned().unwrap();
+        assert_ne!(stored.cipher, br#"["alice"]"#.to_vec());
+
+        let report = engine_b.sync(&[KEY]).unwrap();
+        assert_eq!(report.pulled, 1);
+        assert_eq!(storage_b.get(KEY).unwrap(), br#"["alice"]"#.to_vec());
+
+        // nothing changed on either side
+        assert_eq!(engine_a.sync(&[KEY]).unwrap(), SyncReport::default());
+        assert_eq!(engine_b.sync(&[KEY]).unwrap(), SyncReport::default());
+    }
+
+    #[test]
+    fn test_conflict_merge() {
+        let keychain = KeyChain::from_pass(b"sync_password").unwrap();
+        let remote = MemoryRemote::default();
+        let storage_a = tmp_storage();
+        let storage_b = tmp_storage();
+        let engine_a = SyncEngine::new(
+            &storage_a,
+            remote.clone(),
+            &keychain,
+            &OPTIONS,
+            "a".to_string(),
+            ConflictStrategy::Merge(merge_json),
+        );
+        let engine_b = SyncEngine::new(
+            &storage_b,
+            remote.clone(),
+            &keychain,
+            &OPTIONS,
+            "b".to_string(),
+            ConflictStrategy::Merge(merge_json),
+        );
+
+        storage_a.set(KEY, br#"["alice"]"#).unwrap();
+        engine_a.sync(&[KEY]).unwrap();
+        engine_b.sync(&[KEY]).unwrap();
+
+        storage_a.set(KEY, br#"["alice","bob"]"#).unwrap();
+        storage_b.set(KEY, br#"["alice","carol"]"#).unwrap();
+
+        engine_a.sync(&[KEY]).unwrap();
+
+        let report = engine_b.sync(&[KEY]).unwrap();
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(
+            storage_b.get(KEY).unwrap(),
+            br#"["alice","carol","bob"]"#.to_vec()
+        );
+
+        engine_a.sync(&[KEY]).unwrap();
+        assert_eq!(storage_a.get(KEY).unwrap(), storage_b.get(KEY).unwrap());
+    }
+
+    #[test]
+    fn test_conflict_policy() {
+        let policy = ConflictPolicy::default()
+            .namespace(b"settings:", ConflictStrategy::LocalWins)
+            .namespace(b"contacts", ConflictStrategy::Merge(merge_json));
+
+        assert_eq!(policy.resolve(b"tokens", b"[1]", 1, b"[2]", 2), b"[2]");
+        assert_eq!(
+            policy.resolve(b"settings:theme", b"\"dark\"", 1, b"\"light\"", 2),
+            b"\"dark\""
+        );
+        assert_eq!(
+            policy.resolve(b"contacts", br#"["alice"]"#, 2, br#"["bob"]"#, 1),
+            br#"["alice","bob"]"#
+        );
+        // not mergeable, the later write is kept
+        assert_eq!(policy.resolve(b"contacts", b"1", 1, b"2", 2), b"2");
+    }
+
+    #[test]
+    fn test_merge_json() {
+        let merged = merge_json(
+            br#"{"tokens":["zil"],"name":"local"}"#,
+            br#"{"tokens":["zlp"],"name":"remote","theme":"dark"}"#,
+        )
+        .unwrap();
+        let merged: Value = serde_json::from_slice(&merged).unwrap();
+
+        assert_eq!(
+            merged,
+            serde_json::json!({"tokens":["zil","zlp"],"name":"local","theme":"dark"})
+        );
+        assert_eq!(merge_json(b"1", b"2"), None);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::{
+    canonical::{verify_hashsum_with, HashAlgo},
+    sync::{ConflictPolicy, ConflictStrategy, SyncReport},
+    LocalStorage,
+};
+use cipher::{keychain::KeyChain, options::CipherOrders};
+use config::{
+    sha::SHA256_SIZE,
+    storage::{SYNC_CLIENT_TREE, SYNC_CURSOR_KEY, SYNC_CURSOR_TREE},
+};
+use serde::{Deserialize, Serialize};
+use zil_errors::{storage::LocalStorageError, sync::SyncErrors};
+
+// Synced marker of a key whose removal was exchanged.
+const DELETED: [u8; SHA256_SIZE] = [0u8; SHA256_SIZE];
+
+/// A record on the wire, hex encoded. The hashsum travels sealed with the
+/// payload under the sync keychain, the server only sees keys and times.
+/// `seq` is assigned by the server when it accepts a push, whatever the
+/// client sent. A removal goes out as `deleted` with an empty cipher.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteRecord {
+    pub key: String,
+    pub cipher: String,
+    pub last_update: u64,
+    #[serde(default)]
+    pub seq: u64,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// Pushes and pulls records against an HTTP endpoint:
+/// `POST {endpoint}/push` takes a list of [RemoteRecord], and
+/// `GET {endpoint}/pull?since=N` answers with the records the server
+/// accepted after its sequence N. Device clocks only order conflicts.
+/// Keys whose hashsum did not change since the last exchange are skipped,
+/// when both sides changed the [ConflictPolicy] decides, last write wins
+/// unless told otherwise.
+pub struct SyncClient<'a> {
+    endpoint: String,
+    client: reqwest::Client,
+    keychain: &'a KeyChain,
+    options: &'a [CipherOrders],
+    policy: ConflictPolicy,
+}
+
+impl<'a> SyncClient<'a> {
+    pub fn new(endpoint: &str, keychain: &'a KeyChain, options: &'a [CipherOrders]) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            keychain,
+            options,
+            policy: ConflictPolicy::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_namespace_strategy(mut self, prefix: &[u8], strategy: ConflictStrategy) -> Self {
+        self.policy = self.policy.namespace(prefix, strategy);
+        self
+    }
+
+    // E.g. a client going through the wallet proxy.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub async fn sync(
+        &self,
+        storage: &LocalStorage,
+        keys: &[&[u8]],
+    ) -> Result<SyncReport, SyncErrors> {
+        let mut report = self.pull(storage).await?;
+
+        report.pushed = self.push(storage, keys).await?;
+
+        Ok(report)
+    }
+
+    // Records of `keys` changed since they were last pushed or pulled.
+    pub async fn push(&self, storage: &LocalStorage, keys: &[&[u8]]) -> Result<usize, SyncErrors> {
+        let mut outgoing = Vec::new();
+
+        for key in keys {
+            let data = match storage.get_data(key) {
+                Ok(data) => data,
+                Err(LocalStorageError::StorageDataNotFound) => {
+                    let Some(deleted_at) = storage.tombstone(key)? else {
+                        continue;
+                    };
+
+                    if synced_hashsum(storage, key)? != Some(DELETED) {
+                        outgoing.push((DELETED, tombstone(key, deleted_at)));
+                    }
+
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let hashsum = data.hashsum.unwrap_or_default();
+
+            if synced_hashsum(storage, key)? == Some(hashsum) {
+                continue;
+            }
+
+            outgoing.push((
+                hashsum,
+                self.seal(
+                    key,
+                    &data.payload,
+                    data.hash,
+                    hashsum,
+                    data.last_update.unwrap_or(0),
+                )?,
+            ));
+        }
+
+        if outgoing.is_empty() {
+            return Ok(0);
+        }
+
+        let records: Vec<&RemoteRecord> = outgoing.iter().map(|(_, r)| r).collect();
+        let res = self
+            .client
+            .post(format!("{}/push", self.endpoint))
+            .json(&records)
+            .send()
+            .await
+            .map_err(|e| SyncErrors::RemoteError(e.to_string()))?;
+
+        if !res.status().is_success() {
+            return Err(SyncErrors::RemoteError(res.status().to_string()));
+        }
+
+        for (hashsum, record) in &outgoing {
+            set_synced_hashsum(storage, &decode_hex(&record.key)?, hashsum)?;
+        }
+
+        Ok(outgoing.len())
+    }
+
+    pub async fn pull(&self, storage: &LocalStorage) -> Result<SyncReport, SyncErrors> {
+        let since = cursor(storage)?;
+        let records: Vec<RemoteRecord> = self
+            .client
+            .get(format!("{}/pull", self.endpoint))
+            .query(&[("since", since)])
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| SyncErrors::RemoteError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| SyncErrors::RemoteError(e.to_string()))?;
+        let mut report = SyncReport::default();
+        let mut latest = since;
+
+        for record in records {
+            let key = decode_hex(&record.key)?;
+            let local = match storage.get_data(&key) {
+                Ok(data) => Some(data),
+                Err(LocalStorageError::StorageDataNotFound) => None,
+                Err(e) => return Err(e.into()),
+            };
+
+            latest = latest.max(record.seq);
+
+            if record.deleted {
+                match local {
+                    // changed here after the removal, goes out on push
+                    Some(data)
+                        if synced_hashsum(storage, &key)? != data.hashsum
+                            && data.last_update.unwrap_or(0) >= record.last_update =>
+                    {
+                        report.conflicts += 1;
+                    }
+                    Some(_) => {
+                        storage.remove(&key)?;
+                        set_synced_hashsum(storage, &key, &DELETED)?;
+                        report.pulled += 1;
+                    }
+                    None => set_synced_hashsum(storage, &key, &DELETED)?,
+                }
+
+                continue;
+            }
+
+            let (hashsum, remote) = self.open(&record)?;
+
+            match local {
+                Some(data) if data.hashsum == Some(hashsum) => {
+                    set_synced_hashsum(storage, &key, &hashsum)?;
+                }
+                // changed on both sides since the last exchange
+                Some(data) if synced_hashsum(storage, &key)? != data.hashsum => {
+                    let resolved = self.policy.resolve(
+                        &key,
+                        &data.payload,
+                        data.last_update.unwrap_or(0),
+                        &remote,
+                        record.last_update,
+                    );
+
+                    report.conflicts += 1;
+
+                    if resolved == remote {
+                        storage.set_with_update(&key, &remote, record.last_update)?;
+                        report.pulled += 1;
+                    } else if resolved != data.payload {
+                        storage.set(&key, &resolved)?;
+                        report.pulled += 1;
+                    }
+
+                    // a kept or merged local copy differs and goes out on push
+                    set_synced_hashsum(storage, &key, &hashsum)?;
+                }
+                Some(data) if data.last_update.unwrap_or(0) >= record.last_update => {}
+                // deleted here after the remote copy was written
+                None if storage
+                    .tombstone(&key)?
+                    .is_some_and(|deleted_at| deleted_at >= record.last_update) => {}
+                _ => {
+                    storage.set_with_update(&key, &remote, record.last_update)?;
+                    storage.unbury(&key)?;
+                    set_synced_hashsum(storage, &key, &hashsum)?;
+                    report.pulled += 1;
+                }
+            }
+        }
+
+        set_cursor(storage, latest)?;
+
+        Ok(report)
+    }
+
+    // Sealed as hash algo id, hashsum, payload.
+    fn seal(
+        &self,
+        key: &[u8],
+        payload: &[u8],
+        hash: HashAlgo,
+        hashsum: [u8; SHA256_SIZE],
+        last_update: u64,
+    ) -> Result<RemoteRecord, SyncErrors> {
+        let envelope = [&[hash.id()], hashsum.as_slice(), payload].concat();
+        let cipher = self
+            .keychain
+            .encrypt(envelope, self.options)
+            .map_err(SyncErrors::EncryptError)?;
+
+        Ok(RemoteRecord {
+            key: hex::encode(key),
+            cipher: hex::encode(cipher),
+            last_update,
+            seq: 0,
+            deleted: false,
+        })
+    }
+
+    // The payload must match the hashsum it was pushed with.
+    fn open(&self, record: &RemoteRecord) -> Result<([u8; SHA256_SIZE], Vec<u8>), SyncErrors> {
+        let envelope = self
+            .keychain
+            .decrypt(decode_hex(&record.cipher)?, self.options)
+            .map_err(SyncErrors::DecryptError)?;
+
+        if envelope.len() < SHA256_SIZE + 1 {
+            return Err(SyncErrors::InvalidRemoteRecord(record.key.clone()));
+        }
+
+        let hash = HashAlgo::from_id(envelope[0])?;
+        let hashsum: [u8; SHA256_SIZE] = envelope[1..=SHA256_SIZE]
+            .try_into()
+            .or(Err(SyncErrors::InvalidRemoteRecord(record.key.clone())))?;
+        let payload = envelope[SHA256_SIZE + 1..].to_vec();
+
+        if !verify_hashsum_with(hash, &payload, &hashsum) {
+            return Err(SyncErrors::HashsumMismatch(record.key.clone()));
+        }
+
+        Ok((hashsum, payload))
+    }
+}
+
+fn tombstone(key: &[u8], deleted_at: u64) -> RemoteRecord {
+    RemoteRecord {
+        key: hex::encode(key),
+        cipher: String::new(),
+        last_update: deleted_at,
+        seq: 0,
+        deleted: true,
+    }
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>, SyncErrors> {
+    hex::decode(value).or(Err(SyncErrors::InvalidRemoteRecord(value.to_string())))
+}
+
+fn synced_hashsum(
+    storage: &LocalStorage,
+    key: &[u8],
+) -> Result<Option<[u8; SHA256_SIZE]>, LocalStorageError> {
+    let bytes = storage
+        .open_tree(SYNC_CLIENT_TREE)?
+        .get(key)
+        .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+    Ok(bytes.and_then(|b| b.as_ref().try_into().ok()))
+}
+
+fn set_synced_hashsum(
+    storage: &LocalStorage,
+    key: &[u8],
+    hashsum: &[u8; SHA256_SIZE],
+) -> Result<(), LocalStorageError> {
+    storage
+        .open_tree(SYNC_CLIENT_TREE)?
+        .insert(key, hashsum.as_slice())
+        .or(Err(LocalStorageError::StorageWriteError))?;
+
+    Ok(())
+}
+
+fn cursor(storage: &LocalStorage) -> Result<u64, LocalStorageError> {
+    let bytes = storage
+        .open_tree(SYNC_CURSOR_TREE)?
+        .get(SYNC_CURSOR_KEY)
+        .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+    Ok(bytes
+        .and_then(|b| b.as_ref().try_into().ok())
+        .map_or(0, u64::from_be_bytes))
+}
+
+fn set_cursor(storage: &LocalStorage, since: u64) -> Result<(), LocalStorageError> {
+    storage
+        .open_tree(SYNC_CURSOR_TREE)?
+        .insert(SYNC_CURSOR_KEY, since.to_be_bytes().as_slice())
+        .or(Err(LocalStorageError::StorageWriteError))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical::canonical_hashsum;
+    use mockito::Matcher;
+
+    const OPTIONS: [CipherOrders; 1] = [CipherOrders::AESGCM256];
+
+    #[tokio::test]
+    async fn test_push_pull() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = LocalStorage::from(&dir).unwrap();
+        let keychain = KeyChain::from_pass(b"sync_password").unwrap();
+        let mut server = mockito::Server::new_async().await;
+        let client = SyncClient::new(&server.url(), &keychain, &OPTIONS);
+
+        storage.set(b"contacts", br#"["alice"]"#).unwrap();
+        storage.set(b"tokens", br#"["zlp"]"#).unwrap();
+
+        let push = server
+            .mock("POST", "/push")
+            .match_body(Matcher::Regex(hex::encode(b"contacts")))
+            .expect(1)
+            .create_async()
+            .await;
+
+        assert_eq!(client.push(&storage, &[b"contacts"]).await, Ok(1));
+        // unchanged since the last push, no request at all
+        assert_eq!(client.push(&storage, &[b"contacts"]).await, Ok(0));
+        push.assert_async().await;
+
+        let payload = br#"["zlp","gzil"]"#;
+        let sealed = |key: &[u8], payload: &[u8], last_update, seq| RemoteRecord {
+            seq,
+            ..client
+                .seal(
+                    key,
+                    payload,
+                    HashAlgo::Sha256,
+                    canonical_hashsum(payload),
+                    last_update,
+                )
+                .unwrap()
+        };
+        // the server sequence orders the feed, not the device clocks
+        let remote = vec![
+            sealed(b"tokens", payload, u64::MAX, 7),
+            sealed(b"contacts", b"[]", 1, 8),
+        ];
+        let pull = server
+            .mock("GET", "/pull")
+            .match_query(Matcher::UrlEncoded("since".into(), "0".into()))
+            .with_body(serde_json::to_string(&remote).unwrap())
+            .create_async()
+            .await;
+        let report = client.pull(&storage).await.unwrap();
+
+        pull.assert_async().await;
+        assert_eq!(report.pulled, 1);
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(storage.get(b"tokens").unwrap(), payload.to_vec());
+        // the newer local copy wins
+        assert_eq!(storage.get(b"contacts").unwrap(), br#"["alice"]"#.to_vec());
+        assert_eq!(cursor(&storage), Ok(8));
+
+        let mut forged = sealed(b"tokens", b"[]", 3, 9);
+
+        forged.cipher = client
+            .seal(b"tokens", b"[]", HashAlgo::Sha256, [1u8; SHA256_SIZE], 3)
+            .unwrap()
+            .cipher;
+        server
+            .mock("GET", "/pull")
+            .match_query(Matcher::UrlEncoded("since".into(), "8".into()))
+            .with_body(serde_json::to_string(&[forged]).unwrap())
+            .create_async()
+            .await;
+
+        assert_eq!(
+            client.pull(&storage).await,
+            Err(SyncErrors::HashsumMismatch(hex::encode(b"tokens")))
+        );
+        assert_eq!(cursor(&storage), Ok(8));
+    }
+
+    #[tokio::test]
+    async fn test_tombstones() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut storage = LocalStorage::from(&dir).unwrap();
+        let keychain = KeyChain::from_pass(b"sync_password").unwrap();
+        let mut server = mockito::Server::new_async().await;
+        let client = SyncClient::new(&server.url(), &keychain, &OPTIONS);
+
+        storage.set_tombstones(true);
+        storage.set(b"contacts", b"[]").unwrap();
+        storage.set(b"tokens", b"[]").unwrap();
+        server.mock("POST", "/push").create_async().await;
+        assert_eq!(
+            client.push(&storage, &[b"contacts", b"tokens"]).await,
+            Ok(2)
+        );
+
+        storage.remove(b"contacts").unwrap();
+
+        let push = server
+            .mock("POST", "/push")
+            .match_body(Matcher::PartialJson(serde_json::json!([{
+                "key": hex::encode(b"contacts"),
+                "deleted": true,
+            }])))
+            .expect(1)
+            .create_async()
+            .await;
+
+        assert_eq!(
+            client.push(&storage, &[b"contacts", b"tokens"]).await,
+            Ok(1)
+        );
+        assert_eq!(
+            client.push(&storage, &[b"contacts", b"tokens"]).await,
+            Ok(0)
+        );
+        push.assert_async().await;
+
+        let removed = RemoteRecord {
+            seq: 3,
+            ..tombstone(b"tokens", u64::MAX)
+        };
+
+        server
+            .mock("GET", "/pull")
+            .match_query(Matcher::Any)
+            .with_body(serde_json::to_string(&[removed]).unwrap())
+            .create_async()
+            .await;
+
+        assert_eq!(client.pull(&storage).await.unwrap().pulled, 1);
+        assert!(!storage.contains_key(b"tokens").unwrap());
+        // removed by the remote, nothing to send back
+        assert_eq!(client.push(&storage, &[b"tokens"]).await, Ok(0));
+    }
+}
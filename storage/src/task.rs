@@ -0,0 +1,71 @@
+use crate::LocalStorage;
+use std::sync::Arc;
+use zil_errors::storage::LocalStorageError;
+
+impl LocalStorage {
+    /// `get` on tokio's blocking pool, expiry, decrypt and decode included,
+    /// so an async RPC flow doesn't stall its thread on disk or crypto.
+    pub async fn get_async(self: &Arc<Self>, key: &[u8]) -> Result<Vec<u8>, LocalStorageError> {
+        let (storage, key) = (Arc::clone(self), key.to_vec());
+
+        blocking(move || storage.get(&key)).await
+    }
+
+    // Sealed, audited and written by `set` like any other record.
+    pub async fn set_async(
+        self: &Arc<Self>,
+        key: &[u8],
+        payload: &[u8],
+    ) -> Result<(), LocalStorageError> {
+        let (storage, key, payload) = (Arc::clone(self), key.to_vec(), payload.to_vec());
+
+        blocking(move || storage.set(&key, &payload)).await
+    }
+}
+
+async fn blocking<T, F>(f: F) -> Result<T, LocalStorageError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, LocalStorageError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_async_read_write() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        db.set_compression(true);
+        db.set_audit(Some("sync"));
+        db.set_with_ttl(b"gas_price", b"1000", 60_000).unwrap();
+
+        let db = Arc::new(db);
+
+        let payload = format!(r#"{{"history":"{}"}}"#, "a".repeat(2048));
+
+        db.set_async(b"gas_price", payload.as_bytes())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.get_async(b"gas_price").await.unwrap(),
+            payload.as_bytes()
+        );
+        assert_eq!(db.get(b"gas_price").unwrap(), payload.as_bytes());
+        // a plain write drops the TTL like `set` does
+        assert_eq!(db.ttl_remaining(b"gas_price").unwrap(), None);
+        // the async write is audited like any other
+        assert_eq!(db.audit_log().unwrap().len(), 2);
+        assert_eq!(
+            db.get_async(b"missing").await,
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{now_millis, LocalStorage};
+use config::storage::TOMBSTONE_TREE;
+use sled::IVec;
+use zil_errors::storage::LocalStorageError;
+
+impl LocalStorage {
+    /// While on, `remove` leaves the deletion time behind so sync can tell
+    /// a deleted key from one this device never had. Writing the key again
+    /// clears it.
+    pub fn set_tombstones(&mut self, enabled: bool) {
+        self.tombstones = enabled;
+    }
+
+    pub fn tombstone(&self, key: &[u8]) -> Result<Option<u64>, LocalStorageError> {
+        self.tree_get(TOMBSTONE_TREE, key)?
+            .map(|bytes| decode_time(&bytes))
+            .transpose()
+    }
+
+    pub fn tombstones(&self) -> Result<Vec<(Vec<u8>, u64)>, LocalStorageError> {
+        let pairs = self
+            .open_tree(TOMBSTONE_TREE)?
+            .iter()
+            .collect::<Result<Vec<(IVec, IVec)>, sled::Error>>()
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+
+        pairs
+            .into_iter()
+            .map(|(key, value)| {
+                Ok((
+                    key.to_vec(),
+                    decode_time(&self.decrypt(TOMBSTONE_TREE, &value)?)?,
+                ))
+            })
+            .collect()
+    }
+
+    // Once every device synced past `before` its tombstones can go.
+    pub fn purge_tombstones(&self, before: u64) -> Result<usize, LocalStorageError> {
+        let mut purged = 0;
+
+        for (key, deleted_at) in self.tombstones()? {
+            if deleted_at < before {
+                self.tree_remove(TOMBSTONE_TREE, &key)?;
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+
+    pub(crate) fn bury(&self, key: &[u8]) -> Result<(), LocalStorageError> {
+        if self.tombstones {
+            self.tree_set(TOMBSTONE_TREE, key, &now_millis()?.to_be_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn unbury(&self, key: &[u8]) -> Result<(), LocalStorageError> {
+        if self.tombstones {
+            self.tree_remove(TOMBSTONE_TREE, key)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn decode_time(bytes: &[u8]) -> Result<u64, LocalStorageError> {
+    let bytes: [u8; 8] = bytes
+        .try_into()
+        .or(Err(LocalStorageError::PayloadParseError))?;
+
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tombstones() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut db = LocalStorage::from(&dir).unwrap();
+
+        db.set(b"untracked", b"1").unwrap();
+        db.remove(b"untracked").unwrap();
+        db.set_tombstones(true);
+        db.set(b"account:1", b"{}").unwrap();
+        db.set(b"account:2", b"{}").unwrap();
+
+        assert!(db.remove(b"account:1").unwrap());
+        assert!(db.remove(b"account:2").unwrap());
+        assert_eq!(db.tombstone(b"untracked").unwrap(), None);
+        assert!(db.tombstone(b"account:1").unwrap().is_some());
+
+        // re-created
+        db.set(b"account:2", b"{}").unwrap();
+
+        assert_eq!(db.tombstones().unwrap().len(), 1);
+        assert_eq!(db.purge_tombstones(0).unwrap(), 0);
+        assert_eq!(db.purge_tombstones(u64::MAX).unwrap(), 1);
+        assert!(db.tombstones().unwrap().is_empty());
+    }
+}
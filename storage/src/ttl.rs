@@ -0,0 +1,138 @@
+use crate::{now_millis, LocalStorage};
+use config::storage::TTL_TREE;
+use zil_errors::storage::LocalStorageError;
+
+impl LocalStorage {
+    /// Like `set`, but the record reads as missing once `ttl_ms` passed. A
+    /// later plain `set` makes it permanent again.
+    pub fn set_with_ttl(
+        &self,
+        key: &[u8],
+        payload: &[u8],
+        ttl_ms: u64,
+    ) -> Result<(), LocalStorageError> {
+        let now = now_millis()?;
+
+        self.write(&self.tree, key, payload, now)?;
+        self.unbury(key)?;
+        self.tree_set(TTL_TREE, key, &now.saturating_add(ttl_ms).to_be_bytes())
+    }
+
+    // Milliseconds left, None for records that never expire.
+    pub fn ttl_remaining(&self, key: &[u8]) -> Result<Option<u64>, LocalStorageError> {
+        let now = now_millis()?;
+
+        Ok(self.expires_at(key)?.map(|at| at.saturating_sub(now)))
+    }
+
+    /// Removes every expired record, reads drop them lazily otherwise.
+    pub fn purge_expired(&self, now: u64) -> Result<usize, LocalStorageError> {
+        self.writable()?;
+
+        let keys = self
+            .open_tree(TTL_TREE)?
+            .iter()
+            .keys()
+            .collect::<Result<Vec<_>, sled::Error>>()
+            .map_err(|e| LocalStorageError::StorageAccessError(e.to_string()))?;
+        let mut purged = 0;
+
+        for key in keys {
+            if self.expire_at(&key, now)? {
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+
+    // Drops the record if it expired before `now`, a read-only copy only
+    // reports it as gone.
+    pub(crate) fn expire_at(&self, key: &[u8], now: u64) -> Result<bool, LocalStorageError> {
+        match self.expires_at(key)? {
+            Some(at) if at <= now => {
+                if !self.is_read_only() {
+                    self.remove_entry(key)?;
+                }
+
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub(crate) fn expire(&self, key: &[u8]) -> Result<bool, LocalStorageError> {
+        self.expire_at(key, now_millis()?)
+    }
+
+    fn expires_at(&self, key: &[u8]) -> Result<Option<u64>, LocalStorageError> {
+        self.tree_get(TTL_TREE, key)?
+            .map(|bytes| decode_expiry(&bytes))
+            .transpose()
+    }
+}
+
+pub(crate) fn decode_expiry(bytes: &[u8]) -> Result<u64, LocalStorageError> {
+    let bytes: [u8; 8] = bytes
+        .try_into()
+        .or(Err(LocalStorageError::PayloadParseError))?;
+
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        db.set_with_ttl(b"gas_price", b"2000", 0).unwrap();
+        db.set_with_ttl(b"ssn_list", b"[]", 60_000).unwrap();
+        db.set_with_ttl(b"prices", b"{}", 60_000).unwrap();
+
+        assert_eq!(
+            db.get(b"gas_price"),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+        assert!(!db.contains_key(b"gas_price").unwrap());
+        assert_eq!(db.get(b"ssn_list").unwrap(), b"[]");
+        assert!(db.ttl_remaining(b"ssn_list").unwrap().unwrap() <= 60_000);
+
+        // a plain set keeps the record for good
+        db.set(b"prices", b"{}").unwrap();
+
+        assert_eq!(db.ttl_remaining(b"prices").unwrap(), None);
+        assert_eq!(db.purge_expired(u64::MAX).unwrap(), 1);
+        assert_eq!(
+            db.get(b"ssn_list"),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+        assert_eq!(db.get(b"prices").unwrap(), b"{}");
+    }
+
+    #[test]
+    fn test_expiry_read_only() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = LocalStorage::from(&dir).unwrap();
+
+        db.set_with_ttl(b"gas_price", b"2000", 0).unwrap();
+        db.set(b"prices", b"{}").unwrap();
+        drop(db);
+
+        let read_only = LocalStorage::open_read_only(&dir).unwrap();
+
+        assert_eq!(
+            read_only.get(b"gas_price"),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+        assert!(!read_only.contains_key(b"gas_price").unwrap());
+        assert_eq!(
+            read_only.purge_expired(u64::MAX),
+            Err(LocalStorageError::StorageReadOnly)
+        );
+        assert_eq!(read_only.get(b"prices").unwrap(), b"{}");
+    }
+}
@@ -1,12 +1,15 @@
 use crate::account_type::AccountType;
+use crate::pending::PendingDelta;
 use bincode::{FromBytes, ToOptionVecBytes};
 use config::sha::SHA512_SIZE;
+use config::wallet::ACCOUNT_TAG_MAX_LEN;
 use crypto::bip49::Bip49DerivationPath;
 use num256::uint256::Uint256;
 use proto::address::Address;
 use proto::keypair::KeyPair;
 use proto::pubkey::PubKey;
 use proto::secret_key::SecretKey;
+use proto::xpub::ExtendedPubKey;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use zil_errors::account::AccountErrors;
@@ -19,6 +22,25 @@ pub struct Account {
     pub pub_key: PubKey,
     pub ft_map: HashMap<String, Uint256>, // map with ft token address > balance
     pub nft_map: HashMap<String, u8>,     // TODO: add struct for NFT tokens
+    #[serde(default)]
+    pub archived: bool, // hidden from listings and sync, keys stay in the vault
+    #[serde(default)]
+    pub tags: Vec<String>, // user defined, "Savings", "Trading", "DAO"
+    #[serde(default)]
+    pub color: Option<AccountColor>,
+    #[serde(default)]
+    pub pending: Vec<PendingDelta>, // broadcast, receipt not seen yet
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AccountColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray,
 }
 
 impl Account {
@@ -39,6 +61,10 @@ impl Account {
             name,
             ft_map: HashMap::new(),
             nft_map: HashMap::new(),
+            archived: false,
+            tags: Vec::new(),
+            color: None,
+            pending: Vec::new(),
         })
     }
 
@@ -60,9 +86,67 @@ impl Account {
             name,
             ft_map: HashMap::new(),
             nft_map: HashMap::new(),
+            archived: false,
+            tags: Vec::new(),
+            color: None,
+            pending: Vec::new(),
+        })
+    }
+
+    // Watch-only, the key is derived from the xpub and never leaves it.
+    pub fn from_xpub(
+        xpub: &ExtendedPubKey,
+        name: String,
+        index: usize,
+    ) -> Result<Self, AccountErrors> {
+        let child = u32::try_from(index).or(Err(AccountErrors::InvalidAccountTypeValue))?;
+        let pub_key = PubKey::Secp256k1Sha256Zilliqa(
+            xpub.receive_pub_key(child)
+                .map_err(AccountErrors::InvalidXpub)?,
+        );
+        let addr = Address::from_pubkey(&pub_key)?;
+
+        Ok(Self {
+            account_type: AccountType::WatchOnly(index),
+            addr,
+            pub_key,
+            name,
+            ft_map: HashMap::new(),
+            nft_map: HashMap::new(),
+            archived: false,
+            tags: Vec::new(),
+            color: None,
+            pending: Vec::new(),
         })
     }
 
+    // Tags match case-insensitively, "savings" finds "Savings".
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    pub fn add_tag(&mut self, tag: &str) -> Result<(), AccountErrors> {
+        let tag = tag.trim();
+
+        if tag.is_empty() || tag.chars().count() > ACCOUNT_TAG_MAX_LEN {
+            return Err(AccountErrors::InvalidTag(tag.to_string()));
+        }
+
+        if !self.has_tag(tag) {
+            self.tags.push(tag.to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let len = self.tags.len();
+
+        self.tags.retain(|t| !t.eq_ignore_ascii_case(tag.trim()));
+
+        self.tags.len() != len
+    }
+
     pub fn get_bip49(&self) -> Result<Bip49DerivationPath, AccountErrors> {
         match &self.account_type {
             AccountType::Bip39HD(v) => match &self.pub_key {
@@ -141,6 +225,57 @@ mod tests {
         assert_eq!(res, acc);
     }
 
+    #[test]
+    fn test_tags() {
+        let sk: SecretKey = "00e93c035175b08613c4b0251ca92cd007026ca032ba53bafa3c839838f8b52d04"
+            .parse()
+            .unwrap();
+        let mut acc = Account::from_secret_key(&sk, "Account 0".to_string(), 0).unwrap();
+
+        acc.add_tag(" Savings ").unwrap();
+        acc.add_tag("savings").unwrap();
+        acc.add_tag("DAO").unwrap();
+
+        assert_eq!(acc.tags, vec!["Savings".to_string(), "DAO".to_string()]);
+        assert!(acc.has_tag("SAVINGS"));
+        assert_eq!(
+            acc.add_tag("  "),
+            Err(AccountErrors::InvalidTag(String::new()))
+        );
+        assert!(acc.remove_tag("dao"));
+        assert!(!acc.remove_tag("dao"));
+
+        // accounts saved before tags existed
+        let mut json: serde_json::Value = serde_json::to_value(&acc).unwrap();
+
+        json.as_object_mut().unwrap().remove("tags");
+        json.as_object_mut().unwrap().remove("color");
+
+        let old: Account = serde_json::from_value(json).unwrap();
+
+        assert!(old.tags.is_empty());
+        assert_eq!(old.color, None);
+    }
+
+    #[test]
+    fn test_account_collection() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let db = storage::LocalStorage::from(&dir).unwrap();
+        let accounts =
+            storage::collection::Collection::<Account, Address>::new(&db, "accounts").unwrap();
+        let sk: SecretKey = "00e93c035175b08613c4b0251ca92cd007026ca032ba53bafa3c839838f8b52d04"
+            .parse()
+            .unwrap();
+        let acc = Account::from_secret_key(&sk, "Account 0".to_string(), 0).unwrap();
+
+        accounts.insert(&acc.addr, &acc).unwrap();
+
+        let (addr, stored) = accounts.iter().next().unwrap().unwrap();
+
+        assert_eq!(addr, acc.addr);
+        assert_eq!(accounts.get(&acc.addr).unwrap(), Some(stored));
+    }
+
     #[test]
     fn test_init_from_bip39() {
         let mut rng = rand::thread_rng();
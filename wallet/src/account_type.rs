@@ -11,6 +11,7 @@ pub enum AccountType {
     Ledger(usize),     // Ledger index
     Bip39HD(usize),    // HD key bip39 index
     PrivateKey(usize), // A storage key for cipher secret key
+    WatchOnly(usize),  // receive index under the wallet xpub
 }
 
 impl AccountType {
@@ -25,6 +26,7 @@ impl AccountType {
             0 => Ok(AccountType::Ledger(value)),
             1 => Ok(AccountType::Bip39HD(value)),
             2 => Ok(AccountType::PrivateKey(value)),
+            3 => Ok(AccountType::WatchOnly(value)),
             _ => Err(AccountErrors::InvalidAccountTypeCode),
         }
     }
@@ -34,6 +36,7 @@ impl AccountType {
             AccountType::Ledger(_) => 0,
             AccountType::Bip39HD(_) => 1,
             AccountType::PrivateKey(_) => 2,
+            AccountType::WatchOnly(_) => 3,
         }
     }
 
@@ -42,6 +45,7 @@ impl AccountType {
             AccountType::Ledger(v) => *v,
             AccountType::Bip39HD(v) => *v,
             AccountType::PrivateKey(v) => *v,
+            AccountType::WatchOnly(v) => *v,
         }
     }
 }
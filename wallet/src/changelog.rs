@@ -0,0 +1,152 @@
+use crate::{account::Account, history::History};
+use config::wallet::{UNDO_GRACE_PERIOD_MS, UNDO_LOG_CAPACITY};
+use num256::uint256::Uint256;
+use proto::address::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OpKind {
+    DeleteAccount,
+    RemoveToken,
+    WipeHistory,
+}
+
+/// State removed by a destructive operation, enough to put it back.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Snapshot {
+    Account {
+        index: usize,
+        account: Account,
+    },
+    Token {
+        addr: Address, // indexes shift when accounts are removed or restored
+        token: String,
+        balance: Uint256,
+    },
+    History(History),
+}
+
+impl Snapshot {
+    pub fn kind(&self) -> OpKind {
+        match self {
+            Snapshot::Account { .. } => OpKind::DeleteAccount,
+            Snapshot::Token { .. } => OpKind::RemoveToken,
+            Snapshot::History(_) => OpKind::WipeHistory,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeEntry {
+    pub at: u64, // unix millis
+    pub snapshot: Snapshot,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Changelog {
+    entries: VecDeque<ChangeEntry>,
+}
+
+impl Changelog {
+    // Returns what can no longer be undone, expired or evicted, so the
+    // caller can drop whatever it kept around for it.
+    pub fn record(&mut self, snapshot: Snapshot, at: u64) -> Vec<Snapshot> {
+        let mut dropped = self.prune(at);
+
+        if self.entries.len() == UNDO_LOG_CAPACITY {
+            dropped.extend(self.entries.pop_front().map(|entry| entry.snapshot));
+        }
+
+        self.entries.push_back(ChangeEntry { at, snapshot });
+
+        dropped
+    }
+
+    // Latest entry of that kind still inside the grace period.
+    pub fn take_last(&mut self, kind: OpKind, now: u64) -> Option<Snapshot> {
+        self.prune(now);
+
+        let pos = self
+            .entries
+            .iter()
+            .rposition(|entry| entry.snapshot.kind() == kind)?;
+
+        self.entries.remove(pos).map(|entry| entry.snapshot)
+    }
+
+    pub fn prune(&mut self, now: u64) -> Vec<Snapshot> {
+        let (alive, expired): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| now.saturating_sub(entry.at) < UNDO_GRACE_PERIOD_MS);
+
+        self.entries = alive;
+
+        expired.into_iter().map(|entry| entry.snapshot).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::address::ADDR_LEN;
+
+    fn token(name: &str) -> Snapshot {
+        Snapshot::Token {
+            addr: Address::Secp256k1Sha256Zilliqa([0u8; ADDR_LEN]),
+            token: name.to_string(),
+            balance: Uint256::from(1u8),
+        }
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut log = Changelog::default();
+
+        for i in 0..UNDO_LOG_CAPACITY {
+            assert!(log.record(token(&i.to_string()), 0).is_empty());
+        }
+
+        for i in UNDO_LOG_CAPACITY..UNDO_LOG_CAPACITY + 5 {
+            let evicted = log.record(token(&i.to_string()), 0);
+
+            assert_eq!(evicted, vec![token(&(i - UNDO_LOG_CAPACITY).to_string())]);
+        }
+
+        assert_eq!(log.len(), UNDO_LOG_CAPACITY);
+        assert_eq!(
+            log.take_last(OpKind::RemoveToken, 0),
+            Some(token(&(UNDO_LOG_CAPACITY + 4).to_string()))
+        );
+    }
+
+    #[test]
+    fn test_grace_period() {
+        let mut log = Changelog::default();
+
+        log.record(token("old"), 0);
+
+        assert_eq!(
+            log.record(token("new"), UNDO_GRACE_PERIOD_MS),
+            vec![token("old")]
+        );
+
+        assert_eq!(
+            log.take_last(OpKind::DeleteAccount, UNDO_GRACE_PERIOD_MS),
+            None
+        );
+        assert_eq!(
+            log.take_last(OpKind::RemoveToken, UNDO_GRACE_PERIOD_MS + 1),
+            Some(token("new"))
+        );
+        assert!(log.is_empty());
+    }
+}
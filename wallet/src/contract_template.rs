@@ -0,0 +1,272 @@
+use num256::uint256::Uint256;
+use proto::address::Address;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use zil_errors::contract_template::ContractTemplateErrors;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArgValue {
+    Fixed(String),
+    Placeholder(String), // key filled in at execution time
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateArg {
+    pub vname: String,
+    pub param_type: String, // scilla type, e.g. Uint128, ByStr20
+    pub value: ArgValue,
+}
+
+/// Transition signature as declared in the contract ABI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransitionSig {
+    pub name: String,
+    pub params: Vec<(String, String)>, // (vname, type)
+}
+
+/// Saved contract call, e.g. "claim rewards on contract X".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractTemplate {
+    pub name: String,
+    pub contract: Address,
+    pub transition: String,
+    pub args: Vec<TemplateArg>,
+}
+
+impl ContractTemplate {
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().filter_map(|arg| match &arg.value {
+            ArgValue::Placeholder(key) => Some(key.as_str()),
+            ArgValue::Fixed(_) => None,
+        })
+    }
+
+    pub fn validate(&self, transitions: &[TransitionSig]) -> Result<(), ContractTemplateErrors> {
+        let sig = transitions
+            .iter()
+            .find(|t| t.name == self.transition)
+            .ok_or(ContractTemplateErrors::UnknownTransition(
+                self.transition.clone(),
+            ))?;
+        let args: Vec<(&str, &str)> = self
+            .args
+            .iter()
+            .map(|a| (a.vname.as_str(), a.param_type.as_str()))
+            .collect();
+        let expected: Vec<(&str, &str)> = sig
+            .params
+            .iter()
+            .map(|(vname, ty)| (vname.as_str(), ty.as_str()))
+            .collect();
+
+        if args != expected {
+            return Err(ContractTemplateErrors::ParamsMismatch(
+                self.transition.clone(),
+            ));
+        }
+
+        for arg in self.args.iter() {
+            if let ArgValue::Fixed(value) = &arg.value {
+                validate_value(arg, value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds the `data` field of a contract call transaction.
+    pub fn fill(&self, values: &HashMap<String, String>) -> Result<Value, ContractTemplateErrors> {
+        let params = self
+            .args
+            .iter()
+            .map(|arg| {
+                let value = match &arg.value {
+                    ArgValue::Fixed(v) => v.clone(),
+                    ArgValue::Placeholder(key) => values
+                        .get(key)
+                        .cloned()
+                        .ok_or(ContractTemplateErrors::MissingPlaceholder(key.clone()))?,
+                };
+
+                validate_value(arg, &value)?;
+
+                Ok(json!({
+                    "vname": arg.vname,
+                    "type": arg.param_type,
+                    "value": value,
+                }))
+            })
+            .collect::<Result<Vec<Value>, ContractTemplateErrors>>()?;
+
+        Ok(json!({
+            "_tag": self.transition,
+            "params": params,
+        }))
+    }
+}
+
+// Checks primitive scilla types only, ADTs and maps are passed through.
+fn validate_value(arg: &TemplateArg, value: &str) -> Result<(), ContractTemplateErrors> {
+    let ty = arg.param_type.as_str();
+    let is_valid = if let Some(bits) = ty.strip_prefix("Uint") {
+        int_width(bits)
+            .zip(magnitude(value))
+            .is_some_and(|(bits, value)| value.0.bits() <= bits)
+    } else if let Some(bits) = ty.strip_prefix("Int") {
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, value),
+        };
+
+        // -2^(N-1) ..= 2^(N-1) - 1
+        int_width(bits)
+            .zip(magnitude(digits))
+            .is_some_and(|(bits, value)| {
+                let value = match negative && value > Uint256::from(0u8) {
+                    true => value - Uint256::from(1u8),
+                    false => value,
+                };
+
+                value.0.bits() < bits
+            })
+    } else if let Some(len) = ty.strip_prefix("ByStr") {
+        let hex_value = value.strip_prefix("0x").unwrap_or("");
+        let is_hex = hex_value.chars().all(|c| c.is_ascii_hexdigit());
+
+        match len.parse::<usize>() {
+            Ok(len) => is_hex && hex_value.len() == len * 2,
+            Err(_) => is_hex && len.is_empty() && hex_value.len().is_multiple_of(2),
+        }
+    } else if ty == "BNum" {
+        !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+    } else {
+        true
+    };
+
+    if !is_valid {
+        return Err(ContractTemplateErrors::InvalidValue(
+            arg.vname.clone(),
+            arg.param_type.clone(),
+            value.to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Scilla integers are 32, 64, 128 or 256 bits wide.
+fn int_width(bits: &str) -> Option<u32> {
+    bits.parse()
+        .ok()
+        .filter(|bits| matches!(bits, 32 | 64 | 128 | 256))
+}
+
+fn magnitude(digits: &str) -> Option<Uint256> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::address::ADDR_LEN;
+
+    fn template() -> ContractTemplate {
+        ContractTemplate {
+            name: "vote".to_string(),
+            contract: Address::Secp256k1Sha256Zilliqa([1u8; ADDR_LEN]),
+            transition: "Vote".to_string(),
+            args: vec![
+                TemplateArg {
+                    vname: "proposal".to_string(),
+                    param_type: "Uint32".to_string(),
+                    value: ArgValue::Placeholder("proposal_id".to_string()),
+                },
+                TemplateArg {
+                    vname: "delegate".to_string(),
+                    param_type: "ByStr20".to_string(),
+                    value: ArgValue::Fixed(format!("0x{}", "ab".repeat(ADDR_LEN))),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_fill() {
+        let template = template();
+        let values = HashMap::from([("proposal_id".to_string(), "7".to_string())]);
+        let data = template.fill(&values).unwrap();
+
+        assert_eq!(template.placeholders().collect::<Vec<_>>(), ["proposal_id"]);
+        assert_eq!(data["_tag"], "Vote");
+        assert_eq!(data["params"][0]["value"], "7");
+        assert_eq!(data["params"][1]["type"], "ByStr20");
+
+        assert_eq!(
+            template.fill(&HashMap::new()),
+            Err(ContractTemplateErrors::MissingPlaceholder(
+                "proposal_id".to_string()
+            ))
+        );
+
+        let bad = HashMap::from([("proposal_id".to_string(), "-1".to_string())]);
+
+        assert!(matches!(
+            template.fill(&bad),
+            Err(ContractTemplateErrors::InvalidValue(..))
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let template = template();
+        let sig = TransitionSig {
+            name: "Vote".to_string(),
+            params: vec![
+                ("proposal".to_string(), "Uint32".to_string()),
+                ("delegate".to_string(), "ByStr20".to_string()),
+            ],
+        };
+
+        assert_eq!(template.validate(std::slice::from_ref(&sig)), Ok(()));
+        assert_eq!(
+            template.validate(&[]),
+            Err(ContractTemplateErrors::UnknownTransition(
+                "Vote".to_string()
+            ))
+        );
+
+        let mut other = sig;
+        other.params.pop();
+
+        assert_eq!(
+            template.validate(&[other]),
+            Err(ContractTemplateErrors::ParamsMismatch("Vote".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_int_ranges() {
+        let arg = |ty: &str| TemplateArg {
+            vname: "amount".to_string(),
+            param_type: ty.to_string(),
+            value: ArgValue::Placeholder("amount".to_string()),
+        };
+        let u256_max = Uint256::from_be_bytes(&[0xff; 32]).to_string();
+
+        assert!(validate_value(&arg("Uint32"), "4294967295").is_ok());
+        assert!(validate_value(&arg("Uint32"), "4294967296").is_err());
+        assert!(validate_value(&arg("Uint256"), &u256_max).is_ok());
+        assert!(validate_value(&arg("Uint256"), &format!("{u256_max}0")).is_err());
+        assert!(validate_value(&arg("Int32"), "2147483647").is_ok());
+        assert!(validate_value(&arg("Int32"), "2147483648").is_err());
+        assert!(validate_value(&arg("Int32"), "-2147483648").is_ok());
+        assert!(validate_value(&arg("Int32"), "-2147483649").is_err());
+        assert!(validate_value(&arg("Int64"), "-0").is_ok());
+        assert!(validate_value(&arg("Uint7"), "1").is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::history::{History, HistoryRecord, TxStatus};
+use proto::{
+    address::Address,
+    asset::{AssetAmount, TokenAmount},
+    zrc2::Zrc2Transition,
+};
+
+/// Allowance a dApp holds on a token, increases minus decreases so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Approval {
+    pub token: Address,
+    pub spender: Address,
+    pub amount: u128,
+}
+
+/// What the txs one dApp requested did with the wallet's funds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DappSpending {
+    pub origin: String,
+    pub txs: usize,                    // in the period
+    pub transferred: Vec<AssetAmount>, // in the period, per asset
+    pub approvals: Vec<Approval>,      // still open, whenever granted
+    pub last_used: u64,
+}
+
+impl Approval {
+    // Sent to `token`, brings the allowance back to zero.
+    pub fn revoke(&self) -> Zrc2Transition {
+        Zrc2Transition::DecreaseAllowance {
+            spender: self.spender.clone(),
+            amount: self.amount,
+        }
+    }
+}
+
+impl History {
+    /// Confirmed txs per requesting dApp from `since` (ms), the most recently
+    /// used first. dApps with open allowances are listed without txs too.
+    pub fn dapp_spending(&self, since: u64) -> Vec<DappSpending> {
+        let mut list: Vec<DappSpending> = Vec::new();
+
+        for record in self.records() {
+            let Some(origin) = record
+                .origin
+                .as_ref()
+                .filter(|_| record.status == TxStatus::Confirmed)
+            else {
+                continue;
+            };
+            let spending = match list.iter().position(|s| &s.origin == origin) {
+                Some(i) => &mut list[i],
+                None => {
+                    list.push(DappSpending {
+                        origin: origin.clone(),
+                        txs: 0,
+                        transferred: Vec::new(),
+                        approvals: Vec::new(),
+                        last_used: 0,
+                    });
+                    list.last_mut().unwrap()
+                }
+            };
+            let approval = allowance_change(record);
+
+            if let Some((token, spender, delta)) = &approval {
+                spending.approve(token, spender, *delta);
+            }
+
+            if record.timestamp < since {
+                continue;
+            }
+
+            spending.txs += 1;
+            spending.last_used = spending.last_used.max(record.timestamp);
+
+            // an approval's decoded amount is the allowance, nothing moved
+            if let Some(amount) = record.amount.as_ref().filter(|_| approval.is_none()) {
+                spending.transfer(amount);
+            }
+        }
+
+        for spending in &mut list {
+            spending.approvals.retain(|a| a.amount > 0);
+        }
+
+        list.retain(|s| s.txs > 0 || !s.approvals.is_empty());
+        list.sort_by_key(|s| std::cmp::Reverse(s.last_used));
+
+        list
+    }
+
+    pub fn dapp_spending_of(&self, origin: &str, since: u64) -> Option<DappSpending> {
+        self.dapp_spending(since)
+            .into_iter()
+            .find(|s| s.origin == origin)
+    }
+
+    // Per record, whether it changed an allowance that is still open.
+    pub(crate) fn open_approval_records(&self) -> Vec<bool> {
+        let open = self.dapp_spending(u64::MAX);
+
+        self.records()
+            .iter()
+            .map(|record| {
+                let Some(origin) = record
+                    .origin
+                    .as_ref()
+                    .filter(|_| record.status == TxStatus::Confirmed)
+                else {
+                    return false;
+                };
+
+                allowance_change(record).is_some_and(|(token, spender, _)| {
+                    open.iter()
+                        .filter(|s| &s.origin == origin)
+                        .flat_map(|s| &s.approvals)
+                        .any(|a| a.token == token && a.spender == spender)
+                })
+            })
+            .collect()
+    }
+}
+
+impl DappSpending {
+    fn approve(&mut self, token: &Address, spender: &Address, delta: i128) {
+        let approval = match self
+            .approvals
+            .iter()
+            .position(|a| &a.token == token && &a.spender == spender)
+        {
+            Some(i) => &mut self.approvals[i],
+            None => {
+                self.approvals.push(Approval {
+                    token: token.clone(),
+                    spender: spender.clone(),
+                    amount: 0,
+                });
+                self.approvals.last_mut().unwrap()
+            }
+        };
+
+        approval.amount = approval.amount.saturating_add_signed(delta);
+    }
+
+    fn transfer(&mut self, amount: &AssetAmount) {
+        match self
+            .transferred
+            .iter_mut()
+            .find(|t| t.asset == amount.asset)
+        {
+            Some(total) => {
+                total.amount = TokenAmount(total.amount.0 + amount.amount.0);
+            }
+            None => self.transferred.push(amount.clone()),
+        }
+    }
+}
+
+// Token, spender and signed change of an allowance call.
+fn allowance_change(record: &HistoryRecord) -> Option<(Address, Address, i128)> {
+    let data = serde_json::from_str(&record.payload.as_ref()?.data).ok()?;
+    let token = record
+        .gas
+        .as_ref()
+        .map(|gas| gas.to_addr.clone())
+        .or_else(|| record.amount.as_ref()?.asset.contract().cloned())?;
+    let clamp = |amount: u128| i128::try_from(amount).unwrap_or(i128::MAX);
+
+    match Zrc2Transition::from_data(&data)? {
+        Zrc2Transition::IncreaseAllowance { spender, amount } => {
+            Some((token, spender, clamp(amount)))
+        }
+        Zrc2Transition::DecreaseAllowance { spender, amount } => {
+            Some((token, spender, -clamp(amount)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{GasReceipt, TxPayload};
+    use config::address::ADDR_LEN;
+    use proto::zil_tx::{ScillaGas, ZilAmount};
+    use storage::retention::RetentionPolicy;
+
+    const DEX: &str = "https://zilswap.example";
+
+    fn addr(byte: u8) -> Address {
+        Address::Secp256k1Sha256Zilliqa([byte; ADDR_LEN])
+    }
+
+    fn tx(nonce: u64, timestamp: u64, amount: Option<AssetAmount>) -> HistoryRecord {
+        HistoryRecord {
+            hash: Some(format!("{nonce:064x}")),
+            sender: addr(1),
+            nonce,
+            status: TxStatus::Confirmed,
+            amount,
+            block: None,
+            memo: None,
+            origin: Some(DEX.to_string()),
+            timestamp,
+            fiat: None,
+            gas: None,
+            payload: None,
+        }
+    }
+
+    fn allowance(nonce: u64, timestamp: u64, call: Zrc2Transition) -> HistoryRecord {
+        HistoryRecord {
+            gas: Some(GasReceipt {
+                to_addr: addr(0xa1),
+                gas_used: ScillaGas(1_000),
+                gas_price: ZilAmount::from_raw(2_000_000_000),
+            }),
+            payload: Some(TxPayload {
+                code: String::new(),
+                data: call.data().to_string(),
+            }),
+            ..tx(nonce, timestamp, None)
+        }
+    }
+
+    #[test]
+    fn test_dapp_spending() {
+        let mut history = History::default();
+        let zil = |qa| Some(AssetAmount::native(ZilAmount::from_raw(qa)));
+
+        history.add_intent(allowance(
+            1,
+            10,
+            Zrc2Transition::IncreaseAllowance {
+                spender: addr(2),
+                amount: 500,
+            },
+        ));
+        history.add_intent(tx(2, 20, zil(100)));
+        history.add_intent(tx(3, 30, zil(50)));
+        history.add_intent(allowance(
+            4,
+            40,
+            Zrc2Transition::DecreaseAllowance {
+                spender: addr(2),
+                amount: 200,
+            },
+        ));
+
+        let mut rejected = tx(5, 50, zil(1_000));
+
+        rejected.status = TxStatus::Rejected;
+        history.add_intent(rejected);
+
+        let mut other = tx(6, 60, zil(7));
+
+        other.origin = Some("https://other.example".to_string());
+        history.add_intent(other);
+
+        let spending = history.dapp_spending(15);
+
+        assert_eq!(spending.len(), 2);
+        assert_eq!(spending[0].origin, "https://other.example");
+
+        let dex = history.dapp_spending_of(DEX, 15).unwrap();
+
+        assert_eq!(dex.txs, 3);
+        assert_eq!(dex.last_used, 40);
+        assert_eq!(dex.transferred, vec![zil(150).unwrap()]);
+        assert_eq!(
+            dex.approvals,
+            vec![Approval {
+                token: addr(0xa1),
+                spender: addr(2),
+                amount: 300,
+            }]
+        );
+        assert_eq!(
+            dex.approvals[0].revoke(),
+            Zrc2Transition::DecreaseAllowance {
+                spender: addr(2),
+                amount: 300,
+            }
+        );
+        // open allowances stay listed outside the period
+        assert_eq!(history.dapp_spending_of(DEX, 100).unwrap().txs, 0);
+        assert_eq!(history.dapp_spending_of(DEX, 0).unwrap().txs, 4);
+
+        // pruning old txs keeps what the open allowance was built from
+        let policy = RetentionPolicy {
+            max_records: Some(1),
+            ..RetentionPolicy::unlimited()
+        };
+
+        history.prune(&policy, 100);
+
+        assert_eq!(history.records().len(), 3);
+        assert_eq!(
+            history.dapp_spending_of(DEX, 0).unwrap().approvals,
+            dex.approvals
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{vault_get, Wallet};
+use bincode::{FromBytes, ToBytes};
+use cipher::{
+    aes::AES_GCM_KEY_SIZE,
+    argon2::{derive_key, derive_key_with_params},
+    keychain::KeyChain,
+};
+use config::{argon::KEY_SIZE, cipher::PROOF_SIZE, storage::VAULT_NS, wallet::DURESS_SLOT_DOMAIN};
+use proto::{address::Address, keypair::KeyPair, secret_key::SecretKey};
+use sha2::{Digest, Sha256};
+use zil_errors::{storage::LocalStorageError, wallet::WalletErrors};
+
+// Account index and its secret key bytes.
+type DecoyKeys = Vec<(usize, Vec<u8>)>;
+
+/// Accounts visible after an unlock with the duress password. Nothing of
+/// it is on disk in the clear: the decoy keys sit in an ordinary cipher
+/// slot derived from the duress password, next to the real ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Decoy {
+    slot: usize,
+    // by address, account indexes shift on removal
+    pub(crate) accounts: Vec<Address>,
+}
+
+impl Wallet {
+    /// Seals the keys of `accounts` for `duress_password`. Unlocking with it
+    /// later shows only these accounts and signs with their keys, the real
+    /// vault stays sealed. Set again to replace the decoy accounts.
+    pub fn enable_duress(
+        &self,
+        cipher_key: &[u8; AES_GCM_KEY_SIZE],
+        duress_password: &[u8],
+        accounts: &[usize],
+        passphrase: Option<&str>,
+    ) -> Result<(), WalletErrors> {
+        if accounts.is_empty() {
+            return Err(WalletErrors::DuressNoAccounts);
+        }
+
+        let keychain = self
+            .session
+            .decrypt_keychain(cipher_key)
+            .map_err(WalletErrors::SessionDecryptKeychainError)?;
+        let cipher_proof = vault_get(&self.storage, self.data.proof_key)
+            .map_err(WalletErrors::FailToGetProofFromStorage)?;
+        let proof = keychain
+            .get_proof(&cipher_proof, &self.data.settings.crypto.cipher_orders)
+            .or(Err(WalletErrors::KeyChainFailToGetProof))?;
+        let duress_seed = derive_key_with_params(duress_password, &self.data.settings.crypto.kdf)
+            .map_err(WalletErrors::ArgonCipherErrors)?;
+        let duress_proof =
+            derive_key(&duress_seed[..PROOF_SIZE]).map_err(WalletErrors::ArgonCipherErrors)?;
+
+        if duress_proof == proof {
+            return Err(WalletErrors::DuressPasswordReused);
+        }
+
+        let mut keys: DecoyKeys = Vec::with_capacity(accounts.len());
+
+        for &index in accounts {
+            let sk = self
+                .reveal_keypair(index, cipher_key, passphrase)?
+                .get_secretkey()
+                .map_err(WalletErrors::FailToCreateKeyPair)?
+                .to_bytes()
+                .map_err(WalletErrors::FailToGetSKBytes)?;
+
+            keys.push((index, sk.to_vec()));
+        }
+
+        let bytes = serde_json::to_vec(&keys).or(Err(WalletErrors::FailToSerializeWalletData))?;
+        let decoy_keychain =
+            KeyChain::from_seed(&duress_seed).map_err(WalletErrors::DuressKeyChainError)?;
+        let cipher_keys = decoy_keychain
+            .encrypt(bytes, &self.data.settings.crypto.cipher_orders)
+            .map_err(WalletErrors::EncryptKeyChainErrors)?;
+        let slot = usize::to_le_bytes(duress_slot(&duress_proof));
+
+        self.storage
+            .ns_set(VAULT_NS, &slot, &cipher_keys)
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+        self.storage
+            .flush()
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+
+        Ok(())
+    }
+
+    // Returns whether `duress_password` had decoy accounts.
+    pub fn disable_duress(&self, duress_password: &[u8]) -> Result<bool, WalletErrors> {
+        let duress_seed = derive_key_with_params(duress_password, &self.data.settings.crypto.kdf)
+            .map_err(WalletErrors::ArgonCipherErrors)?;
+        let duress_proof =
+            derive_key(&duress_seed[..PROOF_SIZE]).map_err(WalletErrors::ArgonCipherErrors)?;
+
+        let slot = usize::to_le_bytes(duress_slot(&duress_proof));
+        let legacy = self
+            .storage
+            .remove(&slot)
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+
+        self.storage
+            .ns_remove(VAULT_NS, &slot)
+            .map(|removed| removed || legacy)
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)
+    }
+
+    // The keychain and proof of a password that did not open the vault.
+    pub(crate) fn open_decoy(
+        &self,
+        keychain: &KeyChain,
+        proof: &[u8; KEY_SIZE],
+    ) -> Result<Option<Decoy>, WalletErrors> {
+        let slot = duress_slot(proof);
+
+        let Some(keys) = self.decoy_keys(keychain, slot)? else {
+            return Ok(None);
+        };
+
+        let accounts = keys
+            .into_iter()
+            .map(|(_, sk)| {
+                decoy_keypair(sk)?
+                    .get_addr()
+                    .map_err(WalletErrors::FailToCreateKeyPair)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Some(Decoy { slot, accounts }))
+    }
+
+    pub(crate) fn decoy_keypair(
+        &self,
+        decoy: &Decoy,
+        keychain: &KeyChain,
+        account_index: usize,
+    ) -> Result<KeyPair, WalletErrors> {
+        let addr = self
+            .account(account_index)
+            .map(|account| &account.addr)
+            .ok_or(WalletErrors::FailToGetAccount(account_index))?;
+
+        for (_, sk) in self.decoy_keys(keychain, decoy.slot)?.unwrap_or_default() {
+            let keypair = decoy_keypair(sk)?;
+
+            if keypair.get_addr().as_ref() == Ok(addr) {
+                return Ok(keypair);
+            }
+        }
+
+        Err(WalletErrors::FailToGetAccount(account_index))
+    }
+
+    // Anything the keychain can't open is not a decoy of this password.
+    fn decoy_keys(
+        &self,
+        keychain: &KeyChain,
+        slot: usize,
+    ) -> Result<Option<DecoyKeys>, WalletErrors> {
+        let cipher_keys = match vault_get(&self.storage, slot) {
+            Ok(bytes) => bytes,
+            Err(LocalStorageError::StorageDataNotFound) => return Ok(None),
+            Err(e) => return Err(WalletErrors::FailToGetContent(e)),
+        };
+
+        Ok(keychain
+            .decrypt(cipher_keys, &self.data.settings.crypto.cipher_orders)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok()))
+    }
+}
+
+fn decoy_keypair(sk: Vec<u8>) -> Result<KeyPair, WalletErrors> {
+    let sk = SecretKey::from_bytes(sk.into()).map_err(WalletErrors::FailParseSKBytes)?;
+
+    KeyPair::from_secret_key(&sk).map_err(WalletErrors::FailToCreateKeyPair)
+}
+
+fn duress_slot(proof: &[u8; KEY_SIZE]) -> usize {
+    let hash: [u8; 32] = Sha256::new()
+        .chain_update(DURESS_SLOT_DOMAIN)
+        .chain_update(proof)
+        .finalize()
+        .into();
+    let mut slot = [0u8; std::mem::size_of::<usize>()];
+    let len = slot.len();
+
+    slot.copy_from_slice(&hash[..len]);
+
+    usize::from_le_bytes(slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        history::{HistoryRecord, TxStatus},
+        Wallet, WalletConfig,
+    };
+    use bip39::Mnemonic;
+    use cipher::{argon2::derive_key, keychain::KeyChain};
+    use config::cipher::PROOF_SIZE;
+    use crypto::bip49::Bip49DerivationPath;
+    use session::Session;
+    use std::rc::Rc;
+    use storage::LocalStorage;
+    use zil_errors::wallet::WalletErrors;
+
+    const MNEMONIC_STR: &str =
+        "green process gate doctor slide whip priority shrug diamond crumble average help";
+    const PASSWORD: &[u8] = b"Test_password";
+    const DURESS_PASSWORD: &[u8] = b"Decoy_password";
+
+    #[test]
+    fn test_duress_vault() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let (session, key) = Session::unlock(&argon_seed).unwrap();
+        let mnemonic =
+            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
+        let indexes = [0, 1, 2].map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain: KeyChain::from_seed(&argon_seed).unwrap(),
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let mut wallet =
+            Wallet::from_bip39_words(&proof, &mnemonic, "", &indexes, wallet_config).unwrap();
+        let savings = wallet.reveal_keypair(1, &key, None).unwrap();
+        let record = |account: usize| HistoryRecord {
+            hash: None,
+            sender: wallet.account(account).unwrap().addr.clone(),
+            nonce: account as u64,
+            status: TxStatus::Pending,
+            amount: None,
+            block: None,
+            memo: None,
+            origin: None,
+            timestamp: 0,
+            fiat: None,
+            gas: None,
+            payload: None,
+        };
+        let (main, decoy) = (record(0), record(1));
+
+        wallet.add_intent(main.clone());
+        wallet.add_intent(decoy.clone());
+
+        assert_eq!(
+            wallet.enable_duress(&key, PASSWORD, &[1], None),
+            Err(WalletErrors::DuressPasswordReused)
+        );
+
+        wallet
+            .enable_duress(&key, DURESS_PASSWORD, &[1], None)
+            .unwrap();
+        wallet.lock();
+
+        let decoy_key = wallet.unlock(DURESS_PASSWORD).unwrap();
+
+        assert_eq!(wallet.accounts().map(|(i, _)| i).collect::<Vec<_>>(), [1]);
+        assert_eq!(wallet.reveal_keypair(1, &decoy_key, None).unwrap(), savings);
+        assert!(wallet.reveal_keypair(0, &decoy_key, None).is_err());
+        assert!(wallet.reveal_mnemonic(&decoy_key).is_err());
+        // nothing of the hidden accounts can be read or changed
+        assert_eq!(wallet.selected_account(), 1);
+        assert!(wallet.account(0).is_none());
+        assert_eq!(wallet.history().records(), [decoy]);
+        assert_eq!(
+            wallet.remove_account(2),
+            Err(WalletErrors::FailToGetAccount(2))
+        );
+        assert!(wallet.rename_account(0, "renamed").is_err());
+        assert_eq!(wallet.xpub(), None);
+
+        wallet.wipe_history().unwrap();
+
+        assert!(wallet.history().records().is_empty());
+
+        let key = wallet.unlock(PASSWORD).unwrap();
+
+        assert_eq!(wallet.accounts().count(), 3);
+        assert_eq!(wallet.history().records(), [main]);
+        assert_eq!(wallet.account(0).unwrap().name, "account 0");
+        assert!(wallet.reveal_mnemonic(&key).is_ok());
+        assert!(wallet.unlock(b"wrong").is_err());
+        assert!(wallet.disable_duress(DURESS_PASSWORD).unwrap());
+        assert!(wallet.unlock(DURESS_PASSWORD).is_err());
+    }
+}
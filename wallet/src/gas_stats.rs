@@ -0,0 +1,165 @@
+use crate::history::{History, TxStatus};
+use config::broadcast::{GAS_LIMIT_EXCESS_FACTOR, GAS_STATS_MIN_CALLS};
+use proto::{
+    address::Address,
+    zil_tx::{ScillaGas, ZilAmount},
+};
+
+/// What calls to one contract cost so far, over confirmed txs only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractGas {
+    pub contract: Address,
+    pub calls: usize,
+    pub typical_gas: ScillaGas, // median
+    pub max_gas: ScillaGas,
+    pub typical_fee: ZilAmount, // median of gas used times gas price
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasLimitCheck {
+    Unknown, // not enough calls yet
+    TooLow { typical: ScillaGas },
+    Plausible,
+    Excessive { max_seen: ScillaGas },
+}
+
+impl ContractGas {
+    pub fn check_limit(&self, gas_limit: ScillaGas) -> GasLimitCheck {
+        if self.calls < GAS_STATS_MIN_CALLS {
+            GasLimitCheck::Unknown
+        } else if gas_limit < self.typical_gas {
+            GasLimitCheck::TooLow {
+                typical: self.typical_gas,
+            }
+        } else if gas_limit.0 > self.max_gas.0.saturating_mul(GAS_LIMIT_EXCESS_FACTOR) {
+            GasLimitCheck::Excessive {
+                max_seen: self.max_gas,
+            }
+        } else {
+            GasLimitCheck::Plausible
+        }
+    }
+}
+
+impl History {
+    pub fn gas_stats(&self) -> Vec<ContractGas> {
+        let mut samples: Vec<(Address, Vec<(u64, u128)>)> = Vec::new();
+
+        for record in self.records() {
+            let Some(gas) = record
+                .gas
+                .as_ref()
+                .filter(|_| record.status == TxStatus::Confirmed)
+            else {
+                continue;
+            };
+            let sample = (gas.gas_used.0, gas.gas_price.raw());
+
+            match samples.iter_mut().find(|(addr, _)| addr == &gas.to_addr) {
+                Some((_, list)) => list.push(sample),
+                None => samples.push((gas.to_addr.clone(), vec![sample])),
+            }
+        }
+
+        samples
+            .into_iter()
+            .map(|(contract, list)| {
+                let mut gas: Vec<u64> = list.iter().map(|(used, _)| *used).collect();
+                let mut fees: Vec<u128> = list
+                    .iter()
+                    .map(|(used, price)| u128::from(*used).saturating_mul(*price))
+                    .collect();
+
+                gas.sort_unstable();
+                fees.sort_unstable();
+
+                ContractGas {
+                    contract,
+                    calls: list.len(),
+                    typical_gas: ScillaGas(gas[gas.len() / 2]),
+                    max_gas: ScillaGas(gas[gas.len() - 1]),
+                    typical_fee: ZilAmount::from_raw(fees[fees.len() / 2]),
+                }
+            })
+            .collect()
+    }
+
+    pub fn contract_gas(&self, contract: &Address) -> Option<ContractGas> {
+        self.gas_stats()
+            .into_iter()
+            .find(|stats| &stats.contract == contract)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{GasReceipt, HistoryRecord};
+    use config::address::ADDR_LEN;
+
+    fn call(nonce: u64, to: u8, gas_used: u64, status: TxStatus) -> HistoryRecord {
+        HistoryRecord {
+            hash: Some(format!("{nonce:064x}")),
+            sender: Address::Secp256k1Sha256Zilliqa([1u8; ADDR_LEN]),
+            nonce,
+            status,
+            amount: None,
+            block: None,
+            memo: None,
+            origin: None,
+            timestamp: 0,
+            fiat: None,
+            gas: Some(GasReceipt {
+                to_addr: Address::Secp256k1Sha256Zilliqa([to; ADDR_LEN]),
+                gas_used: ScillaGas(gas_used),
+                gas_price: ZilAmount::from_raw(2_000_000_000),
+            }),
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_contract_gas() {
+        let mut history = History::default();
+        let dex = Address::Secp256k1Sha256Zilliqa([2u8; ADDR_LEN]);
+
+        for (nonce, gas_used) in [(1, 1_000), (2, 1_100), (3, 900), (4, 1_050)] {
+            history.add_intent(call(nonce, 2, gas_used, TxStatus::Confirmed));
+        }
+
+        history.add_intent(call(5, 2, 9_000, TxStatus::Rejected));
+        history.add_intent(call(6, 3, 50, TxStatus::Confirmed));
+
+        let stats = history.contract_gas(&dex).unwrap();
+
+        assert_eq!(history.gas_stats().len(), 2);
+        assert_eq!(stats.calls, 4);
+        assert_eq!(stats.typical_gas, ScillaGas(1_050));
+        assert_eq!(stats.max_gas, ScillaGas(1_100));
+        // ~2.1 ZIL per call
+        assert_eq!(stats.typical_fee, ZilAmount::from_raw(2_100_000_000_000));
+        assert_eq!(
+            stats.check_limit(ScillaGas(2_000)),
+            GasLimitCheck::Plausible
+        );
+        assert_eq!(
+            stats.check_limit(ScillaGas(500)),
+            GasLimitCheck::TooLow {
+                typical: ScillaGas(1_050)
+            }
+        );
+        assert_eq!(
+            stats.check_limit(ScillaGas(100_000)),
+            GasLimitCheck::Excessive {
+                max_seen: ScillaGas(1_100)
+            }
+        );
+        assert_eq!(
+            history
+                .contract_gas(&Address::Secp256k1Sha256Zilliqa([3u8; ADDR_LEN]))
+                .unwrap()
+                .check_limit(ScillaGas(50)),
+            GasLimitCheck::Unknown
+        );
+    }
+}
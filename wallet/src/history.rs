@@ -0,0 +1,345 @@
+use proto::{
+    address::Address,
+    asset::AssetAmount,
+    fiat::FiatAmount,
+    zil_tx::{ScillaGas, ZilAmount},
+};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use storage::{
+    compression::{compress, decompress},
+    gc::Reclaimed,
+    retention::{Retained, RetentionPolicy},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxStatus {
+    Pending,
+    Confirmed,
+    Rejected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub hash: Option<String>,
+    pub sender: Address,
+    pub nonce: u64,
+    pub status: TxStatus,
+    pub amount: Option<AssetAmount>, // decoded amount, token transfers included
+    pub block: Option<u64>,
+    pub memo: Option<String>,
+    pub origin: Option<String>, // dApp which requested the tx
+    pub timestamp: u64,
+    #[serde(default)]
+    pub fiat: Option<FiatAmount>, // value at confirmation, fiat denominated sends
+    #[serde(default)]
+    pub gas: Option<GasReceipt>, // from the receipt, once confirmed
+    #[serde(default)]
+    pub payload: Option<TxPayload>, // deployments and contract calls
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasReceipt {
+    pub to_addr: Address,
+    pub gas_used: ScillaGas,
+    pub gas_price: ZilAmount,
+}
+
+/// Code and data of a deployment or contract call. Stored zstd compressed
+/// once large enough to pay off, reads always see the plain fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxPayload {
+    pub code: String,
+    pub data: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredPayload {
+    Compressed { zstd: String }, // hex of the compressed plain form
+    Plain { code: String, data: String },
+}
+
+impl Serialize for TxPayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let plain = StoredPayload::Plain {
+            code: self.code.clone(),
+            data: self.data.clone(),
+        };
+        let json = serde_json::to_vec(&plain).map_err(ser::Error::custom)?;
+        let stored = match compress(&json).map_err(ser::Error::custom)? {
+            Cow::Owned(compressed) => StoredPayload::Compressed {
+                zstd: hex::encode(compressed),
+            },
+            Cow::Borrowed(_) => plain,
+        };
+
+        stored.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TxPayload {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = match StoredPayload::deserialize(deserializer)? {
+            StoredPayload::Compressed { zstd } => {
+                let bytes = hex::decode(zstd).map_err(de::Error::custom)?;
+                let json = decompress(&bytes).map_err(de::Error::custom)?;
+
+                serde_json::from_slice(&json).map_err(de::Error::custom)?
+            }
+            plain => plain,
+        };
+
+        match stored {
+            StoredPayload::Plain { code, data } => Ok(Self { code, data }),
+            StoredPayload::Compressed { .. } => Err(de::Error::custom("nested payload")),
+        }
+    }
+}
+
+impl HistoryRecord {
+    // Dedup keys: tx hash, or sender + nonce when a hash is not known yet
+    // (or the tx was replaced with a higher gas price).
+    pub fn same_tx(&self, other: &Self) -> bool {
+        let same_hash = matches!((&self.hash, &other.hash), (Some(a), Some(b)) if a == b);
+
+        same_hash || (self.sender == other.sender && self.nonce == other.nonce)
+    }
+
+    // Chain data wins, local-only fields are kept when the chain has nothing.
+    fn merge_local(mut self, local: HistoryRecord) -> Self {
+        self.hash = self.hash.or(local.hash);
+        self.amount = self.amount.or(local.amount);
+        self.block = self.block.or(local.block);
+        self.memo = self.memo.or(local.memo);
+        self.origin = self.origin.or(local.origin);
+        self.fiat = self.fiat.or(local.fiat);
+        self.gas = self.gas.or(local.gas);
+        self.payload = self.payload.or(local.payload);
+
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct History {
+    records: Vec<HistoryRecord>,
+}
+
+impl History {
+    pub fn records(&self) -> &[HistoryRecord] {
+        &self.records
+    }
+
+    pub fn add_intent(&mut self, intent: HistoryRecord) {
+        match self.records.iter_mut().find(|r| r.same_tx(&intent)) {
+            Some(record) => {
+                let existing = record.clone();
+
+                *record = existing.merge_local(intent);
+            }
+            None => self.records.push(intent),
+        }
+    }
+
+    // Records matching `keep`, e.g. those of the accounts a decoy shows.
+    pub(crate) fn filtered(&self, keep: impl Fn(&HistoryRecord) -> bool) -> History {
+        History {
+            records: self.records.iter().filter(|r| keep(r)).cloned().collect(),
+        }
+    }
+
+    pub(crate) fn split_off(&mut self, take: impl Fn(&HistoryRecord) -> bool) -> History {
+        let (taken, rest) = self.records.drain(..).partition(|r| take(r));
+
+        self.records = rest;
+
+        History { records: taken }
+    }
+
+    // Puts wiped records back, ones added since the wipe are kept.
+    pub fn restore(&mut self, wiped: History) {
+        let newer = std::mem::replace(self, wiped);
+
+        for record in newer.records {
+            self.add_intent(record);
+        }
+    }
+
+    /// Folds a chain-derived record into history, merging it with any local
+    /// intents of the same tx. Returns true when a local record was found.
+    pub fn reconcile(&mut self, chain: HistoryRecord) -> bool {
+        let (matched, rest): (Vec<HistoryRecord>, Vec<HistoryRecord>) =
+            self.records.drain(..).partition(|r| r.same_tx(&chain));
+        let found = !matched.is_empty();
+        let merged = matched
+            .into_iter()
+            .fold(chain, |acc, local| acc.merge_local(local));
+
+        self.records = rest;
+        self.records.push(merged);
+
+        found
+    }
+
+    // Oldest records beyond `policy` go, pending ones stay until settled.
+    pub fn prune(&mut self, policy: &RetentionPolicy, now: u64) -> Reclaimed {
+        let approvals = self.open_approval_records();
+        let entries: Vec<Retained> = self
+            .records
+            .iter()
+            .zip(approvals)
+            .map(|(record, approval)| Retained {
+                last_update: Some(record.timestamp),
+                bytes: serde_json::to_vec(record).map_or(0, |json| json.len() as u64),
+                // an allowance still open on chain stays revocable
+                pinned: record.status == TxStatus::Pending || approval,
+            })
+            .collect();
+        let keep = policy.keep_mask(&entries, now);
+        let mut reclaimed = Reclaimed::default();
+
+        for (entry, _) in entries.iter().zip(&keep).filter(|(_, keep)| !**keep) {
+            reclaimed += Reclaimed {
+                entries: 1,
+                bytes: entry.bytes,
+            };
+        }
+
+        let mut keep = keep.into_iter();
+
+        self.records.retain(|_| keep.next().unwrap_or(true));
+
+        reclaimed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::address::ADDR_LEN;
+
+    fn record(hash: Option<&str>, nonce: u64, status: TxStatus) -> HistoryRecord {
+        HistoryRecord {
+            hash: hash.map(String::from),
+            sender: Address::Secp256k1Sha256Zilliqa([1u8; ADDR_LEN]),
+            nonce,
+            status,
+            amount: None,
+            block: None,
+            memo: None,
+            origin: None,
+            timestamp: 0,
+            fiat: None,
+            gas: None,
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_reconcile_keeps_local_fields() {
+        let mut history = History::default();
+        let mut intent = record(None, 5, TxStatus::Pending);
+
+        intent.memo = Some("rent".to_string());
+        intent.origin = Some("dapp.example".to_string());
+        intent.amount = Some(AssetAmount::native(ZilAmount::from_raw(10)));
+        history.add_intent(intent);
+        history.add_intent(record(None, 6, TxStatus::Pending));
+
+        let mut chain = record(Some("0xabc"), 5, TxStatus::Confirmed);
+        chain.block = Some(100);
+
+        assert!(history.reconcile(chain));
+        assert_eq!(history.records().len(), 2);
+
+        let merged = history.records().iter().find(|r| r.nonce == 5).unwrap();
+
+        assert_eq!(merged.status, TxStatus::Confirmed);
+        assert_eq!(merged.hash.as_deref(), Some("0xabc"));
+        assert_eq!(merged.block, Some(100));
+        assert_eq!(merged.memo.as_deref(), Some("rent"));
+        assert_eq!(merged.origin.as_deref(), Some("dapp.example"));
+        assert!(merged.amount.is_some());
+    }
+
+    #[test]
+    fn test_reconcile_dedup() {
+        let mut history = History::default();
+
+        history.add_intent(record(Some("0x1"), 1, TxStatus::Pending));
+        // same tx seen twice from the chain
+        assert!(history.reconcile(record(Some("0x1"), 1, TxStatus::Confirmed)));
+        assert!(history.reconcile(record(Some("0x1"), 1, TxStatus::Confirmed)));
+        // unknown tx is simply added
+        assert!(!history.reconcile(record(Some("0x2"), 2, TxStatus::Rejected)));
+
+        assert_eq!(history.records().len(), 2);
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut history = History::default();
+
+        for (nonce, timestamp) in [(1, 100), (2, 300), (3, 200)] {
+            let mut tx = record(Some(&format!("0x{nonce}")), nonce, TxStatus::Confirmed);
+
+            tx.timestamp = timestamp;
+            history.add_intent(tx);
+        }
+
+        let mut stuck = record(None, 4, TxStatus::Pending);
+
+        stuck.timestamp = 50;
+        history.add_intent(stuck);
+
+        let policy = RetentionPolicy {
+            max_records: Some(2),
+            ..RetentionPolicy::unlimited()
+        };
+
+        assert_eq!(history.prune(&policy, 300).entries, 1);
+        assert_eq!(
+            history
+                .records()
+                .iter()
+                .map(|r| r.nonce)
+                .collect::<Vec<_>>(),
+            [2, 3, 4]
+        );
+        assert_eq!(history.prune(&policy, 300), Reclaimed::default());
+    }
+
+    #[test]
+    fn test_payload_compression() {
+        let mut deploy = record(Some("0x3"), 3, TxStatus::Confirmed);
+        let code = "scilla_version 0\n".repeat(200);
+
+        deploy.payload = Some(TxPayload {
+            code: code.clone(),
+            data: "[]".to_string(),
+        });
+
+        let json = serde_json::to_string(&deploy).unwrap();
+
+        assert!(json.contains("zstd"));
+        assert!(json.len() < code.len() / 4);
+        assert_eq!(
+            serde_json::from_str::<HistoryRecord>(&json).unwrap(),
+            deploy
+        );
+
+        // small calls stay readable as they are
+        let mut call = record(Some("0x4"), 4, TxStatus::Confirmed);
+
+        call.payload = Some(TxPayload {
+            code: String::new(),
+            data: r#"{"_tag":"Transfer"}"#.to_string(),
+        });
+
+        let json = serde_json::to_string(&call).unwrap();
+
+        assert!(json.contains("_tag"));
+        assert_eq!(serde_json::from_str::<HistoryRecord>(&json).unwrap(), call);
+    }
+}
@@ -1,33 +1,62 @@
 pub mod account;
 pub mod account_type;
+pub mod changelog;
+pub mod contract_template;
+pub mod dapp_spending;
+mod duress;
+pub mod gas_stats;
+pub mod history;
+pub mod nft_metadata;
+pub mod pending;
 pub mod wallet_data;
 pub mod wallet_types;
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use cipher::aes::AES_GCM_KEY_SIZE;
-use cipher::argon2::derive_key;
+use cipher::argon2::{derive_key, derive_key_with_params};
 use config::argon::KEY_SIZE;
 use config::cipher::PROOF_SIZE;
+use crypto::entropy;
+use proto::address::Address;
 use proto::keypair::KeyPair;
 use proto::secret_key::SecretKey;
 use proto::signature::Signature;
-use rand::{Rng, SeedableRng};
-use rand_chacha::ChaCha20Rng;
+use proto::statement::{BalanceStatement, SignedStatement};
+use proto::xpub::ExtendedPubKey;
+use rand::Rng;
+use serde::{de::DeserializeOwned, Deserialize};
 
+use account::AccountColor;
+use account_type::AccountType;
 use bincode::{FromBytes, ToBytes};
 use bip39::Mnemonic;
-use cipher::keychain::KeyChain;
+use changelog::{Changelog, OpKind, Snapshot};
+use cipher::escrow::{escrow_unwrap, escrow_wrap, ESCROW_PUB_KEY_SIZE, ESCROW_SECRET_KEY_SIZE};
+use cipher::keychain::{KeyChain, KEYCHAIN_BYTES_SIZE};
+use cipher::timelock::timelock_seal;
 use config::sha::SHA256_SIZE;
-use config::wallet::{N_BYTES_HASH, N_SALT};
+use config::storage::VAULT_NS;
+use config::wallet::{
+    ESCROW_KEY_SUFFIX, HISTORY_KEY_SUFFIX, N_BYTES_HASH, N_SALT, TEMPLATES_KEY_SUFFIX,
+    UNDO_LOG_KEY_SUFFIX,
+};
+use contract_template::{ContractTemplate, TransitionSig};
 use crypto::bip49::Bip49DerivationPath;
+use history::{History, HistoryRecord};
+use num256::uint256::Uint256;
 use session::Session;
-use settings::wallet_settings::WalletSettings;
+use settings::{network::NetworkCapabilities, wallet_settings::WalletSettings};
 use sha2::{Digest, Sha256};
-use storage::LocalStorage;
+use storage::{gc::Reclaimed, retention::RetentionPolicy, LocalStorage};
 use wallet_data::WalletData;
 use wallet_types::WalletTypes;
-use zil_errors::wallet::WalletErrors;
+use zil_errors::{
+    contract_template::ContractTemplateErrors, storage::LocalStorageError, wallet::WalletErrors,
+};
 
 pub struct WalletConfig {
     pub storage: Rc<LocalStorage>,
@@ -39,29 +68,35 @@ pub struct WalletConfig {
 pub struct Wallet {
     session: Session,
     storage: Rc<LocalStorage>,
-    pub data: WalletData,
+    changelog: Changelog,
+    decoy: Option<duress::Decoy>, // unlocked with the duress password
+    templates: Vec<ContractTemplate>,
+    history: History,
+    data: WalletData,
 }
 
 fn safe_storage_save(
     cipher_entropy: &[u8],
     storage: Rc<LocalStorage>,
 ) -> Result<usize, WalletErrors> {
-    let mut rng = ChaCha20Rng::from_entropy();
+    let mut rng = entropy::rng();
     let mut cipher_entropy_key: usize;
 
     loop {
         cipher_entropy_key = rng.r#gen();
         let key = usize::to_le_bytes(cipher_entropy_key);
-        let is_exists_key = storage
-            .exists(&key)
-            .map_err(WalletErrors::FailToSaveCipher)?;
+        let is_exists_key = match vault_get(&storage, cipher_entropy_key) {
+            Ok(_) => true,
+            Err(LocalStorageError::StorageDataNotFound) => false,
+            Err(e) => return Err(WalletErrors::FailToSaveCipher(e)),
+        };
 
         if is_exists_key {
             continue;
         }
 
         storage
-            .set(&key, cipher_entropy)
+            .ns_set(VAULT_NS, &key, cipher_entropy)
             .map_err(WalletErrors::FailToSaveCipher)?;
 
         break;
@@ -70,22 +105,103 @@ fn safe_storage_save(
     Ok(cipher_entropy_key)
 }
 
+// Sealed keys and proofs live in the vault namespace under their own key,
+// slots written before it existed are read from the main tree until the
+// next unlock moves them.
+pub(crate) fn vault_get(storage: &LocalStorage, slot: usize) -> Result<Vec<u8>, LocalStorageError> {
+    let key = usize::to_le_bytes(slot);
+
+    match storage.ns_get(VAULT_NS, &key) {
+        Err(LocalStorageError::StorageDataNotFound) => storage.get(&key),
+        res => res,
+    }
+}
+
+// Templates were kept in the wallet data before they moved to the vault.
+#[derive(Deserialize)]
+struct LegacyTemplates {
+    #[serde(default)]
+    templates: Vec<ContractTemplate>,
+}
+
+fn load_templates(
+    storage: &LocalStorage,
+    key: &[u8],
+    data: &[u8],
+) -> Result<Vec<ContractTemplate>, WalletErrors> {
+    match storage.ns_get(VAULT_NS, &[key, TEMPLATES_KEY_SUFFIX].concat()) {
+        Ok(bytes) => {
+            serde_json::from_slice(&bytes).or(Err(WalletErrors::FailToDeserializeWalletData))
+        }
+        Err(LocalStorageError::StorageDataNotFound) => {
+            serde_json::from_slice::<LegacyTemplates>(data)
+                .map(|legacy| legacy.templates)
+                .or(Err(WalletErrors::FailToDeserializeWalletData))
+        }
+        Err(e) => Err(WalletErrors::FailToLoadWalletData(e)),
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// Wallet records live in the namespace of the wallet key, records written
+// before namespacing are still read from `key || name` in the main tree.
+fn load_or_default<T: DeserializeOwned + Default>(
+    storage: &LocalStorage,
+    key: &[u8],
+    name: &[u8],
+    deserialize_error: WalletErrors,
+) -> Result<T, WalletErrors> {
+    let bytes = match storage.ns_get(key, name) {
+        Err(LocalStorageError::StorageDataNotFound) => storage.get(&[key, name].concat()),
+        res => res,
+    };
+
+    match bytes {
+        Ok(bytes) => serde_json::from_slice(&bytes).or(Err(deserialize_error)),
+        Err(LocalStorageError::StorageDataNotFound) => Ok(T::default()),
+        Err(e) => Err(WalletErrors::FailToLoadWalletData(e)),
+    }
+}
+
 impl Wallet {
     pub fn load_from_storage(
         key: &[u8; SHA256_SIZE],
         storage: Rc<LocalStorage>,
         session: Session,
     ) -> Result<Self, WalletErrors> {
-        let data = storage
+        let bytes = storage
             .get(key)
             .map_err(WalletErrors::FailToLoadWalletData)?;
-        let data = serde_json::from_slice::<WalletData>(&data)
+        let data = serde_json::from_slice::<WalletData>(&bytes)
             .or(Err(WalletErrors::FailToDeserializeWalletData))?;
+        let templates = load_templates(&storage, key, &bytes)?;
+        let changelog = load_or_default(
+            &storage,
+            key,
+            UNDO_LOG_KEY_SUFFIX,
+            WalletErrors::FailToDeserializeChangelog,
+        )?;
+        let history = load_or_default(
+            &storage,
+            key,
+            HISTORY_KEY_SUFFIX,
+            WalletErrors::FailToDeserializeHistory,
+        )?;
 
         Ok(Self {
             session,
             storage,
+            changelog,
+            history,
             data,
+            templates,
+            decoy: None,
         })
     }
 
@@ -129,13 +245,22 @@ impl Wallet {
             wallet_address,
             wallet_type: WalletTypes::SecretKey,
             selected_account: 0,
+            xpub: None,
         };
 
-        Ok(Self {
+        let mut wallet = Self {
             session: config.session,
             storage: config.storage,
+            changelog: Changelog::default(),
+            history: History::default(),
             data,
-        })
+            templates: Vec::new(),
+            decoy: None,
+        };
+
+        wallet.cache_pub_keys();
+
+        Ok(wallet)
     }
 
     pub fn from_bip39_words(
@@ -184,13 +309,22 @@ impl Wallet {
             accounts,
             wallet_type: WalletTypes::SecretPhrase((cipher_entropy_key, !passphrase.is_empty())),
             selected_account: 0,
+            xpub: None,
         };
 
-        Ok(Self {
+        let mut wallet = Self {
             session: config.session,
             storage: config.storage,
+            changelog: Changelog::default(),
+            history: History::default(),
             data,
-        })
+            templates: Vec::new(),
+            decoy: None,
+        };
+
+        wallet.cache_pub_keys();
+
+        Ok(wallet)
     }
 
     pub fn reveal_keypair(
@@ -208,6 +342,38 @@ impl Wallet {
             .decrypt_keychain(cipher_key)
             .map_err(WalletErrors::SessionDecryptKeychainError)?;
 
+        self.keypair_from(&keychain, account_index, passphrase)
+    }
+
+    // Signing needs the spend capability, see `elevate`.
+    fn signing_keypair(
+        &self,
+        account_index: usize,
+        cipher_key: &[u8; AES_GCM_KEY_SIZE],
+        passphrase: Option<&str>,
+    ) -> Result<KeyPair, WalletErrors> {
+        if !self.session.is_enabdle {
+            return Err(WalletErrors::DisabledSessions);
+        }
+
+        let keychain = self
+            .session
+            .spend_keychain(cipher_key, now_millis())
+            .map_err(WalletErrors::SessionDecryptKeychainError)?;
+
+        self.keypair_from(&keychain, account_index, passphrase)
+    }
+
+    fn keypair_from(
+        &self,
+        keychain: &KeyChain,
+        account_index: usize,
+        passphrase: Option<&str>,
+    ) -> Result<KeyPair, WalletErrors> {
+        if let Some(decoy) = &self.decoy {
+            return self.decoy_keypair(decoy, keychain, account_index);
+        }
+
         match self.data.wallet_type {
             WalletTypes::SecretKey => {
                 let account = self
@@ -215,10 +381,7 @@ impl Wallet {
                     .accounts
                     .get(account_index)
                     .ok_or(WalletErrors::FailToGetAccount(account_index))?;
-                let storage_key = usize::to_le_bytes(account.account_type.value());
-                let cipher_sk = self
-                    .storage
-                    .get(&storage_key)
+                let cipher_sk = vault_get(&self.storage, account.account_type.value())
                     .map_err(WalletErrors::FailToGetContent)?;
                 let sk_bytes = keychain
                     .decrypt(cipher_sk, &self.data.settings.crypto.cipher_orders)
@@ -230,7 +393,7 @@ impl Wallet {
 
                 Ok(keypair)
             }
-            WalletTypes::SecretPhrase((key, is_phr)) => {
+            WalletTypes::SecretPhrase((_, is_phr)) => {
                 if is_phr && passphrase.is_none() {
                     return Err(WalletErrors::PassphraseIsNone);
                 }
@@ -240,7 +403,7 @@ impl Wallet {
                     .accounts
                     .get(account_index)
                     .ok_or(WalletErrors::FailToGetAccount(account_index))?;
-                let m = self.reveal_mnemonic(cipher_key)?;
+                let m = self.mnemonic_from(keychain)?;
                 let seed = m.to_seed(passphrase.unwrap_or(""));
                 let bip49 = account.get_bip49().map_err(WalletErrors::InvalidBip49)?;
                 let keypair = KeyPair::from_bip39_seed(&seed, &bip49)
@@ -260,17 +423,25 @@ impl Wallet {
             return Err(WalletErrors::DisabledSessions);
         }
 
+        // checked before the session key, a decoy answers like a wallet
+        // without words so nothing hints at the real vault
+        if self.decoy.is_some() || !matches!(self.data.wallet_type, WalletTypes::SecretPhrase(_)) {
+            return Err(WalletErrors::InvalidAccountType);
+        }
+
+        let keychain = self
+            .session
+            .decrypt_keychain(cipher_key)
+            .map_err(WalletErrors::SessionDecryptKeychainError)?;
+
+        self.mnemonic_from(&keychain)
+    }
+
+    fn mnemonic_from(&self, keychain: &KeyChain) -> Result<Mnemonic, WalletErrors> {
         match self.data.wallet_type {
             WalletTypes::SecretPhrase((key, _)) => {
-                let keychain = self
-                    .session
-                    .decrypt_keychain(cipher_key)
-                    .map_err(WalletErrors::SessionDecryptKeychainError)?;
-                let storage_key = usize::to_le_bytes(key);
-                let cipher_entropy = self
-                    .storage
-                    .get(&storage_key)
-                    .map_err(WalletErrors::FailToGetContent)?;
+                let cipher_entropy =
+                    vault_get(&self.storage, key).map_err(WalletErrors::FailToGetContent)?;
                 let entropy = keychain
                     .decrypt(cipher_entropy, &self.data.settings.crypto.cipher_orders)
                     .map_err(WalletErrors::DecryptKeyChainErrors)?;
@@ -284,6 +455,40 @@ impl Wallet {
         }
     }
 
+    /// Unlocks signing for a short while, the UI calls it right after the
+    /// user confirms a send. Returns when the elevation expires.
+    pub fn elevate(&mut self, cipher_key: &[u8; AES_GCM_KEY_SIZE]) -> Result<u64, WalletErrors> {
+        self.session
+            .elevate(cipher_key, now_millis())
+            .map_err(WalletErrors::SessionDecryptKeychainError)
+    }
+
+    // Public key of a visible account from the session cache, no keychain
+    // needed.
+    pub fn pub_key(&self, account_index: usize) -> Option<&[u8]> {
+        self.session.capabilities.pub_key(account_index)
+    }
+
+    fn cache_pub_keys(&mut self) {
+        self.session.capabilities.clear_pub_keys();
+
+        let keys: Vec<_> = self
+            .visible_accounts()
+            .map(|(index, acc)| (index, acc.pub_key.as_ref().to_vec()))
+            .collect();
+
+        for (index, key) in keys {
+            self.session.capabilities.cache_pub_key(index, key);
+        }
+    }
+
+    // The cache is keyed by index, which shifts with the account list.
+    fn refresh_pub_keys(&mut self) {
+        if self.is_unlocked() {
+            self.cache_pub_keys();
+        }
+    }
+
     pub fn sign_message(
         &self,
         msg: &[u8],
@@ -291,7 +496,7 @@ impl Wallet {
         cipher_key: &[u8; AES_GCM_KEY_SIZE],
         passphrase: Option<&str>,
     ) -> Result<Signature, WalletErrors> {
-        let keypair = self.reveal_keypair(account_index, cipher_key, passphrase)?;
+        let keypair = self.signing_keypair(account_index, cipher_key, passphrase)?;
         let sig = keypair
             .sign_message(msg)
             .map_err(WalletErrors::FailSignMessage)?;
@@ -306,176 +511,1314 @@ impl Wallet {
         Ok(sig)
     }
 
+    /// Proof of funds for `statement.address`, signed by that account's key.
+    pub fn sign_balance_statement(
+        &self,
+        statement: BalanceStatement,
+        account_index: usize,
+        cipher_key: &[u8; AES_GCM_KEY_SIZE],
+        passphrase: Option<&str>,
+    ) -> Result<SignedStatement, WalletErrors> {
+        let keypair = self.signing_keypair(account_index, cipher_key, passphrase)?;
+
+        statement
+            .sign(&keypair)
+            .map_err(WalletErrors::FailToSignStatement)
+    }
+
     pub fn sign_transaction(&self, _account_index: usize) -> Result<(), WalletErrors> {
         // TODO: tx is not impl yet
         Ok(())
     }
 
-    pub fn lock(&mut self) {
-        self.session.logout();
+    // What the configured network supports, so the UI can hide the rest.
+    pub fn capabilities(&self) -> NetworkCapabilities {
+        self.data.settings.network.capabilities()
     }
 
-    pub fn unlock(&mut self, password: &[u8]) -> Result<[u8; AES_GCM_KEY_SIZE], WalletErrors> {
-        let argon_seed = derive_key(password).map_err(WalletErrors::ArgonCipherErrors)?;
-        let (session, key) =
-            Session::unlock(&argon_seed).or(Err(WalletErrors::UnlockSessionError))?;
-        let proof_key = usize::to_le_bytes(self.data.proof_key);
-        let cipher_proof = self
-            .storage
-            .get(&proof_key)
-            .map_err(WalletErrors::FailToGetProofFromStorage)?;
-        let keychain = session
-            .decrypt_keychain(&key)
-            .or(Err(WalletErrors::SessionDecryptError))?;
-        let origin_proof = keychain
-            .get_proof(&cipher_proof, &self.data.settings.crypto.cipher_orders)
-            .or(Err(WalletErrors::KeyChainFailToGetProof))?;
-        let proof =
-            derive_key(&argon_seed[..PROOF_SIZE]).map_err(WalletErrors::ArgonCipherErrors)?;
+    pub fn accounts(&self) -> impl Iterator<Item = (usize, &account::Account)> {
+        self.visible_accounts().filter(|(_, acc)| !acc.archived)
+    }
+
+    // Archived ones included, `None` for accounts a decoy doesn't show.
+    pub fn account(&self, account_index: usize) -> Option<&account::Account> {
+        self.visible_accounts()
+            .find(|(index, _)| *index == account_index)
+            .map(|(_, account)| account)
+    }
 
-        if proof != origin_proof {
-            return Err(WalletErrors::ProofNotMatch);
+    pub fn selected_account(&self) -> usize {
+        match self.account(self.data.selected_account) {
+            Some(_) => self.data.selected_account,
+            None => self.visible_accounts().next().map_or(0, |(index, _)| index),
         }
+    }
 
-        self.session = session;
+    pub fn wallet_type(&self) -> &WalletTypes {
+        &self.data.wallet_type
+    }
 
-        Ok(key)
+    pub fn wallet_address(&self) -> &str {
+        &self.data.wallet_address
     }
 
-    pub fn save_to_storage(&self) -> Result<(), WalletErrors> {
-        let json_bytes =
-            serde_json::to_vec(&self.data).or(Err(WalletErrors::FailToSerializeWalletData))?;
-        let key = self.key()?;
+    pub fn settings(&self) -> &WalletSettings {
+        &self.data.settings
+    }
 
-        self.storage
-            .set(&key, &json_bytes)
-            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+    // It derives every watch-only address, a decoy has none.
+    pub fn xpub(&self) -> Option<&str> {
+        self.data.xpub.as_deref().filter(|_| self.decoy.is_none())
+    }
+
+    /// Addresses of all accounts, also those a decoy hides. Only for
+    /// storage bookkeeping such as garbage collection, never for display.
+    pub fn owned_addresses(&self) -> impl Iterator<Item = &Address> {
+        self.data.accounts.iter().map(|account| &account.addr)
+    }
+
+    /// Records sent from or to the visible accounts.
+    pub fn history(&self) -> Cow<'_, History> {
+        if self.decoy.is_none() {
+            return Cow::Borrowed(&self.history);
+        }
+
+        Cow::Owned(
+            self.history
+                .filtered(|record| self.is_visible_record(record)),
+        )
+    }
+
+    pub fn add_intent(&mut self, intent: HistoryRecord) {
+        self.history.add_intent(intent);
+    }
+
+    pub fn reconcile(&mut self, chain: HistoryRecord) -> bool {
+        self.history.reconcile(chain)
+    }
+
+    pub fn prune_history(&mut self, policy: &RetentionPolicy, now: u64) -> Reclaimed {
+        self.history.prune(policy, now)
+    }
+
+    fn is_visible_record(&self, record: &HistoryRecord) -> bool {
+        let to_addr = record.gas.as_ref().map(|gas| &gas.to_addr);
+
+        self.visible_accounts()
+            .any(|(_, account)| account.addr == record.sender || Some(&account.addr) == to_addr)
+    }
+
+    pub fn archived_accounts(&self) -> impl Iterator<Item = (usize, &account::Account)> {
+        self.visible_accounts().filter(|(_, acc)| acc.archived)
+    }
+
+    pub fn archive_account(&mut self, account_index: usize) -> Result<(), WalletErrors> {
+        if account_index == self.selected_account() {
+            return Err(WalletErrors::CannotArchiveSelectedAccount(account_index));
+        }
+
+        self.set_archived(account_index, true)
+    }
+
+    pub fn restore_account(&mut self, account_index: usize) -> Result<(), WalletErrors> {
+        self.set_archived(account_index, false)
+    }
+
+    fn set_archived(&mut self, account_index: usize, archived: bool) -> Result<(), WalletErrors> {
+        self.account_mut(account_index)?.archived = archived;
+        self.refresh_pub_keys();
 
         Ok(())
     }
 
-    #[inline]
-    pub fn key(&self) -> Result<[u8; SHA256_SIZE], WalletErrors> {
-        hex::decode(&self.data.wallet_address)
-            .or(Err(WalletErrors::InvalidWalletAddressHex))?
-            .try_into()
-            .or(Err(WalletErrors::InvalidWalletAddressSize))
+    pub fn tag_account(&mut self, account_index: usize, tag: &str) -> Result<(), WalletErrors> {
+        self.account_mut(account_index)?
+            .add_tag(tag)
+            .map_err(WalletErrors::InvalidAccountTag)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use core::panic;
-    use std::rc::Rc;
+    pub fn untag_account(&mut self, account_index: usize, tag: &str) -> Result<bool, WalletErrors> {
+        Ok(self.account_mut(account_index)?.remove_tag(tag))
+    }
 
-    use bip39::Mnemonic;
-    use cipher::{argon2::derive_key, keychain::KeyChain};
-    use config::{cipher::PROOF_SIZE, sha::SHA256_SIZE};
-    use crypto::bip49::Bip49DerivationPath;
-    use proto::keypair::KeyPair;
-    use session::Session;
-    use storage::LocalStorage;
-    use zil_errors::wallet::WalletErrors;
+    pub fn rename_account(&mut self, account_index: usize, name: &str) -> Result<(), WalletErrors> {
+        self.account_mut(account_index)?.name = name.to_string();
 
-    use crate::{wallet_types::WalletTypes, Wallet, WalletConfig};
+        Ok(())
+    }
 
-    const MNEMONIC_STR: &str =
-        "green process gate doctor slide whip priority shrug diamond crumble average help";
-    const PASSWORD: &[u8] = b"Test_password";
-    const PASSPHRASE: &str = "";
+    pub fn set_account_color(
+        &mut self,
+        account_index: usize,
+        color: Option<AccountColor>,
+    ) -> Result<(), WalletErrors> {
+        self.account_mut(account_index)?.color = color;
 
-    #[test]
-    fn test_init_from_bip39_zil() {
-        let argon_seed = derive_key(PASSWORD).unwrap();
-        let (session, key) = Session::unlock(&argon_seed).unwrap();
-        let storage = LocalStorage::new(
-            "com.test_write_wallet",
-            "WriteTest Wallet Corp",
-            "WalletWriteTest App",
-        )
-        .unwrap();
-        let storage = Rc::new(storage);
-        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
-        let mnemonic =
-            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
-        let indexes = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
-            .map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
-        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
-        let wallet_config = WalletConfig {
-            session,
-            keychain,
-            storage: Rc::clone(&storage),
-            settings: Default::default(),
-        };
-        let wallet =
-            Wallet::from_bip39_words(&proof, &mnemonic, PASSPHRASE, &indexes, wallet_config)
-                .unwrap();
+        Ok(())
+    }
 
-        wallet.save_to_storage().unwrap();
+    // Every tag in use by an active account, in the spelling first used.
+    pub fn account_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
 
-        match wallet.data.wallet_type {
-            WalletTypes::SecretPhrase((_, is_phr)) => {
-                assert!(!is_phr);
+        for tag in self.accounts().flat_map(|(_, acc)| acc.tags.iter()) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
             }
-            _ => panic!("invalid type"),
         }
 
-        assert_eq!(wallet.data.accounts.len(), indexes.len());
+        tags
+    }
 
-        let wallet_addr: [u8; SHA256_SIZE] = hex::decode(wallet.data.wallet_address.clone())
-            .unwrap()
-            .try_into()
-            .unwrap();
+    pub fn accounts_by_tag<'a>(
+        &'a self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = (usize, &'a account::Account)> {
+        self.accounts().filter(move |(_, acc)| acc.has_tag(tag))
+    }
 
-        drop(wallet);
+    pub fn accounts_by_color(
+        &self,
+        color: AccountColor,
+    ) -> impl Iterator<Item = (usize, &account::Account)> {
+        self.accounts()
+            .filter(move |(_, acc)| acc.color == Some(color))
+    }
 
-        let (session, new_key) = Session::unlock(&argon_seed).unwrap();
-        let res_wallet =
-            Wallet::load_from_storage(&wallet_addr, Rc::clone(&storage), session).unwrap();
+    /// Token balances summed over the active accounts carrying `tag`,
+    /// keyed like `ft_map`.
+    pub fn tag_balances(&self, tag: &str) -> HashMap<String, Uint256> {
+        let mut balances: HashMap<String, Uint256> = HashMap::new();
 
-        assert!(res_wallet.reveal_mnemonic(&key).is_err());
-        assert!(res_wallet.reveal_mnemonic(&new_key).is_ok());
+        for (_, account) in self.accounts_by_tag(tag) {
+            for (token, balance) in &account.ft_map {
+                *balances.entry(token.clone()).or_default() += *balance;
+            }
+        }
+
+        balances
     }
 
-    #[test]
-    fn test_init_from_sk() {
-        let argon_seed = derive_key(PASSWORD).unwrap();
-        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
-        let (session, key) = Session::unlock(&argon_seed).unwrap();
-        let storage = LocalStorage::new(
-            "com.test_write_wallet_sk",
-            "WriteTest Wallet_sk Corp",
-            "WalletWriteTest App_sk",
-        )
-        .unwrap();
-        let storage = Rc::new(storage);
-        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
-        let keypair = KeyPair::gen_keccak256().unwrap();
-        let sk = keypair.get_secretkey().unwrap();
-        let name = "SK Account 0";
-        let wallet_config = WalletConfig {
-            session,
-            keychain,
-            storage: Rc::clone(&storage),
-            settings: Default::default(),
-        };
-        let wallet = Wallet::from_sk(&sk, name.to_string(), &proof, wallet_config).unwrap();
+    // Hidden accounts can't be changed any more than they can be read.
+    pub(crate) fn account_mut(
+        &mut self,
+        account_index: usize,
+    ) -> Result<&mut account::Account, WalletErrors> {
+        if self.account(account_index).is_none() {
+            return Err(WalletErrors::FailToGetAccount(account_index));
+        }
 
-        assert_eq!(wallet.data.accounts.len(), 1);
-        assert_eq!(
-            wallet.reveal_mnemonic(&key),
-            Err(WalletErrors::InvalidAccountType)
-        );
+        Ok(&mut self.data.accounts[account_index])
+    }
 
-        let wallet_addr: [u8; SHA256_SIZE] = hex::decode(wallet.data.wallet_address.clone())
-            .unwrap()
-            .try_into()
-            .unwrap();
-        wallet.save_to_storage().unwrap();
+    pub fn remove_account(&mut self, account_index: usize) -> Result<(), WalletErrors> {
+        if account_index == self.selected_account() {
+            return Err(WalletErrors::CannotRemoveSelectedAccount(account_index));
+        }
 
-        let (session, _key) = Session::unlock(&argon_seed).unwrap();
-        let w = Wallet::load_from_storage(&wallet_addr, Rc::clone(&storage), session).unwrap();
+        if self.account(account_index).is_none() {
+            return Err(WalletErrors::FailToGetAccount(account_index));
+        }
 
-        assert_eq!(w.data, wallet.data);
+        // secrets stay in storage so the removal can be undone
+        let account = self.data.accounts.remove(account_index);
+
+        if account_index < self.data.selected_account {
+            self.data.selected_account -= 1;
+        }
+
+        self.refresh_pub_keys();
+
+        let dropped = self.changelog.record(
+            Snapshot::Account {
+                index: account_index,
+                account,
+            },
+            now_millis(),
+        );
+
+        self.discard_snapshots(dropped)
+    }
+
+    // A token already listed keeps its balance.
+    pub fn add_token(&mut self, account_index: usize, token: &str) -> Result<(), WalletErrors> {
+        self.account_mut(account_index)?
+            .ft_map
+            .entry(token.to_string())
+            .or_insert_with(|| Uint256::from(0u8));
+
+        Ok(())
+    }
+
+    // Balances as polled from the node.
+    pub fn set_token_balance(
+        &mut self,
+        account_index: usize,
+        token: &str,
+        balance: Uint256,
+    ) -> Result<(), WalletErrors> {
+        self.account_mut(account_index)?
+            .ft_map
+            .insert(token.to_string(), balance);
+
+        Ok(())
+    }
+
+    pub fn remove_token(&mut self, account_index: usize, token: &str) -> Result<(), WalletErrors> {
+        let account = self.account_mut(account_index)?;
+        let balance = account
+            .ft_map
+            .remove(token)
+            .ok_or(WalletErrors::FailToGetToken(token.to_string()))?;
+        let addr = account.addr.clone();
+        let dropped = self.changelog.record(
+            Snapshot::Token {
+                addr,
+                token: token.to_string(),
+                balance,
+            },
+            now_millis(),
+        );
+
+        self.discard_snapshots(dropped)
+    }
+
+    // Under the duress password only the records it shows go.
+    pub fn wipe_history(&mut self) -> Result<(), WalletErrors> {
+        let history = match self.decoy {
+            None => std::mem::take(&mut self.history),
+            Some(_) => {
+                let visible = self.history().into_owned();
+
+                self.history
+                    .split_off(|record| visible.records().contains(record))
+            }
+        };
+
+        let dropped = self
+            .changelog
+            .record(Snapshot::History(history), now_millis());
+
+        self.discard_snapshots(dropped)
+    }
+
+    pub fn undo_last(&mut self, kind: OpKind) -> Result<(), WalletErrors> {
+        let now = now_millis();
+        let expired = self.changelog.prune(now);
+
+        self.discard_snapshots(expired)?;
+
+        let snapshot = self
+            .changelog
+            .take_last(kind, now)
+            .ok_or(WalletErrors::NothingToUndo)?;
+
+        match snapshot {
+            Snapshot::Account { index, account } => {
+                let index = index.min(self.data.accounts.len());
+
+                if index <= self.data.selected_account {
+                    self.data.selected_account += 1;
+                }
+
+                self.data.accounts.insert(index, account);
+                self.refresh_pub_keys();
+            }
+            Snapshot::Token {
+                addr,
+                token,
+                balance,
+            } => {
+                let account = self
+                    .data
+                    .accounts
+                    .iter_mut()
+                    .find(|acc| acc.addr == addr)
+                    .ok_or(WalletErrors::FailToGetToken(token.clone()))?;
+
+                account.ft_map.insert(token, balance);
+            }
+            Snapshot::History(history) => self.history.restore(history),
+        }
+
+        Ok(())
+    }
+
+    // The sealed key of a removed account was kept for undo, once that is
+    // no longer possible it goes too.
+    fn discard_snapshots(&self, dropped: Vec<Snapshot>) -> Result<(), WalletErrors> {
+        let slots: Vec<usize> = dropped
+            .iter()
+            .filter_map(|snapshot| match snapshot {
+                Snapshot::Account { account, .. } => match account.account_type {
+                    AccountType::PrivateKey(slot) => Some(slot),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .filter(|slot| {
+                !self
+                    .data
+                    .accounts
+                    .iter()
+                    .any(|acc| acc.account_type == AccountType::PrivateKey(*slot))
+            })
+            .collect();
+
+        if slots.is_empty() {
+            return Ok(());
+        }
+
+        for slot in slots {
+            let key = usize::to_le_bytes(slot);
+
+            self.storage
+                .ns_remove(VAULT_NS, &key)
+                .and_then(|_| self.storage.remove(&key))
+                .map_err(WalletErrors::FailToDeleteVaultSlot)?;
+        }
+
+        self.storage
+            .flush()
+            .map_err(WalletErrors::FailToDeleteVaultSlot)?;
+
+        Ok(())
+    }
+
+    pub fn add_template(
+        &mut self,
+        template: ContractTemplate,
+        transitions: &[TransitionSig],
+    ) -> Result<(), WalletErrors> {
+        if self.template(&template.name).is_some() {
+            return Err(ContractTemplateErrors::AlreadyExists(template.name).into());
+        }
+
+        template.validate(transitions)?;
+        self.templates.push(template);
+
+        Ok(())
+    }
+
+    pub fn template(&self, name: &str) -> Option<&ContractTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    pub fn remove_template(&mut self, name: &str) -> Result<ContractTemplate, WalletErrors> {
+        let pos = self
+            .templates
+            .iter()
+            .position(|t| t.name == name)
+            .ok_or(ContractTemplateErrors::NotFound(name.to_string()))?;
+
+        Ok(self.templates.remove(pos))
+    }
+
+    /// Stores the keychain wrapped for an organization key, so an admin
+    /// can recover the vault without the user password.
+    pub fn enable_escrow(
+        &self,
+        cipher_key: &[u8; AES_GCM_KEY_SIZE],
+        org_pub_key: &[u8; ESCROW_PUB_KEY_SIZE],
+    ) -> Result<(), WalletErrors> {
+        let keychain = self
+            .session
+            .decrypt_keychain(cipher_key)
+            .map_err(WalletErrors::SessionDecryptKeychainError)?;
+        let blob = escrow_wrap(org_pub_key, &keychain.to_bytes())?;
+
+        self.storage
+            .ns_set(&self.key()?, ESCROW_KEY_SUFFIX, &blob)
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+
+        Ok(())
+    }
+
+    pub fn escrow_blob(&self) -> Result<Vec<u8>, WalletErrors> {
+        let key = self.key()?;
+
+        match self.storage.ns_get(&key, ESCROW_KEY_SUFFIX) {
+            Err(LocalStorageError::StorageDataNotFound) => self
+                .storage
+                .get(&[key.as_slice(), ESCROW_KEY_SUFFIX].concat()),
+            res => res,
+        }
+        .map_err(WalletErrors::FailToGetContent)
+    }
+
+    pub fn recover_from_escrow(
+        blob: &[u8],
+        org_secret_key: &[u8; ESCROW_SECRET_KEY_SIZE],
+    ) -> Result<KeyChain, WalletErrors> {
+        let bytes: [u8; KEYCHAIN_BYTES_SIZE] = escrow_unwrap(org_secret_key, blob)?
+            .try_into()
+            .or(Err(WalletErrors::InvalidEscrowKeychain))?;
+
+        KeyChain::from_bytes(&bytes).or(Err(WalletErrors::InvalidEscrowKeychain))
+    }
+
+    /// Inheritance export of the mnemonic, see [timelock_seal]. The notary
+    /// releases its key share only after `unlock_at` (unix millis), the heir
+    /// also needs `heir_key`.
+    pub fn export_time_locked(
+        &self,
+        cipher_key: &[u8; AES_GCM_KEY_SIZE],
+        notary_pub_key: &[u8; ESCROW_PUB_KEY_SIZE],
+        heir_key: &[u8; AES_GCM_KEY_SIZE],
+        unlock_at: u64,
+    ) -> Result<Vec<u8>, WalletErrors> {
+        let phrase = self.reveal_mnemonic(cipher_key)?.to_string();
+
+        Ok(timelock_seal(
+            notary_pub_key,
+            heir_key,
+            unlock_at,
+            now_millis(),
+            phrase.as_bytes(),
+        )?)
+    }
+
+    /// Account level xpub of the Zilliqa path, enough for an external system
+    /// to derive every receive address.
+    pub fn export_xpub(
+        &self,
+        cipher_key: &[u8; AES_GCM_KEY_SIZE],
+        passphrase: Option<&str>,
+    ) -> Result<String, WalletErrors> {
+        if let WalletTypes::SecretPhrase((_, true)) = self.data.wallet_type {
+            if passphrase.is_none() {
+                return Err(WalletErrors::PassphraseIsNone);
+            }
+        }
+
+        let seed = self
+            .reveal_mnemonic(cipher_key)?
+            .to_seed(passphrase.unwrap_or(""));
+        let xpub =
+            ExtendedPubKey::from_seed(&seed, Bip49DerivationPath::Zilliqa(0).get_account_path())?;
+
+        Ok(xpub.to_string())
+    }
+
+    // Derives the next receive address of `xpub` locally, returns its index.
+    pub fn add_watch_only_account(
+        &mut self,
+        xpub: &str,
+        name: String,
+    ) -> Result<usize, WalletErrors> {
+        if self.data.xpub.as_ref().is_some_and(|x| x != xpub) {
+            return Err(WalletErrors::XpubMismatch);
+        }
+
+        let ext = xpub.parse::<ExtendedPubKey>()?;
+        let index = self
+            .data
+            .accounts
+            .iter()
+            .filter_map(|a| match a.account_type {
+                account_type::AccountType::WatchOnly(i) => Some(i + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let account = account::Account::from_xpub(&ext, name, index)
+            .map_err(WalletErrors::InvalidXpubAccount)?;
+
+        if let Some(decoy) = self.decoy.as_mut() {
+            decoy.accounts.push(account.addr.clone());
+        }
+
+        self.data.xpub = Some(xpub.to_string());
+        self.data.accounts.push(account);
+        self.refresh_pub_keys();
+
+        Ok(index)
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.session.is_enabdle
+    }
+
+    pub fn lock(&mut self) {
+        self.session.logout();
+        self.decoy = None;
+    }
+
+    pub fn unlock(&mut self, password: &[u8]) -> Result<[u8; AES_GCM_KEY_SIZE], WalletErrors> {
+        let argon_seed = derive_key_with_params(password, &self.data.settings.crypto.kdf)
+            .map_err(WalletErrors::ArgonCipherErrors)?;
+        let (session, key) =
+            Session::unlock(&argon_seed).or(Err(WalletErrors::UnlockSessionError))?;
+
+        self.migrate_vault()?;
+
+        let cipher_proof = vault_get(&self.storage, self.data.proof_key)
+            .map_err(WalletErrors::FailToGetProofFromStorage)?;
+        let keychain = session
+            .decrypt_keychain(&key)
+            .or(Err(WalletErrors::SessionDecryptError))?;
+        let origin_proof = keychain
+            .get_proof(&cipher_proof, &self.data.settings.crypto.cipher_orders)
+            .or(Err(WalletErrors::KeyChainFailToGetProof));
+        let proof =
+            derive_key(&argon_seed[..PROOF_SIZE]).map_err(WalletErrors::ArgonCipherErrors)?;
+
+        let decoy = if origin_proof.as_ref() == Ok(&proof) {
+            None
+        } else {
+            // not the owner, maybe the duress password
+            match self.open_decoy(&keychain, &proof)? {
+                Some(decoy) => Some(decoy),
+                None => {
+                    origin_proof?;
+
+                    return Err(WalletErrors::ProofNotMatch);
+                }
+            }
+        };
+
+        self.session = session;
+        self.decoy = decoy;
+        self.cache_pub_keys();
+
+        Ok(key)
+    }
+
+    // Slots written before the vault namespace existed sit in the main tree
+    // under the master key, moved over so the isolation covers them too. A
+    // read-only storage keeps reading them from the main tree.
+    fn migrate_vault(&self) -> Result<(), WalletErrors> {
+        if self.storage.is_read_only() {
+            return Ok(());
+        }
+
+        let accounts = self
+            .data
+            .accounts
+            .iter()
+            .filter_map(|account| match account.account_type {
+                AccountType::PrivateKey(slot) => Some(slot),
+                _ => None,
+            });
+        let entropy = match self.data.wallet_type {
+            WalletTypes::SecretPhrase((slot, _)) => Some(slot),
+            _ => None,
+        };
+
+        for slot in accounts.chain(entropy).chain([self.data.proof_key]) {
+            let key = usize::to_le_bytes(slot);
+            let legacy = match self.storage.get(&key) {
+                Ok(legacy) => legacy,
+                Err(LocalStorageError::StorageDataNotFound) => continue,
+                Err(e) => return Err(WalletErrors::FailToMigrateVault(e)),
+            };
+
+            self.storage
+                .ns_set(VAULT_NS, &key, &legacy)
+                .and_then(|_| self.storage.remove(&key))
+                .map_err(WalletErrors::FailToMigrateVault)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_to_storage(&self) -> Result<(), WalletErrors> {
+        let json_bytes =
+            serde_json::to_vec(&self.data).or(Err(WalletErrors::FailToSerializeWalletData))?;
+        let templates_bytes =
+            serde_json::to_vec(&self.templates).or(Err(WalletErrors::FailToSerializeWalletData))?;
+        let key = self.key()?;
+
+        // before the wallet data, which drops the legacy copy
+        self.storage
+            .ns_set(
+                VAULT_NS,
+                &[key.as_slice(), TEMPLATES_KEY_SUFFIX].concat(),
+                &templates_bytes,
+            )
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+
+        self.storage
+            .set(&key, &json_bytes)
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+
+        let changelog_bytes =
+            serde_json::to_vec(&self.changelog).or(Err(WalletErrors::FailToSerializeChangelog))?;
+
+        let history_bytes =
+            serde_json::to_vec(&self.history).or(Err(WalletErrors::FailToSerializeHistory))?;
+
+        for (name, bytes) in [
+            (UNDO_LOG_KEY_SUFFIX, changelog_bytes),
+            (HISTORY_KEY_SUFFIX, history_bytes),
+        ] {
+            self.storage
+                .ns_set(&key, name, &bytes)
+                .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+            // drop the pre-namespace copy
+            self.storage
+                .remove(&[key.as_slice(), name].concat())
+                .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+        }
+
+        // new accounts and imported keys must survive a crash right after
+        self.storage
+            .flush()
+            .map_err(WalletErrors::FailtoSaveWalletDataToStorage)?;
+
+        Ok(())
+    }
+
+    fn visible_accounts(&self) -> impl Iterator<Item = (usize, &account::Account)> {
+        self.data
+            .accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| {
+                self.decoy
+                    .as_ref()
+                    .is_none_or(|decoy| decoy.accounts.contains(&account.addr))
+            })
+    }
+
+    #[inline]
+    pub fn key(&self) -> Result<[u8; SHA256_SIZE], WalletErrors> {
+        hex::decode(&self.data.wallet_address)
+            .or(Err(WalletErrors::InvalidWalletAddressHex))?
+            .try_into()
+            .or(Err(WalletErrors::InvalidWalletAddressSize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::panic;
+    use std::rc::Rc;
+
+    use bincode::ToBytes;
+    use bip39::Mnemonic;
+    use cipher::{argon2::derive_key, keychain::KeyChain};
+    use config::{
+        cipher::PROOF_SIZE, sha::SHA256_SIZE, storage::VAULT_NS, wallet::UNDO_LOG_CAPACITY,
+    };
+    use crypto::bip49::Bip49DerivationPath;
+    use proto::keypair::KeyPair;
+    use session::Session;
+    use storage::LocalStorage;
+    use zil_errors::{session::SessionErrors, storage::LocalStorageError, wallet::WalletErrors};
+
+    use crate::{
+        account::{self, AccountColor},
+        changelog::OpKind,
+        contract_template::{ContractTemplate, TransitionSig},
+        history::{HistoryRecord, TxStatus},
+        safe_storage_save, vault_get,
+        wallet_types::WalletTypes,
+        Wallet, WalletConfig,
+    };
+
+    const MNEMONIC_STR: &str =
+        "green process gate doctor slide whip priority shrug diamond crumble average help";
+    const PASSWORD: &[u8] = b"Test_password";
+    const PASSPHRASE: &str = "";
+
+    #[test]
+    fn test_init_from_bip39_zil() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let (session, key) = Session::unlock(&argon_seed).unwrap();
+        let storage = LocalStorage::new(
+            "com.test_write_wallet",
+            "WriteTest Wallet Corp",
+            "WalletWriteTest App",
+        )
+        .unwrap();
+        let storage = Rc::new(storage);
+        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
+        let mnemonic =
+            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
+        let indexes = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+            .map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain,
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let wallet =
+            Wallet::from_bip39_words(&proof, &mnemonic, PASSPHRASE, &indexes, wallet_config)
+                .unwrap();
+
+        wallet.save_to_storage().unwrap();
+
+        match wallet.data.wallet_type {
+            WalletTypes::SecretPhrase((_, is_phr)) => {
+                assert!(!is_phr);
+            }
+            _ => panic!("invalid type"),
+        }
+
+        assert_eq!(wallet.data.accounts.len(), indexes.len());
+
+        let wallet_addr: [u8; SHA256_SIZE] = hex::decode(wallet.data.wallet_address.clone())
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        drop(wallet);
+
+        let (session, new_key) = Session::unlock(&argon_seed).unwrap();
+        let res_wallet =
+            Wallet::load_from_storage(&wallet_addr, Rc::clone(&storage), session).unwrap();
+
+        assert!(res_wallet.reveal_mnemonic(&key).is_err());
+        assert!(res_wallet.reveal_mnemonic(&new_key).is_ok());
+    }
+
+    #[test]
+    fn test_xpub_watch_only() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let (session, key) = Session::unlock(&argon_seed).unwrap();
+        let storage = LocalStorage::new(
+            "com.test_xpub_wallet",
+            "XpubTest Wallet Corp",
+            "WalletXpubTest App",
+        )
+        .unwrap();
+        let storage = Rc::new(storage);
+        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
+        let mnemonic =
+            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
+        let indexes = [0, 1].map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain,
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let mut wallet =
+            Wallet::from_bip39_words(&proof, &mnemonic, PASSPHRASE, &indexes, wallet_config)
+                .unwrap();
+        let xpub = wallet.export_xpub(&key, None).unwrap();
+
+        assert!(xpub.starts_with("xpub"));
+        assert_eq!(
+            wallet.add_watch_only_account(&xpub, "watch 0".to_string()),
+            Ok(0)
+        );
+        assert_eq!(
+            wallet.add_watch_only_account(&xpub, "watch 1".to_string()),
+            Ok(1)
+        );
+        assert_eq!(wallet.data.accounts[2].addr, wallet.data.accounts[0].addr);
+        assert_eq!(wallet.data.accounts[3].addr, wallet.data.accounts[1].addr);
+        assert_eq!(
+            wallet.data.accounts[3].account_type,
+            crate::account_type::AccountType::WatchOnly(1)
+        );
+        assert_eq!(
+            wallet.add_watch_only_account("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8", String::new()),
+            Err(WalletErrors::XpubMismatch)
+        );
+    }
+
+    #[test]
+    fn test_archive_accounts() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let (session, key) = Session::unlock(&argon_seed).unwrap();
+        let storage = LocalStorage::new(
+            "com.test_archive_wallet",
+            "ArchiveTest Wallet Corp",
+            "WalletArchiveTest App",
+        )
+        .unwrap();
+        let storage = Rc::new(storage);
+        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
+        let mnemonic =
+            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
+        let indexes = [0, 1, 2].map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain,
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let mut wallet =
+            Wallet::from_bip39_words(&proof, &mnemonic, PASSPHRASE, &indexes, wallet_config)
+                .unwrap();
+
+        assert_eq!(
+            wallet.archive_account(0),
+            Err(WalletErrors::CannotArchiveSelectedAccount(0))
+        );
+        assert_eq!(
+            wallet.archive_account(3),
+            Err(WalletErrors::FailToGetAccount(3))
+        );
+
+        wallet.archive_account(1).unwrap();
+
+        let active: Vec<usize> = wallet.accounts().map(|(i, _)| i).collect();
+        let archived: Vec<usize> = wallet.archived_accounts().map(|(i, _)| i).collect();
+
+        assert_eq!(active, vec![0, 2]);
+        assert_eq!(archived, vec![1]);
+        // archived accounts keep their keys
+        assert!(wallet.reveal_keypair(1, &key, None).is_ok());
+
+        wallet.save_to_storage().unwrap();
+
+        let wallet_addr = wallet.key().unwrap();
+        let (session, _) = Session::unlock(&argon_seed).unwrap();
+        let mut restored =
+            Wallet::load_from_storage(&wallet_addr, Rc::clone(&storage), session).unwrap();
+
+        assert_eq!(restored.archived_accounts().count(), 1);
+
+        restored.restore_account(1).unwrap();
+
+        assert_eq!(restored.accounts().count(), 3);
+        assert_eq!(restored.archived_accounts().count(), 0);
+    }
+
+    #[test]
+    fn test_account_tags() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let (session, _key) = Session::unlock(&argon_seed).unwrap();
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
+        let mnemonic =
+            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
+        let indexes = [0, 1, 2].map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain,
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let mut wallet =
+            Wallet::from_bip39_words(&proof, &mnemonic, PASSPHRASE, &indexes, wallet_config)
+                .unwrap();
+
+        for (index, balance) in [(0, 5u8), (1, 7), (2, 11)] {
+            wallet.data.accounts[index]
+                .ft_map
+                .insert("zlp".to_string(), balance.into());
+        }
+
+        wallet.tag_account(0, "Savings").unwrap();
+        wallet.tag_account(1, "savings").unwrap();
+        wallet.tag_account(1, "Trading").unwrap();
+        wallet.tag_account(2, "Savings").unwrap();
+        wallet
+            .set_account_color(1, Some(AccountColor::Green))
+            .unwrap();
+        wallet.archive_account(2).unwrap();
+
+        assert_eq!(
+            wallet.tag_account(3, "DAO"),
+            Err(WalletErrors::FailToGetAccount(3))
+        );
+        assert_eq!(
+            wallet.account_tags(),
+            vec!["Savings".to_string(), "Trading".to_string()]
+        );
+        assert_eq!(
+            wallet
+                .accounts_by_tag("SAVINGS")
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(wallet.accounts_by_color(AccountColor::Green).count(), 1);
+        // archived accounts are not part of the total
+        assert_eq!(wallet.tag_balances("Savings")["zlp"], 12u8.into());
+
+        assert!(wallet.untag_account(1, "Savings").unwrap());
+        assert_eq!(wallet.tag_balances("Savings")["zlp"], 5u8.into());
+        assert!(wallet.tag_balances("DAO").is_empty());
+    }
+
+    #[test]
+    fn test_undo_remove_account() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let (session, _key) = Session::unlock(&argon_seed).unwrap();
+        let storage = LocalStorage::new(
+            "com.test_undo_wallet",
+            "UndoTest Wallet Corp",
+            "WalletUndoTest App",
+        )
+        .unwrap();
+        let storage = Rc::new(storage);
+        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
+        let mnemonic =
+            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
+        let indexes = [0, 1, 2].map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain,
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let mut wallet =
+            Wallet::from_bip39_words(&proof, &mnemonic, PASSPHRASE, &indexes, wallet_config)
+                .unwrap();
+        let removed_addr = wallet.data.accounts[1].addr.clone();
+
+        wallet.data.selected_account = 2;
+        wallet
+            .data
+            .accounts
+            .get_mut(2)
+            .unwrap()
+            .ft_map
+            .insert("zlp".to_string(), 7u8.into());
+
+        assert_eq!(
+            wallet.remove_account(2),
+            Err(WalletErrors::CannotRemoveSelectedAccount(2))
+        );
+
+        wallet.remove_account(1).unwrap();
+        wallet.remove_token(1, "zlp").unwrap();
+
+        assert_eq!(wallet.data.accounts.len(), 2);
+        assert_eq!(wallet.data.selected_account, 1);
+        assert!(wallet.data.accounts[1].ft_map.is_empty());
+
+        wallet.save_to_storage().unwrap();
+
+        let (session, _) = Session::unlock(&argon_seed).unwrap();
+        let mut wallet =
+            Wallet::load_from_storage(&wallet.key().unwrap(), Rc::clone(&storage), session)
+                .unwrap();
+
+        wallet.undo_last(OpKind::DeleteAccount).unwrap();
+
+        assert_eq!(wallet.data.accounts.len(), 3);
+        assert_eq!(wallet.data.accounts[1].addr, removed_addr);
+        assert_eq!(wallet.data.selected_account, 2);
+        assert!(wallet.data.accounts[2].ft_map.is_empty());
+
+        wallet.undo_last(OpKind::RemoveToken).unwrap();
+
+        assert_eq!(wallet.data.accounts[2].ft_map.len(), 1);
+        assert_eq!(
+            wallet.undo_last(OpKind::RemoveToken),
+            Err(WalletErrors::NothingToUndo)
+        );
+
+        let record = |nonce: u64| HistoryRecord {
+            hash: None,
+            sender: removed_addr.clone(),
+            nonce,
+            status: TxStatus::Pending,
+            amount: None,
+            block: None,
+            memo: None,
+            origin: None,
+            timestamp: 0,
+            fiat: None,
+            gas: None,
+            payload: None,
+        };
+
+        wallet.history.add_intent(record(1));
+        wallet.wipe_history().unwrap();
+        wallet.history.add_intent(record(2));
+        wallet.undo_last(OpKind::WipeHistory).unwrap();
+
+        assert_eq!(wallet.history.records(), [record(1), record(2)]);
+    }
+
+    #[test]
+    fn test_pub_key_cache_after_removal() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let (session, _key) = Session::unlock(&argon_seed).unwrap();
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let mnemonic =
+            Mnemonic::parse_in_normalized(bip39::Language::English, MNEMONIC_STR).unwrap();
+        let indexes = [0, 1, 2].map(|i| (Bip49DerivationPath::Zilliqa(i), format!("account {i}")));
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain: KeyChain::from_seed(&argon_seed).unwrap(),
+            storage,
+            settings: Default::default(),
+        };
+        let mut wallet =
+            Wallet::from_bip39_words(&proof, &mnemonic, PASSPHRASE, &indexes, wallet_config)
+                .unwrap();
+        let pub_key = |wallet: &Wallet, i: usize| wallet.data.accounts[i].pub_key.as_ref().to_vec();
+        let first = pub_key(&wallet, 0);
+
+        wallet.data.selected_account = 2;
+        wallet.remove_account(0).unwrap();
+
+        assert_eq!(wallet.pub_key(0), Some(pub_key(&wallet, 0).as_slice()));
+        assert_ne!(wallet.pub_key(0), Some(first.as_slice()));
+        assert_eq!(wallet.pub_key(2), None);
+
+        wallet.undo_last(OpKind::DeleteAccount).unwrap();
+
+        assert_eq!(wallet.pub_key(0), Some(first.as_slice()));
+        assert_eq!(wallet.pub_key(2), Some(pub_key(&wallet, 2).as_slice()));
+    }
+
+    #[test]
+    fn test_evicted_account_slot_deleted() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let (session, _key) = Session::unlock(&argon_seed).unwrap();
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let wallet_config = WalletConfig {
+            session,
+            keychain: KeyChain::from_seed(&argon_seed).unwrap(),
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let sk = KeyPair::gen_keccak256().unwrap().get_secretkey().unwrap();
+        let mut wallet = Wallet::from_sk(&sk, "first".to_string(), &proof, wallet_config).unwrap();
+        let other = KeyPair::gen_keccak256().unwrap().get_secretkey().unwrap();
+        let slot = safe_storage_save(b"sealed", Rc::clone(&storage)).unwrap();
+
+        wallet
+            .data
+            .accounts
+            .push(account::Account::from_secret_key(&other, "second".to_string(), slot).unwrap());
+        wallet.remove_account(1).unwrap();
+
+        // kept while the removal can still be undone
+        for _ in 1..UNDO_LOG_CAPACITY {
+            wallet.wipe_history().unwrap();
+        }
+
+        assert_eq!(vault_get(&storage, slot).unwrap(), b"sealed");
+
+        wallet.wipe_history().unwrap();
+
+        assert_eq!(
+            vault_get(&storage, slot),
+            Err(LocalStorageError::StorageDataNotFound)
+        );
+        assert_eq!(
+            wallet.undo_last(OpKind::DeleteAccount),
+            Err(WalletErrors::NothingToUndo)
+        );
+        // the remaining account keeps its key
+        assert!(vault_get(&storage, wallet.data.accounts[0].account_type.value()).is_ok());
+    }
+
+    #[test]
+    fn test_init_from_sk() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let (session, key) = Session::unlock(&argon_seed).unwrap();
+        let storage = LocalStorage::new(
+            "com.test_write_wallet_sk",
+            "WriteTest Wallet_sk Corp",
+            "WalletWriteTest App_sk",
+        )
+        .unwrap();
+        let storage = Rc::new(storage);
+        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
+        let keypair = KeyPair::gen_keccak256().unwrap();
+        let sk = keypair.get_secretkey().unwrap();
+        let name = "SK Account 0";
+        let wallet_config = WalletConfig {
+            session,
+            keychain,
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let mut wallet = Wallet::from_sk(&sk, name.to_string(), &proof, wallet_config).unwrap();
+
+        assert_eq!(wallet.data.accounts.len(), 1);
+        assert_eq!(
+            wallet.pub_key(0),
+            Some(keypair.get_pubkey().unwrap().as_ref())
+        );
+        // signing needs the spend elevation, revealing only the session
+        assert_eq!(
+            wallet.sign_message(b"hi", 0, &key, None),
+            Err(WalletErrors::SessionDecryptKeychainError(
+                SessionErrors::SpendNotElevated
+            ))
+        );
+        wallet.elevate(&key).unwrap();
+        assert!(wallet.sign_message(b"hi", 0, &key, None).is_ok());
+        assert_eq!(
+            wallet.reveal_mnemonic(&key),
+            Err(WalletErrors::InvalidAccountType)
+        );
+        assert!(wallet.capabilities().staking);
+        assert!(!wallet.capabilities().evm);
+
+        let wallet_addr: [u8; SHA256_SIZE] = hex::decode(wallet.data.wallet_address.clone())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        wallet.save_to_storage().unwrap();
+
+        let (session, _key) = Session::unlock(&argon_seed).unwrap();
+        let w = Wallet::load_from_storage(&wallet_addr, Rc::clone(&storage), session).unwrap();
+
+        assert_eq!(w.data, wallet.data);
+    }
+
+    #[test]
+    fn test_legacy_vault() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let (session, _) = Session::unlock(&argon_seed).unwrap();
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let keypair = KeyPair::gen_sha256().unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain: KeyChain::from_seed(&argon_seed).unwrap(),
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let wallet = Wallet::from_sk(
+            &keypair.get_secretkey().unwrap(),
+            "SK Account 0".to_string(),
+            &proof,
+            wallet_config,
+        )
+        .unwrap();
+        let slots = [
+            usize::to_le_bytes(wallet.data.proof_key),
+            usize::to_le_bytes(wallet.data.accounts[0].account_type.value()),
+        ];
+
+        wallet.save_to_storage().unwrap();
+
+        // as written before the vault namespace
+        for slot in &slots {
+            storage
+                .set(slot, &storage.ns_get(VAULT_NS, slot).unwrap())
+                .unwrap();
+            storage.ns_remove(VAULT_NS, slot).unwrap();
+        }
+
+        let key = wallet.key().unwrap();
+        let mut wallet =
+            Wallet::load_from_storage(&key, Rc::clone(&storage), Session::default()).unwrap();
+        let cipher_key = wallet.unlock(PASSWORD).unwrap();
+
+        assert_eq!(
+            wallet.reveal_keypair(0, &cipher_key, None).unwrap(),
+            keypair
+        );
+
+        for slot in &slots {
+            assert!(storage.ns_get(VAULT_NS, slot).is_ok());
+            assert_eq!(
+                storage.get(slot),
+                Err(LocalStorageError::StorageDataNotFound)
+            );
+        }
+    }
+
+    #[test]
+    fn test_escrow_recovery() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let (session, key) = Session::unlock(&argon_seed).unwrap();
+        let storage = LocalStorage::new(
+            "com.test_escrow_wallet",
+            "EscrowTest Wallet Corp",
+            "WalletEscrowTest App",
+        )
+        .unwrap();
+        let storage = Rc::new(storage);
+        let keychain = KeyChain::from_seed(&argon_seed).unwrap();
+        let sk = KeyPair::gen_sha256().unwrap().get_secretkey().unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain,
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let wallet = Wallet::from_sk(&sk, "SK".to_string(), &proof, wallet_config).unwrap();
+        let org = KeyPair::gen_sha256().unwrap();
+        let org_pk: [u8; 33] = org.get_pubkey().unwrap().as_ref().try_into().unwrap();
+        let org_sk: [u8; 32] = org.get_secretkey().unwrap().as_ref().try_into().unwrap();
+
+        wallet.enable_escrow(&key, &org_pk).unwrap();
+
+        let blob = wallet.escrow_blob().unwrap();
+        let recovered = Wallet::recover_from_escrow(&blob, &org_sk).unwrap();
+        let cipher_sk = vault_get(&storage, wallet.data.accounts[0].account_type.value()).unwrap();
+        let sk_bytes = recovered
+            .decrypt(cipher_sk, &wallet.data.settings.crypto.cipher_orders)
+            .unwrap();
+
+        assert_eq!(sk_bytes, sk.to_bytes().unwrap().to_vec());
+
+        let wrong = KeyPair::gen_sha256().unwrap();
+        let wrong_sk: [u8; 32] = wrong.get_secretkey().unwrap().as_ref().try_into().unwrap();
+
+        assert!(Wallet::recover_from_escrow(&blob, &wrong_sk).is_err());
+    }
+
+    #[test]
+    fn test_templates_in_vault() {
+        let argon_seed = derive_key(PASSWORD).unwrap();
+        let proof = derive_key(&argon_seed[..PROOF_SIZE]).unwrap();
+        let (session, _key) = Session::unlock(&argon_seed).unwrap();
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let sk = KeyPair::gen_sha256().unwrap().get_secretkey().unwrap();
+        let wallet_config = WalletConfig {
+            session,
+            keychain: KeyChain::from_seed(&argon_seed).unwrap(),
+            storage: Rc::clone(&storage),
+            settings: Default::default(),
+        };
+        let wallet = Wallet::from_sk(&sk, "SK".to_string(), &proof, wallet_config).unwrap();
+        let template = ContractTemplate {
+            name: "claim".to_string(),
+            contract: wallet.data.accounts[0].addr.clone(),
+            transition: "Claim".to_string(),
+            args: vec![],
+        };
+        let sig = TransitionSig {
+            name: "Claim".to_string(),
+            params: vec![],
+        };
+        let key = wallet.key().unwrap();
+
+        // written by a version that kept templates in the wallet data
+        let mut legacy = serde_json::to_value(&wallet.data).unwrap();
+        legacy["templates"] = serde_json::to_value([&template]).unwrap();
+        storage
+            .set(&key, &serde_json::to_vec(&legacy).unwrap())
+            .unwrap();
+
+        let (session, _) = Session::unlock(&argon_seed).unwrap();
+        let wallet = Wallet::load_from_storage(&key, Rc::clone(&storage), session).unwrap();
+
+        assert_eq!(wallet.template("claim"), Some(&template));
+
+        wallet.save_to_storage().unwrap();
+
+        assert!(!String::from_utf8(storage.get(&key).unwrap())
+            .unwrap()
+            .contains("Claim"));
+
+        let (session, _) = Session::unlock(&argon_seed).unwrap();
+        let mut wallet = Wallet::load_from_storage(&key, Rc::clone(&storage), session).unwrap();
+
+        assert_eq!(wallet.template("claim"), Some(&template));
+        assert!(wallet.add_template(template, &[sig]).is_err());
     }
 }
@@ -0,0 +1,256 @@
+use config::contracts::{ARWEAVE_GATEWAY, IPFS_GATEWAY};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use zil_errors::nft::NftErrors;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NftAttribute {
+    pub trait_type: Option<String>,
+    pub value: Value,
+}
+
+/// ZRC-6 / ERC-721 token URI metadata. Parsing is tolerant: unknown fields
+/// are ignored and fields of the wrong type are dropped instead of failing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NftMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>, // sanitized, always https
+    pub attributes: Vec<NftAttribute>,
+}
+
+impl NftMetadata {
+    pub fn parse(bytes: &[u8]) -> Result<Self, NftErrors> {
+        let value: Value =
+            serde_json::from_slice(bytes).map_err(|e| NftErrors::InvalidJson(e.to_string()))?;
+        let obj = value.as_object().ok_or(NftErrors::NotAnObject)?;
+
+        Ok(Self {
+            name: get_str(obj, &["name", "title"]),
+            description: get_str(obj, &["description"]),
+            image: get_str(obj, &["image", "image_url", "image_uri"])
+                .and_then(|url| sanitize_url(&url)),
+            attributes: obj
+                .get("attributes")
+                .or_else(|| obj.get("traits"))
+                .map(parse_attributes)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+fn get_str(obj: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| obj.get(*k))
+        .filter_map(|v| v.as_str())
+        .map(|s| s.trim())
+        .find(|s| !s.is_empty())
+        .map(String::from)
+}
+
+fn parse_attributes(value: &Value) -> Vec<NftAttribute> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::Object(obj) => Some(NftAttribute {
+                    trait_type: get_str(obj, &["trait_type", "type", "key"]),
+                    value: obj.get("value").cloned().unwrap_or(Value::Null),
+                }),
+                Value::String(_) | Value::Number(_) | Value::Bool(_) => Some(NftAttribute {
+                    trait_type: None,
+                    value: item.clone(),
+                }),
+                _ => None,
+            })
+            .collect(),
+        // some collections use a plain {"trait": "value"} map
+        Value::Object(obj) => obj
+            .iter()
+            .map(|(k, v)| NftAttribute {
+                trait_type: Some(k.clone()),
+                value: v.clone(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Rewrites ipfs:// and ar:// to https gateways; anything that isn't https
+/// (javascript:, data:, plain http, ...) is dropped.
+pub fn sanitize_url(url: &str) -> Option<String> {
+    let url = url.trim();
+
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return None;
+    }
+
+    if let Some(path) = strip_prefix_ci(url, "ipfs://") {
+        let path = path.strip_prefix("ipfs/").unwrap_or(path);
+
+        return (!path.is_empty()).then(|| format!("{}{}", IPFS_GATEWAY, path));
+    }
+
+    if let Some(path) = strip_prefix_ci(url, "ar://") {
+        return (!path.is_empty()).then(|| format!("{}{}", ARWEAVE_GATEWAY, path));
+    }
+
+    if strip_prefix_ci(url, "https://").is_some_and(|rest| !rest.is_empty()) {
+        return Some(url.to_string());
+    }
+
+    None
+}
+
+fn strip_prefix_ci<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = value.get(..prefix.len())?;
+
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &value[prefix.len()..])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct NftKey {
+    contract: String,
+    token_id: String,
+}
+
+#[derive(Debug, Default)]
+pub struct NftMetadataCache {
+    entries: HashMap<NftKey, NftMetadata>,
+}
+
+impl NftMetadataCache {
+    pub fn get(&self, contract: &str, token_id: &str) -> Option<&NftMetadata> {
+        self.entries.get(&key(contract, token_id))
+    }
+
+    // Parses only on a cache miss.
+    pub fn get_or_parse(
+        &mut self,
+        contract: &str,
+        token_id: &str,
+        raw: &[u8],
+    ) -> Result<&NftMetadata, NftErrors> {
+        let key = key(contract, token_id);
+
+        if !self.entries.contains_key(&key) {
+            let metadata = NftMetadata::parse(raw)?;
+
+            self.entries.insert(key.clone(), metadata);
+        }
+
+        Ok(&self.entries[&key])
+    }
+
+    pub fn invalidate(&mut self, contract: &str, token_id: &str) -> Option<NftMetadata> {
+        self.entries.remove(&key(contract, token_id))
+    }
+
+    pub fn invalidate_contract(&mut self, contract: &str) {
+        let contract = contract.to_lowercase();
+
+        self.entries.retain(|k, _| k.contract != contract);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn key(contract: &str, token_id: &str) -> NftKey {
+    NftKey {
+        contract: contract.to_lowercase(),
+        token_id: token_id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_tolerant() {
+        let raw = json!({
+            "name": "Bear #1",
+            "description": 42,
+            "image": "ipfs://ipfs/QmHash/1.png",
+            "attributes": [
+                { "trait_type": "Fur", "value": "Brown" },
+                { "value": 7 },
+                "legendary",
+                null
+            ],
+            "extra": { "ignored": true }
+        })
+        .to_string();
+        let metadata = NftMetadata::parse(raw.as_bytes()).unwrap();
+
+        assert_eq!(metadata.name.as_deref(), Some("Bear #1"));
+        assert_eq!(metadata.description, None);
+        assert_eq!(
+            metadata.image.as_deref(),
+            Some("https://ipfs.io/ipfs/QmHash/1.png")
+        );
+        assert_eq!(metadata.attributes.len(), 3);
+        assert_eq!(metadata.attributes[0].trait_type.as_deref(), Some("Fur"));
+
+        let map_attrs = json!({ "attributes": { "Eyes": "Blue" } }).to_string();
+        let metadata = NftMetadata::parse(map_attrs.as_bytes()).unwrap();
+
+        assert_eq!(metadata.attributes[0].trait_type.as_deref(), Some("Eyes"));
+        assert_eq!(NftMetadata::parse(b"[]"), Err(NftErrors::NotAnObject));
+        assert!(NftMetadata::parse(b"{").is_err());
+    }
+
+    #[test]
+    fn test_sanitize_url() {
+        assert_eq!(
+            sanitize_url("ar://tx_id").as_deref(),
+            Some("https://arweave.net/tx_id")
+        );
+        assert_eq!(
+            sanitize_url(" https://cdn.example/a.png ").as_deref(),
+            Some("https://cdn.example/a.png")
+        );
+        assert_eq!(sanitize_url("javascript:alert(1)"), None);
+        assert_eq!(sanitize_url("data:text/html;base64,AAAA"), None);
+        assert_eq!(sanitize_url("http://plain.example/a.png"), None);
+        assert_eq!(sanitize_url("https://a.example/\nb"), None);
+        assert_eq!(sanitize_url("ipfs://"), None);
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut cache = NftMetadataCache::default();
+        let raw = br#"{"name":"first"}"#;
+
+        cache.get_or_parse("0xABC", "1", raw).unwrap();
+        // cached entry wins over new raw data until invalidated
+        let cached = cache
+            .get_or_parse("0xabc", "1", br#"{"name":"second"}"#)
+            .unwrap();
+
+        assert_eq!(cached.name.as_deref(), Some("first"));
+
+        cache.get_or_parse("0xabc", "2", raw).unwrap();
+        cache.get_or_parse("0xdef", "1", raw).unwrap();
+        assert_eq!(cache.len(), 3);
+
+        assert!(cache.invalidate("0xabc", "1").is_some());
+        assert!(cache.get("0xabc", "1").is_none());
+
+        cache.invalidate_contract("0xABC");
+        assert_eq!(cache.len(), 1);
+    }
+}
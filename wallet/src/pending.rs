@@ -0,0 +1,232 @@
+use crate::{account::Account, Wallet};
+use num256::uint256::Uint256;
+use serde::{Deserialize, Serialize};
+use zil_errors::wallet::WalletErrors;
+
+/// Expected effect of a broadcast tx on one cached balance, e.g. a ZRC2
+/// transfer debits the token by `amount` and ZIL by `max_fee`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingDelta {
+    pub tx_hash: String,
+    pub token: String, // key of `ft_map`
+    pub amount: Uint256,
+    pub max_fee: Uint256, // gas limit * gas price, zero for other tokens
+    pub base: Uint256,    // cached balance when the delta was applied
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxSettlement {
+    Confirmed { fee: Uint256 },
+    Failed { fee: Uint256 }, // mined but reverted, only the gas is spent
+    Dropped,                 // rejected or expired, nothing is spent
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceView {
+    pub amount: Uint256,
+    pub pending: bool,
+}
+
+impl Account {
+    pub fn apply_pending(&mut self, mut delta: PendingDelta) {
+        delta.base = self.ft_map.get(&delta.token).copied().unwrap_or_default();
+        self.pending.push(delta);
+    }
+
+    /// The cached balance minus what in-flight txs will spend at most.
+    /// Once a refresh from the node changed the cached value it already
+    /// includes the tx, the delta only keeps the pending marker until the
+    /// receipt is seen.
+    pub fn balance(&self, token: &str) -> BalanceView {
+        let cached = self.ft_map.get(token).copied().unwrap_or_default();
+        let mut view = BalanceView {
+            amount: cached,
+            pending: false,
+        };
+
+        for delta in self.pending.iter().filter(|d| d.token == token) {
+            if delta.base == cached {
+                view.amount = saturating_sub(view.amount, delta.amount + delta.max_fee);
+            }
+
+            view.pending = true;
+        }
+
+        view
+    }
+
+    /// Drops the deltas of `tx_hash`. Unless the balance was refreshed in
+    /// the meantime, the actual spend is written to the cached balance so
+    /// the view does not jump back until the next poll.
+    pub fn settle(&mut self, tx_hash: &str, settlement: &TxSettlement) -> bool {
+        let (settled, pending): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|delta| delta.tx_hash == tx_hash);
+
+        self.pending = pending;
+
+        for delta in &settled {
+            let spent = match settlement {
+                TxSettlement::Confirmed { fee } => delta.amount + fee_of(delta, fee),
+                TxSettlement::Failed { fee } => fee_of(delta, fee),
+                TxSettlement::Dropped => continue,
+            };
+
+            let Some(balance) = self.ft_map.get_mut(&delta.token) else {
+                continue;
+            };
+
+            if *balance == delta.base {
+                *balance = saturating_sub(*balance, spent);
+
+                // other txs in flight were applied on top of the old value
+                for other in self.pending.iter_mut().filter(|d| d.token == delta.token) {
+                    if other.base == delta.base {
+                        other.base = *balance;
+                    }
+                }
+            }
+        }
+
+        !settled.is_empty()
+    }
+}
+
+impl Wallet {
+    pub fn apply_pending(
+        &mut self,
+        account_index: usize,
+        deltas: Vec<PendingDelta>,
+    ) -> Result<(), WalletErrors> {
+        let account = self.account_mut(account_index)?;
+
+        for delta in deltas {
+            account.apply_pending(delta);
+        }
+
+        Ok(())
+    }
+
+    // Receipts carry the hash only, the sender account is looked up.
+    pub fn settle_pending(&mut self, tx_hash: &str, settlement: &TxSettlement) -> bool {
+        self.data
+            .accounts
+            .iter_mut()
+            .any(|account| account.settle(tx_hash, settlement))
+    }
+}
+
+// The fee is only charged to the delta that reserved it.
+fn fee_of(delta: &PendingDelta, fee: &Uint256) -> Uint256 {
+    if delta.max_fee == Uint256::default() {
+        Uint256::default()
+    } else {
+        *fee
+    }
+}
+
+fn saturating_sub(a: Uint256, b: Uint256) -> Uint256 {
+    if a > b {
+        a - b
+    } else {
+        Uint256::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::secret_key::SecretKey;
+
+    const ZIL: &str = "zil";
+    const ZLP: &str = "zlp";
+
+    fn account() -> Account {
+        let sk = SecretKey::Secp256k1Sha256Zilliqa([1u8; 32]);
+        let mut account = Account::from_secret_key(&sk, "Main".to_string(), 0).unwrap();
+
+        account.ft_map.insert(ZIL.to_string(), 1_000u16.into());
+        account.ft_map.insert(ZLP.to_string(), 50u8.into());
+
+        account
+    }
+
+    fn delta(tx_hash: &str, token: &str, amount: u16, max_fee: u16) -> PendingDelta {
+        PendingDelta {
+            tx_hash: tx_hash.to_string(),
+            token: token.to_string(),
+            amount: amount.into(),
+            max_fee: max_fee.into(),
+            base: Uint256::default(),
+        }
+    }
+
+    #[test]
+    fn test_pending_balance() {
+        let mut account = account();
+
+        account.apply_pending(delta("0x1", ZIL, 100, 20));
+
+        assert_eq!(
+            account.balance(ZIL),
+            BalanceView {
+                amount: 880u16.into(),
+                pending: true
+            }
+        );
+        assert!(!account.balance(ZLP).pending);
+
+        // mined for less gas than reserved
+        assert!(account.settle("0x1", &TxSettlement::Confirmed { fee: 5u8.into() }));
+        assert_eq!(
+            account.balance(ZIL),
+            BalanceView {
+                amount: 895u16.into(),
+                pending: false
+            }
+        );
+        assert!(!account.settle("0x1", &TxSettlement::Dropped));
+
+        account.apply_pending(delta("0x4", ZIL, 100, 20));
+        account.apply_pending(delta("0x5", ZIL, 200, 20));
+        account.settle("0x4", &TxSettlement::Confirmed { fee: 5u8.into() });
+
+        // the second send is still counted against the new balance
+        assert_eq!(account.balance(ZIL).amount, 570u16.into());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut account = account();
+
+        // token transfer: the token pays the amount, ZIL the gas
+        account.apply_pending(delta("0x2", ZLP, 30, 0));
+        account.apply_pending(delta("0x2", ZIL, 0, 20));
+
+        assert_eq!(account.balance(ZLP).amount, 20u8.into());
+        assert_eq!(account.balance(ZIL).amount, 980u16.into());
+
+        account.settle("0x2", &TxSettlement::Failed { fee: 7u8.into() });
+
+        assert_eq!(account.balance(ZLP).amount, 50u8.into());
+        assert_eq!(account.balance(ZIL).amount, 993u16.into());
+
+        account.apply_pending(delta("0x3", ZIL, 500, 20));
+        // a poll already saw the tx, the settlement must not count it twice
+        account.ft_map.insert(ZIL.to_string(), 470u16.into());
+
+        assert_eq!(
+            account.balance(ZIL),
+            BalanceView {
+                amount: 470u16.into(),
+                pending: true
+            }
+        );
+
+        account.settle("0x3", &TxSettlement::Confirmed { fee: 3u8.into() });
+
+        assert_eq!(account.balance(ZIL).amount, 470u16.into());
+        assert!(account.pending.is_empty());
+    }
+}
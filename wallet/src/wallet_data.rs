@@ -10,4 +10,6 @@ pub struct WalletData {
     pub wallet_address: String,
     pub accounts: Vec<Account>,
     pub selected_account: usize,
+    #[serde(default)]
+    pub xpub: Option<String>, // watch-only accounts derive from it
 }
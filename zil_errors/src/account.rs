@@ -1,4 +1,4 @@
-use crate::{address::AddressError, keypair::KeyPairError, LocalStorageError};
+use crate::{address::AddressError, keypair::KeyPairError, xpub::XpubErrors, LocalStorageError};
 use thiserror::Error;
 
 #[derive(Debug, Error, PartialEq, Eq)]
@@ -33,4 +33,8 @@ pub enum AccountErrors {
     FromBytesErrorNotEnoughBytes,
     #[error("Invalide account type value")]
     InvalidAccountTypeValue,
+    #[error("Invalid xpub: {0}")]
+    InvalidXpub(XpubErrors),
+    #[error("Invalid tag: {0:?}")]
+    InvalidTag(String),
 }
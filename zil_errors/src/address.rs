@@ -26,4 +26,6 @@ pub enum AddressError {
     InvalidBech32Len,
     #[error("Not implemented")]
     NotImpl,
+    #[error("Invalid address checksum")]
+    InvalidChecksum,
 }
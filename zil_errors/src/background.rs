@@ -11,6 +11,8 @@ pub enum BackgroundError {
     TryInitLocalStorageError(LocalStorageError),
     #[error("Fail to write db indicators: {0}")]
     FailToWriteIndicatorsWallet(LocalStorageError),
+    #[error("Fail to flush storage: {0}")]
+    FailToFlushStorage(LocalStorageError),
     #[error("Fail to laod wallet from storage: {0}")]
     TryLoadWalletError(WalletErrors),
     #[error("Fail to write selected wallet: {0}")]
@@ -29,4 +31,28 @@ pub enum BackgroundError {
     FailToInitWallet(WalletErrors),
     #[error("Fail to save wallet data: {0}")]
     FailToSaveWallet(WalletErrors),
+    #[error("Fail to load broadcast queue: {0}")]
+    FailToLoadBroadcastQueue(LocalStorageError),
+    #[error("Fail to save broadcast queue: {0}")]
+    FailToSaveBroadcastQueue(LocalStorageError),
+    #[error("Fail to serialize broadcast queue")]
+    FailToSerializeBroadcastQueue,
+    #[error("Fail to deserialize broadcast queue")]
+    FailToDeserializeBroadcastQueue,
+    #[error("Fail to serialize support bundle")]
+    FailToSerializeSupportBundle,
+    #[error("Fail to purge namespace: {0}")]
+    FailToPurgeNamespace(LocalStorageError),
+    #[error("Fail to collect garbage: {0}")]
+    FailToCollectGarbage(LocalStorageError),
+    #[error("Fail to import extension data: {0}")]
+    FailToImportExtension(LocalStorageError),
+    #[error("Fail to build search index: {0}")]
+    FailToBuildSearchIndex(LocalStorageError),
+    #[error("Wallet {0} does not exist")]
+    WalletNotExists(usize),
+    #[error("Fail to update wallet: {0}")]
+    FailToUpdateWallet(WalletErrors),
+    #[error("Fail to save contact: {0}")]
+    FailToSaveContact(LocalStorageError),
 }
@@ -6,6 +6,8 @@ pub enum CipherErrors {
     ArgonKeyDerivingError(String),
     #[error("Invalid enum code")]
     InvalidTypeCode,
+    #[error("HKDF expand error: {0}")]
+    HkdfExpandError(String),
 }
 
 #[derive(Debug, Error, PartialEq, Eq)]
@@ -0,0 +1,590 @@
+use crate::{
+    account::AccountErrors,
+    address::AddressError,
+    background::BackgroundError,
+    cipher::{AesGCMErrors, CipherErrors},
+    config::ConfigErrors,
+    contract_template::ContractTemplateErrors,
+    crypto::{SchorrError, SignatureError},
+    escrow::EscrowErrors,
+    fiat::FiatSendErrors,
+    flow::FlowErrors,
+    gas_price::GasPriceErrors,
+    key_usage::KeyUsageErrors,
+    keychain::KeyChainErrors,
+    keypair::{KeyPairError, PubKeyError, SecretKeyError},
+    lifecycle::LifecycleErrors,
+    mnemonic::MnemonicChallengeErrors,
+    nft::NftErrors,
+    ntru::NTRULPCipherErrors,
+    session::SessionErrors,
+    sign_request::SignRequestErrors,
+    siwz::SiwzErrors,
+    statement::StatementErrors,
+    storage::LocalStorageError,
+    swap::SwapErrors,
+    sync::SyncErrors,
+    timelock::TimeLockErrors,
+    units::{AmountViolation, UnitsErrors},
+    user_op::UserOpErrors,
+    wallet::WalletErrors,
+    xpub::XpubErrors,
+    zrc2::Zrc2Errors,
+    EvmErrors, ZilliqaErrors,
+};
+use ntrulp::{key::kem_error::KemErrors, ntru::std_error::CipherError, rng::RandomErrors};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display};
+
+/// Stable, language independent identity of an error for front-ends: an
+/// explicit code per variant plus its fields by name. A shipped code never
+/// changes, a renamed variant keeps the code it had.
+pub trait ErrorCode {
+    // Code and named fields of the variant.
+    fn describe(&self) -> (&'static str, Vec<(&'static str, String)>);
+
+    fn code(&self) -> &'static str {
+        self.describe().0
+    }
+
+    fn params(&self) -> BTreeMap<String, String> {
+        self.describe()
+            .1
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+}
+
+/// A field as it shows up in `params`, a nested error by its code.
+pub trait ErrorParam {
+    fn param(&self) -> String;
+}
+
+impl<E: ErrorCode> ErrorParam for E {
+    fn param(&self) -> String {
+        self.code().to_string()
+    }
+}
+
+macro_rules! display_params {
+    ($($param:ty),*) => {
+        $(impl ErrorParam for $param {
+            fn param(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+display_params!(String, &str, char, u8, u16, u32, u64, u128, usize);
+
+// ntrulp errors only have Debug.
+macro_rules! debug_params {
+    ($($param:ty),*) => {
+        $(impl ErrorParam for $param {
+            fn param(&self) -> String {
+                format!("{self:?}")
+            }
+        })*
+    };
+}
+
+debug_params!(KemErrors, CipherError, RandomErrors);
+
+/// What is handed over the FFI boundary, `message` is the English text
+/// for logs and as a fallback.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorInfo {
+    pub code: String,
+    pub params: BTreeMap<String, String>,
+    pub message: String,
+}
+
+impl ErrorInfo {
+    pub fn new<E: ErrorCode + Display>(error: &E) -> Self {
+        Self {
+            code: error.code().to_string(),
+            params: error.params(),
+            message: error.to_string(),
+        }
+    }
+}
+
+macro_rules! error_codes {
+    ($($error:ty {
+        $($variant:ident $(($($field:ident),*))? => $code:literal,)*
+    })*) => {
+        $(impl ErrorCode for $error {
+            fn describe(&self) -> (&'static str, Vec<(&'static str, String)>) {
+                match self {
+                    $(Self::$variant $(($($field),*))? => (
+                        $code,
+                        vec![$($((stringify!($field), $field.param())),*)?],
+                    ),)*
+                }
+            }
+        })*
+    };
+}
+
+error_codes! {
+    AccountErrors {
+        InvalidPubKeyType => "E_ACCOUNT_INVALID_PUB_KEY_TYPE",
+        InvalidAccountType(account_type) => "E_ACCOUNT_INVALID_ACCOUNT_TYPE",
+        FailToDeserialize => "E_ACCOUNT_FAIL_TO_DESERIALIZE",
+        FailToSerialize => "E_ACCOUNT_FAIL_TO_SERIALIZE",
+        InvalidSecretKeyBytes(source) => "E_ACCOUNT_INVALID_SECRET_KEY_BYTES",
+        InvalidSecretKey(source) => "E_ACCOUNT_INVALID_SECRET_KEY",
+        InvalidPubKey(source) => "E_ACCOUNT_INVALID_PUB_KEY",
+        InvalidAddress(source) => "E_ACCOUNT_INVALID_ADDRESS",
+        AddrFromPubKeyError(source) => "E_ACCOUNT_ADDR_FROM_PUB_KEY_ERROR",
+        FailToSaveCipher(source) => "E_ACCOUNT_FAIL_TO_SAVE_CIPHER",
+        InvalidSeed(source) => "E_ACCOUNT_INVALID_SEED",
+        InvalidSecretBytes => "E_ACCOUNT_INVALID_SECRET_BYTES",
+        InvalidAccountTypeCode => "E_ACCOUNT_INVALID_ACCOUNT_TYPE_CODE",
+        FromBytesErrorNotEnoughBytes => "E_ACCOUNT_FROM_BYTES_ERROR_NOT_ENOUGH_BYTES",
+        InvalidAccountTypeValue => "E_ACCOUNT_INVALID_ACCOUNT_TYPE_VALUE",
+        InvalidXpub(source) => "E_ACCOUNT_INVALID_XPUB",
+        InvalidTag(tag) => "E_ACCOUNT_INVALID_TAG",
+    }
+    AddressError {
+        InvalidHex => "E_ADDRESS_INVALID_HEX",
+        InvalidLength => "E_ADDRESS_INVALID_LENGTH",
+        InvalidKeyType => "E_ADDRESS_INVALID_KEY_TYPE",
+        InvalidPubKey => "E_ADDRESS_INVALID_PUB_KEY",
+        InvalidSecp256k1Sha256Type => "E_ADDRESS_INVALID_SECP256K1_SHA256_TYPE",
+        InvalidAddressBytesForBech32 => "E_ADDRESS_INVALID_ADDRESS_BYTES_FOR_BECH32",
+        InvalidBase16Address => "E_ADDRESS_INVALID_BASE16_ADDRESS",
+        InvalidVerifyingKey => "E_ADDRESS_INVALID_VERIFYING_KEY",
+        InvalidAddressSize => "E_ADDRESS_INVALID_ADDRESS_SIZE",
+        InvalidHRP => "E_ADDRESS_INVALID_HRP",
+        InvalidBech32Len => "E_ADDRESS_INVALID_BECH32_LEN",
+        NotImpl => "E_ADDRESS_NOT_IMPL",
+        InvalidChecksum => "E_ADDRESS_INVALID_CHECKSUM",
+    }
+    BackgroundError {
+        TryInitLocalStorageError(source) => "E_BACKGROUND_TRY_INIT_LOCAL_STORAGE_ERROR",
+        FailToWriteIndicatorsWallet(source) => "E_BACKGROUND_FAIL_TO_WRITE_INDICATORS_WALLET",
+        FailToFlushStorage(source) => "E_BACKGROUND_FAIL_TO_FLUSH_STORAGE",
+        TryLoadWalletError(source) => "E_BACKGROUND_TRY_LOAD_WALLET_ERROR",
+        FailWriteSelectedWallet(source) => "E_BACKGROUND_FAIL_WRITE_SELECTED_WALLET",
+        ArgonPasswordHashError(source) => "E_BACKGROUND_ARGON_PASSWORD_HASH_ERROR",
+        ArgonCreateProofError(source) => "E_BACKGROUND_ARGON_CREATE_PROOF_ERROR",
+        CreateSessionError(source) => "E_BACKGROUND_CREATE_SESSION_ERROR",
+        FailCreateKeychain(source) => "E_BACKGROUND_FAIL_CREATE_KEYCHAIN",
+        FailParseMnemonicWords(reason) => "E_BACKGROUND_FAIL_PARSE_MNEMONIC_WORDS",
+        FailToInitWallet(source) => "E_BACKGROUND_FAIL_TO_INIT_WALLET",
+        FailToSaveWallet(source) => "E_BACKGROUND_FAIL_TO_SAVE_WALLET",
+        FailToLoadBroadcastQueue(source) => "E_BACKGROUND_FAIL_TO_LOAD_BROADCAST_QUEUE",
+        FailToSaveBroadcastQueue(source) => "E_BACKGROUND_FAIL_TO_SAVE_BROADCAST_QUEUE",
+        FailToSerializeBroadcastQueue => "E_BACKGROUND_FAIL_TO_SERIALIZE_BROADCAST_QUEUE",
+        FailToDeserializeBroadcastQueue => "E_BACKGROUND_FAIL_TO_DESERIALIZE_BROADCAST_QUEUE",
+        FailToSerializeSupportBundle => "E_BACKGROUND_FAIL_TO_SERIALIZE_SUPPORT_BUNDLE",
+        FailToPurgeNamespace(source) => "E_BACKGROUND_FAIL_TO_PURGE_NAMESPACE",
+        FailToCollectGarbage(source) => "E_BACKGROUND_FAIL_TO_COLLECT_GARBAGE",
+        FailToImportExtension(source) => "E_BACKGROUND_FAIL_TO_IMPORT_EXTENSION",
+        FailToBuildSearchIndex(source) => "E_BACKGROUND_FAIL_TO_BUILD_SEARCH_INDEX",
+        WalletNotExists(index) => "E_BACKGROUND_WALLET_NOT_EXISTS",
+        FailToUpdateWallet(source) => "E_BACKGROUND_FAIL_TO_UPDATE_WALLET",
+        FailToSaveContact(source) => "E_BACKGROUND_FAIL_TO_SAVE_CONTACT",
+    }
+    CipherErrors {
+        ArgonKeyDerivingError(reason) => "E_CIPHER_ARGON_KEY_DERIVING_ERROR",
+        InvalidTypeCode => "E_CIPHER_INVALID_TYPE_CODE",
+        HkdfExpandError(reason) => "E_CIPHER_HKDF_EXPAND_ERROR",
+    }
+    AesGCMErrors {
+        EncryptError(reason) => "E_AES_ENCRYPT_ERROR",
+        DecryptError(reason) => "E_AES_DECRYPT_ERROR",
+    }
+    ConfigErrors {
+        FailToRead(reason) => "E_CONFIG_FAIL_TO_READ",
+        FailToParse(reason) => "E_CONFIG_FAIL_TO_PARSE",
+        InvalidNetwork(network, reason) => "E_CONFIG_INVALID_NETWORK",
+        InvalidKdf(reason) => "E_CONFIG_INVALID_KDF",
+        NoNetworks => "E_CONFIG_NO_NETWORKS",
+    }
+    ContractTemplateErrors {
+        MissingPlaceholder(placeholder) => "E_TEMPLATE_MISSING_PLACEHOLDER",
+        InvalidValue(param, param_type, reason) => "E_TEMPLATE_INVALID_VALUE",
+        UnknownTransition(transition) => "E_TEMPLATE_UNKNOWN_TRANSITION",
+        ParamsMismatch(reason) => "E_TEMPLATE_PARAMS_MISMATCH",
+        AlreadyExists(name) => "E_TEMPLATE_ALREADY_EXISTS",
+        NotFound(name) => "E_TEMPLATE_NOT_FOUND",
+    }
+    SignatureError {
+        InvalidLength => "E_SIGNATURE_INVALID_LENGTH",
+        FailParseSignature => "E_SIGNATURE_FAIL_PARSE_SIGNATURE",
+        FailIntoPubKey(source) => "E_SIGNATURE_FAIL_INTO_PUB_KEY",
+        FailParseRecover(reason) => "E_SIGNATURE_FAIL_PARSE_RECOVER",
+        EvmDisabled => "E_SIGNATURE_EVM_DISABLED",
+    }
+    SchorrError {
+        InvalidSignTry => "E_SCHNORR_INVALID_SIGN_TRY",
+    }
+    EscrowErrors {
+        InvalidPublicKey => "E_ESCROW_INVALID_PUBLIC_KEY",
+        InvalidSecretKey => "E_ESCROW_INVALID_SECRET_KEY",
+        InvalidBlob => "E_ESCROW_INVALID_BLOB",
+        UnsupportedVersion(version) => "E_ESCROW_UNSUPPORTED_VERSION",
+        EncryptError(source) => "E_ESCROW_ENCRYPT_ERROR",
+        DecryptError(source) => "E_ESCROW_DECRYPT_ERROR",
+    }
+    FiatSendErrors {
+        NoRate(currency) => "E_FIAT_NO_RATE",
+        InvalidAmount(source) => "E_FIAT_INVALID_AMOUNT",
+        ZeroAmount => "E_FIAT_ZERO_AMOUNT",
+        SlippageExceeded(moved_bps, max_bps) => "E_FIAT_SLIPPAGE_EXCEEDED",
+    }
+    FlowErrors {
+        EmptyFlow => "E_FLOW_EMPTY_FLOW",
+        NotSigned(step) => "E_FLOW_NOT_SIGNED",
+        PayloadsMismatch(expected, got) => "E_FLOW_PAYLOADS_MISMATCH",
+        Finished => "E_FLOW_FINISHED",
+        RpcError(reason) => "E_FLOW_RPC_ERROR",
+        GasPrice(source) => "E_FLOW_GAS_PRICE",
+    }
+    GasPriceErrors {
+        TooLow(gas_price, min) => "E_GAS_PRICE_TOO_LOW",
+        TooHigh(gas_price, max) => "E_GAS_PRICE_TOO_HIGH",
+        InvalidMinimum(value) => "E_GAS_PRICE_INVALID_MINIMUM",
+    }
+    KeyUsageErrors {
+        FailToLoad(source) => "E_KEY_USAGE_FAIL_TO_LOAD",
+        FailToSave(source) => "E_KEY_USAGE_FAIL_TO_SAVE",
+        FailToSerialize => "E_KEY_USAGE_FAIL_TO_SERIALIZE",
+        FailToDeserialize => "E_KEY_USAGE_FAIL_TO_DESERIALIZE",
+    }
+    KeyChainErrors {
+        NTRUPrimeCipherError(source) => "E_KEYCHAIN_NTRU_PRIME_CIPHER_ERROR",
+        NTRUPrimePubKeyImportError(source) => "E_KEYCHAIN_NTRU_PRIME_PUB_KEY_IMPORT_ERROR",
+        Argon2CipherErrors(source) => "E_KEYCHAIN_ARGON2_CIPHER_ERRORS",
+        AESKeySliceError => "E_KEYCHAIN_AES_KEY_SLICE_ERROR",
+        AESEncryptError(source) => "E_KEYCHAIN_AES_ENCRYPT_ERROR",
+        NTRUPrimeEncryptError(source) => "E_KEYCHAIN_NTRU_PRIME_ENCRYPT_ERROR",
+        AESDecryptError(source) => "E_KEYCHAIN_AES_DECRYPT_ERROR",
+        NTRUPrimeDecryptError(source) => "E_KEYCHAIN_NTRU_PRIME_DECRYPT_ERROR",
+        FailSlicedProofCipher => "E_KEYCHAIN_FAIL_SLICED_PROOF_CIPHER",
+    }
+    KeyPairError {
+        ExtendedPrivKeyDeriveError => "E_KEYPAIR_EXTENDED_PRIV_KEY_DERIVE_ERROR",
+        SchorrError(source) => "E_KEYPAIR_SCHORR_ERROR",
+        InvalidLength => "E_KEYPAIR_INVALID_LENGTH",
+        InvalidSecretKey => "E_KEYPAIR_INVALID_SECRET_KEY",
+        InvalidEntropy => "E_KEYPAIR_INVALID_ENTROPY",
+        InvalidPublicKey => "E_KEYPAIR_INVALID_PUBLIC_KEY",
+        InvalidKeyType => "E_KEYPAIR_INVALID_KEY_TYPE",
+        AddressParseError(source) => "E_KEYPAIR_ADDRESS_PARSE_ERROR",
+        EthersInvalidSecretKey(reason) => "E_KEYPAIR_ETHERS_INVALID_SECRET_KEY",
+        EthersInvalidSign(reason) => "E_KEYPAIR_ETHERS_INVALID_SIGN",
+        InvalidSignature(source) => "E_KEYPAIR_INVALID_SIGNATURE",
+        EvmDisabled => "E_KEYPAIR_EVM_DISABLED",
+    }
+    SecretKeyError {
+        SecretKeySliceError => "E_SECRET_KEY_SLICE_ERROR",
+        InvalidHex => "E_SECRET_KEY_INVALID_HEX",
+        InvalidLength => "E_SECRET_KEY_INVALID_LENGTH",
+        InvalidKeyType => "E_SECRET_KEY_INVALID_KEY_TYPE",
+    }
+    PubKeyError {
+        InvalidLength => "E_PUB_KEY_INVALID_LENGTH",
+        InvalidKeyType => "E_PUB_KEY_INVALID_KEY_TYPE",
+        InvalidHex => "E_PUB_KEY_INVALID_HEX",
+        InvalidVerifyingKey => "E_PUB_KEY_INVALID_VERIFYING_KEY",
+        InvalidPubKey => "E_PUB_KEY_INVALID_PUB_KEY",
+        FailIntoPubKey => "E_PUB_KEY_FAIL_INTO_PUB_KEY",
+        NotImpl => "E_PUB_KEY_NOT_IMPL",
+    }
+    ZilliqaErrors<'_> {
+        Schnorr(reason) => "E_RPC_SCHNORR",
+        BadRequest => "E_RPC_BAD_REQUEST",
+        FailToParseResponse => "E_RPC_FAIL_TO_PARSE_RESPONSE",
+        NetowrkIsDown => "E_RPC_NETOWRK_IS_DOWN",
+        InvalidPayload => "E_RPC_INVALID_PAYLOAD",
+        InvalidRPCReq(reason) => "E_RPC_INVALID_RPC_REQ",
+        InvalidJson(reason) => "E_RPC_INVALID_JSON",
+        TryInitLocalStorageError(source) => "E_RPC_TRY_INIT_LOCAL_STORAGE_ERROR",
+        CacheStorageError(source) => "E_RPC_CACHE_STORAGE_ERROR",
+        DeadlineExceeded(step) => "E_RPC_DEADLINE_EXCEEDED",
+        InvalidTransport(reason) => "E_RPC_INVALID_TRANSPORT",
+        UnsupportedMethod(method) => "E_RPC_UNSUPPORTED_METHOD",
+        VerifierError(reason) => "E_RPC_VERIFIER_ERROR",
+        GasPrice(source) => "E_RPC_GAS_PRICE",
+        TooManyPages(pages) => "E_RPC_TOO_MANY_PAGES",
+    }
+    EvmErrors {
+        InvalidSecretKey(reason) => "E_EVM_INVALID_SECRET_KEY",
+        InvalidSign(reason) => "E_EVM_INVALID_SIGN",
+    }
+    LifecycleErrors {
+        IllegalTransition(from, to) => "E_LIFECYCLE_ILLEGAL_TRANSITION",
+        InvalidState(state, expected) => "E_LIFECYCLE_INVALID_STATE",
+        WalletNotExists(index) => "E_LIFECYCLE_WALLET_NOT_EXISTS",
+        WalletLocked(index) => "E_LIFECYCLE_WALLET_LOCKED",
+        Wallet(source) => "E_LIFECYCLE_WALLET",
+        Background(source) => "E_LIFECYCLE_BACKGROUND",
+        Sync(source) => "E_LIFECYCLE_SYNC",
+    }
+    MnemonicChallengeErrors {
+        TooManyChallenges(asked, words) => "E_MNEMONIC_TOO_MANY_CHALLENGES",
+        WrongWord(position) => "E_MNEMONIC_WRONG_WORD",
+        AnswersMismatch(expected, got) => "E_MNEMONIC_ANSWERS_MISMATCH",
+    }
+    NftErrors {
+        InvalidJson(reason) => "E_NFT_INVALID_JSON",
+        NotAnObject => "E_NFT_NOT_AN_OBJECT",
+    }
+    NTRULPCipherErrors {
+        InvalidSeedPQBytesSize => "E_NTRU_INVALID_SEED_PQ_BYTES_SIZE",
+        FailToInitF(source) => "E_NTRU_FAIL_TO_INIT_F",
+        ComputePubKeyError(source) => "E_NTRU_COMPUTE_PUB_KEY_ERROR",
+        EncryptError(source) => "E_NTRU_ENCRYPT_ERROR",
+        DecryptError(source) => "E_NTRU_DECRYPT_ERROR",
+        PlaintextTooLarge(size, max) => "E_NTRU_PLAINTEXT_TOO_LARGE",
+        InvalidCiphertext => "E_NTRU_INVALID_CIPHERTEXT",
+    }
+    SessionErrors {
+        DeriveKeyError(source) => "E_SESSION_DERIVE_KEY_ERROR",
+        EncryptSessionError(source) => "E_SESSION_ENCRYPT_SESSION_ERROR",
+        DecryptSessionError(source) => "E_SESSION_DECRYPT_SESSION_ERROR",
+        InvalidCipherKeySize => "E_SESSION_INVALID_CIPHER_KEY_SIZE",
+        SessionNotEnabled => "E_SESSION_NOT_ENABLED",
+        InvalidSeed(source) => "E_SESSION_INVALID_SEED",
+        SpendNotElevated => "E_SESSION_SPEND_NOT_ELEVATED",
+        ElevationExpired => "E_SESSION_ELEVATION_EXPIRED",
+    }
+    SignRequestErrors {
+        Expired(expired_at) => "E_SIGN_REQUEST_EXPIRED",
+        ExpiresTooLate(expires_at) => "E_SIGN_REQUEST_EXPIRES_TOO_LATE",
+        Replayed(id) => "E_SIGN_REQUEST_REPLAYED",
+        FailToLoad(source) => "E_SIGN_REQUEST_FAIL_TO_LOAD",
+        FailToSave(source) => "E_SIGN_REQUEST_FAIL_TO_SAVE",
+        FailToSerialize => "E_SIGN_REQUEST_FAIL_TO_SERIALIZE",
+        FailToDeserialize => "E_SIGN_REQUEST_FAIL_TO_DESERIALIZE",
+    }
+    SiwzErrors {
+        MissingField(field) => "E_SIWZ_MISSING_FIELD",
+        InvalidField(field, reason) => "E_SIWZ_INVALID_FIELD",
+        InvalidNonce => "E_SIWZ_INVALID_NONCE",
+        InvalidAddress(source) => "E_SIWZ_INVALID_ADDRESS",
+        DomainMismatch(expected) => "E_SIWZ_DOMAIN_MISMATCH",
+        NonceMismatch => "E_SIWZ_NONCE_MISMATCH",
+        AddressMismatch => "E_SIWZ_ADDRESS_MISMATCH",
+        Expired => "E_SIWZ_EXPIRED",
+        NotYetValid => "E_SIWZ_NOT_YET_VALID",
+        InvalidSignature => "E_SIWZ_INVALID_SIGNATURE",
+        FailToVerify(source) => "E_SIWZ_FAIL_TO_VERIFY",
+    }
+    StatementErrors {
+        NoBalances => "E_STATEMENT_NO_BALANCES",
+        AddressMismatch => "E_STATEMENT_ADDRESS_MISMATCH",
+        InvalidAddress(source) => "E_STATEMENT_INVALID_ADDRESS",
+        InvalidPubKey(source) => "E_STATEMENT_INVALID_PUB_KEY",
+        FailToSign(source) => "E_STATEMENT_FAIL_TO_SIGN",
+        FailToVerify(source) => "E_STATEMENT_FAIL_TO_VERIFY",
+        MalformedSignature => "E_STATEMENT_MALFORMED_SIGNATURE",
+        InvalidSignature => "E_STATEMENT_INVALID_SIGNATURE",
+    }
+    LocalStorageError {
+        StoragePathError => "E_STORAGE_PATH_ERROR",
+        StorageAccessError(reason) => "E_STORAGE_ACCESS_ERROR",
+        FailToloadBytesTree => "E_STORAGE_FAIL_TOLOAD_BYTES_TREE",
+        FailToCreateFile => "E_STORAGE_FAIL_TO_CREATE_FILE",
+        FailToWriteFile => "E_STORAGE_FAIL_TO_WRITE_FILE",
+        FailToReadFile => "E_STORAGE_FAIL_TO_READ_FILE",
+        StorageDataNotFound => "E_STORAGE_DATA_NOT_FOUND",
+        StorageDataBroken => "E_STORAGE_DATA_BROKEN",
+        StorageWriteError => "E_STORAGE_WRITE_ERROR",
+        StorageFlushError(reason) => "E_STORAGE_FLUSH_ERROR",
+        StorageTimeWentBackwards => "E_STORAGE_TIME_WENT_BACKWARDS",
+        PayloadVersionParseError => "E_STORAGE_PAYLOAD_VERSION_PARSE_ERROR",
+        PayloadParseError => "E_STORAGE_PAYLOAD_PARSE_ERROR",
+        InsufficientBytes => "E_STORAGE_INSUFFICIENT_BYTES",
+        PayloadLengthError => "E_STORAGE_PAYLOAD_LENGTH_ERROR",
+        InvalidBytesSizeOverflow => "E_STORAGE_INVALID_BYTES_SIZE_OVERFLOW",
+        StorageWrongPassword => "E_STORAGE_WRONG_PASSWORD",
+        StorageNotEncrypted => "E_STORAGE_NOT_ENCRYPTED",
+        StorageInvalidProfile(name) => "E_STORAGE_INVALID_PROFILE",
+        StorageLockedByAnotherProcess => "E_STORAGE_LOCKED_BY_ANOTHER_PROCESS",
+        StorageReadOnly => "E_STORAGE_READ_ONLY",
+        StorageEncryptError(reason) => "E_STORAGE_ENCRYPT_ERROR",
+        StorageDecryptError(reason) => "E_STORAGE_DECRYPT_ERROR",
+        StorageMigrationMissing(version) => "E_STORAGE_MIGRATION_MISSING",
+        StorageMigrationError(reason) => "E_STORAGE_MIGRATION_ERROR",
+        StorageIndexNotFound(index) => "E_STORAGE_INDEX_NOT_FOUND",
+        StorageExtensionFormat(reason) => "E_STORAGE_EXTENSION_FORMAT",
+        StorageSnapshotVersion(version) => "E_STORAGE_SNAPSHOT_VERSION",
+        StorageSnapshotBroken(reason) => "E_STORAGE_SNAPSHOT_BROKEN",
+        UnknownCodec(codec) => "E_STORAGE_UNKNOWN_CODEC",
+        UnknownHashAlgo(algo) => "E_STORAGE_UNKNOWN_HASH_ALGO",
+        PayloadEncodeError(reason) => "E_STORAGE_PAYLOAD_ENCODE_ERROR",
+        StorageCompressionError(reason) => "E_STORAGE_COMPRESSION_ERROR",
+        StorageAuditError(reason) => "E_STORAGE_AUDIT_ERROR",
+    }
+    SwapErrors {
+        EmptyPool => "E_SWAP_EMPTY_POOL",
+        ZeroAmount => "E_SWAP_ZERO_AMOUNT",
+        InvalidBps(bps) => "E_SWAP_INVALID_BPS",
+        NothingReceived => "E_SWAP_NOTHING_RECEIVED",
+        Overflow => "E_SWAP_OVERFLOW",
+    }
+    SyncErrors {
+        StorageError(source) => "E_SYNC_STORAGE_ERROR",
+        RemoteError(reason) => "E_SYNC_REMOTE_ERROR",
+        EncryptError(source) => "E_SYNC_ENCRYPT_ERROR",
+        DecryptError(source) => "E_SYNC_DECRYPT_ERROR",
+        FailToSerializeMeta => "E_SYNC_FAIL_TO_SERIALIZE_META",
+        FailToDeserializeMeta => "E_SYNC_FAIL_TO_DESERIALIZE_META",
+        InvalidRemoteRecord(value) => "E_SYNC_INVALID_REMOTE_RECORD",
+        HashsumMismatch(key) => "E_SYNC_HASHSUM_MISMATCH",
+    }
+    TimeLockErrors {
+        InvalidExport => "E_TIMELOCK_INVALID_EXPORT",
+        UnsupportedVersion(version) => "E_TIMELOCK_UNSUPPORTED_VERSION",
+        UnlockInPast => "E_TIMELOCK_UNLOCK_IN_PAST",
+        Locked(until) => "E_TIMELOCK_LOCKED",
+        NotaryError(source) => "E_TIMELOCK_NOTARY_ERROR",
+        EncryptError(source) => "E_TIMELOCK_ENCRYPT_ERROR",
+        DecryptError(source) => "E_TIMELOCK_DECRYPT_ERROR",
+    }
+    UnitsErrors {
+        UnknownUnit(unit) => "E_UNITS_UNKNOWN_UNIT",
+        MissingUnit(value) => "E_UNITS_MISSING_UNIT",
+        InvalidNumber(value) => "E_UNITS_INVALID_NUMBER",
+        TooManyDecimals(value, max) => "E_UNITS_TOO_MANY_DECIMALS",
+        Overflow => "E_UNITS_OVERFLOW",
+    }
+    AmountViolation {
+        Empty => "E_AMOUNT_EMPTY",
+        Negative => "E_AMOUNT_NEGATIVE",
+        ExponentNotation => "E_AMOUNT_EXPONENT_NOTATION",
+        InvalidCharacter(character) => "E_AMOUNT_INVALID_CHARACTER",
+        MisplacedSeparator(separator) => "E_AMOUNT_MISPLACED_SEPARATOR",
+        TooManyDecimals(value, max) => "E_AMOUNT_TOO_MANY_DECIMALS",
+        Zero => "E_AMOUNT_ZERO",
+        Overflow => "E_AMOUNT_OVERFLOW",
+        FeeExceedsBalance => "E_AMOUNT_FEE_EXCEEDS_BALANCE",
+        ExceedsBalance(spendable) => "E_AMOUNT_EXCEEDS_BALANCE",
+    }
+    UserOpErrors {
+        InvalidSender => "E_USER_OP_INVALID_SENDER",
+        UnsupportedSignature => "E_USER_OP_UNSUPPORTED_SIGNATURE",
+        SignError(source) => "E_USER_OP_SIGN_ERROR",
+    }
+    WalletErrors {
+        InvalidVerifySig => "E_WALLET_INVALID_VERIFY_SIG",
+        FailVerifySig(source) => "E_WALLET_FAIL_VERIFY_SIG",
+        FailSignMessage(source) => "E_WALLET_FAIL_SIGN_MESSAGE",
+        PassphraseIsNone => "E_WALLET_PASSPHRASE_IS_NONE",
+        FailToCreateKeyPair(source) => "E_WALLET_FAIL_TO_CREATE_KEY_PAIR",
+        InvalidBip49(source) => "E_WALLET_INVALID_BIP49",
+        FailParseSKBytes(source) => "E_WALLET_FAIL_PARSE_SK_BYTES",
+        FailLoadMnemonicFromEntropy(reason) => "E_WALLET_FAIL_LOAD_MNEMONIC_FROM_ENTROPY",
+        FailToGetSKBytes(source) => "E_WALLET_FAIL_TO_GET_SK_BYTES",
+        FailToGetAccount(index) => "E_WALLET_FAIL_TO_GET_ACCOUNT",
+        FailToDeserializeWalletData => "E_WALLET_FAIL_TO_DESERIALIZE_WALLET_DATA",
+        FailToSerializeWalletData => "E_WALLET_FAIL_TO_SERIALIZE_WALLET_DATA",
+        FailtoSaveWalletDataToStorage(source) => "E_WALLET_FAILTO_SAVE_WALLET_DATA_TO_STORAGE",
+        FailToLoadWalletData(source) => "E_WALLET_FAIL_TO_LOAD_WALLET_DATA",
+        InvalidWalletAddressSize => "E_WALLET_INVALID_WALLET_ADDRESS_SIZE",
+        InvalidWalletAddressHex => "E_WALLET_INVALID_WALLET_ADDRESS_HEX",
+        InvalidHexToWalletType => "E_WALLET_INVALID_HEX_TO_WALLET_TYPE",
+        InvalidWalletTypeValue => "E_WALLET_INVALID_WALLET_TYPE_VALUE",
+        UnknownWalletType(code) => "E_WALLET_UNKNOWN_WALLET_TYPE",
+        SessionDecryptKeychainError(source) => "E_WALLET_SESSION_DECRYPT_KEYCHAIN_ERROR",
+        Bip39NotValid(reason) => "E_WALLET_BIP39_NOT_VALID",
+        DecryptKeyChainErrors(source) => "E_WALLET_DECRYPT_KEY_CHAIN_ERRORS",
+        EncryptKeyChainErrors(source) => "E_WALLET_ENCRYPT_KEY_CHAIN_ERRORS",
+        MnemonicError(reason) => "E_WALLET_MNEMONIC_ERROR",
+        ArgonCipherErrors(source) => "E_WALLET_ARGON_CIPHER_ERRORS",
+        InvalidBip39Account => "E_WALLET_INVALID_BIP39_ACCOUNT",
+        InvalidSecretKeyAccount => "E_WALLET_INVALID_SECRET_KEY_ACCOUNT",
+        FailToSaveCipher(source) => "E_WALLET_FAIL_TO_SAVE_CIPHER",
+        FailToGetContent(source) => "E_WALLET_FAIL_TO_GET_CONTENT",
+        TryEncryptSecretKeyError => "E_WALLET_TRY_ENCRYPT_SECRET_KEY_ERROR",
+        InvalidAccountType => "E_WALLET_INVALID_ACCOUNT_TYPE",
+        DisabledSessions => "E_WALLET_DISABLED_SESSIONS",
+        UnlockSessionError => "E_WALLET_UNLOCK_SESSION_ERROR",
+        KeyChainMakeCipherProofError(source) => "E_WALLET_KEY_CHAIN_MAKE_CIPHER_PROOF_ERROR",
+        FailToGetProofFromStorage(source) => "E_WALLET_FAIL_TO_GET_PROOF_FROM_STORAGE",
+        SessionDecryptError => "E_WALLET_SESSION_DECRYPT_ERROR",
+        KeyChainFailToGetProof => "E_WALLET_KEY_CHAIN_FAIL_TO_GET_PROOF",
+        ProofNotMatch => "E_WALLET_PROOF_NOT_MATCH",
+        CannotArchiveSelectedAccount(index) => "E_WALLET_CANNOT_ARCHIVE_SELECTED_ACCOUNT",
+        CannotRemoveSelectedAccount(index) => "E_WALLET_CANNOT_REMOVE_SELECTED_ACCOUNT",
+        FailToGetToken(token) => "E_WALLET_FAIL_TO_GET_TOKEN",
+        NothingToUndo => "E_WALLET_NOTHING_TO_UNDO",
+        FailToSerializeChangelog => "E_WALLET_FAIL_TO_SERIALIZE_CHANGELOG",
+        FailToDeserializeChangelog => "E_WALLET_FAIL_TO_DESERIALIZE_CHANGELOG",
+        FailToSerializeHistory => "E_WALLET_FAIL_TO_SERIALIZE_HISTORY",
+        FailToDeserializeHistory => "E_WALLET_FAIL_TO_DESERIALIZE_HISTORY",
+        ContractTemplateError(source) => "E_WALLET_CONTRACT_TEMPLATE_ERROR",
+        EscrowError(source) => "E_WALLET_ESCROW_ERROR",
+        InvalidEscrowKeychain => "E_WALLET_INVALID_ESCROW_KEYCHAIN",
+        InvalidXpub(source) => "E_WALLET_INVALID_XPUB",
+        XpubMismatch => "E_WALLET_XPUB_MISMATCH",
+        InvalidXpubAccount(source) => "E_WALLET_INVALID_XPUB_ACCOUNT",
+        TimeLockError(source) => "E_WALLET_TIME_LOCK_ERROR",
+        InvalidAccountTag(source) => "E_WALLET_INVALID_ACCOUNT_TAG",
+        FailToSignStatement(source) => "E_WALLET_FAIL_TO_SIGN_STATEMENT",
+        DuressNoAccounts => "E_WALLET_DURESS_NO_ACCOUNTS",
+        DuressPasswordReused => "E_WALLET_DURESS_PASSWORD_REUSED",
+        DuressKeyChainError(source) => "E_WALLET_DURESS_KEY_CHAIN_ERROR",
+        FailToMigrateVault(source) => "E_WALLET_FAIL_TO_MIGRATE_VAULT",
+        FailToDeleteVaultSlot(source) => "E_WALLET_FAIL_TO_DELETE_VAULT_SLOT",
+    }
+    XpubErrors {
+        InvalidPath(path) => "E_XPUB_INVALID_PATH",
+        InvalidChild(index) => "E_XPUB_INVALID_CHILD",
+        HardenedFromPublic(index) => "E_XPUB_HARDENED_FROM_PUBLIC",
+        InvalidEncoding => "E_XPUB_INVALID_ENCODING",
+        InvalidVersion => "E_XPUB_INVALID_VERSION",
+        InvalidPubKey => "E_XPUB_INVALID_PUB_KEY",
+    }
+    Zrc2Errors {
+        Unsupported(transition) => "E_ZRC2_UNSUPPORTED",
+        ZeroAmount => "E_ZRC2_ZERO_AMOUNT",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        let error = GasPriceErrors::TooLow(1, 2_000_000_000);
+
+        assert_eq!(error.code(), "E_GAS_PRICE_TOO_LOW");
+        assert_eq!(
+            error.params(),
+            BTreeMap::from([
+                ("gas_price".to_string(), "1".to_string()),
+                ("min".to_string(), "2000000000".to_string()),
+            ])
+        );
+        assert_eq!(
+            LocalStorageError::StorageAccessError("disk \"full\", retry".to_string()).params(),
+            BTreeMap::from([("reason".to_string(), "disk \"full\", retry".to_string())])
+        );
+        assert_eq!(
+            LocalStorageError::StorageDataNotFound.code(),
+            "E_STORAGE_DATA_NOT_FOUND"
+        );
+        assert_eq!(
+            AesGCMErrors::EncryptError(String::new()).code(),
+            "E_AES_ENCRYPT_ERROR"
+        );
+        assert_eq!(
+            ZilliqaErrors::DeadlineExceeded("balance").params(),
+            BTreeMap::from([("step".to_string(), "balance".to_string())])
+        );
+
+        let info = ErrorInfo::new(&FlowErrors::GasPrice(GasPriceErrors::TooHigh(5, 4)));
+
+        assert_eq!(info.code, "E_FLOW_GAS_PRICE");
+        assert_eq!(info.params["source"], "E_GAS_PRICE_TOO_HIGH");
+        assert!(info.message.starts_with("Gas price error"));
+    }
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConfigErrors {
+    #[error("Failed to read config file: {0}")]
+    FailToRead(String),
+    #[error("Failed to parse config file: {0}")]
+    FailToParse(String),
+    #[error("Invalid network {0}: {1}")]
+    InvalidNetwork(String, String),
+    #[error("Invalid KDF params: {0}")]
+    InvalidKdf(String),
+    #[error("No networks configured")]
+    NoNetworks,
+}
@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ContractTemplateErrors {
+    #[error("Missing value for placeholder: {0}")]
+    MissingPlaceholder(String),
+    #[error("Invalid value for {0} ({1}): {2}")]
+    InvalidValue(String, String, String),
+    #[error("Unknown transition: {0}")]
+    UnknownTransition(String),
+    #[error("Transition params mismatch: {0}")]
+    ParamsMismatch(String),
+    #[error("Template already exists: {0}")]
+    AlreadyExists(String),
+    #[error("Template not found: {0}")]
+    NotFound(String),
+}
@@ -11,6 +11,8 @@ pub enum SignatureError {
     FailIntoPubKey(#[from] PubKeyError),
     #[error("Failed to parse recovery information: {0}")]
     FailParseRecover(String),
+    #[error("Built without EVM support")]
+    EvmDisabled,
 }
 
 #[derive(Debug, Error, PartialEq, Eq)]
@@ -0,0 +1,18 @@
+use crate::cipher::AesGCMErrors;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EscrowErrors {
+    #[error("Invalid escrow public key")]
+    InvalidPublicKey,
+    #[error("Invalid escrow secret key")]
+    InvalidSecretKey,
+    #[error("Invalid escrow blob")]
+    InvalidBlob,
+    #[error("Unsupported escrow version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Escrow encrypt error: {0}")]
+    EncryptError(AesGCMErrors),
+    #[error("Escrow decrypt error: {0}")]
+    DecryptError(AesGCMErrors),
+}
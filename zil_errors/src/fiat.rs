@@ -0,0 +1,14 @@
+use crate::units::UnitsErrors;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FiatSendErrors {
+    #[error("No {0} rate for the asset")]
+    NoRate(String),
+    #[error("Invalid fiat amount: {0}")]
+    InvalidAmount(#[from] UnitsErrors),
+    #[error("Amount too small to send at the current rate")]
+    ZeroAmount,
+    #[error("Rate moved {0} bps, max allowed: {1}")]
+    SlippageExceeded(u64, u16),
+}
@@ -0,0 +1,18 @@
+use crate::gas_price::GasPriceErrors;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FlowErrors {
+    #[error("Flow has no steps")]
+    EmptyFlow,
+    #[error("Step {0} is not signed")]
+    NotSigned(usize),
+    #[error("Expected {0} signed payloads, got {1}")]
+    PayloadsMismatch(usize, usize),
+    #[error("Flow is already finished")]
+    Finished,
+    #[error("RPC error: {0}")]
+    RpcError(String),
+    #[error("Gas price error: {0}")]
+    GasPrice(#[from] GasPriceErrors),
+}
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GasPriceErrors {
+    #[error("Gas price {0} is below the network minimum {1}")]
+    TooLow(u128, u128),
+    #[error("Gas price {0} is above the sane maximum {1}")]
+    TooHigh(u128, u128),
+    #[error("Invalid minimum gas price: {0}")]
+    InvalidMinimum(String),
+}
@@ -0,0 +1,14 @@
+use crate::storage::LocalStorageError;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeyUsageErrors {
+    #[error("Fail to load key usage stats: {0}")]
+    FailToLoad(LocalStorageError),
+    #[error("Fail to save key usage stats: {0}")]
+    FailToSave(LocalStorageError),
+    #[error("Fail to serialize key usage stats")]
+    FailToSerialize,
+    #[error("Fail to deserialize key usage stats")]
+    FailToDeserialize,
+}
@@ -28,6 +28,8 @@ pub enum KeyPairError {
     EthersInvalidSign(String),
     #[error("Invalid signature: {0}")]
     InvalidSignature(#[from] SignatureError),
+    #[error("Built without EVM support")]
+    EvmDisabled,
 }
 
 #[derive(Debug, Error, PartialEq, Eq)]
@@ -1,16 +1,38 @@
+use gas_price::GasPriceErrors;
 use storage::LocalStorageError;
 
 pub mod account;
 pub mod address;
 pub mod background;
 pub mod cipher;
+pub mod code;
+pub mod config;
+pub mod contract_template;
 pub mod crypto;
+pub mod escrow;
+pub mod fiat;
+pub mod flow;
+pub mod gas_price;
+pub mod key_usage;
 pub mod keychain;
 pub mod keypair;
+pub mod lifecycle;
+pub mod mnemonic;
+pub mod nft;
 pub mod ntru;
 pub mod session;
+pub mod sign_request;
+pub mod siwz;
+pub mod statement;
 pub mod storage;
+pub mod swap;
+pub mod sync;
+pub mod timelock;
+pub mod units;
+pub mod user_op;
 pub mod wallet;
+pub mod xpub;
+pub mod zrc2;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ZilliqaErrors<'a> {
@@ -22,6 +44,13 @@ pub enum ZilliqaErrors<'a> {
     InvalidRPCReq(String),
     InvalidJson(String),
     TryInitLocalStorageError(LocalStorageError),
+    CacheStorageError(LocalStorageError),
+    DeadlineExceeded(&'a str), // sub-step that ran out of time
+    InvalidTransport(String),
+    UnsupportedMethod(String), // not served by the detected node software
+    VerifierError(String),     // Sourcify or explorer request failed
+    GasPrice(GasPriceErrors),
+    TooManyPages(u64), // NumPages the node reported
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -0,0 +1,20 @@
+use crate::{background::BackgroundError, sync::SyncErrors, wallet::WalletErrors};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LifecycleErrors {
+    #[error("Illegal wallet state transition: {0} -> {1}")]
+    IllegalTransition(String, String),
+    #[error("Wallet is {0}, expected: {1}")]
+    InvalidState(String, String),
+    #[error("Wallet {0} does not exist")]
+    WalletNotExists(usize),
+    #[error("Wallet {0} is locked")]
+    WalletLocked(usize),
+    #[error("Wallet error: {0}")]
+    Wallet(#[from] WalletErrors),
+    #[error("Background error: {0}")]
+    Background(#[from] BackgroundError),
+    #[error("Sync error: {0}")]
+    Sync(#[from] SyncErrors),
+}
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MnemonicChallengeErrors {
+    #[error("Asked for {0} challenges, the mnemonic has {1} words")]
+    TooManyChallenges(usize, usize),
+    #[error("Wrong word at position {0}")]
+    WrongWord(usize),
+    #[error("Expected {0} answers, got {1}")]
+    AnswersMismatch(usize, usize),
+}
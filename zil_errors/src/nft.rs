@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum NftErrors {
+    #[error("Invalid metadata json: {0}")]
+    InvalidJson(String),
+    #[error("Metadata is not a json object")]
+    NotAnObject,
+}
@@ -9,4 +9,6 @@ pub enum NTRULPCipherErrors {
     ComputePubKeyError(NTRUKemError),
     EncryptError(NTRUCipherError),
     DecryptError(NTRUCipherError),
+    PlaintextTooLarge(usize, usize), // size, max
+    InvalidCiphertext,
 }
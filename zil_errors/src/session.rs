@@ -16,4 +16,8 @@ pub enum SessionErrors {
     SessionNotEnabled,
     #[error("Invalid seed: {0}")]
     InvalidSeed(#[from] KeyChainErrors),
+    #[error("Spend capability requires elevation")]
+    SpendNotElevated,
+    #[error("Spend elevation expired")]
+    ElevationExpired,
 }
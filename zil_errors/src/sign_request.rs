@@ -0,0 +1,20 @@
+use crate::storage::LocalStorageError;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SignRequestErrors {
+    #[error("Sign request expired at: {0}")]
+    Expired(u64),
+    #[error("Sign request expires too far ahead: {0}")]
+    ExpiresTooLate(u64),
+    #[error("Sign request already served: {0}")]
+    Replayed(String),
+    #[error("Fail to load served requests: {0}")]
+    FailToLoad(LocalStorageError),
+    #[error("Fail to save served requests: {0}")]
+    FailToSave(LocalStorageError),
+    #[error("Fail to serialize served requests")]
+    FailToSerialize,
+    #[error("Fail to deserialize served requests")]
+    FailToDeserialize,
+}
@@ -0,0 +1,28 @@
+use crate::{address::AddressError, crypto::SignatureError};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SiwzErrors {
+    #[error("Missing field: {0}")]
+    MissingField(&'static str),
+    #[error("Invalid field {0}: {1}")]
+    InvalidField(&'static str, String),
+    #[error("Invalid nonce, expected at least 8 alphanumeric chars")]
+    InvalidNonce,
+    #[error("Invalid address: {0}")]
+    InvalidAddress(AddressError),
+    #[error("Domain mismatch, expected: {0}")]
+    DomainMismatch(String),
+    #[error("Nonce mismatch")]
+    NonceMismatch,
+    #[error("Address does not match the signer")]
+    AddressMismatch,
+    #[error("Message expired")]
+    Expired,
+    #[error("Message not valid yet")]
+    NotYetValid,
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Fail to verify signature: {0}")]
+    FailToVerify(SignatureError),
+}
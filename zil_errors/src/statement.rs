@@ -0,0 +1,26 @@
+use crate::{
+    address::AddressError,
+    crypto::SignatureError,
+    keypair::{KeyPairError, PubKeyError},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StatementErrors {
+    #[error("Statement has no balances")]
+    NoBalances,
+    #[error("Key does not belong to the statement address")]
+    AddressMismatch,
+    #[error("Invalid address: {0}")]
+    InvalidAddress(AddressError),
+    #[error("Invalid public key: {0}")]
+    InvalidPubKey(PubKeyError),
+    #[error("Fail to sign statement: {0}")]
+    FailToSign(KeyPairError),
+    #[error("Fail to verify statement: {0}")]
+    FailToVerify(SignatureError),
+    #[error("Malformed signature")]
+    MalformedSignature,
+    #[error("Invalid signature")]
+    InvalidSignature,
+}
@@ -12,10 +12,16 @@ pub enum LocalStorageError {
     FailToCreateFile,
     #[error("Failed to write file")]
     FailToWriteFile,
+    #[error("Failed to read file")]
+    FailToReadFile,
     #[error("Storage data not found")]
     StorageDataNotFound,
+    #[error("Storage data broken, hashsum mismatch")]
+    StorageDataBroken,
     #[error("Storage write error")]
     StorageWriteError,
+    #[error("Storage flush error: {0}")]
+    StorageFlushError(String),
     #[error("Storage time went backwards")]
     StorageTimeWentBackwards,
     #[error("Payload version parse error")]
@@ -28,4 +34,40 @@ pub enum LocalStorageError {
     PayloadLengthError,
     #[error("Invalid bytes size overflow")]
     InvalidBytesSizeOverflow,
+    #[error("Wrong storage password")]
+    StorageWrongPassword,
+    #[error("Storage is not encrypted")]
+    StorageNotEncrypted,
+    #[error("Invalid profile name: {0}")]
+    StorageInvalidProfile(String),
+    #[error("Storage is locked by another process")]
+    StorageLockedByAnotherProcess,
+    #[error("Storage is opened read-only")]
+    StorageReadOnly,
+    #[error("Storage encrypt error: {0}")]
+    StorageEncryptError(String),
+    #[error("Storage decrypt error: {0}")]
+    StorageDecryptError(String),
+    #[error("No storage migration from version: {0}")]
+    StorageMigrationMissing(u16),
+    #[error("Storage migration error: {0}")]
+    StorageMigrationError(String),
+    #[error("No such index: {0}")]
+    StorageIndexNotFound(String),
+    #[error("Unrecognized browser extension data: {0}")]
+    StorageExtensionFormat(String),
+    #[error("Unsupported snapshot format: {0}")]
+    StorageSnapshotVersion(u16),
+    #[error("Snapshot broken: {0}")]
+    StorageSnapshotBroken(String),
+    #[error("Unknown payload codec: {0}")]
+    UnknownCodec(u8),
+    #[error("Unknown hashsum algorithm: {0}")]
+    UnknownHashAlgo(u8),
+    #[error("Payload encode error: {0}")]
+    PayloadEncodeError(String),
+    #[error("Storage compression error: {0}")]
+    StorageCompressionError(String),
+    #[error("Audit entry not written, the change itself is stored: {0}")]
+    StorageAuditError(String),
 }
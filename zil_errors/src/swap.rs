@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SwapErrors {
+    #[error("Pool has no liquidity")]
+    EmptyPool,
+    #[error("Amount to swap is zero")]
+    ZeroAmount,
+    #[error("Fee or slippage of {0} bps is out of range")]
+    InvalidBps(u16),
+    #[error("Output is too small to receive anything")]
+    NothingReceived,
+    #[error("Amount overflow")]
+    Overflow,
+}
@@ -0,0 +1,22 @@
+use crate::{keychain::KeyChainErrors, storage::LocalStorageError};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SyncErrors {
+    #[error("Sync storage error: {0}")]
+    StorageError(#[from] LocalStorageError),
+    #[error("Remote error: {0}")]
+    RemoteError(String),
+    #[error("Fail to encrypt sync record: {0}")]
+    EncryptError(KeyChainErrors),
+    #[error("Fail to decrypt sync record: {0}")]
+    DecryptError(KeyChainErrors),
+    #[error("Fail to serialize sync metadata")]
+    FailToSerializeMeta,
+    #[error("Fail to deserialize sync metadata")]
+    FailToDeserializeMeta,
+    #[error("Invalid remote record: {0}")]
+    InvalidRemoteRecord(String),
+    #[error("Remote record does not match its hashsum: {0}")]
+    HashsumMismatch(String),
+}
@@ -0,0 +1,20 @@
+use crate::{cipher::AesGCMErrors, escrow::EscrowErrors};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TimeLockErrors {
+    #[error("Invalid time-locked export")]
+    InvalidExport,
+    #[error("Unsupported time-lock version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Unlock date must be in the future")]
+    UnlockInPast,
+    #[error("Locked until: {0}")]
+    Locked(u64),
+    #[error("Notary share error: {0}")]
+    NotaryError(#[from] EscrowErrors),
+    #[error("Encrypt error: {0}")]
+    EncryptError(AesGCMErrors),
+    #[error("Decrypt error: {0}")]
+    DecryptError(AesGCMErrors),
+}
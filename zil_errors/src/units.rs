@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum UnitsErrors {
+    #[error("Unknown unit: {0}")]
+    UnknownUnit(String),
+    #[error("Missing unit suffix: {0}")]
+    MissingUnit(String),
+    #[error("Invalid number: {0}")]
+    InvalidNumber(String),
+    #[error("Too many decimals for {0}, max: {1}")]
+    TooManyDecimals(String, u32),
+    #[error("Amount overflow")]
+    Overflow,
+}
+
+/// Why an entered amount can't be sent, front-ends show all of them at once.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AmountViolation {
+    #[error("Amount is empty")]
+    Empty,
+    #[error("Amount is negative")]
+    Negative,
+    #[error("Exponent notation is not allowed")]
+    ExponentNotation,
+    #[error("Invalid character: {0}")]
+    InvalidCharacter(char),
+    #[error("Misplaced separator: {0}")]
+    MisplacedSeparator(char),
+    #[error("Too many decimals for {0}, max: {1}")]
+    TooManyDecimals(String, u32),
+    #[error("Amount is zero")]
+    Zero,
+    #[error("Amount overflow")]
+    Overflow,
+    #[error("Fee exceeds the balance")]
+    FeeExceedsBalance,
+    #[error("Amount exceeds the spendable balance: {0}")]
+    ExceedsBalance(u128), // balance minus fee, smallest units
+}
@@ -0,0 +1,12 @@
+use crate::keypair::KeyPairError;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum UserOpErrors {
+    #[error("Smart account sender must be an EVM address")]
+    InvalidSender,
+    #[error("Signer does not produce ECDSA signatures")]
+    UnsupportedSignature,
+    #[error("Fail to sign user operation: {0}")]
+    SignError(KeyPairError),
+}
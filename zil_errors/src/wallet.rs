@@ -1,10 +1,15 @@
 use crate::{
     account::AccountErrors,
     cipher::CipherErrors,
+    contract_template::ContractTemplateErrors,
+    escrow::EscrowErrors,
     keychain::KeyChainErrors,
     keypair::{KeyPairError, SecretKeyError},
     session::SessionErrors,
+    statement::StatementErrors,
     storage::LocalStorageError,
+    timelock::TimeLockErrors,
+    xpub::XpubErrors,
 };
 use thiserror::Error;
 
@@ -86,4 +91,48 @@ pub enum WalletErrors {
     KeyChainFailToGetProof,
     #[error("Proof does not match")]
     ProofNotMatch,
+    #[error("Cannot archive selected account: {0}")]
+    CannotArchiveSelectedAccount(usize),
+    #[error("Cannot remove selected account: {0}")]
+    CannotRemoveSelectedAccount(usize),
+    #[error("Fail to get token: {0}")]
+    FailToGetToken(String),
+    #[error("Nothing to undo")]
+    NothingToUndo,
+    #[error("Fail to serialize changelog")]
+    FailToSerializeChangelog,
+    #[error("Fail to deserialize changelog")]
+    FailToDeserializeChangelog,
+    #[error("Fail to serialize history")]
+    FailToSerializeHistory,
+    #[error("Fail to deserialize history")]
+    FailToDeserializeHistory,
+    #[error("Contract template error: {0}")]
+    ContractTemplateError(#[from] ContractTemplateErrors),
+    #[error("Escrow error: {0}")]
+    EscrowError(#[from] EscrowErrors),
+    #[error("Invalid escrowed keychain")]
+    InvalidEscrowKeychain,
+    #[error("Invalid xpub: {0}")]
+    InvalidXpub(#[from] XpubErrors),
+    #[error("Wallet already watches another xpub")]
+    XpubMismatch,
+    #[error("Fail to derive watch-only account: {0}")]
+    InvalidXpubAccount(AccountErrors),
+    #[error("Time lock error: {0}")]
+    TimeLockError(#[from] TimeLockErrors),
+    #[error("Invalid account tag: {0}")]
+    InvalidAccountTag(AccountErrors),
+    #[error("Fail to sign balance statement: {0}")]
+    FailToSignStatement(StatementErrors),
+    #[error("Duress vault needs at least one account")]
+    DuressNoAccounts,
+    #[error("Duress password must differ from the wallet password")]
+    DuressPasswordReused,
+    #[error("Fail to create duress keychain: {0}")]
+    DuressKeyChainError(KeyChainErrors),
+    #[error("Fail to migrate vault: {0}")]
+    FailToMigrateVault(LocalStorageError),
+    #[error("Fail to delete vault slot: {0}")]
+    FailToDeleteVaultSlot(LocalStorageError),
 }
@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum XpubErrors {
+    #[error("Invalid derivation path: {0}")]
+    InvalidPath(String),
+    #[error("Derived key is invalid at index: {0}")]
+    InvalidChild(u32),
+    #[error("Hardened index {0} can't be derived from a public key")]
+    HardenedFromPublic(u32),
+    #[error("Invalid xpub encoding")]
+    InvalidEncoding,
+    #[error("Unsupported xpub version")]
+    InvalidVersion,
+    #[error("Invalid public key in xpub")]
+    InvalidPubKey,
+}
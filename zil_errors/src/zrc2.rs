@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Zrc2Errors {
+    #[error("Token contract does not implement {0}")]
+    Unsupported(&'static str),
+    #[error("Amount must be greater than zero")]
+    ZeroAmount,
+}
@@ -0,0 +1,463 @@
+use crate::json_rpc::{zil::ZilliqaJsonRPC, zil_interfaces::ResultRes, zil_methods::ZilMethods};
+use config::storage::{ALLOWANCE_CACHE_TTL_MS, ALLOWANCE_TREE};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::rc::Rc;
+use storage::LocalStorage;
+use zil_errors::ZilliqaErrors;
+
+// keccak256("Approval(address,address,uint256)")
+const APPROVAL_TOPIC: &str = "0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
+
+/// An allowance as last seen in a receipt or the contract state. Addresses
+/// are lowercase hex without 0x.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedAllowance {
+    pub token: String,
+    pub spender: String,
+    pub amount: u128,
+    #[serde(default)]
+    pub checked_at: u64, // ms
+}
+
+// What a receipt event does to an allowance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Set(u128),
+    Spent(u128), // `TransferFrom` by the spender
+}
+
+/// Allowances per owner, kept fresh from the `IncreasedAllowance`,
+/// `DecreasedAllowance` and `TransferFromSuccess` events of ZRC-2 receipts
+/// and ERC-20 `Approval` logs. The contract state is read for pairs never
+/// seen or not checked for `ALLOWANCE_CACHE_TTL_MS`.
+pub struct AllowanceCache {
+    storage: Rc<LocalStorage>,
+}
+
+impl std::fmt::Debug for AllowanceCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AllowanceCache").finish_non_exhaustive()
+    }
+}
+
+impl AllowanceCache {
+    pub fn new(storage: Rc<LocalStorage>) -> Self {
+        Self { storage }
+    }
+
+    // Open allowances of `owner`, for the approvals dashboard.
+    pub fn approvals<'a>(
+        &self,
+        network: &str,
+        owner: &str,
+    ) -> Result<Vec<CachedAllowance>, ZilliqaErrors<'a>> {
+        Ok(self
+            .load(network, owner)?
+            .into_iter()
+            .filter(|a| a.amount > 0)
+            .collect())
+    }
+
+    pub fn get<'a>(
+        &self,
+        network: &str,
+        token: &str,
+        owner: &str,
+        spender: &str,
+    ) -> Result<Option<u128>, ZilliqaErrors<'a>> {
+        Ok(self
+            .cached(network, token, owner, spender)?
+            .map(|a| a.amount))
+    }
+
+    fn cached<'a>(
+        &self,
+        network: &str,
+        token: &str,
+        owner: &str,
+        spender: &str,
+    ) -> Result<Option<CachedAllowance>, ZilliqaErrors<'a>> {
+        let (token, spender) = (normalize(token), normalize(spender));
+
+        Ok(self
+            .load(network, owner)?
+            .into_iter()
+            .find(|a| a.token == token && a.spender == spender))
+    }
+
+    pub fn set<'a>(
+        &self,
+        network: &str,
+        owner: &str,
+        allowance: CachedAllowance,
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        let allowance = CachedAllowance {
+            token: normalize(&allowance.token),
+            spender: normalize(&allowance.spender),
+            ..allowance
+        };
+        let mut list = self.load(network, owner)?;
+
+        match list
+            .iter_mut()
+            .find(|a| a.token == allowance.token && a.spender == allowance.spender)
+        {
+            Some(cached) => *cached = allowance,
+            None => list.push(allowance),
+        }
+
+        let bytes = serde_json::to_vec(&list).or(Err(ZilliqaErrors::InvalidPayload))?;
+
+        self.storage
+            .tree_set(ALLOWANCE_TREE, &Self::key(network, owner), &bytes)
+            .map_err(ZilliqaErrors::CacheStorageError)
+    }
+
+    /// Takes the new allowances out of a confirmed tx's receipt, returns how
+    /// many were updated. What a spender took is subtracted from a cached
+    /// pair only, a pair never seen is read from the contract when asked.
+    pub fn apply_receipt<'a>(
+        &self,
+        network: &str,
+        receipt: &Value,
+        now: u64,
+    ) -> Result<usize, ZilliqaErrors<'a>> {
+        let mut updated = 0;
+
+        for (owner, allowance, change) in allowance_events(receipt) {
+            let amount = match change {
+                Change::Set(amount) => amount,
+                Change::Spent(spent) => {
+                    match self.cached(network, &allowance.token, &owner, &allowance.spender)? {
+                        Some(cached) => cached.amount.saturating_sub(spent),
+                        None => continue,
+                    }
+                }
+            };
+
+            self.set(
+                network,
+                &owner,
+                CachedAllowance {
+                    amount,
+                    checked_at: now,
+                    ..allowance
+                },
+            )?;
+            updated += 1;
+        }
+
+        Ok(updated)
+    }
+
+    pub async fn get_or_fetch<'a>(
+        &self,
+        rpc: &ZilliqaJsonRPC,
+        network: &str,
+        token: &str,
+        owner: &str,
+        spender: &str,
+        now: u64,
+    ) -> Result<u128, ZilliqaErrors<'a>> {
+        let cached = self.cached(network, token, owner, spender)?;
+
+        if let Some(cached) =
+            cached.filter(|a| now.saturating_sub(a.checked_at) < ALLOWANCE_CACHE_TTL_MS)
+        {
+            return Ok(cached.amount);
+        }
+
+        let (owner, spender) = (
+            format!("0x{}", normalize(owner)),
+            format!("0x{}", normalize(spender)),
+        );
+        let payload = ZilliqaJsonRPC::build_payload(
+            json!([token, "allowances", [owner, spender]]),
+            ZilMethods::GetSmartContractSubState,
+        );
+        let mut res: Vec<ResultRes<Value>> = rpc.reqwest(vec![payload]).await?;
+        let res = res.pop().ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        if let Some(error) = res.error {
+            return Err(ZilliqaErrors::InvalidRPCReq(error.message));
+        }
+
+        // a null state is a map without the owner, a pair missing from it
+        // was never approved
+        let amount = match res.result {
+            None => 0,
+            Some(state) => {
+                let allowances = state
+                    .get("allowances")
+                    .ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+                match allowances[&owner][&spender].as_str() {
+                    Some(amount) => amount.parse().or(Err(ZilliqaErrors::FailToParseResponse))?,
+                    None => 0,
+                }
+            }
+        };
+
+        self.set(
+            network,
+            &owner,
+            CachedAllowance {
+                token: token.to_string(),
+                spender,
+                amount,
+                checked_at: now,
+            },
+        )?;
+
+        Ok(amount)
+    }
+
+    // A broken entry is refetched.
+    fn load<'a>(
+        &self,
+        network: &str,
+        owner: &str,
+    ) -> Result<Vec<CachedAllowance>, ZilliqaErrors<'a>> {
+        let bytes = self
+            .storage
+            .tree_get(ALLOWANCE_TREE, &Self::key(network, owner))
+            .map_err(ZilliqaErrors::CacheStorageError)?;
+
+        Ok(bytes
+            .and_then(|b| serde_json::from_slice(&b).ok())
+            .unwrap_or_default())
+    }
+
+    fn key(network: &str, owner: &str) -> Vec<u8> {
+        format!("{network}:{}", normalize(owner)).into_bytes()
+    }
+}
+
+// Owner, pair and change of every allowance event in `receipt`, in order.
+fn allowance_events(receipt: &Value) -> Vec<(String, CachedAllowance, Change)> {
+    let pair = |token: &str, spender: &str| CachedAllowance {
+        token: normalize(token),
+        spender: normalize(spender),
+        amount: 0,
+        checked_at: 0,
+    };
+    let scilla = receipt["event_logs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|event| {
+            let param = |name: &str| {
+                event["params"]
+                    .as_array()?
+                    .iter()
+                    .find(|p| p["vname"] == name)?["value"]
+                    .as_str()
+            };
+            let token = event["address"].as_str()?;
+
+            match event["_eventname"].as_str()? {
+                "IncreasedAllowance" | "DecreasedAllowance" => Some((
+                    normalize(param("token_owner")?),
+                    pair(token, param("spender")?),
+                    Change::Set(param("new_allowance")?.parse().ok()?),
+                )),
+                "TransferFromSuccess" => Some((
+                    normalize(param("sender")?),
+                    pair(token, param("initiator")?),
+                    Change::Spent(param("amount")?.parse().ok()?),
+                )),
+                _ => None,
+            }
+        });
+    let evm = receipt["logs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|log| log["topics"][0].as_str() == Some(APPROVAL_TOPIC))
+        .filter_map(|log| {
+            // indexed addresses are left padded to 32 bytes
+            let topic_addr = |i: usize| {
+                let topic = log["topics"][i].as_str()?.trim_start_matches("0x");
+
+                topic.get(24..).map(str::to_lowercase)
+            };
+            let value = log["data"].as_str()?.trim_start_matches("0x");
+            let value = value.trim_start_matches('0');
+            // unlimited approvals don't fit, they stay unlimited
+            let amount = match value.len() {
+                0 => 0,
+                len if len <= 32 => u128::from_str_radix(value, 16).ok()?,
+                _ => u128::MAX,
+            };
+
+            Some((
+                topic_addr(1)?,
+                pair(log["address"].as_str()?, &topic_addr(2)?),
+                Change::Set(amount),
+            ))
+        });
+
+    scilla.chain(evm).collect()
+}
+
+fn normalize(addr: &str) -> String {
+    addr.trim_start_matches("0x").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0xa845c1034cd077bd8d32be0447239c7e4be6cb21";
+    const OWNER: &str = "0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1";
+    const DEX: &str = "0x1a62dd9c84b0c8948cb51fc664ba143e7a34985c";
+
+    #[tokio::test]
+    async fn test_apply_receipt() {
+        let mut server = mockito::Server::new_async().await;
+        let state = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "result": { "allowances": { OWNER: { DEX: "700" } } }
+                }])
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let cache = AllowanceCache::new(Rc::new(LocalStorage::from(&dir).unwrap()));
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let param =
+            |vname: &str, value: &str| json!({ "vname": vname, "type": "ByStr20", "value": value });
+        let receipt = json!({
+            "success": true,
+            "event_logs": [
+                {
+                    "_eventname": "IncreasedAllowance",
+                    "address": TOKEN,
+                    "params": [param("token_owner", OWNER), param("spender", DEX), param("new_allowance", "500")]
+                },
+                { "_eventname": "TransferSuccess", "address": TOKEN, "params": [] }
+            ]
+        });
+
+        assert_eq!(cache.apply_receipt("mainnet", &receipt, 0).unwrap(), 1);
+        assert_eq!(
+            cache
+                .get_or_fetch(
+                    &rpc,
+                    "mainnet",
+                    TOKEN,
+                    &OWNER.to_uppercase().replace("0X", "0x"),
+                    DEX,
+                    0
+                )
+                .await
+                .unwrap(),
+            500
+        );
+        // never seen on testnet, read from the state and again once stale
+        for now in [0, 1, ALLOWANCE_CACHE_TTL_MS] {
+            assert_eq!(
+                cache
+                    .get_or_fetch(&rpc, "testnet", TOKEN, OWNER, DEX, now)
+                    .await
+                    .unwrap(),
+                700
+            );
+        }
+
+        // the dex pulled some of it in our swap
+        let swap = json!({
+            "event_logs": [{
+                "_eventname": "TransferFromSuccess",
+                "address": TOKEN,
+                "params": [param("initiator", DEX), param("sender", OWNER), param("recipient", DEX), param("amount", "120")]
+            }]
+        });
+
+        assert_eq!(cache.apply_receipt("mainnet", &swap, 1).unwrap(), 1);
+        assert_eq!(cache.get("mainnet", TOKEN, OWNER, DEX).unwrap(), Some(380));
+        // nothing cached to subtract from
+        assert_eq!(cache.apply_receipt("devnet", &swap, 1).unwrap(), 0);
+
+        let pad = |addr: &str| format!("0x{:0>64}", &addr[2..]);
+        let evm = json!({
+            "logs": [{
+                "address": TOKEN,
+                "topics": [APPROVAL_TOPIC, pad(OWNER), pad(DEX)],
+                "data": format!("0x{}", "f".repeat(64)),
+            }]
+        });
+
+        assert_eq!(cache.apply_receipt("mainnet", &evm, 2).unwrap(), 1);
+        assert_eq!(
+            cache.approvals("mainnet", OWNER).unwrap(),
+            vec![CachedAllowance {
+                token: normalize(TOKEN),
+                spender: normalize(DEX),
+                amount: u128::MAX,
+                checked_at: 2,
+            }]
+        );
+
+        let revoked = json!({
+            "event_logs": [{
+                "_eventname": "DecreasedAllowance",
+                "address": TOKEN,
+                "params": [param("token_owner", OWNER), param("spender", DEX), param("new_allowance", "0")]
+            }]
+        });
+
+        cache.apply_receipt("mainnet", &revoked, 3).unwrap();
+
+        assert!(cache.approvals("mainnet", OWNER).unwrap().is_empty());
+        assert_eq!(cache.get("mainnet", TOKEN, OWNER, DEX).unwrap(), Some(0));
+        state.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_unknown_pair() {
+        let mut server = mockito::Server::new_async().await;
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let cache = AllowanceCache::new(Rc::new(LocalStorage::from(&dir).unwrap()));
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let never_approved = server
+            .mock("POST", "/")
+            .with_body(json!([{ "id": 1, "jsonrpc": "2.0", "result": null }]).to_string())
+            .create_async()
+            .await;
+
+        assert_eq!(
+            cache
+                .get_or_fetch(&rpc, "mainnet", TOKEN, OWNER, DEX, 0)
+                .await
+                .unwrap(),
+            0
+        );
+        never_approved.remove_async().await;
+
+        // a broken answer is an error, not a zero allowance
+        let broken = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "balances": {} } }]).to_string(),
+            )
+            .create_async()
+            .await;
+
+        assert_eq!(
+            cache
+                .get_or_fetch(&rpc, "testnet", TOKEN, OWNER, DEX, 0)
+                .await,
+            Err(ZilliqaErrors::FailToParseResponse)
+        );
+        assert_eq!(cache.get("testnet", TOKEN, OWNER, DEX).unwrap(), None);
+        broken.assert_async().await;
+    }
+}
@@ -0,0 +1,779 @@
+use crate::json_rpc::{
+    allowance_cache::AllowanceCache,
+    zil::ZilliqaJsonRPC,
+    zil_interfaces::{CreateTransactionRes, ResultRes},
+    zil_methods::ZilMethods,
+};
+use config::broadcast::{
+    BROADCAST_BASE_DELAY_MS, BROADCAST_MAX_DELAY_MS, BROADCAST_RECEIPT_POLL_MS,
+    BROADCAST_RECEIPT_TTL_MS, BROADCAST_TX_TTL_MS, IDEMPOTENCY_KEY_HEADER,
+};
+use proto::{
+    address::Address,
+    pubkey::PubKey,
+    zil_tx::{zilliqa_tx_hash, ScillaGas, ZILTransactionRequest, ZilAmount},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+const EVENTS_CAPACITY: usize = 64;
+// Node answers that mean "try later", anything else is a final rejection.
+const TRANSIENT_ERRORS: [&str; 7] = [
+    "busy",
+    "mempool",
+    "full",
+    "timeout",
+    "timed out",
+    "try again",
+    "unavailable",
+];
+
+/// Session and request a dApp transaction came from, echoed back with the
+/// receipt so the dApp layer can answer the request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DappRequest {
+    pub session_id: String,
+    pub request_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedTx {
+    pub id: String,
+    pub payload: Value, // signed CreateTransaction params
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub expires_at: u64,
+    #[serde(default)]
+    pub idempotency_key: String,
+    #[serde(default)]
+    pub tx_hash: Option<String>, // expected TranID, see `zilliqa_tx_hash`
+    #[serde(default)]
+    pub dapp: Option<DappRequest>,
+    #[serde(default)]
+    pub awaiting_receipt: bool, // accepted, kept until mined
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    Accepted(String), // tx hash
+    Transient(String),
+    Rejected(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastEvent {
+    Queued(String),
+    Retrying {
+        id: String,
+        attempts: u32,
+        next_attempt_at: u64,
+        error: String,
+    },
+    Accepted {
+        id: String,
+        tx_hash: String,
+    },
+    // `request` is set for dApp transactions, so the dApp gets its answer
+    Rejected {
+        id: String,
+        request: Option<DappRequest>,
+        error: String,
+    },
+    Expired {
+        id: String,
+        request: Option<DappRequest>,
+    },
+    Confirmed {
+        id: String,
+        request: DappRequest,
+        tx_hash: String,
+        receipt: Value,
+    },
+}
+
+/// Signed transactions waiting to be accepted by a node. Transient failures
+/// are retried with exponential backoff, every attempt goes to the next node.
+#[derive(Debug)]
+pub struct BroadcastQueue {
+    entries: Vec<QueuedTx>,
+    events: broadcast::Sender<BroadcastEvent>,
+    allowances: Option<(AllowanceCache, String)>, // and the network
+}
+
+impl Default for BroadcastQueue {
+    fn default() -> Self {
+        Self::from_entries(Vec::new())
+    }
+}
+
+impl BroadcastQueue {
+    pub fn from_entries(entries: Vec<QueuedTx>) -> Self {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+
+        Self {
+            entries,
+            events,
+            allowances: None,
+        }
+    }
+
+    /// Every accepted tx then stays queued until its receipt is in, which
+    /// updates the allowances of `network` in `cache`.
+    pub fn track_allowances(&mut self, cache: AllowanceCache, network: &str) {
+        self.allowances = Some((cache, network.to_string()));
+    }
+
+    pub fn entries(&self) -> &[QueuedTx] {
+        &self.entries
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BroadcastEvent> {
+        self.events.subscribe()
+    }
+
+    /// The TranID is computed up front, so a retry first asks the node
+    /// whether an earlier attempt got through, a timed out submit is often
+    /// accepted.
+    pub fn enqueue(&mut self, id: String, payload: Value, now: u64) {
+        self.push(id, payload, None, now);
+    }
+
+    /// Stays queued after acceptance until the receipt is in, which is then
+    /// emitted as [BroadcastEvent::Confirmed] along with `request`.
+    pub fn enqueue_for_dapp(&mut self, id: String, payload: Value, request: DappRequest, now: u64) {
+        self.push(id, payload, Some(request), now);
+    }
+
+    fn push(&mut self, id: String, payload: Value, dapp: Option<DappRequest>, now: u64) {
+        self.entries.retain(|tx| tx.id != id);
+        self.entries.push(QueuedTx {
+            id: id.clone(),
+            tx_hash: payload_hash(&payload),
+            payload,
+            attempts: 0,
+            next_attempt_at: now,
+            expires_at: now.saturating_add(BROADCAST_TX_TTL_MS),
+            idempotency_key: hex::encode(rand::random::<[u8; 16]>()),
+            dapp,
+            awaiting_receipt: false,
+        });
+        self.emit(BroadcastEvent::Queued(id));
+    }
+
+    pub fn backoff(attempts: u32) -> u64 {
+        BROADCAST_BASE_DELAY_MS
+            .saturating_mul(2u64.saturating_pow(attempts))
+            .min(BROADCAST_MAX_DELAY_MS)
+    }
+
+    pub fn apply(&mut self, id: &str, outcome: SubmitOutcome, now: u64) -> Option<BroadcastEvent> {
+        let index = self.entries.iter().position(|tx| tx.id == id)?;
+        let event = match outcome {
+            SubmitOutcome::Accepted(tx_hash)
+                if self.entries[index].dapp.is_some() || self.allowances.is_some() =>
+            {
+                let tx = &mut self.entries[index];
+
+                tx.tx_hash = Some(tx_hash.clone());
+                tx.awaiting_receipt = true;
+                tx.next_attempt_at = now.saturating_add(BROADCAST_RECEIPT_POLL_MS);
+                tx.expires_at = now.saturating_add(BROADCAST_RECEIPT_TTL_MS);
+
+                BroadcastEvent::Accepted {
+                    id: id.to_string(),
+                    tx_hash,
+                }
+            }
+            SubmitOutcome::Accepted(tx_hash) => {
+                self.entries.remove(index);
+
+                BroadcastEvent::Accepted {
+                    id: id.to_string(),
+                    tx_hash,
+                }
+            }
+            SubmitOutcome::Rejected(error) => {
+                let tx = self.entries.remove(index);
+
+                BroadcastEvent::Rejected {
+                    id: tx.id,
+                    request: tx.dapp,
+                    error,
+                }
+            }
+            SubmitOutcome::Transient(error) => {
+                let tx = &mut self.entries[index];
+
+                tx.next_attempt_at = now.saturating_add(Self::backoff(tx.attempts));
+                tx.attempts += 1;
+
+                BroadcastEvent::Retrying {
+                    id: id.to_string(),
+                    attempts: tx.attempts,
+                    next_attempt_at: tx.next_attempt_at,
+                    error,
+                }
+            }
+        };
+
+        self.emit(event.clone());
+
+        Some(event)
+    }
+
+    pub fn expire(&mut self, now: u64) -> Vec<BroadcastEvent> {
+        let (expired, alive): (Vec<QueuedTx>, Vec<QueuedTx>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|tx| tx.expires_at <= now);
+        let events: Vec<BroadcastEvent> = expired
+            .into_iter()
+            .map(|tx| BroadcastEvent::Expired {
+                id: tx.id,
+                request: tx.dapp,
+            })
+            .collect();
+
+        self.entries = alive;
+        events.iter().for_each(|e| self.emit(e.clone()));
+
+        events
+    }
+
+    /// Submits every due transaction once.
+    pub async fn process(&mut self, rpc: &ZilliqaJsonRPC, now: u64) -> Vec<BroadcastEvent> {
+        let mut events = self.expire(now);
+
+        if rpc.nodes.is_empty() {
+            return events;
+        }
+
+        let due: Vec<QueuedTx> = self
+            .entries
+            .iter()
+            .filter(|tx| tx.next_attempt_at <= now)
+            .cloned()
+            .collect();
+
+        for tx in due {
+            let url = &rpc.nodes[tx.attempts as usize % rpc.nodes.len()];
+            let transport = rpc.transport();
+            let client = transport.client(transport.circuit(tx.attempts as usize));
+
+            if tx.awaiting_receipt {
+                let receipt = match &client {
+                    Ok(client) => receipt(client, url, tx.tx_hash.as_deref().unwrap_or("")).await,
+                    Err(_) => None,
+                };
+
+                events.extend(self.confirm(&tx.id, receipt, now));
+
+                continue;
+            }
+
+            // asked where the previous attempt went, other nodes may not
+            // have seen it yet
+            let previous = &rpc.nodes[(tx.attempts as usize).wrapping_sub(1) % rpc.nodes.len()];
+            let outcome = match client {
+                Ok(client) => match (tx.attempts, &tx.tx_hash) {
+                    (1.., Some(hash)) if is_known(&client, previous, hash).await => {
+                        SubmitOutcome::Accepted(hash.clone())
+                    }
+                    _ => submit(&client, url, &tx).await,
+                },
+                Err(e) => SubmitOutcome::Transient(format!("{e:?}")),
+            };
+
+            events.extend(self.apply(&tx.id, outcome, now));
+        }
+
+        events
+    }
+
+    // Not mined yet (or the node is unreachable) means polling again later.
+    fn confirm(&mut self, id: &str, receipt: Option<Value>, now: u64) -> Option<BroadcastEvent> {
+        let index = self.entries.iter().position(|tx| tx.id == id)?;
+
+        // a failed cache write is retried with the next poll
+        let applied = match (&receipt, &self.allowances) {
+            (Some(receipt), Some((cache, network))) => {
+                cache.apply_receipt(network, receipt, now).is_ok()
+            }
+            (receipt, _) => receipt.is_some(),
+        };
+        let Some(receipt) = receipt.filter(|_| applied) else {
+            self.entries[index].next_attempt_at = now.saturating_add(BROADCAST_RECEIPT_POLL_MS);
+
+            return None;
+        };
+        let tx = self.entries.remove(index);
+        let event = BroadcastEvent::Confirmed {
+            id: tx.id,
+            request: tx.dapp?,
+            tx_hash: tx.tx_hash.unwrap_or_default(),
+            receipt,
+        };
+
+        self.emit(event.clone());
+
+        Some(event)
+    }
+
+    fn emit(&self, event: BroadcastEvent) {
+        // no subscribers is fine
+        let _ = self.events.send(event);
+    }
+}
+
+pub fn is_transient(message: &str) -> bool {
+    let message = message.to_lowercase();
+
+    TRANSIENT_ERRORS.iter().any(|e| message.contains(e))
+}
+
+async fn submit(client: &reqwest::Client, url: &str, tx: &QueuedTx) -> SubmitOutcome {
+    let body = vec![ZilliqaJsonRPC::build_payload(
+        json!([tx.payload]),
+        ZilMethods::CreateTransaction,
+    )];
+    let mut req = client.post(url).json(&body);
+
+    if !tx.idempotency_key.is_empty() {
+        req = req.header(IDEMPOTENCY_KEY_HEADER, &tx.idempotency_key);
+    }
+
+    let res = match req.send().await {
+        Ok(res) => res,
+        Err(e) => return SubmitOutcome::Transient(e.to_string()),
+    };
+
+    if res.status().is_server_error() || res.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return SubmitOutcome::Transient(res.status().to_string());
+    }
+
+    let mut res: Vec<ResultRes<CreateTransactionRes>> = match res.json().await {
+        Ok(json) => json,
+        Err(e) => return SubmitOutcome::Transient(e.to_string()),
+    };
+
+    match res.pop() {
+        Some(ResultRes {
+            error: Some(error), ..
+        }) if is_transient(&error.message) => SubmitOutcome::Transient(error.message),
+        Some(ResultRes {
+            error: Some(error), ..
+        }) => SubmitOutcome::Rejected(error.message),
+        Some(ResultRes {
+            result: Some(result),
+            ..
+        }) => SubmitOutcome::Accepted(result.tran_id),
+        _ => SubmitOutcome::Transient("empty response".to_string()),
+    }
+}
+
+async fn receipt(client: &reqwest::Client, url: &str, hash: &str) -> Option<Value> {
+    let body = vec![ZilliqaJsonRPC::build_payload(
+        json!([hash]),
+        ZilMethods::GetTransaction,
+    )];
+    let mut res: Vec<ResultRes<Value>> = client
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+
+    res.pop()?.result?.get("receipt").cloned()
+}
+
+// TranID of signed CreateTransaction params, None if they are incomplete.
+fn payload_hash(payload: &Value) -> Option<String> {
+    let number = |field: &str| match &payload[field] {
+        Value::String(s) => s.parse::<u128>().ok(),
+        value => value.as_u64().map(u128::from),
+    };
+    let text = |field: &str| payload[field].as_str().unwrap_or_default().to_string();
+    let to_addr = text("toAddr");
+    let pub_key = hex::decode(text("pubKey")).ok()?.try_into().ok()?;
+    let txn = ZILTransactionRequest {
+        chain_id: (number("version")? >> 16) as u16,
+        nonce: number("nonce")?.try_into().ok()?,
+        gas_price: ZilAmount::from_raw(number("gasPrice")?),
+        gas_limit: ScillaGas(number("gasLimit")?.try_into().ok()?),
+        to_addr: Address::from_zil_base16(to_addr.trim_start_matches("0x")).ok()?,
+        amount: ZilAmount::from_raw(number("amount")?),
+        code: text("code"),
+        data: text("data"),
+    };
+
+    Some(zilliqa_tx_hash(
+        &txn,
+        PubKey::Secp256k1Sha256Zilliqa(pub_key),
+    ))
+}
+
+// Mined, or still in the pool (pending codes are below 10, dropped ones
+// from 10 up). Any doubt means submitting again.
+async fn is_known(client: &reqwest::Client, url: &str, hash: &str) -> bool {
+    let lookup = |method| async move {
+        let body = vec![ZilliqaJsonRPC::build_payload(json!([hash]), method)];
+        let mut res: Vec<ResultRes<Value>> = client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .ok()?
+            .json()
+            .await
+            .ok()?;
+
+        res.pop()?.result
+    };
+
+    if lookup(ZilMethods::GetTransaction).await.is_some() {
+        return true;
+    }
+
+    match lookup(ZilMethods::GetPendingTxn).await {
+        Some(pending) => {
+            let confirmed = pending["confirmed"].as_bool().unwrap_or(false);
+            let code = pending["code"].as_u64().unwrap_or(0);
+
+            confirmed || (1..10).contains(&code)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::units::QA_PER_ZIL;
+    use std::rc::Rc;
+    use storage::LocalStorage;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(BroadcastQueue::backoff(0), BROADCAST_BASE_DELAY_MS);
+        assert_eq!(BroadcastQueue::backoff(2), BROADCAST_BASE_DELAY_MS * 4);
+        assert_eq!(BroadcastQueue::backoff(64), BROADCAST_MAX_DELAY_MS);
+    }
+
+    #[test]
+    fn test_apply_and_expire() {
+        let mut queue = BroadcastQueue::default();
+        let mut rx = queue.subscribe();
+
+        queue.enqueue("a".to_string(), json!({}), 0);
+        queue.enqueue("b".to_string(), json!({}), 10);
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            BroadcastEvent::Queued("a".to_string())
+        );
+
+        let event = queue.apply("a", SubmitOutcome::Transient("busy".to_string()), 100);
+
+        assert_eq!(
+            event,
+            Some(BroadcastEvent::Retrying {
+                id: "a".to_string(),
+                attempts: 1,
+                next_attempt_at: 100 + BROADCAST_BASE_DELAY_MS,
+                error: "busy".to_string(),
+            })
+        );
+
+        let events = queue.expire(BROADCAST_TX_TTL_MS);
+
+        assert_eq!(
+            events,
+            vec![BroadcastEvent::Expired {
+                id: "a".to_string(),
+                request: None,
+            }]
+        );
+        assert_eq!(queue.entries().len(), 1);
+        assert_eq!(
+            queue.apply("a", SubmitOutcome::Accepted("h".to_string()), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient("Node is busy"));
+        assert!(is_transient("Mempool Full"));
+        assert!(!is_transient("Invalid nonce"));
+    }
+
+    #[tokio::test]
+    async fn test_process_rotates_nodes() {
+        let mut busy = mockito::Server::new_async().await;
+        let mut healthy = mockito::Server::new_async().await;
+        let _busy = busy
+            .mock("POST", "/")
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "error": { "code": -8, "message": "Mempool is full" }
+                }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let _healthy = healthy
+            .mock("POST", "/")
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "result": { "Info": "Non-contract txn, sent to shard", "TranID": "abc" }
+                }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![busy.url(), healthy.url()]);
+        let mut queue = BroadcastQueue::default();
+
+        queue.enqueue("tx".to_string(), json!({ "nonce": 1 }), 0);
+
+        let events = queue.process(&rpc, 0).await;
+
+        assert!(matches!(
+            events[0],
+            BroadcastEvent::Retrying { attempts: 1, .. }
+        ));
+        assert!(queue.process(&rpc, 1).await.is_empty());
+
+        let events = queue.process(&rpc, BROADCAST_BASE_DELAY_MS).await;
+
+        assert_eq!(
+            events,
+            vec![BroadcastEvent::Accepted {
+                id: "tx".to_string(),
+                tx_hash: "abc".to_string(),
+            }]
+        );
+        assert!(queue.entries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retry_finds_pending_tx() {
+        let mut server = mockito::Server::new_async().await;
+        let mut next = mockito::Server::new_async().await;
+        let mut queue = BroadcastQueue::default();
+        let hash = "b9ed162be6d25c69b86bc5397ababef079d8ca5d7b93be46f7529b2178489616";
+
+        queue.enqueue(
+            "tx".to_string(),
+            json!({
+                "version": 65537,
+                "nonce": 7,
+                "toAddr": "0x2222222222222222222222222222222222222222",
+                "amount": QA_PER_ZIL.to_string(),
+                "pubKey": format!("03{}", "11".repeat(32)),
+                "gasPrice": "2000000000",
+                "gasLimit": "50",
+                "code": "",
+                "data": "",
+                "signature": "",
+            }),
+            0,
+        );
+
+        assert_eq!(queue.entries()[0].tx_hash.as_deref(), Some(hash));
+
+        let key = queue.entries()[0].idempotency_key.clone();
+        let create = server
+            .mock("POST", "/")
+            .match_header(IDEMPOTENCY_KEY_HEADER, key.as_str())
+            .match_body(mockito::Matcher::Regex("CreateTransaction".to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "error": { "code": -1, "message": "timed out" } }])
+                    .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let _not_mined = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(r#""GetTransaction""#.to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "error": { "code": -20, "message": "Txn Hash not Present" } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let _pending = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("GetPendingTxn".to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "code": 4, "confirmed": false, "info": "Pending" } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        // the retry goes to this node, it never heard of the tx
+        let resubmit = next
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("CreateTransaction".to_string()))
+            .expect(0)
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url(), next.url()]);
+
+        assert!(matches!(
+            queue.process(&rpc, 0).await[0],
+            BroadcastEvent::Retrying { .. }
+        ));
+        assert_eq!(
+            queue.process(&rpc, BROADCAST_BASE_DELAY_MS).await,
+            vec![BroadcastEvent::Accepted {
+                id: "tx".to_string(),
+                tx_hash: hash.to_string(),
+            }]
+        );
+        // not sent a second time
+        create.assert_async().await;
+        resubmit.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_dapp_receipt() {
+        let mut server = mockito::Server::new_async().await;
+        let _create = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("CreateTransaction".to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "Info": "", "TranID": "h2" } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let _mined = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(r#""GetTransaction""#.to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "receipt": { "success": true, "cumulative_gas": "50" } } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let request = DappRequest {
+            session_id: "wc-session".to_string(),
+            request_id: "42".to_string(),
+        };
+        let mut queue = BroadcastQueue::default();
+
+        queue.enqueue_for_dapp("tx".to_string(), json!({}), request.clone(), 0);
+
+        assert!(matches!(
+            queue.process(&rpc, 0).await[..],
+            [BroadcastEvent::Accepted { .. }]
+        ));
+        assert!(queue.entries()[0].awaiting_receipt);
+        assert!(queue.process(&rpc, 1).await.is_empty());
+        assert_eq!(
+            queue.process(&rpc, BROADCAST_RECEIPT_POLL_MS).await,
+            vec![BroadcastEvent::Confirmed {
+                id: "tx".to_string(),
+                request,
+                tx_hash: "h2".to_string(),
+                receipt: json!({ "success": true, "cumulative_gas": "50" }),
+            }]
+        );
+        assert!(queue.entries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_receipt_updates_allowances() {
+        let mut server = mockito::Server::new_async().await;
+        let receipt = json!({
+            "success": true,
+            "event_logs": [{
+                "_eventname": "IncreasedAllowance",
+                "address": "0xa1",
+                "params": [
+                    { "vname": "token_owner", "type": "ByStr20", "value": "0xb2" },
+                    { "vname": "spender", "type": "ByStr20", "value": "0xc3" },
+                    { "vname": "new_allowance", "type": "Uint128", "value": "500" }
+                ]
+            }]
+        });
+        let _create = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("CreateTransaction".to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "Info": "", "TranID": "h3" } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let _mined = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(r#""GetTransaction""#.to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "receipt": receipt } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let mut queue = BroadcastQueue::default();
+
+        queue.track_allowances(AllowanceCache::new(Rc::clone(&storage)), "mainnet");
+        queue.enqueue("approve".to_string(), json!({}), 0);
+        queue.process(&rpc, 0).await;
+
+        // not a dApp tx, kept for its receipt all the same
+        assert!(queue.entries()[0].awaiting_receipt);
+        assert!(queue
+            .process(&rpc, BROADCAST_RECEIPT_POLL_MS)
+            .await
+            .is_empty());
+        assert!(queue.entries().is_empty());
+        assert_eq!(
+            AllowanceCache::new(storage)
+                .get("mainnet", "a1", "b2", "c3")
+                .unwrap(),
+            Some(500)
+        );
+    }
+
+    #[test]
+    fn test_dapp_rejected_and_expired() {
+        let request = DappRequest {
+            session_id: "wc-session".to_string(),
+            request_id: "7".to_string(),
+        };
+        let mut queue = BroadcastQueue::default();
+
+        queue.enqueue_for_dapp("a".to_string(), json!({}), request.clone(), 0);
+        queue.enqueue_for_dapp("b".to_string(), json!({}), request.clone(), 0);
+
+        assert_eq!(
+            queue.apply("a", SubmitOutcome::Rejected("bad nonce".to_string()), 0),
+            Some(BroadcastEvent::Rejected {
+                id: "a".to_string(),
+                request: Some(request.clone()),
+                error: "bad nonce".to_string(),
+            })
+        );
+        assert_eq!(
+            queue.expire(BROADCAST_TX_TTL_MS),
+            vec![BroadcastEvent::Expired {
+                id: "b".to_string(),
+                request: Some(request),
+            }]
+        );
+    }
+}
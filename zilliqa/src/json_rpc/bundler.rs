@@ -0,0 +1,95 @@
+use crate::json_rpc::{zil::ZilliqaJsonRPC, zil_methods::ZilMethods};
+use config::contracts::ENTRY_POINT_V06;
+use proto::user_op::{UserOpGasEstimate, UserOperation};
+use serde_json::json;
+use zil_errors::ZilliqaErrors;
+
+/// ERC-4337 bundler endpoint, user operations go there instead of to a node.
+#[derive(Debug)]
+pub struct BundlerClient {
+    pub rpc: ZilliqaJsonRPC,
+    pub entry_point: String,
+}
+
+impl BundlerClient {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self::with_entry_point(urls, ENTRY_POINT_V06)
+    }
+
+    pub fn with_entry_point(urls: Vec<String>, entry_point: &str) -> Self {
+        Self {
+            rpc: ZilliqaJsonRPC::from_vec(urls),
+            entry_point: entry_point.to_string(),
+        }
+    }
+
+    // Returns the userOpHash.
+    pub async fn send_user_operation<'a>(
+        &self,
+        op: &UserOperation,
+    ) -> Result<String, ZilliqaErrors<'a>> {
+        self.rpc
+            .call(
+                json!([op, self.entry_point]),
+                ZilMethods::EthSendUserOperation,
+            )
+            .await
+    }
+
+    pub async fn estimate_user_operation_gas<'a>(
+        &self,
+        op: &UserOperation,
+    ) -> Result<UserOpGasEstimate, ZilliqaErrors<'a>> {
+        self.rpc
+            .call(
+                json!([op, self.entry_point]),
+                ZilMethods::EthEstimateUserOperationGas,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn test_bundler_calls() {
+        let mut server = mockito::Server::new_async().await;
+        let _estimate = server
+            .mock("POST", "/")
+            .match_body(Matcher::Regex("eth_estimateUserOperationGas".to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": {
+                    "preVerificationGas": "0xb708",
+                    "verificationGasLimit": "0x13a8a",
+                    "callGasLimit": "0x2f5d"
+                } }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let send = server
+            .mock("POST", "/")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex("eth_sendUserOperation".to_string()),
+                Matcher::Regex(ENTRY_POINT_V06.to_string()),
+                Matcher::Regex(r#""callGasLimit":"0x2f5d""#.to_string()),
+            ]))
+            .with_body(json!([{ "id": 1, "jsonrpc": "2.0", "result": "0xop" }]).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let bundler = BundlerClient::new(vec![server.url()]);
+        let op = UserOperation::default();
+        let estimate = bundler.estimate_user_operation_gas(&op).await.unwrap();
+
+        assert_eq!(estimate.call_gas_limit, 0x2f5d.into());
+
+        let op = op.with_gas(&estimate);
+
+        assert_eq!(bundler.send_user_operation(&op).await.unwrap(), "0xop");
+        send.assert_async().await;
+    }
+}
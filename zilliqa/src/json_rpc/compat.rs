@@ -0,0 +1,214 @@
+use crate::json_rpc::{
+    zil::ZilliqaJsonRPC,
+    zil_interfaces::{GetVersionRes, ResultRes},
+    zil_methods::ZilMethods,
+};
+use config::node::{ZQ1_MIN_MAJOR, ZQ2_STABLE_MAJOR};
+use serde_json::json;
+use zil_errors::ZilliqaErrors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeFlavour {
+    #[default]
+    Zq1,
+    Zq2Interim, // v0.x, API compat layer still incomplete
+    Zq2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeVersion {
+    pub raw: String,
+    pub major: u64,
+    pub minor: u64,
+    pub flavour: NodeFlavour,
+}
+
+impl NodeVersion {
+    // "v9.3.1", "v0.6.0-rc1", "zq2-v1.2.0". Anything unparsable is treated
+    // as ZQ1, the API every node spoke before versions mattered.
+    pub fn parse(raw: &str) -> Self {
+        let trimmed = raw.trim().to_lowercase();
+        let zq2_tag = trimmed.starts_with("zq2");
+        let numbers = trimmed.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let mut parts = numbers
+            .split(|c: char| !c.is_ascii_digit())
+            .map(|n| n.parse::<u64>().ok());
+        let major = parts.next().flatten();
+        let minor = parts.next().flatten().unwrap_or(0);
+        let flavour = match major {
+            Some(m) if m >= ZQ1_MIN_MAJOR && !zq2_tag => NodeFlavour::Zq1,
+            Some(m) if m >= ZQ2_STABLE_MAJOR => NodeFlavour::Zq2,
+            Some(_) => NodeFlavour::Zq2Interim,
+            None if zq2_tag => NodeFlavour::Zq2,
+            None => NodeFlavour::Zq1,
+        };
+
+        Self {
+            raw: raw.to_string(),
+            major: major.unwrap_or(0),
+            minor,
+            flavour,
+        }
+    }
+}
+
+impl NodeFlavour {
+    /// Compatibility matrix, ZQ1 serves the whole API.
+    pub fn supports(&self, method: &ZilMethods) -> bool {
+        match self {
+            NodeFlavour::Zq1 => true,
+            // no shards on ZQ2, block transactions come unpaged
+            NodeFlavour::Zq2 => !matches!(
+                method,
+                ZilMethods::GetTransactionsForTxBlockEx | ZilMethods::GetTxnBodiesForTxBlockEx
+            ),
+            NodeFlavour::Zq2Interim => !matches!(
+                method,
+                ZilMethods::GetTransactionsForTxBlockEx
+                    | ZilMethods::GetTxnBodiesForTxBlockEx
+                    | ZilMethods::GetRecentTransactions
+                    | ZilMethods::GetPendingTxn
+            ),
+        }
+    }
+}
+
+impl ZilliqaJsonRPC {
+    pub async fn get_version<'a>(&self) -> Result<NodeVersion, ZilliqaErrors<'a>> {
+        let res: GetVersionRes = self.call(json!([]), ZilMethods::GetVersion).await?;
+
+        Ok(NodeVersion::parse(&res.version))
+    }
+
+    /// Flavour of the first node, the one requests go to first. Detected
+    /// once per node URL. A node that can't answer `GetVersion` is assumed
+    /// to be ZQ1 and asked again next time, requests then fail the same way
+    /// they always did.
+    pub async fn node_flavour(&self) -> NodeFlavour {
+        let Some(url) = self.nodes.first() else {
+            return NodeFlavour::default();
+        };
+
+        if let Some(flavour) = self.flavours().get(url) {
+            return *flavour;
+        }
+
+        match self.get_version_of(url).await {
+            Ok(version) => {
+                self.flavours().insert(url.clone(), version.flavour);
+
+                version.flavour
+            }
+            Err(_) => NodeFlavour::default(),
+        }
+    }
+
+    async fn get_version_of<'a>(&self, url: &str) -> Result<NodeVersion, ZilliqaErrors<'a>> {
+        let client = self.transport.client(0)?;
+        let payloads = [Self::build_payload(json!([]), ZilMethods::GetVersion)];
+        let mut res: Vec<ResultRes<GetVersionRes>> = self.post(&client, url, &payloads).await?;
+        let version = res
+            .pop()
+            .and_then(|res| res.result)
+            .ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        Ok(NodeVersion::parse(&version.version))
+    }
+
+    pub(crate) async fn ensure_supported<'a>(
+        &self,
+        method: &ZilMethods,
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        let flavour = self.node_flavour().await;
+
+        if flavour.supports(method) {
+            Ok(())
+        } else {
+            Err(ZilliqaErrors::UnsupportedMethod(format!(
+                "{method} on {flavour:?}"
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(NodeVersion::parse("v9.3.1").flavour, NodeFlavour::Zq1);
+        assert_eq!(
+            NodeVersion::parse("v0.6.0-rc1").flavour,
+            NodeFlavour::Zq2Interim
+        );
+        assert_eq!(NodeVersion::parse("zq2-v1.2.0").flavour, NodeFlavour::Zq2);
+        assert_eq!(NodeVersion::parse("").flavour, NodeFlavour::Zq1);
+
+        let version = NodeVersion::parse("v2.4.1");
+
+        assert_eq!((version.major, version.minor), (2, 4));
+        assert_eq!(version.flavour, NodeFlavour::Zq2);
+    }
+
+    #[tokio::test]
+    async fn test_zq2_unpaged_block_txs() {
+        let mut server = mockito::Server::new_async().await;
+        let version = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("GetVersion".to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "Version": "v1.3.0", "Commit": "" } }])
+                    .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let unpaged = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(
+                r#""GetTransactionsForTxBlock""#.to_string(),
+            ))
+            .with_body(json!([{ "id": 1, "jsonrpc": "2.0", "result": [["a1", "a2"]] }]).to_string())
+            .create_async()
+            .await;
+        let zil = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+
+        assert_eq!(
+            zil.get_txs_for_tx_block_ex(42, 0).await.unwrap_err(),
+            ZilliqaErrors::UnsupportedMethod("GetTransactionsForTxBlockEx on Zq2".to_string())
+        );
+
+        let shards = zil.get_all_txs_for_tx_block(42).await.unwrap();
+
+        unpaged.assert_async().await;
+        assert_eq!(shards, vec![vec!["a1".to_string(), "a2".to_string()]]);
+        version.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_flavour_not_cached_on_failure() {
+        let mut server = mockito::Server::new_async().await;
+        let broken = server
+            .mock("POST", "/")
+            .with_status(500)
+            .create_async()
+            .await;
+        let zil = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+
+        assert_eq!(zil.node_flavour().await, NodeFlavour::Zq1);
+        broken.remove_async().await;
+
+        let _version = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "Version": "v1.3.0", "Commit": "" } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+
+        assert_eq!(zil.node_flavour().await, NodeFlavour::Zq2);
+        assert_eq!(zil.flavours().get(&server.url()), Some(&NodeFlavour::Zq2));
+    }
+}
@@ -0,0 +1,41 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Connectivity {
+    #[default]
+    Online,
+    Degraded(usize), // number of nodes that failed before one answered
+    Offline,
+}
+
+impl Connectivity {
+    pub fn from_failures(failed: usize, answered: bool) -> Self {
+        match (failed, answered) {
+            (_, false) => Connectivity::Offline,
+            (0, true) => Connectivity::Online,
+            (n, true) => Connectivity::Degraded(n),
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        matches!(self, Connectivity::Offline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_failures() {
+        assert_eq!(Connectivity::from_failures(0, true), Connectivity::Online);
+        assert_eq!(
+            Connectivity::from_failures(2, true),
+            Connectivity::Degraded(2)
+        );
+        assert_eq!(Connectivity::from_failures(0, false), Connectivity::Offline);
+        assert_eq!(Connectivity::from_failures(3, false), Connectivity::Offline);
+
+        assert!(Connectivity::Offline.is_offline());
+        assert!(!Connectivity::Degraded(1).is_offline());
+        assert!(!Connectivity::default().is_offline());
+    }
+}
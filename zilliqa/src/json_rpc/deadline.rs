@@ -0,0 +1,299 @@
+use crate::json_rpc::{
+    broadcast::BroadcastQueue,
+    zil::ZilliqaJsonRPC,
+    zil_interfaces::{CreateTransactionRes, GetBalanceRes},
+    zil_methods::ZilMethods,
+};
+use proto::{gas_price::GasPriceBounds, zil_tx::ZilAmount};
+use serde_json::{json, Value};
+use std::{future::Future, time::Duration};
+use tokio::time::{timeout_at, Instant};
+use zil_errors::{gas_price::GasPriceErrors, ZilliqaErrors};
+
+pub const STEP_BALANCE: &str = "balance";
+pub const STEP_MIN_GAS_PRICE: &str = "min_gas_price";
+pub const STEP_BROADCAST: &str = "broadcast";
+
+/// One overall deadline for a composite operation. Every nested call gets
+/// only what is left of it, the error names the step that ran out of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now() + timeout)
+    }
+
+    pub fn at(instant: Instant) -> Self {
+        Self(instant)
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    pub async fn run<'a, T, F>(&self, step: &'static str, fut: F) -> Result<T, ZilliqaErrors<'a>>
+    where
+        F: Future<Output = Result<T, ZilliqaErrors<'a>>>,
+    {
+        if self.is_expired() {
+            return Err(ZilliqaErrors::DeadlineExceeded(step));
+        }
+
+        timeout_at(self.0, fut)
+            .await
+            .unwrap_or(Err(ZilliqaErrors::DeadlineExceeded(step)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendPreflight {
+    pub balance: String,
+    pub nonce: u64,
+    pub min_gas_price: String,
+}
+
+impl SendPreflight {
+    // The embedded bounds of `chain_id` with the minimum the node reported.
+    pub fn gas_price_bounds(&self, chain_id: u16) -> Result<GasPriceBounds, GasPriceErrors> {
+        let mut bounds = GasPriceBounds::for_chain(chain_id);
+
+        bounds.refresh(&self.min_gas_price)?;
+
+        Ok(bounds)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendOutcome {
+    Sent(String),   // tx hash
+    Queued(String), // queue id, broadcast once the nodes are back
+}
+
+impl ZilliqaJsonRPC {
+    /// Balance, nonce and min gas price for a send flow, within `deadline`.
+    pub async fn prepare_send<'a>(
+        &self,
+        addr: &str,
+        deadline: &Deadline,
+    ) -> Result<SendPreflight, ZilliqaErrors<'a>> {
+        let balance: GetBalanceRes = deadline
+            .run(
+                STEP_BALANCE,
+                self.call(json!([addr]), ZilMethods::GetBalance),
+            )
+            .await?;
+        let min_gas_price: String = deadline
+            .run(
+                STEP_MIN_GAS_PRICE,
+                self.call(json!([]), ZilMethods::GetMinimumGasPrice),
+            )
+            .await?;
+
+        Ok(SendPreflight {
+            balance: balance.balance,
+            nonce: balance.nonce,
+            min_gas_price,
+        })
+    }
+
+    // Returns the tx hash. A gas price outside `bounds` is refused before
+    // anything is sent, a warning level price is the caller's to confirm.
+    pub async fn broadcast_within<'a>(
+        &self,
+        payload: Value,
+        bounds: &GasPriceBounds,
+        deadline: &Deadline,
+    ) -> Result<String, ZilliqaErrors<'a>> {
+        check_gas_price(&payload, bounds)?;
+
+        let res: CreateTransactionRes = deadline
+            .run(
+                STEP_BROADCAST,
+                self.call(json!([payload]), ZilMethods::CreateTransaction),
+            )
+            .await?;
+
+        Ok(res.tran_id)
+    }
+
+    /// Like [Self::broadcast_within], but while every node is unreachable the
+    /// signed payload goes into `queue` under `id` instead of failing.
+    pub async fn broadcast_or_enqueue<'a>(
+        &self,
+        id: String,
+        payload: Value,
+        bounds: &GasPriceBounds,
+        deadline: &Deadline,
+        queue: &mut BroadcastQueue,
+        now: u64,
+    ) -> Result<SendOutcome, ZilliqaErrors<'a>> {
+        check_gas_price(&payload, bounds)?;
+
+        if !self.connectivity().is_offline() {
+            match self
+                .broadcast_within(payload.clone(), bounds, deadline)
+                .await
+            {
+                Ok(hash) => return Ok(SendOutcome::Sent(hash)),
+                Err(_) if self.connectivity().is_offline() => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        queue.enqueue(id.clone(), payload, now);
+
+        Ok(SendOutcome::Queued(id))
+    }
+}
+
+fn check_gas_price<'a>(payload: &Value, bounds: &GasPriceBounds) -> Result<(), ZilliqaErrors<'a>> {
+    let gas_price = match &payload["gasPrice"] {
+        Value::String(s) => s.parse::<u128>().ok(),
+        value => value.as_u64().map(u128::from),
+    }
+    .ok_or(ZilliqaErrors::InvalidPayload)?;
+
+    bounds
+        .check(ZilAmount::from_raw(gas_price))
+        .map_err(ZilliqaErrors::GasPrice)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn rpc_body(result: Value) -> String {
+        json!([{ "id": 1, "jsonrpc": "2.0", "result": result }]).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_prepare_send() {
+        let mut server = mockito::Server::new_async().await;
+        let _balance = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("GetBalance".to_string()))
+            .with_body(rpc_body(json!({ "balance": "100", "nonce": 7 })))
+            .create_async()
+            .await;
+        let _gas = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("GetMinimumGasPrice".to_string()))
+            .with_body(rpc_body(json!("2000000000")))
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let res = rpc
+            .prepare_send("abc", &Deadline::after(Duration::from_secs(5)))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            res,
+            SendPreflight {
+                balance: "100".to_string(),
+                nonce: 7,
+                min_gas_price: "2000000000".to_string(),
+            }
+        );
+        assert_eq!(
+            res.gas_price_bounds(1).unwrap().max(),
+            proto::zil_tx::ZilAmount::from_raw(2_000_000_000_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deadline_exceeded_step() {
+        // accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let rpc = ZilliqaJsonRPC::from_vec(vec![url]);
+
+        let bounds = GasPriceBounds::for_chain(1);
+        let deadline = Deadline::after(Duration::from_millis(100));
+
+        assert_eq!(
+            rpc.broadcast_within(json!({ "gasPrice": "1" }), &bounds, &deadline)
+                .await,
+            Err(ZilliqaErrors::GasPrice(GasPriceErrors::TooLow(
+                1,
+                2_000_000_000
+            )))
+        );
+
+        let res = rpc
+            .broadcast_within(json!({ "gasPrice": "2000000000" }), &bounds, &deadline)
+            .await;
+
+        assert_eq!(res, Err(ZilliqaErrors::DeadlineExceeded(STEP_BROADCAST)));
+
+        let expired = Deadline::at(Instant::now());
+
+        assert_eq!(
+            rpc.prepare_send("abc", &expired).await,
+            Err(ZilliqaErrors::DeadlineExceeded(STEP_BALANCE))
+        );
+
+        drop(listener);
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_when_offline() {
+        // nothing listens there anymore
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let rpc = ZilliqaJsonRPC::from_vec(vec![url]);
+        let bounds = GasPriceBounds::for_chain(1);
+        let deadline = Deadline::after(Duration::from_secs(5));
+        let mut queue = BroadcastQueue::default();
+        let payload = json!({ "gasPrice": "2000000000" });
+
+        assert_eq!(
+            rpc.broadcast_or_enqueue(
+                "a".to_string(),
+                json!({ "gasPrice": "1" }),
+                &bounds,
+                &deadline,
+                &mut queue,
+                0
+            )
+            .await,
+            Err(ZilliqaErrors::GasPrice(GasPriceErrors::TooLow(
+                1,
+                2_000_000_000
+            )))
+        );
+        assert!(queue.entries().is_empty());
+
+        let res = rpc
+            .broadcast_or_enqueue(
+                "a".to_string(),
+                payload.clone(),
+                &bounds,
+                &deadline,
+                &mut queue,
+                0,
+            )
+            .await;
+
+        assert_eq!(res, Ok(SendOutcome::Queued("a".to_string())));
+        assert!(rpc.connectivity().is_offline());
+
+        // already offline, goes straight into the queue
+        let res = rpc
+            .broadcast_or_enqueue("b".to_string(), payload, &bounds, &deadline, &mut queue, 0)
+            .await;
+
+        assert_eq!(res, Ok(SendOutcome::Queued("b".to_string())));
+        assert_eq!(queue.entries().len(), 2);
+    }
+}
@@ -0,0 +1,502 @@
+use crate::json_rpc::{
+    broadcast::is_transient,
+    zil::ZilliqaJsonRPC,
+    zil_interfaces::{CreateTransactionRes, ResultRes},
+    zil_methods::ZilMethods,
+};
+use config::broadcast::{CONTRACT_CALL_GAS_LIMIT, TRANSFER_GAS_LIMIT};
+use proto::{
+    address::Address,
+    gas_price::{GasPriceBounds, GasPriceCheck},
+    zil_tx::{ScillaGas, ZILTransactionRequest, ZilAmount},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use zil_errors::{flow::FlowErrors, gas_price::GasPriceErrors};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    Planned,
+    Signed(Value), // CreateTransaction params
+    Submitted(String),
+    Confirmed(String),
+    Failed(String),
+    Cancelled, // an earlier step failed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowState {
+    Pending,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowStep {
+    pub label: String, // "approve", "swap", ..
+    pub request: ZILTransactionRequest,
+    pub status: StepStatus,
+}
+
+/// Chains dependent transactions (approve then swap, wrap then transfer)
+/// from one sender, nonces follow each other starting at `next_nonce`.
+#[derive(Debug, Clone)]
+pub struct FlowBuilder {
+    chain_id: u16,
+    next_nonce: u64,
+    gas_price: ZilAmount,
+    gas_price_bounds: GasPriceBounds,
+    steps: Vec<FlowStep>,
+    default_gas: Vec<usize>, // steps still on the static limit
+}
+
+impl FlowBuilder {
+    pub fn new(chain_id: u16, next_nonce: u64, gas_price: ZilAmount) -> Self {
+        Self {
+            chain_id,
+            next_nonce,
+            gas_price,
+            gas_price_bounds: GasPriceBounds::for_chain(chain_id),
+            steps: Vec::new(),
+            default_gas: Vec::new(),
+        }
+    }
+
+    // Bounds refreshed from the node, the embedded ones otherwise.
+    pub fn gas_price_bounds(mut self, bounds: GasPriceBounds) -> Self {
+        self.gas_price_bounds = bounds;
+        self
+    }
+
+    // For a warning before `build`, which only refuses the errors.
+    pub fn check_gas_price(&self) -> Result<GasPriceCheck, GasPriceErrors> {
+        self.gas_price_bounds.check(self.gas_price)
+    }
+
+    // Plain transfers and contract calls get the default gas limit until
+    // `estimate_gas` replaces it.
+    pub fn step(mut self, label: &str, to_addr: Address, amount: ZilAmount, data: &str) -> Self {
+        let gas = if data.is_empty() {
+            TRANSFER_GAS_LIMIT
+        } else {
+            CONTRACT_CALL_GAS_LIMIT
+        };
+
+        self.default_gas.push(self.steps.len());
+        self.step_with_gas(label, to_addr, amount, data, ScillaGas(gas))
+    }
+
+    /// Asks `estimate` for the gas limit of every step added with `step`,
+    /// e.g. from the gas the contract used before. `None` keeps the static
+    /// limit, limits given to `step_with_gas` are never changed.
+    pub fn estimate_gas<E, F>(mut self, mut estimate: F) -> Result<Self, E>
+    where
+        F: FnMut(&ZILTransactionRequest) -> Result<Option<ScillaGas>, E>,
+    {
+        for &index in &self.default_gas {
+            let request = &mut self.steps[index].request;
+
+            if let Some(gas) = estimate(request)? {
+                request.gas_limit = gas;
+            }
+        }
+
+        Ok(self)
+    }
+
+    pub fn step_with_gas(
+        mut self,
+        label: &str,
+        to_addr: Address,
+        amount: ZilAmount,
+        data: &str,
+        gas_limit: ScillaGas,
+    ) -> Self {
+        let nonce = self.next_nonce + self.steps.len() as u64;
+
+        self.steps.push(FlowStep {
+            label: label.to_string(),
+            request: ZILTransactionRequest {
+                chain_id: self.chain_id,
+                nonce,
+                gas_price: self.gas_price,
+                gas_limit,
+                to_addr,
+                amount,
+                code: String::new(),
+                data: data.to_string(),
+            },
+            status: StepStatus::Planned,
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<Flow, FlowErrors> {
+        if self.steps.is_empty() {
+            return Err(FlowErrors::EmptyFlow);
+        }
+
+        self.check_gas_price()?;
+
+        Ok(Flow { steps: self.steps })
+    }
+}
+
+/// Tracked as a whole: a failing step cancels every step after it, and
+/// a step is only submitted once the previous one is confirmed on chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flow {
+    steps: Vec<FlowStep>,
+}
+
+impl Flow {
+    pub fn steps(&self) -> &[FlowStep] {
+        &self.steps
+    }
+
+    // Amounts plus max fees of every step, in raw (10^-12) ZIL units.
+    pub fn total_cost(&self) -> u128 {
+        self.steps
+            .iter()
+            .map(|s| {
+                let fee = s.request.gas_price.raw() * u128::from(s.request.gas_limit.0);
+
+                s.request.amount.raw().saturating_add(fee)
+            })
+            .fold(0u128, u128::saturating_add)
+    }
+
+    /// Signs every step up front so nothing is broadcast unless all of them
+    /// could be signed.
+    pub fn sign<E, F>(&mut self, mut sign: F) -> Result<(), E>
+    where
+        F: FnMut(&ZILTransactionRequest) -> Result<Value, E>,
+    {
+        let payloads = self
+            .steps
+            .iter()
+            .map(|s| sign(&s.request))
+            .collect::<Result<Vec<Value>, E>>()?;
+
+        for (step, payload) in self.steps.iter_mut().zip(payloads) {
+            step.status = StepStatus::Signed(payload);
+        }
+
+        Ok(())
+    }
+
+    pub fn state(&self) -> FlowState {
+        let failed = self
+            .steps
+            .iter()
+            .any(|s| matches!(s.status, StepStatus::Failed(_)));
+        let done = self
+            .steps
+            .iter()
+            .all(|s| matches!(s.status, StepStatus::Confirmed(_)));
+
+        match (failed, done) {
+            (true, _) => FlowState::Failed,
+            (false, true) => FlowState::Completed,
+            (false, false) => FlowState::Pending,
+        }
+    }
+
+    // Sender nonces are unique per chain, so the first one names the flow.
+    fn session(&self) -> String {
+        let first = &self.steps[0].request;
+
+        format!("flow:{}:{}", first.chain_id, first.nonce)
+    }
+
+    /// Moves the flow forward as far as it can without waiting: polls the
+    /// submitted step and submits the next one once it is confirmed. Network
+    /// trouble leaves the state untouched, so calling it again is safe.
+    /// Every call of one flow goes to the same node while it stays healthy,
+    /// a lagging node can't lose a step submitted to another one.
+    pub async fn advance(&mut self, rpc: &ZilliqaJsonRPC) -> Result<FlowState, FlowErrors> {
+        if self.state() != FlowState::Pending {
+            return Err(FlowErrors::Finished);
+        }
+
+        let session = self.session();
+
+        while let Some(index) = self
+            .steps
+            .iter()
+            .position(|s| !matches!(s.status, StepStatus::Confirmed(_)))
+        {
+            let status = match &self.steps[index].status {
+                StepStatus::Planned => return Err(FlowErrors::NotSigned(index)),
+                StepStatus::Signed(payload) => submit(rpc, &session, payload).await?,
+                StepStatus::Submitted(hash) => match confirmation(rpc, &session, hash).await? {
+                    Some(status) => status,
+                    None => break,
+                },
+                StepStatus::Confirmed(_) | StepStatus::Failed(_) | StepStatus::Cancelled => break,
+            };
+            let confirmed = matches!(status, StepStatus::Confirmed(_));
+            let failed = matches!(status, StepStatus::Failed(_));
+
+            self.steps[index].status = status;
+
+            if failed {
+                self.cancel_after(index);
+            }
+
+            if !confirmed {
+                break;
+            }
+        }
+
+        let state = self.state();
+
+        if state != FlowState::Pending {
+            rpc.end_session(&session);
+        }
+
+        Ok(state)
+    }
+
+    fn cancel_after(&mut self, index: usize) {
+        for step in self.steps.iter_mut().skip(index + 1) {
+            step.status = StepStatus::Cancelled;
+        }
+    }
+}
+
+// RPC errors end the step, transport errors are left for the next call.
+async fn submit(
+    rpc: &ZilliqaJsonRPC,
+    session: &str,
+    payload: &Value,
+) -> Result<StepStatus, FlowErrors> {
+    let res: ResultRes<CreateTransactionRes> = request(
+        rpc,
+        session,
+        ZilliqaJsonRPC::build_payload(json!([payload]), ZilMethods::CreateTransaction),
+    )
+    .await?;
+
+    match (res.result, res.error) {
+        (_, Some(error)) if is_transient(&error.message) => {
+            Err(FlowErrors::RpcError(error.message))
+        }
+        (_, Some(error)) => Ok(StepStatus::Failed(error.message)),
+        (Some(res), None) => Ok(StepStatus::Submitted(res.tran_id)),
+        (None, None) => Err(FlowErrors::RpcError("empty response".to_string())),
+    }
+}
+
+// None while the tx is not in a block yet, the node answers with an
+// error ("Txn Hash not Present") until then.
+async fn confirmation(
+    rpc: &ZilliqaJsonRPC,
+    session: &str,
+    hash: &str,
+) -> Result<Option<StepStatus>, FlowErrors> {
+    let res: ResultRes<Value> = request(
+        rpc,
+        session,
+        ZilliqaJsonRPC::build_payload(json!([hash]), ZilMethods::GetTransaction),
+    )
+    .await?;
+    let success = res
+        .result
+        .as_ref()
+        .and_then(|tx| tx.get("receipt"))
+        .and_then(|r| r.get("success"))
+        .and_then(Value::as_bool);
+
+    Ok(success.map(|ok| {
+        if ok {
+            StepStatus::Confirmed(hash.to_string())
+        } else {
+            StepStatus::Failed(format!("{hash} failed on chain"))
+        }
+    }))
+}
+
+async fn request<T>(
+    rpc: &ZilliqaJsonRPC,
+    session: &str,
+    payload: Value,
+) -> Result<ResultRes<T>, FlowErrors>
+where
+    T: DeserializeOwned + std::fmt::Debug,
+{
+    let mut res: Vec<ResultRes<T>> = rpc
+        .reqwest_sticky(session, vec![payload])
+        .await
+        .map_err(|e| FlowErrors::RpcError(format!("{e:?}")))?;
+
+    res.pop()
+        .ok_or(FlowErrors::RpcError("empty response".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    const TOKEN: &str = "00000000000000000000000000000000000000a1";
+    const DEX: &str = "00000000000000000000000000000000000000b2";
+
+    fn rpc_body(result: Value) -> String {
+        json!([{ "id": 1, "jsonrpc": "2.0", "result": result }]).to_string()
+    }
+
+    fn approve_swap() -> Flow {
+        FlowBuilder::new(1, 7, ZilAmount::from_raw(2_000_000_000))
+            .step(
+                "approve",
+                Address::from_zil_base16(TOKEN).unwrap(),
+                ZilAmount::from_raw(0),
+                r#"{"_tag":"IncreaseAllowance"}"#,
+            )
+            .step(
+                "swap",
+                Address::from_zil_base16(DEX).unwrap(),
+                ZilAmount::from_raw(1_000),
+                r#"{"_tag":"SwapExactTokensForZIL"}"#,
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_build_nonces_and_cost() {
+        let flow = approve_swap();
+
+        assert_eq!(flow.steps()[0].request.nonce, 7);
+        assert_eq!(flow.steps()[1].request.nonce, 8);
+        assert_eq!(
+            flow.total_cost(),
+            1_000 + 2 * 2_000_000_000 * u128::from(CONTRACT_CALL_GAS_LIMIT)
+        );
+        assert_eq!(
+            FlowBuilder::new(1, 0, ZilAmount::from_raw(1)).build(),
+            Err(FlowErrors::EmptyFlow)
+        );
+    }
+
+    #[test]
+    fn test_estimate_gas() {
+        let token = Address::from_zil_base16(TOKEN).unwrap();
+        let flow = FlowBuilder::new(1, 7, ZilAmount::from_raw(2_000_000_000))
+            .step("approve", token.clone(), ZilAmount::from_raw(0), "{}")
+            .step_with_gas(
+                "swap",
+                Address::from_zil_base16(DEX).unwrap(),
+                ZilAmount::from_raw(0),
+                "{}",
+                ScillaGas(30_000),
+            )
+            .step("send", token.clone(), ZilAmount::from_raw(1), "")
+            .estimate_gas(|req| {
+                Ok::<_, FlowErrors>(
+                    (req.to_addr == token && !req.data.is_empty()).then_some(ScillaGas(1_200)),
+                )
+            })
+            .unwrap()
+            .build()
+            .unwrap();
+        let limits: Vec<u64> = flow.steps().iter().map(|s| s.request.gas_limit.0).collect();
+
+        assert_eq!(limits, vec![1_200, 30_000, TRANSFER_GAS_LIMIT]);
+    }
+
+    #[test]
+    fn test_gas_price_bounds() {
+        let builder = |gas_price: u128| {
+            FlowBuilder::new(1, 0, ZilAmount::from_raw(gas_price)).step(
+                "send",
+                Address::from_zil_base16(DEX).unwrap(),
+                ZilAmount::from_raw(1_000),
+                "",
+            )
+        };
+
+        assert_eq!(
+            builder(50_000_000_000).check_gas_price(),
+            Ok(GasPriceCheck::High { factor: 25 })
+        );
+        assert!(builder(50_000_000_000).build().is_ok());
+        assert_eq!(
+            builder(1_000_000_000).build(),
+            Err(FlowErrors::GasPrice(GasPriceErrors::TooLow(
+                1_000_000_000,
+                2_000_000_000
+            )))
+        );
+
+        // the node lowered its minimum
+        let mut bounds = GasPriceBounds::for_chain(1);
+
+        bounds.refresh("1000000000").unwrap();
+
+        assert!(builder(1_000_000_000)
+            .gas_price_bounds(bounds)
+            .build()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_staged_broadcast() {
+        let mut server = mockito::Server::new_async().await;
+        let create_first = server
+            .mock("POST", "/")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex("CreateTransaction".to_string()),
+                Matcher::Regex(r#""nonce":7"#.to_string()),
+            ]))
+            .with_body(rpc_body(json!({ "Info": "", "TranID": "h7" })))
+            .expect(1)
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let mut flow = approve_swap();
+
+        assert_eq!(flow.advance(&rpc).await, Err(FlowErrors::NotSigned(0)));
+
+        flow.sign(|req| Ok::<_, ()>(json!({ "nonce": req.nonce })))
+            .unwrap();
+
+        assert_eq!(flow.advance(&rpc).await, Ok(FlowState::Pending));
+        assert_eq!(
+            flow.steps()[0].status,
+            StepStatus::Submitted("h7".to_string())
+        );
+        // the swap waits for the approval
+        assert!(matches!(flow.steps()[1].status, StepStatus::Signed(_)));
+
+        create_first.assert_async().await;
+
+        let _mined = server
+            .mock("POST", "/")
+            .match_body(Matcher::Regex("GetTransaction".to_string()))
+            .with_body(rpc_body(json!({ "receipt": { "success": true } })))
+            .create_async()
+            .await;
+        let _rejected = server
+            .mock("POST", "/")
+            .match_body(Matcher::Regex(r#""nonce":8"#.to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "error": { "code": -8, "message": "Insufficient balance" } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+
+        assert_eq!(flow.advance(&rpc).await, Ok(FlowState::Failed));
+        assert_eq!(
+            flow.steps()[0].status,
+            StepStatus::Confirmed("h7".to_string())
+        );
+        assert_eq!(
+            flow.steps()[1].status,
+            StepStatus::Failed("Insufficient balance".to_string())
+        );
+        assert_eq!(flow.advance(&rpc).await, Err(FlowErrors::Finished));
+    }
+}
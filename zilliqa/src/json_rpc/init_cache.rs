@@ -0,0 +1,126 @@
+use crate::json_rpc::{zil::ZilliqaJsonRPC, zil_interfaces::ContractInitParam};
+use config::storage::CONTRACT_INIT_TREE;
+use std::rc::Rc;
+use storage::LocalStorage;
+use zil_errors::ZilliqaErrors;
+
+/// Permanent cache of `GetSmartContractInit` results. Init params are
+/// immutable, so entries only go away through [ContractInitCache::invalidate]
+/// (a devnet redeploy to the same address).
+pub struct ContractInitCache {
+    storage: Rc<LocalStorage>,
+}
+
+impl ContractInitCache {
+    pub fn new(storage: Rc<LocalStorage>) -> Self {
+        Self { storage }
+    }
+
+    pub fn get<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+    ) -> Result<Option<Vec<ContractInitParam>>, ZilliqaErrors<'a>> {
+        let bytes = self
+            .storage
+            .tree_get(CONTRACT_INIT_TREE, &Self::key(network, contract))
+            .map_err(ZilliqaErrors::CacheStorageError)?;
+
+        // a broken entry is refetched
+        Ok(bytes.and_then(|b| serde_json::from_slice(&b).ok()))
+    }
+
+    pub fn insert<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+        init: &[ContractInitParam],
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        let bytes = serde_json::to_vec(init).or(Err(ZilliqaErrors::InvalidPayload))?;
+
+        self.storage
+            .tree_set(CONTRACT_INIT_TREE, &Self::key(network, contract), &bytes)
+            .map_err(ZilliqaErrors::CacheStorageError)
+    }
+
+    pub async fn get_or_fetch<'a>(
+        &self,
+        rpc: &ZilliqaJsonRPC,
+        network: &str,
+        contract: &str,
+    ) -> Result<Vec<ContractInitParam>, ZilliqaErrors<'a>> {
+        if let Some(init) = self.get(network, contract)? {
+            return Ok(init);
+        }
+
+        let init = rpc.get_smart_contract_init(contract).await?;
+
+        self.insert(network, contract, &init)?;
+
+        Ok(init)
+    }
+
+    pub fn invalidate<'a>(&self, network: &str, contract: &str) -> Result<bool, ZilliqaErrors<'a>> {
+        self.storage
+            .tree_remove(CONTRACT_INIT_TREE, &Self::key(network, contract))
+            .map_err(ZilliqaErrors::CacheStorageError)
+    }
+
+    // Address case and 0x prefix don't matter.
+    pub(crate) fn key(network: &str, contract: &str) -> Vec<u8> {
+        let contract = contract.trim_start_matches("0x").to_lowercase();
+
+        format!("{network}:{contract}").into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONTRACT: &str = "0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1";
+
+    #[tokio::test]
+    async fn test_get_or_fetch() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "result": [
+                        { "vname": "_scilla_version", "type": "Uint32", "value": "0" },
+                        { "vname": "name", "type": "String", "value": "Token" }
+                    ]
+                }])
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let cache = ContractInitCache::new(storage);
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+
+        let init = cache.get_or_fetch(&rpc, "mainnet", CONTRACT).await.unwrap();
+        let cached = cache
+            .get_or_fetch(
+                &rpc,
+                "mainnet",
+                &CONTRACT.to_uppercase().replace("0X", "0x"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(init.len(), 2);
+        assert_eq!(init, cached);
+        assert_eq!(cache.get("testnet", CONTRACT).unwrap(), None);
+        assert!(cache.invalidate("mainnet", CONTRACT).unwrap());
+        cache.get_or_fetch(&rpc, "mainnet", CONTRACT).await.unwrap();
+
+        mock.assert_async().await;
+    }
+}
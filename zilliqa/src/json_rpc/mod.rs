@@ -1,4 +1,38 @@
+#[cfg(feature = "rpc")]
+pub mod allowance_cache;
+#[cfg(feature = "rpc")]
+pub mod broadcast;
+#[cfg(feature = "evm")]
+pub mod bundler;
+#[cfg(feature = "rpc")]
+pub mod compat;
+pub mod connectivity;
+#[cfg(feature = "rpc")]
+pub mod deadline;
+#[cfg(feature = "evm")]
 pub mod evm;
+#[cfg(feature = "rpc")]
+pub mod flow;
+#[cfg(feature = "rpc")]
+pub mod init_cache;
+#[cfg(feature = "rpc")]
+pub mod journal;
+pub mod node_selector;
+#[cfg(feature = "rpc")]
+pub mod prefetch;
+#[cfg(feature = "staking")]
+pub mod staking;
+#[cfg(feature = "ws")]
+pub mod subscription;
+#[cfg(feature = "rpc")]
+pub mod token_overrides;
+#[cfg(feature = "rpc")]
+pub mod transport;
+#[cfg(feature = "rpc")]
+pub mod tx_block_stream;
+#[cfg(feature = "rpc")]
+pub mod verification;
+#[cfg(feature = "rpc")]
 pub mod zil;
 pub mod zil_interfaces;
 pub mod zil_methods;
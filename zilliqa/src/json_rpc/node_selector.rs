@@ -0,0 +1,146 @@
+use config::node::{NODE_MAX_SCORE, NODE_SUCCESS_BONUS};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Picks nodes by weighted random over their health score. A logical session
+/// (e.g. a send flow reading nonce and balance) sticks to one node until that
+/// node fails.
+#[derive(Debug, Default)]
+pub struct NodeSelector {
+    scores: Vec<u32>,
+    sticky: HashMap<String, usize>,
+}
+
+impl NodeSelector {
+    pub fn score(&self, node: usize) -> u32 {
+        self.scores.get(node).copied().unwrap_or(NODE_MAX_SCORE)
+    }
+
+    pub fn select<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        nodes_len: usize,
+        session: Option<&str>,
+    ) -> Option<usize> {
+        self.select_excluding(rng, nodes_len, session, &[])
+    }
+
+    // Same as [Self::select] but never returns a node from `tried`.
+    pub fn select_excluding<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        nodes_len: usize,
+        session: Option<&str>,
+        tried: &[usize],
+    ) -> Option<usize> {
+        self.scores.resize(nodes_len, NODE_MAX_SCORE);
+
+        if let Some(node) = session.and_then(|s| self.sticky.get(s)) {
+            if *node < nodes_len && self.scores[*node] > 0 && !tried.contains(node) {
+                return Some(*node);
+            }
+        }
+
+        let weights: Vec<u32> = self
+            .scores
+            .iter()
+            .enumerate()
+            .map(|(i, score)| if tried.contains(&i) { 0 } else { *score })
+            .collect();
+        let total: u32 = weights.iter().sum();
+        let node = if total == 0 {
+            // everything is down, spread the retries
+            let left: Vec<usize> = (0..nodes_len).filter(|i| !tried.contains(i)).collect();
+
+            (!left.is_empty()).then(|| left[rng.gen_range(0..left.len())])?
+        } else {
+            let mut point = rng.gen_range(0..total);
+
+            weights.iter().position(|score| {
+                if point < *score {
+                    true
+                } else {
+                    point -= score;
+                    false
+                }
+            })?
+        };
+
+        if let Some(session) = session {
+            self.sticky.insert(session.to_string(), node);
+        }
+
+        Some(node)
+    }
+
+    pub fn report_success(&mut self, node: usize) {
+        if let Some(score) = self.scores.get_mut(node) {
+            *score = (*score + NODE_SUCCESS_BONUS).min(NODE_MAX_SCORE);
+        }
+    }
+
+    // Halves the score and unsticks every session on the node.
+    pub fn report_failure(&mut self, node: usize) {
+        if let Some(score) = self.scores.get_mut(node) {
+            *score /= 2;
+        }
+
+        self.sticky.retain(|_, n| *n != node);
+    }
+
+    pub fn end_session(&mut self, session: &str) {
+        self.sticky.remove(session);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_sticky_session() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut selector = NodeSelector::default();
+        let node = selector.select(&mut rng, 5, Some("send")).unwrap();
+
+        for _ in 0..20 {
+            assert_eq!(selector.select(&mut rng, 5, Some("send")), Some(node));
+        }
+
+        selector.report_failure(node);
+        selector.report_failure(node);
+        selector.report_failure(node);
+
+        let restuck = selector.select(&mut rng, 5, Some("send")).unwrap();
+
+        assert_eq!(selector.select(&mut rng, 5, Some("send")), Some(restuck));
+
+        selector.end_session("send");
+
+        assert!(selector.sticky.is_empty());
+    }
+
+    #[test]
+    fn test_weighted() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut selector = NodeSelector::default();
+
+        selector.select(&mut rng, 2, None);
+
+        // node 0 is down, only node 1 should be picked
+        for _ in 0..7 {
+            selector.report_failure(0);
+        }
+
+        for _ in 0..50 {
+            assert_eq!(selector.select(&mut rng, 2, None), Some(1));
+        }
+
+        selector.report_success(0);
+
+        assert_eq!(selector.score(0), NODE_SUCCESS_BONUS);
+        assert_eq!(NodeSelector::default().select(&mut rng, 0, None), None);
+    }
+}
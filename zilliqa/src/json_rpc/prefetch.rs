@@ -0,0 +1,390 @@
+use crate::json_rpc::{zil::ZilliqaJsonRPC, zil_interfaces::ResultRes, zil_methods::ZilMethods};
+use config::storage::PREFETCH_CACHE_TREE;
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, future::Future, pin::Pin, rc::Rc};
+use storage::LocalStorage;
+
+/// In priority order, what the first screen needs comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PrefetchStage {
+    Balance,
+    Tokens,
+    History,
+    Prices,
+}
+
+impl PrefetchStage {
+    pub const ALL: [PrefetchStage; 4] = [
+        PrefetchStage::Balance,
+        PrefetchStage::Tokens,
+        PrefetchStage::History,
+        PrefetchStage::Prices,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrefetchStage::Balance => "balance",
+            PrefetchStage::Tokens => "tokens",
+            PrefetchStage::History => "history",
+            PrefetchStage::Prices => "prices",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefetchEvent {
+    Cached { stage: PrefetchStage, data: Value },
+    Live { stage: PrefetchStage, data: Value },
+    // the live fetch failed with every node down, the cache is all there is
+    Stale { stage: PrefetchStage, data: Value },
+    Failed { stage: PrefetchStage, error: String },
+    Finished,
+}
+
+pub type StageFuture<'a> = Pin<Box<dyn Future<Output = Result<Value, String>> + 'a>>;
+// Live source of a stage core can't fetch itself (indexer history, rates).
+pub type StageFetcher<'a> = Box<dyn Fn() -> StageFuture<'a> + 'a>;
+
+/// Cold-start pipeline run on unlock: every cached stage is emitted right
+/// away, then live data is fetched stage by stage in priority order.
+pub struct Prefetch<'a> {
+    rpc: &'a ZilliqaJsonRPC,
+    chain_id: u16,
+    account: String, // base16, as the node expects it
+    tokens: Vec<String>,
+    cache: Option<Rc<LocalStorage>>,
+    fetchers: HashMap<PrefetchStage, StageFetcher<'a>>,
+}
+
+impl<'a> Prefetch<'a> {
+    pub fn new(rpc: &'a ZilliqaJsonRPC, chain_id: u16, account: &str) -> Self {
+        Self {
+            rpc,
+            chain_id,
+            account: account.trim_start_matches("0x").to_lowercase(),
+            tokens: Vec::new(),
+            cache: None,
+            fetchers: HashMap::new(),
+        }
+    }
+
+    pub fn tokens(mut self, contracts: Vec<String>) -> Self {
+        self.tokens = contracts;
+        self
+    }
+
+    pub fn cache(mut self, storage: Rc<LocalStorage>) -> Self {
+        self.cache = Some(storage);
+        self
+    }
+
+    pub fn fetcher(mut self, stage: PrefetchStage, fetcher: StageFetcher<'a>) -> Self {
+        self.fetchers.insert(stage, fetcher);
+        self
+    }
+
+    // Stages without a source are skipped.
+    fn stages(&self) -> Vec<PrefetchStage> {
+        PrefetchStage::ALL
+            .into_iter()
+            .filter(|stage| match stage {
+                _ if self.fetchers.contains_key(stage) => true,
+                PrefetchStage::Balance => true,
+                PrefetchStage::Tokens => !self.tokens.is_empty(),
+                PrefetchStage::History | PrefetchStage::Prices => false,
+            })
+            .collect()
+    }
+
+    pub async fn run(&self, mut on_event: impl FnMut(PrefetchEvent)) {
+        let stages = self.stages();
+
+        for stage in &stages {
+            if let Some(data) = self.cached(*stage) {
+                on_event(PrefetchEvent::Cached {
+                    stage: *stage,
+                    data,
+                });
+            }
+        }
+
+        for stage in stages {
+            let event = match self.fetch(stage).await {
+                Ok(data) => {
+                    self.store(stage, &data);
+
+                    PrefetchEvent::Live { stage, data }
+                }
+                Err(error) => match self.cached(stage) {
+                    Some(data) if self.rpc.connectivity().is_offline() => {
+                        PrefetchEvent::Stale { stage, data }
+                    }
+                    _ => PrefetchEvent::Failed { stage, error },
+                },
+            };
+
+            on_event(event);
+        }
+
+        on_event(PrefetchEvent::Finished);
+    }
+
+    async fn fetch(&self, stage: PrefetchStage) -> Result<Value, String> {
+        if let Some(fetcher) = self.fetchers.get(&stage) {
+            return fetcher().await;
+        }
+
+        match stage {
+            PrefetchStage::Balance => self
+                .rpc
+                .call(json!([self.account]), ZilMethods::GetBalance)
+                .await
+                .map_err(|e| format!("{e:?}")),
+            PrefetchStage::Tokens => self.token_balances().await,
+            PrefetchStage::History | PrefetchStage::Prices => Err("no source".to_string()),
+        }
+    }
+
+    // One batch for every token, a holder missing from the map owns none.
+    // Tokens the node failed on are left out rather than reported as zero.
+    async fn token_balances(&self) -> Result<Value, String> {
+        let holder = format!("0x{}", self.account);
+        let payloads = self
+            .tokens
+            .iter()
+            .enumerate()
+            .map(|(id, contract)| {
+                let mut payload = ZilliqaJsonRPC::build_payload(
+                    json!([contract, "balances", [holder]]),
+                    ZilMethods::GetSmartContractSubState,
+                );
+
+                payload["id"] = json!(id);
+                payload
+            })
+            .collect();
+        let res: Vec<ResultRes<Value>> = self
+            .rpc
+            .reqwest(payloads)
+            .await
+            .map_err(|e| format!("{e:?}"))?;
+        let mut errors = Vec::new();
+        let mut balances = Map::new();
+
+        // batch answers may come back in any order
+        for res in res {
+            let Some(contract) = self.tokens.get(res.id as usize) else {
+                continue;
+            };
+
+            match (res.result, res.error) {
+                (Some(state), None) => {
+                    let balance = state["balances"].get(&holder).cloned();
+
+                    balances.insert(contract.clone(), balance.unwrap_or_else(|| json!("0")));
+                }
+                (_, error) => errors.push(error.map(|e| e.message).unwrap_or_default()),
+            }
+        }
+
+        if balances.is_empty() && !errors.is_empty() {
+            return Err(errors.join(", "));
+        }
+
+        Ok(Value::Object(balances))
+    }
+
+    fn cached(&self, stage: PrefetchStage) -> Option<Value> {
+        let bytes = self
+            .cache
+            .as_ref()?
+            .tree_get(PREFETCH_CACHE_TREE, &self.cache_key(stage))
+            .ok()??;
+
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    // A failing cache write only costs the next cold start its head start.
+    fn store(&self, stage: PrefetchStage, data: &Value) {
+        let mut data = data.clone();
+
+        // tokens that failed this time keep their last known balance
+        if let (PrefetchStage::Tokens, Some(Value::Object(mut cached))) =
+            (stage, self.cached(stage))
+        {
+            cached.retain(|contract, _| self.tokens.contains(contract));
+            cached.extend(data.as_object().cloned().unwrap_or_default());
+            data = Value::Object(cached);
+        }
+
+        if let (Some(cache), Ok(bytes)) = (&self.cache, serde_json::to_vec(&data)) {
+            let _ = cache.tree_set(PREFETCH_CACHE_TREE, &self.cache_key(stage), &bytes);
+        }
+    }
+
+    fn cache_key(&self, stage: PrefetchStage) -> Vec<u8> {
+        format!("{}:{}:{}", self.chain_id, self.account, stage.name()).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    const ACCOUNT: &str = "0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1";
+    const TOKEN: &str = "0x00000000000000000000000000000000000000a1";
+
+    #[tokio::test]
+    async fn test_cached_then_live() {
+        let mut server = mockito::Server::new_async().await;
+        let _balance = server
+            .mock("POST", "/")
+            .match_body(Matcher::Regex("GetBalance".to_string()))
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "balance": "100", "nonce": 3 } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let _tokens = server
+            .mock("POST", "/")
+            .match_body(Matcher::Regex("GetSmartContractSubState".to_string()))
+            .with_body(
+                json!([{ "id": 0, "jsonrpc": "2.0", "result": { "balances": { ACCOUNT: "42" } } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let storage =
+            Rc::new(LocalStorage::from(&format!("/tmp/{}", rand::random::<usize>())).unwrap());
+        let prefetch = || {
+            Prefetch::new(&rpc, 1, ACCOUNT)
+                .tokens(vec![TOKEN.to_string()])
+                .cache(Rc::clone(&storage))
+                .fetcher(
+                    PrefetchStage::Prices,
+                    Box::new(|| Box::pin(async { Ok(json!({ "zil": 0.02 })) })),
+                )
+        };
+        let mut events = Vec::new();
+
+        prefetch().run(|e| events.push(e)).await;
+
+        assert_eq!(
+            events,
+            vec![
+                PrefetchEvent::Live {
+                    stage: PrefetchStage::Balance,
+                    data: json!({ "balance": "100", "nonce": 3 }),
+                },
+                PrefetchEvent::Live {
+                    stage: PrefetchStage::Tokens,
+                    data: json!({ TOKEN: "42" }),
+                },
+                PrefetchEvent::Live {
+                    stage: PrefetchStage::Prices,
+                    data: json!({ "zil": 0.02 }),
+                },
+                PrefetchEvent::Finished,
+            ]
+        );
+
+        let mut events = Vec::new();
+
+        prefetch().run(|e| events.push(e)).await;
+
+        // the cache of every stage renders before any request returns
+        assert!(events[..3]
+            .iter()
+            .all(|e| matches!(e, PrefetchEvent::Cached { .. })));
+        assert_eq!(events.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_stale_when_offline() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let rpc = ZilliqaJsonRPC::from_vec(vec![url]);
+        let storage =
+            Rc::new(LocalStorage::from(&format!("/tmp/{}", rand::random::<usize>())).unwrap());
+        let history = json!([{ "hash": "0x01" }]);
+        let prefetch = Prefetch::new(&rpc, 1, ACCOUNT)
+            .cache(Rc::clone(&storage))
+            .fetcher(
+                PrefetchStage::History,
+                Box::new(|| Box::pin(async { Err("indexer down".to_string()) })),
+            );
+
+        prefetch.store(
+            PrefetchStage::Balance,
+            &json!({ "balance": "7", "nonce": 1 }),
+        );
+        prefetch.store(PrefetchStage::History, &history);
+
+        let mut events = Vec::new();
+
+        prefetch.run(|e| events.push(e)).await;
+
+        assert!(rpc.connectivity().is_offline());
+        assert_eq!(
+            events[2..],
+            [
+                PrefetchEvent::Stale {
+                    stage: PrefetchStage::Balance,
+                    data: json!({ "balance": "7", "nonce": 1 }),
+                },
+                PrefetchEvent::Stale {
+                    stage: PrefetchStage::History,
+                    data: history,
+                },
+                PrefetchEvent::Finished,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_errors_and_networks() {
+        const OTHER: &str = "0x00000000000000000000000000000000000000b2";
+
+        let mut server = mockito::Server::new_async().await;
+        // answered out of order, the first token failed
+        let _tokens = server
+            .mock("POST", "/")
+            .match_body(Matcher::Regex("GetSmartContractSubState".to_string()))
+            .with_body(
+                json!([
+                    { "id": 1, "jsonrpc": "2.0", "result": { "balances": {} } },
+                    { "id": 0, "jsonrpc": "2.0", "error": { "code": -5, "message": "busy" } },
+                ])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let storage =
+            Rc::new(LocalStorage::from(&format!("/tmp/{}", rand::random::<usize>())).unwrap());
+        let prefetch = |chain_id| {
+            Prefetch::new(&rpc, chain_id, ACCOUNT)
+                .tokens(vec![TOKEN.to_string(), OTHER.to_string()])
+                .cache(Rc::clone(&storage))
+        };
+
+        assert_eq!(
+            prefetch(1).token_balances().await,
+            Ok(json!({ OTHER: "0" }))
+        );
+
+        prefetch(1).store(PrefetchStage::Tokens, &json!({ TOKEN: "5", OTHER: "1" }));
+        prefetch(1).store(PrefetchStage::Tokens, &json!({ OTHER: "0" }));
+
+        assert_eq!(
+            prefetch(1).cached(PrefetchStage::Tokens),
+            Some(json!({ TOKEN: "5", OTHER: "0" }))
+        );
+        assert_eq!(prefetch(333).cached(PrefetchStage::Tokens), None);
+    }
+}
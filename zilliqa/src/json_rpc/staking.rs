@@ -0,0 +1,152 @@
+use crate::json_rpc::{zil::ZilliqaJsonRPC, zil_interfaces::ResultRes, zil_methods::ZilMethods};
+use proto::portfolio::StakeSummary;
+use serde_json::{json, Value};
+use zil_errors::ZilliqaErrors;
+
+const DEPOSITS_FIELD: &str = "deposit_amt_deleg";
+const WITHDRAWALS_FIELD: &str = "withdrawal_pending";
+
+impl ZilliqaJsonRPC {
+    /// Staked and pending withdrawal amounts of `delegator` (base16 with 0x)
+    /// in the staking contract. Rewards are computed per cycle by the staking
+    /// manager and come in as `unclaimed_rewards`.
+    pub async fn get_stake_summary<'a>(
+        &self,
+        contract: &str,
+        delegator: &str,
+        unclaimed_rewards: u128,
+    ) -> Result<StakeSummary, ZilliqaErrors<'a>> {
+        let delegator = delegator.to_lowercase();
+        let deposits = self
+            .get_substate(contract, DEPOSITS_FIELD, &delegator)
+            .await?;
+        let withdrawals = self
+            .get_substate(contract, WITHDRAWALS_FIELD, &delegator)
+            .await?;
+
+        Ok(StakeSummary {
+            staked: sum_substate(deposits.as_ref(), DEPOSITS_FIELD, &delegator)?,
+            pending_withdrawals: sum_substate(withdrawals.as_ref(), WITHDRAWALS_FIELD, &delegator)?,
+            unclaimed_rewards,
+        })
+    }
+
+    // The node answers null when the map has no entry for the key.
+    async fn get_substate<'a>(
+        &self,
+        contract: &str,
+        field: &str,
+        key: &str,
+    ) -> Result<Option<Value>, ZilliqaErrors<'a>> {
+        let payloads = vec![Self::build_payload(
+            json!([contract, field, [key]]),
+            ZilMethods::GetSmartContractSubState,
+        )];
+        let mut res: Vec<ResultRes<Value>> = self.reqwest(payloads).await?;
+        let res = res.pop().ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        if let Some(error) = res.error {
+            return Err(ZilliqaErrors::InvalidRPCReq(error.message));
+        }
+
+        Ok(res.result)
+    }
+}
+
+// `{ field: { delegator: { key: "amount", .. } } }`, a missing entry (the node
+// answers null) means nothing staked.
+fn sum_substate<'a>(
+    state: Option<&Value>,
+    field: &str,
+    delegator: &str,
+) -> Result<u128, ZilliqaErrors<'a>> {
+    let Some(entries) = state
+        .and_then(|s| s.get(field))
+        .and_then(|f| f.get(delegator))
+    else {
+        return Ok(0);
+    };
+    let entries = entries
+        .as_object()
+        .ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+    entries.values().try_fold(0u128, |acc, amount| {
+        let amount: u128 = amount
+            .as_str()
+            .and_then(|a| a.parse().ok())
+            .ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        acc.checked_add(amount)
+            .ok_or(ZilliqaErrors::FailToParseResponse)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELEGATOR: &str = "0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1";
+
+    #[test]
+    fn test_sum_substate() {
+        let state = json!({
+            DEPOSITS_FIELD: {
+                DELEGATOR: {
+                    "0x122219cceab410901e96c3a0e55e46231480341b": "1000",
+                    "0x9bfa14e0a2f6e41e4e17a63ebbf9a46c0aa2e8e5": "250"
+                }
+            }
+        });
+
+        assert_eq!(
+            sum_substate(Some(&state), DEPOSITS_FIELD, DELEGATOR),
+            Ok(1250)
+        );
+        assert_eq!(sum_substate(None, DEPOSITS_FIELD, DELEGATOR), Ok(0));
+
+        let broken = json!({ DEPOSITS_FIELD: { DELEGATOR: { "ssn": 1 } } });
+
+        assert_eq!(
+            sum_substate(Some(&broken), DEPOSITS_FIELD, DELEGATOR),
+            Err(ZilliqaErrors::FailToParseResponse)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_stake_summary() {
+        let mut server = mockito::Server::new_async().await;
+        let _deposits = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(DEPOSITS_FIELD.to_string()))
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "result": { DEPOSITS_FIELD: { DELEGATOR: { "0xssn": "500" } } }
+                }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let _withdrawals = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(WITHDRAWALS_FIELD.to_string()))
+            .with_body(json!([{ "id": 1, "jsonrpc": "2.0", "result": null }]).to_string())
+            .create_async()
+            .await;
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let summary = rpc
+            .get_stake_summary("0xcontract", DELEGATOR, 7)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            StakeSummary {
+                staked: 500,
+                pending_withdrawals: 0,
+                unclaimed_rewards: 7,
+            }
+        );
+    }
+}
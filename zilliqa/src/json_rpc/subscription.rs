@@ -0,0 +1,300 @@
+use config::node::{RECONNECT_BASE_DELAY_MS, RECONNECT_MAX_DELAY_MS};
+use std::{future::Future, ops::ControlFlow, pin::Pin, time::Duration};
+use tokio::sync::mpsc;
+use zil_errors::ZilliqaErrors;
+
+/// Events of a live feed (new blocks, event logs) know the block they
+/// belong to, a reconnect resumes from there.
+pub trait BlockEvent {
+    fn block(&self) -> u64;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionEvent<E> {
+    Event(E),
+    Backfilled {
+        from: u64,
+        count: usize,
+    },
+    Disconnected,
+    Reconnecting {
+        attempt: u32,
+        delay_ms: u64,
+        error: String,
+    },
+}
+
+pub type SubscribeFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + 'a>>;
+// Opens the feed, e.g. a WebSocket subscription. The feed is over once the
+// sender is dropped.
+pub type Connector<'a, E> = Box<dyn FnMut() -> SubscribeFuture<'a, mpsc::Receiver<E>> + 'a>;
+// Events from the given block up to the head, queried over plain RPC.
+pub type Backfiller<'a, E> = Box<dyn FnMut(u64) -> SubscriptionFuture<'a, E> + 'a>;
+pub type SubscriptionFuture<'a, E> = SubscribeFuture<'a, Vec<E>>;
+
+/// Keeps a live feed running across network blips: a dropped feed is
+/// reopened with exponential backoff, then the blocks missed in between are
+/// backfilled before live events go on. A block is expected to arrive with
+/// all of its events, live duplicates of backfilled blocks are dropped.
+pub struct Subscription<'a, E: BlockEvent> {
+    connect: Connector<'a, E>,
+    backfill: Backfiller<'a, E>,
+    max_attempts: Option<u32>,
+    last_block: Option<u64>,
+}
+
+impl<'a, E: BlockEvent> Subscription<'a, E> {
+    pub fn new(connect: Connector<'a, E>, backfill: Backfiller<'a, E>) -> Self {
+        Self {
+            connect,
+            backfill,
+            max_attempts: None,
+            last_block: None,
+        }
+    }
+
+    // Failed reconnects in a row before giving up, unlimited by default.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    // Resume from a block seen in an earlier session.
+    pub fn resume_after(mut self, block: u64) -> Self {
+        self.last_block = Some(block);
+        self
+    }
+
+    pub fn last_block(&self) -> Option<u64> {
+        self.last_block
+    }
+
+    pub fn backoff(attempts: u32) -> u64 {
+        RECONNECT_BASE_DELAY_MS
+            .saturating_mul(2u64.saturating_pow(attempts))
+            .min(RECONNECT_MAX_DELAY_MS)
+    }
+
+    /// Runs until `on_event` breaks, or fails with `NetowrkIsDown` after
+    /// `max_attempts` failed reconnects. A feed that closes before its
+    /// first live event counts as a failed reconnect.
+    pub async fn run<F>(&mut self, mut on_event: F) -> Result<(), ZilliqaErrors<'a>>
+    where
+        F: FnMut(SubscriptionEvent<E>) -> ControlFlow<()>,
+    {
+        let mut failures = 0u32;
+
+        loop {
+            let resume_from = self.last_block.and_then(|block| block.checked_add(1));
+            let error = match self.open(resume_from).await {
+                Ok((mut feed, missed)) => {
+                    if let Some(from) = resume_from {
+                        let count = missed.len();
+
+                        for event in missed {
+                            if self.deliver(event, &mut on_event).is_break() {
+                                return Ok(());
+                            }
+                        }
+
+                        if on_event(SubscriptionEvent::Backfilled { from, count }).is_break() {
+                            return Ok(());
+                        }
+                    }
+
+                    // backfilled blocks are complete, live copies of them are dropped
+                    let floor = self.last_block;
+                    let mut delivered = false;
+
+                    while let Some(event) = feed.recv().await {
+                        if floor.is_some_and(|floor| event.block() <= floor) {
+                            continue;
+                        }
+
+                        delivered = true;
+                        failures = 0;
+
+                        if self.deliver(event, &mut on_event).is_break() {
+                            return Ok(());
+                        }
+                    }
+
+                    if on_event(SubscriptionEvent::Disconnected).is_break() {
+                        return Ok(());
+                    }
+
+                    if delivered {
+                        continue;
+                    }
+
+                    "feed closed without events".to_string()
+                }
+                Err(error) => error,
+            };
+
+            failures += 1;
+
+            if self.max_attempts.is_some_and(|max| failures >= max) {
+                return Err(ZilliqaErrors::NetowrkIsDown);
+            }
+
+            let delay_ms = Self::backoff(failures - 1);
+
+            if on_event(SubscriptionEvent::Reconnecting {
+                attempt: failures,
+                delay_ms,
+                error,
+            })
+            .is_break()
+            {
+                return Ok(());
+            }
+
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
+
+    // The feed is opened first so nothing arriving during backfill is lost.
+    async fn open(
+        &mut self,
+        resume_from: Option<u64>,
+    ) -> Result<(mpsc::Receiver<E>, Vec<E>), String> {
+        let feed = (self.connect)().await?;
+        let mut missed = match resume_from {
+            Some(from) => (self.backfill)(from).await?,
+            None => Vec::new(),
+        };
+
+        missed.sort_by_key(BlockEvent::block);
+
+        Ok((feed, missed))
+    }
+
+    fn deliver<F>(&mut self, event: E, on_event: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(SubscriptionEvent<E>) -> ControlFlow<()>,
+    {
+        self.last_block = self.last_block.max(Some(event.block()));
+
+        on_event(SubscriptionEvent::Event(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct NewBlock(u64);
+
+    impl BlockEvent for NewBlock {
+        fn block(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn feed(blocks: &[u64]) -> mpsc::Receiver<NewBlock> {
+        let (tx, rx) = mpsc::channel(blocks.len().max(1));
+
+        for block in blocks {
+            tx.try_send(NewBlock(*block)).unwrap();
+        }
+
+        rx
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnect_backfill() {
+        let connects = Rc::new(Cell::new(0));
+        let calls = Rc::clone(&connects);
+        let mut subscription = Subscription::new(
+            Box::new(move || {
+                calls.set(calls.get() + 1);
+
+                let result = match calls.get() {
+                    1 => Ok(feed(&[1, 2])),
+                    2 => Err("connection refused".to_string()),
+                    _ => Ok(feed(&[4, 5])),
+                };
+
+                Box::pin(async move { result })
+            }),
+            Box::new(|from| {
+                assert_eq!(from, 3);
+
+                Box::pin(async { Ok(vec![NewBlock(4), NewBlock(3)]) })
+            }),
+        );
+        let mut events = Vec::new();
+
+        subscription
+            .run(|event| {
+                let done = event == SubscriptionEvent::Event(NewBlock(5));
+
+                events.push(event);
+
+                if done {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                SubscriptionEvent::Event(NewBlock(1)),
+                SubscriptionEvent::Event(NewBlock(2)),
+                SubscriptionEvent::Disconnected,
+                SubscriptionEvent::Reconnecting {
+                    attempt: 1,
+                    delay_ms: RECONNECT_BASE_DELAY_MS,
+                    error: "connection refused".to_string()
+                },
+                SubscriptionEvent::Event(NewBlock(3)),
+                SubscriptionEvent::Event(NewBlock(4)),
+                SubscriptionEvent::Backfilled { from: 3, count: 2 },
+                SubscriptionEvent::Event(NewBlock(5)),
+            ]
+        );
+        assert_eq!(connects.get(), 3);
+        assert_eq!(subscription.last_block(), Some(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_empty_feed_backs_off() {
+        let mut subscription = Subscription::new(
+            Box::new(|| Box::pin(async { Ok(feed(&[u64::MAX])) })),
+            Box::new(|_| unreachable!("nothing comes after the last block")),
+        )
+        .resume_after(u64::MAX)
+        .max_attempts(3);
+        let mut events = Vec::new();
+        let result = subscription
+            .run(|event| {
+                events.push(event);
+                ControlFlow::Continue(())
+            })
+            .await;
+        let reconnecting = |attempt, delay_ms| SubscriptionEvent::Reconnecting {
+            attempt,
+            delay_ms,
+            error: "feed closed without events".to_string(),
+        };
+
+        assert_eq!(result, Err(ZilliqaErrors::NetowrkIsDown));
+        assert_eq!(
+            events,
+            vec![
+                SubscriptionEvent::Disconnected,
+                reconnecting(1, RECONNECT_BASE_DELAY_MS),
+                SubscriptionEvent::Disconnected,
+                reconnecting(2, Subscription::<NewBlock>::backoff(1)),
+                SubscriptionEvent::Disconnected,
+            ]
+        );
+    }
+}
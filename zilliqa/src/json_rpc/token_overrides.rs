@@ -0,0 +1,206 @@
+use crate::json_rpc::{
+    init_cache::ContractInitCache, zil::ZilliqaJsonRPC, zil_interfaces::ContractInitParam,
+};
+use config::storage::TOKEN_OVERRIDES_TREE;
+use proto::token_meta::{MetadataOverride, TokenMetadata};
+use std::rc::Rc;
+use storage::LocalStorage;
+use zil_errors::ZilliqaErrors;
+
+/// User corrections of token metadata, persisted per network and contract.
+/// Everything that shows or parses token amounts should get its metadata
+/// from [TokenOverrides::fetch] instead of reading the init params directly.
+pub struct TokenOverrides {
+    storage: Rc<LocalStorage>,
+}
+
+impl TokenOverrides {
+    pub fn new(storage: Rc<LocalStorage>) -> Self {
+        Self { storage }
+    }
+
+    pub fn get<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+    ) -> Result<Option<MetadataOverride>, ZilliqaErrors<'a>> {
+        let bytes = self
+            .storage
+            .tree_get(
+                TOKEN_OVERRIDES_TREE,
+                &ContractInitCache::key(network, contract),
+            )
+            .map_err(ZilliqaErrors::CacheStorageError)?;
+
+        Ok(bytes.and_then(|b| serde_json::from_slice(&b).ok()))
+    }
+
+    // An empty override removes the entry.
+    pub fn set<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+        fix: &MetadataOverride,
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        if fix.is_empty() {
+            return self.remove(network, contract).map(|_| ());
+        }
+
+        let bytes = serde_json::to_vec(fix).or(Err(ZilliqaErrors::InvalidPayload))?;
+
+        self.storage
+            .tree_set(
+                TOKEN_OVERRIDES_TREE,
+                &ContractInitCache::key(network, contract),
+                &bytes,
+            )
+            .map_err(ZilliqaErrors::CacheStorageError)
+    }
+
+    pub fn remove<'a>(&self, network: &str, contract: &str) -> Result<bool, ZilliqaErrors<'a>> {
+        self.storage
+            .tree_remove(
+                TOKEN_OVERRIDES_TREE,
+                &ContractInitCache::key(network, contract),
+            )
+            .map_err(ZilliqaErrors::CacheStorageError)
+    }
+
+    // Init params from the cache (fetched once), then `resolve`.
+    pub async fn fetch<'a>(
+        &self,
+        rpc: &ZilliqaJsonRPC,
+        network: &str,
+        contract: &str,
+    ) -> Result<TokenMetadata, ZilliqaErrors<'a>> {
+        let init = ContractInitCache::new(Rc::clone(&self.storage))
+            .get_or_fetch(rpc, network, contract)
+            .await?;
+
+        self.resolve(network, contract, &init)
+    }
+
+    /// Metadata from the contract init with the local correction applied. A
+    /// broken `decimals` in the init is fine as long as it is overridden.
+    pub fn resolve<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+        init: &[ContractInitParam],
+    ) -> Result<TokenMetadata, ZilliqaErrors<'a>> {
+        let fix = self.get(network, contract)?.unwrap_or_default();
+        let field = |name: &str| {
+            init.iter()
+                .find(|p| p.vname == name)
+                .and_then(|p| p.value.as_str())
+        };
+        let decimals = fix
+            .decimals
+            .or_else(|| field("decimals").and_then(|d| d.parse().ok()))
+            .ok_or(ZilliqaErrors::FailToParseResponse)?;
+        let meta = TokenMetadata {
+            name: field("name").unwrap_or_default().to_string(),
+            symbol: field("symbol").unwrap_or_default().to_string(),
+            decimals,
+        };
+
+        Ok(meta.with_override(&fix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::asset::TokenAmount;
+    use serde_json::json;
+
+    const CONTRACT: &str = "0x7793a8e8c09d189d4d421ce5bc5b3674656c5ac1";
+
+    fn param(vname: &str, value: &str) -> ContractInitParam {
+        ContractInitParam {
+            vname: vname.to_string(),
+            param_type: "String".to_string(),
+            value: json!(value),
+        }
+    }
+
+    #[test]
+    fn test_resolve_with_override() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let overrides = TokenOverrides::new(Rc::new(LocalStorage::from(&dir).unwrap()));
+        let init = vec![
+            param("name", "Token"),
+            param("symbol", "TKN"),
+            param("decimals", "not a number"),
+        ];
+
+        assert_eq!(
+            overrides.resolve("mainnet", CONTRACT, &init),
+            Err(ZilliqaErrors::FailToParseResponse)
+        );
+
+        let fix = MetadataOverride {
+            decimals: Some(6),
+            ..Default::default()
+        };
+
+        overrides.set("mainnet", CONTRACT, &fix).unwrap();
+
+        let meta = overrides.resolve("mainnet", CONTRACT, &init).unwrap();
+
+        assert_eq!(meta.symbol, "TKN");
+        assert_eq!(
+            meta.format_amount(&TokenAmount::from_u128(2_500_000)),
+            "2.5"
+        );
+        assert!(overrides.resolve("testnet", CONTRACT, &init).is_err());
+
+        overrides
+            .set("mainnet", CONTRACT, &MetadataOverride::default())
+            .unwrap();
+
+        assert_eq!(overrides.get("mainnet", CONTRACT).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_applies_override() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "result": [param("symbol", "TKN"), param("decimals", "18")]
+                }])
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let overrides = TokenOverrides::new(Rc::new(LocalStorage::from(&dir).unwrap()));
+        let rpc = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let fix = MetadataOverride {
+            decimals: Some(6),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            overrides
+                .fetch(&rpc, "mainnet", CONTRACT)
+                .await
+                .unwrap()
+                .decimals,
+            18
+        );
+
+        overrides.set("mainnet", CONTRACT, &fix).unwrap();
+
+        // the init comes from the cache, the override is applied on top
+        let meta = overrides.fetch(&rpc, "mainnet", CONTRACT).await.unwrap();
+
+        assert_eq!((meta.symbol.as_str(), meta.decimals), ("TKN", 6));
+        mock.assert_async().await;
+    }
+}
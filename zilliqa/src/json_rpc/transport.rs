@@ -0,0 +1,224 @@
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Resolve, Resolving};
+use settings::transport::TransportConfig;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use zil_errors::ZilliqaErrors;
+
+/// Builds the HTTP clients used for RPC calls. Proxy (Tor/SOCKS), static
+/// DNS entries and timeouts come from [TransportConfig], a custom resolver
+/// (e.g. DNS over HTTPS) can be plugged in with [Transport::with_resolver].
+#[derive(Clone, Default)]
+pub struct Transport {
+    pub config: TransportConfig,
+    resolver: Option<Arc<dyn Resolve>>,
+    clients: Arc<Mutex<ClientCache>>,
+}
+
+// Built clients per circuit, valid for the config they were built with.
+#[derive(Default)]
+struct ClientCache {
+    config: TransportConfig,
+    clients: HashMap<usize, reqwest::Client>,
+}
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transport")
+            .field("config", &self.config)
+            .field("resolver", &self.resolver.is_some())
+            .finish()
+    }
+}
+
+struct SharedResolver(Arc<dyn Resolve>);
+
+impl Resolve for SharedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        self.0.resolve(name)
+    }
+}
+
+impl Transport {
+    pub fn new(config: TransportConfig) -> Self {
+        Self {
+            config,
+            resolver: None,
+            clients: Arc::default(),
+        }
+    }
+
+    pub fn with_resolver(mut self, resolver: Arc<dyn Resolve>) -> Self {
+        self.resolver = Some(resolver);
+        self.clients = Arc::default();
+        self
+    }
+
+    /// Client for one attempt. With `rotate_circuit` every `circuit` gets its
+    /// own proxy credentials, so Tor builds a fresh circuit on failover.
+    /// Clients are built once per circuit and reused, keeping their
+    /// connection pool, until the config changes.
+    pub fn client<'a>(&self, circuit: usize) -> Result<reqwest::Client, ZilliqaErrors<'a>> {
+        // only built clients are stored, a poisoned lock is still usable
+        let mut cache = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+
+        if cache.config != self.config {
+            cache.config = self.config.clone();
+            cache.clients.clear();
+        }
+
+        if let Some(client) = cache.clients.get(&circuit) {
+            return Ok(client.clone());
+        }
+
+        let client = self.build_client(circuit)?;
+
+        cache.clients.insert(circuit, client.clone());
+
+        Ok(client)
+    }
+
+    fn build_client<'a>(&self, circuit: usize) -> Result<reqwest::Client, ZilliqaErrors<'a>> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(proxy) = &self.config.proxy {
+            let mut proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| ZilliqaErrors::InvalidTransport(e.to_string()))?;
+
+            if self.config.rotate_circuit {
+                proxy = proxy.basic_auth(&format!("zilpay-{circuit}"), "zilpay");
+            }
+
+            builder = builder.proxy(proxy);
+        }
+
+        for entry in &self.config.dns {
+            builder = builder.resolve_to_addrs(&entry.host, &entry.addrs);
+        }
+
+        if let Some(resolver) = &self.resolver {
+            builder = builder.dns_resolver(Arc::new(SharedResolver(Arc::clone(resolver))));
+        }
+
+        if let Some(timeout) = self.config.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout));
+        }
+
+        builder
+            .build()
+            .map_err(|e| ZilliqaErrors::InvalidTransport(e.to_string()))
+    }
+
+    // Circuit for the next attempt after `failed` failovers.
+    pub fn circuit(&self, failed: usize) -> usize {
+        if self.config.rotate_circuit {
+            failed
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_rpc::zil::ZilliqaJsonRPC;
+    use serde_json::json;
+    use settings::transport::DnsOverride;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_invalid_proxy() {
+        let transport = Transport::new(TransportConfig {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        });
+
+        assert!(matches!(
+            transport.client(0),
+            Err(ZilliqaErrors::InvalidTransport(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_cache() {
+        let mut transport = Transport::new(TransportConfig {
+            rotate_circuit: true,
+            ..Default::default()
+        });
+        let cached = |t: &Transport| t.clients.lock().unwrap().clients.len();
+
+        transport.client(0).unwrap();
+        transport.client(0).unwrap();
+        transport.clone().client(1).unwrap();
+
+        assert_eq!(cached(&transport), 2);
+
+        transport.config.timeout_ms = Some(1000);
+        transport.client(0).unwrap();
+
+        assert_eq!(cached(&transport), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dns_override() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/")
+            .with_body(json!([{ "id": 1, "jsonrpc": "2.0", "result": "1" }]).to_string())
+            .create_async()
+            .await;
+        let transport = Transport::new(TransportConfig {
+            dns: vec![DnsOverride {
+                host: "zilliqa.node".to_string(),
+                addrs: vec![server.socket_address()],
+            }],
+            ..Default::default()
+        });
+        let url = format!("http://zilliqa.node:{}", server.socket_address().port());
+        let rpc = ZilliqaJsonRPC::from_vec(vec![url]).with_transport(transport);
+        let res: serde_json::Value = rpc.reqwest(vec![json!({})]).await.unwrap();
+
+        assert_eq!(res[0]["result"], "1");
+    }
+
+    struct CountingResolver(AtomicUsize, std::net::SocketAddr);
+
+    impl Resolve for CountingResolver {
+        fn resolve(&self, _name: Name) -> Resolving {
+            let addr = self.1;
+
+            self.0.fetch_add(1, Ordering::SeqCst);
+
+            Box::pin(async move {
+                let addrs: reqwest::dns::Addrs = Box::new(std::iter::once(addr));
+
+                Ok(addrs)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_resolver() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/")
+            .with_body(json!([{ "id": 1, "jsonrpc": "2.0", "result": "1" }]).to_string())
+            .create_async()
+            .await;
+        let resolver = Arc::new(CountingResolver(
+            AtomicUsize::new(0),
+            server.socket_address(),
+        ));
+        let transport = Transport::default().with_resolver(resolver.clone());
+        let url = format!("http://doh.node:{}", server.socket_address().port());
+        let rpc = ZilliqaJsonRPC::from_vec(vec![url]).with_transport(transport);
+        let _: serde_json::Value = rpc.reqwest(vec![json!({})]).await.unwrap();
+
+        assert_eq!(resolver.0.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,439 @@
+use crate::json_rpc::connectivity::Connectivity;
+use crate::json_rpc::journal::JournalEntry;
+use crate::json_rpc::zil::ZilliqaJsonRPC;
+use crate::json_rpc::zil_interfaces::{ResultRes, TxBlockPage};
+use crate::json_rpc::zil_methods::ZilMethods;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::time::Instant;
+use zil_errors::ZilliqaErrors;
+
+/// Push parser for responses carrying one huge array, like the shards or
+/// bodies of a tx block. Elements of the array under `key` are handed out
+/// one at a time as the body arrives, everything else is kept as a small
+/// skeleton document with that array left empty.
+#[derive(Debug)]
+pub struct TxArrayDecoder {
+    key: &'static [u8],
+    skeleton: Vec<u8>,
+    item: Vec<u8>,
+    token: Vec<u8>, // last string seen outside the array
+    key_state: KeyState,
+    depth: usize,
+    array_depth: Option<usize>, // set while inside the array
+    done: bool,                 // only the first match is streamed
+    in_string: bool,
+    escaped: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyState {
+    None,
+    Seen,  // `"key"`
+    Armed, // `"key":`
+}
+
+impl TxArrayDecoder {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key: key.as_bytes(),
+            skeleton: Vec::new(),
+            item: Vec::new(),
+            token: Vec::new(),
+            key_state: KeyState::None,
+            depth: 0,
+            array_depth: None,
+            done: false,
+            in_string: false,
+            escaped: false,
+        }
+    }
+
+    pub fn feed<'a>(
+        &mut self,
+        chunk: &[u8],
+        on_item: &mut impl FnMut(&[u8]) -> Result<(), ZilliqaErrors<'a>>,
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        for &b in chunk {
+            match self.array_depth {
+                Some(level) => self.scan_item(b, level, on_item)?,
+                None => self.scan_skeleton(b)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    // The document minus the streamed elements, fails on a truncated body.
+    pub fn finish<'a>(self) -> Result<Vec<u8>, ZilliqaErrors<'a>> {
+        if self.in_string || self.depth != 0 {
+            return Err(ZilliqaErrors::InvalidJson("truncated response".to_string()));
+        }
+
+        Ok(self.skeleton)
+    }
+
+    fn scan_item<'a>(
+        &mut self,
+        b: u8,
+        level: usize,
+        on_item: &mut impl FnMut(&[u8]) -> Result<(), ZilliqaErrors<'a>>,
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        if self.in_string {
+            self.item.push(b);
+            self.string_byte(b);
+
+            return Ok(());
+        }
+
+        match b {
+            b'"' => {
+                self.in_string = true;
+                self.item.push(b);
+            }
+            b'{' | b'[' => {
+                self.depth += 1;
+                self.item.push(b);
+            }
+            b'}' | b']' if self.depth == level => {
+                self.flush_item(on_item)?;
+                self.depth -= 1;
+                self.array_depth = None;
+                self.done = true;
+                self.skeleton.push(b']');
+            }
+            b'}' | b']' => {
+                self.depth -= 1;
+                self.item.push(b);
+            }
+            b',' if self.depth == level => self.flush_item(on_item)?,
+            b if b.is_ascii_whitespace() && self.depth == level => {}
+            b => self.item.push(b),
+        }
+
+        Ok(())
+    }
+
+    fn scan_skeleton<'a>(&mut self, b: u8) -> Result<(), ZilliqaErrors<'a>> {
+        self.skeleton.push(b);
+
+        if self.in_string {
+            if self.string_byte(b) {
+                self.key_state = if self.token == self.key {
+                    KeyState::Seen
+                } else {
+                    KeyState::None
+                };
+            } else {
+                self.token.push(b);
+            }
+
+            return Ok(());
+        }
+
+        match b {
+            b'"' => {
+                self.in_string = true;
+                self.token.clear();
+            }
+            b':' if self.key_state == KeyState::Seen => self.key_state = KeyState::Armed,
+            b'[' if self.key_state == KeyState::Armed && !self.done => {
+                self.depth += 1;
+                self.array_depth = Some(self.depth);
+                self.key_state = KeyState::None;
+            }
+            b'{' | b'[' => {
+                self.depth += 1;
+                self.key_state = KeyState::None;
+            }
+            b'}' | b']' => {
+                self.depth =
+                    self.depth
+                        .checked_sub(1)
+                        .ok_or(ZilliqaErrors::InvalidJson(format!(
+                            "unexpected {}",
+                            b as char
+                        )))?;
+                self.key_state = KeyState::None;
+            }
+            b if b.is_ascii_whitespace() => {}
+            _ => self.key_state = KeyState::None,
+        }
+
+        Ok(())
+    }
+
+    // True when `b` closes the string.
+    fn string_byte(&mut self, b: u8) -> bool {
+        if self.escaped {
+            self.escaped = false;
+        } else if b == b'\\' {
+            self.escaped = true;
+        } else if b == b'"' {
+            self.in_string = false;
+
+            return true;
+        }
+
+        false
+    }
+
+    fn flush_item<'a>(
+        &mut self,
+        on_item: &mut impl FnMut(&[u8]) -> Result<(), ZilliqaErrors<'a>>,
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        if !self.item.is_empty() {
+            on_item(&self.item)?;
+            self.item.clear();
+        }
+
+        Ok(())
+    }
+}
+
+impl ZilliqaJsonRPC {
+    /// Like [Self::get_txn_bodies_for_tx_block_ex] but every body goes to
+    /// `on_body` while the response is still downloading, so a full page is
+    /// never held in memory. The returned page has no transactions.
+    pub async fn stream_txn_bodies_for_tx_block_ex<'a, T>(
+        &self,
+        block: u64,
+        page: u64,
+        mut on_body: impl FnMut(T),
+    ) -> Result<TxBlockPage<T>, ZilliqaErrors<'a>>
+    where
+        T: DeserializeOwned,
+    {
+        self.ensure_supported(&ZilMethods::GetTxnBodiesForTxBlockEx)
+            .await?;
+        self.stream_tx_block_page(
+            ZilMethods::GetTxnBodiesForTxBlockEx,
+            block,
+            page,
+            &mut on_body,
+        )
+        .await
+    }
+
+    pub(crate) async fn stream_tx_block_page<'a, T>(
+        &self,
+        method: ZilMethods,
+        block: u64,
+        page: u64,
+        on_tx: &mut impl FnMut(T),
+    ) -> Result<TxBlockPage<T>, ZilliqaErrors<'a>>
+    where
+        T: DeserializeOwned,
+    {
+        let params = json!([block.to_string(), page.to_string()]);
+        let skeleton = self
+            .stream_array(params, method, "Transactions", on_tx)
+            .await?;
+        let mut res: Vec<ResultRes<TxBlockPage<T>>> = serde_json::from_slice(&skeleton)
+            .map_err(|e| ZilliqaErrors::InvalidJson(e.to_string()))?;
+        let res = res.pop().ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        if let Some(error) = res.error {
+            return Err(ZilliqaErrors::InvalidRPCReq(error.message));
+        }
+
+        res.result.ok_or(ZilliqaErrors::FailToParseResponse)
+    }
+
+    // `GetTransactionsForTxBlock` answers with the shards as the result.
+    pub(crate) async fn stream_result_array<'a, T>(
+        &self,
+        params: Value,
+        method: ZilMethods,
+        on_item: &mut impl FnMut(T),
+    ) -> Result<(), ZilliqaErrors<'a>>
+    where
+        T: DeserializeOwned,
+    {
+        let skeleton = self.stream_array(params, method, "result", on_item).await?;
+        let mut res: Vec<ResultRes<Vec<T>>> = serde_json::from_slice(&skeleton)
+            .map_err(|e| ZilliqaErrors::InvalidJson(e.to_string()))?;
+        let res = res.pop().ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        if let Some(error) = res.error {
+            return Err(ZilliqaErrors::InvalidRPCReq(error.message));
+        }
+
+        res.result
+            .map(|_| ())
+            .ok_or(ZilliqaErrors::FailToParseResponse)
+    }
+
+    // Nodes are tried in order like [Self::reqwest], but only until the
+    // first element went out, a retry would hand it out twice.
+    async fn stream_array<'a, T>(
+        &self,
+        params: Value,
+        method: ZilMethods,
+        key: &'static str,
+        on_item: &mut impl FnMut(T),
+    ) -> Result<Vec<u8>, ZilliqaErrors<'a>>
+    where
+        T: DeserializeOwned,
+    {
+        let payloads = vec![Self::build_payload(params, method)];
+        let mut error = ZilliqaErrors::NetowrkIsDown;
+        let mut delivered = false;
+
+        for (failed, url) in self.nodes.iter().enumerate() {
+            let client = self.transport.client(self.transport.circuit(failed))?;
+            let mut decode = |bytes: &[u8]| {
+                let item = serde_json::from_slice::<T>(bytes)
+                    .map_err(|e| ZilliqaErrors::InvalidJson(e.to_string()))?;
+
+                delivered = true;
+                on_item(item);
+
+                Ok(())
+            };
+
+            match self
+                .post_streaming(&client, url, &payloads, key, &mut decode)
+                .await
+            {
+                Ok(skeleton) => {
+                    self.set_connectivity(Connectivity::from_failures(failed, true));
+
+                    return Ok(skeleton);
+                }
+                Err(e) if delivered => return Err(e),
+                Err(e) => error = e,
+            }
+        }
+
+        self.set_connectivity(Connectivity::Offline);
+
+        Err(error)
+    }
+
+    // [Self::post] reading the body chunk by chunk, the journal only sees
+    // the skeleton.
+    async fn post_streaming<'a>(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        payloads: &[Value],
+        key: &'static str,
+        on_item: &mut impl FnMut(&[u8]) -> Result<(), ZilliqaErrors<'a>>,
+    ) -> Result<Vec<u8>, ZilliqaErrors<'a>> {
+        let started = Instant::now();
+        let mut entry = self.journal().map(|_| JournalEntry::new(url, payloads));
+        let res = match client.post(url).json(payloads).send().await {
+            Ok(mut res) => {
+                if let Some(entry) = entry.as_mut() {
+                    entry.status = Some(res.status().as_u16());
+                }
+
+                let mut decoder = TxArrayDecoder::new(key);
+
+                loop {
+                    match res.chunk().await {
+                        Ok(Some(chunk)) => {
+                            if let Err(e) = decoder.feed(&chunk, on_item) {
+                                break Err(e);
+                            }
+                        }
+                        Ok(None) => break decoder.finish(),
+                        Err(e) => break Err(ZilliqaErrors::InvalidJson(e.to_string())),
+                    }
+                }
+            }
+            Err(e) => Err(ZilliqaErrors::InvalidRPCReq(e.to_string())),
+        };
+
+        if let (Some(journal), Some(mut entry)) = (self.journal(), entry) {
+            match &res {
+                Ok(skeleton) => entry.add_rpc_errors(skeleton),
+                Err(e) => entry.add_error(&format!("{e:?}")),
+            }
+
+            entry.elapsed_ms = started.elapsed().as_millis() as u64;
+            journal.record(&entry);
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_stream_txn_bodies() {
+        let body = json!([{
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": {
+                "CurrPage": 0,
+                "NumPages": 2,
+                "Transactions": [
+                    { "ID": "a1", "data": "{\"Transactions\":[\"]\"]}" },
+                    { "ID": "a2", "receipt": { "event_logs": [[], {}] } }
+                ]
+            }
+        }])
+        .to_string();
+
+        // split at every byte, the state must carry over between chunks
+        let mut items = Vec::new();
+        let mut decoder = TxArrayDecoder::new("Transactions");
+
+        for byte in body.as_bytes().chunks(1) {
+            decoder
+                .feed(byte, &mut |item| {
+                    items.push(serde_json::from_slice::<Value>(item).unwrap());
+                    Ok(())
+                })
+                .unwrap();
+        }
+
+        let skeleton: Value = serde_json::from_slice(&decoder.finish().unwrap()).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["data"], "{\"Transactions\":[\"]\"]}");
+        assert_eq!(items[1]["receipt"]["event_logs"], json!([[], {}]));
+        assert_eq!(skeleton[0]["result"]["Transactions"], json!([]));
+        assert_eq!(skeleton[0]["result"]["NumPages"], 2);
+
+        let mut server = mockito::Server::new_async().await;
+        let _page = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(
+                "GetTxnBodiesForTxBlockEx".to_string(),
+            ))
+            .with_body(&body)
+            .create_async()
+            .await;
+        let zil = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let mut ids = Vec::new();
+        let page = zil
+            .stream_tx_block_page(
+                ZilMethods::GetTxnBodiesForTxBlockEx,
+                7,
+                0,
+                &mut |tx: Value| ids.push(tx["ID"].as_str().unwrap().to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(ids, ["a1", "a2"]);
+        assert!(page.transactions.is_empty());
+        assert_eq!(page.next_page(0), Some(1));
+        assert_eq!(page.next_page(1), None);
+
+        let mut truncated = TxArrayDecoder::new("Transactions");
+
+        truncated
+            .feed(&body.as_bytes()[..body.len() / 2], &mut |_| Ok(()))
+            .unwrap();
+
+        assert!(truncated.finish().is_err());
+    }
+}
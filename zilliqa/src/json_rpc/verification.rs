@@ -0,0 +1,282 @@
+use crate::json_rpc::{
+    init_cache::ContractInitCache, zil::ZilliqaJsonRPC, zil_interfaces::GetSmartContractCodeRes,
+    zil_methods::ZilMethods,
+};
+use config::{
+    contracts::{SOURCIFY_URL, VERIFICATION_RECHECK_MS},
+    storage::CONTRACT_VERIFICATION_TREE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, rc::Rc};
+use storage::LocalStorage;
+use zil_errors::ZilliqaErrors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationStatus {
+    Full,    // sources and metadata match the bytecode exactly
+    Partial, // same bytecode, metadata (comments, names) may differ
+    OnChain, // Scilla, the deployed code is the source
+    Unverified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractWarning {
+    Unverified,
+    PartialMatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedContract {
+    pub status: VerificationStatus,
+    pub abi: Option<Value>,
+    pub sources: BTreeMap<String, String>, // path -> content
+    pub checked_at: u64,
+}
+
+impl VerifiedContract {
+    // What the confirmation screen shows next to a decoded call.
+    pub fn warning(&self) -> Option<ContractWarning> {
+        match self.status {
+            VerificationStatus::Full | VerificationStatus::OnChain => None,
+            VerificationStatus::Partial => Some(ContractWarning::PartialMatch),
+            VerificationStatus::Unverified => Some(ContractWarning::Unverified),
+        }
+    }
+
+    fn unverified(now: u64) -> Self {
+        Self {
+            status: VerificationStatus::Unverified,
+            abi: None,
+            sources: BTreeMap::new(),
+            checked_at: now,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcifyContract {
+    #[serde(rename = "match")]
+    matched: Option<String>,
+    abi: Option<Value>,
+    #[serde(default)]
+    sources: BTreeMap<String, SourcifyFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcifyFile {
+    content: String,
+}
+
+/// Verification status and verified sources of contracts, cached per
+/// network. EVM contracts are looked up on Sourcify, Scilla contracts are
+/// deployed as source and fetched from the node.
+pub struct ContractVerifier {
+    storage: Rc<LocalStorage>,
+    sourcify_url: String,
+    client: reqwest::Client,
+}
+
+impl ContractVerifier {
+    pub fn new(storage: Rc<LocalStorage>) -> Self {
+        Self {
+            storage,
+            sourcify_url: SOURCIFY_URL.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    // A self hosted Sourcify or an explorer serving the same API.
+    pub fn with_sourcify(mut self, url: &str) -> Self {
+        self.sourcify_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub fn cached<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+    ) -> Result<Option<VerifiedContract>, ZilliqaErrors<'a>> {
+        let bytes = self
+            .storage
+            .tree_get(
+                CONTRACT_VERIFICATION_TREE,
+                &ContractInitCache::key(network, contract),
+            )
+            .map_err(ZilliqaErrors::CacheStorageError)?;
+
+        Ok(bytes.and_then(|b| serde_json::from_slice(&b).ok()))
+    }
+
+    pub async fn evm<'a>(
+        &self,
+        network: &str,
+        chain_id: u64,
+        contract: &str,
+        now: u64,
+    ) -> Result<VerifiedContract, ZilliqaErrors<'a>> {
+        if let Some(cached) = self.fresh(network, contract, now)? {
+            return Ok(cached);
+        }
+
+        let url = format!(
+            "{}/v2/contract/{chain_id}/{contract}?fields=abi,sources",
+            self.sourcify_url
+        );
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| ZilliqaErrors::VerifierError(e.to_string()))?;
+        let verified = if res.status() == reqwest::StatusCode::NOT_FOUND {
+            VerifiedContract::unverified(now)
+        } else {
+            let found: SourcifyContract = res
+                .error_for_status()
+                .map_err(|e| ZilliqaErrors::VerifierError(e.to_string()))?
+                .json()
+                .await
+                .map_err(|e| ZilliqaErrors::InvalidJson(e.to_string()))?;
+            let status = match found.matched.as_deref() {
+                Some("exact_match") => VerificationStatus::Full,
+                Some("match") => VerificationStatus::Partial,
+                _ => VerificationStatus::Unverified,
+            };
+
+            VerifiedContract {
+                status,
+                abi: found.abi,
+                sources: found
+                    .sources
+                    .into_iter()
+                    .map(|(path, file)| (path, file.content))
+                    .collect(),
+                checked_at: now,
+            }
+        };
+
+        self.insert(network, contract, &verified)?;
+
+        Ok(verified)
+    }
+
+    // The node refuses addresses without code, those stay unverified.
+    pub async fn scilla<'a>(
+        &self,
+        rpc: &ZilliqaJsonRPC,
+        network: &str,
+        contract: &str,
+        now: u64,
+    ) -> Result<VerifiedContract, ZilliqaErrors<'a>> {
+        if let Some(cached) = self.fresh(network, contract, now)? {
+            return Ok(cached);
+        }
+
+        let res: Result<GetSmartContractCodeRes, _> = rpc
+            .call(json!([contract]), ZilMethods::GetSmartContractCode)
+            .await;
+        let verified = match res {
+            Ok(res) => VerifiedContract {
+                status: VerificationStatus::OnChain,
+                abi: None,
+                sources: BTreeMap::from([("contract.scilla".to_string(), res.code)]),
+                checked_at: now,
+            },
+            Err(ZilliqaErrors::InvalidRPCReq(_)) => VerifiedContract::unverified(now),
+            Err(e) => return Err(e),
+        };
+
+        self.insert(network, contract, &verified)?;
+
+        Ok(verified)
+    }
+
+    fn fresh<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+        now: u64,
+    ) -> Result<Option<VerifiedContract>, ZilliqaErrors<'a>> {
+        Ok(self.cached(network, contract)?.filter(|c| {
+            c.status != VerificationStatus::Unverified
+                || now.saturating_sub(c.checked_at) < VERIFICATION_RECHECK_MS
+        }))
+    }
+
+    fn insert<'a>(
+        &self,
+        network: &str,
+        contract: &str,
+        verified: &VerifiedContract,
+    ) -> Result<(), ZilliqaErrors<'a>> {
+        let bytes = serde_json::to_vec(verified).or(Err(ZilliqaErrors::InvalidPayload))?;
+
+        self.storage
+            .tree_set(
+                CONTRACT_VERIFICATION_TREE,
+                &ContractInitCache::key(network, contract),
+                &bytes,
+            )
+            .map_err(ZilliqaErrors::CacheStorageError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "0x00000000000000000000000000000000000000a1";
+
+    #[tokio::test]
+    async fn test_sourcify_status() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let storage = Rc::new(LocalStorage::from(&dir).unwrap());
+        let mut server = mockito::Server::new_async().await;
+        let verified = server
+            .mock("GET", format!("/v2/contract/32769/{CONTRACT}").as_str())
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                json!({
+                    "match": "exact_match",
+                    "abi": [{ "type": "function", "name": "transfer" }],
+                    "sources": { "Token.sol": { "content": "contract Token {}" } }
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let _unknown = server
+            .mock("GET", "/v2/contract/33101/0xb2")
+            .match_query(mockito::Matcher::Any)
+            .with_status(404)
+            .create_async()
+            .await;
+        let verifier = ContractVerifier::new(storage).with_sourcify(&server.url());
+        let contract = verifier.evm("mainnet", 32769, CONTRACT, 0).await.unwrap();
+
+        assert_eq!(contract.status, VerificationStatus::Full);
+        assert_eq!(contract.warning(), None);
+        assert_eq!(contract.sources["Token.sol"], "contract Token {}");
+        // cached, the mock expects one request
+        assert_eq!(
+            verifier.evm("mainnet", 32769, CONTRACT, 1).await.unwrap(),
+            contract
+        );
+        verified.assert_async().await;
+
+        let unknown = verifier.evm("testnet", 33101, "0xb2", 0).await.unwrap();
+
+        assert_eq!(unknown.warning(), Some(ContractWarning::Unverified));
+        assert_eq!(
+            verifier.cached("testnet", "0xB2").unwrap(),
+            Some(unknown.clone())
+        );
+    }
+}
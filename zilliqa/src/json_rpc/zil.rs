@@ -1,14 +1,33 @@
+use crate::json_rpc::compat::NodeFlavour;
+use crate::json_rpc::connectivity::Connectivity;
+use crate::json_rpc::journal::{JournalEntry, RpcJournal};
+use crate::json_rpc::node_selector::NodeSelector;
+use crate::json_rpc::transport::Transport;
+use crate::json_rpc::zil_interfaces::{
+    ContractInitParam, ResultRes, TxBlockBodiesPage, TxBlockHashesPage, TxBlockPage,
+};
 use crate::json_rpc::zil_methods::ZilMethods;
 use config::contracts::STAKEING;
+use config::node::MAX_TX_BLOCK_PAGES;
 use config::MAIN_URL;
 use reqwest;
 use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
+use settings::network::Network;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+use tokio::sync::watch;
 use zil_errors::ZilliqaErrors;
 
 #[derive(Debug)]
 pub struct ZilliqaJsonRPC {
     pub nodes: Vec<String>,
+    connectivity: watch::Sender<Connectivity>,
+    selector: Mutex<NodeSelector>,
+    pub(crate) transport: Transport,
+    flavours: Mutex<BTreeMap<String, NodeFlavour>>, // by node URL
+    journal: Option<RpcJournal>,
 }
 
 impl Default for ZilliqaJsonRPC {
@@ -20,14 +39,67 @@ impl Default for ZilliqaJsonRPC {
 impl ZilliqaJsonRPC {
     pub fn new() -> Self {
         let nodes = vec![MAIN_URL.to_string()];
-        ZilliqaJsonRPC { nodes }
+
+        Self::from_vec(nodes)
     }
 
     pub fn from_vec(nodes: Vec<String>) -> Self {
-        ZilliqaJsonRPC { nodes }
+        let (connectivity, _) = watch::channel(Connectivity::default());
+
+        ZilliqaJsonRPC {
+            nodes,
+            connectivity,
+            selector: Mutex::new(NodeSelector::default()),
+            transport: Transport::default(),
+            flavours: Mutex::default(),
+            journal: None,
+        }
+    }
+
+    pub fn from_network(network: &Network) -> Self {
+        Self::from_vec(network.rpc_nodes.clone())
+            .with_transport(Transport::new(network.transport.clone()))
     }
 
-    pub async fn bootstrap(node_url: &str) -> Result<Self, ZilliqaErrors> {
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn with_journal(mut self, journal: RpcJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn journal(&self) -> Option<&RpcJournal> {
+        self.journal.as_ref()
+    }
+
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    pub fn connectivity(&self) -> Connectivity {
+        *self.connectivity.borrow()
+    }
+
+    // Receiver is notified only when the state actually changes.
+    pub fn subscribe_connectivity(&self) -> watch::Receiver<Connectivity> {
+        self.connectivity.subscribe()
+    }
+
+    pub(crate) fn set_connectivity(&self, state: Connectivity) {
+        self.connectivity.send_if_modified(|current| {
+            if *current == state {
+                false
+            } else {
+                *current = state;
+                true
+            }
+        });
+    }
+
+    pub async fn bootstrap(node_url: &str) -> Result<Self, ZilliqaErrors<'_>> {
         let client = reqwest::Client::new();
         let payload = json!({
             "id": "1",
@@ -66,7 +138,7 @@ impl ZilliqaJsonRPC {
             .collect();
 
         nodes.push(node_url.to_string());
-        Ok(Self { nodes })
+        Ok(Self::from_vec(nodes))
     }
 
     pub async fn reqwest<'a, SR>(&self, payloads: Vec<Value>) -> Result<SR, ZilliqaErrors<'a>>
@@ -74,11 +146,10 @@ impl ZilliqaJsonRPC {
         SR: DeserializeOwned + std::fmt::Debug,
     {
         const MAX_ERROR: usize = 5;
-        let client = reqwest::Client::new();
         let mut error: ZilliqaErrors = ZilliqaErrors::NetowrkIsDown;
         let mut k = 0;
-        let mut handle_error = |e: String, zil_err: fn(String) -> ZilliqaErrors<'a>| -> bool {
-            let new_error = zil_err(e.to_string());
+        let mut failed = 0;
+        let mut handle_error = |new_error: ZilliqaErrors<'a>| -> bool {
             if new_error == error && k == MAX_ERROR {
                 false
             } else if new_error == error && k != MAX_ERROR {
@@ -93,31 +164,281 @@ impl ZilliqaJsonRPC {
         };
 
         for url in self.nodes.iter() {
-            let res = match client.post::<&str>(url).json(&payloads).send().await {
-                Ok(response) => response,
+            let client = self.transport.client(self.transport.circuit(failed))?;
+
+            match self.post(&client, url, &payloads).await {
+                Ok(res) => {
+                    self.set_connectivity(Connectivity::from_failures(failed, true));
+
+                    return Ok(res);
+                }
                 Err(e) => {
-                    if handle_error(e.to_string(), ZilliqaErrors::InvalidRPCReq) {
+                    failed += 1;
+
+                    if !handle_error(e) {
                         break;
                     }
+                }
+            }
+        }
+
+        self.set_connectivity(Connectivity::Offline);
+
+        Err(error)
+    }
+
+    /// Like [Self::reqwest] but the node is picked by weighted random and kept
+    /// for the whole `session`, a failing node is replaced by another one.
+    pub async fn reqwest_sticky<'a, SR>(
+        &self,
+        session: &str,
+        payloads: Vec<Value>,
+    ) -> Result<SR, ZilliqaErrors<'a>>
+    where
+        SR: DeserializeOwned + std::fmt::Debug,
+    {
+        let mut error = ZilliqaErrors::NetowrkIsDown;
+
+        let mut tried = Vec::with_capacity(self.nodes.len());
+
+        for failed in 0..self.nodes.len() {
+            let client = self.transport.client(self.transport.circuit(failed))?;
+            let node = self
+                .with_selector(|s| {
+                    s.select_excluding(
+                        &mut rand::thread_rng(),
+                        self.nodes.len(),
+                        Some(session),
+                        &tried,
+                    )
+                })
+                .ok_or(ZilliqaErrors::NetowrkIsDown)?;
+            match self.post(&client, &self.nodes[node], &payloads).await {
+                Ok(res) => {
+                    self.with_selector(|s| s.report_success(node));
+                    self.set_connectivity(Connectivity::from_failures(failed, true));
 
-                    continue;
+                    return Ok(res);
                 }
-            };
-            let res: SR = match res.json().await {
-                Ok(json) => json,
                 Err(e) => {
-                    if handle_error(e.to_string(), ZilliqaErrors::InvalidJson) {
-                        break;
-                    }
+                    self.with_selector(|s| s.report_failure(node));
+                    tried.push(node);
+                    error = e;
+                }
+            }
+        }
 
-                    continue;
+        self.set_connectivity(Connectivity::Offline);
+
+        Err(error)
+    }
+
+    // One attempt against one node, journaled when a journal is set.
+    pub(crate) async fn post<'a, SR>(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        payloads: &[Value],
+    ) -> Result<SR, ZilliqaErrors<'a>>
+    where
+        SR: DeserializeOwned,
+    {
+        let started = Instant::now();
+        let mut entry = self
+            .journal
+            .as_ref()
+            .map(|_| JournalEntry::new(url, payloads));
+        let res = match client.post(url).json(payloads).send().await {
+            Ok(res) => {
+                if let Some(entry) = entry.as_mut() {
+                    entry.status = Some(res.status().as_u16());
                 }
-            };
 
-            return Ok(res);
+                res.bytes()
+                    .await
+                    .map_err(|e| ZilliqaErrors::InvalidJson(e.to_string()))
+            }
+            Err(e) => Err(ZilliqaErrors::InvalidRPCReq(e.to_string())),
+        };
+        let res = res.and_then(|body| {
+            if let Some(entry) = entry.as_mut() {
+                entry.add_rpc_errors(&body);
+            }
+
+            serde_json::from_slice::<SR>(&body)
+                .map_err(|e| ZilliqaErrors::InvalidJson(e.to_string()))
+        });
+
+        if let (Some(journal), Some(mut entry)) = (&self.journal, entry) {
+            if let Err(e) = &res {
+                entry.add_error(&format!("{e:?}"));
+            }
+
+            entry.elapsed_ms = started.elapsed().as_millis() as u64;
+            journal.record(&entry);
         }
 
-        Err(error)
+        res
+    }
+
+    pub fn end_session(&self, session: &str) {
+        self.with_selector(|s| s.end_session(session));
+    }
+
+    pub(crate) fn flavours(&self) -> MutexGuard<'_, BTreeMap<String, NodeFlavour>> {
+        // only detected flavours are stored, a poisoned lock is still usable
+        self.flavours.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn with_selector<T>(&self, f: impl FnOnce(&mut NodeSelector) -> T) -> T {
+        // selector state is only scores, a poisoned lock is still usable
+        let mut selector = self.selector.lock().unwrap_or_else(|e| e.into_inner());
+
+        f(&mut selector)
+    }
+
+    pub async fn get_txs_for_tx_block_ex<'a>(
+        &self,
+        block: u64,
+        page: u64,
+    ) -> Result<TxBlockHashesPage, ZilliqaErrors<'a>> {
+        self.ensure_supported(&ZilMethods::GetTransactionsForTxBlockEx)
+            .await?;
+        self.get_tx_block_page(ZilMethods::GetTransactionsForTxBlockEx, block, page)
+            .await
+    }
+
+    pub async fn get_txn_bodies_for_tx_block_ex<'a>(
+        &self,
+        block: u64,
+        page: u64,
+    ) -> Result<TxBlockBodiesPage, ZilliqaErrors<'a>> {
+        self.ensure_supported(&ZilMethods::GetTxnBodiesForTxBlockEx)
+            .await?;
+        self.get_tx_block_page(ZilMethods::GetTxnBodiesForTxBlockEx, block, page)
+            .await
+    }
+
+    // Walks every page of the block and returns hashes grouped by shard,
+    // nodes without the paged method answer in one shot.
+    pub async fn get_all_txs_for_tx_block<'a>(
+        &self,
+        block: u64,
+    ) -> Result<Vec<Vec<String>>, ZilliqaErrors<'a>> {
+        if !self
+            .node_flavour()
+            .await
+            .supports(&ZilMethods::GetTransactionsForTxBlockEx)
+        {
+            let mut shards = Vec::new();
+
+            self.stream_result_array(
+                json!([block.to_string()]),
+                ZilMethods::GetTransactionsForTxBlock,
+                &mut |hashes: Option<Vec<String>>| shards.push(hashes.unwrap_or_default()),
+            )
+            .await?;
+
+            return Ok(shards);
+        }
+
+        let mut shards: Vec<Vec<String>> = Vec::new();
+        // bounded by the first page, later pages can't extend the walk
+        let mut num_pages = 1;
+        let mut page = Some(0);
+
+        while let Some(n) = page {
+            let mut shard = 0;
+            let res = self
+                .stream_tx_block_page(
+                    ZilMethods::GetTransactionsForTxBlockEx,
+                    block,
+                    n,
+                    &mut |hashes: Option<Vec<String>>| {
+                        if shards.len() <= shard {
+                            shards.resize(shard + 1, Vec::new());
+                        }
+
+                        shards[shard].extend(hashes.unwrap_or_default());
+                        shard += 1;
+                    },
+                )
+                .await?;
+
+            if n == 0 {
+                if res.num_pages > MAX_TX_BLOCK_PAGES {
+                    return Err(ZilliqaErrors::TooManyPages(res.num_pages));
+                }
+
+                num_pages = res.num_pages;
+            }
+
+            page = n.checked_add(1).filter(|next| *next < num_pages);
+        }
+
+        Ok(shards)
+    }
+
+    // Prefer `ContractInitCache::get_or_fetch`, init params never change.
+    pub async fn get_smart_contract_init<'a>(
+        &self,
+        contract: &str,
+    ) -> Result<Vec<ContractInitParam>, ZilliqaErrors<'a>> {
+        let payloads = vec![Self::build_payload(
+            json!([contract]),
+            ZilMethods::GetSmartContractInit,
+        )];
+        let mut res: Vec<ResultRes<Vec<ContractInitParam>>> = self.reqwest(payloads).await?;
+        let res = res.pop().ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        if let Some(error) = res.error {
+            return Err(ZilliqaErrors::InvalidRPCReq(error.message));
+        }
+
+        res.result.ok_or(ZilliqaErrors::FailToParseResponse)
+    }
+
+    async fn get_tx_block_page<'a, T>(
+        &self,
+        method: ZilMethods,
+        block: u64,
+        page: u64,
+    ) -> Result<TxBlockPage<T>, ZilliqaErrors<'a>>
+    where
+        T: DeserializeOwned + std::fmt::Debug,
+    {
+        let payloads = vec![Self::build_payload(
+            json!([block.to_string(), page.to_string()]),
+            method,
+        )];
+        let mut res: Vec<ResultRes<TxBlockPage<T>>> = self.reqwest(payloads).await?;
+        let res = res.pop().ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        if let Some(error) = res.error {
+            return Err(ZilliqaErrors::InvalidRPCReq(error.message));
+        }
+
+        res.result.ok_or(ZilliqaErrors::FailToParseResponse)
+    }
+
+    // Single call, unwraps the RPC level error.
+    pub(crate) async fn call<'a, T>(
+        &self,
+        params: Value,
+        method: ZilMethods,
+    ) -> Result<T, ZilliqaErrors<'a>>
+    where
+        T: DeserializeOwned + std::fmt::Debug,
+    {
+        let payloads = vec![Self::build_payload(params, method)];
+        let mut res: Vec<ResultRes<T>> = self.reqwest(payloads).await?;
+        let res = res.pop().ok_or(ZilliqaErrors::FailToParseResponse)?;
+
+        if let Some(error) = res.error {
+            return Err(ZilliqaErrors::InvalidRPCReq(error.message));
+        }
+
+        res.result.ok_or(ZilliqaErrors::FailToParseResponse)
     }
 
     pub fn build_payload(params: Value, method: ZilMethods) -> Value {
@@ -134,11 +455,14 @@ impl ZilliqaJsonRPC {
 mod tests {
     use super::ZilliqaJsonRPC;
     use crate::json_rpc::{
+        connectivity::Connectivity,
         zil_interfaces::{GetBalanceRes, ResultRes},
         zil_methods::ZilMethods,
     };
+    use config::node::MAX_TX_BLOCK_PAGES;
     use serde_json::json;
     use tokio;
+    use zil_errors::ZilliqaErrors;
 
     #[tokio::test]
     async fn test_bootstrap() {
@@ -163,4 +487,173 @@ mod tests {
         assert!(res[0].result.is_some());
         assert!(res[0].error.is_none());
     }
+
+    #[tokio::test]
+    async fn test_get_all_txs_for_tx_block() {
+        let mut server = mockito::Server::new_async().await;
+        let page_0 = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(r#"\["42","0"\]"#.to_string()))
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "result": {
+                        "CurrPage": 0,
+                        "NumPages": 2,
+                        "Transactions": [["a1", "a2"], null, ["c1"]]
+                    }
+                }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let page_1 = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(r#"\["42","1"\]"#.to_string()))
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    // a stale page number and count must not restart the walk
+                    "result": {
+                        "CurrPage": 0,
+                        "NumPages": 5,
+                        "Transactions": [["a3"], ["b1"]]
+                    }
+                }])
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let zil = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let shards = zil.get_all_txs_for_tx_block(42).await.unwrap();
+
+        page_0.assert_async().await;
+        page_1.assert_async().await;
+        assert_eq!(
+            shards,
+            vec![
+                vec!["a1".to_string(), "a2".to_string(), "a3".to_string()],
+                vec!["b1".to_string()],
+                vec!["c1".to_string()],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_too_many_pages() {
+        let mut server = mockito::Server::new_async().await;
+        let page = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex(
+                "GetTransactionsForTxBlockEx".to_string(),
+            ))
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "result": {
+                        "CurrPage": 0,
+                        "NumPages": MAX_TX_BLOCK_PAGES + 1,
+                        "Transactions": [["a1"]]
+                    }
+                }])
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let zil = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+
+        assert_eq!(
+            zil.get_all_txs_for_tx_block(42).await,
+            Err(ZilliqaErrors::TooManyPages(MAX_TX_BLOCK_PAGES + 1))
+        );
+        page.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_connectivity() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "balance": "1", "nonce": 1 } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let dead_node = "http://127.0.0.1:1".to_string();
+        let payloads = vec![ZilliqaJsonRPC::build_payload(
+            json!(["7793a8e8c09d189d4d421ce5bc5b3674656c5ac1"]),
+            ZilMethods::GetBalance,
+        )];
+
+        let zil = ZilliqaJsonRPC::from_vec(vec![dead_node.clone(), server.url()]);
+        let mut rx = zil.subscribe_connectivity();
+        let res: Vec<ResultRes<GetBalanceRes>> = zil.reqwest(payloads.clone()).await.unwrap();
+
+        assert!(res[0].result.is_some());
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(*rx.borrow_and_update(), Connectivity::Degraded(1));
+
+        let zil = ZilliqaJsonRPC::from_vec(vec![dead_node]);
+        let res: Result<Vec<ResultRes<GetBalanceRes>>, _> = zil.reqwest(payloads).await;
+
+        assert!(res.is_err());
+        assert!(zil.connectivity().is_offline());
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_sticky_restick() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{ "id": 1, "jsonrpc": "2.0", "result": { "balance": "1", "nonce": 1 } }])
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let payloads = vec![ZilliqaJsonRPC::build_payload(
+            json!(["7793a8e8c09d189d4d421ce5bc5b3674656c5ac1"]),
+            ZilMethods::GetBalance,
+        )];
+        let zil = ZilliqaJsonRPC::from_vec(vec!["http://127.0.0.1:1".to_string(), server.url()]);
+
+        for _ in 0..5 {
+            let res: Vec<ResultRes<GetBalanceRes>> =
+                zil.reqwest_sticky("send", payloads.clone()).await.unwrap();
+
+            assert!(res[0].result.is_some());
+        }
+
+        zil.end_session("send");
+    }
+
+    #[tokio::test]
+    async fn test_tx_block_page_error() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/")
+            .with_body(
+                json!([{
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "error": { "code": -1, "message": "TxBlock has no transactions" }
+                }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let zil = ZilliqaJsonRPC::from_vec(vec![server.url()]);
+        let res = zil.get_txn_bodies_for_tx_block_ex(1, 0).await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            ZilliqaErrors::InvalidRPCReq("TxBlock has no transactions".to_string())
+        );
+    }
 }
@@ -21,3 +21,102 @@ pub struct GetBalanceRes {
     pub balance: String,
     pub nonce: u64,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateTransactionRes {
+    #[serde(rename = "Info")]
+    pub info: String,
+    #[serde(rename = "TranID")]
+    pub tran_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContractInitParam {
+    pub vname: String,
+    #[serde(rename = "type")]
+    pub param_type: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSmartContractCodeRes {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GetVersionRes {
+    #[serde(rename = "Version")]
+    pub version: String,
+    #[serde(rename = "Commit", default)]
+    pub commit: String,
+}
+
+// One page of a `*ForTxBlockEx` response, pages are numbered from 0.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TxBlockPage<T> {
+    #[serde(rename = "CurrPage")]
+    pub curr_page: u64,
+    #[serde(rename = "NumPages")]
+    pub num_pages: u64,
+    #[serde(rename = "Transactions")]
+    pub transactions: Vec<T>,
+}
+
+// Hashes are grouped per shard, empty shards come back as null.
+pub type TxBlockHashesPage = TxBlockPage<Option<Vec<String>>>;
+pub type TxBlockBodiesPage = TxBlockPage<Value>;
+
+impl TxBlockHashesPage {
+    pub fn shards(&self) -> impl Iterator<Item = (usize, &[String])> {
+        self.transactions
+            .iter()
+            .enumerate()
+            .map(|(shard, hashes)| (shard, hashes.as_deref().unwrap_or_default()))
+    }
+}
+
+impl<T> TxBlockPage<T> {
+    // Page after `requested`, the node's `CurrPage` is not trusted.
+    pub fn next_page(&self, requested: u64) -> Option<u64> {
+        requested
+            .checked_add(1)
+            .filter(|next| *next < self.num_pages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_rpc_json_never_panics(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = serde_json::from_slice::<Vec<ResultRes<GetBalanceRes>>>(&bytes);
+            let _ = serde_json::from_slice::<ResultRes<TxBlockHashesPage>>(&bytes);
+            let _ = serde_json::from_slice::<ResultRes<CreateTransactionRes>>(&bytes);
+        }
+
+        #[test]
+        fn prop_rpc_json_shapes(balance in "\\PC*", nonce in any::<u64>(), code in any::<i16>()) {
+            let ok = serde_json::json!({
+                "id": 1,
+                "jsonrpc": "2.0",
+                "result": { "balance": balance, "nonce": nonce }
+            });
+            let res: ResultRes<GetBalanceRes> = serde_json::from_value(ok).unwrap();
+
+            prop_assert_eq!(res.result.unwrap().nonce, nonce);
+
+            let err = serde_json::json!({
+                "id": 1,
+                "jsonrpc": "2.0",
+                "error": { "code": code, "message": balance }
+            });
+            let res: ResultRes<GetBalanceRes> = serde_json::from_value(err).unwrap();
+
+            prop_assert!(res.result.is_none());
+            prop_assert_eq!(res.error.unwrap().code, code);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub enum ZilMethods {
     GetSmartContractInit,
+    GetSmartContractCode,
     GetBalance,
     GetSmartContractSubState,
     GetNetworkId,
@@ -10,12 +11,19 @@ pub enum ZilMethods {
     GetLatestTxBlock,
     GetRecentTransactions,
     GetMinimumGasPrice,
+    GetTransactionsForTxBlockEx,
+    GetTxnBodiesForTxBlockEx,
+    GetTransactionsForTxBlock,
+    GetVersion,
+    EthSendUserOperation,
+    EthEstimateUserOperationGas,
 }
 
 impl std::fmt::Display for ZilMethods {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ZilMethods::GetSmartContractInit => write!(f, "GetSmartContractInit"),
+            ZilMethods::GetSmartContractCode => write!(f, "GetSmartContractCode"),
             ZilMethods::GetBalance => write!(f, "GetBalance"),
             ZilMethods::GetSmartContractSubState => write!(f, "GetSmartContractSubState"),
             ZilMethods::GetNetworkId => write!(f, "GetNetworkId"),
@@ -26,6 +34,12 @@ impl std::fmt::Display for ZilMethods {
             ZilMethods::GetLatestTxBlock => write!(f, "GetLatestTxBlock"),
             ZilMethods::GetRecentTransactions => write!(f, "GetRecentTransactions"),
             ZilMethods::GetMinimumGasPrice => write!(f, "GetMinimumGasPrice"),
+            ZilMethods::GetTransactionsForTxBlockEx => write!(f, "GetTransactionsForTxBlockEx"),
+            ZilMethods::GetTxnBodiesForTxBlockEx => write!(f, "GetTxnBodiesForTxBlockEx"),
+            ZilMethods::GetTransactionsForTxBlock => write!(f, "GetTransactionsForTxBlock"),
+            ZilMethods::GetVersion => write!(f, "GetVersion"),
+            ZilMethods::EthSendUserOperation => write!(f, "eth_sendUserOperation"),
+            ZilMethods::EthEstimateUserOperationGas => write!(f, "eth_estimateUserOperationGas"),
         }
     }
 }
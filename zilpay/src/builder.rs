@@ -0,0 +1,386 @@
+use crate::lifecycle::{Lifecycle, WalletState};
+use background::Background;
+use cipher::{
+    ntrup::{set_work_budget, WorkBudget},
+    options::CipherOrders,
+};
+use config::{
+    file::{ConfigFile, KdfConfig, NetworkConfig},
+    storage::{STORAGE_APPLICATION, STORAGE_ORGANIZATION, STORAGE_QUALIFIER},
+};
+use proto::asset::AssetId;
+use settings::network::{Network, NetworkCapabilities};
+use std::collections::BTreeMap;
+use storage::{canonical::HashAlgo, LocalStorage};
+use zil_errors::background::BackgroundError;
+use zilliqa::json_rpc::zil::ZilliqaJsonRPC;
+
+/// Fiat price source, e.g. an exchange API the integrator already uses.
+pub trait RatesProvider {
+    // Price of one whole token in `currency`.
+    fn rate(&self, asset: &AssetId, currency: &str) -> Option<f64>;
+}
+
+#[derive(Debug, Default)]
+pub struct NoRates;
+
+impl RatesProvider for NoRates {
+    fn rate(&self, _asset: &AssetId, _currency: &str) -> Option<f64> {
+        None
+    }
+}
+
+/// Everything a wallet needs, wired together by [WalletBuilder].
+/// Wallets and the lifecycle are only reachable through the facade, so
+/// nothing unlocks or touches the network past its guards.
+pub struct ZilPay {
+    pub(crate) background: Background,
+    pub rpc: ZilliqaJsonRPC,
+    pub network: Network,
+    pub rates: Box<dyn RatesProvider>,
+    pub(crate) lifecycle: Lifecycle,
+    networks: Vec<(String, Network)>,
+    features: BTreeMap<String, bool>,
+}
+
+impl ZilPay {
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    pub fn capabilities(&self) -> NetworkCapabilities {
+        self.network.capabilities()
+    }
+
+    // Named networks of the config file, in the order listed there.
+    pub fn networks(&self) -> &[(String, Network)] {
+        &self.networks
+    }
+
+    // Switches the RPC client and the saved settings, false for unknown names.
+    pub fn use_network(&mut self, name: &str) -> bool {
+        let Some((_, network)) = self.networks.iter().find(|(n, _)| n == name) else {
+            return false;
+        };
+
+        self.rpc = ZilliqaJsonRPC::from_network(network);
+        self.network = network.clone();
+        self.background.wallet_settings.network = network.clone();
+
+        true
+    }
+
+    // Unlisted features are off.
+    pub fn feature(&self, name: &str) -> bool {
+        self.features.get(name).copied().unwrap_or(false)
+    }
+
+    pub fn rate(&self, asset: &AssetId, currency: &str) -> Option<f64> {
+        self.rates.rate(asset, currency)
+    }
+}
+
+/// Mainnet, the default cipher orders, the platform data directory and no
+/// rates unless told otherwise.
+pub struct WalletBuilder {
+    storage_path: Option<String>,
+    storage_password: Option<Vec<u8>>,
+    hash_algo: Option<HashAlgo>,
+    audit: Option<String>,
+    network: Network,
+    networks: Vec<(String, Network)>,
+    cipher_orders: Option<Vec<CipherOrders>>,
+    work_budget: Option<WorkBudget>,
+    kdf: Option<KdfConfig>,
+    features: BTreeMap<String, bool>,
+    rates: Option<Box<dyn RatesProvider>>,
+}
+
+impl Default for WalletBuilder {
+    fn default() -> Self {
+        Self {
+            storage_path: None,
+            storage_password: None,
+            hash_algo: None,
+            audit: None,
+            network: Network::mainnet(),
+            networks: Vec::new(),
+            cipher_orders: None,
+            work_budget: None,
+            kdf: None,
+            features: BTreeMap::new(),
+            rates: None,
+        }
+    }
+}
+
+impl WalletBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything a loaded config file sets, the first network is the one
+    /// the wallet starts on, see `ZilPay::use_network` for the others.
+    pub fn from_config(config: &ConfigFile) -> Self {
+        let mut builder = Self::new().kdf(config.kdf);
+
+        if let Some(path) = &config.storage.path {
+            builder = builder.storage_path(path);
+        }
+
+        if config.storage.blake3 {
+            builder = builder.hash_algo(HashAlgo::Blake3);
+        }
+
+        if let Some(actor) = &config.storage.audit {
+            builder = builder.audit(actor);
+        }
+
+        builder.networks = config
+            .networks
+            .iter()
+            .map(|network| (network.name.clone(), network_from(network)))
+            .collect();
+
+        if let Some((_, network)) = builder.networks.first() {
+            builder.network = network.clone();
+        }
+
+        for (name, enabled) in &config.features {
+            builder = builder.feature(name, *enabled);
+        }
+
+        builder
+    }
+
+    pub fn storage_path(mut self, path: &str) -> Self {
+        self.storage_path = Some(path.to_string());
+        self
+    }
+
+    // Opens the storage encrypted, see `LocalStorage::from_encrypted`.
+    pub fn storage_password(mut self, password: &[u8]) -> Self {
+        self.storage_password = Some(password.to_vec());
+        self
+    }
+
+    // Hashsum of records written from now on, the storage remembers it.
+    pub fn hash_algo(mut self, algo: HashAlgo) -> Self {
+        self.hash_algo = Some(algo);
+        self
+    }
+
+    // Logs every change of the records tagged with `actor`, see
+    // `LocalStorage::set_audit`.
+    pub fn audit(mut self, actor: &str) -> Self {
+        self.audit = Some(actor.to_string());
+        self
+    }
+
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn rpc_nodes(mut self, nodes: Vec<String>) -> Self {
+        self.network.rpc_nodes = nodes;
+        self
+    }
+
+    pub fn ws_url(mut self, url: &str) -> Self {
+        self.network.ws_url = Some(url.to_string());
+        self
+    }
+
+    pub fn indexer_url(mut self, url: &str) -> Self {
+        self.network.indexer_url = Some(url.to_string());
+        self
+    }
+
+    pub fn cipher_orders(mut self, orders: Vec<CipherOrders>) -> Self {
+        self.cipher_orders = Some(orders);
+        self
+    }
+
+    // Threads NTRU may use while locking and unlocking, all cores by default.
+    pub fn work_budget(mut self, budget: WorkBudget) -> Self {
+        self.work_budget = Some(budget);
+        self
+    }
+
+    // Argon2 cost of wallets created from now on, existing ones keep theirs.
+    pub fn kdf(mut self, kdf: KdfConfig) -> Self {
+        self.kdf = Some(kdf);
+        self
+    }
+
+    pub fn feature(mut self, name: &str, enabled: bool) -> Self {
+        self.features.insert(name.to_string(), enabled);
+        self
+    }
+
+    pub fn rates_provider(mut self, rates: impl RatesProvider + 'static) -> Self {
+        self.rates = Some(Box::new(rates));
+        self
+    }
+
+    pub fn build(self) -> Result<ZilPay, BackgroundError> {
+        let path = match &self.storage_path {
+            Some(path) => path.clone(),
+            None => LocalStorage::default_path(
+                STORAGE_QUALIFIER,
+                STORAGE_ORGANIZATION,
+                STORAGE_APPLICATION,
+            )
+            .map_err(BackgroundError::TryInitLocalStorageError)?,
+        };
+        let mut storage = match &self.storage_password {
+            Some(password) => LocalStorage::from_encrypted(&path, password),
+            None => LocalStorage::from(&path),
+        }
+        .map_err(BackgroundError::TryInitLocalStorageError)?;
+
+        if let Some(algo) = self.hash_algo {
+            storage
+                .set_hash_algo(algo)
+                .map_err(BackgroundError::TryInitLocalStorageError)?;
+        }
+
+        storage.set_audit(self.audit.as_deref());
+        let mut background = Background::from_storage(storage)?;
+
+        if let Some(orders) = self.cipher_orders {
+            background.wallet_settings.crypto.cipher_orders = orders;
+        }
+
+        if let Some(budget) = self.work_budget {
+            background.wallet_settings.crypto.work_budget = budget;
+        }
+
+        if let Some(kdf) = self.kdf {
+            background.wallet_settings.crypto.kdf = kdf;
+        }
+
+        set_work_budget(background.wallet_settings.crypto.work_budget);
+
+        background.wallet_settings.network = self.network.clone();
+
+        // loaded wallets start without a session
+        let lifecycle = Lifecycle::new(if background.wallets.is_empty() {
+            WalletState::Uninitialized
+        } else {
+            WalletState::Locked
+        });
+
+        Ok(ZilPay {
+            background,
+            rpc: ZilliqaJsonRPC::from_network(&self.network),
+            network: self.network,
+            rates: self.rates.unwrap_or_else(|| Box::new(NoRates)),
+            lifecycle,
+            networks: self.networks,
+            features: self.features,
+        })
+    }
+}
+
+fn network_from(config: &NetworkConfig) -> Network {
+    Network {
+        chain_id: config.chain_id,
+        rpc_nodes: config.rpc_nodes.clone(),
+        evm_rpc: config.evm_rpc.clone(),
+        ws_url: config.ws_url.clone(),
+        indexer_url: config.indexer_url.clone(),
+        ..Network::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedRate(f64);
+
+    impl RatesProvider for FixedRate {
+        fn rate(&self, asset: &AssetId, _currency: &str) -> Option<f64> {
+            asset.is_native().then_some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let zilpay = WalletBuilder::new()
+            .storage_path(&dir)
+            .rpc_nodes(vec!["http://127.0.0.1:4201".to_string()])
+            .indexer_url("http://127.0.0.1:8080")
+            .cipher_orders(vec![CipherOrders::AESGCM256])
+            .rates_provider(FixedRate(0.02))
+            .build()
+            .unwrap();
+
+        assert_eq!(zilpay.rpc.nodes, vec!["http://127.0.0.1:4201".to_string()]);
+        assert!(zilpay.capabilities().indexer);
+        assert_eq!(
+            zilpay.background.wallet_settings.crypto.cipher_orders,
+            vec![CipherOrders::AESGCM256]
+        );
+        assert_eq!(zilpay.background.wallet_settings.network, zilpay.network);
+        assert_eq!(zilpay.rate(&AssetId::Zil, "usd"), Some(0.02));
+    }
+
+    #[test]
+    fn test_build_encrypted() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let build = |password: &[u8]| {
+            WalletBuilder::new()
+                .storage_path(&dir)
+                .storage_password(password)
+                .build()
+        };
+
+        drop(build(b"storage password").unwrap());
+
+        assert!(build(b"wrong password").is_err());
+        assert!(build(b"storage password").is_ok());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let config = ConfigFile::from_toml(&format!(
+            "[storage]\npath = \"{dir}\"\nblake3 = true\naudit = \"ui\"\n\
+             [[networks]]\nname = \"devnet\"\nchain_id = 333\nrpc_nodes = [\"http://127.0.0.1:4201\"]\n\
+             [[networks]]\nname = \"testnet\"\nchain_id = 333\nrpc_nodes = [\"https://dev-api.zilliqa.com\"]\n\
+             [kdf]\nmemory_kib = 64\niterations = 1\n\
+             [features]\nevm = true"
+        ))
+        .unwrap();
+        let builder = WalletBuilder::from_config(&config);
+
+        assert_eq!(builder.storage_path.as_deref(), Some(dir.as_str()));
+
+        let mut zilpay = builder.build().unwrap();
+
+        assert_eq!(zilpay.network.chain_id, 333);
+        assert_eq!(zilpay.network.ws_url, None);
+        assert_eq!(zilpay.background.wallet_settings.crypto.kdf, config.kdf);
+        assert_eq!(zilpay.background.storage().hash_algo(), HashAlgo::Blake3);
+
+        zilpay.background.storage().set(b"probe", b"1").unwrap();
+
+        let audit = zilpay.background.storage().audit_log().unwrap();
+
+        assert_eq!(audit.last().map(|e| e.actor.as_str()), Some("ui"));
+        assert!(zilpay.feature("evm"));
+        assert!(!zilpay.feature("staking"));
+        assert_eq!(zilpay.networks().len(), 2);
+        assert!(zilpay.use_network("testnet"));
+        assert!(!zilpay.use_network("unknown"));
+        assert_eq!(
+            zilpay.rpc.nodes,
+            vec!["https://dev-api.zilliqa.com".to_string()]
+        );
+        assert_eq!(zilpay.background.wallet_settings.network, zilpay.network);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::builder::RatesProvider;
+use config::wallet::{FIAT_MAX_SLIPPAGE_BPS, FIAT_RATE_DECIMALS};
+use proto::{
+    asset::{AssetAmount, AssetId, TokenAmount},
+    fiat::{FiatAmount, FIAT_DECIMALS},
+    units::ZilUnit,
+};
+use wallet::history::HistoryRecord;
+use zil_errors::{fiat::FiatSendErrors, units::UnitsErrors};
+
+const BPS: u128 = 10_000;
+
+/// "Send $50 of ZIL": the crypto amount is fixed when the quote is taken
+/// and only sent if the rate hasn't moved more than the slippage bound by
+/// confirmation time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatSend {
+    asset: AssetId,
+    decimals: u8,
+    fiat: FiatAmount,
+    max_slippage_bps: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatQuote {
+    pub asset: AssetId,
+    pub fiat: FiatAmount,
+    pub rate: u128, // price of one whole token when quoted, FIAT_RATE_DECIMALS
+    pub amount: TokenAmount,
+    pub max_slippage_bps: u16,
+}
+
+impl FiatSend {
+    pub fn new(asset: AssetId, decimals: u8, fiat: FiatAmount) -> Self {
+        Self {
+            asset,
+            decimals,
+            fiat,
+            max_slippage_bps: FIAT_MAX_SLIPPAGE_BPS,
+        }
+    }
+
+    pub fn native(fiat: FiatAmount) -> Self {
+        Self::new(AssetId::Zil, ZilUnit::Zil.decimals() as u8, fiat)
+    }
+
+    pub fn max_slippage_bps(mut self, bps: u16) -> Self {
+        self.max_slippage_bps = bps;
+        self
+    }
+
+    pub fn quote(&self, rates: &dyn RatesProvider) -> Result<FiatQuote, FiatSendErrors> {
+        let rate = current_rate(rates, &self.asset, &self.fiat.currency)?;
+        let overflow = || FiatSendErrors::InvalidAmount(UnitsErrors::Overflow);
+        let value = u128::from(self.fiat.cents)
+            .checked_mul(10u128.pow(self.decimals.into()))
+            .and_then(|v| v.checked_mul(10u128.pow(FIAT_RATE_DECIMALS)))
+            .ok_or_else(overflow)?;
+        let price = rate
+            .checked_mul(10u128.pow(FIAT_DECIMALS))
+            .ok_or_else(overflow)?;
+        // rounding down never overpays
+        let amount = value / price;
+
+        if amount == 0 {
+            return Err(FiatSendErrors::ZeroAmount);
+        }
+
+        Ok(FiatQuote {
+            asset: self.asset.clone(),
+            fiat: self.fiat.clone(),
+            rate,
+            amount: TokenAmount::from_u128(amount),
+            max_slippage_bps: self.max_slippage_bps,
+        })
+    }
+}
+
+impl FiatQuote {
+    /// Checks the rate again and returns the locked amount to sign.
+    pub fn confirm(&self, rates: &dyn RatesProvider) -> Result<AssetAmount, FiatSendErrors> {
+        let rate = current_rate(rates, &self.asset, &self.fiat.currency)?;
+        let moved = u64::try_from(rate.abs_diff(self.rate).saturating_mul(BPS) / self.rate)
+            .unwrap_or(u64::MAX);
+
+        if moved > u64::from(self.max_slippage_bps) {
+            return Err(FiatSendErrors::SlippageExceeded(
+                moved,
+                self.max_slippage_bps,
+            ));
+        }
+
+        Ok(AssetAmount::new(self.asset.clone(), self.amount))
+    }
+
+    // Both amounts go to history for later reporting.
+    pub fn annotate(&self, mut record: HistoryRecord) -> HistoryRecord {
+        record.amount = Some(AssetAmount::new(self.asset.clone(), self.amount));
+        record.fiat = Some(self.fiat.clone());
+        record
+    }
+}
+
+// The provider's float becomes fixed point here, everything after is
+// integer math. A rate too small to show up at FIAT_RATE_DECIMALS is none.
+fn current_rate(
+    rates: &dyn RatesProvider,
+    asset: &AssetId,
+    currency: &str,
+) -> Result<u128, FiatSendErrors> {
+    rates
+        .rate(asset, currency)
+        .filter(|r| r.is_finite() && *r > 0.0)
+        .map(|r| (r * 10f64.powi(FIAT_RATE_DECIMALS as i32)).round() as u128)
+        .filter(|r| *r > 0)
+        .ok_or(FiatSendErrors::NoRate(currency.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::address::Address;
+    use std::cell::Cell;
+    use wallet::history::TxStatus;
+
+    struct MovingRate(Cell<f64>);
+
+    impl RatesProvider for MovingRate {
+        fn rate(&self, asset: &AssetId, currency: &str) -> Option<f64> {
+            (asset.is_native() && currency == "usd").then(|| self.0.get())
+        }
+    }
+
+    #[test]
+    fn test_fiat_send() {
+        let rates = MovingRate(Cell::new(0.02));
+        let send = FiatSend::native(FiatAmount::parse("usd", "50").unwrap());
+        let quote = send.quote(&rates).unwrap();
+
+        // 2500 ZIL in Qa
+        assert_eq!(quote.amount, TokenAmount::from_u128(2_500_000_000_000_000));
+
+        assert_eq!(quote.rate, 20_000_000_000);
+
+        // past f64 precision, the amount is still exact
+        assert_eq!(
+            FiatSend::native(FiatAmount::parse("usd", "1000000").unwrap())
+                .quote(&rates)
+                .unwrap()
+                .amount,
+            TokenAmount::from_u128(50_000_000_000_000_000_000)
+        );
+
+        rates.0.set(0.0201);
+
+        assert_eq!(
+            quote.confirm(&rates).unwrap().amount,
+            TokenAmount::from_u128(2_500_000_000_000_000)
+        );
+
+        rates.0.set(0.021);
+
+        assert_eq!(
+            quote.confirm(&rates),
+            Err(FiatSendErrors::SlippageExceeded(500, FIAT_MAX_SLIPPAGE_BPS))
+        );
+        assert_eq!(
+            FiatSend::native(FiatAmount::parse("eur", "50").unwrap()).quote(&rates),
+            Err(FiatSendErrors::NoRate("eur".to_string()))
+        );
+
+        let record = quote.annotate(HistoryRecord {
+            hash: None,
+            sender: Address::from_zil_base16("00000000000000000000000000000000000000a1").unwrap(),
+            nonce: 1,
+            status: TxStatus::Pending,
+            amount: None,
+            block: None,
+            memo: None,
+            origin: None,
+            timestamp: 0,
+            fiat: None,
+            gas: None,
+            payload: None,
+        });
+
+        assert_eq!(record.fiat.unwrap().cents, 5_000);
+        assert!(record.amount.is_some());
+    }
+}
@@ -1,2 +1,11 @@
+pub mod builder;
+pub mod fiat_send;
+pub mod lifecycle;
+pub mod prelude;
+
+// Whole sub-crates, for apps written before the prelude. Not covered by
+// semver.
+#[doc(hidden)]
 pub use background;
+#[doc(hidden)]
 pub use zil_errors;
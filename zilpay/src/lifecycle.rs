@@ -0,0 +1,428 @@
+use crate::builder::ZilPay;
+use config::sha::SHA256_SIZE;
+use crypto::bip49::Bip49DerivationPath;
+use proto::secret_key::SecretKey;
+#[cfg(feature = "sync-client")]
+use storage::{sync::SyncReport, sync_client::SyncClient};
+use tokio::sync::broadcast;
+use zil_errors::lifecycle::LifecycleErrors;
+use zilliqa::json_rpc::prefetch::{Prefetch, PrefetchEvent};
+
+const EVENTS_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletState {
+    Uninitialized, // no wallet in storage yet
+    Created,
+    Locked,
+    Unlocked,
+    Syncing,
+    Ready,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChange {
+    pub from: WalletState,
+    pub to: WalletState,
+}
+
+/// Where the facade is between first start and a synced, unlocked vault.
+/// Only the transitions below are legal, everything that needs keys or
+/// touches the network checks the state first.
+#[derive(Debug)]
+pub struct Lifecycle {
+    state: WalletState,
+    events: broadcast::Sender<StateChange>,
+}
+
+impl WalletState {
+    pub fn can_transition(self, to: WalletState) -> bool {
+        use WalletState::*;
+
+        matches!(
+            (self, to),
+            (Uninitialized, Created)
+                | (Created, Locked | Unlocked)
+                | (Locked, Unlocked)
+                | (Unlocked | Ready, Syncing)
+                | (Syncing, Ready | Unlocked) // failed sync leaves it unlocked
+                | (Unlocked | Syncing | Ready, Locked)
+        )
+    }
+
+    pub fn is_unlocked(self) -> bool {
+        matches!(
+            self,
+            WalletState::Unlocked | WalletState::Syncing | WalletState::Ready
+        )
+    }
+}
+
+impl Lifecycle {
+    pub fn new(state: WalletState) -> Self {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+
+        Self { state, events }
+    }
+
+    pub fn state(&self) -> WalletState {
+        self.state
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StateChange> {
+        self.events.subscribe()
+    }
+
+    // Staying in the same state is a no-op and emits nothing.
+    pub fn transition(&mut self, to: WalletState) -> Result<(), LifecycleErrors> {
+        let from = self.state;
+
+        if from == to {
+            return Ok(());
+        }
+
+        if !from.can_transition(to) {
+            return Err(LifecycleErrors::IllegalTransition(
+                format!("{from:?}"),
+                format!("{to:?}"),
+            ));
+        }
+
+        self.state = to;
+        // no subscribers is not an error
+        let _ = self.events.send(StateChange { from, to });
+
+        Ok(())
+    }
+
+    pub fn expect(&self, states: &[WalletState]) -> Result<(), LifecycleErrors> {
+        if states.contains(&self.state) {
+            return Ok(());
+        }
+
+        let expected: Vec<String> = states.iter().map(|s| format!("{s:?}")).collect();
+
+        Err(LifecycleErrors::InvalidState(
+            format!("{:?}", self.state),
+            expected.join("|"),
+        ))
+    }
+}
+
+impl ZilPay {
+    pub fn state(&self) -> WalletState {
+        self.lifecycle.state()
+    }
+
+    pub fn subscribe_state(&self) -> broadcast::Receiver<StateChange> {
+        self.lifecycle.subscribe()
+    }
+
+    /// Guard for RPC and sync code, which must not run against a locked or
+    /// half initialized vault. Wallets unlock one by one, so the wallet at
+    /// `index` has to hold a session as well.
+    pub fn ensure_unlocked(&self, index: usize) -> Result<(), LifecycleErrors> {
+        self.lifecycle.expect(&[
+            WalletState::Unlocked,
+            WalletState::Syncing,
+            WalletState::Ready,
+        ])?;
+
+        let wallet = self
+            .background
+            .wallets
+            .get(index)
+            .ok_or(LifecycleErrors::WalletNotExists(index))?;
+
+        if !wallet.is_unlocked() {
+            return Err(LifecycleErrors::WalletLocked(index));
+        }
+
+        Ok(())
+    }
+
+    pub fn add_bip39_wallet<F>(
+        &mut self,
+        password: &str,
+        mnemonic: &str,
+        indexes: &[usize],
+        derive_fn: F,
+    ) -> Result<[u8; SHA256_SIZE], LifecycleErrors>
+    where
+        F: Fn(usize) -> Bip49DerivationPath,
+    {
+        let key = self
+            .background
+            .add_bip39_wallet(password, mnemonic, indexes, derive_fn)?;
+
+        self.wallet_added()?;
+
+        Ok(key)
+    }
+
+    pub fn add_sk_wallet(
+        &mut self,
+        password: &str,
+        secret_key: &SecretKey,
+        account_name: String,
+    ) -> Result<[u8; SHA256_SIZE], LifecycleErrors> {
+        let key = self
+            .background
+            .add_sk_wallet(password, secret_key, account_name)?;
+
+        self.wallet_added()?;
+
+        Ok(key)
+    }
+
+    // Only the first wallet changes the state.
+    fn wallet_added(&mut self) -> Result<(), LifecycleErrors> {
+        if self.state() == WalletState::Uninitialized {
+            self.lifecycle.transition(WalletState::Created)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn unlock(&mut self, index: usize, password: &[u8]) -> Result<(), LifecycleErrors> {
+        if self.state() == WalletState::Uninitialized {
+            return Err(LifecycleErrors::WalletNotExists(index));
+        }
+
+        self.background
+            .wallets
+            .get_mut(index)
+            .ok_or(LifecycleErrors::WalletNotExists(index))?
+            .unlock(password)?;
+
+        if !self.state().is_unlocked() {
+            self.lifecycle.transition(WalletState::Unlocked)?;
+        }
+
+        Ok(())
+    }
+
+    // Unlock, then warm the first screen of the selected account.
+    pub async fn unlock_with_prefetch(
+        &mut self,
+        index: usize,
+        password: &[u8],
+        on_event: impl FnMut(PrefetchEvent),
+    ) -> Result<(), LifecycleErrors> {
+        self.unlock(index, password)?;
+        self.prefetch(index, on_event).await
+    }
+
+    pub async fn prefetch(
+        &self,
+        index: usize,
+        on_event: impl FnMut(PrefetchEvent),
+    ) -> Result<(), LifecycleErrors> {
+        self.ensure_unlocked(index)?;
+
+        let wallet = &self.background.wallets[index];
+        let Some((_, account)) = wallet
+            .accounts()
+            .find(|(i, _)| *i == wallet.selected_account())
+        else {
+            return Ok(());
+        };
+        let mut tokens: Vec<String> = account.ft_map.keys().cloned().collect();
+
+        tokens.sort();
+        Prefetch::new(
+            &self.rpc,
+            self.network.chain_id,
+            &hex::encode(account.addr.addr_bytes()),
+        )
+        .tokens(tokens)
+        .cache(self.background.storage())
+        .run(on_event)
+        .await;
+
+        Ok(())
+    }
+
+    pub fn lock(&mut self) -> Result<(), LifecycleErrors> {
+        self.lifecycle.transition(WalletState::Locked)?;
+        self.background.wallets.iter_mut().for_each(|w| w.lock());
+
+        Ok(())
+    }
+
+    /// Exchanges `keys` of the wallet at `index` with the sync server, the
+    /// state reads `Syncing` meanwhile and `Ready` once it went through.
+    #[cfg(feature = "sync-client")]
+    pub async fn sync(
+        &mut self,
+        index: usize,
+        client: &SyncClient<'_>,
+        keys: &[&[u8]],
+    ) -> Result<SyncReport, LifecycleErrors> {
+        self.ensure_unlocked(index)?;
+        self.begin_sync()?;
+
+        let report = client.sync(&self.background.storage(), keys).await;
+
+        self.finish_sync(report.is_ok())?;
+
+        Ok(report?)
+    }
+
+    #[cfg(feature = "sync-client")]
+    fn begin_sync(&mut self) -> Result<(), LifecycleErrors> {
+        self.lifecycle.transition(WalletState::Syncing)
+    }
+
+    #[cfg(feature = "sync-client")]
+    fn finish_sync(&mut self, synced: bool) -> Result<(), LifecycleErrors> {
+        self.lifecycle.expect(&[WalletState::Syncing])?;
+        self.lifecycle.transition(if synced {
+            WalletState::Ready
+        } else {
+            WalletState::Unlocked
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::WalletBuilder;
+    use zilliqa::json_rpc::zil::ZilliqaJsonRPC;
+
+    #[test]
+    fn test_transitions() {
+        let mut lifecycle = Lifecycle::new(WalletState::Uninitialized);
+        let mut events = lifecycle.subscribe();
+
+        assert_eq!(
+            lifecycle.transition(WalletState::Syncing),
+            Err(LifecycleErrors::IllegalTransition(
+                "Uninitialized".to_string(),
+                "Syncing".to_string()
+            ))
+        );
+
+        for state in [
+            WalletState::Created,
+            WalletState::Locked,
+            WalletState::Unlocked,
+            WalletState::Syncing,
+            WalletState::Ready,
+            WalletState::Ready,
+            WalletState::Locked,
+        ] {
+            lifecycle.transition(state).unwrap();
+        }
+
+        assert_eq!(
+            events.try_recv().unwrap(),
+            StateChange {
+                from: WalletState::Uninitialized,
+                to: WalletState::Created
+            }
+        );
+        assert_eq!(std::iter::from_fn(|| events.try_recv().ok()).count(), 5);
+        assert!(lifecycle.transition(WalletState::Ready).is_err());
+    }
+
+    #[test]
+    fn test_facade_guards() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut zilpay = WalletBuilder::new().storage_path(&dir).build().unwrap();
+
+        assert_eq!(zilpay.state(), WalletState::Uninitialized);
+        assert_eq!(
+            zilpay.ensure_unlocked(0),
+            Err(LifecycleErrors::InvalidState(
+                "Uninitialized".to_string(),
+                "Unlocked|Syncing|Ready".to_string()
+            ))
+        );
+        assert_eq!(
+            zilpay.unlock(0, b"password"),
+            Err(LifecycleErrors::WalletNotExists(0))
+        );
+        #[cfg(feature = "sync-client")]
+        assert!(zilpay.begin_sync().is_err());
+
+        zilpay.wallet_added().unwrap();
+
+        assert_eq!(zilpay.state(), WalletState::Created);
+        assert_eq!(
+            zilpay.unlock(0, b"password"),
+            Err(LifecycleErrors::WalletNotExists(0))
+        );
+        assert!(zilpay.lock().is_ok());
+        assert!(zilpay.ensure_unlocked(0).is_err());
+    }
+
+    #[test]
+    fn test_guard_per_wallet() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut zilpay = WalletBuilder::new().storage_path(&dir).build().unwrap();
+        let password = "password";
+
+        for name in ["first", "second"] {
+            let keypair = proto::keypair::KeyPair::gen_sha256().unwrap();
+
+            zilpay
+                .add_sk_wallet(
+                    password,
+                    &keypair.get_secretkey().unwrap(),
+                    name.to_string(),
+                )
+                .unwrap();
+        }
+
+        assert_eq!(zilpay.state(), WalletState::Created);
+        zilpay.lock().unwrap();
+        zilpay.unlock(0, password.as_bytes()).unwrap();
+
+        assert_eq!(zilpay.ensure_unlocked(0), Ok(()));
+        assert_eq!(
+            zilpay.ensure_unlocked(1),
+            Err(LifecycleErrors::WalletLocked(1))
+        );
+        assert_eq!(
+            zilpay.ensure_unlocked(2),
+            Err(LifecycleErrors::WalletNotExists(2))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unlock_with_prefetch() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let mut zilpay = WalletBuilder::new().storage_path(&dir).build().unwrap();
+        let keypair = proto::keypair::KeyPair::gen_sha256().unwrap();
+        let password = "password";
+
+        zilpay
+            .add_sk_wallet(
+                password,
+                &keypair.get_secretkey().unwrap(),
+                "prefetch".to_string(),
+            )
+            .unwrap();
+        // nothing listens there, the live stage fails fast
+        zilpay.rpc = ZilliqaJsonRPC::from_vec(vec!["http://127.0.0.1:1".to_string()]);
+
+        let mut events = Vec::new();
+
+        assert!(zilpay.prefetch(0, |e| events.push(e)).await.is_err());
+        assert!(events.is_empty());
+
+        zilpay
+            .unlock_with_prefetch(0, password.as_bytes(), |e| events.push(e))
+            .await
+            .unwrap();
+
+        assert_eq!(zilpay.state(), WalletState::Unlocked);
+        assert_eq!(events.last(), Some(&PrefetchEvent::Finished));
+        assert!(matches!(
+            events.first(),
+            Some(PrefetchEvent::Live { .. } | PrefetchEvent::Failed { .. })
+        ));
+    }
+}
@@ -0,0 +1,54 @@
+//! The supported surface of zilpay_core: `use zilpay::prelude::*;` is all
+//! an app should need. Everything here follows semver, the sub-crates
+//! behind it don't and may change in any release.
+
+pub use crate::{
+    builder::{NoRates, RatesProvider, WalletBuilder, ZilPay},
+    fiat_send::{FiatQuote, FiatSend},
+    lifecycle::{Lifecycle, StateChange, WalletState},
+};
+
+pub use background::Background;
+pub use cipher::{ntrup::WorkBudget, options::CipherOrders};
+pub use config::file::ConfigFile;
+pub use proto::{
+    address::Address,
+    asset::{AssetAmount, AssetId, TokenAmount},
+    fiat::FiatAmount,
+    units::ZilUnit,
+};
+pub use settings::{
+    common_settings::CommonSettings,
+    network::{Network, NetworkCapabilities},
+    wallet_settings::WalletSettings,
+};
+pub use wallet::{history::HistoryRecord, Wallet, WalletConfig};
+pub use zil_errors::{
+    background::BackgroundError, fiat::FiatSendErrors, lifecycle::LifecycleErrors,
+};
+pub use zilliqa::json_rpc::zil::ZilliqaJsonRPC;
+
+#[cfg(test)]
+mod tests {
+    // Only the prelude, so dropping or renaming a re-export breaks the build.
+    use super::*;
+
+    #[test]
+    fn test_prelude() {
+        let dir = format!("/tmp/{}", rand::random::<usize>());
+        let zilpay: ZilPay = WalletBuilder::new()
+            .storage_path(&dir)
+            .network(Network::default())
+            .cipher_orders(vec![CipherOrders::AESGCM256])
+            .build()
+            .unwrap();
+        let send = FiatSend::native(FiatAmount::parse("usd", "50").unwrap());
+
+        assert_eq!(zilpay.state(), WalletState::Uninitialized);
+        assert_eq!(
+            send.quote(&NoRates),
+            Err(FiatSendErrors::NoRate("usd".to_string()))
+        );
+        assert!(zilpay.background().wallets.is_empty());
+    }
+}